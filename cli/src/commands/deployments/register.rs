@@ -133,6 +133,7 @@ pub async fn run_register(State(env): State<CliEnv>, discover_opts: &Register) -
         DeploymentEndpoint::Uri(uri) => RegisterDeploymentRequest::Http {
             uri: uri.clone(),
             additional_headers: headers.clone().map(Into::into),
+            retry_policy: None,
             force,
             dry_run,
         },
@@ -140,6 +141,7 @@ pub async fn run_register(State(env): State<CliEnv>, discover_opts: &Register) -
             arn: arn.to_string(),
             assume_role_arn: discover_opts.assume_role_arn.clone(),
             additional_headers: headers.clone().map(Into::into),
+            retry_policy: None,
             force,
             dry_run,
         },
@@ -357,6 +359,21 @@ pub async fn run_register(State(env): State<CliEnv>, discover_opts: &Register) -
         }
     }
 
+    if !dry_run_result.incompatible_changes.is_empty() {
+        c_println!();
+        c_warn!(
+            "The following changes aren't backward compatible, they might break the clients and \
+            the in-flight invocations of the updated services:\n{}",
+            dry_run_result
+                .incompatible_changes
+                .iter()
+                .map(|change| format!("- {change}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        c_println!();
+    }
+
     confirm_or_exit(&env, "Are you sure you want to apply those changes?")?;
 
     let progress = ProgressBar::new_spinner();
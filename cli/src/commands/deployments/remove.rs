@@ -176,11 +176,7 @@ pub async fn run_remove(State(env): State<CliEnv>, opts: &Remove) -> Result<()>
     confirm_or_exit(&env, "Are you sure you want to remove this deployment?")?;
 
     let result = client
-        .remove_deployment(
-            &opts.deployment_id,
-            //TODO: Use opts.force when the server implements the false + validation case!
-            true,
-        )
+        .remove_deployment(&opts.deployment_id, opts.force)
         .await?;
     let _ = result.success_or_error()?;
 
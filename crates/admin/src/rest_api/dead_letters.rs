@@ -0,0 +1,144 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::error::*;
+
+use crate::rest_api::create_envelope_header;
+use crate::state::AdminServiceState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use okapi_operation::*;
+use restate_meta_rest_model::dead_letters::RedriveDeadLetterResponse;
+use restate_types::identifiers::{InvocationId, InvocationUuid, WithPartitionKey};
+use restate_types::invocation::DeadLetterRedrive;
+use restate_wal_protocol::{append_envelope_to_bifrost, Command, Envelope};
+use tracing::warn;
+
+/// Redrive a dead lettered invocation
+#[openapi(
+    summary = "Redrive a dead lettered invocation",
+    description = "Run a dead lettered invocation again with its original input, and remove it from \
+    the dead letter queue. The invocation is started with a new invocation id. Dead lettered invocations \
+    can be listed with the `sys_dead_letter` table of the SQL introspection.",
+    operation_id = "redrive_dead_letter",
+    tags = "invocation",
+    parameters(path(
+        name = "invocation_id",
+        description = "Identifier of the dead lettered invocation.",
+        schema = "std::string::String"
+    )),
+    responses(
+        ignore_return_type = true,
+        response(
+            status = "202",
+            description = "Accepted",
+            content = "Json<RedriveDeadLetterResponse>",
+        ),
+        from_type = "MetaApiError",
+    )
+)]
+pub async fn redrive_dead_letter<V>(
+    State(mut state): State<AdminServiceState<V>>,
+    Path(invocation_id): Path<String>,
+) -> Result<(StatusCode, Json<RedriveDeadLetterResponse>), MetaApiError> {
+    let invocation_id = invocation_id
+        .parse::<InvocationId>()
+        .map_err(|e| MetaApiError::InvalidField("invocation_id", e.to_string()))?;
+    // Keep the partition key, so the new invocation is processed by the same partition
+    let new_invocation_id =
+        InvocationId::from_parts(invocation_id.partition_key(), InvocationUuid::new());
+
+    append_dead_letter_command(
+        &mut state,
+        invocation_id,
+        Command::RedriveDeadLetter(DeadLetterRedrive {
+            invocation_id,
+            new_invocation_id,
+        }),
+    )
+    .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(RedriveDeadLetterResponse {
+            invocation_id: new_invocation_id,
+        }),
+    ))
+}
+
+/// Purge a dead lettered invocation
+#[openapi(
+    summary = "Purge a dead lettered invocation",
+    description = "Remove a dead lettered invocation from the dead letter queue, without running it again.",
+    operation_id = "purge_dead_letter",
+    tags = "invocation",
+    parameters(path(
+        name = "invocation_id",
+        description = "Identifier of the dead lettered invocation.",
+        schema = "std::string::String"
+    )),
+    responses(
+        ignore_return_type = true,
+        response(
+            status = "202",
+            description = "Accepted",
+            content = "okapi_operation::Empty",
+        ),
+        from_type = "MetaApiError",
+    )
+)]
+pub async fn purge_dead_letter<V>(
+    State(mut state): State<AdminServiceState<V>>,
+    Path(invocation_id): Path<String>,
+) -> Result<StatusCode, MetaApiError> {
+    let invocation_id = invocation_id
+        .parse::<InvocationId>()
+        .map_err(|e| MetaApiError::InvalidField("invocation_id", e.to_string()))?;
+
+    append_dead_letter_command(
+        &mut state,
+        invocation_id,
+        Command::PurgeDeadLetter(invocation_id),
+    )
+    .await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn append_dead_letter_command<V>(
+    state: &mut AdminServiceState<V>,
+    invocation_id: InvocationId,
+    command: Command,
+) -> Result<(), MetaApiError> {
+    let result = state
+        .task_center
+        .run_in_scope(
+            "dead_letter_command",
+            None,
+            append_envelope_to_bifrost(
+                &mut state.bifrost,
+                Envelope::new(
+                    create_envelope_header(invocation_id.partition_key()),
+                    command,
+                ),
+            ),
+        )
+        .await;
+
+    if let Err(err) = result {
+        warn!("Could not append dead letter command to Bifrost: {err}");
+        Err(MetaApiError::Internal(
+            "Failed sending dead letter command to the cluster.".to_owned(),
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -11,14 +11,18 @@
 use super::error::*;
 use crate::state::AdminServiceState;
 
-use crate::rest_api::log_error;
+use crate::rest_api::{log_error, ListParams};
+use crate::schema_registry::error::{DeploymentError, SchemaError};
 use crate::schema_registry::{ApplyMode, Force};
+use crate::storage_query::count_pinned_invocations;
 use axum::extract::{Path, Query, State};
 use axum::http::{header, StatusCode};
 use axum::response::IntoResponse;
 use axum::Json;
 use okapi_operation::*;
 use restate_meta_rest_model::deployments::*;
+use restate_schema_api::deployment::DeploymentStatus;
+use restate_schema_api::listing::ListOptions;
 use restate_service_client::Endpoint;
 use restate_service_protocol::discovery::DiscoverEndpoint;
 use restate_types::identifiers::InvalidLambdaARN;
@@ -44,10 +48,11 @@ pub async fn create_deployment<V>(
     State(state): State<AdminServiceState<V>>,
     #[request_body(required = true)] Json(payload): Json<RegisterDeploymentRequest>,
 ) -> Result<impl IntoResponse, MetaApiError> {
-    let (discover_endpoint, force, dry_run) = match payload {
+    let (discover_endpoint, retry_policy, force, dry_run) = match payload {
         RegisterDeploymentRequest::Http {
             uri,
             additional_headers,
+            retry_policy,
             force,
             dry_run,
         } => (
@@ -55,6 +60,7 @@ pub async fn create_deployment<V>(
                 Endpoint::Http(uri, Default::default()),
                 additional_headers.unwrap_or_default().into(),
             ),
+            retry_policy,
             force,
             dry_run,
         ),
@@ -62,6 +68,7 @@ pub async fn create_deployment<V>(
             arn,
             assume_role_arn,
             additional_headers,
+            retry_policy,
             force,
             dry_run,
         } => (
@@ -74,6 +81,7 @@ pub async fn create_deployment<V>(
                 ),
                 additional_headers.unwrap_or_default().into(),
             ),
+            retry_policy,
             force,
             dry_run,
         ),
@@ -87,19 +95,28 @@ pub async fn create_deployment<V>(
         ApplyMode::Apply
     };
 
-    let (id, services) = state
+    let (id, services, report) = state
         .task_center
         .run_in_scope("create-deployment", None, async {
             log_error(
                 state
                     .schema_registry
-                    .register_deployment(discover_endpoint, force, apply_mode)
+                    .register_deployment(discover_endpoint, retry_policy, force, apply_mode)
                     .await,
             )
         })
         .await?;
 
-    let response_body = RegisterDeploymentResponse { id, services };
+    let response_body = RegisterDeploymentResponse {
+        id,
+        services,
+        changes: report.changes.iter().map(ToString::to_string).collect(),
+        incompatible_changes: report
+            .incompatible_changes
+            .iter()
+            .map(|(service, change)| format!("service '{service}': {change}"))
+            .collect(),
+    };
 
     Ok((
         StatusCode::CREATED,
@@ -145,18 +162,56 @@ pub async fn get_deployment<V>(
 /// List deployments
 #[openapi(
     summary = "List deployments",
-    description = "List all registered deployments.",
+    description = "List the registered deployments, ordered by id.",
     operation_id = "list_deployments",
-    tags = "deployment"
+    tags = "deployment",
+    parameters(
+        query(
+            name = "name_prefix",
+            description = "List only the deployments whose id starts with the given prefix.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "String",
+        ),
+        query(
+            name = "after",
+            description = "List only the deployments after the given id, as returned in the `next` field of the previous page.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "String",
+        ),
+        query(
+            name = "limit",
+            description = "Maximum number of deployments to list.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "usize",
+        ),
+        query(
+            name = "include_retired",
+            description = "If false, draining deployments aren't listed. Defaults to true.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "bool",
+        )
+    )
 )]
 pub async fn list_deployments<V>(
     State(state): State<AdminServiceState<V>>,
+    Query(params): Query<ListParams>,
 ) -> Json<ListDeploymentsResponse> {
-    let deployments = state
+    let options = ListOptions::from(params);
+    let page = state
         .task_center
         .run_in_scope_sync("list-deployments", None, || {
-            state.schema_registry.list_deployments()
-        })
+            state.schema_registry.list_deployments(&options)
+        });
+    let deployments = page
+        .items
         .into_iter()
         .map(|(deployment, services)| DeploymentResponse {
             id: deployment.id,
@@ -168,7 +223,11 @@ pub async fn list_deployments<V>(
         })
         .collect();
 
-    ListDeploymentsResponse { deployments }.into()
+    ListDeploymentsResponse {
+        deployments,
+        next: page.next,
+    }
+    .into()
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -179,7 +238,7 @@ pub struct DeleteDeploymentParams {
 /// Discover endpoint and return discovered endpoints.
 #[openapi(
     summary = "Delete deployment",
-    description = "Delete deployment. Currently it's supported to remove a deployment only using the force flag",
+    description = "Delete deployment. Unless `force` is set to `true`, only a draining deployment can be deleted, that is a deployment which doesn't serve the latest revision of any service, once no in-flight invocation is pinned to it anymore.",
     operation_id = "delete_deployment",
     tags = "deployment",
     parameters(
@@ -204,11 +263,6 @@ pub struct DeleteDeploymentParams {
             description = "Accepted",
             content = "okapi_operation::Empty",
         ),
-        response(
-            status = "501",
-            description = "Not implemented. Only using the force flag is supported at the moment.",
-            content = "okapi_operation::Empty",
-        ),
         from_type = "MetaApiError",
     )
 )]
@@ -218,9 +272,38 @@ pub async fn delete_deployment<V>(
     Query(DeleteDeploymentParams { force }): Query<DeleteDeploymentParams>,
 ) -> Result<StatusCode, MetaApiError> {
     if let Some(true) = force {
-        log_error(state.schema_registry.delete_deployment(deployment_id).await)?;
-        Ok(StatusCode::ACCEPTED)
-    } else {
-        Ok(StatusCode::NOT_IMPLEMENTED)
+        log_error(
+            state
+                .schema_registry
+                .delete_deployment(deployment_id, true)
+                .await,
+        )?;
+        return Ok(StatusCode::ACCEPTED);
+    }
+
+    match state.schema_registry.get_deployment_status(deployment_id) {
+        None => return Err(MetaApiError::DeploymentNotFound(deployment_id)),
+        Some(DeploymentStatus::Active) => {
+            // Let the schema registry report which services are still served by the deployment
+        }
+        Some(DeploymentStatus::Draining) => {
+            let pinned_invocations =
+                count_pinned_invocations(&state.node_svc_client, deployment_id)
+                    .await
+                    .map_err(|e| MetaApiError::Internal(e.to_string()))?;
+            if pinned_invocations > 0 {
+                return Err(MetaApiError::Schema(SchemaError::Deployment(
+                    DeploymentError::PinnedInvocations(deployment_id, pinned_invocations),
+                )));
+            }
+        }
     }
+
+    log_error(
+        state
+            .schema_registry
+            .delete_deployment(deployment_id, false)
+            .await,
+    )?;
+    Ok(StatusCode::ACCEPTED)
 }
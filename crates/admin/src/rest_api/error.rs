@@ -20,7 +20,7 @@ use okapi_operation::okapi::map;
 use okapi_operation::okapi::openapi3::Responses;
 use okapi_operation::{okapi, Components, ToMediaTypes, ToResponses};
 use restate_core::ShutdownError;
-use restate_types::identifiers::{DeploymentId, SubscriptionId};
+use restate_types::identifiers::{DeploymentId, PartitionId, SubscriptionId};
 use schemars::JsonSchema;
 use serde::Serialize;
 
@@ -41,6 +41,10 @@ pub enum MetaApiError {
     },
     #[error("The requested subscription '{0}' does not exist")]
     SubscriptionNotFound(SubscriptionId),
+    #[error("The requested partition '{0}' does not exist")]
+    PartitionNotFound(PartitionId),
+    #[error("{0}")]
+    Conflict(String),
     #[error(transparent)]
     Schema(#[from] SchemaError),
     #[error(transparent)]
@@ -67,14 +71,17 @@ impl IntoResponse for MetaApiError {
             MetaApiError::ServiceNotFound(_)
             | MetaApiError::HandlerNotFound { .. }
             | MetaApiError::DeploymentNotFound(_)
-            | MetaApiError::SubscriptionNotFound(_) => StatusCode::NOT_FOUND,
+            | MetaApiError::SubscriptionNotFound(_)
+            | MetaApiError::PartitionNotFound(_) => StatusCode::NOT_FOUND,
             MetaApiError::InvalidField(_, _) => StatusCode::BAD_REQUEST,
+            MetaApiError::Conflict(_) => StatusCode::CONFLICT,
             MetaApiError::Schema(schema_error) => match schema_error {
                 SchemaError::NotFound(_) => StatusCode::NOT_FOUND,
                 SchemaError::Override(_)
-                | SchemaError::Service(ServiceError::DifferentType { .. })
-                | SchemaError::Service(ServiceError::RemovedHandlers { .. })
-                | SchemaError::Deployment(DeploymentError::IncorrectId { .. }) => {
+                | SchemaError::Service(ServiceError::IncompatibleChanges { .. })
+                | SchemaError::Deployment(DeploymentError::IncorrectId { .. })
+                | SchemaError::Deployment(DeploymentError::StillActive(..))
+                | SchemaError::Deployment(DeploymentError::PinnedInvocations(..)) => {
                     StatusCode::CONFLICT
                 }
                 SchemaError::Service(_) => StatusCode::BAD_REQUEST,
@@ -9,35 +9,72 @@
 // by the Apache License, Version 2.0.
 
 use super::error::*;
+use super::{log_error, ListParams};
 
+use crate::schema_registry::ModifyServiceChange;
 use crate::state::AdminServiceState;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
 use okapi_operation::*;
 use restate_meta_rest_model::handlers::*;
+use restate_schema_api::listing::ListOptions;
 
 /// List discovered handlers for service
 #[openapi(
     summary = "List service handlers",
-    description = "List all the handlers of the given service.",
+    description = "List the handlers of the given service, ordered by name.",
     operation_id = "list_service_handlers",
     tags = "service_handler",
-    parameters(path(
-        name = "service",
-        description = "Fully qualified service name.",
-        schema = "std::string::String"
-    ))
+    parameters(
+        path(
+            name = "service",
+            description = "Fully qualified service name.",
+            schema = "std::string::String"
+        ),
+        query(
+            name = "name_prefix",
+            description = "List only the handlers whose name starts with the given prefix.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "String",
+        ),
+        query(
+            name = "after",
+            description = "List only the handlers after the given name, as returned in the `next` field of the previous page.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "String",
+        ),
+        query(
+            name = "limit",
+            description = "Maximum number of handlers to list.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "usize",
+        )
+    )
 )]
 pub async fn list_service_handlers<V>(
     State(state): State<AdminServiceState<V>>,
     Path(service_name): Path<String>,
+    Query(params): Query<ListParams>,
 ) -> Result<Json<ListServiceHandlersResponse>, MetaApiError> {
+    let options = ListOptions::from(params);
     match state
         .task_center
         .run_in_scope_sync("list-service-handlers", None, || {
-            state.schema_registry.list_service_handlers(&service_name)
+            state
+                .schema_registry
+                .list_service_handlers(&service_name, &options)
         }) {
-        Some(handlers) => Ok(ListServiceHandlersResponse { handlers }.into()),
+        Some(page) => Ok(ListServiceHandlersResponse {
+            handlers: page.items,
+            next: page.next,
+        }
+        .into()),
         None => Err(MetaApiError::ServiceNotFound(service_name)),
     }
 }
@@ -79,3 +116,78 @@ pub async fn get_service_handler<V>(
         }),
     }
 }
+
+/// Modify a handler of a service
+#[openapi(
+    summary = "Modify a service handler",
+    description = "Modify the handler of a registered service.",
+    operation_id = "modify_service_handler",
+    tags = "service_handler",
+    parameters(
+        path(
+            name = "service",
+            description = "Fully qualified service name.",
+            schema = "std::string::String"
+        ),
+        path(
+            name = "handler",
+            description = "Handler name.",
+            schema = "std::string::String"
+        )
+    )
+)]
+pub async fn modify_service_handler<V>(
+    State(state): State<AdminServiceState<V>>,
+    Path((service_name, handler_name)): Path<(String, String)>,
+    #[request_body(required = true)] Json(ModifyServiceHandlerRequest {
+        public,
+        idempotency_retention,
+        execution_timeout,
+        max_concurrent_invocations,
+    }): Json<ModifyServiceHandlerRequest>,
+) -> Result<Json<HandlerMetadata>, MetaApiError> {
+    let mut modify_request = vec![];
+    if let Some(new_public_value) = public {
+        modify_request.push(ModifyServiceChange::HandlerPublic(
+            handler_name.clone(),
+            new_public_value,
+        ));
+    }
+    if let Some(new_idempotency_retention) = idempotency_retention {
+        modify_request.push(ModifyServiceChange::HandlerIdempotencyRetention(
+            handler_name.clone(),
+            new_idempotency_retention.into(),
+        ));
+    }
+    if let Some(new_execution_timeout) = execution_timeout {
+        modify_request.push(ModifyServiceChange::HandlerExecutionTimeout(
+            handler_name.clone(),
+            new_execution_timeout.into(),
+        ));
+    }
+    if let Some(new_max_concurrent_invocations) = max_concurrent_invocations {
+        modify_request.push(ModifyServiceChange::HandlerMaxConcurrentInvocations(
+            handler_name.clone(),
+            new_max_concurrent_invocations,
+        ));
+    }
+
+    if modify_request.is_empty() {
+        // No need to do anything
+        return get_service_handler(State(state), Path((service_name, handler_name))).await;
+    }
+
+    let response = state
+        .task_center
+        .run_in_scope("modify-service-handler", None, async {
+            log_error(
+                state
+                    .schema_registry
+                    .modify_service_handler(service_name, handler_name, modify_request)
+                    .await,
+            )
+        })
+        .await?;
+
+    Ok(response.into())
+}
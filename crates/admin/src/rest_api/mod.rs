@@ -10,11 +10,14 @@
 
 //! This module implements the Meta API endpoint.
 
+mod dead_letters;
 mod deployments;
 mod error;
 mod handlers;
 mod health;
 mod invocations;
+mod partitions;
+mod schema;
 mod services;
 mod subscriptions;
 
@@ -22,11 +25,13 @@ use codederror::CodedError;
 use okapi_operation::axum_integration::{delete, get, patch, post};
 use okapi_operation::*;
 use restate_errors::warn_it;
+use restate_schema_api::listing::ListOptions;
 use restate_schema_api::subscription::SubscriptionValidator;
 use restate_types::identifiers::PartitionKey;
 use restate_wal_protocol::{Destination, Header, Source};
 
 use crate::state::AdminServiceState;
+use serde::Deserialize;
 
 pub fn create_router<V>(state: AdminServiceState<V>) -> axum::Router<()>
 where
@@ -63,6 +68,10 @@ where
             "/services/:service/state",
             post(openapi_handler!(services::modify_service_state)),
         )
+        .route(
+            "/services/:service/state/:key",
+            delete(openapi_handler!(services::purge_service_state)),
+        )
         .route(
             "/services/:service/handlers",
             get(openapi_handler!(handlers::list_service_handlers)),
@@ -71,10 +80,26 @@ where
             "/services/:service/handlers/:handler",
             get(openapi_handler!(handlers::get_service_handler)),
         )
+        .route(
+            "/services/:service/handlers/:handler",
+            patch(openapi_handler!(handlers::modify_service_handler)),
+        )
         .route(
             "/invocations/:invocation_id",
             delete(openapi_handler!(invocations::delete_invocation)),
         )
+        .route(
+            "/dead-letters/:invocation_id",
+            delete(openapi_handler!(dead_letters::purge_dead_letter)),
+        )
+        .route(
+            "/dead-letters/:invocation_id/redrive",
+            post(openapi_handler!(dead_letters::redrive_dead_letter)),
+        )
+        .route(
+            "/partitions/:partition_id/split",
+            post(openapi_handler!(partitions::split_partition)),
+        )
         .route(
             "/subscriptions",
             post(openapi_handler!(subscriptions::create_subscription)),
@@ -91,6 +116,14 @@ where
             "/subscriptions/:subscription",
             delete(openapi_handler!(subscriptions::delete_subscription)),
         )
+        .route(
+            "/schema/export",
+            get(openapi_handler!(schema::export_schema)),
+        )
+        .route(
+            "/schema/import",
+            post(openapi_handler!(schema::import_schema)),
+        )
         .route("/health", get(openapi_handler!(health::health)))
         .route_openapi_specification(
             "/openapi",
@@ -100,6 +133,27 @@ where
         .with_state(state)
 }
 
+/// Query parameters of the paginated listings.
+#[derive(Debug, Deserialize)]
+pub struct ListParams {
+    pub name_prefix: Option<String>,
+    pub after: Option<String>,
+    pub limit: Option<usize>,
+    pub include_retired: Option<bool>,
+}
+
+impl From<ListParams> for ListOptions {
+    fn from(value: ListParams) -> Self {
+        ListOptions {
+            name_prefix: value.name_prefix,
+            after: value.after,
+            limit: value.limit,
+            // Keep listing everything unless asked otherwise
+            include_retired: value.include_retired.unwrap_or(true),
+        }
+    }
+}
+
 fn create_envelope_header(partition_key: PartitionKey) -> Header {
     Header {
         source: Source::ControlPlane {},
@@ -0,0 +1,81 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::error::*;
+
+use crate::state::AdminServiceState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use okapi_operation::*;
+use restate_cluster_controller::SplitPartitionError;
+use restate_meta_rest_model::partitions::{SplitPartitionRequest, SplitPartitionResponse};
+use restate_types::identifiers::PartitionId;
+use restate_types::partition_table::PartitionSplitError;
+use tracing::warn;
+
+/// Split a partition
+#[openapi(
+    summary = "Split a partition",
+    description = "Split a partition in two partitions at the given split key. The partition stops \
+    processing once it has been sealed for the split, the two new partitions take over its state \
+    and continue processing. The new partitions are added to the partition table once the split \
+    has completed.",
+    operation_id = "split_partition",
+    tags = "cluster",
+    parameters(path(
+        name = "partition_id",
+        description = "Identifier of the partition to split.",
+        schema = "u64"
+    )),
+    responses(
+        ignore_return_type = true,
+        response(
+            status = "202",
+            description = "Accepted",
+            content = "Json<SplitPartitionResponse>",
+        ),
+        from_type = "MetaApiError",
+    )
+)]
+pub async fn split_partition<V>(
+    State(state): State<AdminServiceState<V>>,
+    Path(partition_id): Path<u64>,
+    #[request_body(required = true)] Json(payload): Json<SplitPartitionRequest>,
+) -> Result<(StatusCode, Json<SplitPartitionResponse>), MetaApiError> {
+    let partition_id = PartitionId::from(partition_id);
+    let split = state
+        .cluster_controller
+        .split_partition(partition_id, payload.split_key)
+        .await
+        .map_err(|err| match err {
+            SplitPartitionError::InvalidSplit(PartitionSplitError::UnknownPartition(id)) => {
+                MetaApiError::PartitionNotFound(id)
+            }
+            SplitPartitionError::InvalidSplit(PartitionSplitError::InvalidSplitKey(_)) => {
+                MetaApiError::InvalidField("split_key", err.to_string())
+            }
+            SplitPartitionError::InvalidSplit(PartitionSplitError::AlreadySplit(_))
+            | SplitPartitionError::SplitInProgress(_) => MetaApiError::Conflict(err.to_string()),
+            err => {
+                warn!("Could not split partition {partition_id}: {err}");
+                MetaApiError::Internal(err.to_string())
+            }
+        })?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(SplitPartitionResponse {
+            split_key: split.split_key,
+            left_partition_id: split.left_partition_id,
+            right_partition_id: split.right_partition_id,
+        }),
+    ))
+}
@@ -0,0 +1,87 @@
+// Copyright (c) 2024 - Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::error::*;
+use super::log_error;
+
+use crate::state::AdminServiceState;
+use axum::extract::{Query, State};
+use axum::Json;
+use okapi_operation::*;
+use restate_meta_rest_model::schema::*;
+use restate_schema::export::{ImportConflictResolution, SchemaExport};
+use serde::Deserialize;
+
+/// Query parameters of the schema import.
+#[derive(Debug, Deserialize)]
+pub struct ImportSchemaParams {
+    #[serde(default)]
+    pub on_conflict: ImportConflictResolution,
+}
+
+/// Export the schema information
+#[openapi(
+    summary = "Export schema information",
+    description = "Export the registered services, deployments and subscriptions as a self-contained document, which can be imported by another cluster.",
+    operation_id = "export_schema",
+    tags = "schema",
+    responses(
+        ignore_return_type = true,
+        response(
+            status = "200",
+            description = "OK",
+            content = "Json<serde_json::Value>",
+        ),
+    )
+)]
+pub async fn export_schema<V>(State(state): State<AdminServiceState<V>>) -> Json<SchemaExport> {
+    state
+        .task_center
+        .run_in_scope_sync("export-schema", None, || {
+            state.schema_registry.export_schema()
+        })
+        .into()
+}
+
+/// Import the schema information
+#[openapi(
+    summary = "Import schema information",
+    description = "Import the services, deployments and subscriptions of a document produced by the schema export.",
+    operation_id = "import_schema",
+    tags = "schema",
+    parameters(query(
+        name = "on_conflict",
+        description = "How to handle the imported entries conflicting with the registered ones: `fail` rejects the import, `skip` keeps the registered entries, `overwrite` replaces them. Defaults to `fail`.",
+        required = false,
+        style = "simple",
+        allow_empty_value = false,
+        schema = "String",
+    ))
+)]
+pub async fn import_schema<V>(
+    State(state): State<AdminServiceState<V>>,
+    Query(ImportSchemaParams { on_conflict }): Query<ImportSchemaParams>,
+    #[request_body(required = true)] Json(payload): Json<serde_json::Value>,
+) -> Result<Json<ImportSchemaResponse>, MetaApiError> {
+    let export: SchemaExport = serde_json::from_value(payload)
+        .map_err(|err| MetaApiError::InvalidField("payload", err.to_string()))?;
+
+    let changes = log_error(
+        state
+            .schema_registry
+            .import_schema(export, on_conflict)
+            .await,
+    )?;
+
+    Ok(ImportSchemaResponse {
+        changes: changes.iter().map(ToString::to_string).collect(),
+    }
+    .into())
+}
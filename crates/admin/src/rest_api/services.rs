@@ -9,39 +9,73 @@
 // by the Apache License, Version 2.0.
 
 use super::error::*;
-use super::{create_envelope_header, log_error};
+use super::{create_envelope_header, log_error, ListParams};
 use crate::schema_registry::ModifyServiceChange;
 use crate::state::AdminServiceState;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
 use bytes::Bytes;
 use http::StatusCode;
 use okapi_operation::*;
 use restate_meta_rest_model::services::ListServicesResponse;
 use restate_meta_rest_model::services::*;
+use restate_schema_api::listing::ListOptions;
 use restate_types::identifiers::{ServiceId, WithPartitionKey};
-use restate_types::state_mut::ExternalStateMutation;
+use restate_types::state_mut::{ExternalStateMutation, ServiceKeyPurge};
 use restate_wal_protocol::{append_envelope_to_bifrost, Command, Envelope};
+use serde::Deserialize;
 use tracing::warn;
 
 /// List services
 #[openapi(
     summary = "List services",
-    description = "List all registered services.",
+    description = "List the registered services, ordered by name.",
     operation_id = "list_services",
-    tags = "service"
+    tags = "service",
+    parameters(
+        query(
+            name = "name_prefix",
+            description = "List only the services whose name starts with the given prefix.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "String",
+        ),
+        query(
+            name = "after",
+            description = "List only the services after the given name, as returned in the `next` field of the previous page.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "String",
+        ),
+        query(
+            name = "limit",
+            description = "Maximum number of services to list.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "usize",
+        )
+    )
 )]
 pub async fn list_services<V>(
     State(state): State<AdminServiceState<V>>,
+    Query(params): Query<ListParams>,
 ) -> Result<Json<ListServicesResponse>, MetaApiError> {
-    let services = state
+    let options = ListOptions::from(params);
+    let page = state
         .task_center
         .run_in_scope_sync("list-services", None, || {
-            state.schema_registry.list_services()
+            state.schema_registry.list_services(&options)
         });
 
-    Ok(ListServicesResponse { services }.into())
+    Ok(ListServicesResponse {
+        services: page.items,
+        next: page.next,
+    }
+    .into())
 }
 
 /// Get a service
@@ -87,7 +121,9 @@ pub async fn modify_service<V>(
     #[request_body(required = true)] Json(ModifyServiceRequest {
         public,
         idempotency_retention,
+        completion_retention,
         workflow_completion_retention,
+        traffic_split,
     }): Json<ModifyServiceRequest>,
 ) -> Result<Json<ServiceMetadata>, MetaApiError> {
     let mut modify_request = vec![];
@@ -99,12 +135,21 @@ pub async fn modify_service<V>(
             new_idempotency_retention.into(),
         ));
     }
+    if let Some(new_completion_retention) = completion_retention {
+        modify_request.push(ModifyServiceChange::CompletionRetention(
+            new_completion_retention.into(),
+        ));
+    }
     if let Some(new_workflow_completion_retention) = workflow_completion_retention {
         modify_request.push(ModifyServiceChange::WorkflowCompletionRetention(
             new_workflow_completion_retention.into(),
         ));
     }
 
+    if let Some(new_traffic_split) = traffic_split {
+        modify_request.push(ModifyServiceChange::TrafficSplit(new_traffic_split));
+    }
+
     if modify_request.is_empty() {
         // No need to do anything
         return get_service(State(state), Path(service_name)).await;
@@ -193,3 +238,83 @@ pub async fn modify_service_state<V>(
         Ok(StatusCode::ACCEPTED)
     }
 }
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct PurgeServiceStateParams {
+    pub force: Option<bool>,
+}
+
+/// Purge a virtual object instance
+#[openapi(
+    summary = "Purge a virtual object instance",
+    description = "Purge the state, the inbox and the journal of the running invocation of a virtual object \
+    instance. Inboxed invocations are killed. By default, the purge is refused while an invocation is running \
+    for the given key; with force, the running invocation is killed.",
+    operation_id = "purge_service_state",
+    tags = "service",
+    parameters(
+        path(
+            name = "service",
+            description = "Fully qualified service name.",
+            schema = "std::string::String"
+        ),
+        path(
+            name = "key",
+            description = "Virtual object key.",
+            schema = "std::string::String"
+        ),
+        query(
+            name = "force",
+            description = "If true, the running invocation for the given key is killed.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "bool",
+        )
+    ),
+    responses(
+        ignore_return_type = true,
+        response(
+            status = "202",
+            description = "Accepted",
+            content = "okapi_operation::Empty",
+        ),
+        from_type = "MetaApiError",
+    )
+)]
+pub async fn purge_service_state<V>(
+    State(mut state): State<AdminServiceState<V>>,
+    Path((service_name, object_key)): Path<(String, String)>,
+    Query(PurgeServiceStateParams { force }): Query<PurgeServiceStateParams>,
+) -> Result<StatusCode, MetaApiError> {
+    let service_id = ServiceId::new(service_name, object_key);
+    let partition_key = service_id.partition_key();
+    let purge = ServiceKeyPurge {
+        service_id,
+        force: force.unwrap_or_default(),
+    };
+
+    let result = state
+        .task_center
+        .run_in_scope(
+            "purge_service_state",
+            None,
+            append_envelope_to_bifrost(
+                &mut state.bifrost,
+                Envelope::new(
+                    create_envelope_header(partition_key),
+                    Command::PurgeServiceKey(purge),
+                ),
+            ),
+        )
+        .await;
+
+    if let Err(err) = result {
+        warn!("Could not append service key purge command to Bifrost: {err}");
+        Err(MetaApiError::Internal(
+            "Failed sending service key purge command to the cluster.".to_owned(),
+        ))
+    } else {
+        Ok(StatusCode::ACCEPTED)
+    }
+}
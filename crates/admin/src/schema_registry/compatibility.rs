@@ -0,0 +1,325 @@
+// Copyright (c) 2024 - Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Checks whether a new revision of a service can replace the previous one, without breaking its
+//! clients nor its in-flight invocations.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use restate_schema::service::{HandlerSchemas, ServiceSchemas};
+use restate_schema::Schema;
+use restate_schema_api::invocation_target::{
+    InputContentType, InputRules, InputValidationRule, OutputRules,
+};
+use restate_types::invocation::{InvocationTargetType, ServiceType};
+use serde_json::Value;
+
+/// Change of the service contract which the previous revision's clients might not cope with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncompatibleChange {
+    ServiceType {
+        previous: ServiceType,
+        new: ServiceType,
+    },
+    RemovedHandler(String),
+    HandlerType {
+        handler: String,
+        previous: InvocationTargetType,
+        new: InvocationTargetType,
+    },
+    /// The handler rejects some of the inputs accepted by the previous revision.
+    Input {
+        handler: String,
+        previous: InputRules,
+        new: InputRules,
+    },
+    /// A field of the JSON input became required.
+    RequiredInputField {
+        handler: String,
+        field: String,
+    },
+    /// A field of the JSON input changed type.
+    InputFieldType {
+        handler: String,
+        field: String,
+        previous: Value,
+        new: Value,
+    },
+    Output {
+        handler: String,
+        previous: OutputRules,
+        new: OutputRules,
+    },
+}
+
+impl fmt::Display for IncompatibleChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncompatibleChange::ServiceType { previous, new } => {
+                write!(f, "service type changed from {previous} to {new}")
+            }
+            IncompatibleChange::RemovedHandler(handler) => {
+                write!(f, "handler '{handler}' was removed")
+            }
+            IncompatibleChange::HandlerType {
+                handler,
+                previous,
+                new,
+            } => write!(
+                f,
+                "handler '{handler}' type changed from {previous} to {new}"
+            ),
+            IncompatibleChange::Input {
+                handler,
+                previous,
+                new,
+            } => write!(
+                f,
+                "handler '{handler}' input changed from {previous} to {new}"
+            ),
+            IncompatibleChange::RequiredInputField { handler, field } => {
+                write!(
+                    f,
+                    "handler '{handler}' input field '{field}' became required"
+                )
+            }
+            IncompatibleChange::InputFieldType {
+                handler,
+                field,
+                previous,
+                new,
+            } => write!(
+                f,
+                "handler '{handler}' input field '{field}' type changed from {previous} to {new}"
+            ),
+            IncompatibleChange::Output {
+                handler,
+                previous,
+                new,
+            } => write!(
+                f,
+                "handler '{handler}' output changed from {previous} to {new}"
+            ),
+        }
+    }
+}
+
+/// Returns the changes of the new revision of the service which aren't backward compatible.
+pub(super) fn incompatible_changes(
+    previous: &ServiceSchemas,
+    new_ty: ServiceType,
+    new_handlers: &HashMap<String, HandlerSchemas>,
+) -> Vec<IncompatibleChange> {
+    let mut changes = vec![];
+
+    if previous.ty != new_ty {
+        // Every handler type changes as well, no need to report them
+        changes.push(IncompatibleChange::ServiceType {
+            previous: previous.ty,
+            new: new_ty,
+        });
+    }
+
+    let mut handler_names: Vec<_> = previous.handlers.keys().collect();
+    handler_names.sort();
+    for handler_name in handler_names {
+        let previous_meta = &previous.handlers[handler_name].target_meta;
+        let Some(new_handler) = new_handlers.get(handler_name) else {
+            changes.push(IncompatibleChange::RemovedHandler(handler_name.clone()));
+            continue;
+        };
+        let new_meta = &new_handler.target_meta;
+
+        if previous.ty == new_ty && previous_meta.target_ty != new_meta.target_ty {
+            changes.push(IncompatibleChange::HandlerType {
+                handler: handler_name.clone(),
+                previous: previous_meta.target_ty,
+                new: new_meta.target_ty,
+            });
+        }
+        input_changes(
+            handler_name,
+            &previous_meta.input_rules,
+            &new_meta.input_rules,
+            &mut changes,
+        );
+        if previous_meta.output_rules.content_type_rule != new_meta.output_rules.content_type_rule {
+            changes.push(IncompatibleChange::Output {
+                handler: handler_name.clone(),
+                previous: previous_meta.output_rules.clone(),
+                new: new_meta.output_rules.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Returns the incompatible changes of the services whose revision changed between the two
+/// versions of the schema information, ordered by service name.
+pub(super) fn incompatible_service_changes(
+    previous: &Schema,
+    new: &Schema,
+) -> Vec<(String, IncompatibleChange)> {
+    let mut service_names: Vec<_> = new.services.keys().collect();
+    service_names.sort();
+
+    let mut changes = vec![];
+    for service_name in service_names {
+        let new_service = &new.services[service_name];
+        let Some(previous_service) = previous.services.get(service_name) else {
+            continue;
+        };
+        if previous_service.revision == new_service.revision {
+            continue;
+        }
+        changes.extend(
+            incompatible_changes(previous_service, new_service.ty, &new_service.handlers)
+                .into_iter()
+                .map(|change| (service_name.clone(), change)),
+        );
+    }
+    changes
+}
+
+fn input_changes(
+    handler: &str,
+    previous: &InputRules,
+    new: &InputRules,
+    changes: &mut Vec<IncompatibleChange>,
+) {
+    let mut narrowed = false;
+    let mut field_changes = vec![];
+
+    // Every input accepted by a rule of the previous revision must still be accepted
+    for previous_rule in &previous.input_validation_rules {
+        let covering_rule = new
+            .input_validation_rules
+            .iter()
+            .find(|new_rule| covers(new_rule, previous_rule));
+        match (previous_rule, covering_rule) {
+            (_, None) => narrowed = true,
+            (
+                InputValidationRule::JsonValue {
+                    schema: Some(previous_schema),
+                    ..
+                },
+                Some(InputValidationRule::JsonValue {
+                    schema: Some(new_schema),
+                    ..
+                }),
+            ) => json_field_changes(
+                handler,
+                previous_schema.schema(),
+                new_schema.schema(),
+                &mut field_changes,
+            ),
+            _ => {}
+        }
+    }
+
+    if narrowed {
+        changes.push(IncompatibleChange::Input {
+            handler: handler.to_owned(),
+            previous: previous.clone(),
+            new: new.clone(),
+        });
+    }
+    changes.append(&mut field_changes);
+}
+
+fn covers(new: &InputValidationRule, previous: &InputValidationRule) -> bool {
+    match (new, previous) {
+        (InputValidationRule::NoBodyAndContentType, InputValidationRule::NoBodyAndContentType) => {
+            true
+        }
+        (
+            InputValidationRule::ContentType { content_type: new },
+            InputValidationRule::ContentType {
+                content_type: previous,
+            }
+            | InputValidationRule::JsonValue {
+                content_type: previous,
+                ..
+            },
+        ) => content_type_covers(new, previous),
+        (
+            InputValidationRule::JsonValue {
+                content_type: new, ..
+            },
+            InputValidationRule::JsonValue {
+                content_type: previous,
+                ..
+            },
+        ) => content_type_covers(new, previous),
+        _ => false,
+    }
+}
+
+fn content_type_covers(new: &InputContentType, previous: &InputContentType) -> bool {
+    match (new, previous) {
+        (InputContentType::Any, _) => true,
+        (InputContentType::MimeType(new), InputContentType::MimeType(previous))
+        | (InputContentType::MimeType(new), InputContentType::MimeTypeAndSubtype(previous, _)) => {
+            new == previous
+        }
+        (new, previous) => new == previous,
+    }
+}
+
+// Compares the top level fields of JSON object schemas, other schema changes are accepted
+fn json_field_changes(
+    handler: &str,
+    previous: &Value,
+    new: &Value,
+    changes: &mut Vec<IncompatibleChange>,
+) {
+    let previous_required = required_fields(previous);
+    for field in required_fields(new) {
+        if !previous_required.contains(&field) {
+            changes.push(IncompatibleChange::RequiredInputField {
+                handler: handler.to_owned(),
+                field: field.to_owned(),
+            });
+        }
+    }
+
+    let (Some(previous_properties), Some(new_properties)) = (
+        previous.get("properties").and_then(Value::as_object),
+        new.get("properties").and_then(Value::as_object),
+    ) else {
+        return;
+    };
+    let mut fields: Vec<_> = previous_properties.keys().collect();
+    fields.sort();
+    for field in fields {
+        let previous_type = previous_properties[field].get("type");
+        let new_type = new_properties.get(field).and_then(|p| p.get("type"));
+        if let (Some(previous_type), Some(new_type)) = (previous_type, new_type) {
+            if previous_type != new_type {
+                changes.push(IncompatibleChange::InputFieldType {
+                    handler: handler.to_owned(),
+                    field: field.clone(),
+                    previous: previous_type.clone(),
+                    new: new_type.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn required_fields(schema: &Value) -> Vec<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
@@ -8,15 +8,17 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::schema_registry::compatibility::IncompatibleChange;
 use crate::schema_registry::ServiceName;
 use http::header::InvalidHeaderValue;
 use http::Uri;
 use restate_core::metadata_store::ReadModifyWriteError;
 use restate_core::ShutdownError;
-use restate_schema_api::invocation_target::BadInputContentType;
+use restate_schema::export::SCHEMA_EXPORT_FORMAT_VERSION;
+use restate_schema_api::invocation_target::{BadInputContentType, BadInputJsonSchema};
 use restate_service_protocol::discovery::schema;
 use restate_types::errors::GenericError;
-use restate_types::identifiers::DeploymentId;
+use restate_types::identifiers::{DeploymentId, SubscriptionId};
 use restate_types::invocation::ServiceType;
 
 #[derive(Debug, thiserror::Error, codederror::CodedError)]
@@ -70,6 +72,12 @@ pub enum SchemaError {
         #[code]
         SubscriptionError,
     ),
+    #[error(transparent)]
+    Import(
+        #[from]
+        #[code]
+        ImportError,
+    ),
 }
 
 #[derive(Debug, thiserror::Error, codederror::CodedError)]
@@ -77,15 +85,15 @@ pub enum ServiceError {
     #[error("cannot insert/modify service '{0}' as it contains a reserved name")]
     #[code(restate_errors::META0005)]
     ReservedName(String),
-    #[error("detected a new service '{0}' revision with a service type different from the previous revision. Service type cannot be changed across revisions")]
+    #[error("the new revision of the service '{0}' is not compatible with the previous one: [{}]", .1.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
     #[code(restate_errors::META0006)]
-    DifferentType(ServiceName),
-    #[error("the service '{0}' already exists but the new revision removed the handlers {1:?}")]
-    #[code(restate_errors::META0006)]
-    RemovedHandlers(ServiceName, Vec<String>),
+    IncompatibleChanges(ServiceName, Vec<IncompatibleChange>),
     #[error("the handler '{0}' input content-type is not valid: {1}")]
     #[code(unknown)]
     BadInputContentType(String, BadInputContentType),
+    #[error("the handler '{0}' input JSON schema is not valid: {1}")]
+    #[code(unknown)]
+    BadInputJsonSchema(String, BadInputJsonSchema),
     #[error("the handler '{0}' output content-type is not valid: {1}")]
     #[code(unknown)]
     BadOutputContentType(String, InvalidHeaderValue),
@@ -95,6 +103,12 @@ pub enum ServiceError {
     #[error("modifying retention time for service type {0} is unsupported")]
     #[code(unknown)]
     CannotModifyRetentionTime(ServiceType),
+    #[error("the deployment '{1}' doesn't serve the service '{0}', it cannot receive its traffic")]
+    #[code(unknown)]
+    UnknownTrafficSplitDeployment(String, DeploymentId),
+    #[error("the traffic split of the service '{0}' must give a positive weight to at least one deployment")]
+    #[code(unknown)]
+    NoTrafficSplitWeight(String),
 }
 
 #[derive(Debug, thiserror::Error, codederror::CodedError)]
@@ -131,6 +145,25 @@ pub enum DeploymentError {
         requested: DeploymentId,
         existing: DeploymentId,
     },
+    #[error("the deployment '{0}' receives the new invocations of the services {1:?}, register a new deployment of these services or change their traffic split before retiring it")]
+    #[code(unknown)]
+    StillActive(DeploymentId, Vec<String>),
+    #[error("the deployment '{0}' is draining, {1} in-flight invocations are still pinned to it")]
+    #[code(unknown)]
+    PinnedInvocations(DeploymentId, u64),
+}
+
+#[derive(Debug, thiserror::Error, codederror::CodedError)]
+#[code(unknown)]
+pub enum ImportError {
+    #[error("unsupported format version {0} of the schema export, expected version {SCHEMA_EXPORT_FORMAT_VERSION}")]
+    UnsupportedFormatVersion(u32),
+    #[error("the service '{0}' refers to the deployment '{1}', which is neither registered nor imported")]
+    UnknownDeployment(String, DeploymentId),
+    #[error(
+        "the subscription '{0}' refers to the sink '{1}', which is neither registered nor imported"
+    )]
+    UnknownSink(SubscriptionId, String),
 }
 
 impl From<ReadModifyWriteError<SchemaError>> for SchemaRegistryError {
@@ -8,19 +8,27 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+pub mod compatibility;
 pub mod error;
 mod updater;
 
+use crate::schema_registry::compatibility::IncompatibleChange;
 use crate::schema_registry::error::{SchemaError, SchemaRegistryError, ServiceError};
 use crate::schema_registry::updater::SchemaUpdater;
 use http::Uri;
 use restate_core::metadata_store::MetadataStoreClient;
 use restate_core::{metadata, MetadataWriter};
+use restate_schema::changes::SchemaChange;
+use restate_schema::export::{ImportConflictResolution, SchemaExport};
 use restate_schema::Schema;
 use restate_schema_api::deployment::{
-    DeliveryOptions, Deployment, DeploymentMetadata, DeploymentResolver,
+    DeliveryOptions, Deployment, DeploymentMetadata, DeploymentResolver, DeploymentStatus,
+};
+use restate_schema_api::listing::{ListOptions, Page};
+use restate_schema_api::service::{
+    DeploymentWeight, HandlerMetadata, HandlerMetadataResolver, ServiceMetadata,
+    ServiceMetadataResolver,
 };
-use restate_schema_api::service::{HandlerMetadata, ServiceMetadata, ServiceMetadataResolver};
 use restate_schema_api::subscription::{
     ListSubscriptionFilter, Subscription, SubscriptionResolver, SubscriptionValidator,
 };
@@ -28,8 +36,10 @@ use restate_service_client::Endpoint;
 use restate_service_protocol::discovery::{DiscoverEndpoint, ServiceDiscovery};
 use restate_types::identifiers::{DeploymentId, ServiceRevision, SubscriptionId};
 use restate_types::metadata_store::keys::SCHEMA_INFORMATION_KEY;
+use restate_types::retries::RetryPolicy;
 use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::num::NonZeroU32;
 use std::ops::Deref;
 use std::time::Duration;
 use tracing::subscriber::NoSubscriber;
@@ -64,10 +74,39 @@ impl ApplyMode {
 #[derive(Debug, Clone)]
 pub enum ModifyServiceChange {
     Public(bool),
+    /// Overrides the visibility of the given handler.
+    HandlerPublic(String, bool),
+    /// Overrides the idempotency retention of the given handler.
+    HandlerIdempotencyRetention(String, Duration),
+    HandlerExecutionTimeout(String, Duration),
+    HandlerMaxConcurrentInvocations(String, NonZeroU32),
+    /// Splits the new invocations across the given deployments, or sends them all to the latest
+    /// deployment if empty.
+    TrafficSplit(Vec<DeploymentWeight>),
     IdempotencyRetention(Duration),
+    CompletionRetention(Duration),
     WorkflowCompletionRetention(Duration),
 }
 
+/// Changes applied by the registration of a deployment, or which would be applied in case of a
+/// dry run.
+#[derive(Debug, Clone, Default)]
+pub struct RegistrationReport {
+    pub changes: Vec<SchemaChange>,
+    /// Incompatible changes of the re-registered services, accepted because the registration was
+    /// forced.
+    pub incompatible_changes: Vec<(String, IncompatibleChange)>,
+}
+
+impl RegistrationReport {
+    fn new(previous: &Schema, new: &Schema) -> Self {
+        Self {
+            changes: new.changes_since(previous),
+            incompatible_changes: compatibility::incompatible_service_changes(previous, new),
+        }
+    }
+}
+
 /// Responsible for updating the registered schema information. This includes the discovery of
 /// new deployments.
 #[derive(Clone)]
@@ -96,9 +135,10 @@ impl<V> SchemaRegistry<V> {
     pub async fn register_deployment(
         &self,
         discover_endpoint: DiscoverEndpoint,
+        retry_policy: Option<RetryPolicy>,
         force: Force,
         apply_mode: ApplyMode,
-    ) -> Result<(DeploymentId, Vec<ServiceMetadata>), SchemaRegistryError> {
+    ) -> Result<(DeploymentId, Vec<ServiceMetadata>, RegistrationReport), SchemaRegistryError> {
         // The number of concurrent discovery calls is bound by the number of concurrent
         // register_deployment calls. If it should become a problem that a user tries to register
         // the same endpoint too often, then we need to add a synchronization mechanism which
@@ -109,19 +149,21 @@ impl<V> SchemaRegistry<V> {
             (Endpoint::Http(uri, _), headers) => DeploymentMetadata::new_http(
                 uri.clone(),
                 discovered_metadata.protocol_type,
-                DeliveryOptions::new(headers),
+                discovered_metadata.content_encoding,
+                DeliveryOptions::new(headers, retry_policy),
                 discovered_metadata.supported_protocol_versions,
             ),
             (Endpoint::Lambda(arn, assume_role_arn), headers) => DeploymentMetadata::new_lambda(
                 arn,
                 assume_role_arn,
-                DeliveryOptions::new(headers),
+                DeliveryOptions::new(headers, retry_policy),
                 discovered_metadata.supported_protocol_versions,
             ),
         };
 
-        let (id, services) = if !apply_mode.should_apply() {
-            let mut updater = SchemaUpdater::from(metadata().schema().deref().clone());
+        let (id, services, report) = if !apply_mode.should_apply() {
+            let previous_schema_information = metadata().schema();
+            let mut updater = SchemaUpdater::from(previous_schema_information.deref().clone());
 
             // suppress logging output in case of a dry run
             let id = tracing::subscriber::with_default(NoSubscriber::new(), || {
@@ -134,20 +176,22 @@ impl<V> SchemaRegistry<V> {
             })?;
 
             let schema_information = updater.into_inner();
+            let report = RegistrationReport::new(&previous_schema_information, &schema_information);
             let (_, services) = schema_information
                 .get_deployment_and_services(&id)
                 .expect("deployment was just added");
 
-            (id, services)
+            (id, services, report)
         } else {
             let mut new_deployment_id = None;
+            let mut report = None;
             let schema_information = self
                 .metadata_store_client
                 .read_modify_write(
                     SCHEMA_INFORMATION_KEY.clone(),
                     |schema_information: Option<Schema>| {
-                        let mut updater =
-                            SchemaUpdater::from(schema_information.unwrap_or_default());
+                        let previous_schema_information = schema_information.unwrap_or_default();
+                        let mut updater = SchemaUpdater::from(previous_schema_information.clone());
 
                         new_deployment_id = Some(updater.add_deployment(
                             None,
@@ -155,7 +199,12 @@ impl<V> SchemaRegistry<V> {
                             discovered_metadata.services.clone(),
                             force.force_enabled(),
                         )?);
-                        Ok(updater.into_inner())
+                        let schema_information = updater.into_inner();
+                        report = Some(RegistrationReport::new(
+                            &previous_schema_information,
+                            &schema_information,
+                        ));
+                        Ok(schema_information)
                     },
                 )
                 .await?;
@@ -167,36 +216,36 @@ impl<V> SchemaRegistry<V> {
 
             self.metadata_writer.update(schema_information).await?;
 
-            (new_deployment_id, services)
+            (
+                new_deployment_id,
+                services,
+                report.expect("deployment was just added"),
+            )
         };
 
-        Ok((id, services))
+        Ok((id, services, report))
     }
 
+    /// Removes the deployment, recording a tombstone of it. Unless `force` is set, this fails if
+    /// the deployment still serves the latest revision of a service. The caller must make sure
+    /// that no invocations are pinned to the deployment anymore.
     pub async fn delete_deployment(
         &self,
         deployment_id: DeploymentId,
+        force: bool,
     ) -> Result<(), SchemaRegistryError> {
-        let schema_registry = self
+        let schema_information = self
             .metadata_store_client
             .read_modify_write(
                 SCHEMA_INFORMATION_KEY.clone(),
-                |schema_registry: Option<Schema>| {
-                    let schema_information: Schema = schema_registry.unwrap_or_default();
-
-                    if schema_information.get_deployment(&deployment_id).is_some() {
-                        let mut updater = SchemaUpdater::from(schema_information);
-                        updater.remove_deployment(deployment_id);
-                        Ok(updater.into_inner())
-                    } else {
-                        Err(SchemaError::NotFound(format!(
-                            "deployment with id '{deployment_id}'"
-                        )))
-                    }
+                |schema_information: Option<Schema>| {
+                    let mut updater = SchemaUpdater::from(schema_information.unwrap_or_default());
+                    updater.remove_deployment(deployment_id, force)?;
+                    Ok(updater.into_inner())
                 },
             )
             .await?;
-        self.metadata_writer.update(schema_registry).await?;
+        self.metadata_writer.update(schema_information).await?;
 
         Ok(())
     }
@@ -238,6 +287,21 @@ impl<V> SchemaRegistry<V> {
         Ok(response)
     }
 
+    pub async fn modify_service_handler(
+        &self,
+        service_name: String,
+        handler_name: String,
+        changes: Vec<ModifyServiceChange>,
+    ) -> Result<HandlerMetadata, SchemaRegistryError> {
+        let service_metadata = self.modify_service(service_name, changes).await?;
+
+        Ok(service_metadata
+            .handlers
+            .into_iter()
+            .find(|handler| handler.name == handler_name)
+            .expect("handler was just modified"))
+    }
+
     pub async fn delete_subscription(
         &self,
         subscription_id: SubscriptionId,
@@ -270,8 +334,40 @@ impl<V> SchemaRegistry<V> {
         Ok(())
     }
 
-    pub fn list_services(&self) -> Vec<ServiceMetadata> {
-        metadata().schema().list_services()
+    pub fn export_schema(&self) -> SchemaExport {
+        metadata().schema().export()
+    }
+
+    /// Imports the given export, returning the changes it applied to the schema information.
+    pub async fn import_schema(
+        &self,
+        export: SchemaExport,
+        on_conflict: ImportConflictResolution,
+    ) -> Result<Vec<SchemaChange>, SchemaRegistryError> {
+        let mut changes = None;
+        let schema_information = self
+            .metadata_store_client
+            .read_modify_write(
+                SCHEMA_INFORMATION_KEY.clone(),
+                |schema_information: Option<Schema>| {
+                    let previous_schema_information = schema_information.unwrap_or_default();
+                    let mut updater = SchemaUpdater::from(previous_schema_information.clone());
+                    updater.import(export.clone(), on_conflict)?;
+
+                    let schema_information = updater.into_inner();
+                    changes = Some(schema_information.changes_since(&previous_schema_information));
+                    Ok(schema_information)
+                },
+            )
+            .await?;
+
+        self.metadata_writer.update(schema_information).await?;
+
+        Ok(changes.expect("schema information was just imported"))
+    }
+
+    pub fn list_services(&self, options: &ListOptions) -> Page<ServiceMetadata> {
+        metadata().schema().list_services_page(options)
     }
 
     pub fn get_service(&self, service_name: impl AsRef<str>) -> Option<ServiceMetadata> {
@@ -287,18 +383,26 @@ impl<V> SchemaRegistry<V> {
             .get_deployment_and_services(&deployment_id)
     }
 
-    pub fn list_deployments(&self) -> Vec<(Deployment, Vec<(String, ServiceRevision)>)> {
-        metadata().schema().get_deployments()
+    pub fn get_deployment_status(&self, deployment_id: DeploymentId) -> Option<DeploymentStatus> {
+        metadata().schema().get_deployment_status(&deployment_id)
+    }
+
+    pub fn list_deployments(
+        &self,
+        options: &ListOptions,
+    ) -> Page<(Deployment, Vec<(String, ServiceRevision)>)> {
+        metadata().schema().list_deployments_page(options)
     }
 
     pub fn list_service_handlers(
         &self,
         service_name: impl AsRef<str>,
-    ) -> Option<Vec<HandlerMetadata>> {
+        options: &ListOptions,
+    ) -> Option<Page<HandlerMetadata>> {
         metadata()
             .schema()
             .resolve_latest_service(&service_name)
-            .map(|m| m.handlers)
+            .map(|m| options.paginate(m.handlers, |handler| handler.name.clone()))
     }
 
     pub fn get_service_handler(
@@ -308,12 +412,7 @@ impl<V> SchemaRegistry<V> {
     ) -> Option<HandlerMetadata> {
         metadata()
             .schema()
-            .resolve_latest_service(&service_name)
-            .and_then(|m| {
-                m.handlers
-                    .into_iter()
-                    .find(|handler| handler.name == handler_name.as_ref())
-            })
+            .resolve_latest_handler(service_name, handler_name)
     }
 
     pub fn get_subscription(&self, subscription_id: SubscriptionId) -> Option<Subscription> {
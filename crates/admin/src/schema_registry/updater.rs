@@ -9,18 +9,23 @@
 // by the Apache License, Version 2.0.
 
 use crate::schema_registry::error::{
-    DeploymentError, SchemaError, ServiceError, SubscriptionError,
+    DeploymentError, ImportError, SchemaError, ServiceError, SubscriptionError,
 };
-use crate::schema_registry::{ModifyServiceChange, ServiceName};
+use crate::schema_registry::{compatibility, ModifyServiceChange, ServiceName};
 use http::{HeaderValue, Uri};
 use restate_schema::deployment::DeploymentSchemas;
+use restate_schema::export::{
+    ImportConflictResolution, SchemaExport, SCHEMA_EXPORT_FORMAT_VERSION,
+};
 use restate_schema::service::{HandlerSchemas, ServiceLocation, ServiceSchemas};
 use restate_schema::Schema;
-use restate_schema_api::deployment::DeploymentMetadata;
+use restate_schema_api::deployment::{DeploymentMetadata, DeploymentTombstone};
 use restate_schema_api::invocation_target::{
-    InputRules, InputValidationRule, InvocationTargetMetadata, OutputContentTypeRule, OutputRules,
-    DEFAULT_IDEMPOTENCY_RETENTION, DEFAULT_WORKFLOW_COMPLETION_RETENTION,
+    InputJsonSchema, InputRules, InputValidationRule, InvocationTargetMetadata,
+    OutputContentTypeRule, OutputRules, DEFAULT_IDEMPOTENCY_RETENTION,
+    DEFAULT_WORKFLOW_COMPLETION_RETENTION,
 };
+use restate_schema_api::service::DeploymentWeight;
 use restate_schema_api::subscription::{
     EventReceiverServiceType, Sink, Source, Subscription, SubscriptionValidator,
 };
@@ -29,11 +34,15 @@ use restate_types::identifiers::{DeploymentId, SubscriptionId};
 use restate_types::invocation::{
     InvocationTargetType, ServiceType, VirtualObjectHandlerType, WorkflowHandlerType,
 };
+use restate_types::time::MillisSinceEpoch;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use tracing::{info, warn};
 
+/// Number of deployment tombstones kept in the schema information.
+const MAX_DEPLOYMENT_TOMBSTONES: usize = 100;
+
 /// Responsible for updating the provided [`Schema`] with new
 /// schema information. It makes sure that the version of schema information
 /// is incremented on changes.
@@ -140,43 +149,26 @@ impl SchemaUpdater {
             let service_schema = if let Some(existing_service) =
                 self.schema_information.services.get(service_name.as_ref())
             {
-                let removed_handlers: Vec<String> = existing_service
-                    .handlers
-                    .keys()
-                    .filter(|name| !handlers.contains_key(*name))
-                    .map(|name| name.to_string())
-                    .collect();
-
-                if !removed_handlers.is_empty() {
-                    if force {
-                        warn!(
-                            restate.deployment.id = %deployment_id,
-                            restate.deployment.address = %deployment_metadata.address_display(),
-                            "Going to remove the following methods from service type {} due to a forced deployment update: {:?}.",
-                            service.name.as_str(),
-                            removed_handlers
-                        );
-                    } else {
-                        return Err(SchemaError::Service(ServiceError::RemovedHandlers(
-                            service_name,
-                            removed_handlers,
-                        )));
-                    }
-                }
+                let incompatible_changes =
+                    compatibility::incompatible_changes(existing_service, service_type, &handlers);
 
-                if existing_service.ty != service_type {
+                if !incompatible_changes.is_empty() {
                     if force {
                         warn!(
                             restate.deployment.id = %deployment_id,
                             restate.deployment.address = %deployment_metadata.address_display(),
-                            "Going to overwrite service type {} due to a forced deployment update: {:?} != {:?}. This is a potentially dangerous operation, and might result in data loss.",
+                            "Going to apply the following incompatible changes to service {} due to a forced deployment update: [{}]. This is a potentially dangerous operation, and might result in data loss.",
                             service_name,
-                            existing_service.ty,
-                            service_type
+                            incompatible_changes
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join(", ")
                         );
                     } else {
-                        return Err(SchemaError::Service(ServiceError::DifferentType(
+                        return Err(SchemaError::Service(ServiceError::IncompatibleChanges(
                             service_name,
+                            incompatible_changes,
                         )));
                     }
                 }
@@ -190,6 +182,18 @@ impl SchemaUpdater {
                 service_schemas.ty = service_type;
                 service_schemas.handlers = handlers;
                 service_schemas.location.latest_deployment = deployment_id;
+                if !service_schemas.location.traffic_split.is_empty() {
+                    info!(
+                        rpc.service = %service_name,
+                        "Removing the traffic split of the service, the new deployment receives all its new invocations"
+                    );
+                    service_schemas.location.traffic_split.clear();
+                }
+
+                // The new handlers inherit the configuration of the previous revision
+                for change in previous_configuration(existing_service, &service_schemas) {
+                    apply_service_change(&mut service_schemas, change)?;
+                }
 
                 service_schemas
             } else {
@@ -200,8 +204,10 @@ impl SchemaUpdater {
                     location: ServiceLocation {
                         latest_deployment: deployment_id,
                         public: true,
+                        traffic_split: vec![],
                     },
                     idempotency_retention: DEFAULT_IDEMPOTENCY_RETENTION,
+                    completion_retention: None,
                     workflow_completion_retention: if service_type == ServiceType::Workflow {
                         Some(DEFAULT_WORKFLOW_COMPLETION_RETENTION)
                     } else {
@@ -241,24 +247,181 @@ impl SchemaUpdater {
         Ok(deployment_id)
     }
 
-    pub fn remove_deployment(&mut self, deployment_id: DeploymentId) {
-        if let Some(deployment) = self.schema_information.deployments.remove(&deployment_id) {
-            for service_metadata in deployment.services {
-                match self
-                    .schema_information
-                    .services
-                    .entry(service_metadata.name)
-                {
-                    // we need to check for the right revision in the service has been overwritten
-                    // by a different deployment.
-                    Entry::Occupied(entry) if entry.get().revision == service_metadata.revision => {
-                        entry.remove();
-                    }
-                    _ => {}
+    /// Removes the deployment, along with the services whose latest revision it serves, and
+    /// records its tombstone. Unless forced, this fails if the deployment is still active, see
+    /// [`DeploymentStatus`]. The caller must make sure that no invocations are pinned to it
+    /// anymore.
+    ///
+    /// [`DeploymentStatus`]: restate_schema_api::deployment::DeploymentStatus
+    pub fn remove_deployment(
+        &mut self,
+        deployment_id: DeploymentId,
+        force: bool,
+    ) -> Result<(), SchemaError> {
+        if !force {
+            let mut latest_services = self
+                .schema_information
+                .latest_services_of_deployment(&deployment_id);
+            if !latest_services.is_empty() {
+                latest_services.sort();
+                return Err(SchemaError::Deployment(DeploymentError::StillActive(
+                    deployment_id,
+                    latest_services,
+                )));
+            }
+        }
+
+        let Some(deployment) = self.schema_information.deployments.remove(&deployment_id) else {
+            return Err(SchemaError::NotFound(format!(
+                "deployment with id '{deployment_id}'"
+            )));
+        };
+
+        let mut removed_services = vec![];
+        for service_metadata in deployment.services {
+            match self
+                .schema_information
+                .services
+                .entry(service_metadata.name)
+            {
+                // we need to check for the right revision in the service has been overwritten
+                // by a different deployment.
+                Entry::Occupied(entry) if entry.get().revision == service_metadata.revision => {
+                    removed_services.push(entry.key().clone());
+                    entry.remove();
                 }
+                _ => {}
+            }
+        }
+        for service in self.schema_information.services.values_mut() {
+            service
+                .location
+                .traffic_split
+                .retain(|deployment| deployment.deployment_id != deployment_id);
+        }
+        removed_services.sort();
+
+        info!(
+            restate.deployment.id = %deployment_id,
+            restate.deployment.address = %deployment.metadata.address_display(),
+            "Removing deployment{}, along with the services {:?}",
+            if force { " forcefully" } else { "" },
+            removed_services
+        );
+        let tombstones = &mut self.schema_information.deployment_tombstones;
+        tombstones.push(DeploymentTombstone {
+            id: deployment_id,
+            address: deployment.metadata.address_display().to_string(),
+            created_at: deployment.metadata.created_at,
+            removed_at: MillisSinceEpoch::now(),
+            forced: force,
+            removed_services,
+        });
+        if tombstones.len() > MAX_DEPLOYMENT_TOMBSTONES {
+            tombstones.drain(..tombstones.len() - MAX_DEPLOYMENT_TOMBSTONES);
+        }
+        self.modified = true;
+
+        Ok(())
+    }
+
+    /// Imports the services, deployments and subscriptions of the given export. The imported
+    /// entries conflicting with the registered ones are handled as requested by `on_conflict`.
+    /// A deployment conflicts with the registered one with the same id or endpoint.
+    pub fn import(
+        &mut self,
+        export: SchemaExport,
+        on_conflict: ImportConflictResolution,
+    ) -> Result<(), SchemaError> {
+        if export.format_version != SCHEMA_EXPORT_FORMAT_VERSION {
+            return Err(ImportError::UnsupportedFormatVersion(export.format_version).into());
+        }
+
+        let mut schema_information = self.schema_information.clone();
+
+        for (deployment_id, deployment) in export.deployments {
+            let existing_deployment_id = schema_information
+                .find_existing_deployment_by_id(&deployment_id)
+                .or_else(|| {
+                    schema_information.find_existing_deployment_by_endpoint(&deployment.metadata.ty)
+                })
+                .map(|(id, _)| *id);
+            if let Some(existing_deployment_id) = existing_deployment_id {
+                if !overwrite_on_conflict(on_conflict, || {
+                    format!("deployment with id '{existing_deployment_id}'")
+                })? {
+                    continue;
+                }
+                schema_information
+                    .deployments
+                    .remove(&existing_deployment_id);
+            }
+            schema_information
+                .deployments
+                .insert(deployment_id, deployment);
+        }
+
+        for (service_name, service) in export.services {
+            if schema_information.services.contains_key(&service_name)
+                && !overwrite_on_conflict(on_conflict, || {
+                    format!("service with name '{service_name}'")
+                })?
+            {
+                continue;
+            }
+            schema_information.services.insert(service_name, service);
+        }
+
+        for (subscription_id, subscription) in export.subscriptions {
+            if schema_information
+                .subscriptions
+                .contains_key(&subscription_id)
+                && !overwrite_on_conflict(on_conflict, || {
+                    format!("subscription with id '{subscription_id}'")
+                })?
+            {
+                continue;
+            }
+            schema_information
+                .subscriptions
+                .insert(subscription_id, subscription);
+        }
+
+        // Mixing the imported and registered entries must not leave dangling references
+        for (service_name, service) in &schema_information.services {
+            let location = &service.location;
+            if let Some(deployment_id) = std::iter::once(&location.latest_deployment)
+                .chain(location.traffic_split.iter().map(|w| &w.deployment_id))
+                .find(|id| !schema_information.deployments.contains_key(id))
+            {
+                return Err(
+                    ImportError::UnknownDeployment(service_name.clone(), *deployment_id).into(),
+                );
+            }
+        }
+        for subscription in schema_information.subscriptions.values() {
+            let Sink::Service { name, handler, .. } = subscription.sink();
+            if !schema_information
+                .services
+                .get(name)
+                .is_some_and(|service| service.handlers.contains_key(handler))
+            {
+                return Err(ImportError::UnknownSink(
+                    subscription.id(),
+                    subscription.sink().to_string(),
+                )
+                .into());
             }
-            self.modified = true;
         }
+
+        info!(
+            "Imported the schema information exported at version {}",
+            export.schema_version
+        );
+        self.schema_information = schema_information;
+        self.modified = true;
+
+        Ok(())
     }
 
     pub fn add_subscription<V: SubscriptionValidator>(
@@ -396,40 +559,15 @@ impl SchemaUpdater {
         name: String,
         changes: Vec<ModifyServiceChange>,
     ) -> Result<(), SchemaError> {
+        for change in &changes {
+            if let ModifyServiceChange::TrafficSplit(traffic_split) = change {
+                self.check_traffic_split(&name, traffic_split)?;
+            }
+        }
+
         if let Some(schemas) = self.schema_information.services.get_mut(&name) {
             for command in changes {
-                match command {
-                    ModifyServiceChange::Public(new_public_value) => {
-                        schemas.location.public = new_public_value;
-                        for h in schemas.handlers.values_mut() {
-                            h.target_meta.public = new_public_value;
-                        }
-                    }
-                    ModifyServiceChange::IdempotencyRetention(new_idempotency_retention) => {
-                        schemas.idempotency_retention = new_idempotency_retention;
-                        for h in schemas.handlers.values_mut() {
-                            h.target_meta.idempotency_retention = new_idempotency_retention;
-                        }
-                    }
-                    ModifyServiceChange::WorkflowCompletionRetention(
-                        new_workflow_completion_retention,
-                    ) => {
-                        if schemas.ty != ServiceType::Workflow {
-                            return Err(SchemaError::Service(
-                                ServiceError::CannotModifyRetentionTime(schemas.ty),
-                            ));
-                        }
-                        schemas.workflow_completion_retention =
-                            Some(new_workflow_completion_retention);
-                        for h in schemas.handlers.values_mut().filter(|w| {
-                            w.target_meta.target_ty
-                                == InvocationTargetType::Workflow(WorkflowHandlerType::Workflow)
-                        }) {
-                            h.target_meta.completion_retention =
-                                Some(new_workflow_completion_retention);
-                        }
-                    }
-                }
+                apply_service_change(schemas, command)?;
             }
         }
 
@@ -437,6 +575,185 @@ impl SchemaUpdater {
 
         Ok(())
     }
+
+    fn check_traffic_split(
+        &self,
+        service_name: &str,
+        traffic_split: &[DeploymentWeight],
+    ) -> Result<(), SchemaError> {
+        for deployment in traffic_split {
+            let serves_service = self
+                .schema_information
+                .deployments
+                .get(&deployment.deployment_id)
+                .is_some_and(|schemas| {
+                    schemas
+                        .services
+                        .iter()
+                        .any(|service| service.name == service_name)
+                });
+            if !serves_service {
+                return Err(SchemaError::Service(
+                    ServiceError::UnknownTrafficSplitDeployment(
+                        service_name.to_owned(),
+                        deployment.deployment_id,
+                    ),
+                ));
+            }
+        }
+
+        if !traffic_split.is_empty() && traffic_split.iter().all(|d| d.weight == 0) {
+            return Err(SchemaError::Service(ServiceError::NoTrafficSplitWeight(
+                service_name.to_owned(),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether the imported entry must replace the registered one it conflicts with.
+fn overwrite_on_conflict(
+    on_conflict: ImportConflictResolution,
+    describe_entry: impl FnOnce() -> String,
+) -> Result<bool, SchemaError> {
+    match on_conflict {
+        ImportConflictResolution::Fail => Err(SchemaError::Override(describe_entry())),
+        ImportConflictResolution::Skip => Ok(false),
+        ImportConflictResolution::Overwrite => Ok(true),
+    }
+}
+
+fn apply_service_change(
+    schemas: &mut ServiceSchemas,
+    change: ModifyServiceChange,
+) -> Result<(), SchemaError> {
+    match change {
+        ModifyServiceChange::Public(new_public_value) => {
+            schemas.location.public = new_public_value;
+            for h in schemas.handlers.values_mut() {
+                h.target_meta.public = h.public.unwrap_or(new_public_value);
+            }
+        }
+        ModifyServiceChange::HandlerPublic(handler_name, new_public_value) => {
+            let h = handler_schemas_mut(schemas, &handler_name)?;
+            h.public = Some(new_public_value);
+            h.target_meta.public = new_public_value;
+        }
+        ModifyServiceChange::HandlerIdempotencyRetention(
+            handler_name,
+            new_idempotency_retention,
+        ) => {
+            let h = handler_schemas_mut(schemas, &handler_name)?;
+            h.idempotency_retention = Some(new_idempotency_retention);
+            h.target_meta.idempotency_retention = new_idempotency_retention;
+        }
+        ModifyServiceChange::HandlerExecutionTimeout(handler_name, new_execution_timeout) => {
+            handler_schemas_mut(schemas, &handler_name)?.execution_timeout =
+                Some(new_execution_timeout);
+        }
+        ModifyServiceChange::HandlerMaxConcurrentInvocations(
+            handler_name,
+            new_max_concurrent_invocations,
+        ) => {
+            handler_schemas_mut(schemas, &handler_name)?.max_concurrent_invocations =
+                Some(new_max_concurrent_invocations);
+        }
+        ModifyServiceChange::TrafficSplit(traffic_split) => {
+            schemas.location.traffic_split = traffic_split;
+        }
+        ModifyServiceChange::IdempotencyRetention(new_idempotency_retention) => {
+            schemas.idempotency_retention = new_idempotency_retention;
+            for h in schemas.handlers.values_mut() {
+                h.target_meta.idempotency_retention =
+                    h.idempotency_retention.unwrap_or(new_idempotency_retention);
+            }
+        }
+        ModifyServiceChange::CompletionRetention(new_completion_retention) => {
+            schemas.completion_retention = Some(new_completion_retention);
+            for h in schemas.handlers.values_mut().filter(|h| {
+                h.target_meta.target_ty
+                    != InvocationTargetType::Workflow(WorkflowHandlerType::Workflow)
+            }) {
+                h.target_meta.completion_retention = Some(new_completion_retention);
+            }
+        }
+        ModifyServiceChange::WorkflowCompletionRetention(new_workflow_completion_retention) => {
+            if schemas.ty != ServiceType::Workflow {
+                return Err(SchemaError::Service(
+                    ServiceError::CannotModifyRetentionTime(schemas.ty),
+                ));
+            }
+            schemas.workflow_completion_retention = Some(new_workflow_completion_retention);
+            for h in schemas.handlers.values_mut().filter(|w| {
+                w.target_meta.target_ty
+                    == InvocationTargetType::Workflow(WorkflowHandlerType::Workflow)
+            }) {
+                h.target_meta.completion_retention = Some(new_workflow_completion_retention);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handler_schemas_mut<'a>(
+    schemas: &'a mut ServiceSchemas,
+    handler_name: &str,
+) -> Result<&'a mut HandlerSchemas, SchemaError> {
+    schemas
+        .handlers
+        .get_mut(handler_name)
+        .ok_or_else(|| SchemaError::NotFound(format!("handler '{handler_name}'")))
+}
+
+/// Configuration of the previous revision of the service, to apply to the handlers of a new one.
+fn previous_configuration(
+    previous: &ServiceSchemas,
+    new: &ServiceSchemas,
+) -> Vec<ModifyServiceChange> {
+    let mut changes = vec![
+        ModifyServiceChange::Public(previous.location.public),
+        ModifyServiceChange::IdempotencyRetention(previous.idempotency_retention),
+    ];
+    for (name, h) in previous
+        .handlers
+        .iter()
+        .filter(|(name, _)| new.handlers.contains_key(*name))
+    {
+        changes.extend(
+            h.public
+                .map(|public| ModifyServiceChange::HandlerPublic(name.clone(), public)),
+        );
+        changes.extend(h.idempotency_retention.map(|idempotency_retention| {
+            ModifyServiceChange::HandlerIdempotencyRetention(name.clone(), idempotency_retention)
+        }));
+        changes.extend(h.execution_timeout.map(|execution_timeout| {
+            ModifyServiceChange::HandlerExecutionTimeout(name.clone(), execution_timeout)
+        }));
+        changes.extend(
+            h.max_concurrent_invocations
+                .map(|max_concurrent_invocations| {
+                    ModifyServiceChange::HandlerMaxConcurrentInvocations(
+                        name.clone(),
+                        max_concurrent_invocations,
+                    )
+                }),
+        );
+    }
+    changes.extend(
+        previous
+            .completion_retention
+            .map(ModifyServiceChange::CompletionRetention),
+    );
+    if new.ty == ServiceType::Workflow {
+        changes.extend(
+            previous
+                .workflow_completion_retention
+                .map(ModifyServiceChange::WorkflowCompletionRetention),
+        );
+    }
+    changes
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -514,8 +831,13 @@ impl DiscoveredHandlerMetadata {
             })
             .transpose()?
         {
-            if schema.json_schema.is_some() {
-                input_validation_rules.push(InputValidationRule::JsonValue { content_type });
+            if let Some(json_schema) = schema.json_schema {
+                let schema = InputJsonSchema::new(json_schema)
+                    .map_err(|e| ServiceError::BadInputJsonSchema(handler_name.to_owned(), e))?;
+                input_validation_rules.push(InputValidationRule::JsonValue {
+                    content_type,
+                    schema: Some(schema),
+                });
             } else {
                 input_validation_rules.push(InputValidationRule::ContentType { content_type });
             }
@@ -568,6 +890,10 @@ impl DiscoveredHandlerMetadata {
                             input_rules: handler.input,
                             output_rules: handler.output,
                         },
+                        public: None,
+                        idempotency_retention: None,
+                        execution_timeout: None,
+                        max_concurrent_invocations: None,
                     },
                 )
             })
@@ -579,11 +905,17 @@ impl DiscoveredHandlerMetadata {
 mod tests {
     use super::*;
 
-    use restate_schema_api::deployment::{Deployment, DeploymentResolver};
-    use restate_schema_api::service::ServiceMetadataResolver;
+    use restate_schema::changes::SchemaChange;
+    use restate_schema_api::deployment::{Deployment, DeploymentResolver, DeploymentStatus};
+    use restate_schema_api::invocation_target::InvocationTargetResolver;
+    use restate_schema_api::listing::ListOptions;
+    use restate_schema_api::service::{HandlerMetadataResolver, ServiceMetadataResolver};
     use restate_test_util::{assert, assert_eq, let_assert};
 
+    use restate_types::identifiers::{InvocationId, InvocationUuid};
     use restate_types::Versioned;
+    use std::num::NonZeroU32;
+    use std::time::Duration;
     use test_log::test;
 
     const GREETER_SERVICE_NAME: &str = "greeter.Greeter";
@@ -738,10 +1070,317 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn modify_handler_visibility() -> Result<(), SchemaError> {
+        let mut updater = SchemaUpdater::default();
+        let deployment = Deployment::mock();
+
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            false,
+        )?;
+        updater.modify_service(
+            GREETER_SERVICE_NAME.to_owned(),
+            vec![ModifyServiceChange::HandlerPublic(
+                "greet".to_owned(),
+                false,
+            )],
+        )?;
+        assert!(let SchemaError::NotFound(_) = updater
+            .modify_service(
+                GREETER_SERVICE_NAME.to_owned(),
+                vec![ModifyServiceChange::HandlerPublic("unknown".to_owned(), false)],
+            )
+            .unwrap_err());
+
+        // The handler override takes precedence over the service visibility
+        updater.modify_service(
+            GREETER_SERVICE_NAME.to_owned(),
+            vec![ModifyServiceChange::Public(true)],
+        )?;
+        let schemas = updater.into_inner();
+
+        let service = schemas.assert_service(GREETER_SERVICE_NAME);
+        assert!(service.public);
+        assert!(!service.handlers[0].public);
+        assert!(
+            !schemas
+                .resolve_latest_invocation_target(GREETER_SERVICE_NAME, "greet")
+                .unwrap()
+                .public
+        );
+
+        // The override is kept when updating the deployment
+        updater = SchemaUpdater::from(schemas);
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            true,
+        )?;
+        let schemas = updater.into_inner();
+
+        assert!(schemas.assert_service(GREETER_SERVICE_NAME).public);
+        assert!(
+            !schemas
+                .resolve_latest_invocation_target(GREETER_SERVICE_NAME, "greet")
+                .unwrap()
+                .public
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn modify_handler_policies() -> Result<(), SchemaError> {
+        let mut updater = SchemaUpdater::default();
+        let deployment = Deployment::mock();
+
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            false,
+        )?;
+        updater.modify_service(
+            GREETER_SERVICE_NAME.to_owned(),
+            vec![
+                ModifyServiceChange::HandlerIdempotencyRetention(
+                    "greet".to_owned(),
+                    Duration::from_secs(10),
+                ),
+                ModifyServiceChange::HandlerExecutionTimeout(
+                    "greet".to_owned(),
+                    Duration::from_secs(30),
+                ),
+                ModifyServiceChange::HandlerMaxConcurrentInvocations(
+                    "greet".to_owned(),
+                    NonZeroU32::new(5).unwrap(),
+                ),
+            ],
+        )?;
+        assert!(let SchemaError::NotFound(_) = updater
+            .modify_service(
+                GREETER_SERVICE_NAME.to_owned(),
+                vec![ModifyServiceChange::HandlerExecutionTimeout(
+                    "unknown".to_owned(),
+                    Duration::from_secs(30),
+                )],
+            )
+            .unwrap_err());
+
+        // The handler override takes precedence over the service idempotency retention
+        updater.modify_service(
+            GREETER_SERVICE_NAME.to_owned(),
+            vec![ModifyServiceChange::IdempotencyRetention(
+                Duration::from_secs(60),
+            )],
+        )?;
+        let schemas = updater.into_inner();
+
+        let assert_handler_policies = |schemas: &Schema| {
+            let handler = schemas
+                .resolve_latest_handler(GREETER_SERVICE_NAME, "greet")
+                .unwrap();
+            assert_eq!(
+                handler.idempotency_retention,
+                Some(Duration::from_secs(10).into())
+            );
+            assert_eq!(
+                handler.execution_timeout,
+                Some(Duration::from_secs(30).into())
+            );
+            assert_eq!(handler.max_concurrent_invocations, NonZeroU32::new(5));
+            assert_eq!(
+                schemas
+                    .resolve_latest_invocation_target(GREETER_SERVICE_NAME, "greet")
+                    .unwrap()
+                    .idempotency_retention,
+                Duration::from_secs(10)
+            );
+        };
+        assert_handler_policies(&schemas);
+        assert!(schemas
+            .resolve_latest_handler(GREETER_SERVICE_NAME, "unknown")
+            .is_none());
+
+        // The overrides are kept when updating the deployment
+        updater = SchemaUpdater::from(schemas);
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            true,
+        )?;
+        assert_handler_policies(&updater.into_inner());
+
+        Ok(())
+    }
+
+    #[test]
+    fn schema_changes() -> Result<(), SchemaError> {
+        let mut updater = SchemaUpdater::default();
+        let deployment = Deployment::mock();
+
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            false,
+        )?;
+        let registered = updater.into_inner();
+        assert_eq!(
+            registered.changes_since(&Schema::default()),
+            vec![
+                SchemaChange::DeploymentRegistered(deployment.id),
+                SchemaChange::ServiceRegistered {
+                    service: GREETER_SERVICE_NAME.to_owned(),
+                    revision: 1,
+                }
+            ]
+        );
+
+        updater = SchemaUpdater::from(registered.clone());
+        updater.modify_service(
+            GREETER_SERVICE_NAME.to_owned(),
+            vec![ModifyServiceChange::HandlerPublic(
+                "greet".to_owned(),
+                false,
+            )],
+        )?;
+        let modified = updater.into_inner();
+        assert_eq!(
+            modified.changes_since(&registered),
+            vec![SchemaChange::HandlerVisibilityChanged {
+                service: GREETER_SERVICE_NAME.to_owned(),
+                handler: "greet".to_owned(),
+                public: false,
+            }]
+        );
+
+        updater = SchemaUpdater::from(modified.clone());
+        updater.remove_deployment(deployment.id, true)?;
+        let removed = updater.into_inner();
+        assert_eq!(
+            removed.changes_since(&modified),
+            vec![
+                SchemaChange::DeploymentRemoved(deployment.id),
+                SchemaChange::ServiceRemoved {
+                    service: GREETER_SERVICE_NAME.to_owned(),
+                }
+            ]
+        );
+        assert!(removed.changes_since(&removed).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn modify_completion_retention() -> Result<(), SchemaError> {
+        let mut updater = SchemaUpdater::default();
+        let deployment = Deployment::mock();
+
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            false,
+        )?;
+        updater.modify_service(
+            GREETER_SERVICE_NAME.to_owned(),
+            vec![ModifyServiceChange::CompletionRetention(
+                Duration::from_secs(60),
+            )],
+        )?;
+        let schemas = updater.into_inner();
+
+        assert_eq!(
+            schemas
+                .resolve_latest_invocation_target(GREETER_SERVICE_NAME, "greet")
+                .unwrap()
+                .compute_retention(false),
+            Some(Duration::from_secs(60))
+        );
+
+        // Retention is kept when updating the deployment
+        updater = SchemaUpdater::from(schemas);
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            true,
+        )?;
+        let schemas = updater.into_inner();
+
+        assert_eq!(
+            schemas
+                .assert_service(GREETER_SERVICE_NAME)
+                .completion_retention,
+            Some(Duration::from_secs(60).into())
+        );
+        assert_eq!(
+            schemas
+                .resolve_latest_invocation_target(GREETER_SERVICE_NAME, "greet")
+                .unwrap()
+                .compute_retention(false),
+            Some(Duration::from_secs(60))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn keep_service_configuration_when_updating_the_deployment() -> Result<(), SchemaError> {
+        let mut updater = SchemaUpdater::default();
+        let deployment = Deployment::mock();
+
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            false,
+        )?;
+        updater.modify_service(
+            GREETER_SERVICE_NAME.to_owned(),
+            vec![
+                ModifyServiceChange::Public(false),
+                ModifyServiceChange::IdempotencyRetention(Duration::from_secs(120)),
+            ],
+        )?;
+        let schemas = updater.into_inner();
+
+        // The new revision keeps the configuration of the previous one
+        updater = SchemaUpdater::from(schemas);
+        updater.add_deployment(
+            Some(deployment.id),
+            deployment.metadata.clone(),
+            vec![greeter_service()],
+            true,
+        )?;
+        let schemas = updater.into_inner();
+
+        let service = schemas.assert_service(GREETER_SERVICE_NAME);
+        assert!(!service.public);
+        assert_eq!(
+            service.idempotency_retention,
+            Duration::from_secs(120).into()
+        );
+        let target = schemas
+            .resolve_latest_invocation_target(GREETER_SERVICE_NAME, "greet")
+            .unwrap();
+        assert!(!target.public);
+        assert_eq!(target.idempotency_retention, Duration::from_secs(120));
+
+        Ok(())
+    }
+
     mod change_instance_type {
         use super::*;
 
-        use restate_test_util::assert;
+        use crate::schema_registry::compatibility::IncompatibleChange;
+        use restate_test_util::{check, let_assert};
         use test_log::test;
 
         #[test]
@@ -770,9 +1409,17 @@ mod tests {
                 false,
             );
 
-            assert!(let &SchemaError::Service(
-                ServiceError::DifferentType(_)
-            ) = compute_result.unwrap_err());
+            let_assert!(
+                SchemaError::Service(ServiceError::IncompatibleChanges(_, changes)) =
+                    compute_result.unwrap_err()
+            );
+            check!(
+                changes
+                    == vec![IncompatibleChange::ServiceType {
+                        previous: ServiceType::Service,
+                        new: ServiceType::VirtualObject
+                    }]
+            );
         }
     }
 
@@ -900,7 +1547,7 @@ mod tests {
 
         let version_before_removal = schemas.version();
         updater = schemas.into();
-        updater.remove_deployment(deployment_1.id);
+        updater.remove_deployment(deployment_1.id, true).unwrap();
         let schemas = updater.into_inner();
 
         schemas.assert_service_deployment(GREETER_SERVICE_NAME, deployment_2.id);
@@ -910,11 +1557,296 @@ mod tests {
             .resolve_latest_service(ANOTHER_GREETER_SERVICE_NAME)
             .is_none());
         assert!(schemas.get_deployment(&deployment_1.id).is_none());
+
+        let tombstone = schemas.deployment_tombstones.last().unwrap();
+        assert_eq!(tombstone.id, deployment_1.id);
+        assert!(tombstone.forced);
+        assert_eq!(
+            tombstone.removed_services,
+            vec![ANOTHER_GREETER_SERVICE_NAME.to_owned()]
+        );
+    }
+
+    #[test]
+    fn retire_drained_deployment() {
+        let mut updater = SchemaUpdater::default();
+
+        let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+        let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+
+        updater
+            .add_deployment(
+                Some(deployment_1.id),
+                deployment_1.metadata.clone(),
+                vec![greeter_service(), another_greeter_service()],
+                false,
+            )
+            .unwrap();
+        updater
+            .add_deployment(
+                Some(deployment_2.id),
+                deployment_2.metadata.clone(),
+                vec![greeter_service()],
+                false,
+            )
+            .unwrap();
+        let schemas = updater.into_inner();
+        assert_eq!(
+            schemas.get_deployment_status(&deployment_1.id),
+            Some(DeploymentStatus::Active)
+        );
+
+        // The first deployment still serves the latest revision of the other greeter
+        updater = schemas.into();
+        let_assert!(
+            Err(SchemaError::Deployment(DeploymentError::StillActive(
+                _,
+                services
+            ))) = updater.remove_deployment(deployment_1.id, false)
+        );
+        assert_eq!(services, vec![ANOTHER_GREETER_SERVICE_NAME.to_owned()]);
+
+        updater
+            .add_deployment(
+                Some(deployment_2.id),
+                deployment_2.metadata.clone(),
+                vec![greeter_service(), another_greeter_service()],
+                true,
+            )
+            .unwrap();
+        let schemas = updater.into_inner();
+        assert_eq!(
+            schemas.get_deployment_status(&deployment_1.id),
+            Some(DeploymentStatus::Draining)
+        );
+
+        let listed_deployments = |include_retired| {
+            schemas
+                .list_deployments_page(&ListOptions {
+                    include_retired,
+                    ..Default::default()
+                })
+                .items
+                .into_iter()
+                .map(|(deployment, _)| deployment.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(listed_deployments(false), vec![deployment_2.id]);
+        assert_eq!(listed_deployments(true).len(), 2);
+
+        // Invocations pinned to the first deployment can still resolve it until it's retired
+        let version_before_retirement = schemas.version();
+        updater = schemas.into();
+        updater.remove_deployment(deployment_1.id, false).unwrap();
+        let schemas = updater.into_inner();
+
+        assert!(version_before_retirement < schemas.version());
+        assert!(schemas.get_deployment(&deployment_1.id).is_none());
+        schemas.assert_service_deployment(GREETER_SERVICE_NAME, deployment_2.id);
+        schemas.assert_service_deployment(ANOTHER_GREETER_SERVICE_NAME, deployment_2.id);
+
+        updater = schemas.into();
+        let_assert!(
+            Err(SchemaError::NotFound(_)) = updater.remove_deployment(deployment_1.id, false)
+        );
+    }
+
+    #[test]
+    fn split_traffic_across_deployments() {
+        let mut updater = SchemaUpdater::default();
+
+        let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+        let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+        let deployment_3 = Deployment::mock_with_uri("http://localhost:9082");
+
+        for deployment in [&deployment_1, &deployment_2] {
+            updater
+                .add_deployment(
+                    Some(deployment.id),
+                    deployment.metadata.clone(),
+                    vec![greeter_service()],
+                    false,
+                )
+                .unwrap();
+        }
+        updater
+            .add_deployment(
+                Some(deployment_3.id),
+                deployment_3.metadata.clone(),
+                vec![another_greeter_service()],
+                false,
+            )
+            .unwrap();
+
+        let_assert!(
+            Err(SchemaError::Service(
+                ServiceError::UnknownTrafficSplitDeployment(_, id)
+            )) = updater.modify_service(
+                GREETER_SERVICE_NAME.to_owned(),
+                vec![ModifyServiceChange::TrafficSplit(vec![DeploymentWeight {
+                    deployment_id: deployment_3.id,
+                    weight: 1,
+                }])],
+            )
+        );
+        assert_eq!(id, deployment_3.id);
+        let_assert!(
+            Err(SchemaError::Service(ServiceError::NoTrafficSplitWeight(_))) = updater
+                .modify_service(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    vec![ModifyServiceChange::TrafficSplit(vec![DeploymentWeight {
+                        deployment_id: deployment_1.id,
+                        weight: 0,
+                    }])],
+                )
+        );
+
+        updater
+            .modify_service(
+                GREETER_SERVICE_NAME.to_owned(),
+                vec![ModifyServiceChange::TrafficSplit(vec![
+                    DeploymentWeight {
+                        deployment_id: deployment_1.id,
+                        weight: 95,
+                    },
+                    DeploymentWeight {
+                        deployment_id: deployment_2.id,
+                        weight: 5,
+                    },
+                ])],
+            )
+            .unwrap();
+        let schemas = updater.into_inner();
+
+        let resolve = |schemas: &Schema, uuid: u128| {
+            schemas
+                .resolve_deployment_for_invocation(
+                    GREETER_SERVICE_NAME,
+                    &InvocationId::from_parts(0, InvocationUuid::from(uuid)),
+                )
+                .unwrap()
+                .id
+        };
+        let to_deployment_1 = (0..1000)
+            .filter(|uuid| resolve(&schemas, *uuid) == deployment_1.id)
+            .count();
+        assert_eq!(to_deployment_1, 950);
+        assert_eq!(resolve(&schemas, 42), resolve(&schemas, 42));
+        assert_eq!(
+            schemas.get_deployment_status(&deployment_1.id),
+            Some(DeploymentStatus::Active)
+        );
+
+        // A new deployment of the service receives all the new invocations
+        updater = schemas.into();
+        updater
+            .add_deployment(
+                Some(deployment_3.id),
+                deployment_3.metadata.clone(),
+                vec![greeter_service(), another_greeter_service()],
+                true,
+            )
+            .unwrap();
+        let schemas = updater.into_inner();
+
+        assert!(schemas
+            .assert_service(GREETER_SERVICE_NAME)
+            .traffic_split
+            .is_empty());
+        assert_eq!(resolve(&schemas, 42), deployment_3.id);
+        assert_eq!(
+            schemas.get_deployment_status(&deployment_1.id),
+            Some(DeploymentStatus::Draining)
+        );
+    }
+
+    #[test]
+    fn import_exported_schema() -> Result<(), SchemaError> {
+        let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+        let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+        let deployment_3 = Deployment::mock_with_uri("http://localhost:9080");
+
+        let mut updater = SchemaUpdater::default();
+        updater.add_deployment(
+            Some(deployment_1.id),
+            deployment_1.metadata.clone(),
+            vec![greeter_service()],
+            false,
+        )?;
+        let exported = updater.into_inner().export();
+        let exported: SchemaExport =
+            serde_json::from_value(serde_json::to_value(&exported).unwrap()).unwrap();
+
+        let mut updater = SchemaUpdater::default();
+        updater.import(exported.clone(), ImportConflictResolution::Fail)?;
+        let schemas = updater.into_inner();
+        schemas.assert_service_deployment(GREETER_SERVICE_NAME, deployment_1.id);
+        assert!(schemas.get_deployment(&deployment_1.id).is_some());
+
+        // The greeter is served by another deployment in the importing cluster
+        let mut updater = SchemaUpdater::default();
+        updater.add_deployment(
+            Some(deployment_2.id),
+            deployment_2.metadata,
+            vec![greeter_service(), another_greeter_service()],
+            false,
+        )?;
+        let schemas = updater.into_inner();
+
+        let mut updater = SchemaUpdater::from(schemas.clone());
+        let_assert!(
+            Err(SchemaError::Override(_)) =
+                updater.import(exported.clone(), ImportConflictResolution::Fail)
+        );
+
+        let mut updater = SchemaUpdater::from(schemas.clone());
+        updater.import(exported.clone(), ImportConflictResolution::Skip)?;
+        let schemas_with_skipped = updater.into_inner();
+        schemas_with_skipped.assert_service_deployment(GREETER_SERVICE_NAME, deployment_2.id);
+        assert!(schemas_with_skipped
+            .get_deployment(&deployment_1.id)
+            .is_some());
+
+        let mut updater = SchemaUpdater::from(schemas);
+        updater.import(exported.clone(), ImportConflictResolution::Overwrite)?;
+        let schemas_with_overwritten = updater.into_inner();
+        schemas_with_overwritten.assert_service_deployment(GREETER_SERVICE_NAME, deployment_1.id);
+        schemas_with_overwritten
+            .assert_service_deployment(ANOTHER_GREETER_SERVICE_NAME, deployment_2.id);
+
+        // Skipping the deployment registered at the same endpoint leaves the greeter dangling
+        let mut updater = SchemaUpdater::default();
+        updater.add_deployment(
+            Some(deployment_3.id),
+            deployment_3.metadata,
+            vec![another_greeter_service()],
+            false,
+        )?;
+        let mut updater = SchemaUpdater::from(updater.into_inner());
+        let_assert!(
+            Err(SchemaError::Import(ImportError::UnknownDeployment(
+                service,
+                deployment_id
+            ))) = updater.import(exported.clone(), ImportConflictResolution::Skip)
+        );
+        assert_eq!(service, GREETER_SERVICE_NAME);
+        assert_eq!(deployment_id, deployment_1.id);
+
+        let mut unsupported = exported;
+        unsupported.format_version += 1;
+        let_assert!(
+            Err(SchemaError::Import(ImportError::UnsupportedFormatVersion(
+                _
+            ))) = SchemaUpdater::default().import(unsupported, ImportConflictResolution::Fail)
+        );
+
+        Ok(())
     }
 
     mod remove_method {
         use super::*;
 
+        use crate::schema_registry::compatibility::IncompatibleChange;
         use restate_test_util::{check, let_assert};
         use test_log::test;
 
@@ -984,11 +1916,186 @@ mod tests {
             schemas.assert_service_revision(GREETER_SERVICE_NAME, 1); // unchanged
 
             let_assert!(
-                SchemaError::Service(ServiceError::RemovedHandlers(service, missing_methods)) =
+                SchemaError::Service(ServiceError::IncompatibleChanges(service, changes)) =
                     rejection
             );
             check!(service.as_ref() == GREETER_SERVICE_NAME);
-            check!(missing_methods == &["doSomething"]);
+            check!(changes == vec![IncompatibleChange::RemovedHandler("doSomething".to_owned())]);
+        }
+
+        #[test]
+        fn report_forced_removal_of_existing_methods() {
+            let mut updater = SchemaUpdater::default();
+
+            let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+            let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+
+            updater
+                .add_deployment(
+                    Some(deployment_1.id),
+                    deployment_1.metadata,
+                    vec![greeter_v1_service()],
+                    false,
+                )
+                .unwrap();
+            let previous = updater.into_inner();
+
+            updater = previous.clone().into();
+            updater
+                .add_deployment(
+                    Some(deployment_2.id),
+                    deployment_2.metadata,
+                    vec![greeter_v2_service()],
+                    true,
+                )
+                .unwrap();
+            let schemas = updater.into_inner();
+            schemas.assert_service_revision(GREETER_SERVICE_NAME, 2);
+
+            let report = crate::schema_registry::RegistrationReport::new(&previous, &schemas);
+            check!(report
+                .changes
+                .contains(&SchemaChange::DeploymentRegistered(deployment_2.id)));
+            check!(report.changes.contains(&SchemaChange::HandlerRemoved {
+                service: GREETER_SERVICE_NAME.to_owned(),
+                handler: "doSomething".to_owned(),
+            }));
+            check!(
+                report.incompatible_changes
+                    == vec![(
+                        GREETER_SERVICE_NAME.to_owned(),
+                        IncompatibleChange::RemovedHandler("doSomething".to_owned())
+                    )]
+            );
+        }
+    }
+
+    mod change_input {
+        use super::*;
+
+        use crate::schema_registry::compatibility::IncompatibleChange;
+        use restate_test_util::{check, let_assert};
+        use test_log::test;
+
+        fn greeter_service_with_input(input: schema::InputPayload) -> schema::Service {
+            schema::Service {
+                ty: schema::ServiceType::Service,
+                name: GREETER_SERVICE_NAME.parse().unwrap(),
+                handlers: vec![schema::Handler {
+                    name: "greet".parse().unwrap(),
+                    ty: None,
+                    input: Some(input),
+                    output: None,
+                }],
+            }
+        }
+
+        fn json_input(required: bool, json_schema: serde_json::Value) -> schema::InputPayload {
+            schema::InputPayload {
+                content_type: Some("application/json".to_owned()),
+                json_schema: Some(json_schema),
+                required: Some(required),
+            }
+        }
+
+        fn register(
+            schemas: Schema,
+            uri: &str,
+            input: schema::InputPayload,
+            force: bool,
+        ) -> (Schema, Result<DeploymentId, SchemaError>) {
+            let mut updater = SchemaUpdater::from(schemas);
+            let deployment = Deployment::mock_with_uri(uri);
+            let result = updater.add_deployment(
+                Some(deployment.id),
+                deployment.metadata,
+                vec![greeter_service_with_input(input)],
+                force,
+            );
+            (updater.into_inner(), result)
+        }
+
+        #[test]
+        fn accept_compatible_input_changes() {
+            let (schemas, result) = register(
+                Schema::default(),
+                "http://localhost:9080",
+                json_input(
+                    true,
+                    serde_json::json!({"type": "object", "required": ["name"]}),
+                ),
+                false,
+            );
+            result.unwrap();
+
+            // Accepting empty inputs and new optional fields is fine
+            let (schemas, result) = register(
+                schemas,
+                "http://localhost:9081",
+                json_input(
+                    false,
+                    serde_json::json!({
+                        "type": "object",
+                        "properties": {"name": {"type": "string"}, "age": {"type": "number"}},
+                        "required": ["name"]
+                    }),
+                ),
+                false,
+            );
+            result.unwrap();
+            schemas.assert_service_revision(GREETER_SERVICE_NAME, 2);
+        }
+
+        #[test]
+        fn reject_incompatible_input_changes() {
+            let (schemas, result) = register(
+                Schema::default(),
+                "http://localhost:9080",
+                json_input(
+                    false,
+                    serde_json::json!({
+                        "type": "object",
+                        "properties": {"name": {"type": "string"}}
+                    }),
+                ),
+                false,
+            );
+            result.unwrap();
+
+            let new_input = json_input(
+                true,
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {"name": {"type": "number"}},
+                    "required": ["name"]
+                }),
+            );
+            let (schemas, result) =
+                register(schemas, "http://localhost:9081", new_input.clone(), false);
+            schemas.assert_service_revision(GREETER_SERVICE_NAME, 1);
+
+            let_assert!(
+                Err(SchemaError::Service(ServiceError::IncompatibleChanges(
+                    service, changes
+                ))) = result
+            );
+            check!(service.as_ref() == GREETER_SERVICE_NAME);
+            let_assert!(
+                [
+                    IncompatibleChange::Input { handler, .. },
+                    IncompatibleChange::RequiredInputField { field, .. },
+                    IncompatibleChange::InputFieldType { previous, new, .. }
+                ] = changes.as_slice()
+            );
+            check!(handler == "greet");
+            check!(field == "name");
+            check!(previous == &serde_json::json!("string"));
+            check!(new == &serde_json::json!("number"));
+
+            // Forcing the registration applies the changes
+            let (schemas, result) = register(schemas, "http://localhost:9081", new_input, true);
+            result.unwrap();
+            schemas.assert_service_revision(GREETER_SERVICE_NAME, 2);
         }
     }
 }
@@ -13,6 +13,7 @@ use std::sync::Arc;
 use axum::error_handling::HandleErrorLayer;
 use http::StatusCode;
 use restate_bifrost::Bifrost;
+use restate_cluster_controller::ClusterControllerHandle;
 use restate_types::arc_util::Updateable;
 use restate_types::config::AdminOptions;
 use tonic::transport::Channel;
@@ -35,6 +36,7 @@ pub struct BuildError(#[from] restate_service_client::BuildError);
 
 pub struct AdminService<V> {
     schema_registry: SchemaRegistry<V>,
+    cluster_controller: ClusterControllerHandle,
 }
 
 impl<V> AdminService<V>
@@ -46,6 +48,7 @@ where
         metadata_store_client: MetadataStoreClient,
         subscription_validator: V,
         service_discovery: ServiceDiscovery,
+        cluster_controller: ClusterControllerHandle,
     ) -> Self {
         Self {
             schema_registry: SchemaRegistry::new(
@@ -54,6 +57,7 @@ where
                 service_discovery,
                 subscription_validator,
             ),
+            cluster_controller,
         }
     }
 
@@ -65,8 +69,13 @@ where
     ) -> anyhow::Result<()> {
         let opts = updateable_config.load();
 
-        let rest_state =
-            state::AdminServiceState::new(self.schema_registry, bifrost, task_center());
+        let rest_state = state::AdminServiceState::new(
+            self.schema_registry,
+            bifrost,
+            task_center(),
+            node_svc_client.clone(),
+            self.cluster_controller,
+        );
 
         let query_state = Arc::new(state::QueryServiceState { node_svc_client });
         let router = axum::Router::new().merge(storage_query::create_router(query_state));
@@ -11,6 +11,7 @@
 
 use crate::schema_registry::SchemaRegistry;
 use restate_bifrost::Bifrost;
+use restate_cluster_controller::ClusterControllerHandle;
 use restate_core::TaskCenter;
 use restate_node_services::node_svc::node_svc_client::NodeSvcClient;
 use tonic::transport::Channel;
@@ -20,6 +21,8 @@ pub struct AdminServiceState<V> {
     pub schema_registry: SchemaRegistry<V>,
     pub bifrost: Bifrost,
     pub task_center: TaskCenter,
+    pub node_svc_client: NodeSvcClient<Channel>,
+    pub cluster_controller: ClusterControllerHandle,
 }
 
 #[derive(Clone)]
@@ -32,11 +35,15 @@ impl<V> AdminServiceState<V> {
         schema_registry: SchemaRegistry<V>,
         bifrost: Bifrost,
         task_center: TaskCenter,
+        node_svc_client: NodeSvcClient<Channel>,
+        cluster_controller: ClusterControllerHandle,
     ) -> Self {
         Self {
             schema_registry,
             bifrost,
             task_center,
+            node_svc_client,
+            cluster_controller,
         }
     }
 }
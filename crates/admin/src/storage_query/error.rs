@@ -25,6 +25,10 @@ use serde::Serialize;
 pub enum StorageQueryError {
     #[error("failed grpc: {0}")]
     Tonic(#[from] tonic::Status),
+    #[error("failed reading the query results: {0}")]
+    Flight(#[from] arrow_flight::error::FlightError),
+    #[error("unexpected query results: {0}")]
+    UnexpectedResult(&'static str),
 }
 
 /// # Error description response
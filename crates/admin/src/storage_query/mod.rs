@@ -11,6 +11,8 @@
 mod error;
 mod query;
 
+pub(crate) use query::count_pinned_invocations;
+
 use axum::{routing::post, Router};
 use std::sync::Arc;
 
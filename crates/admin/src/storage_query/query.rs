@@ -24,16 +24,21 @@ use datafusion::arrow::array::{
     Array, ArrayRef, AsArray, BinaryArray, GenericByteArray, StringArray,
 };
 use datafusion::arrow::buffer::{OffsetBuffer, ScalarBuffer};
-use datafusion::arrow::datatypes::{ByteArrayType, DataType, Field, FieldRef, Schema, SchemaRef};
+use datafusion::arrow::datatypes::{
+    ByteArrayType, DataType, Field, FieldRef, Int64Type, Schema, SchemaRef,
+};
 use datafusion::arrow::error::ArrowError;
 use datafusion::arrow::ipc::writer::StreamWriter;
 use datafusion::arrow::record_batch::RecordBatch;
 use futures::{ready, Stream, StreamExt, TryStreamExt};
 use okapi_operation::*;
+use restate_node_services::node_svc::node_svc_client::NodeSvcClient;
 use restate_node_services::node_svc::StorageQueryRequest;
+use restate_types::identifiers::DeploymentId;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_with::serde_as;
+use tonic::transport::Channel;
 
 use crate::state::QueryServiceState;
 
@@ -94,6 +99,44 @@ pub async fn query(
     ))
 }
 
+/// Counts the invocations pinned to the given deployment, which would break if it was removed.
+pub(crate) async fn count_pinned_invocations(
+    node_svc_client: &NodeSvcClient<Channel>,
+    deployment_id: DeploymentId,
+) -> Result<u64, StorageQueryError> {
+    let response_stream = node_svc_client
+        .clone()
+        .query_storage(StorageQueryRequest {
+            query: format!(
+                "SELECT COUNT(*) AS count FROM sys_invocation_status WHERE pinned_deployment_id = '{deployment_id}'"
+            ),
+        })
+        .await?
+        .into_inner();
+
+    let batches: Vec<RecordBatch> = FlightRecordBatchStream::new_from_flight_data(
+        response_stream
+            .map_ok(|response| FlightData {
+                data_header: response.header,
+                data_body: response.data,
+                ..FlightData::default()
+            })
+            .map_err(FlightError::from),
+    )
+    .try_collect()
+    .await?;
+
+    let count = batches
+        .iter()
+        .find(|batch| batch.num_rows() > 0)
+        .and_then(|batch| batch.column(0).as_primitive_opt::<Int64Type>())
+        .ok_or(StorageQueryError::UnexpectedResult(
+            "expected a single count column",
+        ))?
+        .value(0);
+    Ok(count as u64)
+}
+
 fn convert_schema(schema: SchemaRef) -> SchemaRef {
     let mut fields = Vec::with_capacity(schema.fields.len());
     for field in schema.fields.iter() {
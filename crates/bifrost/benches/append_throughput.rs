@@ -178,9 +178,7 @@ fn write_throughput_local_loglet(c: &mut Criterion) {
     let stats = db.get_statistics_str();
     let total_wb_usage = db_manager.get_total_write_buffer_usage();
     let wb_capacity = db_manager.get_total_write_buffer_capacity();
-    let memory = db_manager
-        .get_memory_usage_stats(&[DbName::new("local-loglet")])
-        .unwrap();
+    let memory = db_manager.get_memory_usage_stats(&[DbName::new("local-loglet")]);
     test_runner_rt.block_on(tc.shutdown_node("completed", 0));
     test_runner_rt.block_on(RocksDbManager::get().shutdown());
 
@@ -198,12 +196,12 @@ fn write_throughput_local_loglet(c: &mut Criterion) {
 
     info!(
         "RocksDB approximate memory usage of all the table readers: {}",
-        memory.approximate_mem_table_readers_total(),
+        memory.approximate_table_readers_total(),
     );
 
     info!(
         "RocksDB approximate memory usage by cache: {}",
-        memory.approximate_cache_total(),
+        memory.block_cache_usage,
     );
 
     info!(
@@ -99,6 +99,12 @@ impl Bifrost {
         self.inner.find_tail(log_id, attributes).await
     }
 
+    /// The trim point of a log, the LSN of the slot **before** the first readable record. Records
+    /// up to and including the trim point can no longer be read.
+    pub async fn get_trim_point(&self, log_id: LogId) -> Result<Lsn, Error> {
+        self.inner.get_trim_point(log_id).await
+    }
+
     /// The version of the currently loaded logs metadata
     pub fn version(&self) -> Version {
         metadata().logs_version()
@@ -175,6 +181,12 @@ impl BifrostInner {
         loglet.find_tail().await
     }
 
+    pub async fn get_trim_point(&self, log_id: LogId) -> Result<Lsn, Error> {
+        self.fail_if_shutting_down()?;
+        let loglet = self.find_loglet_for_lsn(log_id, Lsn::OLDEST).await?;
+        loglet.get_trim_point().await
+    }
+
     #[inline]
     fn fail_if_shutting_down(&self) -> Result<(), Error> {
         if self.shutting_down.load(Ordering::Relaxed) {
@@ -129,7 +129,7 @@ fn db_options(options: &LocalLogletOptions) -> rocksdb::Options {
 }
 
 // todo: optimize
-fn cf_data_options(mut opts: rocksdb::Options) -> rocksdb::Options {
+fn cf_data_options(_: &RocksDbOptions, mut opts: rocksdb::Options) -> rocksdb::Options {
     //
     // Set compactions per level
     //
@@ -151,7 +151,7 @@ fn cf_data_options(mut opts: rocksdb::Options) -> rocksdb::Options {
 }
 
 // todo: optimize
-fn cf_metadata_options(mut opts: rocksdb::Options) -> rocksdb::Options {
+fn cf_metadata_options(_: &RocksDbOptions, mut opts: rocksdb::Options) -> rocksdb::Options {
     //
     // Set compactions per level
     //
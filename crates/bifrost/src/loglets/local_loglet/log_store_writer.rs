@@ -183,15 +183,28 @@ impl LogStoreWriter {
     }
 
     async fn commit(&mut self, opts: &LocalLogletOptions, write_batch: WriteBatch) {
+        if opts.sync_wal_before_ack && !opts.rocksdb.rocksdb_disable_wal() {
+            trace!(
+                "Group committing local loglet current write batch: {} items",
+                write_batch.len(),
+            );
+            // writes the batch and shares the WAL sync with concurrent writes
+            let result = self
+                .rocksdb
+                .write_batch_group_commit(Priority::High, IoMode::Default, write_batch)
+                .await;
+            if let Err(e) = result {
+                error!("Failed to commit local loglet write batch: {}", e);
+                self.send_acks(Err(Error::LogStoreError(e.into())));
+                return;
+            }
+            self.send_acks(Ok(()));
+            return;
+        }
+
         let mut write_opts = rocksdb::WriteOptions::new();
         write_opts.disable_wal(opts.rocksdb.rocksdb_disable_wal());
 
-        if !self.manual_wal_flush && !opts.rocksdb.rocksdb_disable_wal() {
-            // if we are not manually flushing the wal, we need to configure the sync behaviour
-            // for the write operation explicitly
-            write_opts.set_sync(opts.sync_wal_before_ack);
-        }
-
         trace!(
             "Committing local loglet current write batch: {} items",
             write_batch.len(),
@@ -210,14 +223,12 @@ impl LogStoreWriter {
         if self.manual_wal_flush {
             // WAL flush is done in the foreground, but sync will happen in the background to avoid
             // blocking IO.
-            if let Err(e) = self.rocksdb.flush_wal(opts.sync_wal_before_ack).await {
+            if let Err(e) = self.rocksdb.flush_wal(false).await {
                 warn!("Failed to flush rocksdb WAL in local loglet : {}", e);
                 self.send_acks(Err(Error::LogStoreError(e.into())));
                 return;
             }
-            if !opts.sync_wal_before_ack {
-                self.rocksdb.run_bg_wal_sync();
-            }
+            self.rocksdb.run_bg_wal_sync();
         }
 
         self.send_acks(Ok(()));
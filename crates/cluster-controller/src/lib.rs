@@ -10,4 +10,4 @@
 
 mod service;
 
-pub use service::{ClusterControllerHandle, Error, Service};
+pub use service::{ClusterControllerHandle, Error, Service, SplitPartitionError};
@@ -8,8 +8,31 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::HashMap;
+use std::time::Duration;
+
 use codederror::CodedError;
-use restate_core::cancellation_watcher;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+
+use restate_bifrost::Bifrost;
+use restate_core::metadata_store::{MetadataStoreClient, ReadModifyWriteError, ReadWriteError};
+use restate_core::{cancellation_watcher, metadata, MetadataWriter, ShutdownError};
+use restate_types::identifiers::{PartitionId, PartitionKey};
+use restate_types::logs::metadata::Logs;
+use restate_types::logs::{LogId, Payload};
+use restate_types::metadata_store::keys::{
+    partition_split_key, BIFROST_CONFIG_KEY, PARTITION_TABLE_KEY,
+};
+use restate_types::partition_table::{
+    FixedPartitionTable, PartitionSplit, PartitionSplitError, RegisteredPartitionSplit,
+};
+use restate_types::Version;
+use restate_wal_protocol::{Command as WalCommand, Destination, Envelope, Header, Source};
+
+/// How often the controller looks for completed partition splits to add to the partition table.
+const SPLIT_REGISTRATION_INTERVAL: Duration = Duration::from_secs(5);
 
 #[derive(Debug, thiserror::Error, CodedError)]
 pub enum Error {
@@ -18,19 +41,263 @@ pub enum Error {
     Error,
 }
 
-#[derive(Debug, Default)]
-pub struct Service {}
+#[derive(Debug, thiserror::Error)]
+pub enum SplitPartitionError {
+    #[error(transparent)]
+    InvalidSplit(#[from] PartitionSplitError),
+    #[error("a split of partition {0} is already in progress")]
+    SplitInProgress(PartitionId),
+    #[error("there is no log for partition {0}")]
+    MissingLog(PartitionId),
+    #[error("failed creating the logs of the new partitions: {0}")]
+    MetadataStore(#[from] ReadWriteError),
+    #[error("failed sealing the partition: {0}")]
+    Seal(#[from] restate_wal_protocol::Error),
+    #[error(transparent)]
+    Shutdown(#[from] ShutdownError),
+}
+
+enum Command {
+    SplitPartition {
+        partition_id: PartitionId,
+        split_key: Option<PartitionKey>,
+        response_tx: oneshot::Sender<Result<PartitionSplit, SplitPartitionError>>,
+    },
+}
+
+pub struct Service {
+    metadata_writer: MetadataWriter,
+    metadata_store_client: MetadataStoreClient,
+    command_tx: mpsc::Sender<Command>,
+    command_rx: mpsc::Receiver<Command>,
+    /// Partitions which have been sealed for a split that isn't part of the partition table yet
+    pending_splits: HashMap<PartitionId, PartitionSplit>,
+}
 
-// todo: Replace with proper handle
-pub struct ClusterControllerHandle;
+#[derive(Clone)]
+pub struct ClusterControllerHandle {
+    command_tx: mpsc::Sender<Command>,
+}
+
+impl ClusterControllerHandle {
+    /// Splits the partition in two at `split_key`, by default in the middle of its key range.
+    /// Returns once the partition has been sealed for the split. The new partitions are added to
+    /// the partition table once the partition processors have completed the split.
+    pub async fn split_partition(
+        &self,
+        partition_id: PartitionId,
+        split_key: Option<PartitionKey>,
+    ) -> Result<PartitionSplit, SplitPartitionError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.command_tx
+            .send(Command::SplitPartition {
+                partition_id,
+                split_key,
+                response_tx,
+            })
+            .await
+            .map_err(|_| ShutdownError)?;
+        response_rx.await.map_err(|_| ShutdownError)?
+    }
+}
 
 impl Service {
+    pub fn new(
+        metadata_writer: MetadataWriter,
+        metadata_store_client: MetadataStoreClient,
+    ) -> Self {
+        let (command_tx, command_rx) = mpsc::channel(2);
+        Self {
+            metadata_writer,
+            metadata_store_client,
+            command_tx,
+            command_rx,
+            pending_splits: HashMap::default(),
+        }
+    }
+
     pub fn handle(&self) -> ClusterControllerHandle {
-        ClusterControllerHandle
+        ClusterControllerHandle {
+            command_tx: self.command_tx.clone(),
+        }
     }
 
-    pub async fn run(self) -> anyhow::Result<()> {
-        let _ = cancellation_watcher().await;
+    pub async fn run(mut self, mut bifrost: Bifrost) -> anyhow::Result<()> {
+        let mut split_registration = tokio::time::interval(SPLIT_REGISTRATION_INTERVAL);
+        split_registration.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let shutdown = cancellation_watcher();
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    return Ok(());
+                }
+                Some(command) = self.command_rx.recv() => {
+                    match command {
+                        Command::SplitPartition { partition_id, split_key, response_tx } => {
+                            let result = self.split_partition(&mut bifrost, partition_id, split_key).await;
+                            let _ = response_tx.send(result);
+                        }
+                    }
+                }
+                // splits might have been requested by a previous cluster controller, look at all partitions
+                _ = split_registration.tick() => {
+                    if let Err(e) = self.register_completed_splits().await {
+                        warn!("Failed registering completed partition splits: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn split_partition(
+        &mut self,
+        bifrost: &mut Bifrost,
+        partition_id: PartitionId,
+        split_key: Option<PartitionKey>,
+    ) -> Result<PartitionSplit, SplitPartitionError> {
+        if self.pending_splits.contains_key(&partition_id) {
+            return Err(SplitPartitionError::SplitInProgress(partition_id));
+        }
+        let partition_table = metadata().wait_for_partition_table(Version::MIN).await?;
+        let partition_range = partition_table
+            .partition_range(partition_id)
+            .ok_or(PartitionSplitError::UnknownPartition(partition_id))?;
+        if partition_table.is_split(partition_id) {
+            return Err(PartitionSplitError::AlreadySplit(partition_id).into());
+        }
+        let split_key = split_key.unwrap_or_else(|| {
+            (partition_range.start() + (partition_range.end() - partition_range.start()) / 2)
+                .saturating_add(1)
+        });
+        // both partitions must own at least one key
+        if split_key <= *partition_range.start() || split_key > *partition_range.end() {
+            return Err(PartitionSplitError::InvalidSplitKey(split_key).into());
+        }
+
+        // the logs of the new partitions reserve their partition ids
+        let mut new_partition_ids = None;
+        let logs = self
+            .metadata_store_client
+            .read_modify_write(BIFROST_CONFIG_KEY.clone(), |logs: Option<Logs>| {
+                let mut logs = logs.ok_or(SplitPartitionError::MissingLog(partition_id))?;
+                let kind = logs
+                    .tail_segment(LogId::from(partition_id))
+                    .ok_or(SplitPartitionError::MissingLog(partition_id))?
+                    .config
+                    .kind;
+                let left_log_id = logs.next_log_id();
+                logs.add_log(left_log_id, kind);
+                let right_log_id = logs.next_log_id();
+                logs.add_log(right_log_id, kind);
+                logs.version = logs.version.next();
+
+                new_partition_ids = Some((
+                    PartitionId::from(u64::from(left_log_id)),
+                    PartitionId::from(u64::from(right_log_id)),
+                ));
+                Ok(logs)
+            })
+            .await
+            .map_err(ReadModifyWriteError::<SplitPartitionError>::transpose)?;
+        self.metadata_writer.update(logs).await?;
+
+        let (left_partition_id, right_partition_id) =
+            new_partition_ids.expect("logs have been modified");
+        let split = PartitionSplit {
+            partition_id,
+            split_key,
+            left_partition_id,
+            right_partition_id,
+        };
+
+        // the partition processors split the partition once they've read this record
+        let envelope = Envelope::new(
+            Header {
+                source: Source::ControlPlane {},
+                dest: Destination::Processor {
+                    partition_key: *partition_range.start(),
+                    dedup: None,
+                },
+            },
+            WalCommand::SplitPartition(split.clone()),
+        );
+        let payload = Payload::from(
+            envelope
+                .to_bytes()
+                .map_err(restate_wal_protocol::Error::from)?,
+        );
+        bifrost
+            .append(LogId::from(partition_id), payload)
+            .await
+            .map_err(restate_wal_protocol::Error::from)?;
+
+        info!(
+            %partition_id,
+            split_key,
+            %left_partition_id,
+            %right_partition_id,
+            "Partition sealed for split"
+        );
+        self.pending_splits.insert(partition_id, split.clone());
+        Ok(split)
+    }
+
+    /// Adds the splits which partition processors have completed and registered to the partition
+    /// table.
+    async fn register_completed_splits(&mut self) -> anyhow::Result<()> {
+        let Some(partition_table) = metadata().partition_table() else {
+            return Ok(());
+        };
+
+        for (partition_id, _) in partition_table.partitioner() {
+            let Some(registered_split) = self
+                .metadata_store_client
+                .get::<RegisteredPartitionSplit>(partition_split_key(partition_id))
+                .await?
+            else {
+                continue;
+            };
+
+            let result = self
+                .metadata_store_client
+                .read_modify_write(
+                    PARTITION_TABLE_KEY.clone(),
+                    |partition_table: Option<FixedPartitionTable>| {
+                        let mut partition_table = partition_table
+                            .ok_or(PartitionSplitError::UnknownPartition(partition_id))?;
+                        partition_table.add_split(registered_split.split.clone())?;
+                        partition_table.increment_version();
+                        Ok(partition_table)
+                    },
+                )
+                .await;
+            let updated_partition_table = match result {
+                Ok(updated_partition_table) => updated_partition_table,
+                Err(ReadModifyWriteError::FailedOperation(PartitionSplitError::AlreadySplit(
+                    _,
+                ))) => {
+                    debug!(%partition_id, "Partition split has been registered before");
+                    self.metadata_store_client
+                        .get::<FixedPartitionTable>(PARTITION_TABLE_KEY.clone())
+                        .await?
+                        .expect("partition table exists")
+                }
+                Err(e) => return Err(e.into()),
+            };
+            self.metadata_writer.update(updated_partition_table).await?;
+            self.pending_splits.remove(&partition_id);
+
+            info!(
+                %partition_id,
+                left_partition_id = %registered_split.split.left_partition_id,
+                right_partition_id = %registered_split.split.right_partition_id,
+                sealed_lsn = %registered_split.sealed_lsn,
+                "Added completed partition split to the partition table"
+            );
+        }
         Ok(())
     }
 }
@@ -14,10 +14,12 @@ use std::sync::Arc;
 
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::time::MissedTickBehavior;
 use tracing::{debug, info, trace, warn};
 
 use restate_node_protocol::metadata::{MetadataMessage, MetadataUpdate, Schema};
 use restate_node_protocol::MessageEnvelope;
+use restate_types::config::Configuration;
 use restate_types::logs::metadata::Logs;
 use restate_types::metadata_store::keys::{
     BIFROST_CONFIG_KEY, NODES_CONFIG_KEY, PARTITION_TABLE_KEY, SCHEMA_INFORMATION_KEY,
@@ -71,9 +73,7 @@ where
             MetadataKind::NodesConfiguration => self.send_nodes_config(peer, min_version),
             MetadataKind::PartitionTable => self.send_partition_table(peer, min_version),
             MetadataKind::Logs => self.send_logs(peer, min_version),
-            _ => {
-                todo!("Can't send metadata '{}' to peer", metadata_kind)
-            }
+            MetadataKind::Schema => self.send_schema(peer, min_version),
         };
     }
 
@@ -94,6 +94,11 @@ where
         }
     }
 
+    fn send_schema(&self, to: GenerationalNodeId, version: Option<Version>) {
+        let schema = metadata().schema();
+        self.send_metadata_internal(to, version, schema.deref(), "schema");
+    }
+
     fn send_metadata_internal<T>(
         &self,
         to: GenerationalNodeId,
@@ -237,6 +242,10 @@ where
     pub async fn run(mut self) -> anyhow::Result<()> {
         debug!("Metadata manager started");
 
+        let mut update_interval =
+            tokio::time::interval(*Configuration::pinned().common.metadata_update_interval);
+        update_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
         loop {
             tokio::select! {
                 biased;
@@ -247,11 +256,29 @@ where
                 Some(cmd) = self.inbound.recv() => {
                     self.handle_command(cmd).await;
                 }
+                _ = update_interval.tick() => {
+                    if let Err(err) = self.refresh_schema().await {
+                        debug!("Failed checking the metadata store for schema updates: {}", err);
+                    }
+                }
             }
         }
         Ok(())
     }
 
+    /// Fetches the schema registry from the metadata store if the admin servers updated it, so
+    /// that all nodes converge on the same schema.
+    async fn refresh_schema(&mut self) -> Result<(), ReadError> {
+        let latest_version = self
+            .metadata_store_client
+            .get_version(SCHEMA_INFORMATION_KEY.clone())
+            .await?;
+        if latest_version.is_some_and(|version| version > self.inner.schema.load().version()) {
+            self.sync_metadata(MetadataKind::Schema).await?;
+        }
+        Ok(())
+    }
+
     async fn handle_command(&mut self, cmd: Command) {
         match cmd {
             Command::UpdateMetadata(value, callback) => self.update_metadata(value, callback),
@@ -423,6 +450,7 @@ mod tests {
     use restate_types::{GenerationalNodeId, Version};
 
     use crate::metadata::spawn_metadata_manager;
+    use crate::metadata_store::Precondition;
     use crate::test_env::MockNetworkSender;
     use crate::{TaskCenterBuilder, TaskKind};
 
@@ -507,6 +535,38 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_schema_updates_from_metadata_store() -> Result<()> {
+        let tc = TaskCenterBuilder::default().build()?;
+        tc.block_on("test", None, async move {
+            let metadata_store_client = MetadataStoreClient::new_in_memory();
+            let metadata_manager =
+                MetadataManager::build(MockNetworkSender::default(), metadata_store_client.clone());
+            let metadata = metadata_manager.metadata();
+
+            // the admin server of another node updated the schema
+            let mut schema = Schema::default();
+            schema.increment_version();
+            schema.increment_version();
+            metadata_store_client
+                .put(SCHEMA_INFORMATION_KEY.clone(), schema, Precondition::None)
+                .await?;
+            assert_eq!(Version::INVALID, metadata.schema_version());
+
+            let tc = task_center();
+            spawn_metadata_manager(&tc, metadata_manager)?;
+
+            let version = metadata
+                .wait_for_version(MetadataKind::Schema, Version::from(2))
+                .await
+                .unwrap();
+            assert_eq!(Version::from(2), version);
+
+            tc.cancel_tasks(None, None).await;
+            Ok(())
+        })
+    }
+
     fn test_nodes_config_watchers() -> Result<()> {
         test_watchers(
             create_mock_nodes_config(),
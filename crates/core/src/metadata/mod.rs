@@ -22,6 +22,7 @@ use tokio::sync::{oneshot, watch};
 
 pub use restate_node_protocol::metadata::MetadataKind;
 use restate_node_protocol::metadata::{MetadataContainer, Schema, UpdateableSchema};
+use restate_schema::changes::SchemaChange;
 use restate_types::logs::metadata::Logs;
 use restate_types::nodes_config::NodesConfiguration;
 use restate_types::partition_table::FixedPartitionTable;
@@ -146,6 +147,14 @@ impl Metadata {
         self.inner.write_watches[metadata_kind].receive.clone()
     }
 
+    /// Watch for changes of the schema information, starting from its current version.
+    pub fn watch_schema_changes(&self) -> SchemaChangesWatcher {
+        SchemaChangesWatcher {
+            metadata: self.clone(),
+            last_seen: self.schema(),
+        }
+    }
+
     /// Syncs the given metadata_kind from the underlying metadata store.
     pub async fn sync(&self, metadata_kind: MetadataKind) -> Result<(), SyncError> {
         let (result_tx, result_rx) = oneshot::channel();
@@ -158,6 +167,26 @@ impl Metadata {
     }
 }
 
+/// Yields the changes of the schema information, see [`Metadata::watch_schema_changes`].
+pub struct SchemaChangesWatcher {
+    metadata: Metadata,
+    last_seen: Arc<Schema>,
+}
+
+impl SchemaChangesWatcher {
+    /// Waits for a newer version of the schema information and returns its changes. The changes
+    /// of several versions might be returned at once, and they might cancel each other out.
+    pub async fn next(&mut self) -> Result<Vec<SchemaChange>, ShutdownError> {
+        self.metadata
+            .wait_for_version(MetadataKind::Schema, self.last_seen.version().next())
+            .await?;
+        let schema = self.metadata.schema();
+        let changes = schema.changes_since(&self.last_seen);
+        self.last_seen = schema;
+        Ok(changes)
+    }
+}
+
 #[derive(Default)]
 struct MetadataInner {
     my_node_id: OnceLock<GenerationalNodeId>,
@@ -79,13 +79,19 @@ mod mocks {
                     ty: invocation_target_metadata.target_ty.into(),
                     input_description: "any".to_string(),
                     output_description: "any".to_string(),
+                    public: invocation_target_metadata.public,
+                    idempotency_retention: None,
+                    execution_timeout: None,
+                    max_concurrent_invocations: None,
                 }],
                 ty: invocation_target_metadata.target_ty.into(),
                 deployment_id: DeploymentId::default(),
                 revision: 0,
                 public: invocation_target_metadata.public,
                 idempotency_retention: DEFAULT_IDEMPOTENCY_RETENTION.into(),
+                completion_retention: None,
                 workflow_completion_retention: None,
+                traffic_split: vec![],
             });
             self.1
                 .add(service_name, [(handler_name, invocation_target_metadata)]);
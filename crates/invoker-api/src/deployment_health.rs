@@ -0,0 +1,83 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_types::identifiers::DeploymentId;
+use restate_types::time::MillisSinceEpoch;
+
+/// Outcome of the last health check of a deployment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeploymentHealth {
+    Healthy,
+    Unhealthy,
+}
+
+/// Outcome of the invocation attempts sent to a deployment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeploymentInvocationsHealth {
+    /// Last time an invocation attempt didn't fail because of the deployment.
+    pub last_success: Option<MillisSinceEpoch>,
+    /// Number of invocation attempts which failed because of the deployment since the last
+    /// successful one, e.g. because of connection failures or 5xx responses.
+    pub consecutive_failures: u64,
+}
+
+/// Struct to access the health of the deployments, as probed by the invoker
+pub trait DeploymentHealthReader {
+    /// Returns `None` if the deployment wasn't probed yet, or the health checks are disabled.
+    fn deployment_health(&self, deployment_id: &DeploymentId) -> Option<DeploymentHealth>;
+
+    /// Returns `None` if no invocation was sent to the deployment yet.
+    fn deployment_invocations_health(
+        &self,
+        deployment_id: &DeploymentId,
+    ) -> Option<DeploymentInvocationsHealth>;
+}
+
+#[cfg(any(test, feature = "mocks"))]
+pub mod mocks {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone, Default)]
+    pub struct MockDeploymentHealthReader(
+        HashMap<DeploymentId, DeploymentHealth>,
+        HashMap<DeploymentId, DeploymentInvocationsHealth>,
+    );
+
+    impl MockDeploymentHealthReader {
+        pub fn with(mut self, deployment_id: DeploymentId, health: DeploymentHealth) -> Self {
+            self.0.insert(deployment_id, health);
+            self
+        }
+
+        pub fn with_invocations(
+            mut self,
+            deployment_id: DeploymentId,
+            invocations_health: DeploymentInvocationsHealth,
+        ) -> Self {
+            self.1.insert(deployment_id, invocations_health);
+            self
+        }
+    }
+
+    impl DeploymentHealthReader for MockDeploymentHealthReader {
+        fn deployment_health(&self, deployment_id: &DeploymentId) -> Option<DeploymentHealth> {
+            self.0.get(deployment_id).copied()
+        }
+
+        fn deployment_invocations_health(
+            &self,
+            deployment_id: &DeploymentId,
+        ) -> Option<DeploymentInvocationsHealth> {
+            self.1.get(deployment_id).copied()
+        }
+    }
+}
@@ -12,6 +12,7 @@ use restate_types::errors::InvocationError;
 use restate_types::identifiers::InvocationId;
 use restate_types::identifiers::{DeploymentId, EntryIndex};
 use restate_types::journal::enriched::EnrichedRawEntry;
+use restate_types::time::MillisSinceEpoch;
 use std::collections::HashSet;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -26,6 +27,12 @@ pub struct Effect {
 pub enum EffectKind {
     /// This is sent before any new entry is created by the invoker. This won't be sent if the deployment_id is already set.
     SelectedDeployment(DeploymentId),
+    /// This is sent instead of [`Self::SelectedDeployment`] when the deployment of the invocation reached its concurrency limit.
+    /// The invoker dropped the invocation, which should be invoked again at `retry_at`.
+    DeploymentSaturated {
+        deployment_id: DeploymentId,
+        retry_at: MillisSinceEpoch,
+    },
     JournalEntry {
         entry_index: EntryIndex,
         entry: EnrichedRawEntry,
@@ -36,5 +43,19 @@ pub enum EffectKind {
     /// This is sent always after [`Self::JournalEntry`] with `OutputStreamEntry`(s).
     End,
     /// This is sent when the invoker exhausted all its attempts to make progress on the specific invocation.
-    Failed(InvocationError),
+    Failed {
+        error: InvocationError,
+        /// When the invoker gave up on the invocation.
+        failed_at: MillisSinceEpoch,
+    },
+}
+
+impl EffectKind {
+    /// Fails the invocation as of now.
+    pub fn failed(error: InvocationError) -> Self {
+        Self::Failed {
+            error,
+            failed_at: MillisSinceEpoch::now(),
+        }
+    }
 }
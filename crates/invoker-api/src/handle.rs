@@ -19,6 +19,7 @@ use restate_types::journal::raw::PlainRawEntry;
 use restate_types::journal::Completion;
 use std::future::Future;
 use std::ops::RangeInclusive;
+use std::time::Duration;
 use tokio::sync::mpsc;
 
 #[derive(Debug, Default)]
@@ -53,8 +54,20 @@ pub trait ServiceHandle<SR> {
         entry_index: EntryIndex,
     ) -> Self::Future;
 
+    /// Aborts all invocations of the partition right away, e.g. when its partition processor
+    /// lost the leadership.
     fn abort_all_partition(&mut self, partition: PartitionLeaderEpoch) -> Self::Future;
 
+    /// Like [`ServiceHandle::abort_all_partition`], but first drains the partition: no new
+    /// invocations are started, while the in-flight ones get up to `grace_period` to suspend or
+    /// complete. Their effects and stored entry acks keep flowing until they're done, after which
+    /// the remaining invocations are aborted.
+    fn drain_partition(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        grace_period: Duration,
+    ) -> Self::Future;
+
     fn abort_invocation(
         &mut self,
         partition_leader_epoch: PartitionLeaderEpoch,
@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+pub mod deployment_health;
 mod effects;
 pub mod entry_enricher;
 mod handle;
@@ -15,6 +16,9 @@ pub mod journal_reader;
 pub mod state_reader;
 pub mod status_handle;
 
+pub use deployment_health::{
+    DeploymentHealth, DeploymentHealthReader, DeploymentInvocationsHealth,
+};
 pub use effects::*;
 pub use entry_enricher::EntryEnricher;
 pub use handle::*;
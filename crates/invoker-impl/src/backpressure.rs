@@ -0,0 +1,231 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use hyper::header::RETRY_AFTER;
+use hyper::HeaderMap;
+use metrics::counter;
+use restate_types::identifiers::DeploymentId;
+use tracing::debug;
+
+use crate::metric_definitions::INVOKER_DEPLOYMENT_OVERLOADED;
+
+#[derive(Debug, Default)]
+struct DeploymentState {
+    in_flight: usize,
+    backoff: Option<Backoff>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Backoff {
+    until: Instant,
+    concurrency_limit: usize,
+}
+
+/// Why a request to a deployment was not admitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AcquireError {
+    /// The deployment asked to back off for the given time, and its reduced concurrency is reached.
+    BackingOff(Duration),
+    /// The deployment reached the deployment concurrency limit of the invoker.
+    Saturated,
+}
+
+/// Tracks the requests in flight to each deployment, shared by all invocation tasks.
+///
+/// The concurrent requests to a deployment are capped by the deployment concurrency limit, so
+/// that a slow deployment can't take up all the invoker slots. Deployments can also ask to back
+/// off, responding with 429 or 503 and a `Retry-After` header. Until the requested time, the
+/// concurrent requests to such a deployment are capped to half of the requests that were in
+/// flight when it signaled the overload. The invocation tasks exceeding the cap don't send their
+/// request and are retried once the deployment stops backing off.
+#[derive(Debug, Default)]
+pub(crate) struct DeploymentBackpressure {
+    deployments: Mutex<HashMap<DeploymentId, DeploymentState>>,
+}
+
+impl DeploymentBackpressure {
+    /// Admits a request to the deployment, unless it reached the `concurrency_limit` or its
+    /// reduced concurrency while backing off.
+    pub(crate) fn try_acquire(
+        self: &Arc<Self>,
+        deployment_id: DeploymentId,
+        concurrency_limit: Option<usize>,
+    ) -> Result<BackpressurePermit, AcquireError> {
+        let mut deployments = self.deployments.lock().unwrap();
+        let state = deployments.entry(deployment_id).or_default();
+        if concurrency_limit.is_some_and(|limit| state.in_flight >= limit) {
+            return Err(AcquireError::Saturated);
+        }
+        if let Some(backoff) = state.backoff {
+            let now = Instant::now();
+            if now >= backoff.until {
+                state.backoff = None;
+            } else if state.in_flight >= backoff.concurrency_limit {
+                return Err(AcquireError::BackingOff(backoff.until - now));
+            }
+        }
+        state.in_flight += 1;
+
+        Ok(BackpressurePermit {
+            backpressure: Arc::clone(self),
+            deployment_id,
+        })
+    }
+
+    /// Records that the deployment asked to back off for `retry_after`, while holding a permit.
+    pub(crate) fn record_overload(&self, deployment_id: DeploymentId, retry_after: Duration) {
+        let mut deployments = self.deployments.lock().unwrap();
+        let Some(state) = deployments.get_mut(&deployment_id) else {
+            return;
+        };
+
+        let now = Instant::now();
+        let mut backoff = Backoff {
+            until: now + retry_after,
+            concurrency_limit: (state.in_flight / 2).max(1),
+        };
+        if let Some(previous) = state.backoff.filter(|previous| previous.until > now) {
+            backoff.until = backoff.until.max(previous.until);
+            backoff.concurrency_limit = backoff.concurrency_limit.min(previous.concurrency_limit);
+        }
+        state.backoff = Some(backoff);
+
+        debug!(
+            restate.deployment.id = %deployment_id,
+            "Deployment asked to back off for {}, limiting its concurrent requests to {}",
+            humantime::format_duration(retry_after),
+            backoff.concurrency_limit
+        );
+        counter!(INVOKER_DEPLOYMENT_OVERLOADED).increment(1);
+    }
+
+    fn release(&self, deployment_id: &DeploymentId) {
+        let mut deployments = self.deployments.lock().unwrap();
+        if let Some(state) = deployments.get_mut(deployment_id) {
+            state.in_flight -= 1;
+            let backing_off = state
+                .backoff
+                .is_some_and(|backoff| backoff.until > Instant::now());
+            if state.in_flight == 0 && !backing_off {
+                deployments.remove(deployment_id);
+            }
+        }
+    }
+}
+
+/// Request admitted to a deployment, released on drop.
+#[derive(Debug)]
+pub(crate) struct BackpressurePermit {
+    backpressure: Arc<DeploymentBackpressure>,
+    deployment_id: DeploymentId,
+}
+
+impl Drop for BackpressurePermit {
+    fn drop(&mut self) {
+        self.backpressure.release(&self.deployment_id);
+    }
+}
+
+/// Parses the `Retry-After` header, either in seconds or as an HTTP date.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    httpdate::parse_http_date(value)
+        .ok()
+        .map(|date| date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn overload_halves_the_concurrency_until_the_retry_after() {
+        let backpressure = Arc::new(DeploymentBackpressure::default());
+        let deployment_id = DeploymentId::new();
+
+        let permits: Vec<_> = (0..4)
+            .map(|_| backpressure.try_acquire(deployment_id, None).unwrap())
+            .collect();
+        backpressure.record_overload(deployment_id, Duration::from_secs(60));
+
+        // 4 requests in flight, the limit is 2 until they complete
+        assert!(backpressure.try_acquire(deployment_id, None).is_err());
+        drop(permits);
+        let _permit_1 = backpressure.try_acquire(deployment_id, None).unwrap();
+        let _permit_2 = backpressure.try_acquire(deployment_id, None).unwrap();
+        let Err(AcquireError::BackingOff(wait)) = backpressure.try_acquire(deployment_id, None)
+        else {
+            panic!("deployment is backing off");
+        };
+        assert!(wait <= Duration::from_secs(60));
+
+        // Other deployments are not affected
+        assert!(backpressure.try_acquire(DeploymentId::new(), None).is_ok());
+    }
+
+    #[test]
+    fn concurrency_limit_saturates_the_deployment() {
+        let backpressure = Arc::new(DeploymentBackpressure::default());
+        let deployment_id = DeploymentId::new();
+
+        let permit = backpressure.try_acquire(deployment_id, Some(1)).unwrap();
+        assert_eq!(
+            backpressure
+                .try_acquire(deployment_id, Some(1))
+                .unwrap_err(),
+            AcquireError::Saturated
+        );
+        assert!(backpressure
+            .try_acquire(DeploymentId::new(), Some(1))
+            .is_ok());
+
+        // The slot is free again once the request completes
+        drop(permit);
+        let _permit = backpressure.try_acquire(deployment_id, Some(1)).unwrap();
+    }
+
+    #[test]
+    fn backoff_expires() {
+        let backpressure = Arc::new(DeploymentBackpressure::default());
+        let deployment_id = DeploymentId::new();
+
+        let permit = backpressure.try_acquire(deployment_id, None).unwrap();
+        backpressure.record_overload(deployment_id, Duration::ZERO);
+        let _permit_2 = backpressure.try_acquire(deployment_id, None).unwrap();
+        drop(permit);
+    }
+
+    #[test]
+    fn parse_retry_after_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(120)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+}
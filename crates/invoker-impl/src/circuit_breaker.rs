@@ -0,0 +1,206 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use metrics::counter;
+use restate_types::identifiers::DeploymentId;
+use tracing::warn;
+
+use crate::metric_definitions::INVOKER_CIRCUIT_BREAKER_OPENED;
+
+#[derive(Debug, Clone, Copy)]
+enum CircuitState {
+    Closed {
+        consecutive_failures: usize,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A single probe request is in flight, all other requests fail fast.
+    HalfOpen {
+        probe_started_at: Instant,
+    },
+}
+
+/// Tracks the consecutive connection failures and 5xx responses per deployment, shared by all
+/// invocation tasks. Once a deployment reaches the failure threshold, its circuit opens and
+/// invocation tasks fail fast without sending requests to it. After the open duration a single
+/// probe request is let through, which closes the circuit again if it succeeds.
+#[derive(Debug, Default)]
+pub(crate) struct CircuitBreaker {
+    deployments: Mutex<HashMap<DeploymentId, CircuitState>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a handle configured with the current options, `None` if the circuit breaker is
+    /// disabled.
+    pub(crate) fn handle(
+        self: &Arc<Self>,
+        failure_threshold: Option<usize>,
+        open_duration: Duration,
+    ) -> Option<CircuitBreakerHandle> {
+        failure_threshold.map(|failure_threshold| CircuitBreakerHandle {
+            circuit_breaker: Arc::clone(self),
+            failure_threshold,
+            open_duration,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CircuitBreakerHandle {
+    circuit_breaker: Arc<CircuitBreaker>,
+    failure_threshold: usize,
+    open_duration: Duration,
+}
+
+impl CircuitBreakerHandle {
+    /// Returns whether a request can be sent to the deployment.
+    pub(crate) fn try_acquire(&self, deployment_id: DeploymentId) -> bool {
+        let mut deployments = self.circuit_breaker.deployments.lock().unwrap();
+        let Some(state) = deployments.get_mut(&deployment_id) else {
+            return true;
+        };
+
+        let now = Instant::now();
+        match *state {
+            CircuitState::Closed { .. } => true,
+            CircuitState::Open { until } if now >= until => {
+                *state = CircuitState::HalfOpen {
+                    probe_started_at: now,
+                };
+                true
+            }
+            // the probe might have been aborted without reporting back
+            CircuitState::HalfOpen { probe_started_at }
+                if now >= probe_started_at + self.open_duration =>
+            {
+                *state = CircuitState::HalfOpen {
+                    probe_started_at: now,
+                };
+                true
+            }
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => false,
+        }
+    }
+
+    pub(crate) fn record_success(&self, deployment_id: DeploymentId) {
+        self.circuit_breaker
+            .deployments
+            .lock()
+            .unwrap()
+            .remove(&deployment_id);
+    }
+
+    pub(crate) fn record_failure(&self, deployment_id: DeploymentId) {
+        let mut deployments = self.circuit_breaker.deployments.lock().unwrap();
+        let state = deployments
+            .entry(deployment_id)
+            .or_insert(CircuitState::Closed {
+                consecutive_failures: 0,
+            });
+
+        let open = match state {
+            CircuitState::Closed {
+                consecutive_failures,
+            } => {
+                *consecutive_failures += 1;
+                *consecutive_failures >= self.failure_threshold
+            }
+            CircuitState::HalfOpen { .. } => true,
+            // requests which were sent before the circuit opened
+            CircuitState::Open { .. } => false,
+        };
+
+        if open {
+            warn!(
+                restate.deployment.id = %deployment_id,
+                "Opening the circuit of the deployment for {} after repeated failures",
+                humantime::format_duration(self.open_duration)
+            );
+            counter!(INVOKER_CIRCUIT_BREAKER_OPENED).increment(1);
+            *state = CircuitState::Open {
+                until: Instant::now() + self.open_duration,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let circuit_breaker = Arc::new(CircuitBreaker::default());
+        let handle = circuit_breaker
+            .handle(Some(2), Duration::from_secs(60))
+            .unwrap();
+        let deployment_id = DeploymentId::new();
+
+        handle.record_failure(deployment_id);
+        handle.record_success(deployment_id);
+        handle.record_failure(deployment_id);
+        assert!(handle.try_acquire(deployment_id));
+
+        handle.record_failure(deployment_id);
+        assert!(!handle.try_acquire(deployment_id));
+        // other deployments are not affected
+        assert!(handle.try_acquire(DeploymentId::new()));
+    }
+
+    #[test]
+    fn half_open_lets_a_single_probe_through() {
+        let circuit_breaker = Arc::new(CircuitBreaker::default());
+        let handle = circuit_breaker
+            .handle(Some(1), Duration::from_secs(60))
+            .unwrap();
+        let deployment_id = DeploymentId::new();
+
+        handle.record_failure(deployment_id);
+        assert!(!handle.try_acquire(deployment_id));
+
+        // the open duration elapsed
+        expire_open_circuit(&circuit_breaker, deployment_id);
+        assert!(handle.try_acquire(deployment_id));
+        assert!(!handle.try_acquire(deployment_id));
+
+        // a failed probe opens the circuit again
+        handle.record_failure(deployment_id);
+        assert!(!handle.try_acquire(deployment_id));
+
+        // a successful probe closes it
+        expire_open_circuit(&circuit_breaker, deployment_id);
+        assert!(handle.try_acquire(deployment_id));
+        handle.record_success(deployment_id);
+        assert!(handle.try_acquire(deployment_id));
+        assert!(handle.try_acquire(deployment_id));
+    }
+
+    #[test]
+    fn disabled_without_failure_threshold() {
+        let circuit_breaker = Arc::new(CircuitBreaker::default());
+        assert!(circuit_breaker
+            .handle(None, Duration::from_secs(60))
+            .is_none());
+    }
+
+    fn expire_open_circuit(circuit_breaker: &CircuitBreaker, deployment_id: DeploymentId) {
+        let mut deployments = circuit_breaker.deployments.lock().unwrap();
+        let state = deployments.get_mut(&deployment_id).unwrap();
+        assert!(matches!(state, CircuitState::Open { .. }));
+        *state = CircuitState::Open {
+            until: Instant::now(),
+        };
+    }
+}
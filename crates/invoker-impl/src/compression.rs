@@ -0,0 +1,114 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::io;
+use std::io::Write;
+
+use bytes::Bytes;
+use restate_schema_api::deployment::ContentEncoding;
+
+/// Compresses the request message stream. Every chunk is flushed, so the deployment can decode
+/// each message as soon as it arrives.
+pub(crate) enum Compressor {
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl Compressor {
+    pub(crate) fn new(content_encoding: ContentEncoding) -> io::Result<Self> {
+        Ok(match content_encoding {
+            ContentEncoding::Gzip => Compressor::Gzip(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            )),
+            ContentEncoding::Zstd => {
+                Compressor::Zstd(zstd::stream::write::Encoder::new(Vec::new(), 0)?)
+            }
+        })
+    }
+
+    pub(crate) fn compress(&mut self, buf: &[u8]) -> io::Result<Bytes> {
+        match self {
+            Compressor::Gzip(encoder) => {
+                encoder.write_all(buf)?;
+                encoder.flush()?;
+                Ok(std::mem::take(encoder.get_mut()).into())
+            }
+            Compressor::Zstd(encoder) => {
+                encoder.write_all(buf)?;
+                encoder.flush()?;
+                Ok(std::mem::take(encoder.get_mut()).into())
+            }
+        }
+    }
+
+    /// Writes the end of the compressed stream.
+    pub(crate) fn finish(self) -> io::Result<Bytes> {
+        Ok(match self {
+            Compressor::Gzip(encoder) => encoder.finish()?,
+            Compressor::Zstd(encoder) => encoder.finish()?,
+        }
+        .into())
+    }
+}
+
+/// Decompresses the response message stream chunk by chunk.
+pub(crate) enum Decompressor {
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+impl Decompressor {
+    pub(crate) fn new(content_encoding: ContentEncoding) -> io::Result<Self> {
+        Ok(match content_encoding {
+            ContentEncoding::Gzip => Decompressor::Gzip(flate2::write::GzDecoder::new(Vec::new())),
+            ContentEncoding::Zstd => {
+                Decompressor::Zstd(zstd::stream::write::Decoder::new(Vec::new())?)
+            }
+        })
+    }
+
+    pub(crate) fn decompress(&mut self, buf: &[u8]) -> io::Result<Bytes> {
+        match self {
+            Decompressor::Gzip(decoder) => {
+                decoder.write_all(buf)?;
+                decoder.flush()?;
+                Ok(std::mem::take(decoder.get_mut()).into())
+            }
+            Decompressor::Zstd(decoder) => {
+                decoder.write_all(buf)?;
+                decoder.flush()?;
+                Ok(std::mem::take(decoder.get_mut()).into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_compressed_chunk_can_be_decompressed() {
+        for content_encoding in [ContentEncoding::Gzip, ContentEncoding::Zstd] {
+            let mut compressor = Compressor::new(content_encoding).unwrap();
+            let mut decompressor = Decompressor::new(content_encoding).unwrap();
+
+            for message in [&b"start"[..], &[42; 4096], b"end"] {
+                let chunk = compressor.compress(message).unwrap();
+                assert_eq!(decompressor.decompress(&chunk).unwrap(), message);
+            }
+            assert!(decompressor
+                .decompress(&compressor.finish().unwrap())
+                .unwrap()
+                .is_empty());
+        }
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future;
+use hyper::http::uri::{InvalidUri, PathAndQuery};
+use hyper::{http, Uri};
+use metrics::counter;
+use restate_core::cancellation_watcher;
+use restate_invoker_api::{DeploymentHealth, DeploymentHealthReader, DeploymentInvocationsHealth};
+use restate_schema_api::deployment::{DeploymentResolver, DeploymentType, ProtocolType};
+use restate_service_client::ServiceClient;
+use restate_types::identifiers::DeploymentId;
+use restate_types::time::MillisSinceEpoch;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+
+use crate::metric_definitions::INVOKER_DEPLOYMENT_UNHEALTHY;
+
+/// Outcome of the last health check of the deployments, and of the invocation attempts sent to
+/// them, shared by the invocation tasks.
+#[derive(Debug, Default)]
+pub(crate) struct DeploymentsHealth {
+    deployments: Mutex<HashMap<DeploymentId, DeploymentHealth>>,
+    invocations: Mutex<HashMap<DeploymentId, DeploymentInvocationsHealth>>,
+}
+
+impl DeploymentsHealth {
+    pub(crate) fn is_unhealthy(&self, deployment_id: &DeploymentId) -> bool {
+        self.deployments.lock().unwrap().get(deployment_id) == Some(&DeploymentHealth::Unhealthy)
+    }
+
+    /// Records the outcome of an invocation attempt sent to the deployment.
+    pub(crate) fn record_attempt(&self, deployment_id: DeploymentId, deployment_failure: bool) {
+        let mut invocations = self.invocations.lock().unwrap();
+        let invocations_health = invocations.entry(deployment_id).or_default();
+        if deployment_failure {
+            invocations_health.consecutive_failures += 1;
+        } else {
+            invocations_health.last_success = Some(MillisSinceEpoch::now());
+            invocations_health.consecutive_failures = 0;
+        }
+    }
+
+    /// Records the outcome of a health check, returning the previous one.
+    fn record(
+        &self,
+        deployment_id: DeploymentId,
+        health: DeploymentHealth,
+    ) -> Option<DeploymentHealth> {
+        self.deployments
+            .lock()
+            .unwrap()
+            .insert(deployment_id, health)
+    }
+
+    /// Forgets the deployments which are not registered anymore.
+    fn retain(&self, deployment_ids: &HashSet<DeploymentId>) {
+        self.deployments
+            .lock()
+            .unwrap()
+            .retain(|deployment_id, _| deployment_ids.contains(deployment_id));
+        self.invocations
+            .lock()
+            .unwrap()
+            .retain(|deployment_id, _| deployment_ids.contains(deployment_id));
+    }
+}
+
+/// Reads the health of the deployments probed by the invoker.
+#[derive(Debug, Clone)]
+pub struct HealthCheckReader(pub(crate) Arc<DeploymentsHealth>);
+
+impl DeploymentHealthReader for HealthCheckReader {
+    fn deployment_health(&self, deployment_id: &DeploymentId) -> Option<DeploymentHealth> {
+        self.0
+            .deployments
+            .lock()
+            .unwrap()
+            .get(deployment_id)
+            .copied()
+    }
+
+    fn deployment_invocations_health(
+        &self,
+        deployment_id: &DeploymentId,
+    ) -> Option<DeploymentInvocationsHealth> {
+        self.0
+            .invocations
+            .lock()
+            .unwrap()
+            .get(deployment_id)
+            .copied()
+    }
+}
+
+/// Periodically sends a `GET` request to the health check path of every HTTP deployment. Once a
+/// deployment fails a health check, its pooled connections are closed, and the invocation tasks
+/// fail fast instead of sending requests to it, until it passes a health check again.
+pub(crate) struct HealthChecker<DMR> {
+    deployment_metadata_resolver: DMR,
+    probe: HealthProbe,
+    health: Arc<DeploymentsHealth>,
+}
+
+struct HealthProbe {
+    client: ServiceClient,
+    path: PathAndQuery,
+    interval: Duration,
+}
+
+impl<DMR: DeploymentResolver> HealthChecker<DMR> {
+    pub(crate) fn new(
+        client: ServiceClient,
+        deployment_metadata_resolver: DMR,
+        health: Arc<DeploymentsHealth>,
+        path: &str,
+        interval: Duration,
+    ) -> Result<Self, InvalidUri> {
+        Ok(Self {
+            deployment_metadata_resolver,
+            probe: HealthProbe {
+                client,
+                path: path.parse()?,
+                interval,
+            },
+            health,
+        })
+    }
+
+    pub(crate) async fn run(self) -> anyhow::Result<()> {
+        debug!(
+            "Checking the health of the deployments every {}",
+            humantime::format_duration(self.probe.interval)
+        );
+        tokio::select! {
+            _ = cancellation_watcher() => {},
+            _ = self.check_periodically() => {},
+        }
+        Ok(())
+    }
+
+    async fn check_periodically(self) {
+        let mut interval = tokio::time::interval(self.probe.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            let deployments = self.http_deployments();
+            self.health
+                .retain(&deployments.iter().map(|(id, _, _)| *id).collect());
+            let probe = &self.probe;
+            let checks =
+                deployments
+                    .into_iter()
+                    .map(|(deployment_id, address, protocol_type)| async move {
+                        let health = probe.check(address.clone(), protocol_type).await;
+                        (deployment_id, address, health)
+                    });
+            for (deployment_id, address, health) in future::join_all(checks).await {
+                let previous = self.health.record(deployment_id, health);
+                self.on_health_checked(deployment_id, &address, previous, health);
+            }
+        }
+    }
+
+    fn http_deployments(&self) -> Vec<(DeploymentId, Uri, ProtocolType)> {
+        self.deployment_metadata_resolver
+            .get_deployments()
+            .into_iter()
+            .filter_map(|(deployment, _)| match deployment.metadata.ty {
+                DeploymentType::Http {
+                    address,
+                    protocol_type,
+                    ..
+                } => Some((deployment.id, address, protocol_type)),
+                DeploymentType::Lambda { .. } => None,
+            })
+            .collect()
+    }
+
+    fn on_health_checked(
+        &self,
+        deployment_id: DeploymentId,
+        address: &Uri,
+        previous: Option<DeploymentHealth>,
+        health: DeploymentHealth,
+    ) {
+        match (previous, health) {
+            (Some(DeploymentHealth::Unhealthy), DeploymentHealth::Unhealthy) => {}
+            (_, DeploymentHealth::Unhealthy) => {
+                warn!(
+                    restate.deployment.id = %deployment_id,
+                    deployment.address = %address,
+                    "Deployment failed its health check, closing its connections"
+                );
+                counter!(INVOKER_DEPLOYMENT_UNHEALTHY).increment(1);
+                self.probe.client.evict_connections(address);
+            }
+            (Some(DeploymentHealth::Unhealthy), DeploymentHealth::Healthy) => {
+                info!(
+                    restate.deployment.id = %deployment_id,
+                    deployment.address = %address,
+                    "Deployment passed its health check again"
+                );
+            }
+            (_, DeploymentHealth::Healthy) => {}
+        }
+    }
+}
+
+impl HealthProbe {
+    async fn check(&self, address: Uri, protocol_type: ProtocolType) -> DeploymentHealth {
+        let version = match protocol_type {
+            ProtocolType::RequestResponse => http::Version::default(),
+            ProtocolType::BidiStream => http::Version::HTTP_2,
+        };
+        let response = tokio::time::timeout(
+            self.interval,
+            self.client
+                .check_health(address.clone(), version, self.path.clone()),
+        )
+        .await;
+
+        match response {
+            Ok(Ok(response)) if response.status().is_success() => DeploymentHealth::Healthy,
+            Ok(Ok(response)) => {
+                debug!(
+                    deployment.address = %address,
+                    "Health check responded with {}",
+                    response.status()
+                );
+                DeploymentHealth::Unhealthy
+            }
+            Ok(Err(e)) => {
+                debug!(deployment.address = %address, "Health check failed: {}", e);
+                DeploymentHealth::Unhealthy
+            }
+            Err(_) => {
+                debug!(deployment.address = %address, "Health check timed out");
+                DeploymentHealth::Unhealthy
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgets_removed_deployments() {
+        let health = Arc::new(DeploymentsHealth::default());
+        let reader = HealthCheckReader(Arc::clone(&health));
+        let removed = DeploymentId::new();
+        let registered = DeploymentId::new();
+
+        assert_eq!(health.record(removed, DeploymentHealth::Unhealthy), None);
+        assert_eq!(health.record(registered, DeploymentHealth::Healthy), None);
+        assert!(health.is_unhealthy(&removed));
+        assert!(!health.is_unhealthy(&registered));
+
+        health.retain(&HashSet::from([registered]));
+        assert!(!health.is_unhealthy(&removed));
+        assert_eq!(reader.deployment_health(&removed), None);
+        assert_eq!(
+            reader.deployment_health(&registered),
+            Some(DeploymentHealth::Healthy)
+        );
+    }
+
+    #[test]
+    fn counts_consecutive_failures() {
+        let health = Arc::new(DeploymentsHealth::default());
+        let reader = HealthCheckReader(Arc::clone(&health));
+        let deployment_id = DeploymentId::new();
+
+        assert_eq!(reader.deployment_invocations_health(&deployment_id), None);
+
+        health.record_attempt(deployment_id, true);
+        health.record_attempt(deployment_id, true);
+        assert_eq!(
+            reader.deployment_invocations_health(&deployment_id),
+            Some(DeploymentInvocationsHealth {
+                last_success: None,
+                consecutive_failures: 2
+            })
+        );
+
+        health.record_attempt(deployment_id, false);
+        let invocations_health = reader
+            .deployment_invocations_health(&deployment_id)
+            .unwrap();
+        assert!(invocations_health.last_success.is_some());
+        assert_eq!(invocations_health.consecutive_failures, 0);
+
+        health.retain(&HashSet::new());
+        assert_eq!(reader.deployment_invocations_health(&deployment_id), None);
+    }
+}
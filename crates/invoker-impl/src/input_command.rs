@@ -16,6 +16,7 @@ use restate_types::identifiers::{EntryIndex, InvocationId, PartitionKey, Partiti
 use restate_types::invocation::InvocationTarget;
 use restate_types::journal::Completion;
 use std::ops::RangeInclusive;
+use std::time::Duration;
 use tokio::sync::mpsc;
 
 // -- Input messages
@@ -54,6 +55,13 @@ pub(crate) enum InputCommand<SR> {
         partition: PartitionLeaderEpoch,
     },
 
+    /// Command used to let the in-flight invocations of a partition leader that is going away
+    /// finish before cleaning up
+    DrainPartition {
+        partition: PartitionLeaderEpoch,
+        grace_period: Duration,
+    },
+
     // needed for dynamic registration at Invoker
     RegisterPartition {
         partition: PartitionLeaderEpoch,
@@ -133,6 +141,21 @@ impl<SR> ServiceHandle<SR> for InvokerHandle<SR> {
         )
     }
 
+    fn drain_partition(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        grace_period: Duration,
+    ) -> Self::Future {
+        futures::future::ready(
+            self.input
+                .send(InputCommand::DrainPartition {
+                    partition,
+                    grace_period,
+                })
+                .map_err(|_| NotRunningError),
+        )
+    }
+
     fn abort_invocation(
         &mut self,
         partition: PartitionLeaderEpoch,
@@ -86,6 +86,9 @@ enum InvocationState {
 
         // If Some, we need to notify the deployment id to the partition processor
         chosen_deployment: Option<DeploymentId>,
+
+        // Whether new entries or completions were added to the journal since the task started
+        journal_changed: bool,
     },
 
     WaitingRetry {
@@ -155,6 +158,7 @@ impl InvocationStateMachine {
             abort_handle,
             entries_to_ack: Default::default(),
             chosen_deployment: None,
+            journal_changed: false,
         };
     }
 
@@ -206,9 +210,11 @@ impl InvocationStateMachine {
         if let InvocationState::InFlight {
             journal_tracker,
             entries_to_ack,
+            journal_changed,
             ..
         } = &mut self.invocation_state
         {
+            *journal_changed = true;
             if requires_ack {
                 entries_to_ack.insert(entry_index);
             }
@@ -240,9 +246,12 @@ impl InvocationStateMachine {
 
     pub(super) fn notify_completion(&mut self, completion: Completion) {
         if let InvocationState::InFlight {
-            notifications_tx, ..
+            notifications_tx,
+            journal_changed,
+            ..
         } = &mut self.invocation_state
         {
+            *journal_changed = true;
             Self::try_send_notification(notifications_tx, Notification::Completion(completion));
         }
     }
@@ -303,6 +312,21 @@ impl InvocationStateMachine {
         }
     }
 
+    pub(super) fn is_in_flight(&self) -> bool {
+        matches!(self.invocation_state, InvocationState::InFlight { .. })
+    }
+
+    /// Whether the journal read by the in-flight invocation task is still the stored one.
+    pub(super) fn is_journal_unchanged(&self) -> bool {
+        matches!(
+            self.invocation_state,
+            InvocationState::InFlight {
+                journal_changed: false,
+                ..
+            }
+        )
+    }
+
     pub(super) fn is_ready_to_retry(&self) -> bool {
         match self.invocation_state {
             InvocationState::WaitingRetry {
@@ -9,6 +9,16 @@
 // by the Apache License, Version 2.0.
 
 use super::Notification;
+use crate::backpressure::{self, AcquireError, BackpressurePermit, DeploymentBackpressure};
+use crate::circuit_breaker::CircuitBreakerHandle;
+use crate::compression::{Compressor, Decompressor};
+use crate::health_check::DeploymentsHealth;
+use crate::journal_cache;
+use crate::journal_memory_budget::{JournalMemoryBudget, JournalMemoryLease};
+use crate::metric_definitions::{
+    INVOKER_ATTEMPT_DURATION, INVOKER_ATTEMPT_ERROR, INVOKER_TIME_TO_FIRST_ENTRY,
+    TASK_OP_COMPLETED, TASK_OP_FAILED, TASK_OP_SUSPENDED,
+};
 
 use bytes::Bytes;
 use futures::future::FusedFuture;
@@ -18,22 +28,24 @@ use hyper::http::response::Parts as ResponseParts;
 use hyper::http::uri::PathAndQuery;
 use hyper::http::{HeaderName, HeaderValue};
 use hyper::{http, Body, HeaderMap, Response};
+use metrics::{counter, histogram, Label};
 use opentelemetry::propagation::TextMapPropagator;
 use opentelemetry_http::HeaderInjector;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
 use restate_errors::warn_it;
 use restate_invoker_api::{
-    EagerState, EntryEnricher, InvocationErrorReport, InvokeInputJournal, JournalReader,
-    StateReader,
+    EagerState, EntryEnricher, InvocationErrorReport, InvokeInputJournal, JournalMetadata,
+    JournalReader, StateReader,
 };
 use restate_schema_api::deployment::{
-    DeploymentMetadata, DeploymentResolver, DeploymentType, ProtocolType,
+    ContentEncoding, DeploymentMetadata, DeploymentResolver, DeploymentType, ProtocolType,
 };
 use restate_service_client::{Endpoint, Parts, Request, ServiceClient, ServiceClientError};
 use restate_service_protocol::message::{
     Decoder, Encoder, EncodingError, MessageHeader, MessageType, ProtocolMessage,
 };
-use restate_types::errors::InvocationError;
+use restate_service_protocol::pb::protocol::ServiceProtocolVersion;
+use restate_types::errors::{codes, InvocationError, InvocationErrorCode};
 use restate_types::identifiers::{DeploymentId, EntryIndex, InvocationId, PartitionLeaderEpoch};
 use restate_types::invocation::{InvocationTarget, ServiceInvocationSpanContext};
 use restate_types::journal::enriched::EnrichedRawEntry;
@@ -43,9 +55,11 @@ use std::collections::HashSet;
 use std::error::Error;
 use std::future::{poll_fn, Future};
 use std::iter;
+use std::ops::RangeInclusive;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{ready, Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::task::JoinError;
 use tokio::task::JoinHandle;
@@ -54,9 +68,6 @@ use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 // Clippy false positive, might be caused by Bytes contained within HeaderValue.
 // https://github.com/rust-lang/rust/issues/40543#issuecomment-1212981256
-#[allow(clippy::declare_interior_mutable_const)]
-const APPLICATION_RESTATE: HeaderValue = HeaderValue::from_static("application/restate");
-
 #[allow(clippy::declare_interior_mutable_const)]
 const X_RESTATE_SERVER: HeaderName = HeaderName::from_static("x-restate-server");
 
@@ -72,12 +83,27 @@ pub(crate) enum InvocationTaskError {
     #[error("unexpected http status code: {0}")]
     #[code(restate_errors::RT0012)]
     UnexpectedResponse(http::StatusCode),
+    #[error("the deployment is overloaded, got http status code {0}")]
+    #[code(unknown)]
+    Overloaded(http::StatusCode, Option<Duration>),
     #[error("unexpected content type: {0:?}")]
     #[code(restate_errors::RT0012)]
     UnexpectedContentType(Option<HeaderValue>),
+    #[error("unexpected content encoding: {0:?}")]
+    #[code(restate_errors::RT0012)]
+    UnexpectedContentEncoding(HeaderValue),
+    #[error("error when compressing or decompressing the message stream: {0}")]
+    #[code(unknown)]
+    Compression(#[source] std::io::Error),
     #[error("received unexpected message: {0:?}")]
     #[code(restate_errors::RT0012)]
     UnexpectedMessage(MessageType),
+    #[error("deployment {0} supports the service protocol versions {1:?}, none of which is supported by this runtime")]
+    #[code(unknown)]
+    IncompatibleServiceEndpoint(DeploymentId, RangeInclusive<i32>),
+    #[error("cannot send message {0:?} to the deployment, it requires a newer service protocol version than the negotiated {1:?}")]
+    #[code(unknown)]
+    UnsupportedMessage(MessageType, ServiceProtocolVersion),
     #[error("message encoding error: {0}")]
     Encoding(
         #[from]
@@ -116,6 +142,17 @@ pub(crate) enum InvocationTaskError {
     #[error("response timeout")]
     #[code(restate_errors::RT0001)]
     ResponseTimeout,
+    #[error(
+        "the circuit of deployment {0} is open after repeated failures, not sending the request"
+    )]
+    #[code(unknown)]
+    CircuitOpen(DeploymentId),
+    #[error("deployment {0} asked to back off, not sending the request")]
+    #[code(unknown)]
+    BackingOff(DeploymentId, Duration),
+    #[error("deployment {0} failed its last health check, not sending the request")]
+    #[code(unknown)]
+    UnhealthyDeployment(DeploymentId),
 
     #[error("cannot process incoming entry at index {0} of type {1}: {2}")]
     #[code(unknown)]
@@ -136,9 +173,85 @@ pub struct InvocationErrorRelatedEntry {
     pub related_entry_type: Option<EntryType>,
 }
 
+/// Classification of an [`InvocationTaskError`], decides whether the invoker retries the
+/// invocation and with which code it fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InvocationErrorClass {
+    /// Failure of the runtime or of the communication with the deployment. Retried according to
+    /// the retry policy.
+    Retryable,
+    /// Failure which retrying cannot fix, the invocation fails right away.
+    Terminal,
+    /// Failure reported by the service code. Retried according to the retry policy, the
+    /// invocation fails with the code reported by the service once the retries are exhausted.
+    User,
+}
+
 impl InvocationTaskError {
+    pub(crate) fn class(&self) -> InvocationErrorClass {
+        match self {
+            InvocationTaskError::ErrorMessageReceived(..) => InvocationErrorClass::User,
+            // the deployment the invocation is pinned to is gone for good
+            InvocationTaskError::UnknownDeployment(_) => InvocationErrorClass::Terminal,
+            // replaying the journal would fail the same entry again
+            InvocationTaskError::EntryEnrichment(_, _, e) if e.code() == codes::BAD_REQUEST => {
+                InvocationErrorClass::Terminal
+            }
+            _ => InvocationErrorClass::Retryable,
+        }
+    }
+
     pub(crate) fn is_transient(&self) -> bool {
-        true
+        self.class() != InvocationErrorClass::Terminal
+    }
+
+    /// How long the deployment asked to wait before retrying, overriding the retry policy.
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        match self {
+            InvocationTaskError::Overloaded(_, retry_after) => *retry_after,
+            InvocationTaskError::BackingOff(_, retry_after) => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// Whether the error counts as a failure of the deployment for its circuit breaker.
+    fn is_deployment_failure(&self) -> bool {
+        match self {
+            InvocationTaskError::Client(_) => true,
+            InvocationTaskError::UnexpectedResponse(status) => status.is_server_error(),
+            _ => false,
+        }
+    }
+
+    /// Code the invocation fails with if it's not retried.
+    fn invocation_error_code(&self) -> InvocationErrorCode {
+        match self {
+            InvocationTaskError::ErrorMessageReceived(_, e)
+            | InvocationTaskError::EntryEnrichment(_, _, e) => e.code(),
+            InvocationTaskError::NoDeploymentForService
+            | InvocationTaskError::UnknownDeployment(_) => codes::NOT_FOUND,
+            InvocationTaskError::ResponseTimeout => codes::TIMEOUT,
+            InvocationTaskError::Client(_)
+            | InvocationTaskError::UnexpectedResponse(_)
+            | InvocationTaskError::Overloaded(_, _)
+            | InvocationTaskError::CircuitOpen(_)
+            | InvocationTaskError::BackingOff(_, _)
+            | InvocationTaskError::UnhealthyDeployment(_) => codes::UNAVAILABLE,
+            InvocationTaskError::UnexpectedContentType(_)
+            | InvocationTaskError::UnexpectedContentEncoding(_)
+            | InvocationTaskError::Compression(_)
+            | InvocationTaskError::UnexpectedMessage(_)
+            | InvocationTaskError::IncompatibleServiceEndpoint(_, _)
+            | InvocationTaskError::UnsupportedMessage(_, _)
+            | InvocationTaskError::Encoding(_)
+            | InvocationTaskError::WriteAfterEndOfStream
+            | InvocationTaskError::BadHeader(_, _)
+            | InvocationTaskError::EmptySuspensionMessage
+            | InvocationTaskError::BadSuspensionMessage(_, _) => codes::PROTOCOL_VIOLATION,
+            InvocationTaskError::JournalReader(_)
+            | InvocationTaskError::StateReader(_)
+            | InvocationTaskError::UnexpectedJoinError(_) => codes::INTERNAL,
+        }
     }
 
     pub(crate) fn into_invocation_error(self) -> InvocationError {
@@ -157,7 +270,7 @@ impl InvocationTaskError {
                 }
                 err
             }
-            e => InvocationError::internal(e),
+            e => InvocationError::new(e.invocation_error_code(), e),
         }
     }
 
@@ -230,6 +343,11 @@ pub(super) enum InvocationTaskOutputInner {
     },
     Closed,
     Suspended(HashSet<EntryIndex>),
+    /// The deployment reached its concurrency limit, the request was not sent.
+    DeploymentSaturated(DeploymentId),
+    /// The whole journal replayed before failing with a transient error, sent before
+    /// [`InvocationTaskOutputInner::Failed`] so that the invoker can cache it for the retry.
+    ReplayedJournal(JournalMetadata, Vec<PlainRawEntry>),
     Failed(InvocationTaskError),
 }
 
@@ -257,15 +375,43 @@ pub(super) struct InvocationTask<SR, JR, EE, DMR> {
     journal_reader: JR,
     entry_enricher: EE,
     deployment_metadata_resolver: DMR,
+    circuit_breaker: Option<CircuitBreakerHandle>,
+    backpressure: Arc<DeploymentBackpressure>,
+    deployment_concurrency_limit: Option<usize>,
+    deployment_health: Arc<DeploymentsHealth>,
+    journal_memory_budget: JournalMemoryBudget,
     invoker_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
     invoker_rx: mpsc::UnboundedReceiver<Notification>,
 
     // Encoder/Decoder
     encoder: Encoder,
     decoder: Decoder,
+    compressor: Option<Compressor>,
+    decompressor: Option<Decompressor>,
 
     // Task state
     next_journal_index: EntryIndex,
+    selected_deployment: Option<DeploymentId>,
+    service_protocol_version: Option<ServiceProtocolVersion>,
+    // Held until the invocation task ends
+    backpressure_permit: Option<BackpressurePermit>,
+    journal_cache_limit: Option<usize>,
+    replayed_journal: Option<ReplayedJournal>,
+    request_sent_at: Option<Instant>,
+    received_first_entry: bool,
+}
+
+/// Entries replayed to the deployment, kept to hand them to the journal cache of the invoker.
+struct ReplayedJournal {
+    metadata: JournalMetadata,
+    entries: Vec<PlainRawEntry>,
+    size: usize,
+}
+
+impl ReplayedJournal {
+    fn is_complete(&self) -> bool {
+        self.entries.len() == self.metadata.length as usize
+    }
 }
 
 /// This is needed to split the run_internal in multiple loop functions and have shortcircuiting.
@@ -273,6 +419,7 @@ enum TerminalLoopState<T> {
     Continue(T),
     Closed,
     Suspended(HashSet<EntryIndex>),
+    DeploymentSaturated(DeploymentId),
     Failed(InvocationTaskError),
 }
 
@@ -292,6 +439,9 @@ macro_rules! shortcircuit {
             TerminalLoopState::Continue(v) => v,
             TerminalLoopState::Closed => return TerminalLoopState::Closed,
             TerminalLoopState::Suspended(v) => return TerminalLoopState::Suspended(v),
+            TerminalLoopState::DeploymentSaturated(v) => {
+                return TerminalLoopState::DeploymentSaturated(v)
+            }
             TerminalLoopState::Failed(e) => return TerminalLoopState::Failed(e),
         }
     };
@@ -322,6 +472,12 @@ where
         journal_reader: JR,
         entry_enricher: EE,
         deployment_metadata_resolver: DMR,
+        circuit_breaker: Option<CircuitBreakerHandle>,
+        backpressure: Arc<DeploymentBackpressure>,
+        deployment_concurrency_limit: Option<usize>,
+        deployment_health: Arc<DeploymentsHealth>,
+        journal_memory_budget: JournalMemoryBudget,
+        journal_cache_limit: Option<usize>,
         invoker_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
         invoker_rx: mpsc::UnboundedReceiver<Notification>,
     ) -> Self {
@@ -334,14 +490,28 @@ where
             abort_timeout,
             disable_eager_state,
             next_journal_index: 0,
+            selected_deployment: None,
+            service_protocol_version: None,
+            backpressure_permit: None,
+            journal_cache_limit,
+            replayed_journal: None,
+            request_sent_at: None,
+            received_first_entry: false,
             state_reader,
             journal_reader,
             entry_enricher,
             deployment_metadata_resolver,
+            circuit_breaker,
+            backpressure,
+            deployment_concurrency_limit,
+            deployment_health,
+            journal_memory_budget,
             invoker_tx,
             invoker_rx,
             encoder: Encoder::new(protocol_version),
             decoder: Decoder::new(message_size_warning, message_size_limit),
+            compressor: None,
+            decompressor: None,
         }
     }
 
@@ -359,6 +529,44 @@ where
             );
         }
 
+        if let (Some(circuit_breaker), Some(deployment_id)) =
+            (&self.circuit_breaker, self.selected_deployment)
+        {
+            match &terminal_state {
+                TerminalLoopState::Failed(e) if e.is_deployment_failure() => {
+                    circuit_breaker.record_failure(deployment_id)
+                }
+                _ => circuit_breaker.record_success(deployment_id),
+            }
+        }
+        if let Some(deployment_id) = self.selected_deployment {
+            self.deployment_health.record_attempt(
+                deployment_id,
+                matches!(&terminal_state, TerminalLoopState::Failed(e) if e.is_deployment_failure()),
+            );
+        }
+        if let (
+            TerminalLoopState::Failed(InvocationTaskError::Overloaded(_, Some(retry_after))),
+            Some(deployment_id),
+        ) = (&terminal_state, self.selected_deployment)
+        {
+            self.backpressure
+                .record_overload(deployment_id, *retry_after);
+        }
+        self.record_attempt_metrics(&terminal_state);
+
+        // Let the invoker cache the journal for the retry
+        if let (TerminalLoopState::Failed(e), Some(replayed_journal)) =
+            (&terminal_state, self.replayed_journal.take())
+        {
+            if e.is_transient() && replayed_journal.is_complete() {
+                self.send_invoker_tx(InvocationTaskOutputInner::ReplayedJournal(
+                    replayed_journal.metadata,
+                    replayed_journal.entries,
+                ));
+            }
+        }
+
         // Sanity check of the final state
         let inner = match terminal_state {
             TerminalLoopState::Continue(_) => {
@@ -366,6 +574,9 @@ where
             }
             TerminalLoopState::Closed => InvocationTaskOutputInner::Closed,
             TerminalLoopState::Suspended(v) => InvocationTaskOutputInner::Suspended(v),
+            TerminalLoopState::DeploymentSaturated(v) => {
+                InvocationTaskOutputInner::DeploymentSaturated(v)
+            }
             TerminalLoopState::Failed(e) => InvocationTaskOutputInner::Failed(e),
         };
 
@@ -373,6 +584,10 @@ where
     }
 
     async fn run_internal(&mut self, input_journal: InvokeInputJournal) -> TerminalLoopState<()> {
+        let (input_journal, cached_journal_lease) = self
+            .journal_memory_budget
+            .reserve_input_journal(input_journal);
+
         // Resolve journal and its metadata
         let read_journal_future = async {
             Ok(match input_journal {
@@ -382,12 +597,19 @@ where
                         .read_journal(&self.invocation_id)
                         .await
                         .map_err(|e| InvocationTaskError::JournalReader(e.into()))?;
-                    (journal_meta, future::Either::Left(journal_stream))
+                    (journal_meta, future::Either::Left(journal_stream), None)
+                }
+                InvokeInputJournal::CachedJournal(journal_meta, journal_items) => {
+                    let replayed_entries = self
+                        .journal_cache_limit
+                        .is_some()
+                        .then(|| journal_items.clone());
+                    (
+                        journal_meta,
+                        future::Either::Right(stream::iter(journal_items)),
+                        replayed_entries,
+                    )
                 }
-                InvokeInputJournal::CachedJournal(journal_meta, journal_items) => (
-                    journal_meta,
-                    future::Either::Right(stream::iter(journal_items)),
-                ),
             })
         };
         // Read eager state
@@ -405,8 +627,16 @@ where
         };
 
         // We execute those concurrently
-        let ((journal_metadata, journal_stream), state_iter) =
+        let ((journal_metadata, journal_stream, replayed_entries), state_iter) =
             shortcircuit!(tokio::try_join!(read_journal_future, read_state_future));
+        if self.journal_cache_limit.is_some() {
+            let entries = replayed_entries.unwrap_or_default();
+            self.replayed_journal = Some(ReplayedJournal {
+                metadata: journal_metadata.clone(),
+                size: journal_cache::journal_size(&entries),
+                entries,
+            });
+        }
 
         // Resolve the deployment metadata
         let (deployment, deployment_changed) =
@@ -420,19 +650,74 @@ where
                 (deployment_metadata, /* has_changed= */ false)
             } else {
                 // We can choose the freshest deployment for the latest revision
-                // of the registered service.
+                // of the registered service, or the one picked by its traffic split.
                 let deployment = shortcircuit!(self
                     .deployment_metadata_resolver
-                    .resolve_latest_deployment_for_service(self.invocation_target.service_name())
+                    .resolve_deployment_for_invocation(
+                        self.invocation_target.service_name(),
+                        &self.invocation_id
+                    )
                     .ok_or(InvocationTaskError::NoDeploymentForService));
                 (deployment, /* has_changed= */ true)
             };
 
+        // Don't send the request if the deployment reached its concurrency limit, or if it asked
+        // to back off and its reduced concurrency is reached. Saturation is checked before
+        // selecting the deployment, so that new invocations are not pinned to it.
+        self.backpressure_permit = Some(
+            match self
+                .backpressure
+                .try_acquire(deployment.id, self.deployment_concurrency_limit)
+            {
+                Ok(permit) => permit,
+                Err(AcquireError::Saturated) => {
+                    return TerminalLoopState::DeploymentSaturated(deployment.id)
+                }
+                Err(AcquireError::BackingOff(retry_after)) => {
+                    return TerminalLoopState::Failed(InvocationTaskError::BackingOff(
+                        deployment.id,
+                        retry_after,
+                    ))
+                }
+            },
+        );
+
         self.send_invoker_tx(InvocationTaskOutputInner::SelectedDeployment(
             deployment.id,
             deployment_changed,
         ));
 
+        // Speak the newest service protocol version supported by both the runtime and the deployment
+        let supported_protocol_versions = &deployment.metadata.supported_protocol_versions;
+        self.service_protocol_version = Some(shortcircuit!(
+            ServiceProtocolVersion::max_supported_version(
+                *supported_protocol_versions.start(),
+                *supported_protocol_versions.end(),
+            )
+            .filter(|version| version.content_type().is_some())
+            .ok_or_else(|| InvocationTaskError::IncompatibleServiceEndpoint(
+                deployment.id,
+                supported_protocol_versions.clone()
+            ))
+        ));
+
+        // Fail fast if the deployment failed its last health check
+        if self.deployment_health.is_unhealthy(&deployment.id) {
+            return TerminalLoopState::Failed(InvocationTaskError::UnhealthyDeployment(
+                deployment.id,
+            ));
+        }
+
+        // Fail fast if the deployment is known to be unavailable
+        if self
+            .circuit_breaker
+            .as_ref()
+            .is_some_and(|circuit_breaker| !circuit_breaker.try_acquire(deployment.id))
+        {
+            return TerminalLoopState::Failed(InvocationTaskError::CircuitOpen(deployment.id));
+        }
+        self.selected_deployment = Some(deployment.id);
+
         // Figure out the protocol type. Force RequestResponse if inactivity_timeout is zero
         let protocol_type = if self.inactivity_timeout.is_zero() {
             ProtocolType::RequestResponse
@@ -473,19 +758,26 @@ where
         let service_invocation_span_context = journal_metadata.span_context;
 
         // Prepare the request and send start message
-        let (mut http_stream_tx, request) = self.prepare_request(path, deployment.metadata);
+        let (mut http_stream_tx, request) =
+            shortcircuit!(self.prepare_request(path, deployment.metadata));
         shortcircuit!(
             self.write_start(&mut http_stream_tx, journal_size, state_iter)
                 .await
         );
 
         // Initialize the response stream state
+        self.request_sent_at = Some(Instant::now());
         let mut http_stream_rx = ResponseStreamState::initialize(&self.client, request);
 
         // Execute the replay
         shortcircuit!(
-            self.replay_loop(&mut http_stream_tx, &mut http_stream_rx, journal_stream)
-                .await
+            self.replay_loop(
+                &mut http_stream_tx,
+                &mut http_stream_rx,
+                journal_stream,
+                cached_journal_lease
+            )
+            .await
         );
 
         // Check all the entries have been replayed
@@ -503,6 +795,10 @@ where
                 .await
             );
         } else {
+            shortcircuit!(
+                self.write_end_of_compressed_stream(&mut http_stream_tx)
+                    .await
+            );
             // Drop the http_stream_tx.
             // This is required in HTTP/1.1 to let the deployment send the headers back
             drop(http_stream_tx)
@@ -516,11 +812,15 @@ where
     // --- Loops
 
     /// This loop concurrently pushes journal entries and waits for the response headers and end of replay.
+    ///
+    /// A cached journal was accounted as a whole against the journal memory budget, entries read
+    /// from storage are accounted one by one while being written.
     async fn replay_loop<JournalStream>(
         &mut self,
         http_stream_tx: &mut Sender,
         http_stream_rx: &mut ResponseStreamState,
         journal_stream: JournalStream,
+        cached_journal_lease: Option<JournalMemoryLease>,
     ) -> TerminalLoopState<()>
     where
         JournalStream: Stream<Item = PlainRawEntry> + Unpin,
@@ -540,6 +840,12 @@ where
                 opt_je = journal_stream.next() => {
                     match opt_je {
                         Some(je) => {
+                            let _entry_lease = if cached_journal_lease.is_none() {
+                                Some(self.journal_memory_budget.reserve(je.serialized_entry().len()).await)
+                            } else {
+                                None
+                            };
+                            self.record_replayed_entry(&je);
                             shortcircuit!(self.write(http_stream_tx, ProtocolMessage::UnparsedEntry(je)).await);
                             self.next_journal_index += 1;
                         },
@@ -663,8 +969,43 @@ where
         msg: ProtocolMessage,
     ) -> Result<(), InvocationTaskError> {
         trace!(restate.protocol.message = ?msg, "Sending message");
-        let buf = self.encoder.encode(msg);
+        if let Some(version) = self.service_protocol_version {
+            let message_type = msg.message_type();
+            if message_type.min_service_protocol_version() > version {
+                return Err(InvocationTaskError::UnsupportedMessage(
+                    message_type,
+                    version,
+                ));
+            }
+        }
+        let mut buf = self.encoder.encode(msg);
+        if let Some(compressor) = &mut self.compressor {
+            buf = compressor
+                .compress(&buf)
+                .map_err(InvocationTaskError::Compression)?;
+        }
+
+        self.send_data(http_stream_tx, buf).await
+    }
+
+    async fn write_end_of_compressed_stream(
+        &mut self,
+        http_stream_tx: &mut Sender,
+    ) -> Result<(), InvocationTaskError> {
+        if let Some(compressor) = self.compressor.take() {
+            let buf = compressor
+                .finish()
+                .map_err(InvocationTaskError::Compression)?;
+            self.send_data(http_stream_tx, buf).await?;
+        }
+        Ok(())
+    }
 
+    async fn send_data(
+        &mut self,
+        http_stream_tx: &mut Sender,
+        buf: Bytes,
+    ) -> Result<(), InvocationTaskError> {
         if let Err(hyper_err) = http_stream_tx.send_data(buf).await {
             // is_closed() is try only if the request channel (Sender) has been closed.
             // This can happen if the deployment is suspending.
@@ -681,23 +1022,40 @@ where
         &mut self,
         mut parts: ResponseParts,
     ) -> Result<(), InvocationTaskError> {
+        if parts.status == http::StatusCode::TOO_MANY_REQUESTS
+            || parts.status == http::StatusCode::SERVICE_UNAVAILABLE
+        {
+            return Err(InvocationTaskError::Overloaded(
+                parts.status,
+                backpressure::parse_retry_after(&parts.headers),
+            ));
+        }
         if !parts.status.is_success() {
             return Err(InvocationTaskError::UnexpectedResponse(parts.status));
         }
 
         let content_type = parts.headers.remove(http::header::CONTENT_TYPE);
         match content_type {
-            // Check content type is application/restate
-            Some(ct) =>
-            {
-                #[allow(clippy::borrow_interior_mutable_const)]
-                if ct != APPLICATION_RESTATE {
+            // Check the deployment speaks the negotiated service protocol version
+            Some(ct) => {
+                if ct != self.content_type() {
                     return Err(InvocationTaskError::UnexpectedContentType(Some(ct)));
                 }
             }
             None => return Err(InvocationTaskError::UnexpectedContentType(None)),
         }
 
+        if let Some(hv) = parts.headers.remove(http::header::CONTENT_ENCODING) {
+            let content_encoding = hv
+                .to_str()
+                .ok()
+                .and_then(|content_encoding| content_encoding.parse::<ContentEncoding>().ok())
+                .ok_or_else(|| InvocationTaskError::UnexpectedContentEncoding(hv.clone()))?;
+            self.decompressor = Some(
+                Decompressor::new(content_encoding).map_err(InvocationTaskError::Compression)?,
+            );
+        }
+
         if let Some(hv) = parts.headers.remove(X_RESTATE_SERVER) {
             self.send_invoker_tx(InvocationTaskOutputInner::ServerHeaderReceived(
                 hv.to_str()
@@ -712,8 +1070,13 @@ where
     fn handle_read(
         &mut self,
         parent_span_context: &ServiceInvocationSpanContext,
-        buf: Bytes,
+        mut buf: Bytes,
     ) -> TerminalLoopState<()> {
+        if let Some(decompressor) = &mut self.decompressor {
+            buf = shortcircuit!(decompressor
+                .decompress(&buf)
+                .map_err(InvocationTaskError::Compression));
+        }
         self.decoder.push(buf);
 
         while let Some((frame_header, frame)) = shortcircuit!(self.decoder.consume_next()) {
@@ -771,6 +1134,15 @@ where
             }
             ProtocolMessage::End(_) => TerminalLoopState::Closed,
             ProtocolMessage::UnparsedEntry(entry) => {
+                if !self.received_first_entry {
+                    self.received_first_entry = true;
+                    if let (Some(labels), Some(request_sent_at)) =
+                        (self.deployment_labels(), self.request_sent_at)
+                    {
+                        histogram!(INVOKER_TIME_TO_FIRST_ENTRY, labels)
+                            .record(request_sent_at.elapsed());
+                    }
+                }
                 let entry_type = entry.header().as_entry_type();
                 let enriched_entry = shortcircuit!(self
                     .entry_enricher
@@ -797,12 +1169,12 @@ where
         &mut self,
         path: PathAndQuery,
         deployment_metadata: DeploymentMetadata,
-    ) -> (Sender, Request<Body>) {
+    ) -> Result<(Sender, Request<Body>), InvocationTaskError> {
         let (http_stream_tx, req_body) = Body::channel();
 
         let mut headers = HeaderMap::from_iter([
-            (http::header::CONTENT_TYPE, APPLICATION_RESTATE),
-            (http::header::ACCEPT, APPLICATION_RESTATE),
+            (http::header::CONTENT_TYPE, self.content_type()),
+            (http::header::ACCEPT, self.content_type()),
         ]);
 
         // Inject OpenTelemetry context
@@ -819,23 +1191,105 @@ where
             DeploymentType::Http {
                 address,
                 protocol_type,
-            } => Endpoint::Http(
-                address,
-                match protocol_type {
-                    ProtocolType::RequestResponse => http::Version::default(),
-                    ProtocolType::BidiStream => http::Version::HTTP_2,
-                },
-            ),
+                content_encoding,
+            } => {
+                if let Some(content_encoding) = content_encoding {
+                    let hv = HeaderValue::from_static(content_encoding.as_str());
+                    headers.insert(http::header::CONTENT_ENCODING, hv.clone());
+                    headers.insert(http::header::ACCEPT_ENCODING, hv);
+                    self.compressor = Some(
+                        Compressor::new(content_encoding)
+                            .map_err(InvocationTaskError::Compression)?,
+                    );
+                }
+                Endpoint::Http(
+                    address,
+                    match protocol_type {
+                        ProtocolType::RequestResponse => http::Version::default(),
+                        ProtocolType::BidiStream => http::Version::HTTP_2,
+                    },
+                )
+            }
         };
 
         headers.extend(deployment_metadata.delivery_options.additional_headers);
 
-        (
+        Ok((
             http_stream_tx,
             Request::new(Parts::new(address, path, headers), req_body),
+        ))
+    }
+
+    /// Content type of the negotiated service protocol version.
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static(
+            self.service_protocol_version
+                .and_then(ServiceProtocolVersion::content_type)
+                .expect("the service protocol version is negotiated before sending the request"),
         )
     }
 
+    /// Keeps the entries read from storage for the journal cache, unless the journal doesn't fit
+    /// in the cache.
+    fn record_replayed_entry(&mut self, entry: &PlainRawEntry) {
+        let (Some(replayed_journal), Some(limit)) =
+            (&mut self.replayed_journal, self.journal_cache_limit)
+        else {
+            return;
+        };
+        if replayed_journal.entries.len() != self.next_journal_index as usize {
+            // The entries come from a cached journal, they are recorded already
+            return;
+        }
+
+        replayed_journal.size += entry.serialized_entry().len();
+        if replayed_journal.size > limit {
+            self.replayed_journal = None;
+        } else {
+            replayed_journal.entries.push(entry.clone());
+        }
+    }
+
+    /// Labels of the per deployment metrics, `None` until the deployment is selected.
+    fn deployment_labels(&self) -> Option<Vec<Label>> {
+        let deployment_id = self.selected_deployment?;
+        Some(vec![
+            Label::new("deployment", deployment_id.to_string()),
+            Label::new("service", self.invocation_target.service_name().to_string()),
+        ])
+    }
+
+    fn record_attempt_metrics(&self, terminal_state: &TerminalLoopState<()>) {
+        let Some(labels) = self.deployment_labels() else {
+            return;
+        };
+
+        if let TerminalLoopState::Failed(e) = terminal_state {
+            let class = match e.class() {
+                InvocationErrorClass::Retryable => "retryable",
+                InvocationErrorClass::Terminal => "terminal",
+                InvocationErrorClass::User => "user",
+            };
+            let mut error_labels = labels.clone();
+            error_labels.push(Label::new("class", class));
+            error_labels.push(Label::new("code", e.invocation_error_code().to_string()));
+            counter!(INVOKER_ATTEMPT_ERROR, error_labels).increment(1);
+        }
+
+        if let Some(request_sent_at) = self.request_sent_at {
+            let outcome = match terminal_state {
+                TerminalLoopState::Continue(_) => return,
+                TerminalLoopState::Closed => TASK_OP_COMPLETED,
+                TerminalLoopState::Suspended(_) => TASK_OP_SUSPENDED,
+                TerminalLoopState::DeploymentSaturated(_) => return,
+                TerminalLoopState::Failed(_) => TASK_OP_FAILED,
+            };
+            let mut labels = labels;
+            labels.push(Label::new("outcome", outcome));
+            histogram!(INVOKER_ATTEMPT_DURATION, labels).record(request_sent_at.elapsed());
+        }
+    }
+
     fn send_invoker_tx(&mut self, invocation_task_output_inner: InvocationTaskOutputInner) {
         let _ = self.invoker_tx.send(InvocationTaskOutput {
             partition: self.partition,
@@ -0,0 +1,170 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{BTreeMap, HashMap};
+
+use restate_invoker_api::JournalMetadata;
+use restate_types::identifiers::InvocationId;
+use restate_types::journal::raw::PlainRawEntry;
+
+#[derive(Debug)]
+struct CachedJournal {
+    metadata: JournalMetadata,
+    entries: Vec<PlainRawEntry>,
+    size: usize,
+    last_used: u64,
+}
+
+/// Journals replayed by the invocation tasks which failed, kept to replay them again on retry
+/// without reading them from storage. Bounded by the total size of the entries, evicting the
+/// least recently cached journals first.
+#[derive(Debug, Default)]
+pub(crate) struct JournalCache {
+    limit: usize,
+    size: usize,
+    journals: HashMap<InvocationId, CachedJournal>,
+    // Recency index, from the insertion counter to the cached journal
+    lru: BTreeMap<u64, InvocationId>,
+    next_use: u64,
+}
+
+impl JournalCache {
+    /// Creates the cache, disabled if `limit` is `None`.
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit: limit.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        invocation_id: InvocationId,
+        metadata: JournalMetadata,
+        entries: Vec<PlainRawEntry>,
+    ) {
+        self.remove(&invocation_id);
+
+        let size = journal_size(&entries);
+        if size > self.limit {
+            return;
+        }
+        while self.size + size > self.limit {
+            let (_, evicted) = self
+                .lru
+                .pop_first()
+                .expect("the cache can't be empty if it's full");
+            let journal = self
+                .journals
+                .remove(&evicted)
+                .expect("the recency index must be consistent with the cached journals");
+            self.size -= journal.size;
+        }
+
+        let last_used = self.next_use;
+        self.next_use += 1;
+        self.size += size;
+        self.lru.insert(last_used, invocation_id);
+        self.journals.insert(
+            invocation_id,
+            CachedJournal {
+                metadata,
+                entries,
+                size,
+                last_used,
+            },
+        );
+    }
+
+    /// Removes the journal from the cache, returning it if it was cached.
+    pub(crate) fn remove(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> Option<(JournalMetadata, Vec<PlainRawEntry>)> {
+        let journal = self.journals.remove(invocation_id)?;
+        self.lru.remove(&journal.last_used);
+        self.size -= journal.size;
+        Some((journal.metadata, journal.entries))
+    }
+}
+
+pub(crate) fn journal_size(entries: &[PlainRawEntry]) -> usize {
+    entries
+        .iter()
+        .map(|entry| entry.serialized_entry().len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::Bytes;
+    use restate_types::invocation::ServiceInvocationSpanContext;
+    use restate_types::journal::raw::EntryHeader;
+
+    fn journal(entry_size: usize) -> (JournalMetadata, Vec<PlainRawEntry>) {
+        (
+            JournalMetadata::new(1, ServiceInvocationSpanContext::empty(), None),
+            vec![PlainRawEntry::new(
+                EntryHeader::Output,
+                Bytes::from(vec![0; entry_size]),
+            )],
+        )
+    }
+
+    #[test]
+    fn evicts_least_recently_cached() {
+        let mut cache = JournalCache::new(Some(100));
+        let (first, second, third) = (
+            InvocationId::mock_random(),
+            InvocationId::mock_random(),
+            InvocationId::mock_random(),
+        );
+
+        let (metadata, entries) = journal(40);
+        cache.insert(first, metadata, entries);
+        let (metadata, entries) = journal(40);
+        cache.insert(second, metadata, entries);
+        let (metadata, entries) = journal(40);
+        cache.insert(third, metadata, entries);
+
+        assert!(cache.remove(&first).is_none());
+        assert!(cache.remove(&second).is_some());
+        assert!(cache.remove(&third).is_some());
+        assert_eq!(cache.size, 0);
+    }
+
+    #[test]
+    fn skips_journals_larger_than_the_cache() {
+        let mut cache = JournalCache::new(Some(100));
+        let invocation_id = InvocationId::mock_random();
+
+        let (metadata, entries) = journal(40);
+        cache.insert(invocation_id, metadata, entries);
+        let (metadata, entries) = journal(120);
+        cache.insert(invocation_id, metadata, entries);
+
+        // The previous version of the journal is not kept
+        assert!(cache.remove(&invocation_id).is_none());
+        assert_eq!(cache.size, 0);
+    }
+
+    #[test]
+    fn disabled_cache_is_empty() {
+        let mut cache = JournalCache::new(None);
+        let invocation_id = InvocationId::mock_random();
+
+        let (metadata, entries) = journal(1);
+        cache.insert(invocation_id, metadata, entries);
+
+        assert!(cache.remove(&invocation_id).is_none());
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use metrics::counter;
+use restate_invoker_api::InvokeInputJournal;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+use crate::metric_definitions::INVOKER_JOURNAL_MEMORY_BUDGET_EXHAUSTED;
+
+/// Bounds the bytes of journal entries the invocation tasks hold in memory to replay them,
+/// shared by all invocation tasks. `None` if the budget is unlimited.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct JournalMemoryBudget(Option<Arc<BudgetInner>>);
+
+#[derive(Debug)]
+struct BudgetInner {
+    semaphore: Arc<Semaphore>,
+    limit: usize,
+}
+
+impl JournalMemoryBudget {
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        Self(limit.map(|limit| {
+            let limit = limit.min(Semaphore::MAX_PERMITS);
+            Arc::new(BudgetInner {
+                semaphore: Arc::new(Semaphore::new(limit)),
+                limit,
+            })
+        }))
+    }
+
+    /// Reserves the memory of a cached journal. If it doesn't fit in the remaining budget, the
+    /// cached journal is dropped and the invocation task reads the journal from storage instead.
+    pub(crate) fn reserve_input_journal(
+        &self,
+        input_journal: InvokeInputJournal,
+    ) -> (InvokeInputJournal, Option<JournalMemoryLease>) {
+        let InvokeInputJournal::CachedJournal(_, entries) = &input_journal else {
+            return (input_journal, None);
+        };
+        let Some(inner) = &self.0 else {
+            return (input_journal, Some(JournalMemoryLease { _permit: None }));
+        };
+
+        let size = entries
+            .iter()
+            .map(|entry| entry.serialized_entry().len())
+            .sum();
+        match Arc::clone(&inner.semaphore).try_acquire_many_owned(inner.permits(size)) {
+            Ok(permit) => (
+                input_journal,
+                Some(JournalMemoryLease {
+                    _permit: Some(permit),
+                }),
+            ),
+            Err(_) => {
+                debug!(
+                    "Journal memory budget exhausted, dropping the cached journal of {} bytes",
+                    size
+                );
+                counter!(INVOKER_JOURNAL_MEMORY_BUDGET_EXHAUSTED).increment(1);
+                (InvokeInputJournal::NoCachedJournal, None)
+            }
+        }
+    }
+
+    /// Waits until `size` bytes are available in the budget.
+    pub(crate) async fn reserve(&self, size: usize) -> JournalMemoryLease {
+        let Some(inner) = &self.0 else {
+            return JournalMemoryLease { _permit: None };
+        };
+        let permit = Arc::clone(&inner.semaphore)
+            .acquire_many_owned(inner.permits(size))
+            .await
+            .expect("the journal memory budget semaphore is never closed");
+        JournalMemoryLease {
+            _permit: Some(permit),
+        }
+    }
+}
+
+impl BudgetInner {
+    fn permits(&self, size: usize) -> u32 {
+        // Larger reservations are capped to the whole budget, so that they can still proceed
+        // once the other reservations are released
+        u32::try_from(size.min(self.limit)).unwrap_or(u32::MAX)
+    }
+}
+
+/// Memory reserved from the [`JournalMemoryBudget`], released on drop.
+#[derive(Debug)]
+pub(crate) struct JournalMemoryLease {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::Bytes;
+    use restate_invoker_api::JournalMetadata;
+    use restate_types::invocation::ServiceInvocationSpanContext;
+    use restate_types::journal::raw::{EntryHeader, PlainRawEntry};
+
+    fn cached_journal(entry_size: usize) -> InvokeInputJournal {
+        InvokeInputJournal::CachedJournal(
+            JournalMetadata::new(1, ServiceInvocationSpanContext::empty(), None),
+            vec![PlainRawEntry::new(
+                EntryHeader::Output,
+                Bytes::from(vec![0; entry_size]),
+            )],
+        )
+    }
+
+    #[test]
+    fn falls_back_to_storage_when_exhausted() {
+        let budget = JournalMemoryBudget::new(Some(100));
+
+        let (journal, lease) = budget.reserve_input_journal(cached_journal(60));
+        assert!(matches!(journal, InvokeInputJournal::CachedJournal(..)));
+        assert!(lease.is_some());
+
+        let (journal, lease2) = budget.reserve_input_journal(cached_journal(60));
+        assert!(matches!(journal, InvokeInputJournal::NoCachedJournal));
+        assert!(lease2.is_none());
+
+        drop(lease);
+        let (journal, _) = budget.reserve_input_journal(cached_journal(60));
+        assert!(matches!(journal, InvokeInputJournal::CachedJournal(..)));
+    }
+
+    #[tokio::test]
+    async fn reserve_waits_for_released_memory() {
+        let budget = JournalMemoryBudget::new(Some(100));
+
+        let lease = budget.reserve(1000).await;
+        let mut reserve = std::pin::pin!(budget.reserve(10));
+        assert!(futures::poll!(reserve.as_mut()).is_pending());
+
+        drop(lease);
+        reserve.await;
+    }
+}
@@ -8,25 +8,37 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod backpressure;
+mod circuit_breaker;
+mod compression;
+mod health_check;
 mod input_command;
 mod invocation_state_machine;
 mod invocation_task;
+mod journal_cache;
+mod journal_memory_budget;
+mod local;
 mod metric_definitions;
 mod quota;
 mod state_machine_manager;
 mod status_store;
 
+use backpressure::DeploymentBackpressure;
+use circuit_breaker::CircuitBreaker;
 use futures::Stream;
+use health_check::{DeploymentsHealth, HealthChecker};
 use input_command::{InputCommand, InvokeCommand};
 use invocation_state_machine::InvocationStateMachine;
 use invocation_task::InvocationTask;
 use invocation_task::{InvocationTaskOutput, InvocationTaskOutputInner};
+use journal_cache::JournalCache;
+use journal_memory_budget::JournalMemoryBudget;
 use metrics::counter;
-use restate_core::cancellation_watcher;
+use restate_core::{cancellation_watcher, task_center, TaskKind};
 use restate_errors::warn_it;
 use restate_invoker_api::{
     Effect, EffectKind, EntryEnricher, InvocationErrorReport, InvocationStatusReport,
-    InvokeInputJournal, JournalReader, StateReader,
+    InvokeInputJournal, JournalMetadata, JournalReader, StateReader,
 };
 use restate_queue::SegmentQueue;
 use restate_schema_api::deployment::DeploymentResolver;
@@ -39,28 +51,33 @@ use restate_types::journal::enriched::EnrichedRawEntry;
 use restate_types::journal::raw::PlainRawEntry;
 use restate_types::journal::Completion;
 use restate_types::retries::RetryPolicy;
+use restate_types::time::MillisSinceEpoch;
 use status_store::InvocationStatusStore;
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::pin::Pin;
-use std::time::SystemTime;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use std::{cmp, panic};
 use tokio::sync::mpsc;
 use tokio::task::{AbortHandle, JoinSet};
 use tracing::instrument;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use crate::invocation_task::InvocationTaskError;
+pub use health_check::HealthCheckReader;
 pub use input_command::ChannelStatusReader;
 pub use input_command::InvokerHandle;
+pub use local::{LocalInvoker, LocalRequest, LocalServices};
 use restate_service_client::{AssumeRoleCacheMode, ServiceClient};
 use restate_service_protocol::RESTATE_SERVICE_PROTOCOL_VERSION;
 use restate_types::invocation::InvocationTarget;
 
 use crate::metric_definitions::{
-    INVOKER_ENQUEUE, INVOKER_INVOCATION_TASK, TASK_OP_COMPLETED, TASK_OP_FAILED, TASK_OP_STARTED,
+    INVOKER_DEPLOYMENT_QUOTA_SATURATED, INVOKER_ENQUEUE, INVOKER_INVOCATION_TASK,
+    INVOKER_JOURNAL_CACHE_HIT, INVOKER_RETRY, TASK_OP_COMPLETED, TASK_OP_FAILED, TASK_OP_STARTED,
     TASK_OP_SUSPENDED,
 };
 
@@ -86,6 +103,15 @@ trait InvocationTaskRunner<SR> {
         input_journal: InvokeInputJournal,
         task_pool: &mut JoinSet<()>,
     ) -> AbortHandle;
+
+    /// Retry policy for the invocations of the given target.
+    fn retry_policy(
+        &self,
+        options: &InvokerOptions,
+        _invocation_target: &InvocationTarget,
+    ) -> RetryPolicy {
+        options.retry_policy.clone()
+    }
 }
 
 #[derive(Debug)]
@@ -93,6 +119,11 @@ struct DefaultInvocationTaskRunner<EE, DMR> {
     client: ServiceClient,
     entry_enricher: EE,
     deployment_metadata_resolver: DMR,
+    circuit_breaker: Arc<CircuitBreaker>,
+    backpressure: Arc<DeploymentBackpressure>,
+    deployment_health: Arc<DeploymentsHealth>,
+    journal_memory_budget: JournalMemoryBudget,
+    journal_cache_size: Option<usize>,
 }
 
 impl<SR, EE, DMR> InvocationTaskRunner<SR> for DefaultInvocationTaskRunner<EE, DMR>
@@ -115,6 +146,9 @@ where
         input_journal: InvokeInputJournal,
         task_pool: &mut JoinSet<()>,
     ) -> AbortHandle {
+        let service_name = invocation_target.service_name();
+        let inactivity_timeout = opts.service_inactivity_timeout(service_name);
+        let abort_timeout = opts.service_abort_timeout(service_name);
         task_pool.spawn(
             InvocationTask::new(
                 self.client.clone(),
@@ -122,8 +156,8 @@ where
                 invocation_id,
                 invocation_target,
                 RESTATE_SERVICE_PROTOCOL_VERSION,
-                opts.inactivity_timeout.into(),
-                opts.abort_timeout.into(),
+                inactivity_timeout,
+                abort_timeout,
                 opts.disable_eager_state,
                 opts.message_size_warning.get(),
                 opts.message_size_limit(),
@@ -131,12 +165,33 @@ where
                 storage_reader,
                 self.entry_enricher.clone(),
                 self.deployment_metadata_resolver.clone(),
+                self.circuit_breaker.handle(
+                    opts.circuit_breaker_failure_threshold(),
+                    opts.circuit_breaker_open_duration.into(),
+                ),
+                Arc::clone(&self.backpressure),
+                opts.deployment_concurrency_limit(),
+                Arc::clone(&self.deployment_health),
+                self.journal_memory_budget.clone(),
+                self.journal_cache_size,
                 invoker_tx,
                 invoker_rx,
             )
             .run(input_journal),
         )
     }
+
+    /// The latest deployment of the service can override the retry policy of the invoker.
+    fn retry_policy(
+        &self,
+        options: &InvokerOptions,
+        invocation_target: &InvocationTarget,
+    ) -> RetryPolicy {
+        self.deployment_metadata_resolver
+            .resolve_latest_deployment_for_service(invocation_target.service_name())
+            .and_then(|deployment| deployment.metadata.delivery_options.retry_policy)
+            .unwrap_or_else(|| options.retry_policy.clone())
+    }
 }
 
 // -- Service implementation
@@ -189,10 +244,20 @@ impl<SR, EE, DMR> Service<SR, EE, DMR> {
                     client,
                     entry_enricher,
                     deployment_metadata_resolver,
+                    circuit_breaker: Default::default(),
+                    backpressure: Default::default(),
+                    deployment_health: Default::default(),
+                    journal_memory_budget: JournalMemoryBudget::new(
+                        options.journal_memory_budget(),
+                    ),
+                    journal_cache_size: options.journal_cache_size(),
                 },
                 invocation_tasks: Default::default(),
                 retry_timers: Default::default(),
+                drain_timers: Default::default(),
                 quota: quota::InvokerConcurrencyQuota::new(options.concurrent_invocations_limit()),
+                service_quota: Default::default(),
+                journal_cache: JournalCache::new(options.journal_cache_size()),
                 status_store: Default::default(),
                 invocation_state_machine_manager: Default::default(),
             },
@@ -248,6 +313,12 @@ where
         ChannelStatusReader(self.status_tx.clone())
     }
 
+    pub fn health_check_reader(&self) -> HealthCheckReader {
+        HealthCheckReader(Arc::clone(
+            &self.inner.invocation_task_runner.deployment_health,
+        ))
+    }
+
     pub async fn run(
         self,
         mut updateable_options: impl Updateable<InvokerOptions> + Send + 'static,
@@ -261,6 +332,31 @@ where
         let shutdown = cancellation_watcher();
         tokio::pin!(shutdown);
 
+        let options = updateable_options.load();
+        if let Some(interval) = options.health_check_interval() {
+            let runner = &service.invocation_task_runner;
+            match HealthChecker::new(
+                runner.client.clone(),
+                runner.deployment_metadata_resolver.clone(),
+                Arc::clone(&runner.deployment_health),
+                &options.health_check_path,
+                interval,
+            ) {
+                Ok(health_checker) => {
+                    task_center().spawn_child(
+                        TaskKind::SystemService,
+                        "invoker-health-checker",
+                        None,
+                        health_checker.run(),
+                    )?;
+                }
+                Err(e) => warn!(
+                    "Health checks are disabled, invalid health check path '{}': {}",
+                    options.health_check_path, e
+                ),
+            }
+        }
+
         // Prepare the segmented queue
         let mut segmented_input_queue = SegmentQueue::init(tmp_dir, 1_056_784)
             .await
@@ -302,7 +398,10 @@ struct ServiceInner<InvocationTaskRunner, SR> {
     // Invoker state machine
     invocation_tasks: JoinSet<()>,
     retry_timers: TimerQueue<(PartitionLeaderEpoch, InvocationId)>,
+    drain_timers: TimerQueue<PartitionLeaderEpoch>,
     quota: quota::InvokerConcurrencyQuota,
+    service_quota: quota::ServiceConcurrencyQuota,
+    journal_cache: JournalCache,
     status_store: InvocationStatusStore,
     invocation_state_machine_manager: state_machine_manager::InvocationStateMachineManager<SR>,
 }
@@ -350,10 +449,14 @@ where
                     },
                     InputCommand::Abort { partition, invocation_id } => {
                         self.handle_abort_invocation(partition, invocation_id);
+                        self.finish_drain_if_done(partition);
                     }
                     InputCommand::AbortAllPartition { partition } => {
                         self.handle_abort_partition(partition);
                     }
+                    InputCommand::DrainPartition { partition, grace_period } => {
+                        self.handle_drain_partition(partition, grace_period);
+                    }
                     InputCommand::Completion { partition, invocation_id, completion } => {
                         self.handle_completion(partition, invocation_id, completion);
                     },
@@ -364,6 +467,20 @@ where
             },
 
             Some(invoke_input_command) = segmented_input_queue.dequeue(), if !segmented_input_queue.is_empty() && self.quota.is_slot_available() => {
+                if self.invocation_state_machine_manager.is_draining(invoke_input_command.partition) {
+                    trace!(
+                        restate.invocation.id = %invoke_input_command.invocation_id,
+                        "Not starting the invocation because its partition is draining"
+                    );
+                    return true;
+                }
+                let limit = options.service_concurrency_limit(invoke_input_command.invocation_target.service_name());
+                if let Some(invoke_input_command) = self.service_quota.reserve_slot_or_park(invoke_input_command, limit) {
+                    self.handle_invoke(options, invoke_input_command.partition, invoke_input_command.invocation_id, invoke_input_command.invocation_target, invoke_input_command.journal).await;
+                }
+            },
+
+            Some(invoke_input_command) = self.service_quota.next_runnable(), if self.service_quota.has_runnable() && self.quota.is_slot_available() => {
                 self.handle_invoke(options, invoke_input_command.partition, invoke_input_command.invocation_id, invoke_input_command.invocation_target, invoke_input_command.journal).await;
             },
 
@@ -401,18 +518,28 @@ where
                     InvocationTaskOutputInner::Closed => {
                         self.handle_invocation_task_closed(partition, invocation_id).await
                     },
+                    InvocationTaskOutputInner::ReplayedJournal(journal_metadata, entries) => {
+                        self.handle_replayed_journal(partition, invocation_id, journal_metadata, entries)
+                    },
                     InvocationTaskOutputInner::Failed(e) => {
                         self.handle_invocation_task_failed(partition, invocation_id, e).await
                     },
                     InvocationTaskOutputInner::Suspended(indexes) => {
                         self.handle_invocation_task_suspended(partition, invocation_id, indexes).await
                     }
+                    InvocationTaskOutputInner::DeploymentSaturated(deployment_id) => {
+                        self.handle_invocation_task_saturated(options, partition, invocation_id, deployment_id).await
+                    }
                 };
+                self.finish_drain_if_done(partition);
             },
             timer = self.retry_timers.await_timer() => {
                 let (partition, fid) = timer.into_inner();
                 self.handle_retry_timer_fired(options, partition, fid).await;
             },
+            timer = self.drain_timers.await_timer() => {
+                self.handle_drain_timer_fired(timer.into_inner());
+            },
             Some(invocation_task_result) = self.invocation_tasks.join_next() => {
                 if let Err(err) = invocation_task_result {
                     // Propagate panics coming from invocation tasks.
@@ -489,13 +616,16 @@ where
             .partition_storage_reader(partition)
             .expect("partition is registered");
         self.quota.reserve_slot();
+        let retry_policy = self
+            .invocation_task_runner
+            .retry_policy(options, &invocation_target);
         self.start_invocation_task(
             options,
             partition,
             storage_reader.clone(),
             invocation_id,
             journal,
-            InvocationStateMachine::create(invocation_target, options.retry_policy.clone()),
+            InvocationStateMachine::create(invocation_target, retry_policy),
         )
         .await
     }
@@ -682,6 +812,8 @@ where
         invocation_id: InvocationId,
         completion: Completion,
     ) {
+        // The cached journal misses the completion
+        self.journal_cache.remove(&invocation_id);
         if let Some((_, ism)) = self
             .invocation_state_machine_manager
             .resolve_invocation(partition, &invocation_id)
@@ -706,6 +838,37 @@ where
             restate.invoker.partition_leader_epoch = ?partition,
         )
     )]
+    fn handle_replayed_journal(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+        journal_metadata: JournalMetadata,
+        entries: Vec<PlainRawEntry>,
+    ) {
+        if let Some((_, ism)) = self
+            .invocation_state_machine_manager
+            .resolve_invocation(partition, &invocation_id)
+        {
+            // The entries and completions added meanwhile are only in storage
+            if ism.is_journal_unchanged() {
+                trace!("Caching the replayed journal for the retry");
+                self.journal_cache
+                    .insert(invocation_id, journal_metadata, entries);
+            }
+        } else {
+            // If no state machine, this might be a result for an aborted invocation.
+            trace!("No state machine found for replayed journal");
+        }
+    }
+
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invocation.id = %invocation_id,
+            restate.invoker.partition_leader_epoch = ?partition,
+        )
+    )]
     async fn handle_invocation_task_closed(
         &mut self,
         partition: PartitionLeaderEpoch,
@@ -719,7 +882,6 @@ where
             trace!(
                 restate.invocation.target = %ism.invocation_target,
                 "Invocation task closed correctly");
-            self.quota.unreserve_slot();
             self.status_store.on_end(&partition, &invocation_id);
             let _ = sender
                 .send(Effect {
@@ -727,6 +889,7 @@ where
                     kind: EffectKind::End,
                 })
                 .await;
+            self.release_slots(&ism);
         } else {
             // If no state machine, this might be a result for an aborted invocation.
             trace!("No state machine found for invocation task closed signal");
@@ -755,7 +918,6 @@ where
             trace!(
                 restate.invocation.target = %ism.invocation_target,
                 "Suspending invocation");
-            self.quota.unreserve_slot();
             self.status_store.on_end(&partition, &invocation_id);
             let _ = sender
                 .send(Effect {
@@ -765,12 +927,56 @@ where
                     },
                 })
                 .await;
+            self.release_slots(&ism);
         } else {
             // If no state machine, this might be a result for an aborted invocation.
             trace!("No state machine found for invocation task suspended signal");
         }
     }
 
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invocation.id = %invocation_id,
+            restate.invoker.partition_leader_epoch = ?partition,
+            restate.deployment.id = %deployment_id,
+        )
+    )]
+    async fn handle_invocation_task_saturated(
+        &mut self,
+        options: &InvokerOptions,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+        deployment_id: DeploymentId,
+    ) {
+        if let Some((sender, _, ism)) = self
+            .invocation_state_machine_manager
+            .remove_invocation(partition, &invocation_id)
+        {
+            counter!(INVOKER_DEPLOYMENT_QUOTA_SATURATED).increment(1);
+            trace!(
+                restate.invocation.target = %ism.invocation_target,
+                "Deployment reached its concurrency limit, handing the invocation back to the partition");
+            self.journal_cache.remove(&invocation_id);
+            self.status_store.on_end(&partition, &invocation_id);
+            let retry_interval: Duration = options.deployment_saturation_retry_interval.into();
+            let _ = sender
+                .send(Effect {
+                    invocation_id,
+                    kind: EffectKind::DeploymentSaturated {
+                        deployment_id,
+                        retry_at: MillisSinceEpoch::from(SystemTime::now() + retry_interval),
+                    },
+                })
+                .await;
+            self.release_slots(&ism);
+        } else {
+            // If no state machine, this might be a result for an aborted invocation.
+            trace!("No state machine found for invocation task saturated signal");
+        }
+    }
+
     #[instrument(
         level = "debug",
         skip_all,
@@ -818,8 +1024,14 @@ where
                 restate.invocation.target = %ism.invocation_target,
                 "Aborting invocation");
             ism.abort();
-            self.quota.unreserve_slot();
+            self.release_slots(&ism);
+            self.journal_cache.remove(&invocation_id);
             self.status_store.on_end(&partition, &invocation_id);
+        } else if self
+            .service_quota
+            .remove_invocation(partition, &invocation_id)
+        {
+            trace!("Aborting invocation waiting for a service concurrency slot");
         } else {
             trace!("Ignoring Abort command because there is no matching partition/invocation");
         }
@@ -833,6 +1045,7 @@ where
         )
     )]
     fn handle_abort_partition(&mut self, partition: PartitionLeaderEpoch) {
+        self.remove_parked_invocations(partition);
         if let Some(invocation_state_machines) = self
             .invocation_state_machine_manager
             .remove_partition(partition)
@@ -844,7 +1057,8 @@ where
                     "Aborting invocation"
                 );
                 ism.abort();
-                self.quota.unreserve_slot();
+                self.release_slots(&ism);
+                self.journal_cache.remove(&fid);
                 self.status_store.on_end(&partition, &fid);
             }
         } else {
@@ -852,6 +1066,58 @@ where
         }
     }
 
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invoker.partition_leader_epoch = ?partition,
+        )
+    )]
+    fn handle_drain_partition(&mut self, partition: PartitionLeaderEpoch, grace_period: Duration) {
+        // Invocations which didn't start yet are left to the next leader
+        self.remove_parked_invocations(partition);
+        let Some(not_in_flight) = self
+            .invocation_state_machine_manager
+            .start_draining(partition)
+        else {
+            trace!("Ignoring DrainPartition command because there is no matching partition");
+            return;
+        };
+        for (fid, mut ism) in not_in_flight {
+            trace!(
+                restate.invocation.id = %fid,
+                restate.invocation.target = %ism.invocation_target,
+                "Aborting invocation waiting for a retry"
+            );
+            ism.abort();
+            self.release_slots(&ism);
+            self.journal_cache.remove(&fid);
+            self.status_store.on_end(&partition, &fid);
+        }
+
+        debug!(
+            "Draining partition, waiting up to {} for the in-flight invocations",
+            humantime::format_duration(grace_period)
+        );
+        self.drain_timers
+            .sleep_until(SystemTime::now() + grace_period, partition);
+        self.finish_drain_if_done(partition);
+    }
+
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invoker.partition_leader_epoch = ?partition,
+        )
+    )]
+    fn handle_drain_timer_fired(&mut self, partition: PartitionLeaderEpoch) {
+        if self.invocation_state_machine_manager.is_draining(partition) {
+            debug!("Drain grace period expired, aborting the remaining invocations");
+            self.handle_abort_partition(partition);
+        }
+    }
+
     #[instrument(level = "trace", skip_all)]
     fn handle_shutdown(&mut self) {
         let partitions = self
@@ -864,6 +1130,26 @@ where
 
     // --- Helpers
 
+    fn remove_parked_invocations(&mut self, partition: PartitionLeaderEpoch) {
+        self.service_quota.remove_partition(partition);
+    }
+
+    /// Cleans up a draining partition once its last invocation is gone.
+    fn finish_drain_if_done(&mut self, partition: PartitionLeaderEpoch) {
+        if self.invocation_state_machine_manager.is_drained(partition) {
+            debug!(
+                restate.invoker.partition_leader_epoch = ?partition,
+                "Partition drained"
+            );
+            self.handle_abort_partition(partition);
+        }
+    }
+
+    fn release_slots(&mut self, ism: &InvocationStateMachine) {
+        self.quota.unreserve_slot();
+        self.service_quota.unreserve_slot(&ism.invocation_target);
+    }
+
     async fn handle_error_event(
         &mut self,
         partition: PartitionLeaderEpoch,
@@ -872,7 +1158,28 @@ where
         mut ism: InvocationStateMachine,
     ) {
         match ism.handle_task_error() {
+            Some(_)
+                if error.is_transient()
+                    && self.invocation_state_machine_manager.is_draining(partition) =>
+            {
+                counter!(INVOKER_INVOCATION_TASK,
+                    "status" => TASK_OP_FAILED,
+                    "transient" => "true"
+                )
+                .increment(1);
+                warn_it!(
+                    error,
+                    restate.invocation.id = %invocation_id,
+                    restate.invocation.target = %ism.invocation_target,
+                    "Error when executing the invocation, not retrying because the partition is draining.");
+                self.release_slots(&ism);
+                self.journal_cache.remove(&invocation_id);
+                self.status_store.on_end(&partition, &invocation_id);
+            }
             Some(next_retry_timer_duration) if error.is_transient() => {
+                // The deployment asked to wait a specific amount of time
+                let next_retry_timer_duration =
+                    error.retry_after().unwrap_or(next_retry_timer_duration);
                 counter!(INVOKER_INVOCATION_TASK,
                     "status" => TASK_OP_FAILED,
                     "transient" => "true"
@@ -885,6 +1192,16 @@ where
                     "Error when executing the invocation, retrying in {}.",
                     humantime::format_duration(next_retry_timer_duration));
                 trace!("Invocation state: {:?}.", ism.invocation_state_debug());
+                if let Some(deployment_id) = self
+                    .status_store
+                    .last_attempt_deployment_id(&partition, &invocation_id)
+                {
+                    counter!(INVOKER_RETRY,
+                        "deployment" => deployment_id.to_string(),
+                        "service" => ism.invocation_target.service_name().to_string()
+                    )
+                    .increment(1);
+                }
                 let next_retry_at = SystemTime::now() + next_retry_timer_duration;
 
                 self.status_store.on_failure(
@@ -912,7 +1229,8 @@ where
                     restate.invocation.id = %invocation_id,
                     restate.invocation.target = %ism.invocation_target,
                     "Error when executing the invocation, not going to retry.");
-                self.quota.unreserve_slot();
+                self.release_slots(&ism);
+                self.journal_cache.remove(&invocation_id);
                 self.status_store.on_end(&partition, &invocation_id);
 
                 let _ = self
@@ -921,7 +1239,7 @@ where
                     .expect("Partition should be registered")
                     .send(Effect {
                         invocation_id,
-                        kind: EffectKind::Failed(error.into_invocation_error()),
+                        kind: EffectKind::failed(error.into_invocation_error()),
                     })
                     .await;
             }
@@ -983,12 +1301,19 @@ where
                     restate.invocation.target = %ism.invocation_target,
                     "Going to retry now");
                 let storage_reader = storage_reader.clone();
+                let journal = match self.journal_cache.remove(&invocation_id) {
+                    Some((journal_metadata, entries)) => {
+                        counter!(INVOKER_JOURNAL_CACHE_HIT).increment(1);
+                        InvokeInputJournal::CachedJournal(journal_metadata, entries)
+                    }
+                    None => InvokeInputJournal::NoCachedJournal,
+                };
                 self.start_invocation_task(
                     options,
                     partition,
                     storage_reader,
                     invocation_id,
-                    InvokeInputJournal::NoCachedJournal,
+                    journal,
                     ism,
                 )
                 .await;
@@ -1033,10 +1358,14 @@ mod tests {
 
     use restate_invoker_api::{entry_enricher, ServiceHandle};
     use restate_schema_api::deployment::mocks::MockDeploymentMetadataRegistry;
+    use restate_schema_api::deployment::Deployment;
     use restate_test_util::{check, let_assert};
+    use restate_types::errors::codes;
     use restate_types::identifiers::{LeaderEpoch, PartitionId};
+    use restate_types::invocation::ServiceInvocationSpanContext;
     use restate_types::journal::enriched::EnrichedEntryHeader;
-    use restate_types::journal::raw::RawEntry;
+    use restate_types::journal::raw::{EntryHeader, RawEntry};
+    use restate_types::journal::CompletionResult;
     use restate_types::retries::RetryPolicy;
 
     use crate::invocation_task::InvocationTaskError;
@@ -1078,7 +1407,10 @@ mod tests {
                 invocation_task_runner,
                 invocation_tasks: Default::default(),
                 retry_timers: Default::default(),
+                drain_timers: Default::default(),
                 quota: InvokerConcurrencyQuota::new(concurrency_limit),
+                service_quota: Default::default(),
+                journal_cache: Default::default(),
                 status_store: Default::default(),
                 invocation_state_machine_manager: Default::default(),
             };
@@ -1306,6 +1638,429 @@ mod tests {
         assert!(!service_inner.quota.is_slot_available());
     }
 
+    #[test(tokio::test)]
+    async fn service_quota_parks_invocations_of_the_same_service() {
+        let invoker_options = InvokerOptionsBuilder::default()
+            .service_concurrency_limits(HashMap::from([(
+                "Greeter".to_owned(),
+                NonZeroUsize::new(1).unwrap(),
+            )]))
+            .build()
+            .unwrap();
+
+        let mut segment_queue = SegmentQueue::new(tempdir().unwrap().into_path(), 1024);
+        let cancel_token = CancellationToken::new();
+        let shutdown = cancel_token.cancelled();
+        tokio::pin!(shutdown);
+
+        let invocation_id_1 = InvocationId::mock_random();
+        let invocation_id_2 = InvocationId::mock_random();
+        let invocation_id_3 = InvocationId::mock_random();
+
+        // Invocation tasks never complete, so every step dequeues an invocation
+        let (_invoker_tx, _status_tx, mut service_inner) =
+            ServiceInner::mock(|_, _, _, _, _, _, _| pending(), None);
+        let _ = service_inner.register_mock_partition(EmptyStorageReader);
+
+        for (invocation_id, service_name) in [
+            (invocation_id_1, "Greeter"),
+            (invocation_id_2, "Greeter"),
+            (invocation_id_3, "Counter"),
+        ] {
+            segment_queue
+                .enqueue(InvokeCommand {
+                    partition: MOCK_PARTITION,
+                    invocation_id,
+                    invocation_target: InvocationTarget::service(service_name, "greet"),
+                    journal: InvokeInputJournal::NoCachedJournal,
+                })
+                .await;
+        }
+        for _ in 0..3 {
+            assert!(
+                service_inner
+                    .step(&invoker_options, &mut segment_queue, shutdown.as_mut())
+                    .await
+            );
+        }
+
+        // The second Greeter invocation waits, while the Counter invocation runs
+        assert!(service_inner
+            .status_store
+            .resolve_invocation(MOCK_PARTITION, &invocation_id_1)
+            .unwrap()
+            .in_flight());
+        assert!(service_inner
+            .status_store
+            .resolve_invocation(MOCK_PARTITION, &invocation_id_2)
+            .is_none());
+        assert!(service_inner
+            .status_store
+            .resolve_invocation(MOCK_PARTITION, &invocation_id_3)
+            .unwrap()
+            .in_flight());
+
+        // Ending the first Greeter invocation hands its slot over to the second one
+        service_inner
+            .handle_invocation_task_closed(MOCK_PARTITION, invocation_id_1)
+            .await;
+        assert!(service_inner.service_quota.has_runnable());
+        assert!(
+            service_inner
+                .step(&invoker_options, &mut segment_queue, shutdown.as_mut())
+                .await
+        );
+        assert!(service_inner
+            .status_store
+            .resolve_invocation(MOCK_PARTITION, &invocation_id_2)
+            .unwrap()
+            .in_flight());
+        assert!(!service_inner.service_quota.has_runnable());
+    }
+
+    #[test(tokio::test)]
+    async fn saturated_deployment_hands_the_invocation_back_to_the_partition() {
+        let invoker_options = InvokerOptionsBuilder::default()
+            .deployment_saturation_retry_interval(Duration::from_secs(60).into())
+            .build()
+            .unwrap();
+        let invocation_id = InvocationId::mock_random();
+        let deployment_id = DeploymentId::new();
+
+        let (_invoker_tx, _status_tx, mut service_inner) =
+            ServiceInner::mock(|_, _, _, _, _, _, _| pending(), Some(1));
+        let mut effects_rx = service_inner.register_mock_partition(EmptyStorageReader);
+
+        service_inner
+            .handle_invoke(
+                &invoker_options,
+                MOCK_PARTITION,
+                invocation_id,
+                InvocationTarget::mock_service(),
+                InvokeInputJournal::NoCachedJournal,
+            )
+            .await;
+        assert!(!service_inner.quota.is_slot_available());
+
+        let saturated_at = MillisSinceEpoch::now();
+        service_inner
+            .handle_invocation_task_saturated(
+                &invoker_options,
+                MOCK_PARTITION,
+                invocation_id,
+                deployment_id,
+            )
+            .await;
+
+        // The invoker doesn't keep the invocation, the partition invokes it again later
+        assert!(service_inner.quota.is_slot_available());
+        assert!(service_inner
+            .status_store
+            .resolve_invocation(MOCK_PARTITION, &invocation_id)
+            .is_none());
+        assert!(service_inner
+            .invocation_state_machine_manager
+            .resolve_invocation(MOCK_PARTITION, &invocation_id)
+            .is_none());
+
+        let effect = effects_rx.recv().await.unwrap();
+        assert_eq!(effect.invocation_id, invocation_id);
+        let_assert!(
+            EffectKind::DeploymentSaturated {
+                deployment_id: saturated_deployment_id,
+                retry_at
+            } = effect.kind
+        );
+        assert_eq!(saturated_deployment_id, deployment_id);
+        assert!(retry_at.as_u64() >= saturated_at.as_u64() + 60_000);
+    }
+
+    #[test(tokio::test)]
+    async fn drain_partition_waits_for_in_flight_invocations() {
+        let invoker_options = InvokerOptionsBuilder::default().build().unwrap();
+
+        let mut segment_queue = SegmentQueue::new(tempdir().unwrap().into_path(), 1024);
+        let cancel_token = CancellationToken::new();
+        let shutdown = cancel_token.cancelled();
+        tokio::pin!(shutdown);
+
+        let invocation_id_1 = InvocationId::mock_random();
+        let invocation_id_2 = InvocationId::mock_random();
+        let invocation_id_3 = InvocationId::mock_random();
+
+        let (_invoker_tx, _status_tx, mut service_inner) =
+            ServiceInner::mock(|_, _, _, _, _, _, _| pending::<()>(), None);
+        let mut partition_rx = service_inner.register_mock_partition(EmptyStorageReader);
+
+        for invocation_id in [invocation_id_1, invocation_id_2] {
+            segment_queue
+                .enqueue(InvokeCommand {
+                    partition: MOCK_PARTITION,
+                    invocation_id,
+                    invocation_target: InvocationTarget::mock_virtual_object(),
+                    journal: InvokeInputJournal::NoCachedJournal,
+                })
+                .await;
+            assert!(
+                service_inner
+                    .step(&invoker_options, &mut segment_queue, shutdown.as_mut())
+                    .await
+            );
+        }
+
+        service_inner.handle_drain_partition(MOCK_PARTITION, Duration::from_secs(60));
+
+        // New invocations are not started while draining
+        segment_queue
+            .enqueue(InvokeCommand {
+                partition: MOCK_PARTITION,
+                invocation_id: invocation_id_3,
+                invocation_target: InvocationTarget::mock_virtual_object(),
+                journal: InvokeInputJournal::NoCachedJournal,
+            })
+            .await;
+        assert!(
+            service_inner
+                .step(&invoker_options, &mut segment_queue, shutdown.as_mut())
+                .await
+        );
+        assert!(service_inner
+            .status_store
+            .resolve_invocation(MOCK_PARTITION, &invocation_id_3)
+            .is_none());
+
+        // In-flight invocations can still complete
+        service_inner
+            .handle_invocation_task_closed(MOCK_PARTITION, invocation_id_1)
+            .await;
+        service_inner.finish_drain_if_done(MOCK_PARTITION);
+        let effect = partition_rx.recv().await.unwrap();
+        assert_eq!(effect.invocation_id, invocation_id_1);
+        assert!(service_inner
+            .invocation_state_machine_manager
+            .has_partition(MOCK_PARTITION));
+
+        // The partition goes away with its last invocation
+        service_inner
+            .handle_invocation_task_closed(MOCK_PARTITION, invocation_id_2)
+            .await;
+        service_inner.finish_drain_if_done(MOCK_PARTITION);
+        assert!(!service_inner
+            .invocation_state_machine_manager
+            .has_partition(MOCK_PARTITION));
+    }
+
+    #[test(tokio::test)]
+    async fn drain_partition_aborts_after_grace_period() {
+        let invoker_options = InvokerOptionsBuilder::default().build().unwrap();
+
+        let mut segment_queue = SegmentQueue::new(tempdir().unwrap().into_path(), 1024);
+        let cancel_token = CancellationToken::new();
+        let shutdown = cancel_token.cancelled();
+        tokio::pin!(shutdown);
+
+        let invocation_id = InvocationId::mock_random();
+
+        let (_invoker_tx, _status_tx, mut service_inner) =
+            ServiceInner::mock(|_, _, _, _, _, _, _| pending::<()>(), None);
+        let _ = service_inner.register_mock_partition(EmptyStorageReader);
+
+        segment_queue
+            .enqueue(InvokeCommand {
+                partition: MOCK_PARTITION,
+                invocation_id,
+                invocation_target: InvocationTarget::mock_virtual_object(),
+                journal: InvokeInputJournal::NoCachedJournal,
+            })
+            .await;
+        assert!(
+            service_inner
+                .step(&invoker_options, &mut segment_queue, shutdown.as_mut())
+                .await
+        );
+
+        service_inner.handle_drain_partition(MOCK_PARTITION, Duration::ZERO);
+        assert!(service_inner
+            .invocation_state_machine_manager
+            .has_partition(MOCK_PARTITION));
+
+        // Step to fire the drain timer
+        assert!(
+            service_inner
+                .step(&invoker_options, &mut segment_queue, shutdown.as_mut())
+                .await
+        );
+        assert!(!service_inner
+            .invocation_state_machine_manager
+            .has_partition(MOCK_PARTITION));
+        assert!(service_inner
+            .status_store
+            .resolve_invocation(MOCK_PARTITION, &invocation_id)
+            .is_none());
+    }
+
+    #[test(tokio::test)]
+    async fn deployment_retry_policy_overrides_invoker_retry_policy() {
+        let invoker_options = InvokerOptionsBuilder::default()
+            .retry_policy(RetryPolicy::fixed_delay(Duration::ZERO, Some(1)))
+            .build()
+            .unwrap();
+
+        let mut deployment = Deployment::mock_with_uri("http://localhost:9081");
+        deployment.metadata.delivery_options.retry_policy = Some(RetryPolicy::exponential(
+            Duration::from_millis(100),
+            2.0,
+            Some(10),
+            Some(Duration::from_secs(10)),
+        ));
+        let mut deployment_registry = MockDeploymentMetadataRegistry::default();
+        deployment_registry.mock_service_with_metadata("Greeter", deployment);
+        deployment_registry.mock_service("Counter");
+
+        let invocation_task_runner = DefaultInvocationTaskRunner {
+            client: ServiceClient::from_options(
+                &ServiceClientOptions::default(),
+                AssumeRoleCacheMode::None,
+            )
+            .unwrap(),
+            entry_enricher: entry_enricher::mocks::MockEntryEnricher,
+            deployment_metadata_resolver: deployment_registry,
+            circuit_breaker: Default::default(),
+            backpressure: Default::default(),
+            deployment_health: Default::default(),
+            journal_memory_budget: Default::default(),
+            journal_cache_size: None,
+        };
+        let retry_policy = |service_name| {
+            InvocationTaskRunner::<EmptyStorageReader>::retry_policy(
+                &invocation_task_runner,
+                &invoker_options,
+                &InvocationTarget::service(service_name, "greet"),
+            )
+        };
+
+        check!(let RetryPolicy::Exponential { .. } = retry_policy("Greeter"));
+        check!(let RetryPolicy::FixedDelay { .. } = retry_policy("Counter"));
+    }
+
+    #[test(tokio::test)]
+    async fn terminal_error_is_not_retried() {
+        let invoker_options = InvokerOptionsBuilder::default()
+            .retry_policy(RetryPolicy::fixed_delay(Duration::ZERO, Some(10)))
+            .build()
+            .unwrap();
+        let invocation_id = InvocationId::mock_random();
+
+        let (_, _status_tx, mut service_inner) =
+            ServiceInner::mock(|_, _, _, _, _, _, _| pending(), None);
+        let mut partition_rx = service_inner.register_mock_partition(EmptyStorageReader);
+
+        service_inner
+            .handle_invoke(
+                &invoker_options,
+                MOCK_PARTITION,
+                invocation_id,
+                InvocationTarget::mock_virtual_object(),
+                InvokeInputJournal::NoCachedJournal,
+            )
+            .await;
+
+        service_inner
+            .handle_invocation_task_failed(
+                MOCK_PARTITION,
+                invocation_id,
+                InvocationTaskError::UnknownDeployment(DeploymentId::new()),
+            )
+            .await;
+
+        // The invocation fails right away, although retries are left
+        let effect = partition_rx.recv().await.unwrap();
+        assert_eq!(effect.invocation_id, invocation_id);
+        let_assert!(EffectKind::Failed { error, .. } = effect.kind);
+        assert_eq!(error.code(), codes::NOT_FOUND);
+        assert!(service_inner
+            .status_store
+            .resolve_invocation(MOCK_PARTITION, &invocation_id)
+            .is_none());
+    }
+
+    #[test(tokio::test)]
+    async fn retry_replays_the_cached_journal() {
+        let invoker_options = InvokerOptionsBuilder::default()
+            .retry_policy(RetryPolicy::fixed_delay(Duration::ZERO, Some(10)))
+            .build()
+            .unwrap();
+        let invocation_id = InvocationId::mock_random();
+
+        let (input_journal_tx, mut input_journal_rx) = mpsc::unbounded_channel();
+        let (_, _status_tx, mut service_inner) = ServiceInner::mock(
+            move |_, _, _, _, _, _, input_journal| {
+                let _ = input_journal_tx.send(input_journal);
+                pending()
+            },
+            None,
+        );
+        service_inner.journal_cache = JournalCache::new(Some(1024));
+        let _ = service_inner.register_mock_partition(EmptyStorageReader);
+
+        service_inner
+            .handle_invoke(
+                &invoker_options,
+                MOCK_PARTITION,
+                invocation_id,
+                InvocationTarget::mock_virtual_object(),
+                InvokeInputJournal::NoCachedJournal,
+            )
+            .await;
+        check!(let Some(InvokeInputJournal::NoCachedJournal) = input_journal_rx.recv().await);
+
+        // The task fails after replaying the journal
+        let replay = |service_inner: &mut ServiceInner<_, _>| {
+            service_inner.handle_replayed_journal(
+                MOCK_PARTITION,
+                invocation_id,
+                JournalMetadata::new(1, ServiceInvocationSpanContext::empty(), None),
+                vec![PlainRawEntry::new(EntryHeader::Output, Bytes::default())],
+            )
+        };
+        replay(&mut service_inner);
+        service_inner
+            .handle_invocation_task_failed(
+                MOCK_PARTITION,
+                invocation_id,
+                InvocationTaskError::EmptySuspensionMessage,
+            )
+            .await;
+        service_inner
+            .handle_retry_timer_fired(&invoker_options, MOCK_PARTITION, invocation_id)
+            .await;
+        let_assert!(
+            Some(InvokeInputJournal::CachedJournal(journal_metadata, entries)) =
+                input_journal_rx.recv().await
+        );
+        assert_eq!(journal_metadata.length, 1);
+        assert_eq!(entries.len(), 1);
+
+        // A completion received while waiting for the retry is not in the cached journal
+        replay(&mut service_inner);
+        service_inner
+            .handle_invocation_task_failed(
+                MOCK_PARTITION,
+                invocation_id,
+                InvocationTaskError::EmptySuspensionMessage,
+            )
+            .await;
+        service_inner.handle_completion(
+            MOCK_PARTITION,
+            invocation_id,
+            Completion::new(1, CompletionResult::Empty),
+        );
+        service_inner
+            .handle_retry_timer_fired(&invoker_options, MOCK_PARTITION, invocation_id)
+            .await;
+        check!(let Some(InvokeInputJournal::NoCachedJournal) = input_journal_rx.recv().await);
+    }
+
     #[test(tokio::test)]
     async fn reclaim_quota_after_abort() {
         let invoker_options = InvokerOptionsBuilder::default()
@@ -0,0 +1,460 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use restate_errors::NotRunningError;
+use restate_invoker_api::{Effect, EffectKind, InvokeInputJournal, JournalReader, ServiceHandle};
+use restate_service_protocol::codec::ProtobufRawEntryCodec;
+use restate_types::errors::InvocationError;
+use restate_types::identifiers::{EntryIndex, InvocationId, PartitionKey, PartitionLeaderEpoch};
+use restate_types::invocation::{Header, InvocationTarget};
+use restate_types::journal::raw::{PlainEntryHeader, PlainRawEntry, RawEntryCodec};
+use restate_types::journal::{Completion, EntryResult};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tracing::trace;
+
+type LocalHandler =
+    Arc<dyn Fn(LocalRequest) -> BoxFuture<'static, Result<Bytes, InvocationError>> + Send + Sync>;
+
+/// Invocation of a handler registered in [`LocalServices`].
+#[derive(Debug, Clone)]
+pub struct LocalRequest {
+    pub invocation_id: InvocationId,
+    pub invocation_target: InvocationTarget,
+    pub headers: Vec<Header>,
+    pub input: Bytes,
+}
+
+/// Rust closures invoked in-process by the [`LocalInvoker`], by service and handler name.
+#[derive(Clone, Default)]
+pub struct LocalServices {
+    handlers: HashMap<(String, String), LocalHandler>,
+}
+
+impl LocalServices {
+    /// Registers the closure handling the invocations of `service/handler`. The invocation
+    /// completes with the output returned by the closure, or fails with its error.
+    pub fn with_handler<F, Fut>(
+        mut self,
+        service: impl Into<String>,
+        handler: impl Into<String>,
+        f: F,
+    ) -> Self
+    where
+        F: Fn(LocalRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Bytes, InvocationError>> + Send + 'static,
+    {
+        self.handlers.insert(
+            (service.into(), handler.into()),
+            Arc::new(move |request| f(request).boxed()),
+        );
+        self
+    }
+
+    fn resolve(&self, invocation_target: &InvocationTarget) -> Option<LocalHandler> {
+        self.handlers
+            .get(&(
+                invocation_target.service_name().to_string(),
+                invocation_target.handler_name().to_string(),
+            ))
+            .cloned()
+    }
+}
+
+impl fmt::Debug for LocalServices {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(
+                self.handlers
+                    .keys()
+                    .map(|(service, handler)| format!("{service}/{handler}")),
+            )
+            .finish()
+    }
+}
+
+/// [`ServiceHandle`] invoking the [`LocalServices`] in-process instead of sending requests to
+/// deployments, to run the partition processor without deploying a service with an SDK, for
+/// example in tests.
+///
+/// The handlers don't have access to the journal: every invocation runs once and completes
+/// with an output entry, hence completions and stored entry acks are ignored.
+#[derive(Debug)]
+pub struct LocalInvoker<SR> {
+    services: Arc<LocalServices>,
+    state: Arc<Mutex<LocalInvokerState<SR>>>,
+}
+
+#[derive(Debug)]
+struct LocalInvokerState<SR> {
+    partitions: HashMap<PartitionLeaderEpoch, (SR, mpsc::Sender<Effect>)>,
+    invocations: HashMap<PartitionLeaderEpoch, HashMap<InvocationId, AbortHandle>>,
+}
+
+impl<SR> Clone for LocalInvoker<SR> {
+    fn clone(&self) -> Self {
+        Self {
+            services: Arc::clone(&self.services),
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<SR> LocalInvoker<SR> {
+    pub fn new(services: LocalServices) -> Self {
+        Self {
+            services: Arc::new(services),
+            state: Arc::new(Mutex::new(LocalInvokerState {
+                partitions: HashMap::new(),
+                invocations: HashMap::new(),
+            })),
+        }
+    }
+}
+
+impl<SR> LocalInvokerState<SR> {
+    fn remove_invocation(&mut self, partition: PartitionLeaderEpoch, invocation_id: InvocationId) {
+        if let Some(invocations) = self.invocations.get_mut(&partition) {
+            invocations.remove(&invocation_id);
+            if invocations.is_empty() {
+                self.invocations.remove(&partition);
+            }
+        }
+    }
+
+    fn abort_partition(&mut self, partition: PartitionLeaderEpoch) {
+        for (_, abort_handle) in self.invocations.remove(&partition).unwrap_or_default() {
+            abort_handle.abort();
+        }
+    }
+}
+
+impl<SR> ServiceHandle<SR> for LocalInvoker<SR>
+where
+    SR: JournalReader + Clone + Send + Sync + 'static,
+    <SR as JournalReader>::JournalStream: Unpin + Send + 'static,
+{
+    type Future = futures::future::Ready<Result<(), NotRunningError>>;
+
+    fn invoke(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+        invocation_target: InvocationTarget,
+        journal: InvokeInputJournal,
+    ) -> Self::Future {
+        let mut state = self.state.lock().unwrap();
+        let Some((storage_reader, sender)) = state.partitions.get(&partition).cloned() else {
+            trace!(
+                restate.invocation.id = %invocation_id,
+                "Partition {:?} is not registered, ignoring the invocation", partition
+            );
+            return futures::future::ready(Ok(()));
+        };
+
+        let handler = self.services.resolve(&invocation_target);
+        let task_state = Arc::clone(&self.state);
+        let task = tokio::spawn(async move {
+            let effects = run_invocation(
+                storage_reader,
+                invocation_id,
+                invocation_target,
+                journal,
+                handler,
+            )
+            .await;
+            for kind in effects {
+                if sender
+                    .send(Effect {
+                        invocation_id,
+                        kind,
+                    })
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            task_state
+                .lock()
+                .unwrap()
+                .remove_invocation(partition, invocation_id);
+        });
+        // The task can only remove itself once the lock is released
+        state
+            .invocations
+            .entry(partition)
+            .or_default()
+            .insert(invocation_id, task.abort_handle());
+
+        futures::future::ready(Ok(()))
+    }
+
+    fn notify_completion(
+        &mut self,
+        _partition: PartitionLeaderEpoch,
+        _invocation_id: InvocationId,
+        _completion: Completion,
+    ) -> Self::Future {
+        futures::future::ready(Ok(()))
+    }
+
+    fn notify_stored_entry_ack(
+        &mut self,
+        _partition: PartitionLeaderEpoch,
+        _invocation_id: InvocationId,
+        _entry_index: EntryIndex,
+    ) -> Self::Future {
+        futures::future::ready(Ok(()))
+    }
+
+    fn abort_all_partition(&mut self, partition: PartitionLeaderEpoch) -> Self::Future {
+        let mut state = self.state.lock().unwrap();
+        state.partitions.remove(&partition);
+        state.abort_partition(partition);
+        futures::future::ready(Ok(()))
+    }
+
+    fn drain_partition(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        grace_period: Duration,
+    ) -> Self::Future {
+        // No new invocations are started, the in-flight ones are aborted after the grace period
+        self.state.lock().unwrap().partitions.remove(&partition);
+        let state = Arc::clone(&self.state);
+        tokio::spawn(async move {
+            tokio::time::sleep(grace_period).await;
+            state.lock().unwrap().abort_partition(partition);
+        });
+        futures::future::ready(Ok(()))
+    }
+
+    fn abort_invocation(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+    ) -> Self::Future {
+        let mut state = self.state.lock().unwrap();
+        if let Some(abort_handle) = state
+            .invocations
+            .get(&partition)
+            .and_then(|invocations| invocations.get(&invocation_id))
+        {
+            abort_handle.abort();
+        }
+        state.remove_invocation(partition, invocation_id);
+        futures::future::ready(Ok(()))
+    }
+
+    fn register_partition(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        _partition_key_range: RangeInclusive<PartitionKey>,
+        storage_reader: SR,
+        sender: mpsc::Sender<Effect>,
+    ) -> Self::Future {
+        self.state
+            .lock()
+            .unwrap()
+            .partitions
+            .insert(partition, (storage_reader, sender));
+        futures::future::ready(Ok(()))
+    }
+}
+
+/// Runs the handler with the input entry of the journal, returning the effects to send to the
+/// partition processor.
+async fn run_invocation<SR>(
+    mut storage_reader: SR,
+    invocation_id: InvocationId,
+    invocation_target: InvocationTarget,
+    journal: InvokeInputJournal,
+    handler: Option<LocalHandler>,
+) -> Vec<EffectKind>
+where
+    SR: JournalReader,
+    <SR as JournalReader>::JournalStream: Unpin,
+{
+    let entries: Vec<PlainRawEntry> = match journal {
+        InvokeInputJournal::CachedJournal(_, entries) => entries,
+        InvokeInputJournal::NoCachedJournal => {
+            match storage_reader.read_journal(&invocation_id).await {
+                Ok((_, journal_stream)) => journal_stream.collect().await,
+                Err(e) => return vec![EffectKind::failed(InvocationError::internal(e))],
+            }
+        }
+    };
+
+    // A previous run stored the output already
+    if entries
+        .iter()
+        .any(|entry| matches!(entry.header(), PlainEntryHeader::Output {}))
+    {
+        return vec![EffectKind::End];
+    }
+
+    let Some(input_entry) = entries
+        .first()
+        .filter(|entry| matches!(entry.header(), PlainEntryHeader::Input {}))
+    else {
+        return vec![EffectKind::failed(InvocationError::internal(
+            "the journal doesn't start with an input entry",
+        ))];
+    };
+    let (headers, input) = match ProtobufRawEntryCodec::deserialize_input_entry(
+        input_entry.serialized_entry().clone(),
+    ) {
+        Ok(input) => input,
+        Err(e) => return vec![EffectKind::failed(InvocationError::internal(e))],
+    };
+
+    let Some(handler) = handler else {
+        return vec![EffectKind::failed(
+            InvocationError::service_handler_not_found(
+                invocation_target.service_name(),
+                invocation_target.handler_name(),
+            )
+            .with_static_description("No local handler is registered for it."),
+        )];
+    };
+
+    let result = match handler(LocalRequest {
+        invocation_id,
+        invocation_target,
+        headers,
+        input,
+    })
+    .await
+    {
+        Ok(output) => EntryResult::Success(output),
+        Err(e) => EntryResult::Failure(e.code(), e.message().into()),
+    };
+
+    vec![
+        EffectKind::JournalEntry {
+            entry_index: entries.len() as EntryIndex,
+            entry: ProtobufRawEntryCodec::serialize_as_output_entry(result),
+        },
+        EffectKind::End,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use googletest::prelude::*;
+    use restate_invoker_api::mocks::EmptyStorageReader;
+    use restate_invoker_api::JournalMetadata;
+    use restate_types::errors::codes;
+    use restate_types::identifiers::{LeaderEpoch, PartitionId};
+    use restate_types::invocation::ServiceInvocationSpanContext;
+    use restate_types::journal::enriched::EnrichedEntryHeader;
+    use restate_types::journal::{Entry, EntryType, OutputEntry};
+    use test_log::test;
+
+    const MOCK_PARTITION: PartitionLeaderEpoch = (PartitionId::MIN, LeaderEpoch::INITIAL);
+
+    async fn invoke(
+        services: LocalServices,
+        invocation_target: InvocationTarget,
+    ) -> Vec<EffectKind> {
+        let mut invoker = LocalInvoker::new(services);
+        let (effects_tx, mut effects_rx) = mpsc::channel(10);
+        invoker
+            .register_partition(
+                MOCK_PARTITION,
+                0..=PartitionKey::MAX,
+                EmptyStorageReader,
+                effects_tx,
+            )
+            .await
+            .unwrap();
+
+        let input_entry =
+            ProtobufRawEntryCodec::serialize_as_input_entry(vec![], Bytes::from_static(b"Till"))
+                .erase_enrichment();
+        invoker
+            .invoke(
+                MOCK_PARTITION,
+                InvocationId::mock_random(),
+                invocation_target,
+                InvokeInputJournal::CachedJournal(
+                    JournalMetadata::new(1, ServiceInvocationSpanContext::empty(), None),
+                    vec![input_entry],
+                ),
+            )
+            .await
+            .unwrap();
+
+        let mut effects = vec![];
+        while let Some(effect) = effects_rx.recv().await {
+            let done = matches!(effect.kind, EffectKind::End | EffectKind::Failed { .. });
+            effects.push(effect.kind);
+            if done {
+                break;
+            }
+        }
+        effects
+    }
+
+    #[test(tokio::test)]
+    async fn completes_with_the_handler_output() {
+        let services =
+            LocalServices::default().with_handler("Greeter", "greet", |request| async move {
+                Ok(Bytes::from(format!(
+                    "Hello {}",
+                    String::from_utf8_lossy(&request.input)
+                )))
+            });
+
+        let effects = invoke(services, InvocationTarget::service("Greeter", "greet")).await;
+
+        assert_eq!(effects.len(), 2);
+        let EffectKind::JournalEntry { entry_index, entry } = &effects[0] else {
+            panic!("expected the output entry, got {:?}", effects[0]);
+        };
+        assert_eq!(*entry_index, 1);
+        assert_eq!(entry.header(), &EnrichedEntryHeader::Output {});
+        assert_eq!(
+            ProtobufRawEntryCodec::deserialize(EntryType::Output, entry.serialized_entry().clone())
+                .unwrap(),
+            Entry::Output(OutputEntry {
+                result: EntryResult::Success(Bytes::from_static(b"Hello Till"))
+            })
+        );
+        assert_that!(effects[1], pat!(EffectKind::End));
+    }
+
+    #[test(tokio::test)]
+    async fn fails_without_a_handler() {
+        let effects = invoke(
+            LocalServices::default(),
+            InvocationTarget::service("Greeter", "greet"),
+        )
+        .await;
+
+        let [EffectKind::Failed { error, .. }] = &effects[..] else {
+            panic!("expected the invocation to fail, got {:?}", effects);
+        };
+        assert_eq!(error.code(), codes::NOT_FOUND);
+    }
+}
@@ -10,10 +10,24 @@
 
 /// Optional to have but adds description/help message to the metrics emitted to
 /// the metrics' sink.
-use metrics::{describe_counter, Unit};
+use metrics::{describe_counter, describe_histogram, Unit};
 
 pub const INVOKER_ENQUEUE: &str = "restate.invoker.enqueue.total";
 pub const INVOKER_INVOCATION_TASK: &str = "restate.invoker.invocation_task.total";
+pub const INVOKER_CIRCUIT_BREAKER_OPENED: &str = "restate.invoker.circuit_breaker.opened.total";
+pub const INVOKER_DEPLOYMENT_QUOTA_SATURATED: &str =
+    "restate.invoker.deployment_quota.saturated.total";
+pub const INVOKER_DEPLOYMENT_OVERLOADED: &str = "restate.invoker.deployment_overloaded.total";
+pub const INVOKER_DEPLOYMENT_UNHEALTHY: &str = "restate.invoker.deployment_unhealthy.total";
+pub const INVOKER_JOURNAL_MEMORY_BUDGET_EXHAUSTED: &str =
+    "restate.invoker.journal_memory_budget.exhausted.total";
+pub const INVOKER_JOURNAL_CACHE_HIT: &str = "restate.invoker.journal_cache.hit.total";
+
+// Per deployment and service metrics, labeled with `deployment` and `service`
+pub const INVOKER_ATTEMPT_DURATION: &str = "restate.invoker.attempt_duration.seconds";
+pub const INVOKER_TIME_TO_FIRST_ENTRY: &str = "restate.invoker.time_to_first_entry.seconds";
+pub const INVOKER_ATTEMPT_ERROR: &str = "restate.invoker.attempt_error.total";
+pub const INVOKER_RETRY: &str = "restate.invoker.retry.total";
 
 pub const TASK_OP_STARTED: &str = "started";
 pub const TASK_OP_SUSPENDED: &str = "suspended";
@@ -32,4 +46,64 @@ pub(crate) fn describe_metrics() {
         Unit::Count,
         "Invocation task operation"
     );
+
+    describe_counter!(
+        INVOKER_CIRCUIT_BREAKER_OPENED,
+        Unit::Count,
+        "Number of times the circuit of a deployment was opened"
+    );
+
+    describe_counter!(
+        INVOKER_DEPLOYMENT_QUOTA_SATURATED,
+        Unit::Count,
+        "Number of invocations handed back to their partition because their deployment reached its concurrency limit"
+    );
+
+    describe_counter!(
+        INVOKER_DEPLOYMENT_OVERLOADED,
+        Unit::Count,
+        "Number of times a deployment asked to back off with a Retry-After header"
+    );
+
+    describe_counter!(
+        INVOKER_DEPLOYMENT_UNHEALTHY,
+        Unit::Count,
+        "Number of times a deployment failed its health check after being healthy"
+    );
+
+    describe_counter!(
+        INVOKER_JOURNAL_MEMORY_BUDGET_EXHAUSTED,
+        Unit::Count,
+        "Number of cached journals dropped because the journal memory budget was exhausted"
+    );
+
+    describe_counter!(
+        INVOKER_JOURNAL_CACHE_HIT,
+        Unit::Count,
+        "Number of invocation retries which replayed the journal from the journal cache"
+    );
+
+    describe_histogram!(
+        INVOKER_ATTEMPT_DURATION,
+        Unit::Seconds,
+        "Duration of the requests to a deployment, from sending the request until the invocation task ends, by outcome"
+    );
+
+    describe_histogram!(
+        INVOKER_TIME_TO_FIRST_ENTRY,
+        Unit::Seconds,
+        "Time from sending the request to a deployment until it sends the first new journal entry"
+    );
+
+    describe_counter!(
+        INVOKER_ATTEMPT_ERROR,
+        Unit::Count,
+        "Number of invocation attempts sent to a deployment which failed, by error class and code"
+    );
+
+    describe_counter!(
+        INVOKER_RETRY,
+        Unit::Count,
+        "Number of invocation retries scheduled after an attempt failed on a deployment"
+    );
 }
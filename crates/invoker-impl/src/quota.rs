@@ -8,6 +8,14 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::{HashMap, VecDeque};
+
+use bytestring::ByteString;
+use restate_types::identifiers::{InvocationId, PartitionLeaderEpoch};
+use restate_types::invocation::InvocationTarget;
+
+use crate::input_command::InvokeCommand;
+
 #[derive(Debug)]
 pub(super) enum InvokerConcurrencyQuota {
     Unlimited,
@@ -44,3 +52,101 @@ impl InvokerConcurrencyQuota {
         }
     }
 }
+
+/// Counts the running invocations per service, and parks the invocations of services that
+/// reached their concurrency limit until one of the running invocations of the same service ends.
+#[derive(Debug, Default)]
+pub(super) struct ServiceConcurrencyQuota {
+    running: HashMap<ByteString, usize>,
+    parked: HashMap<ByteString, VecDeque<InvokeCommand>>,
+    // Parked invocations which got a service slot, and wait for an invoker slot
+    runnable: VecDeque<InvokeCommand>,
+}
+
+impl ServiceConcurrencyQuota {
+    /// Reserves a slot for the service of the invocation and returns it back, or parks it if the
+    /// service reached its limit.
+    pub(super) fn reserve_slot_or_park(
+        &mut self,
+        invoke_command: InvokeCommand,
+        limit: Option<usize>,
+    ) -> Option<InvokeCommand> {
+        let service_name = invoke_command.invocation_target.service_name();
+        let running = self.running.get(service_name).copied().unwrap_or_default();
+        if limit.is_some_and(|limit| running >= limit) {
+            self.parked
+                .entry(service_name.clone())
+                .or_default()
+                .push_back(invoke_command);
+            return None;
+        }
+        *self.running.entry(service_name.clone()).or_default() += 1;
+        Some(invoke_command)
+    }
+
+    /// Releases the slot of an ended invocation, handing it over to the next parked invocation
+    /// of the same service if any.
+    pub(super) fn unreserve_slot(&mut self, invocation_target: &InvocationTarget) {
+        let service_name = invocation_target.service_name();
+        if let Some(parked) = self.parked.get_mut(service_name) {
+            if let Some(next) = parked.pop_front() {
+                if parked.is_empty() {
+                    self.parked.remove(service_name);
+                }
+                self.runnable.push_back(next);
+                return;
+            }
+        }
+        if let Some(running) = self.running.get_mut(service_name) {
+            *running -= 1;
+            if *running == 0 {
+                self.running.remove(service_name);
+            }
+        }
+    }
+
+    pub(super) fn has_runnable(&self) -> bool {
+        !self.runnable.is_empty()
+    }
+
+    pub(super) async fn next_runnable(&mut self) -> Option<InvokeCommand> {
+        self.runnable.pop_front()
+    }
+
+    /// Drops the parked invocation, returns true if it was parked.
+    pub(super) fn remove_invocation(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: &InvocationId,
+    ) -> bool {
+        self.remove_where(|cmd| cmd.partition == partition && &cmd.invocation_id == invocation_id)
+    }
+
+    /// Drops the parked invocations of the partition.
+    pub(super) fn remove_partition(&mut self, partition: PartitionLeaderEpoch) {
+        self.remove_where(|cmd| cmd.partition == partition);
+    }
+
+    fn remove_where(&mut self, predicate: impl Fn(&InvokeCommand) -> bool) -> bool {
+        let mut removed = false;
+        self.parked.retain(|_, queue| {
+            queue.retain(|cmd| {
+                let matches = predicate(cmd);
+                removed |= matches;
+                !matches
+            });
+            !queue.is_empty()
+        });
+
+        // Runnable invocations hold a service slot already
+        let (dropped, runnable) = std::mem::take(&mut self.runnable)
+            .into_iter()
+            .partition::<Vec<_>, _>(&predicate);
+        self.runnable = runnable.into();
+        for cmd in dropped {
+            removed = true;
+            self.unreserve_slot(&cmd.invocation_target);
+        }
+        removed
+    }
+}
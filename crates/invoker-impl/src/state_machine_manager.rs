@@ -34,6 +34,7 @@ struct PartitionInvocationStateMachineCoordinator<SR> {
     invocation_state_machines: HashMap<InvocationId, InvocationStateMachine>,
     partition_key_range: RangeInclusive<PartitionKey>,
     storage_reader: SR,
+    draining: bool,
 }
 
 impl<SR> InvocationStateMachineManager<SR>
@@ -96,6 +97,34 @@ where
             .map(|p| p.invocation_state_machines)
     }
 
+    /// Marks the partition as draining, and removes the invocations which are not in flight.
+    pub(super) fn start_draining(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+    ) -> Option<Vec<(InvocationId, InvocationStateMachine)>> {
+        let p = self.resolve_partition(partition)?;
+        p.draining = true;
+        let (in_flight, not_in_flight): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut p.invocation_state_machines)
+                .into_iter()
+                .partition(|(_, ism)| ism.is_in_flight());
+        p.invocation_state_machines = in_flight.into_iter().collect();
+        Some(not_in_flight)
+    }
+
+    #[inline]
+    pub(super) fn is_draining(&self, partition: PartitionLeaderEpoch) -> bool {
+        self.partitions.get(&partition).is_some_and(|p| p.draining)
+    }
+
+    /// Returns true if the partition is draining and none of its invocations is left.
+    #[inline]
+    pub(super) fn is_drained(&self, partition: PartitionLeaderEpoch) -> bool {
+        self.partitions
+            .get(&partition)
+            .is_some_and(|p| p.draining && p.invocation_state_machines.is_empty())
+    }
+
     #[inline]
     pub(super) fn register_partition(
         &mut self,
@@ -111,6 +140,7 @@ where
                 invocation_state_machines: Default::default(),
                 partition_key_range,
                 storage_reader,
+                draining: false,
             },
         );
     }
@@ -38,6 +38,17 @@ impl InvocationStatusStore {
             })
     }
 
+    pub(super) fn last_attempt_deployment_id(
+        &self,
+        partition: &PartitionLeaderEpoch,
+        invocation_id: &InvocationId,
+    ) -> Option<DeploymentId> {
+        self.0
+            .get(partition)
+            .and_then(|inner| inner.get(invocation_id))
+            .and_then(|report| report.last_attempt_deployment_id)
+    }
+
     // -- Methods used by the invoker to notify the status
 
     pub(super) fn on_start(
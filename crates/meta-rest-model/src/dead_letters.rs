@@ -0,0 +1,22 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_types::identifiers::InvocationId;
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedriveDeadLetterResponse {
+    /// # Invocation id
+    ///
+    /// Id of the invocation that runs the dead lettered invocation again.
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub invocation_id: InvocationId,
+}
@@ -21,6 +21,7 @@ use restate_schema_api::deployment::DeploymentType;
 pub use restate_schema_api::deployment::{DeploymentMetadata, ProtocolType};
 use restate_types::identifiers::ServiceRevision;
 pub use restate_types::identifiers::{DeploymentId, LambdaARN};
+pub use restate_types::retries::RetryPolicy;
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -34,6 +35,9 @@ pub enum Deployment {
         #[serde(skip_serializing_if = "SerdeableHeaderHashMap::is_empty")]
         #[serde(default)]
         additional_headers: SerdeableHeaderHashMap,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        retry_policy: Option<RetryPolicy>,
         #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
         #[cfg_attr(feature = "schema", schemars(with = "String"))]
         created_at: humantime::Timestamp,
@@ -48,6 +52,9 @@ pub enum Deployment {
         #[serde(skip_serializing_if = "SerdeableHeaderHashMap::is_empty")]
         #[serde(default)]
         additional_headers: SerdeableHeaderHashMap,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        retry_policy: Option<RetryPolicy>,
         #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
         #[cfg_attr(feature = "schema", schemars(with = "String"))]
         created_at: humantime::Timestamp,
@@ -62,10 +69,12 @@ impl From<DeploymentMetadata> for Deployment {
             DeploymentType::Http {
                 address,
                 protocol_type,
+                ..
             } => Self::Http {
                 uri: address,
                 protocol_type,
                 additional_headers: value.delivery_options.additional_headers.into(),
+                retry_policy: value.delivery_options.retry_policy,
                 created_at: SystemTime::from(value.created_at).into(),
                 min_protocol_version: *value.supported_protocol_versions.start(),
                 max_protocol_version: *value.supported_protocol_versions.end(),
@@ -77,6 +86,7 @@ impl From<DeploymentMetadata> for Deployment {
                 arn,
                 assume_role_arn: assume_role_arn.map(Into::into),
                 additional_headers: value.delivery_options.additional_headers.into(),
+                retry_policy: value.delivery_options.retry_policy,
                 created_at: SystemTime::from(value.created_at).into(),
                 min_protocol_version: *value.supported_protocol_versions.start(),
                 max_protocol_version: *value.supported_protocol_versions.end(),
@@ -104,6 +114,14 @@ pub enum RegisterDeploymentRequest {
         /// Additional headers added to the discover/invoke requests to the deployment.
         ///
         additional_headers: Option<SerdeableHeaderHashMap>,
+
+        /// # Retry policy
+        ///
+        /// Retry policy for the invocations of the services of this deployment.
+        /// If unset, the retry policy configured in the invoker options is used.
+        #[serde(default)]
+        retry_policy: Option<RetryPolicy>,
+
         /// # Force
         ///
         /// If `true`, it will override, if existing, any deployment using the same `uri`.
@@ -138,6 +156,14 @@ pub enum RegisterDeploymentRequest {
         /// Additional headers added to the discover/invoke requests to the deployment.
         ///
         additional_headers: Option<SerdeableHeaderHashMap>,
+
+        /// # Retry policy
+        ///
+        /// Retry policy for the invocations of the services of this deployment.
+        /// If unset, the retry policy configured in the invoker options is used.
+        #[serde(default)]
+        retry_policy: Option<RetryPolicy>,
+
         /// # Force
         ///
         /// If `true`, it will override, if existing, any deployment using the same `uri`.
@@ -170,12 +196,31 @@ pub struct ServiceNameRevPair {
 pub struct RegisterDeploymentResponse {
     pub id: DeploymentId,
     pub services: Vec<ServiceMetadata>,
+
+    /// # Changes
+    ///
+    /// Changes of the registered services and deployments, applied by the registration or which
+    /// would be applied in case of a dry run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<String>,
+
+    /// # Incompatible changes
+    ///
+    /// Backward incompatible changes of the re-registered services, accepted because `force` is set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub incompatible_changes: Vec<String>,
 }
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListDeploymentsResponse {
     pub deployments: Vec<DeploymentResponse>,
+
+    /// # Next
+    ///
+    /// Set if more items are left. Pass it as the `after` query parameter to list the next page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
 }
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
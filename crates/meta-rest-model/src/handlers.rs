@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
 // Export schema types to be used by other crates without exposing the fact
 // that we are using proxying to restate-schema-api or restate-types
 pub use restate_schema_api::service::HandlerMetadata;
@@ -17,4 +18,47 @@ pub use restate_schema_api::service::HandlerMetadata;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListServiceHandlersResponse {
     pub handlers: Vec<HandlerMetadata>,
+
+    /// # Next
+    ///
+    /// Set if more items are left. Pass it as the `after` query parameter to list the next page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModifyServiceHandlerRequest {
+    /// # Public
+    ///
+    /// If true, the handler can be invoked through the ingress.
+    /// If false, the handler can be invoked only from another Restate service.
+    /// This overrides the visibility of the service.
+    #[serde(default)]
+    pub public: Option<bool>,
+
+    /// # Idempotency retention
+    ///
+    /// Modify the retention of idempotent requests for this handler.
+    /// This overrides the idempotency retention of the service.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde(default, with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    pub idempotency_retention: Option<humantime::Duration>,
+
+    /// # Execution timeout
+    ///
+    /// Modify the maximum duration of an invocation of this handler.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde(default, with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    pub execution_timeout: Option<humantime::Duration>,
+
+    /// # Max concurrent invocations
+    ///
+    /// Modify the maximum number of invocations of this handler running at the same time.
+    #[serde(default)]
+    pub max_concurrent_invocations: Option<NonZeroU32>,
 }
@@ -8,7 +8,10 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+pub mod dead_letters;
 pub mod deployments;
 pub mod handlers;
+pub mod partitions;
+pub mod schema;
 pub mod services;
 pub mod subscriptions;
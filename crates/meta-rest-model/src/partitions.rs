@@ -0,0 +1,41 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_types::identifiers::{PartitionId, PartitionKey};
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SplitPartitionRequest {
+    /// # Split key
+    ///
+    /// First partition key of the right partition. If not set, the partition is split in the
+    /// middle of its key range.
+    pub split_key: Option<PartitionKey>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitPartitionResponse {
+    /// # Split key
+    ///
+    /// First partition key of the right partition.
+    pub split_key: PartitionKey,
+    /// # Left partition id
+    ///
+    /// Id of the partition owning the keys before the split key.
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub left_partition_id: PartitionId,
+    /// # Right partition id
+    ///
+    /// Id of the partition owning the split key and the keys after it.
+    #[cfg_attr(feature = "schema", schemars(with = "u64"))]
+    pub right_partition_id: PartitionId,
+}
@@ -14,7 +14,7 @@ use std::collections::HashMap;
 
 // Export schema types to be used by other crates without exposing the fact
 // that we are using proxying to restate-schema-api or restate-types
-pub use restate_schema_api::service::{HandlerMetadata, ServiceMetadata};
+pub use restate_schema_api::service::{DeploymentWeight, HandlerMetadata, ServiceMetadata};
 pub use restate_types::identifiers::ServiceRevision;
 pub use restate_types::invocation::ServiceType;
 
@@ -22,6 +22,12 @@ pub use restate_types::invocation::ServiceType;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListServicesResponse {
     pub services: Vec<ServiceMetadata>,
+
+    /// # Next
+    ///
+    /// Set if more items are left. Pass it as the `after` query parameter to list the next page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
 }
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -43,6 +49,16 @@ pub struct ModifyServiceRequest {
     #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
     pub idempotency_retention: Option<humantime::Duration>,
 
+    /// # Completion retention
+    ///
+    /// Modify the retention of the results of completed invocations for this service.
+    /// This doesn't apply to workflow methods, use the workflow completion retention for them.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde(default, with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    pub completion_retention: Option<humantime::Duration>,
+
     /// # Workflow completion retention
     ///
     /// Modify the retention of the workflow completion. This can be modified only for workflow services!
@@ -51,6 +67,14 @@ pub struct ModifyServiceRequest {
     #[serde(default, with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")]
     #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
     pub workflow_completion_retention: Option<humantime::Duration>,
+
+    /// # Traffic split
+    ///
+    /// Split the new invocations of this service across the given deployments, according to their weights.
+    /// The deployments must serve this service. If empty, the latest deployment receives all the new invocations.
+    /// Registering a new deployment of this service removes the traffic split.
+    #[serde(default)]
+    pub traffic_split: Option<Vec<DeploymentWeight>>,
 }
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -131,7 +131,7 @@ impl LocalMetadataStore {
             data_dir.as_ref().to_path_buf(),
             Options::default(),
         )
-        .add_cf_pattern(CfPrefixPattern::ANY, |opts| opts)
+        .add_cf_pattern(CfPrefixPattern::ANY, |_, opts| opts)
         .ensure_column_families(cfs)
         .build_as_db();
 
@@ -287,14 +287,22 @@ impl LocalMetadataStore {
         self.buffer.clear();
         Self::encode(value, &mut self.buffer)?;
 
-        let write_options = self.write_options();
         let cf_handle = self.kv_cf_handle();
         let mut wb = WriteBatch::default();
         wb.put_cf(&cf_handle, key, self.buffer.as_ref());
-        Ok(self
-            .rocksdb
-            .write_batch(Priority::High, IoMode::default(), write_options, wb)
-            .await?)
+        if self.opts.load().rocksdb_disable_wal() {
+            let write_options = self.write_options();
+            Ok(self
+                .rocksdb
+                .write_batch(Priority::High, IoMode::default(), write_options, wb)
+                .await?)
+        } else {
+            // the WAL sync is shared with concurrent writes
+            Ok(self
+                .rocksdb
+                .write_batch_group_commit(Priority::High, IoMode::default(), wb)
+                .await?)
+        }
     }
 
     fn delete(&mut self, key: &ByteString, precondition: Precondition) -> Result<()> {
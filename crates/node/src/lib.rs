@@ -31,13 +31,14 @@ use restate_core::{task_center, TaskKind};
 use restate_metadata_store::local::LocalMetadataStoreService;
 use restate_metadata_store::MetadataStoreClient;
 use restate_types::logs::metadata::{create_static_metadata, Logs};
+use restate_types::logs::LogId;
 use restate_types::metadata_store::keys::{
     BIFROST_CONFIG_KEY, NODES_CONFIG_KEY, PARTITION_TABLE_KEY,
 };
 use restate_types::nodes_config::{NodeConfig, NodesConfiguration, Role};
 use restate_types::partition_table::FixedPartitionTable;
 use restate_types::retries::RetryPolicy;
-use restate_types::Version;
+use restate_types::{StateMachineVersion, Version};
 
 use crate::network_server::{AdminDependencies, NetworkServer, WorkerDependencies};
 use crate::roles::{AdminRole, WorkerRole};
@@ -352,11 +353,12 @@ impl Node {
         )
         .await?;
 
-        // sanity check
-        if partition_table.num_partitions()
-            != u64::try_from(logs.logs.len()).expect("usize fits into u64")
+        // sanity check, partitions created by splits have a log as well
+        if let Some(partition_id) = partition_table
+            .partition_ids()
+            .find(|partition_id| !logs.logs.contains_key(&LogId::from(*partition_id)))
         {
-            return Err(Error::SafetyCheck(format!("The partition table (number partitions: {}) and logs configuration (number logs: {}) don't match. Please make sure that they are aligned.", partition_table.num_partitions(), logs.logs.len())))?;
+            return Err(Error::SafetyCheck(format!("The partition table (number partitions: {}) and logs configuration (number logs: {}) don't match, there is no log for partition {}. Please make sure that they are aligned.", partition_table.num_partitions(), logs.logs.len(), partition_id)))?;
         }
 
         Ok((partition_table, logs))
@@ -437,6 +439,7 @@ impl Node {
                     // update node_config
                     node_config.roles = common_opts.roles;
                     node_config.address = common_opts.advertised_address.clone();
+                    node_config.state_machine_version = StateMachineVersion::LATEST;
                     node_config.current_generation.bump_generation();
 
                     node_config
@@ -13,11 +13,11 @@ pub mod node;
 
 use std::fmt::Write;
 
-use axum::extract::State;
+use axum::extract::{Path, State};
 use metrics_exporter_prometheus::formatting;
-use rocksdb::statistics::{Histogram, Ticker};
+use rocksdb::statistics::Histogram;
 
-use restate_rocksdb::{CfName, RocksDbManager};
+use restate_rocksdb::{CfName, DbName, RocksDbManager, NODE_RENDERED_TICKERS};
 
 use crate::network_server::prometheus_helpers::{
     format_rocksdb_histogram_for_prometheus, format_rocksdb_property_for_prometheus,
@@ -25,35 +25,8 @@ use crate::network_server::prometheus_helpers::{
 };
 use crate::network_server::state::NodeCtrlHandlerState;
 
-const ROCKSDB_TICKERS: &[Ticker] = &[
-    Ticker::BlockCacheBytesRead,
-    Ticker::BlockCacheBytesWrite,
-    Ticker::BlockCacheHit,
-    Ticker::BlockCacheMiss,
-    Ticker::BloomFilterUseful,
-    Ticker::BytesRead,
-    Ticker::BytesWritten,
-    Ticker::CompactReadBytes,
-    Ticker::CompactWriteBytes,
-    Ticker::FlushWriteBytes,
-    Ticker::IterBytesRead,
-    Ticker::MemtableHit,
-    Ticker::MemtableMiss,
-    Ticker::NoIteratorCreated,
-    Ticker::NoIteratorDeleted,
-    Ticker::NumberDbNext,
-    Ticker::NumberDbSeek,
-    Ticker::NumberIterSkip,
-    Ticker::NumberKeysRead,
-    Ticker::NumberKeysUpdated,
-    Ticker::NumberKeysWritten,
-    Ticker::NumberOfReseeksInIteration,
-    Ticker::StallMicros,
-    Ticker::WalFileBytes,
-    Ticker::WalFileSynced,
-    Ticker::WriteWithWal,
-];
-
+// Keep in sync with restate_rocksdb::NODE_RENDERED_HISTOGRAMS, the rocksdb manager doesn't
+// export these histograms itself.
 const ROCKSDB_HISTOGRAMS: &[(Histogram, &str, MetricUnit)] = &[
     (Histogram::DbGet, "rocksdb.db.get", MetricUnit::Micros),
     (
@@ -202,13 +175,14 @@ pub async fn render_metrics(State(state): State<NodeCtrlHandlerState>) -> String
         manager.get_total_write_buffer_usage(),
     );
 
+    let memory_usage = manager.get_memory_usage_stats(&[]);
     for db in &all_dbs {
         let labels = vec![format!(
             "db=\"{}\"",
             formatting::sanitize_label_value(&db.name)
         )];
         // Tickers (Counters)
-        for ticker in ROCKSDB_TICKERS {
+        for ticker in NODE_RENDERED_TICKERS {
             format_rocksdb_stat_ticker_for_prometheus(&mut out, db, &labels, *ticker);
         }
         // Histograms
@@ -223,33 +197,31 @@ pub async fn render_metrics(State(state): State<NodeCtrlHandlerState>) -> String
         }
 
         // Memory Usage Stats (Gauges)
-        let memory_usage = manager
-            .get_memory_usage_stats(&[])
-            .expect("get_memory_usage_stats");
-
-        format_rocksdb_property_for_prometheus(
-            &mut out,
-            &labels,
-            MetricUnit::Bytes,
-            "rocksdb.memory.approx-memtable",
-            memory_usage.approximate_mem_table_total(),
-        );
+        if let Some(db_memory) = memory_usage.dbs.iter().find(|m| m.name == db.name) {
+            format_rocksdb_property_for_prometheus(
+                &mut out,
+                &labels,
+                MetricUnit::Bytes,
+                "rocksdb.memory.approx-memtable",
+                db_memory.mem_table_total(),
+            );
 
-        format_rocksdb_property_for_prometheus(
-            &mut out,
-            &labels,
-            MetricUnit::Bytes,
-            "rocksdb.memory.approx-memtable-unflushed",
-            memory_usage.approximate_mem_table_unflushed(),
-        );
+            format_rocksdb_property_for_prometheus(
+                &mut out,
+                &labels,
+                MetricUnit::Bytes,
+                "rocksdb.memory.approx-memtable-unflushed",
+                db_memory.mem_table_unflushed(),
+            );
 
-        format_rocksdb_property_for_prometheus(
-            &mut out,
-            &labels,
-            MetricUnit::Bytes,
-            "rocksdb.memory.approx-memtable-readers",
-            memory_usage.approximate_mem_table_readers_total(),
-        );
+            format_rocksdb_property_for_prometheus(
+                &mut out,
+                &labels,
+                MetricUnit::Bytes,
+                "rocksdb.memory.approx-memtable-readers",
+                db_memory.table_readers_total(),
+            );
+        }
 
         // Other per-database properties
         for (property, unit) in ROCKSDB_DB_PROPERTIES {
@@ -272,6 +244,20 @@ pub async fn render_metrics(State(state): State<NodeCtrlHandlerState>) -> String
             let mut cf_labels = Vec::with_capacity(labels.len() + 1);
             labels.clone_into(&mut cf_labels);
             cf_labels.push(format!("cf=\"{}\"", sanitized_cf_name));
+            if let Some(cf_memory) = memory_usage
+                .dbs
+                .iter()
+                .find(|m| m.name == db.name)
+                .and_then(|m| m.cfs.iter().find(|m| &m.name == cf))
+            {
+                format_rocksdb_property_for_prometheus(
+                    &mut out,
+                    &cf_labels,
+                    MetricUnit::Bytes,
+                    "rocksdb.memory.block-cache-pinned",
+                    cf_memory.block_cache_pinned,
+                );
+            }
             for (property, unit) in ROCKSDB_CF_PROPERTIES {
                 format_rocksdb_property_for_prometheus(
                     &mut out,
@@ -288,3 +274,23 @@ pub async fn render_metrics(State(state): State<NodeCtrlHandlerState>) -> String
     }
     out
 }
+
+/// Attempts to lift the quarantine of a rocksdb database after a background error.
+pub async fn resume_rocksdb(Path(db_name): Path<String>) -> (http::StatusCode, String) {
+    let Some(db) = RocksDbManager::get().get_db(DbName::new(&db_name)) else {
+        return (
+            http::StatusCode::NOT_FOUND,
+            format!("Unknown database '{}'", db_name),
+        );
+    };
+    match db.try_resume().await {
+        Ok(()) => (
+            http::StatusCode::OK,
+            format!("Database '{}' is accepting writes", db_name),
+        ),
+        Err(e) => (
+            http::StatusCode::SERVICE_UNAVAILABLE,
+            format!("Failed to resume database '{}': {}", db_name, e),
+        ),
+    }
+}
@@ -63,16 +63,22 @@ impl AdminRole {
             ServiceClient::from_options(&config.common.service_client, AssumeRoleCacheMode::None)?;
         let service_discovery = ServiceDiscovery::new(retry_policy, client);
 
+        let controller = restate_cluster_controller::Service::new(
+            metadata_writer.clone(),
+            metadata_store_client.clone(),
+        );
+
         let admin = AdminService::new(
             metadata_writer,
             metadata_store_client,
             config.ingress.clone(),
             service_discovery,
+            controller.handle(),
         );
 
         Ok(AdminRole {
             updateable_config,
-            controller: restate_cluster_controller::Service::default(),
+            controller,
             admin,
         })
     }
@@ -92,7 +98,7 @@ impl AdminRole {
             TaskKind::SystemService,
             "cluster-controller-service",
             None,
-            self.controller.run(),
+            self.controller.run(bifrost.clone()),
         )?;
 
         // todo: Make address configurable
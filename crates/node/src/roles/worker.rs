@@ -16,12 +16,11 @@ use restate_core::{cancellation_watcher, metadata, task_center, Metadata};
 use restate_core::{ShutdownError, TaskKind};
 use restate_metadata_store::MetadataStoreClient;
 use restate_network::Networking;
-use restate_node_protocol::metadata::MetadataKind;
+use restate_schema::changes::SchemaChange;
 use restate_schema::UpdateableSchema;
 use restate_schema_api::subscription::SubscriptionResolver;
 use restate_storage_query_datafusion::context::QueryContext;
 use restate_types::config::UpdateableConfiguration;
-use restate_types::Version;
 use restate_worker::SubscriptionController;
 use restate_worker::{SubscriptionControllerHandle, Worker};
 
@@ -117,17 +116,29 @@ impl WorkerRole {
     {
         let metadata = metadata();
         let schema_view = metadata.schema_updateable();
-        let mut next_version = Version::MIN;
+        let mut schema_changes = metadata.watch_schema_changes();
         let cancellation_watcher = cancellation_watcher();
         tokio::pin!(cancellation_watcher);
 
+        subscription_controller
+            .update_subscriptions(schema_view.list_subscriptions(&[]))
+            .await?;
+
         loop {
             tokio::select! {
                 _ = &mut cancellation_watcher => {
                     break;
                 },
-                version = metadata.wait_for_version(MetadataKind::Schema, next_version) => {
-                    next_version = version?.next();
+                changes = schema_changes.next() => {
+                    let subscriptions_changed = changes?.iter().any(|change| {
+                        matches!(
+                            change,
+                            SchemaChange::SubscriptionAdded(_) | SchemaChange::SubscriptionRemoved(_)
+                        )
+                    });
+                    if !subscriptions_changed {
+                        continue;
+                    }
 
                     // This might return subscriptions belonging to a higher schema version. As a
                     // result we might re-apply the same list of subscriptions. This is not a
@@ -0,0 +1,224 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Read-only access to checkpoints of the partition store database.
+//!
+//! A checkpoint is taken with the rocksdb checkpoint API, it's a consistent view of all partition
+//! stores as of the time it was taken. Partitions are exported from it as one SST file for their
+//! data and one for their journal blobs, which can be ingested into an empty partition store.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{debug, warn};
+
+use restate_core::ShutdownError;
+use restate_rocksdb::{
+    CfName, CfPrefixPattern, DbName, DbSpecBuilder, OpenMode as DbOpenMode, RocksDbManager,
+    RocksError,
+};
+use restate_storage_api::fsm_table::ReadOnlyFsmTable;
+use restate_storage_api::{Result, StorageError};
+use restate_types::arc_util::Constant;
+use restate_types::config::RocksDbOptions;
+use restate_types::identifiers::PartitionId;
+use restate_types::storage::{StorageCodec, StorageDecode};
+
+use crate::cf_options;
+use crate::fsm_table::PartitionStateMachineKey;
+use crate::keys::TableKey;
+use crate::partition_store_manager::{
+    blobs_cf_for_partition, cf_for_partition, db_options, DB_NAME, PARTITION_BLOBS_CF_PREFIX,
+    PARTITION_CF_PREFIX,
+};
+
+/// Checkpoints are registered with the [`RocksDbManager`] under a unique name
+static NEXT_CHECKPOINT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// How long closing a checkpoint waits for in-flight storage tasks
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A consistent, read-only view of all partition stores as of the time the checkpoint was taken.
+pub struct PartitionStoreCheckpoint {
+    name: DbName,
+    path: PathBuf,
+    db: Option<Arc<rocksdb::DB>>,
+}
+
+impl PartitionStoreCheckpoint {
+    pub(crate) async fn open(
+        path: PathBuf,
+        opts: RocksDbOptions,
+    ) -> std::result::Result<Self, RocksError> {
+        let name = DbName::new(&format!(
+            "{}-checkpoint-{}",
+            DB_NAME,
+            NEXT_CHECKPOINT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let db_spec = DbSpecBuilder::new(name.clone(), path.clone(), db_options())
+            .add_cf_pattern(CfPrefixPattern::new(PARTITION_CF_PREFIX), cf_options)
+            .add_cf_pattern(CfPrefixPattern::new(PARTITION_BLOBS_CF_PREFIX), cf_options)
+            .open_mode(DbOpenMode::ReadOnly)
+            .build_as_db();
+
+        let manager = RocksDbManager::get();
+        let db = tokio::task::spawn_blocking(move || manager.open_db(Constant::new(opts), db_spec))
+            .await
+            .map_err(|_| ShutdownError)??;
+
+        Ok(Self {
+            name,
+            path,
+            db: Some(db),
+        })
+    }
+
+    fn db(&self) -> &rocksdb::DB {
+        self.db.as_ref().expect("checkpoint is open")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn has_partition(&self, partition_id: PartitionId) -> bool {
+        self.db()
+            .cf_handle(&cf_for_partition(partition_id))
+            .is_some()
+    }
+
+    /// Exports all data of the partition into a new SST file at `target`. Returns `false` and
+    /// creates no file if the partition store is empty.
+    pub async fn export_partition(
+        &self,
+        partition_id: PartitionId,
+        target: PathBuf,
+    ) -> std::result::Result<bool, RocksError> {
+        self.export(partition_id, cf_for_partition(partition_id), target)
+            .await
+    }
+
+    /// Exports the journal blobs of the partition into a new SST file at `target`. Returns `false`
+    /// and creates no file if the partition has no journal blobs.
+    pub async fn export_partition_blobs(
+        &self,
+        partition_id: PartitionId,
+        target: PathBuf,
+    ) -> std::result::Result<bool, RocksError> {
+        let cf_name = blobs_cf_for_partition(partition_id);
+        if self.db().cf_handle(&cf_name).is_none() {
+            // partition stores created by older versions have no blobs cf
+            return Ok(false);
+        }
+        self.export(partition_id, cf_name, target).await
+    }
+
+    async fn export(
+        &self,
+        partition_id: PartitionId,
+        cf_name: CfName,
+        target: PathBuf,
+    ) -> std::result::Result<bool, RocksError> {
+        let db = self.db.clone().expect("checkpoint is open");
+        tokio::task::spawn_blocking(move || export_cf(&db, partition_id, &cf_name, &target))
+            .await
+            .map_err(|_| ShutdownError)?
+    }
+
+    /// Closes the checkpoint and removes it from disk.
+    pub async fn close(mut self) -> std::result::Result<(), RocksError> {
+        // the manager only closes the database once it holds the last reference
+        drop(self.db.take());
+        RocksDbManager::get()
+            .close_db(self.name.clone(), CLOSE_TIMEOUT)
+            .await?;
+
+        if let Err(e) = tokio::fs::remove_dir_all(&self.path).await {
+            warn!(
+                path = %self.path.display(),
+                "Failed to remove partition store checkpoint: {}", e
+            );
+        }
+        Ok(())
+    }
+}
+
+fn export_cf(
+    db: &rocksdb::DB,
+    partition_id: PartitionId,
+    cf_name: &CfName,
+    target: &Path,
+) -> std::result::Result<bool, RocksError> {
+    let cf = db
+        .cf_handle(cf_name)
+        .ok_or_else(|| RocksError::UnknownColumnFamily(cf_name.clone()))?;
+
+    let options = rocksdb::Options::default();
+    let mut writer = rocksdb::SstFileWriter::create(&options);
+    writer.open(target)?;
+
+    // the partition column families use a prefix extractor, make sure we see all keys
+    let mut read_options = rocksdb::ReadOptions::default();
+    read_options.set_total_order_seek(true);
+
+    let mut num_entries: u64 = 0;
+    for item in db.iterator_cf_opt(&cf, read_options, rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        writer.put(key, value)?;
+        num_entries += 1;
+    }
+
+    if num_entries == 0 {
+        // rocksdb refuses to finish empty SST files
+        drop(writer);
+        let _ = std::fs::remove_file(target);
+        return Ok(false);
+    }
+    writer.finish()?;
+
+    debug!(
+        %partition_id,
+        cf = %cf_name,
+        num_entries,
+        path = %target.display(),
+        "Exported partition store from checkpoint"
+    );
+    Ok(true)
+}
+
+impl ReadOnlyFsmTable for PartitionStoreCheckpoint {
+    async fn get<T>(&mut self, partition_id: PartitionId, state_id: u64) -> Result<Option<T>>
+    where
+        T: StorageDecode,
+    {
+        let cf_name = cf_for_partition(partition_id);
+        let cf = self.db().cf_handle(&cf_name).ok_or_else(|| {
+            StorageError::Generic(RocksError::UnknownColumnFamily(cf_name.clone()).into())
+        })?;
+        let key = PartitionStateMachineKey::default()
+            .partition_id(partition_id)
+            .state_id(state_id)
+            .serialize();
+
+        let value = self
+            .db()
+            .get_pinned_cf(&cf, key)
+            .map_err(|err| StorageError::Generic(err.into()))?;
+
+        value
+            .map(|value| {
+                StorageCodec::decode::<T, _>(&mut value.as_ref())
+                    .map_err(|err| StorageError::Generic(err.into()))
+            })
+            .transpose()
+    }
+}
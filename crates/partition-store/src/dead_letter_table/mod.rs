@@ -0,0 +1,118 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::keys::{define_table_key, KeyKind, TableKey};
+use crate::owned_iter::OwnedIterator;
+use crate::scan::TableScan;
+use crate::{PartitionStore, TableKind};
+use crate::{RocksDBTransaction, StorageAccess};
+use futures::Stream;
+use futures_util::stream;
+use restate_storage_api::dead_letter_table::{
+    DeadLetter, DeadLetterTable, ReadOnlyDeadLetterTable,
+};
+use restate_storage_api::{Result, StorageError};
+use restate_types::identifiers::{InvocationId, InvocationUuid, PartitionKey, WithPartitionKey};
+use restate_types::storage::StorageCodec;
+use std::ops::RangeInclusive;
+
+define_table_key!(
+    TableKind::DeadLetter,
+    KeyKind::DeadLetter,
+    DeadLetterKey(
+        partition_key: PartitionKey,
+        invocation_uuid: InvocationUuid
+    )
+);
+
+fn create_key(invocation_id: &InvocationId) -> DeadLetterKey {
+    DeadLetterKey::default()
+        .partition_key(invocation_id.partition_key())
+        .invocation_uuid(invocation_id.invocation_uuid())
+}
+
+fn get_dead_letter<S: StorageAccess>(
+    storage: &mut S,
+    invocation_id: &InvocationId,
+) -> Result<Option<DeadLetter>> {
+    storage.get_value(create_key(invocation_id))
+}
+
+fn all_dead_letters<S: StorageAccess>(
+    storage: &mut S,
+    range: RangeInclusive<PartitionKey>,
+) -> impl Stream<Item = Result<(InvocationId, DeadLetter)>> + Send + '_ {
+    let iter = storage.iterator_from(TableScan::FullScanPartitionKeyRange::<DeadLetterKey>(range));
+    stream::iter(OwnedIterator::new(iter).map(|(mut k, mut v)| {
+        let key = DeadLetterKey::deserialize_from(&mut k)?;
+        let dead_letter = StorageCodec::decode::<DeadLetter, _>(&mut v)
+            .map_err(|err| StorageError::Generic(err.into()))?;
+
+        Ok((
+            InvocationId::from_parts(*key.partition_key_ok_or()?, *key.invocation_uuid_ok_or()?),
+            dead_letter,
+        ))
+    }))
+}
+
+fn put_dead_letter<S: StorageAccess>(
+    storage: &mut S,
+    invocation_id: &InvocationId,
+    dead_letter: DeadLetter,
+) {
+    storage.put_kv(create_key(invocation_id), dead_letter);
+}
+
+fn delete_dead_letter<S: StorageAccess>(storage: &mut S, invocation_id: &InvocationId) {
+    let key = create_key(invocation_id);
+    storage.delete_key(&key);
+}
+
+impl ReadOnlyDeadLetterTable for PartitionStore {
+    async fn get_dead_letter(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> Result<Option<DeadLetter>> {
+        get_dead_letter(self, invocation_id)
+    }
+
+    fn all_dead_letters(
+        &mut self,
+        range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = Result<(InvocationId, DeadLetter)>> + Send {
+        all_dead_letters(self, range)
+    }
+}
+
+impl<'a> ReadOnlyDeadLetterTable for RocksDBTransaction<'a> {
+    async fn get_dead_letter(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> Result<Option<DeadLetter>> {
+        get_dead_letter(self, invocation_id)
+    }
+
+    fn all_dead_letters(
+        &mut self,
+        range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = Result<(InvocationId, DeadLetter)>> + Send {
+        all_dead_letters(self, range)
+    }
+}
+
+impl<'a> DeadLetterTable for RocksDBTransaction<'a> {
+    async fn put_dead_letter(&mut self, invocation_id: &InvocationId, dead_letter: DeadLetter) {
+        put_dead_letter(self, invocation_id, dead_letter)
+    }
+
+    async fn delete_dead_letter(&mut self, invocation_id: &InvocationId) {
+        delete_dead_letter(self, invocation_id)
+    }
+}
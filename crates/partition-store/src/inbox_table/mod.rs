@@ -10,13 +10,15 @@
 
 use crate::keys::{define_table_key, KeyKind, TableKey};
 use crate::TableKind::Inbox;
-use crate::{RocksDBTransaction, StorageAccess};
+use crate::{PartitionStore, RocksDBTransaction, StorageAccess};
 use crate::{TableScan, TableScanIterationDecision};
 use bytestring::ByteString;
 use futures::Stream;
 use futures_util::stream;
-use restate_storage_api::inbox_table::{InboxEntry, InboxTable, SequenceNumberInboxEntry};
-use restate_storage_api::{Result, StorageError};
+use restate_storage_api::inbox_table::{
+    InboxEntry, InboxPriority, InboxTable, ReadOnlyInboxTable, SequenceNumberInboxEntry,
+};
+use restate_storage_api::{Result, StorageError, Transaction};
 use restate_types::identifiers::{PartitionKey, ServiceId, WithPartitionKey};
 use restate_types::storage::StorageCodec;
 use std::io::Cursor;
@@ -24,43 +26,63 @@ use std::ops::RangeInclusive;
 
 define_table_key!(
     Inbox,
-    KeyKind::Inbox,
+    KeyKind::PriorityInbox,
     InboxKey(
         partition_key: PartitionKey,
         service_name: ByteString,
         service_key: ByteString,
+        priority: InboxPriority,
         sequence_number: u64
     )
 );
 
-impl<'a> InboxTable for RocksDBTransaction<'a> {
-    async fn put_inbox_entry(
-        &mut self,
-        service_id: &ServiceId,
-        SequenceNumberInboxEntry {
-            inbox_sequence_number,
-            inbox_entry,
-        }: SequenceNumberInboxEntry,
-    ) {
-        let key = InboxKey::default()
-            .partition_key(service_id.partition_key())
-            .service_name(service_id.service_name.clone())
-            .service_key(service_id.key.clone())
-            .sequence_number(inbox_sequence_number);
+// Inbox keys written before the inbox had priority lanes
+define_table_key!(
+    Inbox,
+    KeyKind::Inbox,
+    LegacyInboxKey(
+        partition_key: PartitionKey,
+        service_name: ByteString,
+        service_key: ByteString,
+        sequence_number: u64
+    )
+);
 
-        self.put_kv(key, inbox_entry);
-    }
+impl PartitionStore {
+    /// Moves the inbox entries stored with the key format that predates the priority lanes into
+    /// the background lane, which keeps their order. Returns the number of migrated entries.
+    pub async fn migrate_legacy_inbox(&mut self) -> Result<usize> {
+        let key_range = self.partition_key_range().clone();
+        let mut txn = self.transaction();
+        let legacy_entries = txn.for_each_key_value_in_place(
+            TableScan::FullScanPartitionKeyRange::<LegacyInboxKey>(key_range),
+            |k, v| {
+                let entry = LegacyInboxKey::deserialize_from(&mut Cursor::new(k))
+                    .map(|key| (key, v.to_vec()));
+                TableScanIterationDecision::Emit(entry)
+            },
+        );
 
-    async fn delete_inbox_entry(&mut self, service_id: &ServiceId, sequence_number: u64) {
-        let key = InboxKey::default()
-            .partition_key(service_id.partition_key())
-            .service_name(service_id.service_name.clone())
-            .service_key(service_id.key.clone())
-            .sequence_number(sequence_number);
+        let migrated = legacy_entries.len();
+        for entry in legacy_entries {
+            let (legacy_key, value) = entry?;
+            let key = InboxKey {
+                partition_key: legacy_key.partition_key,
+                service_name: legacy_key.service_name.clone(),
+                service_key: legacy_key.service_key.clone(),
+                priority: Some(InboxPriority::Background),
+                sequence_number: legacy_key.sequence_number,
+            };
+            txn.put_kv_raw(key, value);
+            txn.delete_key(&legacy_key);
+        }
+        txn.commit().await?;
 
-        self.delete_key(&key);
+        Ok(migrated)
     }
+}
 
+impl<'a> ReadOnlyInboxTable for RocksDBTransaction<'a> {
     async fn peek_inbox(
         &mut self,
         service_id: &ServiceId,
@@ -82,20 +104,6 @@ impl<'a> InboxTable for RocksDBTransaction<'a> {
         )
     }
 
-    async fn pop_inbox(
-        &mut self,
-        service_id: &ServiceId,
-    ) -> Result<Option<SequenceNumberInboxEntry>> {
-        let result = self.peek_inbox(service_id).await;
-
-        if let Ok(Some(inbox_entry)) = &result {
-            self.delete_inbox_entry(service_id, inbox_entry.inbox_sequence_number)
-                .await
-        }
-
-        result
-    }
-
     fn inbox(
         &mut self,
         service_id: &ServiceId,
@@ -128,28 +136,87 @@ impl<'a> InboxTable for RocksDBTransaction<'a> {
     }
 }
 
+impl<'a> InboxTable for RocksDBTransaction<'a> {
+    async fn put_inbox_entry(
+        &mut self,
+        service_id: &ServiceId,
+        SequenceNumberInboxEntry {
+            inbox_sequence_number,
+            priority,
+            inbox_entry,
+        }: SequenceNumberInboxEntry,
+    ) {
+        let key = InboxKey::default()
+            .partition_key(service_id.partition_key())
+            .service_name(service_id.service_name.clone())
+            .service_key(service_id.key.clone())
+            .priority(priority)
+            .sequence_number(inbox_sequence_number);
+
+        self.put_kv(key, inbox_entry);
+    }
+
+    async fn delete_inbox_entry(
+        &mut self,
+        service_id: &ServiceId,
+        priority: InboxPriority,
+        sequence_number: u64,
+    ) {
+        let key = InboxKey::default()
+            .partition_key(service_id.partition_key())
+            .service_name(service_id.service_name.clone())
+            .service_key(service_id.key.clone())
+            .priority(priority)
+            .sequence_number(sequence_number);
+
+        self.delete_key(&key);
+    }
+
+    async fn pop_inbox(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> Result<Option<SequenceNumberInboxEntry>> {
+        let result = self.peek_inbox(service_id).await;
+
+        if let Ok(Some(inbox_entry)) = &result {
+            self.delete_inbox_entry(
+                service_id,
+                inbox_entry.priority,
+                inbox_entry.inbox_sequence_number,
+            )
+            .await
+        }
+
+        result
+    }
+}
+
 fn decode_inbox_key_value(k: &[u8], mut v: &[u8]) -> Result<SequenceNumberInboxEntry> {
     let key = InboxKey::deserialize_from(&mut Cursor::new(k))?;
+    let priority = *key.priority_ok_or()?;
     let sequence_number = *key.sequence_number_ok_or()?;
 
     let inbox_entry = StorageCodec::decode::<InboxEntry, _>(&mut v)
         .map_err(|error| StorageError::Generic(error.into()))?;
 
-    Ok(SequenceNumberInboxEntry::new(sequence_number, inbox_entry))
+    Ok(SequenceNumberInboxEntry::new(sequence_number, inbox_entry).with_priority(priority))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::inbox_table::InboxKey;
-    use crate::keys::TableKey;
-    use bytes::{Bytes, BytesMut};
+    use crate::inbox_table::{InboxKey, LegacyInboxKey};
+    use crate::keys::{KeyKind, TableKey};
+    use bytes::{BufMut, Bytes, BytesMut};
+    use restate_storage_api::inbox_table::InboxPriority;
     use restate_types::identifiers::{ServiceId, WithPartitionKey};
+    use std::io::Cursor;
 
     fn message_key(service_id: &ServiceId, sequence_number: u64) -> Bytes {
         let key = InboxKey {
             partition_key: Some(service_id.partition_key()),
             service_name: Some(service_id.service_name.clone()),
             service_key: Some(service_id.key.clone()),
+            priority: Some(InboxPriority::Background),
             sequence_number: Some(sequence_number),
         };
         let mut buf = BytesMut::new();
@@ -162,12 +229,53 @@ mod tests {
             partition_key: Some(service_id.partition_key()),
             service_name: Some(service_id.service_name.clone()),
             service_key: Some(service_id.key.clone()),
+            priority: None,
             sequence_number: None,
         };
 
         key.serialize().freeze()
     }
 
+    #[test]
+    fn decode_legacy_inbox_key() {
+        // key layout of the inbox before it had priority lanes
+        let mut buf = BytesMut::new();
+        buf.put_slice(b"ib");
+        buf.put_u64(1337);
+        // length delimited with a varint
+        buf.put_u8(5);
+        buf.put_slice(b"svc-1");
+        buf.put_u8(5);
+        buf.put_slice(b"key-a");
+        buf.put_u64(42);
+
+        let key = LegacyInboxKey::deserialize_from(&mut Cursor::new(&buf[..])).unwrap();
+        assert_eq!(key.partition_key, Some(1337));
+        assert_eq!(key.service_name.as_deref(), Some("svc-1"));
+        assert_eq!(key.service_key.as_deref(), Some("key-a"));
+        assert_eq!(key.sequence_number, Some(42));
+        assert_eq!(key.serialize(), buf);
+
+        // legacy keys are never mistaken for keys of the current format
+        assert!(InboxKey::deserialize_from(&mut Cursor::new(&buf[..])).is_err());
+        assert_ne!(KeyKind::Inbox.as_bytes(), KeyKind::PriorityInbox.as_bytes());
+    }
+
+    #[test]
+    fn interactive_messages_sort_before_background_messages() {
+        let service_id = ServiceId::with_partition_key(1337, "svc-1", "key-a");
+        let interactive_key = InboxKey {
+            partition_key: Some(service_id.partition_key()),
+            service_name: Some(service_id.service_name.clone()),
+            service_key: Some(service_id.key.clone()),
+            priority: Some(InboxPriority::Interactive),
+            sequence_number: Some(u64::MAX),
+        }
+        .serialize()
+        .freeze();
+        assert!(interactive_key < message_key(&service_id, 0));
+    }
+
     #[test]
     fn inbox_key_covers_all_messages_of_a_service() {
         let prefix_key = inbox_key(&ServiceId::with_partition_key(1337, "svc-1", "key-a"));
@@ -85,11 +85,39 @@ fn delete_invocation_status<S: StorageAccess>(storage: &mut S, invocation_id: &I
 fn invoked_invocations<S: StorageAccess>(
     storage: &mut S,
     partition_key_range: RangeInclusive<PartitionKey>,
+) -> Vec<Result<(InvocationId, InvocationTarget)>> {
+    invocations_with_status(storage, partition_key_range, |invocation_status| {
+        if let InvocationStatus::Invoked(invocation_meta) = invocation_status {
+            Some(invocation_meta.invocation_target)
+        } else {
+            None
+        }
+    })
+}
+
+fn inboxed_invocations<S: StorageAccess>(
+    storage: &mut S,
+    partition_key_range: RangeInclusive<PartitionKey>,
+) -> Vec<Result<(InvocationId, InvocationTarget)>> {
+    invocations_with_status(storage, partition_key_range, |invocation_status| {
+        if let InvocationStatus::Inboxed(inboxed_invocation) = invocation_status {
+            Some(inboxed_invocation.invocation_target)
+        } else {
+            None
+        }
+    })
+}
+
+/// Scans the invocation statuses, `target_of` selects the invocations to emit.
+fn invocations_with_status<S: StorageAccess>(
+    storage: &mut S,
+    partition_key_range: RangeInclusive<PartitionKey>,
+    target_of: fn(InvocationStatus) -> Option<InvocationTarget>,
 ) -> Vec<Result<(InvocationId, InvocationTarget)>> {
     storage.for_each_key_value_in_place(
         FullScanPartitionKeyRange::<InvocationStatusKey>(partition_key_range),
         |mut k, mut v| {
-            let result = read_invoked_full_invocation_id(&mut k, &mut v).transpose();
+            let result = read_full_invocation_id(&mut k, &mut v, target_of).transpose();
             if let Some(res) = result {
                 TableScanIterationDecision::Emit(res)
             } else {
@@ -99,18 +127,15 @@ fn invoked_invocations<S: StorageAccess>(
     )
 }
 
-fn read_invoked_full_invocation_id(
+fn read_full_invocation_id(
     mut k: &mut &[u8],
     v: &mut &[u8],
+    target_of: fn(InvocationStatus) -> Option<InvocationTarget>,
 ) -> Result<Option<(InvocationId, InvocationTarget)>> {
     let invocation_id = invocation_id_from_bytes(&mut k)?;
     let invocation_status = StorageCodec::decode::<InvocationStatus, _>(v)
         .map_err(|err| StorageError::Generic(err.into()))?;
-    if let InvocationStatus::Invoked(invocation_meta) = invocation_status {
-        Ok(Some((invocation_id, invocation_meta.invocation_target)))
-    } else {
-        Ok(None)
-    }
+    Ok(target_of(invocation_status).map(|invocation_target| (invocation_id, invocation_target)))
 }
 
 impl ReadOnlyInvocationStatusTable for PartitionStore {
@@ -127,6 +152,13 @@ impl ReadOnlyInvocationStatusTable for PartitionStore {
     ) -> impl Stream<Item = Result<(InvocationId, InvocationTarget)>> + Send {
         stream::iter(invoked_invocations(self, partition_key_range))
     }
+
+    fn inboxed_invocations(
+        &mut self,
+        partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = Result<(InvocationId, InvocationTarget)>> + Send {
+        stream::iter(inboxed_invocations(self, partition_key_range))
+    }
 }
 
 impl<'a> ReadOnlyInvocationStatusTable for RocksDBTransaction<'a> {
@@ -144,6 +176,13 @@ impl<'a> ReadOnlyInvocationStatusTable for RocksDBTransaction<'a> {
     ) -> impl Stream<Item = Result<(InvocationId, InvocationTarget)>> + Send {
         stream::iter(invoked_invocations(self, partition_key_range))
     }
+
+    fn inboxed_invocations(
+        &mut self,
+        partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = Result<(InvocationId, InvocationTarget)>> + Send {
+        stream::iter(inboxed_invocations(self, partition_key_range))
+    }
 }
 
 impl<'a> InvocationStatusTable for RocksDBTransaction<'a> {
@@ -12,17 +12,20 @@ use crate::keys::TableKey;
 use crate::keys::{define_table_key, KeyKind};
 use crate::owned_iter::OwnedIterator;
 use crate::scan::TableScan::FullScanPartitionKeyRange;
-use crate::TableKind::Journal;
+use crate::TableKind::{Journal, JournalBlob};
 use crate::{PartitionStore, RocksDBTransaction, StorageAccess};
 use crate::{TableScan, TableScanIterationDecision};
+use bytes::{Buf, Bytes};
 use futures::Stream;
 use futures_util::stream;
 use restate_storage_api::journal_table::{JournalEntry, JournalTable, ReadOnlyJournalTable};
 use restate_storage_api::{Result, StorageError};
+use restate_types::flexbuffers_storage_encode_decode;
 use restate_types::identifiers::{
     EntryIndex, InvocationId, InvocationUuid, PartitionKey, WithPartitionKey,
 };
-use restate_types::storage::StorageCodec;
+use restate_types::storage::{StorageCodec, StorageCodecKind, StorageDecode, StorageDecodeError};
+use sha2::{Digest, Sha256};
 use std::io::Cursor;
 use std::ops::RangeInclusive;
 
@@ -36,6 +39,54 @@ define_table_key!(
     )
 );
 
+// Journal entries exceeding the journal blob threshold are stored under this key in the blobs
+// column family, the journal only contains a reference to them.
+define_table_key!(
+    JournalBlob,
+    KeyKind::JournalBlob,
+    JournalBlobKey(
+        partition_key: PartitionKey,
+        invocation_uuid: InvocationUuid,
+        hash: Bytes
+    )
+);
+
+/// Reference to a journal entry which has been spilled to the blobs column family.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct JournalBlobRef {
+    hash: Vec<u8>,
+}
+
+flexbuffers_storage_encode_decode!(JournalBlobRef);
+
+/// Value stored under a journal key, either the journal entry itself or a reference to its blob.
+enum StoredJournalEntry {
+    Inline(Box<JournalEntry>),
+    Blob(JournalBlobRef),
+}
+
+impl StorageDecode for StoredJournalEntry {
+    fn decode<B: Buf>(
+        buf: &mut B,
+        kind: StorageCodecKind,
+    ) -> std::result::Result<Self, StorageDecodeError> {
+        // Journal entries are encoded with protobuf, blob references with flexbuffers
+        match kind {
+            StorageCodecKind::Protobuf => {
+                JournalEntry::decode(buf, kind).map(|entry| Self::Inline(Box::new(entry)))
+            }
+            StorageCodecKind::FlexbuffersSerde => JournalBlobRef::decode(buf, kind).map(Self::Blob),
+        }
+    }
+}
+
+fn journal_blob_key(invocation_id: &InvocationId, hash: &[u8]) -> JournalBlobKey {
+    JournalBlobKey::default()
+        .partition_key(invocation_id.partition_key())
+        .invocation_uuid(invocation_id.invocation_uuid())
+        .hash(Bytes::copy_from_slice(hash))
+}
+
 fn write_journal_entry_key(invocation_id: &InvocationId, journal_index: u32) -> JournalKey {
     JournalKey::default()
         .partition_key(invocation_id.partition_key())
@@ -51,7 +102,40 @@ fn put_journal_entry<S: StorageAccess>(
 ) {
     let key = write_journal_entry_key(invocation_id, journal_index);
 
-    storage.put_kv(key, journal_entry);
+    let value_buffer = storage.cleared_value_buffer_mut(0);
+    StorageCodec::encode(&journal_entry, value_buffer).unwrap();
+    let value = value_buffer.split();
+
+    if value.len() > storage.journal_blob_threshold() {
+        let hash = Sha256::digest(&value);
+        storage.put_kv_raw(journal_blob_key(invocation_id, &hash), &value);
+        storage.put_kv(
+            key,
+            JournalBlobRef {
+                hash: hash.to_vec(),
+            },
+        );
+    } else {
+        storage.put_kv_raw(key, &value);
+    }
+}
+
+fn resolve_journal_entry<S: StorageAccess>(
+    storage: &S,
+    invocation_id: &InvocationId,
+    stored_journal_entry: StoredJournalEntry,
+) -> Result<JournalEntry> {
+    match stored_journal_entry {
+        StoredJournalEntry::Inline(journal_entry) => Ok(*journal_entry),
+        StoredJournalEntry::Blob(JournalBlobRef { hash }) => {
+            let key = journal_blob_key(invocation_id, &hash).serialize();
+            let blob = storage
+                .get(JournalBlob, &key)?
+                .ok_or(StorageError::DataIntegrityError)?;
+            StorageCodec::decode::<JournalEntry, _>(&mut blob.as_ref())
+                .map_err(|error| StorageError::Generic(error.into()))
+        }
+    }
 }
 
 fn get_journal_entry<S: StorageAccess>(
@@ -61,7 +145,12 @@ fn get_journal_entry<S: StorageAccess>(
 ) -> Result<Option<JournalEntry>> {
     let key = write_journal_entry_key(invocation_id, journal_index);
 
-    storage.get_value(key)
+    storage
+        .get_value::<_, StoredJournalEntry>(key)?
+        .map(|stored_journal_entry| {
+            resolve_journal_entry(storage, invocation_id, stored_journal_entry)
+        })
+        .transpose()
 }
 
 fn get_journal<S: StorageAccess>(
@@ -73,6 +162,7 @@ fn get_journal<S: StorageAccess>(
         .partition_key(invocation_id.partition_key())
         .invocation_uuid(invocation_id.invocation_uuid());
 
+    let storage = &*storage;
     let mut n = 0;
     storage.for_each_key_value_in_place(
         TableScan::SinglePartitionKeyPrefix(invocation_id.partition_key(), key),
@@ -82,8 +172,11 @@ fn get_journal<S: StorageAccess>(
                     .journal_index
                     .expect("The journal index must be part of the journal key.")
             });
-            let entry = StorageCodec::decode::<JournalEntry, _>(&mut v)
-                .map_err(|error| StorageError::Generic(error.into()));
+            let entry = StorageCodec::decode::<StoredJournalEntry, _>(&mut v)
+                .map_err(|error| StorageError::Generic(error.into()))
+                .and_then(|stored_journal_entry| {
+                    resolve_journal_entry(storage, invocation_id, stored_journal_entry)
+                });
 
             let result = key.and_then(|key| entry.map(|entry| (key, entry)));
 
@@ -108,6 +201,21 @@ fn delete_journal<S: StorageAccess>(
         k.journal_index = Some(journal_index);
         storage.delete_key(k);
     }
+
+    // Blobs are keyed by invocation, this also removes the blobs of overwritten entries
+    let blob_keys_prefix = JournalBlobKey::default()
+        .partition_key(invocation_id.partition_key())
+        .invocation_uuid(invocation_id.invocation_uuid());
+    let blob_keys = storage.for_each_key_value_in_place(
+        TableScan::SinglePartitionKeyPrefix(invocation_id.partition_key(), blob_keys_prefix),
+        |k, _| TableScanIterationDecision::Emit(Ok(Bytes::copy_from_slice(k))),
+    );
+    for blob_key in blob_keys {
+        storage.delete_cf(
+            JournalBlob,
+            blob_key.expect("emitting blob keys cannot fail"),
+        );
+    }
 }
 
 impl ReadOnlyJournalTable for PartitionStore {
@@ -177,17 +285,20 @@ impl PartitionStore {
         OwnedIterator::new(iter).map(|(mut key, mut value)| {
             let journal_key = JournalKey::deserialize_from(&mut key)
                 .expect("journal key must deserialize into JournalKey");
-            let journal_entry = StorageCodec::decode::<JournalEntry, _>(&mut value)
+            let invocation_id = InvocationId::from_parts(
+                journal_key
+                    .partition_key
+                    .expect("journal key must have a partition key"),
+                journal_key
+                    .invocation_uuid
+                    .expect("journal key must have an invocation uuid"),
+            );
+            let stored_journal_entry = StorageCodec::decode::<StoredJournalEntry, _>(&mut value)
                 .expect("journal entry must deserialize into JournalEntry");
+            let journal_entry = resolve_journal_entry(self, &invocation_id, stored_journal_entry)
+                .expect("journal entry blob must exist");
             OwnedJournalRow {
-                invocation_id: InvocationId::from_parts(
-                    journal_key
-                        .partition_key
-                        .expect("journal key must have a partition key"),
-                    journal_key
-                        .invocation_uuid
-                        .expect("journal key must have an invocation uuid"),
-                ),
+                invocation_id,
                 journal_index: journal_key
                     .journal_index
                     .expect("journal key must have an index"),
@@ -25,15 +25,20 @@ use strum_macros::EnumIter;
     Debug, Copy, Clone, Eq, PartialEq, EnumIter, derive_more::Display, strum_macros::VariantArray,
 )]
 pub enum KeyKind {
+    DeadLetter,
     Deduplication,
     Fsm,
     Idempotency,
     Inbox,
     InvocationStatus,
     Journal,
+    JournalBlob,
     Outbox,
+    PriorityInbox,
+    Promise,
     ServiceStatus,
     State,
+    StateExpiration,
     Timers,
 }
 
@@ -63,15 +68,20 @@ impl KeyKind {
         // NOTE: do not use &[0xff, 0xff] as key byte prefix, ever!
         // We should always be able to +1 the those bytes when interpreted as u16
         match self {
+            KeyKind::DeadLetter => b"dl",
             KeyKind::Deduplication => b"de",
             KeyKind::Fsm => b"fs",
             KeyKind::Idempotency => b"ip",
             KeyKind::Inbox => b"ib",
             KeyKind::InvocationStatus => b"is",
             KeyKind::Journal => b"jo",
+            KeyKind::JournalBlob => b"jb",
             KeyKind::Outbox => b"ob",
+            KeyKind::PriorityInbox => b"pi",
+            KeyKind::Promise => b"pr",
             KeyKind::ServiceStatus => b"ss",
             KeyKind::State => b"st",
+            KeyKind::StateExpiration => b"sx",
             KeyKind::Timers => b"ti",
         }
     }
@@ -86,15 +96,20 @@ impl KeyKind {
     /// ```
     pub const fn from_bytes(bytes: &[u8; Self::SERIALIZED_LENGTH]) -> Option<Self> {
         match bytes {
+            b"dl" => Some(KeyKind::DeadLetter),
             b"de" => Some(KeyKind::Deduplication),
             b"fs" => Some(KeyKind::Fsm),
             b"ip" => Some(KeyKind::Idempotency),
             b"ib" => Some(KeyKind::Inbox),
             b"is" => Some(KeyKind::InvocationStatus),
             b"jo" => Some(KeyKind::Journal),
+            b"jb" => Some(KeyKind::JournalBlob),
             b"ob" => Some(KeyKind::Outbox),
+            b"pi" => Some(KeyKind::PriorityInbox),
+            b"pr" => Some(KeyKind::Promise),
             b"ss" => Some(KeyKind::ServiceStatus),
             b"st" => Some(KeyKind::State),
+            b"sx" => Some(KeyKind::StateExpiration),
             b"ti" => Some(KeyKind::Timers),
             _ => None,
         }
@@ -306,6 +321,7 @@ macro_rules! define_table_key {
 use crate::TableKind;
 pub(crate) use define_table_key;
 use restate_storage_api::deduplication_table::ProducerId;
+use restate_storage_api::inbox_table::InboxPriority;
 use restate_storage_api::timer_table::TimerKeyKind;
 use restate_storage_api::StorageError;
 use restate_types::identifiers::{InvocationUuid, PartitionId};
@@ -487,6 +503,39 @@ impl KeyCodec for ProducerId {
     }
 }
 
+impl KeyCodec for InboxPriority {
+    fn encode<B: BufMut>(&self, target: &mut B) {
+        // encoded in the order in which the inbox lanes are dequeued
+        match self {
+            InboxPriority::Interactive => target.put_u8(0),
+            InboxPriority::Background => target.put_u8(1),
+        }
+    }
+
+    fn decode<B: Buf>(source: &mut B) -> crate::partition_store::Result<Self> {
+        if source.remaining() < mem::size_of::<u8>() {
+            return Err(StorageError::Generic(anyhow!(
+                "InboxPriority discriminator byte is missing"
+            )));
+        }
+
+        Ok(match source.get_u8() {
+            0 => InboxPriority::Interactive,
+            1 => InboxPriority::Background,
+            i => {
+                return Err(StorageError::Generic(anyhow!(
+                    "Unknown discriminator for InboxPriority: '{}'",
+                    i
+                )))
+            }
+        })
+    }
+
+    fn serialized_length(&self) -> usize {
+        1
+    }
+}
+
 impl KeyCodec for TimerKeyKind {
     fn encode<B: BufMut>(&self, target: &mut B) {
         assert!(
@@ -511,6 +560,26 @@ impl KeyCodec for TimerKeyKind {
                 target.put_u8(2);
                 invocation_uuid.encode(target);
             }
+            TimerKeyKind::ExpireState {
+                invocation_uuid,
+                journal_index,
+            } => {
+                target.put_u8(3);
+                invocation_uuid.encode(target);
+                journal_index.encode(target);
+            }
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => {
+                target.put_u8(4);
+                invocation_uuid.encode(target);
+            }
+            TimerKeyKind::CleanDeadLetter { invocation_uuid } => {
+                target.put_u8(5);
+                invocation_uuid.encode(target);
+            }
+            TimerKeyKind::ResumeInvocation { invocation_uuid } => {
+                target.put_u8(6);
+                invocation_uuid.encode(target);
+            }
         }
     }
 
@@ -538,6 +607,26 @@ impl KeyCodec for TimerKeyKind {
                 let invocation_uuid = InvocationUuid::decode(source)?;
                 TimerKeyKind::CleanInvocationStatus { invocation_uuid }
             }
+            3 => {
+                let invocation_uuid = InvocationUuid::decode(source)?;
+                let journal_index = u32::decode(source)?;
+                TimerKeyKind::ExpireState {
+                    invocation_uuid,
+                    journal_index,
+                }
+            }
+            4 => {
+                let invocation_uuid = InvocationUuid::decode(source)?;
+                TimerKeyKind::InvocationDeadline { invocation_uuid }
+            }
+            5 => {
+                let invocation_uuid = InvocationUuid::decode(source)?;
+                TimerKeyKind::CleanDeadLetter { invocation_uuid }
+            }
+            6 => {
+                let invocation_uuid = InvocationUuid::decode(source)?;
+                TimerKeyKind::ResumeInvocation { invocation_uuid }
+            }
             i => {
                 return Err(StorageError::Generic(anyhow!(
                     "Unknown discriminator for TimerKind: '{}'",
@@ -562,6 +651,18 @@ impl KeyCodec for TimerKeyKind {
             TimerKeyKind::CleanInvocationStatus { invocation_uuid } => {
                 KeyCodec::serialized_length(invocation_uuid)
             }
+            TimerKeyKind::ExpireState {
+                invocation_uuid,
+                journal_index,
+            } => {
+                KeyCodec::serialized_length(invocation_uuid)
+                    + KeyCodec::serialized_length(journal_index)
+            }
+            TimerKeyKind::InvocationDeadline { invocation_uuid }
+            | TimerKeyKind::CleanDeadLetter { invocation_uuid }
+            | TimerKeyKind::ResumeInvocation { invocation_uuid } => {
+                KeyCodec::serialized_length(invocation_uuid)
+            }
         }
     }
 }
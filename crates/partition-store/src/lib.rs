@@ -8,6 +8,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod checkpoint;
+pub mod dead_letter_table;
 pub mod deduplication_table;
 pub mod fsm_table;
 pub mod idempotency_table;
@@ -19,12 +21,16 @@ pub mod outbox_table;
 mod owned_iter;
 mod partition_store;
 mod partition_store_manager;
+pub mod promise_table;
 pub mod scan;
 pub mod service_status_table;
+mod split;
 pub mod state_table;
 pub mod timer_table;
 
+pub use checkpoint::PartitionStoreCheckpoint;
 pub use partition_store::*;
 pub use partition_store_manager::*;
+pub use split::{SplitFsmVariables, SplitTarget};
 
 use crate::scan::TableScan;
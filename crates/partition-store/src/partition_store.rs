@@ -31,6 +31,7 @@ use restate_core::ShutdownError;
 use restate_rocksdb::{RocksDb, RocksError};
 use restate_storage_api::{Storage, StorageError, Transaction};
 
+use restate_types::config::RocksDbOptions;
 use restate_types::identifiers::{PartitionId, PartitionKey};
 use restate_types::storage::{StorageCodec, StorageDecode, StorageEncode};
 
@@ -81,21 +82,27 @@ pub enum TableKind {
     Idempotency,
     Inbox,
     Journal,
+    JournalBlob,
+    DeadLetter,
+    Promise,
 }
 
 impl TableKind {
     pub const fn key_kinds(self) -> &'static [KeyKind] {
         match self {
-            Self::State => &[KeyKind::State],
+            Self::State => &[KeyKind::State, KeyKind::StateExpiration],
             Self::InvocationStatus => &[KeyKind::InvocationStatus],
             Self::ServiceStatus => &[KeyKind::ServiceStatus],
             Self::Idempotency => &[KeyKind::Idempotency],
-            Self::Inbox => &[KeyKind::Inbox],
+            Self::Inbox => &[KeyKind::PriorityInbox, KeyKind::Inbox],
             Self::Outbox => &[KeyKind::Outbox],
             Self::Deduplication => &[KeyKind::Deduplication],
             Self::PartitionStateMachine => &[KeyKind::Fsm],
             Self::Timers => &[KeyKind::Timers],
             Self::Journal => &[KeyKind::Journal],
+            Self::JournalBlob => &[KeyKind::JournalBlob],
+            Self::DeadLetter => &[KeyKind::DeadLetter],
+            Self::Promise => &[KeyKind::Promise],
         }
     }
 
@@ -140,7 +147,9 @@ pub struct PartitionStore {
     rocksdb: Arc<RocksDb>,
     partition_id: PartitionId,
     data_cf_name: CfName,
+    blobs_cf_name: CfName,
     key_range: RangeInclusive<PartitionKey>,
+    journal_blob_threshold: usize,
     key_buffer: BytesMut,
     value_buffer: BytesMut,
 }
@@ -151,6 +160,7 @@ impl std::fmt::Debug for PartitionStore {
             .field("db", &self.raw_db)
             .field("partition_id", &self.partition_id)
             .field("cf", &self.data_cf_name)
+            .field("blobs_cf", &self.blobs_cf_name)
             .field("key_buffer", &self.key_buffer.len())
             .field("value_buffer", &self.value_buffer.len())
             .finish()
@@ -164,14 +174,21 @@ impl Clone for PartitionStore {
             rocksdb: self.rocksdb.clone(),
             partition_id: self.partition_id,
             data_cf_name: self.data_cf_name.clone(),
+            blobs_cf_name: self.blobs_cf_name.clone(),
             key_range: self.key_range.clone(),
+            journal_blob_threshold: self.journal_blob_threshold,
             key_buffer: BytesMut::default(),
             value_buffer: BytesMut::default(),
         }
     }
 }
 
-pub(crate) fn cf_options(mut cf_options: rocksdb::Options) -> rocksdb::Options {
+/// `configured_compression` is true if the compression per level is configured through
+/// `RocksDbOptions`, our defaults must not override it in that case.
+pub(crate) fn cf_options(
+    opts: &RocksDbOptions,
+    mut cf_options: rocksdb::Options,
+) -> rocksdb::Options {
     // Actually, we would love to use CappedPrefixExtractor but unfortunately it's neither exposed
     // in the C API nor the rust binding. That's okay and we can change it later.
     cf_options.set_prefix_extractor(SliceTransform::create_fixed_prefix(DB_PREFIX_LENGTH));
@@ -185,15 +202,17 @@ pub(crate) fn cf_options(mut cf_options: rocksdb::Options) -> rocksdb::Options {
     // Set compactions per level
     //
     cf_options.set_num_levels(7);
-    cf_options.set_compression_per_level(&[
-        DBCompressionType::None,
-        DBCompressionType::Snappy,
-        DBCompressionType::Snappy,
-        DBCompressionType::Snappy,
-        DBCompressionType::Snappy,
-        DBCompressionType::Snappy,
-        DBCompressionType::Zstd,
-    ]);
+    if opts.rocksdb_compression_per_level().is_none() {
+        cf_options.set_compression_per_level(&[
+            DBCompressionType::None,
+            DBCompressionType::Snappy,
+            DBCompressionType::Snappy,
+            DBCompressionType::Snappy,
+            DBCompressionType::Snappy,
+            DBCompressionType::Snappy,
+            DBCompressionType::Zstd,
+        ]);
+    }
 
     cf_options
 }
@@ -209,15 +228,19 @@ impl PartitionStore {
         raw_db: Arc<DB>,
         rocksdb: Arc<RocksDb>,
         data_cf_name: CfName,
+        blobs_cf_name: CfName,
         partition_id: PartitionId,
         key_range: RangeInclusive<PartitionKey>,
+        journal_blob_threshold: usize,
     ) -> Self {
         Self {
             raw_db,
             rocksdb,
             partition_id,
             data_cf_name,
+            blobs_cf_name,
             key_range,
+            journal_blob_threshold,
             key_buffer: BytesMut::new(),
             value_buffer: BytesMut::new(),
         }
@@ -231,8 +254,40 @@ impl PartitionStore {
         self.key_range.contains(&key)
     }
 
+    /// Deletes all keys in `[from, to)` of the table with a single range tombstone. The deletion
+    /// bypasses transactions, use it to drop whole key ranges (e.g. a service or a journal)
+    /// that are no longer referenced.
+    pub async fn delete_range(
+        &self,
+        table: TableKind,
+        from: impl AsRef<[u8]>,
+        to: impl AsRef<[u8]>,
+    ) -> Result<()> {
+        let mut opts = rocksdb::WriteOptions::default();
+        // We disable WAL since bifrost is our durable distributed log.
+        opts.disable_wal(true);
+        self.rocksdb
+            .delete_range(
+                Priority::High,
+                self.cf_name(table).clone(),
+                from.as_ref().to_vec(),
+                to.as_ref().to_vec(),
+                opts,
+            )
+            .await
+            .map_err(|error| StorageError::Generic(error.into()))
+    }
+
+    fn cf_name(&self, table_kind: TableKind) -> &CfName {
+        // Journal blobs are kept apart, everything else is in the data cf
+        match table_kind {
+            TableKind::JournalBlob => &self.blobs_cf_name,
+            _ => &self.data_cf_name,
+        }
+    }
+
     fn table_handle(&self, table_kind: TableKind) -> Arc<BoundColumnFamily> {
-        find_cf_handle(&self.rocksdb, &self.data_cf_name, table_kind)
+        find_cf_handle(&self.rocksdb, self.cf_name(table_kind))
     }
 
     fn prefix_iterator(&self, table: TableKind, _key_kind: KeyKind, prefix: Bytes) -> DBIterator {
@@ -311,38 +366,27 @@ impl PartitionStore {
     #[allow(clippy::needless_lifetimes)]
     pub fn transaction(&mut self) -> RocksDBTransaction {
         let rocksdb = self.rocksdb.clone();
-        // An optimization to avoid looking up the cf handle everytime, if we split into more
+        // An optimization to avoid looking up the cf handles everytime, if we split into more
         // column families, we will need to cache those cfs here as well.
-        let data_cf_handle = self
-            .rocksdb
-            .inner()
-            .cf_handle(&self.data_cf_name)
-            .unwrap_or_else(|| {
-                panic!(
-                    "Access a column family that must exist: {}",
-                    &self.data_cf_name
-                )
-            });
+        let data_cf_handle = find_cf_handle(&self.rocksdb, &self.data_cf_name);
+        let blobs_cf_handle = find_cf_handle(&self.rocksdb, &self.blobs_cf_name);
 
         RocksDBTransaction {
             txn: self.raw_db.transaction(),
             data_cf_handle,
+            blobs_cf_handle,
             rocksdb,
+            journal_blob_threshold: self.journal_blob_threshold,
             key_buffer: &mut self.key_buffer,
             value_buffer: &mut self.value_buffer,
         }
     }
 }
 
-fn find_cf_handle<'a>(
-    db: &'a Arc<RocksDb>,
-    data_cf_name: &CfName,
-    _table_kind: TableKind,
-) -> Arc<BoundColumnFamily<'a>> {
-    // At the moment, everything is in one cf
+fn find_cf_handle<'a>(db: &'a Arc<RocksDb>, cf_name: &CfName) -> Arc<BoundColumnFamily<'a>> {
     db.inner()
-        .cf_handle(data_cf_name)
-        .unwrap_or_else(|| panic!("Access a column family that must exist: {}", data_cf_name))
+        .cf_handle(cf_name)
+        .unwrap_or_else(|| panic!("Access a column family that must exist: {}", cf_name))
 }
 
 impl Storage for PartitionStore {
@@ -379,6 +423,11 @@ impl StorageAccess for PartitionStore {
         &mut self.value_buffer
     }
 
+    #[inline]
+    fn journal_blob_threshold(&self) -> usize {
+        self.journal_blob_threshold
+    }
+
     #[inline]
     fn get<K: AsRef<[u8]>>(&self, table: TableKind, key: K) -> Result<Option<DBPinnableSlice>> {
         let table = self.table_handle(table);
@@ -414,6 +463,8 @@ pub struct RocksDBTransaction<'a> {
     txn: rocksdb::Transaction<'a, DB>,
     rocksdb: Arc<RocksDb>,
     data_cf_handle: Arc<BoundColumnFamily<'a>>,
+    blobs_cf_handle: Arc<BoundColumnFamily<'a>>,
+    journal_blob_threshold: usize,
     key_buffer: &'a mut BytesMut,
     value_buffer: &'a mut BytesMut,
 }
@@ -457,9 +508,12 @@ impl<'a> RocksDBTransaction<'a> {
         it
     }
 
-    pub(crate) fn table_handle(&self, _table_kind: TableKind) -> &Arc<BoundColumnFamily> {
-        // Right now, everything is in one cf, return a reference and save CPU.
-        &self.data_cf_handle
+    pub(crate) fn table_handle(&self, table_kind: TableKind) -> &Arc<BoundColumnFamily> {
+        // Return a reference to the cached handles and save CPU.
+        match table_kind {
+            TableKind::JournalBlob => &self.blobs_cf_handle,
+            _ => &self.data_cf_handle,
+        }
     }
 }
 
@@ -536,6 +590,11 @@ impl<'a> StorageAccess for RocksDBTransaction<'a> {
         self.value_buffer
     }
 
+    #[inline]
+    fn journal_blob_threshold(&self) -> usize {
+        self.journal_blob_threshold
+    }
+
     #[inline]
     fn get<K: AsRef<[u8]>>(&self, table: TableKind, key: K) -> Result<Option<DBPinnableSlice>> {
         let table = self.table_handle(table);
@@ -571,6 +630,9 @@ pub(crate) trait StorageAccess {
 
     fn cleared_value_buffer_mut(&mut self, min_size: usize) -> &mut BytesMut;
 
+    /// Journal entries whose encoded size exceeds the threshold are stored as blobs.
+    fn journal_blob_threshold(&self) -> usize;
+
     fn get<K: AsRef<[u8]>>(&self, table: TableKind, key: K) -> Result<Option<DBPinnableSlice>>;
 
     fn put_cf(&mut self, table: TableKind, key: impl AsRef<[u8]>, value: impl AsRef<[u8]>);
@@ -10,6 +10,7 @@
 
 use std::collections::BTreeMap;
 use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use tokio::sync::Mutex;
@@ -17,8 +18,9 @@ use tracing::debug;
 
 use restate_core::ShutdownError;
 use restate_rocksdb::{
-    CfName, CfPrefixPattern, DbName, DbSpecBuilder, RocksDb, RocksDbManager, RocksError,
+    CfName, CfPrefixPattern, DbName, DbSpecBuilder, KeyRange, RocksDb, RocksDbManager, RocksError,
 };
+use restate_storage_api::StorageError;
 use restate_types::arc_util::Updateable;
 use restate_types::config::RocksDbOptions;
 use restate_types::config::StorageOptions;
@@ -26,11 +28,18 @@ use restate_types::identifiers::PartitionId;
 use restate_types::identifiers::PartitionKey;
 
 use crate::cf_options;
+use crate::checkpoint::PartitionStoreCheckpoint;
+use crate::keys::KeyKind;
+use crate::split::{
+    split_cf, write_seq_numbers, SplitFsmVariables, SplitSequenceNumbers, SplitTarget,
+};
 use crate::PartitionStore;
+use crate::TableKind;
 use crate::DB;
 
-const DB_NAME: &str = "db";
-const PARTITION_CF_PREFIX: &str = "data-";
+pub(crate) const DB_NAME: &str = "db";
+pub(crate) const PARTITION_CF_PREFIX: &str = "data-";
+pub(crate) const PARTITION_BLOBS_CF_PREFIX: &str = "blobs-";
 
 /// Controls how a partition store is opened
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -44,6 +53,7 @@ pub struct PartitionStoreManager {
     lookup: Arc<Mutex<PartitionLookup>>,
     rocksdb: Arc<RocksDb>,
     raw_db: Arc<DB>,
+    journal_blob_threshold: usize,
 }
 
 #[derive(Default, Debug)]
@@ -59,8 +69,21 @@ impl PartitionStoreManager {
     ) -> std::result::Result<Self, RocksError> {
         let options = storage_opts.load();
 
-        let db_spec = DbSpecBuilder::new(DbName::new(DB_NAME), options.data_dir(), db_options())
-            .add_cf_pattern(CfPrefixPattern::new(PARTITION_CF_PREFIX), cf_options)
+        let mut db_spec =
+            DbSpecBuilder::new(DbName::new(DB_NAME), options.data_dir(), db_options())
+                .add_cf_pattern(CfPrefixPattern::new(PARTITION_CF_PREFIX), cf_options)
+                .add_cf_pattern(CfPrefixPattern::new(PARTITION_BLOBS_CF_PREFIX), cf_options);
+        // inbox, outbox and timers see a delete for every insert
+        for key_kind in [TableKind::Inbox, TableKind::Outbox, TableKind::Timers]
+            .into_iter()
+            .flat_map(TableKind::key_kinds)
+        {
+            db_spec = db_spec.add_tombstone_heavy_key_range(
+                CfPrefixPattern::new(PARTITION_CF_PREFIX),
+                key_kind_range(*key_kind),
+            );
+        }
+        let db_spec = db_spec
             .ensure_column_families(partition_ids_to_cfs(initial_partition_set))
             .build_as_optimistic_db();
 
@@ -76,6 +99,7 @@ impl PartitionStoreManager {
             raw_db,
             rocksdb,
             lookup: Arc::default(),
+            journal_blob_threshold: options.journal_blob_threshold(),
         })
     }
 
@@ -105,38 +129,255 @@ impl PartitionStoreManager {
         if !already_exists {
             if open_mode == OpenMode::CreateIfMissing {
                 debug!("Initializing storage for partition {}", partition_id);
-                self.rocksdb.open_cf(cf_name.clone(), opts).await?;
+                self.rocksdb.open_cf(cf_name, opts).await?;
             } else {
                 return Err(RocksError::AlreadyOpen);
             }
         }
+        let blobs_cf_name = blobs_cf_for_partition(partition_id);
+        if self.rocksdb.inner().cf_handle(&blobs_cf_name).is_none() {
+            // partition stores created by older versions have no blobs cf yet
+            self.rocksdb.open_cf(blobs_cf_name, opts).await?;
+        }
+
+        let partition_store = self.new_partition_store(partition_id, partition_key_range);
+        guard.live.insert(partition_id, partition_store.clone());
 
-        let partition_store = PartitionStore::new(
+        Ok(partition_store)
+    }
+
+    fn new_partition_store(
+        &self,
+        partition_id: PartitionId,
+        partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> PartitionStore {
+        PartitionStore::new(
             self.raw_db.clone(),
             self.rocksdb.clone(),
-            cf_name,
+            cf_for_partition(partition_id),
+            blobs_cf_for_partition(partition_id),
             partition_id,
             partition_key_range,
-        );
+            self.journal_blob_threshold,
+        )
+    }
+
+    /// Drops the column family if it exists and creates it anew.
+    async fn recreate_cf(
+        &self,
+        cf_name: CfName,
+        opts: &RocksDbOptions,
+    ) -> std::result::Result<(), RocksError> {
+        if self.rocksdb.inner().cf_handle(&cf_name).is_some() {
+            self.rocksdb.drop_cf(cf_name.clone()).await?;
+        }
+        self.rocksdb.open_cf(cf_name, opts).await
+    }
+
+    /// Returns true if the partition has local storage, whether or not it's open.
+    pub fn partition_store_exists(&self, partition_id: PartitionId) -> bool {
+        self.rocksdb
+            .inner()
+            .cf_handle(&cf_for_partition(partition_id))
+            .is_some()
+    }
+
+    /// Replaces the storage of a partition with the data and journal blobs exported from a
+    /// checkpoint (see [`PartitionStoreCheckpoint::export_partition`] and
+    /// [`PartitionStoreCheckpoint::export_partition_blobs`]) and opens it. Existing data of the
+    /// partition is dropped, `None` leaves the respective column family empty. The partition
+    /// store must not be in use while it is being imported.
+    pub async fn import_partition_store(
+        &self,
+        partition_id: PartitionId,
+        partition_key_range: RangeInclusive<PartitionKey>,
+        data_file: Option<PathBuf>,
+        blobs_file: Option<PathBuf>,
+        opts: &RocksDbOptions,
+    ) -> std::result::Result<PartitionStore, RocksError> {
+        let mut guard = self.lookup.lock().await;
+        guard.live.remove(&partition_id);
+
+        debug!("Dropping existing storage of partition {}", partition_id);
+        for (cf_name, file) in [
+            (cf_for_partition(partition_id), data_file),
+            (blobs_cf_for_partition(partition_id), blobs_file),
+        ] {
+            self.recreate_cf(cf_name.clone(), opts).await?;
+
+            if let Some(file) = file {
+                debug!(
+                    "Importing storage of partition {} from {}",
+                    partition_id,
+                    file.display()
+                );
+                self.rocksdb
+                    .ingest_external_files(cf_name, vec![file])
+                    .await?;
+            }
+        }
+
+        let partition_store = self.new_partition_store(partition_id, partition_key_range);
         guard.live.insert(partition_id, partition_store.clone());
 
         Ok(partition_store)
     }
+
+    /// Splits the partition store into the stores of the two target partitions and opens them.
+    /// Data and journal blobs are distributed by partition key, the outbox must be empty. Only
+    /// the inherited `fsm_variables` of the state machine are copied into both partitions, the
+    /// inbox and outbox sequence numbers are recomputed for each of them. Existing data of the
+    /// target partitions is dropped. The source partition store is left untouched and must not be
+    /// written to while it's being split.
+    pub async fn split_partition_store(
+        &self,
+        partition_id: PartitionId,
+        left: SplitTarget,
+        right: SplitTarget,
+        fsm_variables: SplitFsmVariables,
+        opts: &RocksDbOptions,
+    ) -> std::result::Result<(PartitionStore, PartitionStore), StorageError> {
+        let mut guard = self.lookup.lock().await;
+        let cf_names = |partition_id| {
+            [
+                cf_for_partition(partition_id),
+                blobs_cf_for_partition(partition_id),
+            ]
+        };
+        let source_cfs = cf_names(partition_id);
+        let left_cfs = cf_names(left.partition_id);
+        let right_cfs = cf_names(right.partition_id);
+
+        for (target_id, target_cfs) in [
+            (left.partition_id, &left_cfs),
+            (right.partition_id, &right_cfs),
+        ] {
+            guard.live.remove(&target_id);
+            debug!("Dropping existing storage of partition {}", target_id);
+            for target_cf in target_cfs {
+                self.recreate_cf(target_cf.clone(), opts)
+                    .await
+                    .map_err(|err| StorageError::Generic(err.into()))?;
+            }
+        }
+
+        let raw_db = self.raw_db.clone();
+        let rocksdb = self.rocksdb.clone();
+        let (left, right) = tokio::task::spawn_blocking(move || {
+            let mut seq_numbers = SplitSequenceNumbers::default();
+            for ((source_cf, left_cf), right_cf) in source_cfs.iter().zip(&left_cfs).zip(&right_cfs)
+            {
+                split_cf(
+                    &raw_db,
+                    source_cf,
+                    [(&left, left_cf), (&right, right_cf)],
+                    &fsm_variables,
+                    &mut seq_numbers,
+                )?;
+            }
+            write_seq_numbers(
+                &raw_db,
+                [(&left, &left_cfs[0]), (&right, &right_cfs[0])],
+                &fsm_variables,
+                &seq_numbers,
+            )?;
+            // partition stores are written without WAL, persist the split before the
+            // source partition store can be dropped
+            rocksdb
+                .inner()
+                .flush_memtables(&[left_cfs, right_cfs].concat(), true)
+                .map_err(|err| StorageError::Generic(err.into()))?;
+            Ok::<_, StorageError>((left, right))
+        })
+        .await
+        .map_err(|_| StorageError::OperationalError)??;
+
+        let mut open = |target: SplitTarget| {
+            let partition_store =
+                self.new_partition_store(target.partition_id, target.partition_key_range);
+            guard
+                .live
+                .insert(target.partition_id, partition_store.clone());
+            partition_store
+        };
+
+        Ok((open(left), open(right)))
+    }
+
+    /// Closes the partition store and drops all of its data.
+    pub async fn drop_partition_store(
+        &self,
+        partition_id: PartitionId,
+    ) -> std::result::Result<(), RocksError> {
+        let mut guard = self.lookup.lock().await;
+        guard.live.remove(&partition_id);
+
+        for cf_name in [
+            cf_for_partition(partition_id),
+            blobs_cf_for_partition(partition_id),
+        ] {
+            if self.rocksdb.inner().cf_handle(&cf_name).is_some() {
+                debug!("Dropping storage of partition {}", partition_id);
+                self.rocksdb.drop_cf(cf_name).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the partition ids and key ranges of all open partition stores.
+    pub async fn get_live_partitions(&self) -> Vec<(PartitionId, RangeInclusive<PartitionKey>)> {
+        self.lookup
+            .lock()
+            .await
+            .live
+            .iter()
+            .map(|(partition_id, store)| (*partition_id, store.partition_key_range().clone()))
+            .collect()
+    }
+
+    /// Creates a consistent checkpoint of all partition stores in `path` and opens it read-only.
+    /// The directory must not exist.
+    pub async fn open_checkpoint(
+        &self,
+        path: PathBuf,
+        opts: &RocksDbOptions,
+    ) -> std::result::Result<PartitionStoreCheckpoint, RocksError> {
+        self.rocksdb.create_checkpoint(path.clone()).await?;
+        PartitionStoreCheckpoint::open(path, opts.clone()).await
+    }
 }
 
-fn cf_for_partition(partition_id: PartitionId) -> CfName {
+pub(crate) fn cf_for_partition(partition_id: PartitionId) -> CfName {
     CfName::from(format!("{}{}", PARTITION_CF_PREFIX, partition_id))
 }
 
+pub(crate) fn blobs_cf_for_partition(partition_id: PartitionId) -> CfName {
+    CfName::from(format!("{}{}", PARTITION_BLOBS_CF_PREFIX, partition_id))
+}
+
 #[inline]
 fn partition_ids_to_cfs<T>(partition_ids: &[(PartitionId, T)]) -> Vec<CfName> {
     partition_ids
         .iter()
-        .map(|(partition, _)| cf_for_partition(*partition))
+        .flat_map(|(partition, _)| {
+            [
+                cf_for_partition(*partition),
+                blobs_cf_for_partition(*partition),
+            ]
+        })
         .collect()
 }
 
-fn db_options() -> rocksdb::Options {
+/// The keys of the given kind. The upper bound is inclusive, it covers the kind's exclusive upper
+/// bound as well, which is never a key of its own.
+fn key_kind_range(key_kind: KeyKind) -> KeyRange {
+    KeyRange {
+        from: Some(key_kind.as_bytes().to_vec()),
+        to: Some(key_kind.exclusive_upper_bound().to_vec()),
+    }
+}
+
+pub(crate) fn db_options() -> rocksdb::Options {
     let mut db_options = rocksdb::Options::default();
     // no need to retain 1000 log files by default.
     //
@@ -0,0 +1,102 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::keys::{define_table_key, KeyKind};
+use crate::{PartitionStore, TableKind, TableScan, TableScanIterationDecision};
+use crate::{RocksDBTransaction, StorageAccess};
+use bytes::Bytes;
+use bytestring::ByteString;
+use restate_storage_api::promise_table::{Promise, PromiseTable, ReadOnlyPromiseTable};
+use restate_storage_api::Result;
+use restate_types::identifiers::{PartitionKey, ServiceId, WithPartitionKey};
+
+define_table_key!(
+    TableKind::Promise,
+    KeyKind::Promise,
+    PromiseKey(
+        partition_key: PartitionKey,
+        service_name: ByteString,
+        service_key: ByteString,
+        key: ByteString
+    )
+);
+
+fn create_key(service_id: &ServiceId, key: &ByteString) -> PromiseKey {
+    PromiseKey::default()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone())
+        .key(key.clone())
+}
+
+fn get_promise<S: StorageAccess>(
+    storage: &mut S,
+    service_id: &ServiceId,
+    key: &ByteString,
+) -> Result<Option<Promise>> {
+    storage.get_value(create_key(service_id, key))
+}
+
+fn put_promise<S: StorageAccess>(
+    storage: &mut S,
+    service_id: &ServiceId,
+    key: &ByteString,
+    promise: Promise,
+) {
+    storage.put_kv(create_key(service_id, key), promise);
+}
+
+fn delete_all_promises<S: StorageAccess>(storage: &mut S, service_id: &ServiceId) -> Result<()> {
+    let prefix_key = PromiseKey::default()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone());
+
+    let keys = storage.for_each_key_value_in_place(
+        TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), prefix_key),
+        |k, _| TableScanIterationDecision::Emit(Ok(Bytes::copy_from_slice(k))),
+    );
+
+    for k in keys {
+        storage.delete_cf(TableKind::Promise, &k?);
+    }
+
+    Ok(())
+}
+
+impl ReadOnlyPromiseTable for PartitionStore {
+    async fn get_promise(
+        &mut self,
+        service_id: &ServiceId,
+        key: &ByteString,
+    ) -> Result<Option<Promise>> {
+        get_promise(self, service_id, key)
+    }
+}
+
+impl<'a> ReadOnlyPromiseTable for RocksDBTransaction<'a> {
+    async fn get_promise(
+        &mut self,
+        service_id: &ServiceId,
+        key: &ByteString,
+    ) -> Result<Option<Promise>> {
+        get_promise(self, service_id, key)
+    }
+}
+
+impl<'a> PromiseTable for RocksDBTransaction<'a> {
+    async fn put_promise(&mut self, service_id: &ServiceId, key: &ByteString, promise: Promise) {
+        put_promise(self, service_id, key, promise)
+    }
+
+    async fn delete_all_promises(&mut self, service_id: &ServiceId) -> Result<()> {
+        delete_all_promises(self, service_id)
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Splitting a partition store in two by partition key.
+//!
+//! Tables keyed by partition key are copied as is into the partition owning the key. Tables keyed
+//! by partition id are re-keyed:
+//!
+//! * timers go to the partition owning the partition key of the timer
+//! * deduplication information is inherited by both partitions, except for the epoch sequence
+//!   number of the partition itself since every partition has its own leader epochs
+//! * the outbox must be empty: receivers deduplicate messages by producer partition and sequence
+//!   number, the new partitions couldn't send pending messages under the ids of the source
+//! * state machine variables are partition specific since the new partitions read their own
+//!   logs, only the explicitly inherited ones are copied into both partitions. The inbox
+//!   sequence numbers are recomputed for each partition from the entries it received, the outbox
+//!   sequence numbers start from scratch
+
+use std::io::Cursor;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use bytes::BytesMut;
+use rocksdb::{
+    BoundColumnFamily, IteratorMode, ReadOptions, WriteBatchWithTransaction, WriteOptions,
+};
+use tracing::debug;
+
+use restate_rocksdb::{CfName, RocksError};
+use restate_storage_api::deduplication_table::ProducerId;
+use restate_storage_api::fsm_table::SequenceNumber;
+use restate_storage_api::timer_table::Timer;
+use restate_storage_api::{Result, StorageError};
+use restate_types::identifiers::{PartitionId, PartitionKey, WithPartitionKey};
+use restate_types::storage::StorageCodec;
+
+use crate::deduplication_table::DeduplicationKey;
+use crate::fsm_table::PartitionStateMachineKey;
+use crate::inbox_table::{InboxKey, LegacyInboxKey};
+use crate::keys::{KeyCodec, KeyKind, TableKey};
+use crate::outbox_table::OutboxKey;
+use crate::timer_table::TimersKey;
+use crate::DB;
+
+const WRITE_BATCH_SIZE: usize = 1024;
+
+/// One of the two partitions a partition is split into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitTarget {
+    pub partition_id: PartitionId,
+    pub partition_key_range: RangeInclusive<PartitionKey>,
+}
+
+impl SplitTarget {
+    pub fn new(
+        partition_id: PartitionId,
+        partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> Self {
+        Self {
+            partition_id,
+            partition_key_range,
+        }
+    }
+}
+
+/// The state machine variables of the partition store which the split needs to know about.
+#[derive(Debug, Clone, Default)]
+pub struct SplitFsmVariables {
+    /// Copied as is into both partitions
+    pub inherited: Vec<u64>,
+    /// Next inbox sequence number, recomputed for every partition
+    pub inbox_seq_number: u64,
+    /// Next outbox sequence number, reset for every partition
+    pub outbox_seq_number: u64,
+}
+
+/// Sequence numbers of the inbox entries the targets received so far.
+#[derive(Debug, Default)]
+pub(crate) struct SplitSequenceNumbers {
+    next_inbox_seq_number: [u64; 2],
+}
+
+impl SplitSequenceNumbers {
+    fn record_inbox_entry(&mut self, target: usize, seq_number: u64) {
+        let next = &mut self.next_inbox_seq_number[target];
+        *next = (*next).max(seq_number + 1);
+    }
+}
+
+fn cf_handle<'a>(db: &'a DB, cf_name: &CfName) -> Result<Arc<BoundColumnFamily<'a>>> {
+    db.cf_handle(cf_name).ok_or_else(|| {
+        StorageError::Generic(RocksError::UnknownColumnFamily(cf_name.clone()).into())
+    })
+}
+
+/// Copies the data of the `source` column family into the column families of the two targets,
+/// which must exist.
+pub(crate) fn split_cf(
+    db: &DB,
+    source: &CfName,
+    targets: [(&SplitTarget, &CfName); 2],
+    fsm_variables: &SplitFsmVariables,
+    seq_numbers: &mut SplitSequenceNumbers,
+) -> Result<()> {
+    let source_cf = cf_handle(db, source)?;
+    let target_cfs = [cf_handle(db, targets[0].1)?, cf_handle(db, targets[1].1)?];
+
+    // the partition column families use a prefix extractor, make sure we see all keys
+    let mut read_options = ReadOptions::default();
+    read_options.set_total_order_seek(true);
+    // bifrost is the source of truth, see the write path of partition stores
+    let mut write_options = WriteOptions::default();
+    write_options.disable_wal(true);
+
+    let mut batch = WriteBatchWithTransaction::<true>::default();
+    let mut num_entries = [0u64; 2];
+    for item in db.iterator_cf_opt(&source_cf, read_options, IteratorMode::Start) {
+        let (key, value) = item.map_err(|err| StorageError::Generic(err.into()))?;
+        split_entry(
+            &key,
+            &value,
+            [targets[0].0, targets[1].0],
+            fsm_variables,
+            seq_numbers,
+            |target, key| {
+                batch.put_cf(&target_cfs[target], key, &value);
+                num_entries[target] += 1;
+            },
+        )?;
+
+        if batch.len() >= WRITE_BATCH_SIZE {
+            db.write_opt(&batch, &write_options)
+                .map_err(|err| StorageError::Generic(err.into()))?;
+            batch.clear();
+        }
+    }
+    db.write_opt(&batch, &write_options)
+        .map_err(|err| StorageError::Generic(err.into()))?;
+
+    debug!(
+        source = %source,
+        left = %targets[0].1,
+        right = %targets[1].1,
+        left_entries = num_entries[0],
+        right_entries = num_entries[1],
+        "Split partition store"
+    );
+    Ok(())
+}
+
+/// Writes the inbox and outbox sequence numbers of the targets into their column families. Must
+/// be called once all column families have been split.
+pub(crate) fn write_seq_numbers(
+    db: &DB,
+    targets: [(&SplitTarget, &CfName); 2],
+    fsm_variables: &SplitFsmVariables,
+    seq_numbers: &SplitSequenceNumbers,
+) -> Result<()> {
+    let mut write_options = WriteOptions::default();
+    write_options.disable_wal(true);
+
+    let mut batch = WriteBatchWithTransaction::<true>::default();
+    for (idx, (target, cf_name)) in targets.into_iter().enumerate() {
+        let target_cf = cf_handle(db, cf_name)?;
+        for (state_id, seq_number) in [
+            (
+                fsm_variables.inbox_seq_number,
+                seq_numbers.next_inbox_seq_number[idx],
+            ),
+            // the outbox is empty, the partitions send their messages under their own ids
+            (fsm_variables.outbox_seq_number, 0),
+        ] {
+            let key = PartitionStateMachineKey::default()
+                .partition_id(target.partition_id)
+                .state_id(state_id)
+                .serialize();
+            let mut value = BytesMut::new();
+            StorageCodec::encode(SequenceNumber::from(seq_number), &mut value)
+                .map_err(|err| StorageError::Conversion(err.into()))?;
+            batch.put_cf(&target_cf, key, value);
+        }
+    }
+    db.write_opt(&batch, &write_options)
+        .map_err(|err| StorageError::Generic(err.into()))
+}
+
+/// Emits the entry with its key for the target(s) it belongs to, identified by their index.
+fn split_entry(
+    key: &[u8],
+    value: &[u8],
+    targets: [&SplitTarget; 2],
+    fsm_variables: &SplitFsmVariables,
+    seq_numbers: &mut SplitSequenceNumbers,
+    mut emit: impl FnMut(usize, &[u8]),
+) -> Result<()> {
+    let owner_of = |partition_key: PartitionKey| {
+        targets
+            .iter()
+            .position(|target| target.partition_key_range.contains(&partition_key))
+    };
+
+    let mut cursor = Cursor::new(key);
+    match KeyKind::deserialize(&mut cursor)? {
+        KeyKind::DeadLetter
+        | KeyKind::Idempotency
+        | KeyKind::InvocationStatus
+        | KeyKind::Journal
+        | KeyKind::JournalBlob
+        | KeyKind::Promise
+        | KeyKind::ServiceStatus
+        | KeyKind::State
+        | KeyKind::StateExpiration => {
+            if key.len() < KeyKind::SERIALIZED_LENGTH + std::mem::size_of::<PartitionKey>() {
+                return Err(StorageError::DataIntegrityError);
+            }
+            let partition_key = PartitionKey::decode(&mut cursor)?;
+            if let Some(target) = owner_of(partition_key) {
+                emit(target, key);
+            }
+        }
+        KeyKind::PriorityInbox => {
+            let inbox_key = InboxKey::deserialize_from(&mut Cursor::new(key))?;
+            if let Some(target) = owner_of(*inbox_key.partition_key_ok_or()?) {
+                seq_numbers.record_inbox_entry(target, *inbox_key.sequence_number_ok_or()?);
+                emit(target, key);
+            }
+        }
+        KeyKind::Inbox => {
+            let inbox_key = LegacyInboxKey::deserialize_from(&mut Cursor::new(key))?;
+            if let Some(target) = owner_of(*inbox_key.partition_key_ok_or()?) {
+                seq_numbers.record_inbox_entry(target, *inbox_key.sequence_number_ok_or()?);
+                emit(target, key);
+            }
+        }
+        KeyKind::Timers => {
+            let timers_key = TimersKey::deserialize_from(&mut Cursor::new(key))?;
+            let timer = StorageCodec::decode::<Timer, _>(&mut &value[..])
+                .map_err(|err| StorageError::Conversion(err.into()))?;
+            if let Some(target) = owner_of(timer.partition_key()) {
+                let key = timers_key
+                    .partition_id(targets[target].partition_id)
+                    .serialize();
+                emit(target, &key);
+            }
+        }
+        KeyKind::Deduplication => {
+            let dedup_key = DeduplicationKey::deserialize_from(&mut Cursor::new(key))?;
+            if dedup_key.producer_id_ok_or()? != &ProducerId::self_producer() {
+                for (idx, target) in targets.iter().enumerate() {
+                    let key = dedup_key
+                        .clone()
+                        .partition_id(target.partition_id)
+                        .serialize();
+                    emit(idx, &key);
+                }
+            }
+        }
+        KeyKind::Outbox => {
+            // the partition is sealed once its outbox has been truncated
+            let outbox_key = OutboxKey::deserialize_from(&mut Cursor::new(key))?;
+            return Err(StorageError::Generic(anyhow::anyhow!(
+                "outbox message {} hasn't been truncated before the split",
+                outbox_key.message_index_ok_or()?
+            )));
+        }
+        KeyKind::Fsm => {
+            let fsm_key = PartitionStateMachineKey::deserialize_from(&mut Cursor::new(key))?;
+            if fsm_variables.inherited.contains(fsm_key.state_id_ok_or()?) {
+                for (idx, target) in targets.iter().enumerate() {
+                    let key = fsm_key
+                        .clone()
+                        .partition_id(target.partition_id)
+                        .serialize();
+                    emit(idx, &key);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -20,6 +20,7 @@ use futures_util::stream;
 use restate_storage_api::state_table::{ReadOnlyStateTable, StateTable};
 use restate_storage_api::{Result, StorageError};
 use restate_types::identifiers::{PartitionKey, ServiceId, WithPartitionKey};
+use restate_types::time::MillisSinceEpoch;
 use std::future;
 use std::future::Future;
 use std::ops::RangeInclusive;
@@ -35,6 +36,17 @@ define_table_key!(
     )
 );
 
+define_table_key!(
+    State,
+    KeyKind::StateExpiration,
+    StateExpirationKey(
+        partition_key: PartitionKey,
+        service_name: ByteString,
+        service_key: ByteString,
+        state_key: Bytes
+    )
+);
+
 #[inline]
 fn write_state_entry_key(service_id: &ServiceId, state_key: impl AsRef<[u8]>) -> StateKey {
     StateKey::default()
@@ -44,6 +56,18 @@ fn write_state_entry_key(service_id: &ServiceId, state_key: impl AsRef<[u8]>) ->
         .state_key(state_key.as_ref().to_vec().into())
 }
 
+#[inline]
+fn write_state_expiration_key(
+    service_id: &ServiceId,
+    state_key: impl AsRef<[u8]>,
+) -> StateExpirationKey {
+    StateExpirationKey::default()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone())
+        .state_key(state_key.as_ref().to_vec().into())
+}
+
 fn user_state_key_from_slice(key: &[u8]) -> Result<Bytes> {
     let mut key = Bytes::copy_from_slice(key);
     let key = StateKey::deserialize_from(&mut key)?;
@@ -69,8 +93,9 @@ fn delete_user_state<S: StorageAccess>(
     service_id: &ServiceId,
     state_key: impl AsRef<[u8]>,
 ) {
-    let key = write_state_entry_key(service_id, state_key);
+    let key = write_state_entry_key(service_id, state_key.as_ref());
     storage.delete_key(&key);
+    delete_user_state_expiration(storage, service_id, state_key);
 }
 
 fn delete_all_user_state<S: StorageAccess>(storage: &mut S, service_id: &ServiceId) -> Result<()> {
@@ -78,11 +103,19 @@ fn delete_all_user_state<S: StorageAccess>(storage: &mut S, service_id: &Service
         .partition_key(service_id.partition_key())
         .service_name(service_id.service_name.clone())
         .service_key(service_id.key.clone());
+    let expiration_prefix_key = StateExpirationKey::default()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone());
 
-    let keys = storage.for_each_key_value_in_place(
+    let mut keys = storage.for_each_key_value_in_place(
         TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), prefix_key),
         |k, _| TableScanIterationDecision::Emit(Ok(Bytes::copy_from_slice(k))),
     );
+    keys.extend(storage.for_each_key_value_in_place(
+        TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), expiration_prefix_key),
+        |k, _| TableScanIterationDecision::Emit(Ok(Bytes::copy_from_slice(k))),
+    ));
 
     for k in keys {
         storage.delete_cf(State, &k?);
@@ -91,6 +124,49 @@ fn delete_all_user_state<S: StorageAccess>(storage: &mut S, service_id: &Service
     Ok(())
 }
 
+fn put_user_state_expiration<S: StorageAccess>(
+    storage: &mut S,
+    service_id: &ServiceId,
+    state_key: impl AsRef<[u8]>,
+    expiration_time: MillisSinceEpoch,
+) {
+    let key = write_state_expiration_key(service_id, state_key);
+    storage.put_kv_raw(key, expiration_time.as_u64().to_be_bytes());
+}
+
+fn delete_user_state_expiration<S: StorageAccess>(
+    storage: &mut S,
+    service_id: &ServiceId,
+    state_key: impl AsRef<[u8]>,
+) {
+    let key = write_state_expiration_key(service_id, state_key);
+    storage.delete_key(&key);
+}
+
+fn get_user_state_expiration<S: StorageAccess>(
+    storage: &mut S,
+    service_id: &ServiceId,
+    state_key: impl AsRef<[u8]>,
+) -> Result<Option<MillisSinceEpoch>> {
+    let key = write_state_expiration_key(service_id, state_key);
+    storage.get_kv_raw(key, move |_k, v| v.map(decode_expiration_time).transpose())
+}
+
+fn get_all_user_state_expirations<S: StorageAccess>(
+    storage: &mut S,
+    service_id: &ServiceId,
+) -> Vec<Result<(Bytes, MillisSinceEpoch)>> {
+    let key = StateExpirationKey::default()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone());
+
+    storage.for_each_key_value_in_place(
+        TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), key),
+        |k, v| TableScanIterationDecision::Emit(decode_user_state_expiration(k, v)),
+    )
+}
+
 fn get_user_state<S: StorageAccess>(
     storage: &mut S,
     service_id: &ServiceId,
@@ -130,6 +206,21 @@ impl ReadOnlyStateTable for PartitionStore {
     ) -> impl Stream<Item = Result<(Bytes, Bytes)>> + Send {
         stream::iter(get_all_user_states(self, service_id))
     }
+
+    fn get_user_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        state_key: impl AsRef<[u8]>,
+    ) -> impl Future<Output = Result<Option<MillisSinceEpoch>>> + Send {
+        future::ready(get_user_state_expiration(self, service_id, state_key))
+    }
+
+    fn get_all_user_state_expirations(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Stream<Item = Result<(Bytes, MillisSinceEpoch)>> + Send {
+        stream::iter(get_all_user_state_expirations(self, service_id))
+    }
 }
 
 impl<'a> ReadOnlyStateTable for RocksDBTransaction<'a> {
@@ -147,6 +238,21 @@ impl<'a> ReadOnlyStateTable for RocksDBTransaction<'a> {
     ) -> impl Stream<Item = Result<(Bytes, Bytes)>> + Send {
         stream::iter(get_all_user_states(self, service_id))
     }
+
+    fn get_user_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        state_key: impl AsRef<[u8]>,
+    ) -> impl Future<Output = Result<Option<MillisSinceEpoch>>> + Send {
+        future::ready(get_user_state_expiration(self, service_id, state_key))
+    }
+
+    fn get_all_user_state_expirations(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Stream<Item = Result<(Bytes, MillisSinceEpoch)>> + Send {
+        stream::iter(get_all_user_state_expirations(self, service_id))
+    }
 }
 
 impl<'a> StateTable for RocksDBTransaction<'a> {
@@ -175,6 +281,25 @@ impl<'a> StateTable for RocksDBTransaction<'a> {
     ) -> impl Future<Output = Result<()>> + Send {
         future::ready(delete_all_user_state(self, service_id))
     }
+
+    fn put_user_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        state_key: impl AsRef<[u8]>,
+        expiration_time: MillisSinceEpoch,
+    ) -> impl Future<Output = ()> + Send {
+        put_user_state_expiration(self, service_id, state_key, expiration_time);
+        future::ready(())
+    }
+
+    fn delete_user_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        state_key: impl AsRef<[u8]>,
+    ) -> impl Future<Output = ()> + Send {
+        delete_user_state_expiration(self, service_id, state_key);
+        future::ready(())
+    }
 }
 
 fn decode_user_state_key_value(k: &[u8], v: &[u8]) -> Result<(Bytes, Bytes)> {
@@ -183,6 +308,19 @@ fn decode_user_state_key_value(k: &[u8], v: &[u8]) -> Result<(Bytes, Bytes)> {
     Ok((user_key, user_value))
 }
 
+fn decode_user_state_expiration(k: &[u8], v: &[u8]) -> Result<(Bytes, MillisSinceEpoch)> {
+    let mut key = Bytes::copy_from_slice(k);
+    let user_key = StateExpirationKey::deserialize_from(&mut key)?
+        .state_key
+        .ok_or(StorageError::DataIntegrityError)?;
+    Ok((user_key, decode_expiration_time(v)?))
+}
+
+fn decode_expiration_time(v: &[u8]) -> Result<MillisSinceEpoch> {
+    let millis = u64::from_be_bytes(v.try_into().map_err(|_| StorageError::DataIntegrityError)?);
+    Ok(MillisSinceEpoch::new(millis))
+}
+
 #[derive(Clone, Debug)]
 pub struct OwnedStateRow {
     pub partition_key: PartitionKey,
@@ -99,6 +99,45 @@ fn exclusive_start_key_range(
                     },
                 }
             }
+            TimerKeyKind::ExpireState {
+                invocation_uuid,
+                journal_index,
+            } => TimerKey {
+                timestamp: timer_key.timestamp,
+                kind: TimerKeyKind::ExpireState {
+                    invocation_uuid,
+                    journal_index: journal_index
+                        .checked_add(1)
+                        .expect("journal index should be smaller than u64::MAX"),
+                },
+            },
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => {
+                let incremented_invocation_uuid = increment_invocation_uuid(invocation_uuid);
+                TimerKey {
+                    timestamp: timer_key.timestamp,
+                    kind: TimerKeyKind::InvocationDeadline {
+                        invocation_uuid: incremented_invocation_uuid,
+                    },
+                }
+            }
+            TimerKeyKind::CleanDeadLetter { invocation_uuid } => {
+                let incremented_invocation_uuid = increment_invocation_uuid(invocation_uuid);
+                TimerKey {
+                    timestamp: timer_key.timestamp,
+                    kind: TimerKeyKind::CleanDeadLetter {
+                        invocation_uuid: incremented_invocation_uuid,
+                    },
+                }
+            }
+            TimerKeyKind::ResumeInvocation { invocation_uuid } => {
+                let incremented_invocation_uuid = increment_invocation_uuid(invocation_uuid);
+                TimerKey {
+                    timestamp: timer_key.timestamp,
+                    kind: TimerKeyKind::ResumeInvocation {
+                        invocation_uuid: incremented_invocation_uuid,
+                    },
+                }
+            }
         };
 
         let lower_bound = write_timer_key(partition_id, &next_timer_key);
@@ -275,6 +314,19 @@ mod tests {
             TimerKeyKind::CleanInvocationStatus {
                 invocation_uuid: FIXTURE_INVOCATION,
             },
+            TimerKeyKind::ExpireState {
+                invocation_uuid: FIXTURE_INVOCATION,
+                journal_index: 0,
+            },
+            TimerKeyKind::InvocationDeadline {
+                invocation_uuid: FIXTURE_INVOCATION,
+            },
+            TimerKeyKind::CleanDeadLetter {
+                invocation_uuid: FIXTURE_INVOCATION,
+            },
+            TimerKeyKind::ResumeInvocation {
+                invocation_uuid: FIXTURE_INVOCATION,
+            },
         ];
 
         for first_kind in &kinds {
@@ -492,6 +544,19 @@ mod tests {
                         invocation_uuid: InvocationUuid::new(),
                     }
                 }
+                TimerKeyKindDiscriminants::ExpireState => TimerKeyKind::ExpireState {
+                    invocation_uuid: InvocationUuid::new(),
+                    journal_index: rand::thread_rng().gen_range(0..2 ^ 16),
+                },
+                TimerKeyKindDiscriminants::InvocationDeadline => TimerKeyKind::InvocationDeadline {
+                    invocation_uuid: InvocationUuid::new(),
+                },
+                TimerKeyKindDiscriminants::CleanDeadLetter => TimerKeyKind::CleanDeadLetter {
+                    invocation_uuid: InvocationUuid::new(),
+                },
+                TimerKeyKindDiscriminants::ResumeInvocation => TimerKeyKind::ResumeInvocation {
+                    invocation_uuid: InvocationUuid::new(),
+                },
             }
         };
 
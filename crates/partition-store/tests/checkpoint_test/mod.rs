@@ -0,0 +1,127 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::storage_test_environment_with_manager;
+use restate_storage_api::fsm_table::{FsmTable, ReadOnlyFsmTable, SequenceNumber};
+use restate_storage_api::Transaction;
+use restate_types::config::WorkerOptions;
+use restate_types::identifiers::{PartitionId, PartitionKey};
+
+const STATE_ID: u64 = 1337;
+
+#[tokio::test]
+async fn test_checkpoint() {
+    let (manager, mut rocksdb) = storage_test_environment_with_manager().await;
+
+    let mut txn = rocksdb.transaction();
+    txn.put(PartitionId::MIN, STATE_ID, SequenceNumber::from(1))
+        .await;
+    txn.commit().await.expect("commit succeeds");
+
+    let staging_dir = tempfile::tempdir().unwrap();
+    let mut checkpoint = manager
+        .open_checkpoint(
+            staging_dir.path().join("checkpoint"),
+            &WorkerOptions::default().storage.rocksdb,
+        )
+        .await
+        .expect("checkpoint is created");
+
+    // writes after taking the checkpoint are not visible in it
+    let mut txn = rocksdb.transaction();
+    txn.put(PartitionId::MIN, STATE_ID, SequenceNumber::from(2))
+        .await;
+    txn.commit().await.expect("commit succeeds");
+
+    assert!(checkpoint.has_partition(PartitionId::MIN));
+    let value = checkpoint
+        .get::<SequenceNumber>(PartitionId::MIN, STATE_ID)
+        .await
+        .expect("read succeeds");
+    assert_eq!(value.map(u64::from), Some(1));
+
+    let data_file = staging_dir.path().join("data.sst");
+    assert!(checkpoint
+        .export_partition(PartitionId::MIN, data_file.clone())
+        .await
+        .expect("export succeeds"));
+    assert!(data_file.exists());
+
+    // no journal entry has been spilled
+    let blobs_file = staging_dir.path().join("blobs.sst");
+    assert!(!checkpoint
+        .export_partition_blobs(PartitionId::MIN, blobs_file.clone())
+        .await
+        .expect("export succeeds"));
+    assert!(!blobs_file.exists());
+
+    let checkpoint_dir = checkpoint.path().to_owned();
+    checkpoint.close().await.expect("checkpoint is closed");
+    assert!(!checkpoint_dir.exists());
+}
+
+#[tokio::test]
+async fn test_import() {
+    let (manager, mut rocksdb) = storage_test_environment_with_manager().await;
+    let opts = WorkerOptions::default().storage.rocksdb;
+
+    let mut txn = rocksdb.transaction();
+    txn.put(PartitionId::MIN, STATE_ID, SequenceNumber::from(1))
+        .await;
+    txn.commit().await.expect("commit succeeds");
+
+    let staging_dir = tempfile::tempdir().unwrap();
+    let checkpoint = manager
+        .open_checkpoint(staging_dir.path().join("checkpoint"), &opts)
+        .await
+        .expect("checkpoint is created");
+    let data_file = staging_dir.path().join("data.sst");
+    assert!(checkpoint
+        .export_partition(PartitionId::MIN, data_file.clone())
+        .await
+        .expect("export succeeds"));
+    checkpoint.close().await.expect("checkpoint is closed");
+
+    let mut txn = rocksdb.transaction();
+    txn.put(PartitionId::MIN, STATE_ID, SequenceNumber::from(2))
+        .await;
+    txn.commit().await.expect("commit succeeds");
+
+    // the existing data of the partition is replaced by the imported one
+    let mut imported = manager
+        .import_partition_store(
+            PartitionId::MIN,
+            0..=PartitionKey::MAX - 1,
+            Some(data_file),
+            None,
+            &opts,
+        )
+        .await
+        .expect("import succeeds");
+    let value = imported
+        .get::<SequenceNumber>(PartitionId::MIN, STATE_ID)
+        .await
+        .expect("read succeeds");
+    assert_eq!(value.map(u64::from), Some(1));
+
+    // importing nothing leaves an empty partition store
+    let other = PartitionId::from(1);
+    assert!(!manager.partition_store_exists(other));
+    let mut imported = manager
+        .import_partition_store(other, 0..=PartitionKey::MAX - 1, None, None, &opts)
+        .await
+        .expect("import succeeds");
+    assert!(manager.partition_store_exists(other));
+    let value = imported
+        .get::<SequenceNumber>(other, STATE_ID)
+        .await
+        .expect("read succeeds");
+    assert!(value.is_none());
+}
@@ -0,0 +1,74 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::{assert_stream_eq, mock_random_service_invocation, storage_test_environment};
+use restate_storage_api::dead_letter_table::{
+    DeadLetter, DeadLetterTable, ReadOnlyDeadLetterTable,
+};
+use restate_storage_api::Transaction;
+use restate_types::errors::InvocationError;
+use restate_types::identifiers::{InvocationId, InvocationUuid, PartitionKey};
+use restate_types::time::MillisSinceEpoch;
+
+fn mock_dead_letter(invocation_id: InvocationId) -> DeadLetter {
+    let mut service_invocation = mock_random_service_invocation();
+    service_invocation.invocation_id = invocation_id;
+    DeadLetter {
+        service_invocation,
+        failure: InvocationError::new(500u16, "my-failure"),
+        failed_at: MillisSinceEpoch::new(1706027034946),
+    }
+}
+
+#[tokio::test]
+async fn test_dead_letter() {
+    let mut rocksdb = storage_test_environment().await;
+
+    let invocation_id_1 = InvocationId::from_parts(10, InvocationUuid::new());
+    let invocation_id_2 = InvocationId::from_parts(20, InvocationUuid::new());
+    let dead_letter_1 = mock_dead_letter(invocation_id_1);
+    let dead_letter_2 = mock_dead_letter(invocation_id_2);
+
+    // Fill in some data
+    let mut txn = rocksdb.transaction();
+    txn.put_dead_letter(&invocation_id_1, dead_letter_1.clone())
+        .await;
+    txn.put_dead_letter(&invocation_id_2, dead_letter_2.clone())
+        .await;
+    txn.commit().await.unwrap();
+
+    // Query
+    assert_eq!(
+        rocksdb.get_dead_letter(&invocation_id_1).await.unwrap(),
+        Some(dead_letter_1.clone())
+    );
+    assert_stream_eq(
+        rocksdb.all_dead_letters(PartitionKey::MIN..=PartitionKey::MAX),
+        vec![
+            (invocation_id_1, dead_letter_1),
+            (invocation_id_2, dead_letter_2.clone()),
+        ],
+    )
+    .await;
+
+    // Delete and query afterwards
+    let mut txn = rocksdb.transaction();
+    txn.delete_dead_letter(&invocation_id_1).await;
+    txn.commit().await.unwrap();
+    assert_eq!(
+        rocksdb.get_dead_letter(&invocation_id_1).await.unwrap(),
+        None
+    );
+    assert_stream_eq(
+        rocksdb.all_dead_letters(PartitionKey::MIN..=PartitionKey::MAX),
+        vec![(invocation_id_2, dead_letter_2)],
+    )
+    .await;
+}
@@ -8,12 +8,16 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::{assert_stream_eq, mock_state_mutation};
+use crate::{assert_stream_eq, mock_state_mutation, storage_test_environment};
+use bytes::{BufMut, BytesMut};
 use once_cell::sync::Lazy;
 use restate_partition_store::PartitionStore;
-use restate_storage_api::inbox_table::{InboxEntry, InboxTable, SequenceNumberInboxEntry};
+use restate_storage_api::inbox_table::{
+    InboxEntry, InboxPriority, InboxTable, ReadOnlyInboxTable, SequenceNumberInboxEntry,
+};
 use restate_storage_api::Transaction;
-use restate_types::identifiers::{InvocationId, ServiceId};
+use restate_types::identifiers::{InvocationId, PartitionId, ServiceId, WithPartitionKey};
+use restate_types::storage::StorageCodec;
 
 static INBOX_ENTRIES: Lazy<Vec<SequenceNumberInboxEntry>> = Lazy::new(|| {
     vec![
@@ -73,7 +77,7 @@ async fn get_svc_inbox<T: InboxTable>(table: &mut T) {
 
 async fn delete_entry<T: InboxTable>(table: &mut T) {
     table
-        .delete_inbox_entry(INBOX_ENTRIES[0].service_id(), 7)
+        .delete_inbox_entry(INBOX_ENTRIES[0].service_id(), InboxPriority::Background, 7)
         .await;
 }
 
@@ -83,6 +87,30 @@ async fn peek_after_delete<T: InboxTable>(table: &mut T) {
     assert_eq!(result.unwrap(), Some(INBOX_ENTRIES[1].clone()));
 }
 
+async fn peek_interactive_before_background<T: InboxTable>(table: &mut T) {
+    let service_id = ServiceId::new("svc-3", "key-1");
+    let background = SequenceNumberInboxEntry::new(
+        11,
+        InboxEntry::Invocation(service_id.clone(), InvocationId::mock_random()),
+    );
+    let interactive = SequenceNumberInboxEntry::new(
+        12,
+        InboxEntry::Invocation(service_id.clone(), InvocationId::mock_random()),
+    )
+    .with_priority(InboxPriority::Interactive);
+
+    table.put_inbox_entry(&service_id, background.clone()).await;
+    table
+        .put_inbox_entry(&service_id, interactive.clone())
+        .await;
+
+    let result = table.peek_inbox(&service_id).await;
+    assert_eq!(result.unwrap(), Some(interactive.clone()));
+
+    let stream = table.inbox(&service_id);
+    assert_stream_eq(stream, vec![interactive, background]).await;
+}
+
 pub(crate) async fn run_tests(mut rocksdb: PartitionStore) {
     let mut txn = rocksdb.transaction();
     populate_data(&mut txn).await;
@@ -90,9 +118,85 @@ pub(crate) async fn run_tests(mut rocksdb: PartitionStore) {
     find_the_next_message_in_an_inbox(&mut txn).await;
     get_svc_inbox(&mut txn).await;
     delete_entry(&mut txn).await;
+    peek_interactive_before_background(&mut txn).await;
 
     txn.commit().await.expect("should not fail");
 
     let mut txn = rocksdb.transaction();
     peek_after_delete(&mut txn).await;
 }
+
+/// Writes the entry with the key format the inbox used before it had priority lanes
+fn put_legacy_inbox_entry(rocksdb: &PartitionStore, entry: &SequenceNumberInboxEntry) {
+    let service_id = entry.service_id();
+    let mut key = BytesMut::new();
+    key.put_slice(b"ib");
+    key.put_u64(service_id.partition_key());
+    // service name and key are length delimited with a single byte varint
+    key.put_u8(service_id.service_name.len() as u8);
+    key.put_slice(service_id.service_name.as_bytes());
+    key.put_u8(service_id.key.len() as u8);
+    key.put_slice(service_id.key.as_bytes());
+    key.put_u64(entry.inbox_sequence_number);
+
+    let mut value = BytesMut::new();
+    StorageCodec::encode(&entry.inbox_entry, &mut value).unwrap();
+
+    let db = rocksdb.inner();
+    let cf = db
+        .cf_handle(&format!("data-{}", PartitionId::MIN))
+        .expect("data cf exists");
+    db.put_cf(&cf, key, value).unwrap();
+}
+
+#[tokio::test]
+async fn migrate_legacy_inbox() {
+    let mut rocksdb = storage_test_environment().await;
+
+    for entry in INBOX_ENTRIES.iter() {
+        put_legacy_inbox_entry(&rocksdb, entry);
+    }
+    // legacy entries are invisible until they have been migrated
+    let mut txn = rocksdb.transaction();
+    assert_eq!(
+        txn.peek_inbox(INBOX_ENTRIES[0].service_id()).await.unwrap(),
+        None
+    );
+    drop(txn);
+
+    assert_eq!(
+        rocksdb.migrate_legacy_inbox().await.unwrap(),
+        INBOX_ENTRIES.len()
+    );
+    // migrating twice is a no-op
+    assert_eq!(rocksdb.migrate_legacy_inbox().await.unwrap(), 0);
+
+    // the migrated entries keep their order in the background lane, interactive entries
+    // enqueued afterwards are dequeued first
+    let service_id = INBOX_ENTRIES[0].service_id().clone();
+    let interactive = SequenceNumberInboxEntry::new(
+        11,
+        InboxEntry::Invocation(service_id.clone(), InvocationId::mock_random()),
+    )
+    .with_priority(InboxPriority::Interactive);
+    let mut txn = rocksdb.transaction();
+    txn.put_inbox_entry(&service_id, interactive.clone()).await;
+    txn.commit().await.unwrap();
+
+    let mut txn = rocksdb.transaction();
+    assert_stream_eq(
+        txn.inbox(&service_id),
+        vec![
+            interactive,
+            INBOX_ENTRIES[0].clone(),
+            INBOX_ENTRIES[1].clone(),
+            INBOX_ENTRIES[3].clone(),
+        ],
+    )
+    .await;
+    assert_stream_eq(
+        txn.inbox(INBOX_ENTRIES[2].service_id()),
+        vec![INBOX_ENTRIES[2].clone()],
+    )
+    .await;
+}
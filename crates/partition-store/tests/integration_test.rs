@@ -14,6 +14,7 @@ use std::ops::RangeInclusive;
 use std::pin::pin;
 
 use futures::Stream;
+use once_cell::sync::Lazy;
 use tokio_stream::StreamExt;
 
 use restate_core::TaskCenterBuilder;
@@ -26,16 +27,27 @@ use restate_types::identifiers::{InvocationId, PartitionId, PartitionKey, Servic
 use restate_types::invocation::{InvocationTarget, ServiceInvocation, Source};
 use restate_types::state_mut::ExternalStateMutation;
 
+mod checkpoint_test;
+mod dead_letter_table_test;
 mod idempotency_table_test;
 mod inbox_table_test;
 mod invocation_status_table_test;
 mod journal_table_test;
 mod outbox_table_test;
+mod promise_table_test;
+mod split_test;
 mod state_table_test;
 mod timer_table_test;
 mod virtual_object_status_table_test;
 
+// The default options own the temporary data directory, keep it until the test process exits
+static WORKER_OPTIONS: Lazy<WorkerOptions> = Lazy::new(WorkerOptions::default);
+
 async fn storage_test_environment() -> PartitionStore {
+    storage_test_environment_with_manager().await.1
+}
+
+async fn storage_test_environment_with_manager() -> (PartitionStoreManager, PartitionStore) {
     //
     // create a rocksdb storage from options
     //
@@ -46,7 +58,7 @@ async fn storage_test_environment() -> PartitionStore {
     tc.run_in_scope_sync("db-manager-init", None, || {
         RocksDbManager::init(Constant::new(CommonOptions::default()))
     });
-    let worker_options = WorkerOptions::default();
+    let worker_options = &*WORKER_OPTIONS;
     let manager = PartitionStoreManager::create(
         Constant::new(worker_options.storage.clone()),
         Constant::new(worker_options.storage.rocksdb.clone()),
@@ -55,7 +67,7 @@ async fn storage_test_environment() -> PartitionStore {
     .await
     .expect("DB storage creation succeeds");
     // A single partition store that spans all keys.
-    manager
+    let partition_store = manager
         .open_partition_store(
             PartitionId::MIN,
             RangeInclusive::new(0, PartitionKey::MAX - 1),
@@ -63,7 +75,8 @@ async fn storage_test_environment() -> PartitionStore {
             &worker_options.storage.rocksdb,
         )
         .await
-        .expect("DB storage creation succeeds")
+        .expect("DB storage creation succeeds");
+    (manager, partition_store)
 }
 
 #[tokio::test]
@@ -92,6 +105,7 @@ pub(crate) fn mock_service_invocation(service_id: ServiceId) -> ServiceInvocatio
         span_context: Default::default(),
         headers: vec![],
         execution_time: None,
+        execution_deadline: None,
         completion_retention_time: None,
         idempotency_key: None,
     }
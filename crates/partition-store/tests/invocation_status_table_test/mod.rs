@@ -17,9 +17,10 @@ use super::assert_stream_eq;
 use bytestring::ByteString;
 use once_cell::sync::Lazy;
 use restate_partition_store::PartitionStore;
+use restate_storage_api::inbox_table::InboxPriority;
 use restate_storage_api::invocation_status_table::{
-    InFlightInvocationMetadata, InvocationStatus, InvocationStatusTable, JournalMetadata,
-    StatusTimestamps,
+    InFlightInvocationMetadata, InboxedInvocation, InvocationStatus, InvocationStatusTable,
+    JournalMetadata, StatusTimestamps,
 };
 use restate_types::identifiers::InvocationId;
 use restate_types::invocation::{
@@ -56,6 +57,13 @@ const INVOCATION_TARGET_3: InvocationTarget = InvocationTarget::VirtualObject {
 static INVOCATION_ID_3: Lazy<InvocationId> =
     Lazy::new(|| InvocationId::generate(&INVOCATION_TARGET_3));
 
+const INVOCATION_TARGET_4: InvocationTarget = InvocationTarget::Service {
+    name: ByteString::from_static("def"),
+    handler: ByteString::from_static("myhandler"),
+};
+static INVOCATION_ID_4: Lazy<InvocationId> =
+    Lazy::new(|| InvocationId::generate(&INVOCATION_TARGET_4));
+
 fn invoked_status(invocation_target: InvocationTarget) -> InvocationStatus {
     InvocationStatus::Invoked(InFlightInvocationMetadata {
         invocation_target,
@@ -66,6 +74,7 @@ fn invoked_status(invocation_target: InvocationTarget) -> InvocationStatus {
         source: Source::Ingress,
         completion_retention_time: Duration::ZERO,
         idempotency_key: None,
+        execution_deadline: None,
     })
 }
 
@@ -80,11 +89,30 @@ fn suspended_status(invocation_target: InvocationTarget) -> InvocationStatus {
             source: Source::Ingress,
             completion_retention_time: Duration::ZERO,
             idempotency_key: None,
+            execution_deadline: None,
         },
         waiting_for_completed_entries: HashSet::default(),
     }
 }
 
+fn inboxed_status(invocation_target: InvocationTarget) -> InvocationStatus {
+    InvocationStatus::Inboxed(InboxedInvocation {
+        inbox_sequence_number: 0,
+        inbox_priority: InboxPriority::Interactive,
+        response_sinks: HashSet::new(),
+        timestamps: StatusTimestamps::new(MillisSinceEpoch::new(0), MillisSinceEpoch::new(0)),
+        invocation_target,
+        argument: Default::default(),
+        source: Source::Ingress,
+        span_context: ServiceInvocationSpanContext::empty(),
+        headers: vec![],
+        execution_time: None,
+        execution_deadline: None,
+        completion_retention_time: Duration::ZERO,
+        idempotency_key: None,
+    })
+}
+
 async fn populate_data<T: InvocationStatusTable>(txn: &mut T) {
     txn.put_invocation_status(
         &INVOCATION_ID_1,
@@ -103,6 +131,12 @@ async fn populate_data<T: InvocationStatusTable>(txn: &mut T) {
         suspended_status(INVOCATION_TARGET_3.clone()),
     )
     .await;
+
+    txn.put_invocation_status(
+        &INVOCATION_ID_4,
+        inboxed_status(INVOCATION_TARGET_4.clone()),
+    )
+    .await;
 }
 
 async fn verify_point_lookups<T: InvocationStatusTable>(txn: &mut T) {
@@ -125,10 +159,19 @@ async fn verify_all_svc_with_status_invoked<T: InvocationStatusTable>(txn: &mut
     assert_stream_eq(stream, expected).await;
 }
 
+async fn verify_all_svc_with_status_inboxed<T: InvocationStatusTable>(txn: &mut T) {
+    let stream = txn.inboxed_invocations(0..=u64::MAX);
+
+    let expected = vec![(*INVOCATION_ID_4, INVOCATION_TARGET_4.clone())];
+
+    assert_stream_eq(stream, expected).await;
+}
+
 pub(crate) async fn run_tests(mut rocksdb: PartitionStore) {
     let mut txn = rocksdb.transaction();
     populate_data(&mut txn).await;
 
     verify_point_lookups(&mut txn).await;
     verify_all_svc_with_status_invoked(&mut txn).await;
+    verify_all_svc_with_status_inboxed(&mut txn).await;
 }
@@ -9,14 +9,16 @@
 // by the Apache License, Version 2.0.
 
 use crate::storage_test_environment;
+use restate_partition_store::PartitionStore;
+use rocksdb::IteratorMode;
 
 use bytes::Bytes;
 use bytestring::ByteString;
 use futures_util::StreamExt;
 use once_cell::sync::Lazy;
-use restate_storage_api::journal_table::{JournalEntry, JournalTable};
+use restate_storage_api::journal_table::{JournalEntry, JournalTable, ReadOnlyJournalTable};
 use restate_storage_api::Transaction;
-use restate_types::identifiers::{InvocationId, InvocationUuid};
+use restate_types::identifiers::{InvocationId, InvocationUuid, PartitionId};
 use restate_types::invocation::{InvocationTarget, ServiceInvocationSpanContext};
 use restate_types::journal::enriched::{
     CallEnrichmentResult, EnrichedEntryHeader, EnrichedRawEntry,
@@ -52,6 +54,22 @@ static MOCK_INVOKE_JOURNAL_ENTRY: Lazy<JournalEntry> = Lazy::new(|| {
 const MOCK_INVOCATION_ID_1: InvocationId =
     InvocationId::from_parts(1, InvocationUuid::from_parts(1706027034946, 12345678900001));
 
+/// Exceeds the default journal blob threshold
+fn oversized_journal_entry() -> JournalEntry {
+    JournalEntry::Entry(EnrichedRawEntry::new(
+        EnrichedEntryHeader::Output,
+        Bytes::from(vec![42; 256 * 1024]),
+    ))
+}
+
+fn num_journal_blobs(rocksdb: &PartitionStore) -> usize {
+    let db = rocksdb.inner();
+    let cf = db
+        .cf_handle(&format!("blobs-{}", PartitionId::MIN))
+        .expect("blobs cf exists");
+    db.iterator_cf(&cf, IteratorMode::Start).count()
+}
+
 async fn populate_data<T: JournalTable>(txn: &mut T) {
     txn.put_journal_entry(&MOCK_INVOCATION_ID_1, 0, MOCK_SLEEP_JOURNAL_ENTRY)
         .await;
@@ -144,3 +162,44 @@ async fn journal_tests() {
     let mut txn = rocksdb.transaction();
     verify_journal_deleted(&mut txn).await;
 }
+
+#[tokio::test]
+async fn oversized_journal_entries_are_stored_as_blobs() {
+    let mut rocksdb = storage_test_environment().await;
+
+    let mut txn = rocksdb.transaction();
+    txn.put_journal_entry(&MOCK_INVOCATION_ID_1, 0, oversized_journal_entry())
+        .await;
+    txn.put_journal_entry(&MOCK_INVOCATION_ID_1, 1, MOCK_SLEEP_JOURNAL_ENTRY)
+        .await;
+    txn.commit().await.expect("should not fail");
+    assert_eq!(num_journal_blobs(&rocksdb), 1);
+
+    let mut txn = rocksdb.transaction();
+    let result = txn
+        .get_journal_entry(&MOCK_INVOCATION_ID_1, 0)
+        .await
+        .expect("should not fail");
+    assert_eq!(result, Some(oversized_journal_entry()));
+
+    let journal: Vec<_> = txn
+        .get_journal(&MOCK_INVOCATION_ID_1, 2)
+        .map(|entry| entry.expect("should not fail"))
+        .collect()
+        .await;
+    // false positive because of Bytes
+    #[allow(clippy::borrow_interior_mutable_const)]
+    {
+        assert_eq!(
+            journal,
+            vec![
+                (0, oversized_journal_entry()),
+                (1, MOCK_SLEEP_JOURNAL_ENTRY)
+            ]
+        );
+    }
+
+    txn.delete_journal(&MOCK_INVOCATION_ID_1, 2).await;
+    txn.commit().await.expect("should not fail");
+    assert_eq!(num_journal_blobs(&rocksdb), 0);
+}
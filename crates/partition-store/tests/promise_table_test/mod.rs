@@ -0,0 +1,92 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::storage_test_environment;
+use bytes::Bytes;
+use bytestring::ByteString;
+use restate_storage_api::promise_table::{
+    Promise, PromiseState, PromiseTable, ReadOnlyPromiseTable,
+};
+use restate_storage_api::Transaction;
+use restate_types::identifiers::{InvocationId, InvocationUuid, JournalEntryId, ServiceId};
+use restate_types::journal::EntryResult;
+
+#[tokio::test]
+async fn test_promise_table() {
+    let mut rocksdb = storage_test_environment().await;
+
+    let service_id_1 = ServiceId::with_partition_key(1337, "wf-1", "key-1");
+    let service_id_2 = ServiceId::with_partition_key(1337, "wf-1", "key-2");
+    let promise_key_1 = ByteString::from_static("promise1");
+    let promise_key_2 = ByteString::from_static("promise2");
+
+    let not_completed_promise = Promise {
+        state: PromiseState::NotCompleted(vec![JournalEntryId::from_parts(
+            InvocationId::from_parts(1337, InvocationUuid::new()),
+            2,
+        )]),
+    };
+    let completed_promise = Promise {
+        state: PromiseState::Completed(EntryResult::Success(Bytes::from_static(b"done"))),
+    };
+
+    // Fill in some data
+    let mut txn = rocksdb.transaction();
+    txn.put_promise(&service_id_1, &promise_key_1, not_completed_promise.clone())
+        .await;
+    txn.put_promise(&service_id_1, &promise_key_2, completed_promise.clone())
+        .await;
+    txn.put_promise(&service_id_2, &promise_key_1, completed_promise.clone())
+        .await;
+    txn.commit().await.unwrap();
+
+    // Query
+    assert_eq!(
+        rocksdb
+            .get_promise(&service_id_1, &promise_key_1)
+            .await
+            .unwrap(),
+        Some(not_completed_promise)
+    );
+    assert_eq!(
+        rocksdb
+            .get_promise(&service_id_1, &promise_key_2)
+            .await
+            .unwrap(),
+        Some(completed_promise.clone())
+    );
+
+    // Delete and query afterwards
+    let mut txn = rocksdb.transaction();
+    txn.delete_all_promises(&service_id_1).await.unwrap();
+    txn.commit().await.unwrap();
+
+    assert_eq!(
+        rocksdb
+            .get_promise(&service_id_1, &promise_key_1)
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        rocksdb
+            .get_promise(&service_id_1, &promise_key_2)
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        rocksdb
+            .get_promise(&service_id_2, &promise_key_1)
+            .await
+            .unwrap(),
+        Some(completed_promise)
+    );
+}
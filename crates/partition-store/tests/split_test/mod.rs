@@ -0,0 +1,277 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::storage_test_environment_with_manager;
+use futures_util::StreamExt;
+use restate_partition_store::{OpenMode, SplitFsmVariables, SplitTarget};
+use restate_storage_api::deduplication_table::{
+    DedupSequenceNumber, DeduplicationTable, EpochSequenceNumber, ProducerId,
+    ReadOnlyDeduplicationTable,
+};
+use restate_storage_api::fsm_table::{FsmTable, ReadOnlyFsmTable, SequenceNumber};
+use restate_storage_api::inbox_table::{
+    InboxEntry, InboxTable, ReadOnlyInboxTable, SequenceNumberInboxEntry,
+};
+use restate_storage_api::outbox_table::{OutboxMessage, OutboxTable};
+use restate_storage_api::state_table::{ReadOnlyStateTable, StateTable};
+use restate_storage_api::timer_table::{Timer, TimerTable};
+use restate_storage_api::Transaction;
+use restate_types::config::WorkerOptions;
+use restate_types::identifiers::{
+    InvocationId, InvocationUuid, LeaderEpoch, PartitionId, PartitionKey, ServiceId,
+    WithPartitionKey,
+};
+use restate_types::invocation::InvocationTermination;
+use std::pin::pin;
+
+const SPLIT_KEY: PartitionKey = 1 << 63;
+const LEFT: PartitionId = PartitionId::new_unchecked(1);
+const RIGHT: PartitionId = PartitionId::new_unchecked(2);
+const STATE_ID: u64 = 1337;
+const INHERITED_STATE_ID: u64 = 1338;
+const INBOX_SEQ_NUMBER: u64 = 1339;
+const OUTBOX_SEQ_NUMBER: u64 = 1340;
+
+fn service_id(partition_key: PartitionKey) -> ServiceId {
+    ServiceId::with_partition_key(partition_key, "svc", format!("key-{partition_key}"))
+}
+
+fn invocation_id(partition_key: PartitionKey) -> InvocationId {
+    InvocationId::from_parts(partition_key, InvocationUuid::new())
+}
+
+fn outbox_message(partition_key: PartitionKey) -> OutboxMessage {
+    OutboxMessage::InvocationTermination(InvocationTermination::kill(invocation_id(partition_key)))
+}
+
+fn inbox_entry(service_id: &ServiceId, seq_number: u64) -> SequenceNumberInboxEntry {
+    SequenceNumberInboxEntry::new(
+        seq_number,
+        InboxEntry::Invocation(
+            service_id.clone(),
+            invocation_id(service_id.partition_key()),
+        ),
+    )
+}
+
+async fn seq_number(
+    store: &mut impl ReadOnlyFsmTable,
+    partition_id: PartitionId,
+    state_id: u64,
+) -> Option<u64> {
+    store
+        .get::<SequenceNumber>(partition_id, state_id)
+        .await
+        .unwrap()
+        .map(u64::from)
+}
+
+async fn timers(store: &mut impl TimerTable, partition_id: PartitionId) -> Vec<Timer> {
+    let mut timers = pin!(store.next_timers_greater_than(partition_id, None, usize::MAX));
+    let mut result = vec![];
+    while let Some(timer) = timers.next().await {
+        result.push(timer.expect("timer can be read").1);
+    }
+    result
+}
+
+#[tokio::test]
+async fn test_split() {
+    let (manager, mut rocksdb) = storage_test_environment_with_manager().await;
+    let low = service_id(42);
+    let high = service_id(SPLIT_KEY + 42);
+    let low_timer = Timer::clean_invocation_status(1, invocation_id(7));
+    let high_timer = Timer::clean_invocation_status(2, invocation_id(SPLIT_KEY));
+    let low_inbox_entries = [inbox_entry(&low, 2), inbox_entry(&low, 5)];
+    let high_inbox_entry = inbox_entry(&high, 4);
+    let producer = ProducerId::Partition(PartitionId::new_unchecked(42));
+
+    let mut txn = rocksdb.transaction();
+    txn.put_user_state(&low, "k", "low").await;
+    txn.put_user_state(&high, "k", "high").await;
+    txn.add_timer(PartitionId::MIN, &low_timer.0, low_timer.1.clone())
+        .await;
+    txn.add_timer(PartitionId::MIN, &high_timer.0, high_timer.1.clone())
+        .await;
+    for entry in &low_inbox_entries {
+        txn.put_inbox_entry(&low, entry.clone()).await;
+    }
+    txn.put_inbox_entry(&high, high_inbox_entry.clone()).await;
+    txn.put(PartitionId::MIN, INBOX_SEQ_NUMBER, SequenceNumber::from(6))
+        .await;
+    txn.put(PartitionId::MIN, OUTBOX_SEQ_NUMBER, SequenceNumber::from(7))
+        .await;
+    txn.put_dedup_seq_number(
+        PartitionId::MIN,
+        producer.clone(),
+        DedupSequenceNumber::Sn(3),
+    )
+    .await;
+    txn.put_dedup_seq_number(
+        PartitionId::MIN,
+        ProducerId::self_producer(),
+        DedupSequenceNumber::Esn(EpochSequenceNumber::new(LeaderEpoch::INITIAL)),
+    )
+    .await;
+    txn.put(PartitionId::MIN, STATE_ID, SequenceNumber::from(1))
+        .await;
+    txn.put(
+        PartitionId::MIN,
+        INHERITED_STATE_ID,
+        SequenceNumber::from(2),
+    )
+    .await;
+    txn.commit().await.expect("commit succeeds");
+
+    let (mut left, mut right) = manager
+        .split_partition_store(
+            PartitionId::MIN,
+            SplitTarget::new(LEFT, 0..=SPLIT_KEY - 1),
+            SplitTarget::new(RIGHT, SPLIT_KEY..=PartitionKey::MAX - 1),
+            SplitFsmVariables {
+                inherited: vec![INHERITED_STATE_ID],
+                inbox_seq_number: INBOX_SEQ_NUMBER,
+                outbox_seq_number: OUTBOX_SEQ_NUMBER,
+            },
+            &WorkerOptions::default().storage.rocksdb,
+        )
+        .await
+        .expect("split succeeds");
+
+    // state is owned by the partition of its key
+    assert_eq!(
+        left.get_user_state(&low, "k").await.unwrap(),
+        Some("low".into())
+    );
+    assert_eq!(left.get_user_state(&high, "k").await.unwrap(), None);
+    assert_eq!(
+        right.get_user_state(&high, "k").await.unwrap(),
+        Some("high".into())
+    );
+    assert_eq!(right.get_user_state(&low, "k").await.unwrap(), None);
+
+    // timers are re-keyed for the owning partition
+    assert_eq!(timers(&mut left, LEFT).await, vec![low_timer.1]);
+    assert_eq!(timers(&mut right, RIGHT).await, vec![high_timer.1]);
+
+    // the outbox has been truncated, the new partitions start their own
+    for (store, partition_id) in [(&mut left, LEFT), (&mut right, RIGHT)] {
+        assert_eq!(
+            seq_number(store, partition_id, OUTBOX_SEQ_NUMBER).await,
+            Some(0)
+        );
+    }
+
+    // inbox entries keep their sequence numbers, the next sequence number follows the last entry
+    // (the decoded entries carry the hashed partition key of their service id, compare the
+    // sequence numbers only)
+    let mut txn = left.transaction();
+    assert_eq!(
+        txn.peek_inbox(&low)
+            .await
+            .unwrap()
+            .map(|entry| entry.inbox_sequence_number),
+        Some(low_inbox_entries[0].inbox_sequence_number)
+    );
+    assert_eq!(txn.peek_inbox(&high).await.unwrap(), None);
+    drop(txn);
+    assert_eq!(
+        right
+            .transaction()
+            .peek_inbox(&high)
+            .await
+            .unwrap()
+            .map(|entry| entry.inbox_sequence_number),
+        Some(high_inbox_entry.inbox_sequence_number)
+    );
+    assert_eq!(seq_number(&mut left, LEFT, INBOX_SEQ_NUMBER).await, Some(6));
+    assert_eq!(
+        seq_number(&mut right, RIGHT, INBOX_SEQ_NUMBER).await,
+        Some(5)
+    );
+
+    // deduplication information is inherited, except for the own epoch sequence number,
+    // state machine variables only if requested
+    for (store, partition_id) in [(&mut left, LEFT), (&mut right, RIGHT)] {
+        assert_eq!(
+            store
+                .get_dedup_sequence_number(partition_id, &producer)
+                .await
+                .unwrap(),
+            Some(DedupSequenceNumber::Sn(3))
+        );
+        assert_eq!(
+            store
+                .get_dedup_sequence_number(partition_id, &ProducerId::self_producer())
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(seq_number(store, partition_id, STATE_ID).await, None);
+        assert_eq!(
+            seq_number(store, partition_id, INHERITED_STATE_ID).await,
+            Some(2)
+        );
+    }
+
+    // the source partition store is left untouched
+    assert_eq!(
+        rocksdb.get_user_state(&high, "k").await.unwrap(),
+        Some("high".into())
+    );
+}
+
+#[tokio::test]
+async fn test_split_with_untruncated_outbox_message() {
+    // a partition of its own, the other tests use the partitions of test_split
+    const SOURCE: PartitionId = PartitionId::new_unchecked(3);
+    let (manager, _) = storage_test_environment_with_manager().await;
+    let mut source = manager
+        .open_partition_store(
+            SOURCE,
+            0..=PartitionKey::MAX - 1,
+            OpenMode::CreateIfMissing,
+            &WorkerOptions::default().storage.rocksdb,
+        )
+        .await
+        .unwrap();
+    // the message has been delivered, but the outbox hasn't been truncated yet. The receiver
+    // would accept it again under the id of a new partition.
+    let message = outbox_message(SPLIT_KEY);
+    let mut txn = source.transaction();
+    txn.add_message(SOURCE, 3, message.clone()).await;
+    txn.put(SOURCE, OUTBOX_SEQ_NUMBER, SequenceNumber::from(4))
+        .await;
+    txn.commit().await.expect("commit succeeds");
+
+    let result = manager
+        .split_partition_store(
+            SOURCE,
+            SplitTarget::new(PartitionId::new_unchecked(4), 0..=SPLIT_KEY - 1),
+            SplitTarget::new(
+                PartitionId::new_unchecked(5),
+                SPLIT_KEY..=PartitionKey::MAX - 1,
+            ),
+            SplitFsmVariables {
+                inherited: vec![],
+                inbox_seq_number: INBOX_SEQ_NUMBER,
+                outbox_seq_number: OUTBOX_SEQ_NUMBER,
+            },
+            &WorkerOptions::default().storage.rocksdb,
+        )
+        .await;
+    assert!(result.is_err());
+
+    // the message stays with the source partition
+    assert_eq!(
+        source.get_outbox_message(SOURCE, 3).await.unwrap(),
+        Some(message)
+    );
+}
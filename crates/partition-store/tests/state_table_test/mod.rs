@@ -14,6 +14,7 @@ use restate_partition_store::PartitionStore;
 use restate_storage_api::state_table::{ReadOnlyStateTable, StateTable};
 use restate_storage_api::Transaction;
 use restate_types::identifiers::ServiceId;
+use restate_types::time::MillisSinceEpoch;
 
 async fn populate_data<T: StateTable>(table: &mut T) {
     table
@@ -144,3 +145,68 @@ async fn test_delete_all() {
         .expect("should not fail")
         .is_some());
 }
+
+#[tokio::test]
+async fn test_expirations() {
+    let mut rocksdb = storage_test_environment().await;
+    let service_id = ServiceId::with_partition_key(1337, "svc-1", "key-1");
+
+    let mut txn = rocksdb.transaction();
+    populate_data(&mut txn).await;
+    txn.put_user_state_expiration(
+        &service_id,
+        &Bytes::from_static(b"k1"),
+        MillisSinceEpoch::new(1000),
+    )
+    .await;
+    txn.put_user_state_expiration(
+        &service_id,
+        &Bytes::from_static(b"k2"),
+        MillisSinceEpoch::new(2000),
+    )
+    .await;
+    txn.commit().await.expect("should not fail");
+
+    let mut txn = rocksdb.transaction();
+    assert_eq!(
+        txn.get_user_state_expiration(&service_id, &Bytes::from_static(b"k1"))
+            .await
+            .expect("should not fail"),
+        Some(MillisSinceEpoch::new(1000))
+    );
+    assert_stream_eq(
+        txn.get_all_user_state_expirations(&service_id),
+        vec![
+            (Bytes::from_static(b"k1"), MillisSinceEpoch::new(1000)),
+            (Bytes::from_static(b"k2"), MillisSinceEpoch::new(2000)),
+        ],
+    )
+    .await;
+    // Expirations don't show up as user state
+    assert_stream_eq(
+        txn.get_all_user_states(&service_id),
+        vec![
+            (Bytes::from_static(b"k1"), Bytes::from_static(b"v1")),
+            (Bytes::from_static(b"k2"), Bytes::from_static(b"v2")),
+        ],
+    )
+    .await;
+
+    // Deleting the state deletes its expiration
+    txn.delete_user_state(&service_id, &Bytes::from_static(b"k1"))
+        .await;
+    txn.commit().await.expect("should not fail");
+
+    let mut txn = rocksdb.transaction();
+    assert!(txn
+        .get_user_state_expiration(&service_id, &Bytes::from_static(b"k1"))
+        .await
+        .expect("should not fail")
+        .is_none());
+
+    txn.delete_all_user_state(&service_id).await.unwrap();
+    txn.commit().await.expect("should not fail");
+
+    let mut txn = rocksdb.transaction();
+    assert_stream_eq(txn.get_all_user_state_expirations(&service_id), vec![]).await;
+}
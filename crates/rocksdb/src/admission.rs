@@ -0,0 +1,126 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use metrics::{counter, gauge};
+use parking_lot::Mutex;
+
+use crate::metric_definitions::*;
+use crate::{DbName, Priority};
+
+/// Bounds the number of storage tasks that are queued or running on one of the storage thread
+/// pools.
+///
+/// Fairness: a database is admitted beyond its fair share (`depth / number of databases`) only
+/// while the queue is less than half full. This way a single busy database can use the idle
+/// capacity but can't starve the others.
+///
+/// Tasks that bypass admission are always admitted, they take a slot nonetheless.
+pub(crate) struct AdmissionQueue {
+    priority: Priority,
+    depth: AtomicUsize,
+    state: Mutex<QueueState>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    in_flight: usize,
+    per_db: HashMap<DbName, usize>,
+}
+
+impl AdmissionQueue {
+    pub(crate) fn new(priority: Priority, depth: usize) -> Arc<Self> {
+        Arc::new(Self {
+            priority,
+            depth: AtomicUsize::new(depth),
+            state: Mutex::default(),
+        })
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_depth(&self, depth: usize) {
+        self.depth.store(depth, Ordering::Relaxed);
+    }
+
+    /// Returns `None` if the task must be rejected.
+    pub(crate) fn try_admit(
+        self: &Arc<Self>,
+        db: Option<&DbName>,
+        num_dbs: usize,
+        bypass: bool,
+    ) -> Option<AdmissionPermit> {
+        let depth = self.depth();
+        let fair_share = depth.div_ceil(num_dbs.max(1));
+
+        let mut state = self.state.lock();
+        let admitted = bypass
+            || (state.in_flight < depth
+                && match db {
+                    Some(db) => {
+                        state.per_db.get(db).copied().unwrap_or_default() < fair_share
+                            || state.in_flight < depth / 2
+                    }
+                    None => true,
+                });
+        if !admitted {
+            drop(state);
+            counter!(STORAGE_BG_TASK_REJECTED,
+                PRIORITY => self.priority.as_static_str(),
+            )
+            .increment(1);
+            return None;
+        }
+
+        state.in_flight += 1;
+        if let Some(db) = db {
+            *state.per_db.entry(db.clone()).or_default() += 1;
+        }
+        gauge!(STORAGE_QUEUE_DEPTH, PRIORITY => self.priority.as_static_str())
+            .set(state.in_flight as f64);
+
+        Some(AdmissionPermit {
+            queue: self.clone(),
+            db: db.cloned(),
+        })
+    }
+
+    fn release(&self, db: Option<&DbName>) {
+        let mut state = self.state.lock();
+        state.in_flight -= 1;
+        if let Some(db) = db {
+            if let Some(count) = state.per_db.get_mut(db) {
+                *count -= 1;
+                if *count == 0 {
+                    state.per_db.remove(db);
+                }
+            }
+        }
+        gauge!(STORAGE_QUEUE_DEPTH, PRIORITY => self.priority.as_static_str())
+            .set(state.in_flight as f64);
+    }
+}
+
+/// Holds a slot in the queue until dropped, it moves with the task into the thread pool.
+pub(crate) struct AdmissionPermit {
+    queue: Arc<AdmissionQueue>,
+    db: Option<DbName>,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.queue.release(self.db.as_ref());
+    }
+}
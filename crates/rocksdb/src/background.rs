@@ -15,25 +15,45 @@ use metrics::{gauge, histogram};
 use tokio::sync::oneshot;
 
 use crate::metric_definitions::{
-    STORAGE_BG_TASK_RUN_DURATION, STORAGE_BG_TASK_TOTAL_DURATION, STORAGE_BG_TASK_WAIT_DURATION,
+    DB_NAME, STORAGE_BG_TASK_QUEUE_LATENCY, STORAGE_BG_TASK_RUN_DURATION,
+    STORAGE_BG_TASK_TOTAL_DURATION, STORAGE_BG_TASK_WAIT_DURATION,
 };
-use crate::{Priority, OP_TYPE, PRIORITY, STORAGE_BG_TASK_IN_FLIGHT};
+use crate::{DbName, Priority, OP_TYPE, PRIORITY, STORAGE_BG_TASK_IN_FLIGHT};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::IntoStaticStr)]
 #[strum(serialize_all = "kebab-case")]
 pub enum StorageTaskKind {
     WriteBatch,
+    DeleteRange,
+    Scan,
+    MultiGet,
+    VerifyChecksums,
+    Resume,
     OpenColumnFamily,
+    DropColumnFamily,
     FlushWal,
     FlushMemtables,
     Shutdown,
     OpenDb,
+    Compaction,
+    Checkpoint,
+    IngestExternalFiles,
+    CatchUpWithPrimary,
 }
 
 impl StorageTaskKind {
     pub fn as_static_str(&self) -> &'static str {
         self.into()
     }
+
+    /// Writes and flushes are admitted even if their queue is full. Rejecting them would fail
+    /// partition store and log writes under load, they still count towards the queue depth.
+    pub(crate) fn bypasses_admission(&self) -> bool {
+        matches!(
+            self,
+            Self::WriteBatch | Self::DeleteRange | Self::FlushWal | Self::FlushMemtables
+        )
+    }
 }
 
 #[derive(Builder)]
@@ -44,7 +64,10 @@ pub struct ReadyStorageTask<OP> {
     #[builder(default)]
     pub(crate) priority: Priority,
     /// required
-    kind: StorageTaskKind,
+    pub(crate) kind: StorageTaskKind,
+    /// The database this task operates on, used for fair admission to the storage pools.
+    #[builder(default, setter(strip_option))]
+    pub(crate) db: Option<DbName>,
     #[builder(setter(skip))]
     #[builder(default = "Instant::now()")]
     created_at: Instant,
@@ -91,6 +114,13 @@ where
              OP_TYPE => self.kind.as_static_str(),
         )
         .record(self.created_at.elapsed());
+        if let Some(db) = &self.db {
+            histogram!(STORAGE_BG_TASK_QUEUE_LATENCY,
+                 PRIORITY => self.priority.as_static_str(),
+                 DB_NAME => db.to_string(),
+            )
+            .record(self.created_at.elapsed());
+        }
         let res = (self.op)();
         histogram!(STORAGE_BG_TASK_RUN_DURATION,
              PRIORITY => self.priority.as_static_str(),
@@ -0,0 +1,210 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Reports background activity of rocksdb (flushes, compactions, write stalls and background
+//! errors) to tracing and metrics.
+//!
+//! The rocksdb bindings don't support event listeners, the activity is derived from database
+//! properties that the watchdog samples every `rocksdb-properties-poll-interval` instead.
+//! Durations of individual flushes and compactions are part of the exported statistics.
+
+use std::collections::HashMap;
+
+use metrics::{counter, gauge};
+use tracing::{error, info, warn};
+
+use crate::metric_definitions::*;
+use crate::{DbName, RocksDb, RocksDbManager};
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum WriteStall {
+    #[default]
+    Normal,
+    Delayed,
+    Stopped,
+}
+
+impl WriteStall {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WriteStall::Normal => "normal",
+            WriteStall::Delayed => "delayed",
+            WriteStall::Stopped => "stopped",
+        }
+    }
+}
+
+/// Database-wide properties sampled at a single point in time.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct ActivitySample {
+    pub(crate) running_flushes: u64,
+    pub(crate) running_compactions: u64,
+    pub(crate) write_stall: WriteStall,
+    /// Number of background errors since the database was opened
+    pub(crate) background_errors: u64,
+}
+
+impl ActivitySample {
+    /// Returns `None` if the database has no column family to read the properties from.
+    fn read(db: &RocksDb) -> Option<Self> {
+        // the properties are database-wide, any column family will do
+        let cf = db.cfs().into_iter().next()?;
+        let read = |property| db.inner().get_property_int_cf(&cf, property).ok().flatten();
+
+        let write_stall = if read("rocksdb.is-write-stopped").unwrap_or_default() > 0 {
+            WriteStall::Stopped
+        } else if read("rocksdb.actual-delayed-write-rate").unwrap_or_default() > 0 {
+            WriteStall::Delayed
+        } else {
+            WriteStall::Normal
+        };
+        Some(Self {
+            running_flushes: read("rocksdb.num-running-flushes").unwrap_or_default(),
+            running_compactions: read("rocksdb.num-running-compactions").unwrap_or_default(),
+            write_stall,
+            background_errors: read("rocksdb.background-errors").unwrap_or_default(),
+        })
+    }
+}
+
+/// What changed since the previous sample of a database.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub(crate) struct ActivityChanges {
+    /// The new write stall condition, if it changed
+    pub(crate) write_stall: Option<WriteStall>,
+    /// Number of background errors that occurred since the previous sample
+    pub(crate) new_background_errors: u64,
+}
+
+/// Owned by the watchdog, remembers the last sample of every open database.
+#[derive(Debug, Default)]
+pub(crate) struct BackgroundActivity {
+    last_samples: HashMap<DbName, ActivitySample>,
+}
+
+impl BackgroundActivity {
+    /// Samples all open databases and reports what changed. Databases with new background errors
+    /// are quarantined.
+    pub(crate) fn poll(&mut self, manager: &RocksDbManager) {
+        let dbs = manager.get_all_dbs();
+        self.last_samples
+            .retain(|name, _| dbs.iter().any(|db| &db.name == name));
+
+        for db in dbs {
+            let Some(sample) = ActivitySample::read(&db) else {
+                continue;
+            };
+            let db_name = db.name.to_string();
+            gauge!(ROCKSDB_FLUSH_IN_PROGRESS, DB_NAME => db_name.clone())
+                .set(sample.running_flushes as f64);
+            gauge!(ROCKSDB_COMPACTION_IN_PROGRESS, DB_NAME => db_name.clone())
+                .set(sample.running_compactions as f64);
+
+            let changes = self.observe(&db.name, sample);
+            match changes.write_stall {
+                Some(WriteStall::Normal) => {
+                    info!(db = %db.name, "Rocksdb write stall condition cleared");
+                }
+                Some(condition) => {
+                    warn!(
+                        db = %db.name,
+                        "Rocksdb writes are {} due to a write stall condition",
+                        condition.as_str()
+                    );
+                    counter!(ROCKSDB_WRITE_STALL,
+                        DB_NAME => db_name.clone(),
+                        STALL_CONDITION => condition.as_str(),
+                    )
+                    .increment(1);
+                }
+                None => {}
+            }
+            if changes.new_background_errors > 0 {
+                error!(
+                    db = %db.name,
+                    "Rocksdb reported {} background error(s), see the rocksdb LOG for details",
+                    changes.new_background_errors
+                );
+                counter!(ROCKSDB_BACKGROUND_ERRORS, DB_NAME => db_name)
+                    .increment(changes.new_background_errors);
+                manager.record_background_error(
+                    &db.name,
+                    format!(
+                        "{} background error(s) since the database was opened",
+                        sample.background_errors
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Records the sample and returns the changes since the previous one. The first sample of a
+    /// database is compared against an idle database.
+    pub(crate) fn observe(&mut self, name: &DbName, sample: ActivitySample) -> ActivityChanges {
+        let previous = self
+            .last_samples
+            .insert(name.clone(), sample)
+            .unwrap_or_default();
+        ActivityChanges {
+            write_stall: (previous.write_stall != sample.write_stall).then_some(sample.write_stall),
+            // the counter restarts when the database is reopened
+            new_background_errors: sample
+                .background_errors
+                .saturating_sub(previous.background_errors),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_write_stall_transitions() {
+        let mut activity = BackgroundActivity::default();
+        let name = DbName::new("db");
+        let delayed = ActivitySample {
+            write_stall: WriteStall::Delayed,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            activity.observe(&name, ActivitySample::default()),
+            ActivityChanges::default()
+        );
+        assert_eq!(
+            activity.observe(&name, delayed).write_stall,
+            Some(WriteStall::Delayed)
+        );
+        assert_eq!(activity.observe(&name, delayed).write_stall, None);
+        assert_eq!(
+            activity
+                .observe(&name, ActivitySample::default())
+                .write_stall,
+            Some(WriteStall::Normal)
+        );
+    }
+
+    #[test]
+    fn reports_only_new_background_errors() {
+        let mut activity = BackgroundActivity::default();
+        let name = DbName::new("db");
+        let errors = |background_errors| ActivitySample {
+            background_errors,
+            ..Default::default()
+        };
+
+        assert_eq!(activity.observe(&name, errors(2)).new_background_errors, 2);
+        assert_eq!(activity.observe(&name, errors(2)).new_background_errors, 0);
+        assert_eq!(activity.observe(&name, errors(3)).new_background_errors, 1);
+        // reopened database
+        assert_eq!(activity.observe(&name, errors(0)).new_background_errors, 0);
+    }
+}
@@ -0,0 +1,721 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Incremental backups of rocksdb databases to an object store.
+//!
+//! A backup is taken by creating a local checkpoint of the database and uploading its files.
+//! SST and blob files are immutable, so they are uploaded once to a shared location and
+//! referenced by every backup that contains them. Their local names are only unique within one
+//! incarnation of a database, the shared copies are named after their content instead
+//! (`<file-number>_<sha256>_<size>.sst`, similar to rocksdb's BackupEngine). All other files
+//! (MANIFEST, CURRENT, OPTIONS, WAL) are uploaded per backup.
+//!
+//! The destination may be shared by several nodes, every node only writes to and deletes from
+//! its own directory. Layout in the object store:
+//!
+//! ```text
+//! <prefix>/<node-name>/<db-name>/shared/<content-named-file>
+//! <prefix>/<node-name>/<db-name>/backups/<backup-id>/<file>
+//! <prefix>/<node-name>/<db-name>/backups/<backup-id>.json
+//! ```
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::TryStreamExt;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+use restate_core::cancellation_watcher;
+use restate_types::config::CommonOptions;
+
+use crate::{DbName, RocksDb, RocksDbManager, RocksError};
+
+const SHARED_DIR: &str = "shared";
+const BACKUPS_DIR: &str = "backups";
+const MANIFEST_SUFFIX: &str = ".json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("invalid backup destination '{0}': {1}")]
+    InvalidDestination(String, String),
+    #[error(transparent)]
+    ObjectStore(#[from] object_store::Error),
+    #[error(transparent)]
+    Rocks(#[from] RocksError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("malformed backup manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+}
+
+/// Identifies a backup of a database, or a snapshot of other state that is kept in an object
+/// store. Ids are derived from the creation time and are monotonically increasing, they sort
+/// lexicographically in their string form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct BackupId(u64);
+
+impl BackupId {
+    pub fn now() -> Self {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time is after unix epoch")
+            .as_millis();
+        Self(u64::try_from(millis).expect("millis fit in u64"))
+    }
+}
+
+impl std::fmt::Display for BackupId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:020}", self.0)
+    }
+}
+
+impl std::str::FromStr for BackupId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// Name of the file in the database directory
+    pub name: String,
+    pub size: u64,
+    /// Name of the content-addressed copy in the shared directory, for files that are stored
+    /// once and referenced by all backups containing them. `None` for files of a single backup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub id: BackupId,
+    pub db_name: String,
+    pub files: Vec<BackupFile>,
+}
+
+/// Uploads backups of databases to an object store and enforces the retention policy. All
+/// objects are kept below `prefix`, which is expected to be owned by this node.
+#[derive(Clone)]
+pub struct BackupService {
+    store: Arc<dyn ObjectStore>,
+    prefix: ObjectPath,
+    retention: usize,
+}
+
+impl BackupService {
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: ObjectPath, retention: usize) -> Self {
+        Self {
+            store,
+            prefix,
+            retention: retention.max(1),
+        }
+    }
+
+    /// Creates a backup service from a destination url such as `s3://bucket/prefix`,
+    /// `gs://bucket/prefix` or `file:///path`. Cloud credentials are read from the environment.
+    pub fn from_destination(destination: &str, retention: usize) -> Result<Self, BackupError> {
+        let (store, prefix) = build_object_store(destination)?;
+        Ok(Self::new(store, prefix, retention))
+    }
+
+    /// Scopes the service to the backups of the given node, below `<prefix>/<node-name>`.
+    pub fn for_node(mut self, node_name: &str) -> Self {
+        self.prefix = self.prefix.child(node_name);
+        self
+    }
+
+    pub fn store(&self) -> &Arc<dyn ObjectStore> {
+        &self.store
+    }
+
+    fn db_prefix(&self, db_name: &DbName) -> ObjectPath {
+        self.prefix.child(db_name.as_str())
+    }
+
+    fn manifest_path(&self, db_name: &DbName, id: BackupId) -> ObjectPath {
+        self.db_prefix(db_name)
+            .child(BACKUPS_DIR)
+            .child(format!("{}{}", id, MANIFEST_SUFFIX))
+    }
+
+    pub(crate) fn file_path(
+        &self,
+        db_name: &DbName,
+        id: BackupId,
+        file: &BackupFile,
+    ) -> ObjectPath {
+        if let Some(shared_name) = &file.shared_name {
+            self.db_prefix(db_name)
+                .child(SHARED_DIR)
+                .child(shared_name.as_str())
+        } else {
+            self.db_prefix(db_name)
+                .child(BACKUPS_DIR)
+                .child(id.to_string())
+                .child(file.name.as_str())
+        }
+    }
+
+    /// Lists the ids of all complete backups of the database, oldest first.
+    pub async fn list_backups(&self, db_name: &DbName) -> Result<Vec<BackupId>, BackupError> {
+        let backups_dir = self.db_prefix(db_name).child(BACKUPS_DIR);
+        let mut ids: Vec<BackupId> = self
+            .store
+            .list(Some(&backups_dir))
+            .try_filter_map(|meta| async move {
+                Ok(meta
+                    .location
+                    .filename()
+                    .and_then(|name| name.strip_suffix(MANIFEST_SUFFIX))
+                    .and_then(|id| id.parse::<BackupId>().ok()))
+            })
+            .try_collect()
+            .await?;
+        ids.sort();
+        Ok(ids)
+    }
+
+    pub async fn read_manifest(
+        &self,
+        db_name: &DbName,
+        id: BackupId,
+    ) -> Result<BackupManifest, BackupError> {
+        let bytes = self
+            .store
+            .get(&self.manifest_path(db_name, id))
+            .await?
+            .bytes()
+            .await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Returns the manifest of the most recent backup of the database, if any.
+    pub async fn latest_backup(
+        &self,
+        db_name: &DbName,
+    ) -> Result<Option<BackupManifest>, BackupError> {
+        match self.list_backups(db_name).await?.last() {
+            Some(id) => Ok(Some(self.read_manifest(db_name, *id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Takes a new backup of the database. Only SST and blob files whose content is not yet
+    /// present in the object store are uploaded.
+    #[tracing::instrument(skip_all, fields(db = %db.name))]
+    pub async fn backup_db(
+        &self,
+        db: &RocksDb,
+        staging_dir: PathBuf,
+    ) -> Result<BackupManifest, BackupError> {
+        let id = BackupId::now();
+        let checkpoint_dir = staging_dir.join(format!("{}-{}", db.name, id));
+        tokio::fs::create_dir_all(&staging_dir).await?;
+        db.create_checkpoint(checkpoint_dir.clone()).await?;
+
+        let result = self.upload_checkpoint(&db.name, id, &checkpoint_dir).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&checkpoint_dir).await {
+            warn!(
+                path = %checkpoint_dir.display(),
+                "Failed to remove backup staging checkpoint: {}", e
+            );
+        }
+        let manifest = result?;
+
+        info!(
+            backup_id = %id,
+            num_files = manifest.files.len(),
+            "Backup of rocksdb database completed"
+        );
+        Ok(manifest)
+    }
+
+    async fn upload_checkpoint(
+        &self,
+        db_name: &DbName,
+        id: BackupId,
+        checkpoint_dir: &std::path::Path,
+    ) -> Result<BackupManifest, BackupError> {
+        let shared_dir = self.db_prefix(db_name).child(SHARED_DIR);
+        let already_uploaded: HashSet<String> = self
+            .store
+            .list(Some(&shared_dir))
+            .try_filter_map(
+                |meta| async move { Ok(meta.location.filename().map(ToOwned::to_owned)) },
+            )
+            .try_collect()
+            .await?;
+
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(checkpoint_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let shared_name = if is_shared_file(&name) {
+                Some(shared_file_name(entry.path(), name.clone(), metadata.len()).await?)
+            } else {
+                None
+            };
+            let file = BackupFile {
+                name,
+                size: metadata.len(),
+                shared_name,
+            };
+            if file
+                .shared_name
+                .as_ref()
+                .is_some_and(|shared_name| already_uploaded.contains(shared_name))
+            {
+                debug!("Skipping upload of {}, already backed up", file.name);
+            } else {
+                upload_file(
+                    self.store.as_ref(),
+                    &entry.path(),
+                    &self.file_path(db_name, id, &file),
+                )
+                .await?;
+            }
+            files.push(file);
+        }
+
+        let manifest = BackupManifest {
+            id,
+            db_name: db_name.to_string(),
+            files,
+        };
+        // The manifest is uploaded last, a backup without manifest is incomplete and will be
+        // ignored (and eventually garbage collected).
+        self.store
+            .put(
+                &self.manifest_path(db_name, id),
+                serde_json::to_vec(&manifest)?.into(),
+            )
+            .await?;
+        Ok(manifest)
+    }
+
+    /// Downloads the latest backup of the database into `target`, which must not exist or be
+    /// empty. Returns the id of the restored backup, or `None` if the database has no backups.
+    #[tracing::instrument(skip_all, fields(db = %db_name, path = %target.display()))]
+    pub async fn restore_latest(
+        &self,
+        db_name: &DbName,
+        target: &std::path::Path,
+    ) -> Result<Option<BackupId>, BackupError> {
+        let Some(manifest) = self.latest_backup(db_name).await? else {
+            return Ok(None);
+        };
+
+        // Download into a staging directory first and move it in place once complete, a
+        // partially downloaded database must never be opened.
+        let staging_dir = target.with_extension("restoring");
+        if tokio::fs::try_exists(&staging_dir).await? {
+            tokio::fs::remove_dir_all(&staging_dir).await?;
+        }
+        tokio::fs::create_dir_all(&staging_dir).await?;
+
+        for file in &manifest.files {
+            download_file(
+                self.store.as_ref(),
+                &self.file_path(db_name, manifest.id, file),
+                &staging_dir.join(&file.name),
+            )
+            .await?;
+        }
+
+        if tokio::fs::try_exists(target).await? {
+            // only empty directories are replaced
+            tokio::fs::remove_dir(target).await?;
+        }
+        tokio::fs::rename(&staging_dir, target).await?;
+        info!(
+            backup_id = %manifest.id,
+            num_files = manifest.files.len(),
+            "Restored rocksdb database from backup"
+        );
+        Ok(Some(manifest.id))
+    }
+
+    /// Deletes backups exceeding the retention limit, and all files that are no longer
+    /// referenced by any retained backup. Only objects below this node's prefix are touched.
+    #[tracing::instrument(skip_all, fields(db = %db_name))]
+    pub async fn apply_retention(&self, db_name: &DbName) -> Result<(), BackupError> {
+        let backups = self.list_backups(db_name).await?;
+        let num_expired = backups.len().saturating_sub(self.retention);
+        let (expired, retained) = backups.split_at(num_expired);
+
+        let mut live_files = HashSet::new();
+        for id in retained {
+            for file in self.read_manifest(db_name, *id).await?.files {
+                live_files.insert(self.file_path(db_name, *id, &file));
+            }
+        }
+        for id in expired {
+            // remove the manifest first, so that a partially deleted backup is never visible
+            self.store.delete(&self.manifest_path(db_name, *id)).await?;
+            debug!(backup_id = %id, "Deleted expired backup");
+        }
+
+        // Sweep everything that isn't referenced by a retained backup. This also removes leftovers
+        // of incomplete backups.
+        let retained_manifests: HashSet<ObjectPath> = retained
+            .iter()
+            .map(|id| self.manifest_path(db_name, *id))
+            .collect();
+        let orphans: Vec<ObjectPath> = self
+            .store
+            .list(Some(&self.db_prefix(db_name)))
+            .try_filter_map(|meta| {
+                let orphan = !live_files.contains(&meta.location)
+                    && !retained_manifests.contains(&meta.location);
+                async move { Ok(orphan.then_some(meta.location)) }
+            })
+            .try_collect()
+            .await?;
+        for path in orphans {
+            self.store.delete(&path).await?;
+        }
+        Ok(())
+    }
+
+    /// Periodically backs up all databases registered in the manager.
+    pub(crate) async fn run(
+        self,
+        manager: &'static RocksDbManager,
+        interval: Duration,
+        staging_dir: PathBuf,
+    ) -> anyhow::Result<()> {
+        let mut ticker = tokio::time::interval(interval);
+        // the first tick completes immediately, databases are probably still being opened.
+        ticker.tick().await;
+
+        let shutdown_watch = cancellation_watcher();
+        tokio::pin!(shutdown_watch);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_watch => {
+                    break;
+                }
+                _ = ticker.tick() => {
+                    // only the owner of a database backs it up
+                    for db in manager.get_all_dbs().into_iter().filter(|db| db.open_mode.is_primary()) {
+                        if let Err(e) = self.backup_db(&db, staging_dir.clone()).await {
+                            warn!(db = %db.name, "Failed to backup rocksdb database: {}", e);
+                            continue;
+                        }
+                        if let Err(e) = self.apply_retention(&db.name).await {
+                            warn!(db = %db.name, "Failed to apply backup retention: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn from_common_options(opts: &CommonOptions) -> Option<Result<Self, BackupError>> {
+        opts.rocksdb_backup_destination.as_ref().map(|destination| {
+            Ok(
+                Self::from_destination(destination, opts.rocksdb_backup_retention.get())?
+                    .for_node(opts.node_name()),
+            )
+        })
+    }
+
+    pub(crate) fn restore_source_from_common_options(
+        opts: &CommonOptions,
+    ) -> Option<Result<Self, BackupError>> {
+        opts.rocksdb_restore_source
+            .as_ref()
+            .map(|source| Self::from_destination(source, opts.rocksdb_backup_retention.get()))
+    }
+
+    /// Same as [`Self::restore_latest`] for synchronous callers. The restore runs on a dedicated
+    /// thread with its own runtime, so this is safe to call from within a tokio runtime.
+    pub(crate) fn restore_latest_blocking(
+        &self,
+        db_name: &DbName,
+        target: &std::path::Path,
+    ) -> Result<Option<BackupId>, BackupError> {
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(self.restore_latest(db_name, target))
+                })
+                .join()
+                .expect("restore thread doesn't panic")
+        })
+    }
+}
+
+/// SST and blob files never change once written, they can be shared across backups.
+fn is_shared_file(name: &str) -> bool {
+    name.ends_with(".sst") || name.ends_with(".blob")
+}
+
+/// Names the shared copy of a file after its content, `000042.sst` becomes
+/// `000042_<sha256>_<size>.sst`. The file number alone isn't unique, it restarts when a database
+/// is recreated.
+async fn shared_file_name(path: PathBuf, name: String, size: u64) -> Result<String, BackupError> {
+    tokio::task::spawn_blocking(move || {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut std::fs::File::open(path)?.take(size), &mut hasher)?;
+        let (stem, extension) = name.rsplit_once('.').unwrap_or((&name, ""));
+        Ok(format!(
+            "{}_{:x}_{}.{}",
+            stem,
+            hasher.finalize(),
+            size,
+            extension
+        ))
+    })
+    .await
+    .map_err(|e| BackupError::Io(std::io::Error::other(e)))?
+}
+
+/// Uploads a local file to the object store in multiple parts.
+pub async fn upload_file(
+    store: &dyn ObjectStore,
+    source: &std::path::Path,
+    target: &ObjectPath,
+) -> Result<(), BackupError> {
+    let (multipart_id, mut writer) = store.put_multipart(target).await?;
+    let upload = async {
+        let mut file = tokio::fs::File::open(source).await?;
+        tokio::io::copy(&mut file, &mut writer).await?;
+        writer.shutdown().await
+    };
+    if let Err(e) = upload.await {
+        let _ = store.abort_multipart(target, &multipart_id).await;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Downloads an object to a local file and syncs it to disk.
+pub async fn download_file(
+    store: &dyn ObjectStore,
+    source: &ObjectPath,
+    target: &std::path::Path,
+) -> Result<(), BackupError> {
+    let mut stream = store.get(source).await?.into_stream();
+    let mut file = tokio::fs::File::create(target).await?;
+    while let Some(chunk) = stream.try_next().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.sync_all().await?;
+    Ok(())
+}
+
+/// Creates an object store from a destination url such as `s3://bucket/prefix`,
+/// `gs://bucket/prefix` or `file:///path`. Returns the store and the prefix within it.
+pub fn build_object_store(
+    destination: &str,
+) -> Result<(Arc<dyn ObjectStore>, ObjectPath), BackupError> {
+    let invalid = |e: &dyn std::fmt::Display| {
+        BackupError::InvalidDestination(destination.to_owned(), e.to_string())
+    };
+    let url = url::Url::parse(destination).map_err(|e| invalid(&e))?;
+    let prefix = ObjectPath::parse(url.path()).map_err(|e| invalid(&e))?;
+
+    let store: Arc<dyn ObjectStore> = match url.scheme() {
+        "s3" => Arc::new(
+            object_store::aws::AmazonS3Builder::from_env()
+                .with_url(destination)
+                .build()?,
+        ),
+        "gs" => Arc::new(
+            object_store::gcp::GoogleCloudStorageBuilder::from_env()
+                .with_url(destination)
+                .build()?,
+        ),
+        "file" => {
+            let path = url
+                .to_file_path()
+                .map_err(|_| invalid(&"not a valid file path"))?;
+            std::fs::create_dir_all(&path)?;
+            return Ok((
+                Arc::new(object_store::local::LocalFileSystem::new_with_prefix(path)?),
+                ObjectPath::default(),
+            ));
+        }
+        scheme => return Err(invalid(&format!("unsupported scheme '{}'", scheme))),
+    };
+    Ok((store, prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use object_store::memory::InMemory;
+
+    fn write_checkpoint(dir: &std::path::Path, files: &[(&str, &str)]) {
+        std::fs::create_dir_all(dir).unwrap();
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+    }
+
+    async fn backup(
+        service: &BackupService,
+        db_name: &DbName,
+        id: u64,
+        files: &[(&str, &str)],
+    ) -> BackupManifest {
+        let dir = tempfile::tempdir().unwrap();
+        write_checkpoint(dir.path(), files);
+        service
+            .upload_checkpoint(db_name, BackupId(id), dir.path())
+            .await
+            .unwrap()
+    }
+
+    async fn list_objects(store: &Arc<dyn ObjectStore>) -> Vec<String> {
+        let mut objects: Vec<String> = store
+            .list(None)
+            .map_ok(|meta| meta.location.to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        objects.sort();
+        objects
+    }
+
+    #[tokio::test]
+    async fn backup_and_restore() {
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let service = BackupService::new(store, ObjectPath::from("backups"), 2).for_node("n1");
+        let db_name = DbName::new("db");
+
+        backup(
+            &service,
+            &db_name,
+            1,
+            &[("000007.sst", "data"), ("CURRENT", "MANIFEST-000005")],
+        )
+        .await;
+
+        let restore_dir = tempfile::tempdir().unwrap();
+        let target = restore_dir.path().join("db");
+        assert_eq!(
+            service.restore_latest(&db_name, &target).await.unwrap(),
+            Some(BackupId(1))
+        );
+        assert_eq!(
+            std::fs::read_to_string(target.join("000007.sst")).unwrap(),
+            "data"
+        );
+        assert_eq!(
+            std::fs::read_to_string(target.join("CURRENT")).unwrap(),
+            "MANIFEST-000005"
+        );
+
+        // nothing to restore from
+        assert_eq!(
+            service
+                .restore_latest(&DbName::new("other"), &restore_dir.path().join("other"))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn shared_files_are_named_by_content() {
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let service = BackupService::new(store, ObjectPath::from("backups"), 3).for_node("n1");
+        let db_name = DbName::new("db");
+
+        let first = backup(&service, &db_name, 1, &[("000007.sst", "old")]).await;
+        let unchanged = backup(&service, &db_name, 2, &[("000007.sst", "old")]).await;
+        // the database was recreated, file numbers restart
+        let recreated = backup(&service, &db_name, 3, &[("000007.sst", "new")]).await;
+
+        assert_eq!(first.files, unchanged.files);
+        let shared_name = recreated.files[0].shared_name.as_ref().unwrap();
+        assert_ne!(first.files[0].shared_name.as_ref(), Some(shared_name));
+        assert!(shared_name.starts_with("000007_") && shared_name.ends_with("_3.sst"));
+
+        let restore_dir = tempfile::tempdir().unwrap();
+        let target = restore_dir.path().join("db");
+        service.restore_latest(&db_name, &target).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(target.join("000007.sst")).unwrap(),
+            "new"
+        );
+    }
+
+    #[tokio::test]
+    async fn retention_only_deletes_unreferenced_objects_of_own_node() {
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let service = BackupService::new(store.clone(), ObjectPath::from("backups"), 2);
+        let n1 = service.clone().for_node("n1");
+        let n2 = service.for_node("n2");
+        let db_name = DbName::new("db");
+
+        backup(&n2, &db_name, 1, &[("000007.sst", "a"), ("CURRENT", "1")]).await;
+        backup(&n1, &db_name, 1, &[("000007.sst", "a"), ("CURRENT", "1")]).await;
+        let second = backup(
+            &n1,
+            &db_name,
+            2,
+            &[("000007.sst", "a"), ("000008.sst", "b")],
+        )
+        .await;
+        backup(&n1, &db_name, 3, &[("000008.sst", "b"), ("CURRENT", "3")]).await;
+        let n2_objects: Vec<_> = list_objects(&store)
+            .await
+            .into_iter()
+            .filter(|object| object.starts_with("backups/n2/"))
+            .collect();
+
+        n1.apply_retention(&db_name).await.unwrap();
+
+        assert_eq!(
+            n1.list_backups(&db_name).await.unwrap(),
+            vec![BackupId(2), BackupId(3)]
+        );
+        // the per-backup files of the first backup are gone, the shared file of the first backup
+        // is still referenced by the second one
+        let mut expected = n2_objects;
+        expected.extend(
+            second
+                .files
+                .iter()
+                .map(|file| n1.file_path(&db_name, second.id, file).to_string()),
+        );
+        expected.extend([
+            format!("backups/n1/db/backups/{}.json", BackupId(2)),
+            format!("backups/n1/db/backups/{}.json", BackupId(3)),
+            format!("backups/n1/db/backups/{}/CURRENT", BackupId(3)),
+        ]);
+        expected.sort();
+        assert_eq!(list_objects(&store).await, expected);
+    }
+}
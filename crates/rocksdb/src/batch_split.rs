@@ -0,0 +1,186 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Splits serialized write batches at record boundaries.
+//!
+//! A serialized write batch is a 12 byte header (8 bytes sequence number, 4 bytes record count,
+//! both little endian) followed by the records. Every record starts with a tag, records of column
+//! families other than the default one carry the column family id, followed by the length
+//! prefixed key and value (see rocksdb's `db/write_batch.cc`). Records are self-contained, so a
+//! batch can be split by copying ranges of records into new batches.
+
+const HEADER_SIZE: usize = 12;
+const COUNT_OFFSET: usize = 8;
+
+const TYPE_DELETION: u8 = 0x0;
+const TYPE_VALUE: u8 = 0x1;
+const TYPE_MERGE: u8 = 0x2;
+const TYPE_LOG_DATA: u8 = 0x3;
+const TYPE_CF_DELETION: u8 = 0x4;
+const TYPE_CF_VALUE: u8 = 0x5;
+const TYPE_CF_MERGE: u8 = 0x6;
+const TYPE_SINGLE_DELETION: u8 = 0x7;
+const TYPE_CF_SINGLE_DELETION: u8 = 0x8;
+const TYPE_NOOP: u8 = 0xD;
+const TYPE_CF_RANGE_DELETION: u8 = 0xE;
+const TYPE_RANGE_DELETION: u8 = 0xF;
+const TYPE_CF_BLOB_INDEX: u8 = 0x10;
+const TYPE_BLOB_INDEX: u8 = 0x11;
+const TYPE_WIDE_COLUMN_ENTITY: u8 = 0x16;
+const TYPE_CF_WIDE_COLUMN_ENTITY: u8 = 0x17;
+
+/// Splits the serialized write batch into batches of at most `max_bytes` each, unless a single
+/// record is larger than that. The records keep their order.
+///
+/// Returns `None` if the batch can't be split, because it contains transaction markers or is
+/// malformed.
+pub(crate) fn split_write_batch(data: &[u8], max_bytes: usize) -> Option<Vec<Vec<u8>>> {
+    if data.len() < HEADER_SIZE {
+        return None;
+    }
+    let mut splits = Vec::new();
+    let mut current = new_split();
+    let mut current_count = 0u32;
+
+    let mut input = &data[HEADER_SIZE..];
+    while !input.is_empty() {
+        let (len, counted) = record_len(input)?;
+        if current_count > 0 && current.len() + len > max_bytes {
+            splits.push(finish_split(current, current_count));
+            current = new_split();
+            current_count = 0;
+        }
+        current.extend_from_slice(&input[..len]);
+        if counted {
+            current_count += 1;
+        }
+        input = &input[len..];
+    }
+    if current.len() > HEADER_SIZE {
+        splits.push(finish_split(current, current_count));
+    }
+    Some(splits)
+}
+
+fn new_split() -> Vec<u8> {
+    // the sequence number is assigned by the write
+    vec![0; HEADER_SIZE]
+}
+
+fn finish_split(mut split: Vec<u8>, count: u32) -> Vec<u8> {
+    split[COUNT_OFFSET..HEADER_SIZE].copy_from_slice(&count.to_le_bytes());
+    split
+}
+
+/// Returns the length of the record at the start of `input` and whether it counts towards the
+/// number of records in the batch header.
+fn record_len(input: &[u8]) -> Option<(usize, bool)> {
+    let tag = *input.first()?;
+    let mut pos = 1;
+    let (slices, counted) = match tag {
+        TYPE_CF_VALUE
+        | TYPE_CF_MERGE
+        | TYPE_CF_RANGE_DELETION
+        | TYPE_CF_BLOB_INDEX
+        | TYPE_CF_WIDE_COLUMN_ENTITY => {
+            pos += varint32_len(input.get(pos..)?)?;
+            (2, true)
+        }
+        TYPE_CF_DELETION | TYPE_CF_SINGLE_DELETION => {
+            pos += varint32_len(input.get(pos..)?)?;
+            (1, true)
+        }
+        TYPE_VALUE
+        | TYPE_MERGE
+        | TYPE_RANGE_DELETION
+        | TYPE_BLOB_INDEX
+        | TYPE_WIDE_COLUMN_ENTITY => (2, true),
+        TYPE_DELETION | TYPE_SINGLE_DELETION => (1, true),
+        TYPE_LOG_DATA => (1, false),
+        TYPE_NOOP => (0, false),
+        // prepare/commit markers of two-phase commit, such batches must not be split
+        _ => return None,
+    };
+    for _ in 0..slices {
+        let (len, len_size) = read_varint32(input.get(pos..)?)?;
+        pos += len_size + len as usize;
+    }
+    (pos <= input.len()).then_some((pos, counted))
+}
+
+fn varint32_len(input: &[u8]) -> Option<usize> {
+    read_varint32(input).map(|(_, len)| len)
+}
+
+fn read_varint32(input: &[u8]) -> Option<(u32, usize)> {
+    let mut value = 0u32;
+    for (i, byte) in input.iter().take(5).enumerate() {
+        value |= u32::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(data: &[u8]) -> &[u8] {
+        &data[HEADER_SIZE..]
+    }
+
+    #[test]
+    fn splits_at_record_boundaries() {
+        let mut batch = rocksdb::WriteBatch::default();
+        for i in 0..100u32 {
+            batch.put(i.to_be_bytes(), [0u8; 100]);
+        }
+        batch.delete(7u32.to_be_bytes());
+        batch.delete_range(10u32.to_be_bytes(), 20u32.to_be_bytes());
+
+        let splits = split_write_batch(batch.data(), 1024).unwrap();
+        assert!(splits.len() > 1);
+        assert!(splits.iter().all(|split| split.len() <= 1024));
+        // every record ends up in exactly one split, in order
+        let records_of_splits: Vec<u8> = splits
+            .iter()
+            .flat_map(|split| records(split).iter().copied())
+            .collect();
+        assert_eq!(records_of_splits, records(batch.data()));
+        let count: usize = splits
+            .iter()
+            .map(|split| rocksdb::WriteBatch::from_data(split).len())
+            .sum();
+        assert_eq!(count, batch.len());
+    }
+
+    #[test]
+    fn oversized_records_are_not_split() {
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put(b"a", [0u8; 2048]);
+        batch.put(b"b", b"1");
+
+        let splits = split_write_batch(batch.data(), 1024).unwrap();
+        assert_eq!(splits.len(), 2);
+        assert!(splits[0].len() > 2048);
+        assert_eq!(rocksdb::WriteBatch::from_data(&splits[1]).len(), 1);
+    }
+
+    #[test]
+    fn malformed_batches_are_not_split() {
+        assert!(split_write_batch(&[0; HEADER_SIZE - 1], 1024).is_none());
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put(b"a", b"1");
+        let truncated = &batch.data()[..batch.data().len() - 1];
+        assert!(split_write_batch(truncated, 1024).is_none());
+    }
+}
@@ -8,29 +8,49 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize};
-use std::sync::{Arc, OnceLock};
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use metrics::{counter, gauge};
 use parking_lot::RwLock;
-use rocksdb::{BlockBasedOptions, Cache, WriteBufferManager};
+use rocksdb::{BlockBasedOptions, Cache, RateLimiter, WriteBufferManager};
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
 use restate_core::{cancellation_watcher, task_center, ShutdownError, TaskKind};
 use restate_serde_util::ByteCount;
 use restate_types::arc_util::Updateable;
-use restate_types::config::{CommonOptions, Configuration, RocksDbOptions, StatisticsLevel};
+use restate_types::config::{
+    node_filepath, CommonOptions, CompressionType, ConfigWatch, Configuration, RocksDbOptions,
+    StatisticsLevel, StorageWorkStealing,
+};
 
+use crate::admission::AdmissionQueue;
 use crate::background::ReadyStorageTask;
-use crate::{metric_definitions, DbName, DbSpec, Priority, RocksAccess, RocksDb, RocksError};
+use crate::background_activity::BackgroundActivity;
+use crate::backup::BackupService;
+use crate::metric_definitions::*;
+use crate::pool::StoragePool;
+use crate::statistics;
+use crate::{
+    metric_definitions, CfName, Corruption, DbMemoryUsage, DbName, DbSpec, MemoryUsageStats,
+    OpenMode, Priority, RocksAccess, RocksDb, RocksError,
+};
 
-static DB_MANAGER: OnceLock<RocksDbManager> = OnceLock::new();
+/// The manager of this process, leaked so that it can be handed out as `&'static`.
+static DB_MANAGER: RwLock<Option<&'static RocksDbManager>> = RwLock::new(None);
 
 enum WatchdogCommand {
     Register(ConfigSubscription),
+    /// Stop delivering config updates to a closed database
+    Deregister(DbName),
+    /// Periodically catch up a secondary instance with its primary
+    TailPrimary(DbName, Duration),
     #[cfg(any(test, feature = "test-util"))]
     ResetAll(tokio::sync::oneshot::Sender<()>),
 }
@@ -45,12 +65,28 @@ pub struct RocksDbManager {
     cache: Cache,
     // auto updates to changes in common.rocksdb_memory_limit and common.rocksdb_memtable_total_size_limit
     write_buffer_manager: WriteBufferManager,
+    // shared by all databases, auto updates to changes in common.rocksdb_rate_limit_bytes_per_sec
+    rate_limiter: RateLimiter,
     stall_detection_millis: AtomicUsize,
+    group_commit_max_latency_micros: AtomicUsize,
+    group_commit_max_bytes: AtomicUsize,
+    /// 0 if write batches are not split
+    max_write_batch_size: AtomicUsize,
     dbs: RwLock<HashMap<DbName, Arc<RocksDb>>>,
+    /// Last background error reported by rocksdb for each database. A database with a background
+    /// error is considered unhealthy, rocksdb might have switched it to read-only mode.
+    background_errors: RwLock<HashMap<DbName, String>>,
     watchdog_tx: mpsc::UnboundedSender<WatchdogCommand>,
     shutting_down: AtomicBool,
-    high_pri_pool: rayon::ThreadPool,
-    low_pri_pool: rayon::ThreadPool,
+    high_pri_pool: StoragePool,
+    low_pri_pool: StoragePool,
+    // auto updates to changes in common.storage_work_stealing
+    work_stealing: RwLock<StorageWorkStealing>,
+    // auto updates to changes in common.storage_*_priority_queue_depth
+    high_pri_queue: Arc<AdmissionQueue>,
+    low_pri_queue: Arc<AdmissionQueue>,
+    /// Databases that are opened with an empty data directory are restored from here, if set.
+    restore_service: Option<BackupService>,
 }
 
 impl Debug for RocksDbManager {
@@ -62,7 +98,27 @@ impl Debug for RocksDbManager {
 impl RocksDbManager {
     #[track_caller]
     pub fn get() -> &'static RocksDbManager {
-        DB_MANAGER.get().expect("DBManager not initialized")
+        DB_MANAGER.read().expect("DBManager not initialized")
+    }
+
+    /// Forgets the manager of this process and drops its databases, so that the next
+    /// [`RocksDbManager::init`] creates a new manager with the options it's given. The old manager
+    /// isn't freed, it keeps its threads around but refuses to open databases.
+    ///
+    /// Lets the tests of a single process run with managers of different options.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn uninit() {
+        if let Some(manager) = DB_MANAGER.write().take() {
+            manager
+                .shutting_down
+                .store(true, std::sync::atomic::Ordering::Release);
+            // the databases are closed once the last handle to them is dropped
+            manager.dbs.write().clear();
+        }
+    }
+
+    pub(crate) fn try_get() -> Option<&'static RocksDbManager> {
+        *DB_MANAGER.read()
     }
 
     /// Create a new instance of the database manager. This should not be executed concurrently,
@@ -71,23 +127,60 @@ impl RocksDbManager {
     /// Must run in task_center scope.
     pub fn init(mut base_opts: impl Updateable<CommonOptions> + Send + 'static) -> &'static Self {
         // best-effort, it doesn't make concurrent access safe, but it's better than nothing.
-        if let Some(manager) = DB_MANAGER.get() {
+        if let Some(manager) = *DB_MANAGER.read() {
             return manager;
         }
         metric_definitions::describe_metrics();
+        // subscribe before loading the options so that the watchdog, which might start running
+        // only after the first update, doesn't miss any of them
+        let config_watch = Configuration::watcher();
         let opts = base_opts.load();
+        let backup_service = match BackupService::from_common_options(opts) {
+            Some(Ok(service)) => Some((service, *opts.rocksdb_backup_interval)),
+            Some(Err(e)) => {
+                warn!(
+                    "Rocksdb backups are disabled, invalid backup configuration: {}",
+                    e
+                );
+                None
+            }
+            None => None,
+        };
+        let restore_service = match BackupService::restore_source_from_common_options(opts) {
+            Some(Ok(service)) => Some(service),
+            Some(Err(e)) => {
+                warn!("Rocksdb restore is disabled, invalid restore source: {}", e);
+                None
+            }
+            None => None,
+        };
         let cache = Cache::new_lru_cache(opts.rocksdb_total_memory_size.get());
         let write_buffer_manager = WriteBufferManager::new_write_buffer_manager_with_cache(
             opts.rocksdb_total_memtables_size(),
             true,
             cache.clone(),
         );
+        let rate_limiter = RateLimiter::new_rate_limiter(
+            rate_limit_bytes_per_sec(opts),
+            RATE_LIMITER_REFILL_PERIOD_US,
+            RATE_LIMITER_FAIRNESS,
+        );
         // There is no atomic u128 (and it's a ridiculous amount of time anyway), we trim the value
         // to usize and hope for the best.
         let stall_detection_millis = AtomicUsize::new(
             usize::try_from(opts.rocksdb_write_stall_threshold.as_millis())
                 .expect("threshold fits usize"),
         );
+        let group_commit_max_latency_micros = AtomicUsize::new(
+            usize::try_from(opts.rocksdb_group_commit_max_latency.as_micros())
+                .expect("latency fits usize"),
+        );
+        let group_commit_max_bytes = AtomicUsize::new(opts.rocksdb_group_commit_max_bytes.get());
+        let max_write_batch_size = AtomicUsize::new(
+            opts.rocksdb_max_write_batch_size
+                .map(NonZeroUsize::get)
+                .unwrap_or_default(),
+        );
         // Setup the shared rocksdb environment
         let mut env = rocksdb::Env::new().expect("rocksdb env is created");
         env.set_low_priority_background_threads(opts.rocksdb_bg_threads().get() as i32);
@@ -106,7 +199,13 @@ impl RocksDbManager {
             .build()
             .expect("storage low priority thread pool to be created");
 
+        let high_pri_queue =
+            AdmissionQueue::new(Priority::High, opts.storage_high_priority_queue_depth.get());
+        let low_pri_queue =
+            AdmissionQueue::new(Priority::Low, opts.storage_low_priority_queue_depth.get());
+
         let dbs = RwLock::default();
+        let applied_opts = opts.clone();
 
         // unbounded channel since commands are rare and we don't want to block
         let (watchdog_tx, watchdog_rx) = mpsc::unbounded_channel();
@@ -115,25 +214,56 @@ impl RocksDbManager {
             env,
             cache,
             write_buffer_manager,
+            rate_limiter,
             dbs,
+            background_errors: RwLock::default(),
             watchdog_tx,
             shutting_down: AtomicBool::new(false),
-            high_pri_pool,
-            low_pri_pool,
+            high_pri_pool: StoragePool::new(high_pri_pool),
+            low_pri_pool: StoragePool::new(low_pri_pool),
+            work_stealing: RwLock::new(opts.storage_work_stealing),
+            high_pri_queue,
+            low_pri_queue,
             stall_detection_millis,
+            group_commit_max_latency_micros,
+            group_commit_max_bytes,
+            max_write_batch_size,
+            restore_service,
         };
 
-        DB_MANAGER.set(manager).expect("DBManager initialized once");
+        let previous = DB_MANAGER.write().replace(Box::leak(Box::new(manager)));
+        assert!(previous.is_none(), "DBManager initialized once");
         // Start db monitoring.
         task_center()
             .spawn(
                 TaskKind::SystemService,
                 "db-manager",
                 None,
-                DbWatchdog::run(Self::get(), watchdog_rx, base_opts),
+                DbWatchdog::run(
+                    Self::get(),
+                    watchdog_rx,
+                    base_opts,
+                    applied_opts,
+                    config_watch,
+                ),
             )
             .expect("run db watchdog");
 
+        if let Some((backup_service, interval)) = backup_service {
+            task_center()
+                .spawn(
+                    TaskKind::SystemService,
+                    "db-backup",
+                    None,
+                    backup_service.run(
+                        Self::get(),
+                        interval,
+                        node_filepath("rocksdb-backup-staging"),
+                    ),
+                )
+                .expect("run db backups");
+        }
+
         Self::get()
     }
 
@@ -141,6 +271,38 @@ impl RocksDbManager {
         self.dbs.read().get(&name).cloned()
     }
 
+    /// Returns the last background error reported by rocksdb for this database, if any.
+    pub fn background_error(&self, name: &DbName) -> Option<String> {
+        self.background_errors.read().get(name).cloned()
+    }
+
+    /// Databases that have reported a background error since they were opened.
+    pub fn unhealthy_dbs(&self) -> Vec<DbName> {
+        self.background_errors.read().keys().cloned().collect()
+    }
+
+    /// Quarantines the database, writes are rejected until [`RocksDb::try_resume`] succeeds.
+    pub(crate) fn record_background_error(&self, name: &DbName, error: String) {
+        if self
+            .background_errors
+            .write()
+            .insert(name.clone(), error)
+            .is_none()
+        {
+            warn!(
+                db = %name,
+                "Rocksdb database is quarantined after a background error, writes are rejected until it's resumed"
+            );
+            gauge!(ROCKSDB_QUARANTINED, DB_NAME => name.to_string()).set(1.0);
+        }
+    }
+
+    pub(crate) fn clear_background_error(&self, name: &DbName) {
+        if self.background_errors.write().remove(name).is_some() {
+            gauge!(ROCKSDB_QUARANTINED, DB_NAME => name.to_string()).set(0.0);
+        }
+    }
+
     // todo: move this to async after allowing bifrost to async-create providers.
     pub fn open_db<T: RocksAccess + Send + Sync + 'static>(
         &'static self,
@@ -157,19 +319,56 @@ impl RocksDbManager {
         // get latest options
         let options = updateable_opts.load().clone();
         let name = db_spec.name.clone();
+        if self.dbs.read().contains_key(&name) {
+            return Err(RocksError::AlreadyOpen);
+        }
         // use the spec default options as base then apply the config from the updateable.
         self.amend_db_options(&mut db_spec.db_options, &options);
+        if !db_spec.open_mode.is_primary() {
+            // We only look at an existing database, never create one.
+            db_spec.db_options.create_if_missing(false);
+            db_spec.db_options.create_missing_column_families(false);
+        }
+        if let OpenMode::Secondary { .. } = db_spec.open_mode {
+            // required by rocksdb for secondary instances
+            db_spec.db_options.set_max_open_files(-1);
+        }
 
+        if let Some(restore_service) = &self.restore_service {
+            if db_spec.open_mode.is_primary() && is_empty_dir(&db_spec.path)? {
+                self.restore_db(restore_service, &db_spec.name, &db_spec.path)?;
+            }
+        }
+
+        // a freshly opened database starts healthy
+        self.clear_background_error(&name);
         let db = Arc::new(RocksAccess::open_db(
             &db_spec,
             self.default_cf_options(&options),
+            &options,
         )?);
 
         let path = db_spec.path.clone();
+        let open_mode = db_spec.open_mode;
         let wrapper = Arc::new(RocksDb::new(self, db_spec, db.clone()));
 
         self.dbs.write().insert(name.clone(), wrapper);
 
+        if let OpenMode::Secondary { tail_interval } = open_mode {
+            if let Err(e) = self
+                .watchdog_tx
+                .send(WatchdogCommand::TailPrimary(name.clone(), tail_interval))
+            {
+                warn!(
+                    db = %name,
+                    path = %path.display(),
+                    "Failed to register secondary database with watchdog: {}, this database will \
+                        not catch up with the primary",
+                    e
+                );
+            }
+        }
+
         if let Err(e) = self
             .watchdog_tx
             .send(WatchdogCommand::Register(ConfigSubscription {
@@ -194,6 +393,73 @@ impl RocksDbManager {
         Ok(db)
     }
 
+    /// Closes the database and removes it from the manager, it can be opened again with
+    /// [`Self::open_db`] afterwards.
+    ///
+    /// The caller must drop its handles to the database (the one returned by `open_db` and any
+    /// [`RocksDb`]) first. Storage tasks that are still running for the database are waited for
+    /// up to `timeout`, if the database is still in use after that, it's left open and
+    /// [`RocksError::DbInUse`] is returned.
+    pub async fn close_db(
+        &'static self,
+        name: DbName,
+        timeout: Duration,
+    ) -> Result<(), RocksError> {
+        let Some(db) = self.dbs.write().remove(&name) else {
+            return Err(RocksError::UnknownDb(name));
+        };
+
+        // Wait until we hold the last reference, the database is closed when it's dropped.
+        let deadline = Instant::now() + timeout;
+        while Arc::strong_count(&db) > 1 || Arc::strong_count(db.inner()) > 1 {
+            if Instant::now() >= deadline {
+                self.dbs.write().insert(name.clone(), db);
+                return Err(RocksError::DbInUse(name));
+            }
+            tokio::time::sleep(CLOSE_DB_POLL_INTERVAL).await;
+        }
+
+        let _ = self
+            .watchdog_tx
+            .send(WatchdogCommand::Deregister(name.clone()));
+        // flushes according to the spec and stops background work
+        db.shutdown().await;
+        self.clear_background_error(&name);
+        info!(db = %name, "Rocksdb database closed");
+        Ok(())
+    }
+
+    fn restore_db(
+        &self,
+        restore_service: &BackupService,
+        name: &DbName,
+        path: &Path,
+    ) -> Result<(), RocksError> {
+        info!(
+            db = %name,
+            path = %path.display(),
+            "Database directory is empty, attempting to restore from latest backup"
+        );
+        match restore_service.restore_latest_blocking(name, path) {
+            Ok(Some(backup_id)) => {
+                info!(
+                    db = %name,
+                    %backup_id,
+                    "Database was restored from backup"
+                );
+                Ok(())
+            }
+            Ok(None) => {
+                info!(
+                    db = %name,
+                    "No backup found for database, it'll be created empty"
+                );
+                Ok(())
+            }
+            Err(e) => Err(RocksError::Restore(Arc::new(e))),
+        }
+    }
+
     #[cfg(any(test, feature = "test-util"))]
     pub async fn reset(&self) -> anyhow::Result<()> {
         let (tx, rx) = tokio::sync::oneshot::channel();
@@ -213,33 +479,64 @@ impl RocksDbManager {
         self.write_buffer_manager.get_usage() as u64
     }
 
-    /// Returns aggregated memory usage for all databases if filter is empty
-    pub fn get_memory_usage_stats(
-        &self,
-        filter: &[DbName],
-    ) -> Result<rocksdb::perf::MemoryUsage, RocksError> {
-        let mut builder = rocksdb::perf::MemoryUsageBuilder::new()?;
-        builder.add_cache(&self.cache);
-
-        if filter.is_empty() {
-            for db in self.dbs.read().values() {
-                db.inner().record_memory_stats(&mut builder);
-            }
+    /// Returns the memory usage of every column family of the given databases, or of all
+    /// databases if filter is empty. The values are read from in-memory rocksdb properties.
+    pub fn get_memory_usage_stats(&self, filter: &[DbName]) -> MemoryUsageStats {
+        let dbs = if filter.is_empty() {
+            self.get_all_dbs()
         } else {
-            for key in filter {
-                if let Some(db) = self.dbs.read().get(key) {
-                    db.inner().record_memory_stats(&mut builder);
-                }
-            }
-        }
+            filter
+                .iter()
+                .filter_map(|name| self.get_db(name.clone()))
+                .collect()
+        };
 
-        Ok(builder.build()?)
+        MemoryUsageStats {
+            block_cache_usage: self.cache.get_usage() as u64,
+            block_cache_pinned_usage: self.cache.get_pinned_usage() as u64,
+            dbs: dbs.iter().map(|db| DbMemoryUsage::collect(db)).collect(),
+        }
     }
 
     pub fn get_all_dbs(&self) -> Vec<Arc<RocksDb>> {
         self.dbs.read().values().cloned().collect()
     }
 
+    /// Creates a checkpoint of every registered database under `base_dir/<db-name>`. The
+    /// checkpoints are consistent per database, but not across databases.
+    ///
+    /// Returns the location of each database's checkpoint.
+    pub async fn checkpoint_all(
+        &self,
+        base_dir: &Path,
+    ) -> Result<Vec<(DbName, PathBuf)>, RocksError> {
+        let dbs = self.get_all_dbs();
+        let mut checkpoints = Vec::with_capacity(dbs.len());
+        for db in dbs {
+            let path = base_dir.join(db.name.as_str());
+            db.create_checkpoint(path.clone()).await?;
+            info!(
+                db = %db.name,
+                path = %path.display(),
+                "Created rocksdb checkpoint"
+            );
+            checkpoints.push((db.name.clone(), path));
+        }
+        Ok(checkpoints)
+    }
+
+    /// Verifies the checksums of all blocks of every registered database, see
+    /// [`RocksDb::verify_checksums`]. Databases are verified one after the other on the
+    /// low-priority storage pool.
+    pub async fn verify_all(&self) -> Result<Vec<Corruption>, RocksError> {
+        let mut corruptions = Vec::new();
+        for db in self.get_all_dbs() {
+            info!(db = %db.name, "Verifying rocksdb checksums");
+            corruptions.extend(db.verify_checksums(..).await?);
+        }
+        Ok(corruptions)
+    }
+
     pub async fn shutdown(&'static self) {
         // Ask all databases to shutdown cleanly.
         let start = Instant::now();
@@ -274,6 +571,8 @@ impl RocksDbManager {
 
         // write buffer is controlled by write buffer manager
         db_options.set_write_buffer_manager(&self.write_buffer_manager);
+        // flush and compaction IO is throttled by the shared rate limiter
+        db_options.set_rate_limiter(&self.rate_limiter);
 
         // todo: set avoid_unnecessary_blocking_io = true;
 
@@ -315,11 +614,57 @@ impl RocksDbManager {
         )
     }
 
+    pub(crate) fn group_commit_max_latency(&self) -> std::time::Duration {
+        std::time::Duration::from_micros(
+            self.group_commit_max_latency_micros
+                .load(std::sync::atomic::Ordering::Relaxed) as u64,
+        )
+    }
+
+    pub(crate) fn group_commit_max_bytes(&self) -> usize {
+        self.group_commit_max_bytes
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub(crate) fn max_write_batch_size(&self) -> Option<usize> {
+        match self
+            .max_write_batch_size
+            .load(std::sync::atomic::Ordering::Relaxed)
+        {
+            0 => None,
+            max => Some(max),
+        }
+    }
+
     pub(crate) fn default_cf_options(&self, opts: &RocksDbOptions) -> rocksdb::Options {
         let mut cf_options = rocksdb::Options::default();
         // write buffer
         //
         cf_options.set_write_buffer_size(opts.rocksdb_write_buffer_size().get());
+        if let Some(max_write_buffer_number) = opts.rocksdb_max_write_buffer_number() {
+            cf_options.set_max_write_buffer_number(max_write_buffer_number.get() as i32);
+        }
+        if let Some(compression) = opts.rocksdb_compression() {
+            cf_options.set_compression_type(convert_compression_type(compression));
+        }
+        if let Some(per_level) = opts.rocksdb_compression_per_level() {
+            let per_level: Vec<_> = per_level
+                .iter()
+                .copied()
+                .map(convert_compression_type)
+                .collect();
+            cf_options.set_compression_per_level(&per_level);
+        }
+        if let Some(max_dict_bytes) = opts.rocksdb_zstd_max_dict_bytes() {
+            let max_dict_bytes = i32::try_from(max_dict_bytes.get()).unwrap_or(i32::MAX);
+            // rocksdb's defaults for window bits, level and strategy
+            cf_options.set_compression_options(-14, 32767, 0, max_dict_bytes);
+            // zstd recommends training on samples of ~100x the dictionary size
+            cf_options.set_zstd_max_train_bytes(max_dict_bytes.saturating_mul(100));
+        }
+        if let Some(target_file_size) = opts.rocksdb_target_file_size_base() {
+            cf_options.set_target_file_size_base(target_file_size.get() as u64);
+        }
         // bloom filters and block cache.
         //
         let mut block_opts = BlockBasedOptions::default();
@@ -334,11 +679,26 @@ impl RocksDbManager {
         cf_options
     }
 
-    /// Spawn a rocksdb blocking operation in the background
+    /// Spawn a rocksdb blocking operation in the background. Fails with [`RocksError::Busy`]
+    /// if the queue of the task's priority is full, unless the task kind bypasses admission.
     pub(crate) async fn async_spawn<OP, R>(
         &self,
         task: ReadyStorageTask<OP>,
-    ) -> Result<R, ShutdownError>
+    ) -> Result<R, RocksError>
+    where
+        OP: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let rx = self.spawn_with_result(task)?;
+        Ok(rx.await.map_err(|_| ShutdownError)?)
+    }
+
+    /// Schedules the task immediately and returns a receiver for its result. Unlike
+    /// [`Self::async_spawn`], the task doesn't wait for the returned future to be polled to start.
+    pub(crate) fn spawn_with_result<OP, R>(
+        &self,
+        task: ReadyStorageTask<OP>,
+    ) -> Result<tokio::sync::oneshot::Receiver<R>, RocksError>
     where
         OP: FnOnce() -> R + Send + 'static,
         R: Send + 'static,
@@ -347,14 +707,34 @@ impl RocksDbManager {
             .shutting_down
             .load(std::sync::atomic::Ordering::Acquire)
         {
-            return Err(ShutdownError);
+            return Err(RocksError::Shutdown(ShutdownError));
         }
 
-        self.async_spawn_unchecked(task).await
+        let priority = task.priority;
+        let queue = match priority {
+            Priority::High => &self.high_pri_queue,
+            Priority::Low => &self.low_pri_queue,
+        };
+        let num_dbs = self.dbs.read().len();
+        let Some(permit) =
+            queue.try_admit(task.db.as_ref(), num_dbs, task.kind.bypasses_admission())
+        else {
+            return Err(RocksError::Busy(priority));
+        };
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let runner = task.into_async_runner(tx);
+        let runner = move || {
+            // release the slot once the task is done
+            let _permit = permit;
+            runner()
+        };
+        self.pool_for(priority).spawn(runner);
+        Ok(rx)
     }
 
-    /// Ignores the shutdown signal. This should be used if an IO operation needs
-    /// to be performed _during_ shutdown.
+    /// Ignores the shutdown signal and the queue limits. This should be used if an IO operation
+    /// needs to be performed _during_ shutdown.
     pub(crate) async fn async_spawn_unchecked<OP, R>(
         &self,
         task: ReadyStorageTask<OP>,
@@ -364,15 +744,11 @@ impl RocksDbManager {
         R: Send + 'static,
     {
         let (tx, rx) = tokio::sync::oneshot::channel();
-        let priority = task.priority;
-        match priority {
-            Priority::High => self.high_pri_pool.spawn(task.into_async_runner(tx)),
-            Priority::Low => self.low_pri_pool.spawn(task.into_async_runner(tx)),
-        }
+        self.pool_for(task.priority)
+            .spawn(task.into_async_runner(tx));
         rx.await.map_err(|_| ShutdownError)
     }
 
-    #[allow(dead_code)]
     pub(crate) fn spawn<OP>(&self, task: ReadyStorageTask<OP>) -> Result<(), ShutdownError>
     where
         OP: FnOnce() + Send + 'static,
@@ -391,10 +767,26 @@ impl RocksDbManager {
     where
         OP: FnOnce() + Send + 'static,
     {
-        match task.priority {
-            Priority::High => self.high_pri_pool.spawn(task.into_runner()),
-            Priority::Low => self.low_pri_pool.spawn(task.into_runner()),
+        self.pool_for(task.priority).spawn(task.into_runner());
+    }
+
+    /// Returns the pool of the given priority, or the pool of the other priority if work
+    /// stealing allows it and our pool is saturated while the other one has idle threads.
+    fn pool_for(&self, priority: Priority) -> &StoragePool {
+        let (own, other) = match priority {
+            Priority::High => (&self.high_pri_pool, &self.low_pri_pool),
+            Priority::Low => (&self.low_pri_pool, &self.high_pri_pool),
+        };
+        let may_steal = match *self.work_stealing.read() {
+            StorageWorkStealing::Disabled => false,
+            StorageWorkStealing::HighPriority => priority == Priority::High,
+            StorageWorkStealing::Both => true,
+        };
+        if may_steal && own.is_saturated() && !other.is_saturated() {
+            counter!(STORAGE_BG_TASK_STOLEN, PRIORITY => priority.as_static_str()).increment(1);
+            return other;
         }
+        own
     }
 }
 
@@ -412,30 +804,48 @@ struct DbWatchdog {
     updateable_common_opts: Box<dyn Updateable<CommonOptions> + Send>,
     current_common_opts: CommonOptions,
     subscriptions: Vec<ConfigSubscription>,
+    /// Number of entries of the active memtable per column family, and since when it's unchanged
+    memtable_activity: HashMap<(DbName, CfName), (u64, Instant)>,
+    /// When the tombstone-heavy column families were last compacted (or first seen)
+    last_tombstone_compaction: HashMap<(DbName, CfName), Instant>,
+    /// Databases that exceeded their disk budget at the last check
+    over_disk_budget: HashSet<DbName>,
+    background_activity: BackgroundActivity,
 }
 
 impl DbWatchdog {
     pub async fn run(
         manager: &'static RocksDbManager,
         watchdog_rx: mpsc::UnboundedReceiver<WatchdogCommand>,
-        mut updateable_common_opts: impl Updateable<CommonOptions> + Send + 'static,
+        updateable_common_opts: impl Updateable<CommonOptions> + Send + 'static,
+        current_common_opts: CommonOptions,
+        config_watch: ConfigWatch,
     ) -> anyhow::Result<()> {
-        let prev_opts = updateable_common_opts.load().clone();
         let mut watchdog = Self {
             manager,
             cache: manager.cache.clone(),
             watchdog_rx,
             updateable_common_opts: Box::new(updateable_common_opts),
-            current_common_opts: prev_opts,
+            current_common_opts,
             subscriptions: Vec::new(),
+            memtable_activity: HashMap::new(),
+            last_tombstone_compaction: HashMap::new(),
+            over_disk_budget: HashSet::new(),
+            background_activity: BackgroundActivity::default(),
         };
 
         let shutdown_watch = cancellation_watcher();
         tokio::pin!(shutdown_watch);
 
-        let config_watch = Configuration::watcher();
         tokio::pin!(config_watch);
 
+        let mut properties_poll_interval = tokio::time::interval(
+            *watchdog
+                .current_common_opts
+                .rocksdb_properties_poll_interval,
+        );
+        properties_poll_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
         loop {
             tokio::select! {
                 biased;
@@ -451,6 +861,24 @@ impl DbWatchdog {
                 }
                 _ = config_watch.changed() => {
                     watchdog.on_config_update();
+                    let poll_interval =
+                        *watchdog.current_common_opts.rocksdb_properties_poll_interval;
+                    if properties_poll_interval.period() != poll_interval {
+                        properties_poll_interval = tokio::time::interval(poll_interval);
+                        properties_poll_interval
+                            .set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                    }
+                }
+                _ = properties_poll_interval.tick() => {
+                    watchdog.collect_properties();
+                    watchdog.background_activity.poll(watchdog.manager);
+                    watchdog.flush_idle_memtables();
+                    watchdog.compact_tombstone_heavy_cfs();
+                    watchdog.check_disk_budgets();
+                    for db in watchdog.manager.get_all_dbs() {
+                        statistics::export_statistics(&db);
+                        statistics::export_cf_statistics(&db);
+                    }
                 }
             }
         }
@@ -475,7 +903,189 @@ impl DbWatchdog {
                 response.send(()).unwrap();
             }
             WatchdogCommand::Register(sub) => self.subscriptions.push(sub),
+            WatchdogCommand::Deregister(name) => self.subscriptions.retain(|sub| sub.name != name),
+            WatchdogCommand::TailPrimary(name, tail_interval) => {
+                if let Err(e) = task_center().spawn_child(
+                    TaskKind::Disposable,
+                    "db-tail-primary",
+                    None,
+                    tail_primary(self.manager, name.clone(), tail_interval),
+                ) {
+                    warn!(db = %name, "Failed to start tailing the primary: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Publishes a selection of rocksdb properties of every column family of every registered
+    /// database as metrics. The properties are in-memory values, reading them doesn't incur IO.
+    fn collect_properties(&self) {
+        for db in self.manager.get_all_dbs() {
+            for cf in db.cfs() {
+                for (property, metric) in [
+                    ("rocksdb.estimate-num-keys", ROCKSDB_ESTIMATE_NUM_KEYS),
+                    (
+                        "rocksdb.estimate-pending-compaction-bytes",
+                        ROCKSDB_ESTIMATE_PENDING_COMPACTION_BYTES,
+                    ),
+                    (
+                        "rocksdb.num-immutable-mem-table",
+                        ROCKSDB_NUM_IMMUTABLE_MEMTABLES,
+                    ),
+                    ("rocksdb.total-sst-files-size", ROCKSDB_TOTAL_SST_FILES_SIZE),
+                ] {
+                    if let Some(value) = read_property(&db, &cf, property) {
+                        gauge!(metric,
+                            DB_NAME => db.name.to_string(),
+                            CF_NAME => cf.to_string(),
+                        )
+                        .set(value as f64);
+                    }
+                }
+
+                for level in 0..NUM_LEVELS {
+                    let property = format!("rocksdb.num-files-at-level{}", level);
+                    if let Some(value) = read_property(&db, &cf, &property) {
+                        gauge!(ROCKSDB_NUM_FILES_AT_LEVEL,
+                            DB_NAME => db.name.to_string(),
+                            CF_NAME => cf.to_string(),
+                            LEVEL => level.to_string(),
+                        )
+                        .set(value as f64);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Flushes active memtables of databases with disabled WAL that haven't changed for
+    /// `rocksdb_idle_memtable_flush_timeout`. A memtable is considered idle if the number of its
+    /// entries doesn't change between two checks.
+    fn flush_idle_memtables(&mut self) {
+        let Some(timeout) = self.current_common_opts.rocksdb_idle_memtable_flush_timeout else {
+            self.memtable_activity.clear();
+            return;
+        };
+        let timeout: Duration = timeout.into();
+        let now = Instant::now();
+        let mut seen = HashSet::with_capacity(self.memtable_activity.len());
+
+        for sub in &self.subscriptions {
+            if !sub.last_applied_opts.rocksdb_disable_wal() {
+                continue;
+            }
+            let Some(db) = self.manager.get_db(sub.name.clone()) else {
+                continue;
+            };
+            let mut idle_cfs = Vec::new();
+            for cf in db.cfs() {
+                let Some(num_entries) =
+                    read_property(&db, &cf, "rocksdb.num-entries-active-mem-table")
+                else {
+                    continue;
+                };
+                let key = (db.name.clone(), cf.clone());
+                seen.insert(key.clone());
+                match self.memtable_activity.get_mut(&key) {
+                    Some((last_entries, since)) if *last_entries == num_entries => {
+                        if num_entries > 0 && now.duration_since(*since) >= timeout {
+                            idle_cfs.push(cf);
+                            // restart the timer, the flush will reset the entries anyway.
+                            *since = now;
+                        }
+                    }
+                    _ => {
+                        self.memtable_activity.insert(key, (num_entries, now));
+                    }
+                }
+            }
+            if !idle_cfs.is_empty() {
+                debug!(
+                    db = %db.name,
+                    "Flushing idle memtables of column families {:?}",
+                    idle_cfs
+                );
+                db.run_bg_flush_memtables(idle_cfs);
+            }
+        }
+        // forget closed databases and dropped column families
+        self.memtable_activity.retain(|key, _| seen.contains(key));
+    }
+
+    /// Schedules a compaction of the tombstone-heavy key ranges of the column families that
+    /// haven't been compacted for `rocksdb-tombstone-compaction-interval`.
+    fn compact_tombstone_heavy_cfs(&mut self) {
+        let Some(interval) = self
+            .current_common_opts
+            .rocksdb_tombstone_compaction_interval
+        else {
+            self.last_tombstone_compaction.clear();
+            return;
+        };
+        let interval: Duration = interval.into();
+        let now = Instant::now();
+        let mut seen = HashSet::with_capacity(self.last_tombstone_compaction.len());
+
+        for db in self.manager.get_all_dbs() {
+            if !db.open_mode.is_primary() {
+                continue;
+            }
+            let mut due_ranges = Vec::new();
+            for (cf, ranges) in db.tombstone_heavy_ranges() {
+                let key = (db.name.clone(), cf.clone());
+                seen.insert(key.clone());
+                // the first compaction is due one interval after we first see the cf
+                let last = self.last_tombstone_compaction.entry(key).or_insert(now);
+                if now.duration_since(*last) >= interval {
+                    due_ranges.extend(ranges.into_iter().map(|range| (cf.clone(), range)));
+                    *last = now;
+                }
+            }
+            if !due_ranges.is_empty() {
+                debug!(
+                    db = %db.name,
+                    "Compacting tombstone-heavy key ranges {:?}",
+                    due_ranges
+                );
+                db.run_bg_compaction(due_ranges);
+            }
         }
+        // forget closed databases and dropped column families
+        self.last_tombstone_compaction
+            .retain(|key, _| seen.contains(key));
+    }
+
+    /// Publishes the disk usage of the databases with a disk budget and calls the overflow
+    /// callback of those that exceed it.
+    fn check_disk_budgets(&mut self) {
+        let mut over_budget = HashSet::with_capacity(self.over_disk_budget.len());
+        for db in self.manager.get_all_dbs() {
+            let Some(budget) = db.disk_budget() else {
+                continue;
+            };
+            let usage = db.disk_usage();
+            gauge!(ROCKSDB_DISK_USAGE, DB_NAME => db.name.to_string()).set(usage.total() as f64);
+            gauge!(ROCKSDB_DISK_BUDGET_HEADROOM, DB_NAME => db.name.to_string())
+                .set(budget.limit() as f64 - usage.total() as f64);
+            if usage.total() <= budget.limit() {
+                if self.over_disk_budget.contains(&db.name) {
+                    info!(db = %db.name, "Disk usage of database is back within its budget");
+                }
+                continue;
+            }
+            if !self.over_disk_budget.contains(&db.name) {
+                warn!(
+                    db = %db.name,
+                    live_sst_files = usage.live_sst_files,
+                    wal_files = usage.wal_files,
+                    "Database exceeds its disk budget of {} bytes",
+                    budget.limit()
+                );
+            }
+            over_budget.insert(db.name.clone());
+            (budget.on_overflow)(&db.name, &usage);
+        }
+        self.over_disk_budget = over_budget;
     }
 
     fn on_config_update(&mut self) {
@@ -509,6 +1119,44 @@ impl DbWatchdog {
             );
         }
 
+        // Group commit limits changed?
+        if new_common_opts.rocksdb_group_commit_max_latency
+            != self.current_common_opts.rocksdb_group_commit_max_latency
+            || new_common_opts.rocksdb_group_commit_max_bytes
+                != self.current_common_opts.rocksdb_group_commit_max_bytes
+        {
+            info!(
+                max_latency = %new_common_opts.rocksdb_group_commit_max_latency,
+                max_bytes = %ByteCount::from(new_common_opts.rocksdb_group_commit_max_bytes),
+                "[config update] Group commit limits are updated",
+            );
+            self.manager.group_commit_max_latency_micros.store(
+                new_common_opts.rocksdb_group_commit_max_latency.as_micros() as usize,
+                std::sync::atomic::Ordering::Relaxed,
+            );
+            self.manager.group_commit_max_bytes.store(
+                new_common_opts.rocksdb_group_commit_max_bytes.get(),
+                std::sync::atomic::Ordering::Relaxed,
+            );
+        }
+
+        // Write batch size limit changed?
+        if new_common_opts.rocksdb_max_write_batch_size
+            != self.current_common_opts.rocksdb_max_write_batch_size
+        {
+            let max_write_batch_size = new_common_opts
+                .rocksdb_max_write_batch_size
+                .map(NonZeroUsize::get)
+                .unwrap_or_default();
+            info!(
+                max_write_batch_size = %ByteCount::from(max_write_batch_size),
+                "[config update] Max write batch size is updated (0 disables splitting)",
+            );
+            self.manager
+                .max_write_batch_size
+                .store(max_write_batch_size, std::sync::atomic::Ordering::Relaxed);
+        }
+
         // Memory budget changed?
         if new_common_opts.rocksdb_total_memory_size
             != self.current_common_opts.rocksdb_total_memory_size
@@ -541,8 +1189,208 @@ impl DbWatchdog {
                 .set_buffer_size(new_common_opts.rocksdb_total_memtables_size());
         }
 
-        // todo: Apply other changes to the databases.
-        // e.g. set write_buffer_size
+        // IO rate limit changed?
+        if new_common_opts.rocksdb_rate_limit_bytes_per_sec
+            != self.current_common_opts.rocksdb_rate_limit_bytes_per_sec
+        {
+            info!(
+                old = ?self.current_common_opts.rocksdb_rate_limit_bytes_per_sec,
+                new = ?new_common_opts.rocksdb_rate_limit_bytes_per_sec,
+                "[config update] Setting rocksdb IO rate limit",
+            );
+            self.manager
+                .rate_limiter
+                .set_bytes_per_second(rate_limit_bytes_per_sec(new_common_opts));
+        }
+
+        // Storage queue depth changed?
+        for (queue, depth) in [
+            (
+                &self.manager.high_pri_queue,
+                new_common_opts.storage_high_priority_queue_depth,
+            ),
+            (
+                &self.manager.low_pri_queue,
+                new_common_opts.storage_low_priority_queue_depth,
+            ),
+        ] {
+            if queue.depth() != depth.get() {
+                info!(
+                    old = queue.depth(),
+                    new = depth.get(),
+                    "[config update] Setting storage queue depth"
+                );
+                queue.set_depth(depth.get());
+            }
+        }
+
+        // Work stealing changed?
+        if new_common_opts.storage_work_stealing != self.current_common_opts.storage_work_stealing {
+            info!(
+                old = ?self.current_common_opts.storage_work_stealing,
+                new = ?new_common_opts.storage_work_stealing,
+                "[config update] Setting storage work stealing"
+            );
+            *self.manager.work_stealing.write() = new_common_opts.storage_work_stealing;
+        }
+
+        self.current_common_opts = new_common_opts.clone();
+
+        // Apply per-database changes to all column families.
+        for sub in &mut self.subscriptions {
+            let new_opts = sub.updateable_rocksdb_opts.load().clone();
+            let changes = mutable_cf_options_diff(&sub.last_applied_opts, &new_opts);
+            sub.last_applied_opts = new_opts;
+            if changes.is_empty() {
+                continue;
+            }
+            let Some(db) = self.manager.get_db(sub.name.clone()) else {
+                continue;
+            };
+            info!(
+                db = %sub.name,
+                "[config update] Setting column family options {:?}",
+                changes
+            );
+            let changes: Vec<_> = changes.iter().map(|(k, v)| (*k, v.as_str())).collect();
+            for cf in db.cfs() {
+                if let Err(e) = db.inner().set_options_cf(&cf, &changes) {
+                    warn!(
+                        db = %sub.name,
+                        %cf,
+                        "Failed to apply column family options: {}",
+                        e
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Translates changes of the options that rocksdb can change on a live column family into
+/// `set_options_cf` arguments. Options that are unset in `new` are left as they are.
+fn mutable_cf_options_diff(
+    old: &RocksDbOptions,
+    new: &RocksDbOptions,
+) -> Vec<(&'static str, String)> {
+    let mut changes = Vec::new();
+    if old.rocksdb_write_buffer_size() != new.rocksdb_write_buffer_size() {
+        changes.push((
+            "write_buffer_size",
+            new.rocksdb_write_buffer_size().to_string(),
+        ));
+    }
+    if let Some(max_write_buffer_number) = new.rocksdb_max_write_buffer_number() {
+        if old.rocksdb_max_write_buffer_number() != Some(max_write_buffer_number) {
+            changes.push((
+                "max_write_buffer_number",
+                max_write_buffer_number.to_string(),
+            ));
+        }
+    }
+    if let Some(compression) = new.rocksdb_compression() {
+        if old.rocksdb_compression() != Some(compression) {
+            changes.push(("compression", compression_type_name(compression).to_owned()));
+        }
+    }
+    if let Some(per_level) = new.rocksdb_compression_per_level() {
+        if old.rocksdb_compression_per_level() != Some(per_level) {
+            let per_level: Vec<_> = per_level
+                .iter()
+                .map(|compression| compression_type_name(*compression))
+                .collect();
+            changes.push(("compression_per_level", per_level.join(":")));
+        }
+    }
+    if let Some(target_file_size) = new.rocksdb_target_file_size_base() {
+        if old.rocksdb_target_file_size_base() != Some(target_file_size) {
+            changes.push(("target_file_size_base", target_file_size.to_string()));
+        }
+    }
+    changes
+}
+
+/// Name of the compression type in rocksdb's options strings
+fn compression_type_name(compression: CompressionType) -> &'static str {
+    match compression {
+        CompressionType::None => "kNoCompression",
+        CompressionType::Snappy => "kSnappyCompression",
+        CompressionType::Lz4 => "kLZ4Compression",
+        CompressionType::Zstd => "kZSTD",
+    }
+}
+
+fn convert_compression_type(compression: CompressionType) -> rocksdb::DBCompressionType {
+    match compression {
+        CompressionType::None => rocksdb::DBCompressionType::None,
+        CompressionType::Snappy => rocksdb::DBCompressionType::Snappy,
+        CompressionType::Lz4 => rocksdb::DBCompressionType::Lz4,
+        CompressionType::Zstd => rocksdb::DBCompressionType::Zstd,
+    }
+}
+
+/// Rocksdb's default refill period and fairness for the rate limiter
+const RATE_LIMITER_REFILL_PERIOD_US: i64 = 100_000;
+const RATE_LIMITER_FAIRNESS: i32 = 10;
+/// The rate limiter is always installed so that a limit can be set at runtime, this is the
+/// effective limit if unset (1TiB/s).
+const UNLIMITED_BYTES_PER_SEC: i64 = 1 << 40;
+/// How often `close_db` checks whether the database is still in use
+const CLOSE_DB_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+fn rate_limit_bytes_per_sec(opts: &CommonOptions) -> i64 {
+    opts.rocksdb_rate_limit_bytes_per_sec
+        .map(|limit| i64::try_from(limit.get()).unwrap_or(UNLIMITED_BYTES_PER_SEC))
+        .unwrap_or(UNLIMITED_BYTES_PER_SEC)
+}
+
+/// Rocksdb's default number of levels
+const NUM_LEVELS: usize = 7;
+
+fn read_property(db: &RocksDb, cf: &CfName, property: &str) -> Option<u64> {
+    match db.inner().get_property_int_cf(cf, property) {
+        Ok(value) => value,
+        Err(e) => {
+            debug!(
+                db = %db.name,
+                %cf,
+                "Failed to read rocksdb property {}: {}",
+                property,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Catches up the secondary instance `name` with its primary until the database is closed.
+async fn tail_primary(
+    manager: &'static RocksDbManager,
+    name: DbName,
+    tail_interval: Duration,
+) -> anyhow::Result<()> {
+    let mut ticker = tokio::time::interval(tail_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let Some(db) = manager.get_db(name.clone()) else {
+            // database was closed
+            return Ok(());
+        };
+        if !matches!(db.open_mode, OpenMode::Secondary { .. }) {
+            return Ok(());
+        }
+        if let Err(e) = db.try_catch_up_with_primary().await {
+            warn!(db = %name, "Failed to catch up with the primary: {}", e);
+        }
+    }
+}
+
+fn is_empty_dir(path: &Path) -> Result<bool, RocksError> {
+    match std::fs::read_dir(path) {
+        Ok(mut entries) => Ok(entries.next().is_none()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(RocksError::Restore(Arc::new(e.into()))),
     }
 }
 
@@ -9,11 +9,15 @@
 // by the Apache License, Version 2.0.
 
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use derive_builder::Builder;
 use derive_getters::Getters;
+use rocksdb::compaction_filter::Decision;
 
-use crate::{BoxedCfMatcher, BoxedCfOptionUpdater};
+use restate_types::config::RocksDbOptions;
+
+use crate::{BoxedCfMatcher, BoxedCfOptionUpdater, DiskBudget, DiskUsage};
 
 type SmartString = smartstring::SmartString<smartstring::LazyCompact>;
 
@@ -115,6 +119,40 @@ impl CfNameMatch for CfExactPattern {
     }
 }
 
+/// How the database is opened. Only a primary instance can write to the database, the other
+/// modes are meant for tools that inspect a database that is owned by a running node.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum OpenMode {
+    #[default]
+    Primary,
+    /// Opens a consistent view of the database as of the time of opening. Fails if the database
+    /// doesn't exist, writes will fail.
+    ReadOnly,
+    /// Opens the database as a secondary instance that doesn't take the write lock. The secondary
+    /// catches up with the primary by tailing its MANIFEST and WAL every `tail_interval`.
+    ///
+    /// Only supported for [`rocksdb::DB`].
+    Secondary { tail_interval: Duration },
+}
+
+impl OpenMode {
+    pub fn is_primary(&self) -> bool {
+        matches!(self, OpenMode::Primary)
+    }
+}
+
+/// Extracts the expiry time (milliseconds since unix epoch) that is embedded in a key-value
+/// pair. Returns `None` if the entry doesn't expire.
+pub type ExpiryExtractor = fn(key: &[u8], value: &[u8]) -> Option<u64>;
+
+/// A key range of a column family, both bounds are inclusive. `None` for either bound means the
+/// start/end of the column family.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyRange {
+    pub from: Option<Vec<u8>>,
+    pub to: Option<Vec<u8>>,
+}
+
 #[derive(Builder, Getters)]
 #[builder(pattern = "owned", build_fn(name = "build"))]
 pub struct DbSpec<T> {
@@ -124,17 +162,29 @@ pub struct DbSpec<T> {
     /// which should be the default for most cases.
     #[builder(default)]
     pub(crate) flush_on_shutdown: Vec<BoxedCfMatcher>,
+    /// Key ranges of column families that accumulate many tombstones, the manager periodically
+    /// compacts them down to the bottommost level to drop deleted entries for good.
+    #[builder(default)]
+    pub(crate) tombstone_heavy: Vec<(BoxedCfMatcher, KeyRange)>,
     /// Ensure that those column families exist. It's the caller's responsibility to make sure that
     /// those column families have matchers defined in cf_patterns to configure them properly,
     /// otherwise opening the database will fail with `UnknownColumnFamily` error.
     #[builder(default)]
     pub(crate) ensure_column_families: Vec<CfName>,
+    /// Disk space the live SST and WAL files of the database may take up, see
+    /// [`DbSpecBuilder::with_disk_budget`].
+    #[builder(default, setter(custom))]
+    #[getter(skip)]
+    pub(crate) disk_budget: Option<DiskBudget>,
     /// Options applied to the database _before_ applying RocksDbOptions loaded from disk/env.
     #[builder(default)]
     pub(crate) db_options: rocksdb::Options,
+    #[builder(default)]
+    pub(crate) open_mode: OpenMode,
     /// Options of the column family are applied after the values loaded from
     /// RocksDbOptions from disk/env. Those act as column-family specific overrides for that
-    /// particular pattern.
+    /// particular pattern. The patches get the RocksDbOptions the database is opened with, to
+    /// only override what isn't configured.
     ///
     /// Overriding per-column family options from config file is not supported.
     ///
@@ -167,16 +217,104 @@ impl<T> DbSpecBuilder<T> {
         self
     }
 
+    /// Marks the matching column families as tombstone-heavy, see
+    /// `rocksdb-tombstone-compaction-interval`.
+    pub fn add_tombstone_heavy_cf(self, pattern: impl CfNameMatch + 'static + Send + Sync) -> Self {
+        self.add_tombstone_heavy_key_range(pattern, KeyRange::default())
+    }
+
+    /// Like [`Self::add_tombstone_heavy_cf`], but only the given key range of the matching column
+    /// families is compacted. Can be called multiple times for the same pattern.
+    pub fn add_tombstone_heavy_key_range(
+        mut self,
+        pattern: impl CfNameMatch + 'static + Send + Sync,
+        range: KeyRange,
+    ) -> Self {
+        let mut ranges = self.tombstone_heavy.unwrap_or_default();
+        ranges.push((Box::new(pattern), range));
+        self.tombstone_heavy = Some(ranges);
+        self
+    }
+
+    /// Limits the disk space the live SST and WAL files of the database may take up. The manager
+    /// checks the usage every `rocksdb-properties-poll-interval` and calls `on_overflow` on every
+    /// check while the budget is exceeded, e.g. to trim logs or to reject writes until usage
+    /// drops again. The callback runs on the manager's watchdog, it must not block.
+    pub fn with_disk_budget(
+        mut self,
+        limit: u64,
+        on_overflow: impl Fn(&DbName, &DiskUsage) + Send + Sync + 'static,
+    ) -> Self {
+        self.disk_budget = Some(Some(DiskBudget {
+            limit,
+            on_overflow: Box::new(on_overflow),
+        }));
+        self
+    }
+
+    /// Registers a patch for the options of the column families that match `pattern`. The patch
+    /// gets the manager's default column family options (already configured from the
+    /// `RocksDbOptions`) and returns the options to open the column family with.
     pub fn add_cf_pattern(
         mut self,
         pattern: impl CfNameMatch + Send + Sync + 'static,
-        options: impl Fn(rocksdb::Options) -> rocksdb::Options + Send + Sync + 'static,
+        options: impl Fn(&RocksDbOptions, rocksdb::Options) -> rocksdb::Options + Send + Sync + 'static,
     ) -> Self {
         let mut cfs = self.cf_patterns.unwrap_or_default();
         cfs.push((Box::new(pattern), Box::new(options)));
         self.cf_patterns = Some(cfs);
         self
     }
+
+    /// Same as [`Self::add_cf_pattern`], additionally installs a compaction filter on the
+    /// matching column families. The filter is invoked for every key-value pair that is visited
+    /// by a compaction, possibly concurrently.
+    pub fn add_cf_pattern_with_compaction_filter(
+        self,
+        pattern: impl CfNameMatch + Send + Sync + 'static,
+        options: impl Fn(&RocksDbOptions, rocksdb::Options) -> rocksdb::Options + Send + Sync + 'static,
+        filter_name: &'static str,
+        filter: impl Fn(u32, &[u8], &[u8]) -> Decision + Clone + Send + Sync + 'static,
+    ) -> Self {
+        self.add_cf_pattern(pattern, move |rocksdb_opts, opts| {
+            let mut opts = options(rocksdb_opts, opts);
+            opts.set_compaction_filter(filter_name, filter.clone());
+            opts
+        })
+    }
+
+    /// Same as [`Self::add_cf_pattern`], additionally drops entries of the matching column
+    /// families during compaction once the expiry time extracted by `expiry` has passed. Files
+    /// are compacted at least once every `max_file_age` so that expired entries in files that are
+    /// otherwise not compacted are dropped too.
+    pub fn add_cf_pattern_with_expiry(
+        self,
+        pattern: impl CfNameMatch + Send + Sync + 'static,
+        options: impl Fn(&RocksDbOptions, rocksdb::Options) -> rocksdb::Options + Send + Sync + 'static,
+        expiry: ExpiryExtractor,
+        max_file_age: Duration,
+    ) -> Self {
+        self.add_cf_pattern_with_compaction_filter(
+            pattern,
+            move |rocksdb_opts, opts| {
+                let mut opts = options(rocksdb_opts, opts);
+                opts.set_periodic_compaction_seconds(max_file_age.as_secs());
+                opts
+            },
+            "restate-expiry",
+            move |_level, key: &[u8], value: &[u8]| match expiry(key, value) {
+                Some(expires_at) if expires_at <= now_millis() => Decision::Remove,
+                _ => Decision::Keep,
+            },
+        )
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
 }
 
 impl DbSpecBuilder<rocksdb::DB> {
@@ -0,0 +1,85 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::Path;
+
+use tracing::debug;
+
+use crate::{DbName, RocksDb};
+
+/// WAL files are the only `.log` files rocksdb keeps in the database directory.
+const WAL_FILE_EXTENSION: &str = "log";
+
+/// Called by the manager while a database exceeds its disk budget, see
+/// [`crate::DbSpecBuilder::with_disk_budget`].
+pub type DiskBudgetOverflowCallback = Box<dyn Fn(&DbName, &DiskUsage) + Send + Sync>;
+
+pub struct DiskBudget {
+    pub(crate) limit: u64,
+    pub(crate) on_overflow: DiskBudgetOverflowCallback,
+}
+
+impl DiskBudget {
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+/// Disk space taken up by a database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// SST files referenced by the current version of the column families. Files that became
+    /// obsolete but are not deleted yet are not included.
+    pub live_sst_files: u64,
+    /// Live WAL files in the database directory.
+    pub wal_files: u64,
+}
+
+impl DiskUsage {
+    pub(crate) fn collect(db: &RocksDb) -> Self {
+        let live_sst_files = db
+            .cfs()
+            .into_iter()
+            .filter_map(|cf| {
+                db.inner()
+                    .get_property_int_cf(&cf, "rocksdb.live-sst-files-size")
+                    .ok()
+                    .flatten()
+            })
+            .sum();
+        let wal_files = wal_files_size(&db.path).unwrap_or_else(|e| {
+            debug!(db = %db.name, "Failed to determine the size of the WAL files: {}", e);
+            0
+        });
+        Self {
+            live_sst_files,
+            wal_files,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.live_sst_files + self.wal_files
+    }
+}
+
+fn wal_files_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        if entry
+            .path()
+            .extension()
+            .is_some_and(|ext| ext == WAL_FILE_EXTENSION)
+        {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
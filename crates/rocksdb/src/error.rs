@@ -8,10 +8,12 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::sync::Arc;
+
 use codederror::CodedError;
 use restate_core::ShutdownError;
 
-use crate::CfName;
+use crate::{BackupError, CfName, DbName, OpenMode, Priority};
 
 #[derive(Debug, Clone, thiserror::Error, CodedError)]
 pub enum RocksError {
@@ -21,12 +23,36 @@ pub enum RocksError {
     #[error(transparent)]
     #[code(unknown)]
     Shutdown(#[from] ShutdownError),
+    #[error("unknown database: {0}")]
+    #[code(unknown)]
+    UnknownDb(DbName),
+    #[error("db {0} is still in use")]
+    #[code(unknown)]
+    DbInUse(DbName),
     #[error("unknown column family: {0}")]
     #[code(unknown)]
     UnknownColumnFamily(CfName),
+    #[error("column family already exists: {0}")]
+    #[code(unknown)]
+    ColumnFamilyExists(CfName),
+    #[error(
+        "storage is busy, too many {} priority storage tasks are queued",
+        .0.as_static_str()
+    )]
+    #[code(unknown)]
+    Busy(Priority),
+    #[error("db {0} is quarantined after a background error: {1}")]
+    #[code(unknown)]
+    Quarantined(DbName, String),
     #[error("already open")]
     #[code(unknown)]
     AlreadyOpen,
+    #[error("open mode {0:?} is not supported for this database type")]
+    #[code(unknown)]
+    UnsupportedOpenMode(OpenMode),
+    #[error("failed to restore database from backup: {0}")]
+    #[code(unknown)]
+    Restore(Arc<BackupError>),
     #[error(transparent)]
     #[code(unknown)]
     Other(#[from] rocksdb::Error),
@@ -0,0 +1,228 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Group commit of WAL syncs.
+//!
+//! Writers announce their write, write their batch to the WAL without syncing it and then join
+//! the current group. The first writer of a group becomes its leader. While announced writes are
+//! still in progress, it waits for them to join (bounded by the configured max latency/bytes),
+//! then it syncs the WAL once for the whole group. A lone writer doesn't wait. A sync makes every
+//! write that completed before it started durable.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::sync::{watch, Notify};
+
+use crate::RocksError;
+
+/// Identifies a write that joined group commit, tickets are handed out in write order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Ticket(u64);
+
+pub(crate) struct GroupCommit {
+    /// Last ticket handed out.
+    last_ticket: AtomicU64,
+    /// Last ticket that is known to be durable.
+    synced: watch::Sender<u64>,
+    /// Set while a writer leads a group, there is at most one WAL sync in flight.
+    leading: AtomicBool,
+    /// Bytes written since the current leader took over.
+    pending_bytes: AtomicUsize,
+    /// Announced writes that didn't join yet.
+    writing: AtomicUsize,
+    /// Notified once no announced write is in progress anymore or the max bytes are reached. Only
+    /// wakes up the leader that is waiting, a notification without a leader is not kept for the
+    /// next one.
+    group_complete: Notify,
+}
+
+impl Default for GroupCommit {
+    fn default() -> Self {
+        Self {
+            last_ticket: AtomicU64::new(0),
+            synced: watch::channel(0).0,
+            leading: AtomicBool::new(false),
+            pending_bytes: AtomicUsize::new(0),
+            writing: AtomicUsize::new(0),
+            group_complete: Notify::new(),
+        }
+    }
+}
+
+impl GroupCommit {
+    /// Announces a write that is about to start, the leader of the group waits for it to join.
+    pub(crate) fn announce(&self) -> Announcement<'_> {
+        self.writing.fetch_add(1, Ordering::AcqRel);
+        Announcement(self)
+    }
+
+    /// Joins the current group, must be called after the announced write completed.
+    pub(crate) fn join(
+        &self,
+        announcement: Announcement<'_>,
+        bytes: usize,
+        max_bytes: usize,
+    ) -> Ticket {
+        let ticket = Ticket(self.last_ticket.fetch_add(1, Ordering::AcqRel) + 1);
+        if self.pending_bytes.fetch_add(bytes, Ordering::AcqRel) + bytes >= max_bytes {
+            self.group_complete.notify_waiters();
+        }
+        drop(announcement);
+        ticket
+    }
+
+    /// Waits until the write of the given ticket is durable. Either another writer syncs the WAL
+    /// on our behalf or we lead the next group and sync it ourselves using `sync_wal`.
+    ///
+    /// Cancellation safe, if the leader is dropped or fails to sync, one of the waiting writers
+    /// takes over.
+    pub(crate) async fn wait_durable<F, Fut>(
+        &self,
+        ticket: Ticket,
+        max_latency: Duration,
+        max_bytes: usize,
+        sync_wal: F,
+    ) -> Result<usize, RocksError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(), RocksError>>,
+    {
+        let mut synced = self.synced.subscribe();
+        loop {
+            if *synced.borrow_and_update() >= ticket.0 {
+                return Ok(0);
+            }
+            if self
+                .leading
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                let _guard = LeaderGuard(self);
+                // the previous leader might have synced our write while we were joining
+                if *self.synced.borrow() >= ticket.0 {
+                    return Ok(0);
+                }
+                // register before checking, so that a write joining after the check wakes us up
+                let group_complete = self.group_complete.notified();
+                tokio::pin!(group_complete);
+                group_complete.as_mut().enable();
+                if self.writing.load(Ordering::Acquire) > 0
+                    && self.pending_bytes.load(Ordering::Acquire) < max_bytes
+                {
+                    tokio::select! {
+                        _ = tokio::time::sleep(max_latency) => {},
+                        _ = group_complete => {},
+                    }
+                }
+                self.pending_bytes.store(0, Ordering::Release);
+                // Everything up to here was written before the sync starts.
+                let upto = self.last_ticket.load(Ordering::Acquire);
+                sync_wal().await?;
+
+                let mut group_size = 0;
+                self.synced.send_if_modified(|synced| {
+                    if upto > *synced {
+                        group_size = (upto - *synced) as usize;
+                        *synced = upto;
+                        true
+                    } else {
+                        false
+                    }
+                });
+                return Ok(group_size);
+            }
+            // Wait for the leader to finish (or give up), the sender lives as long as self.
+            let _ = synced.changed().await;
+        }
+    }
+}
+
+/// An announced write, it stops counting as in progress once it joins the group or is dropped
+/// because the write failed.
+pub(crate) struct Announcement<'a>(&'a GroupCommit);
+
+impl Drop for Announcement<'_> {
+    fn drop(&mut self) {
+        if self.0.writing.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.group_complete.notify_waiters();
+        }
+    }
+}
+
+/// Steps down from leading the group, wakes up the waiting writers so that one of them can take
+/// over if the sync didn't cover their writes.
+struct LeaderGuard<'a>(&'a GroupCommit);
+
+impl Drop for LeaderGuard<'_> {
+    fn drop(&mut self) {
+        self.0.leading.store(false, Ordering::Release);
+        self.0.synced.send_modify(|_| {});
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    const MAX_LATENCY: Duration = Duration::from_millis(100);
+    const MAX_BYTES: usize = 1024;
+
+    async fn sync(group_commit: &GroupCommit, ticket: Ticket) -> usize {
+        group_commit
+            .wait_durable(ticket, MAX_LATENCY, MAX_BYTES, || async { Ok(()) })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn lone_writer_does_not_wait() {
+        let group_commit = GroupCommit::default();
+        let ticket = group_commit.join(group_commit.announce(), 1, MAX_BYTES);
+
+        let start = Instant::now();
+        assert_eq!(sync(&group_commit, ticket).await, 1);
+        assert!(start.elapsed() < MAX_LATENCY);
+    }
+
+    #[tokio::test]
+    async fn completed_group_does_not_cut_the_next_one_short() {
+        let group_commit = GroupCommit::default();
+        // reaches the max bytes while nobody leads the group
+        let ticket = group_commit.join(group_commit.announce(), MAX_BYTES, MAX_BYTES);
+        assert_eq!(sync(&group_commit, ticket).await, 1);
+
+        // the next leader waits for the write that is still in progress
+        let in_progress = group_commit.announce();
+        let ticket = group_commit.join(group_commit.announce(), 1, MAX_BYTES);
+        let start = Instant::now();
+        assert_eq!(sync(&group_commit, ticket).await, 1);
+        assert!(start.elapsed() >= MAX_LATENCY);
+        drop(in_progress);
+    }
+
+    #[tokio::test]
+    async fn leader_is_woken_up_once_the_group_is_complete() {
+        let group_commit = GroupCommit::default();
+        let in_progress = group_commit.announce();
+        let ticket = group_commit.join(group_commit.announce(), 1, MAX_BYTES);
+
+        let start = Instant::now();
+        let (group_size, _) = tokio::join!(sync(&group_commit, ticket), async {
+            tokio::task::yield_now().await;
+            group_commit.join(in_progress, 1, MAX_BYTES)
+        });
+        assert_eq!(group_size, 2);
+        assert!(start.elapsed() < MAX_LATENCY);
+    }
+}
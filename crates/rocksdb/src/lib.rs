@@ -8,14 +8,27 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod admission;
 mod background;
+mod background_activity;
+mod backup;
+mod batch_split;
 mod db_manager;
 mod db_spec;
+mod disk_usage;
 mod error;
+mod group_commit;
+mod memory;
 mod metric_definitions;
 mod perf;
+mod pool;
 mod rock_access;
+mod snapshot;
+mod statistics;
+mod trace;
 
+use bytes::Bytes;
+use futures::{Stream, TryStreamExt};
 use metrics::counter;
 use metrics::gauge;
 use metrics::histogram;
@@ -27,7 +40,8 @@ use tracing::info;
 use tracing::warn;
 
 use std::fmt;
-use std::path::PathBuf;
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -36,22 +50,50 @@ use rocksdb::statistics::HistogramData;
 use rocksdb::statistics::Ticker;
 
 use self::background::ReadyStorageTask;
+use self::group_commit::GroupCommit;
+use self::trace::WorkloadTracer;
 // re-exports
+pub use self::backup::{
+    build_object_store, download_file, upload_file, BackupError, BackupFile, BackupId,
+    BackupManifest, BackupService,
+};
 pub use self::db_manager::RocksDbManager;
 pub use self::db_spec::*;
+pub use self::disk_usage::{DiskBudget, DiskBudgetOverflowCallback, DiskUsage};
 pub use self::error::*;
-pub use self::rock_access::RocksAccess;
+pub use self::memory::{CfMemoryUsage, DbMemoryUsage, MemoryUsageStats};
+pub use self::rock_access::{CorruptFile, RocksAccess};
+pub use self::snapshot::ConsistentReadTxn;
+pub use self::statistics::{NODE_RENDERED_HISTOGRAMS, NODE_RENDERED_TICKERS};
+pub use self::trace::{
+    replay_trace, ReplayOptions, ReplaySummary, TraceError, TraceOp, TraceRecord,
+};
 
 use self::background::StorageTask;
 use self::background::StorageTaskKind;
 use self::metric_definitions::*;
 
+/// Number of entries read by a single storage task of [`RocksDb::iter_stream`].
+pub const ITER_STREAM_CHUNK_SIZE: usize = 1024;
+
 type BoxedCfMatcher = Box<dyn CfNameMatch + Send + Sync>;
-type BoxedCfOptionUpdater = Box<dyn Fn(rocksdb::Options) -> rocksdb::Options + Send + Sync>;
+type BoxedCfOptionUpdater =
+    Box<dyn Fn(&RocksDbOptions, rocksdb::Options) -> rocksdb::Options + Send + Sync>;
 
 /// Denotes whether an operation is considered latency sensitive or not
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, strum_macros::IntoStaticStr)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum_macros::IntoStaticStr,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 #[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub enum Priority {
     High,
     #[default]
@@ -77,6 +119,16 @@ pub enum IoMode {
     Default,
 }
 
+/// A corruption found by [`RocksDb::verify_checksums`].
+#[derive(Debug, Clone)]
+pub struct Corruption {
+    pub db: DbName,
+    pub cf: CfName,
+    /// File name relative to the database directory
+    pub file: String,
+    pub message: String,
+}
+
 #[derive(derive_more::Display, Clone)]
 #[display(fmt = "{}", name)]
 pub struct RocksDb {
@@ -84,9 +136,14 @@ pub struct RocksDb {
     pub name: DbName,
     pub path: PathBuf,
     pub db_options: rocksdb::Options,
+    pub open_mode: OpenMode,
     cf_patterns: Arc<[(BoxedCfMatcher, BoxedCfOptionUpdater)]>,
     flush_on_shutdown: Arc<[BoxedCfMatcher]>,
+    tombstone_heavy: Arc<[(BoxedCfMatcher, KeyRange)]>,
+    disk_budget: Option<Arc<DiskBudget>>,
     db: Arc<dyn RocksAccess + Send + Sync + 'static>,
+    group_commit: Arc<GroupCommit>,
+    tracer: Arc<parking_lot::RwLock<Option<Arc<WorkloadTracer>>>>,
 }
 
 static_assertions::assert_impl_all!(RocksDb: Send, Sync);
@@ -109,7 +166,12 @@ impl RocksDb {
             cf_patterns: spec.cf_patterns.into(),
             db,
             db_options: spec.db_options,
+            open_mode: spec.open_mode,
             flush_on_shutdown: spec.flush_on_shutdown.into(),
+            tombstone_heavy: spec.tombstone_heavy.into(),
+            disk_budget: spec.disk_budget.map(Arc::new),
+            group_commit: Arc::default(),
+            tracer: Arc::default(),
         }
     }
 
@@ -125,6 +187,78 @@ impl RocksDb {
         self.db.cfs()
     }
 
+    /// Returns the last background error reported by rocksdb for this database, if any.
+    pub fn background_error(&self) -> Option<String> {
+        self.manager.background_error(&self.name)
+    }
+
+    /// A database is healthy as long as rocksdb has not reported a background error.
+    pub fn is_healthy(&self) -> bool {
+        self.background_error().is_none()
+    }
+
+    /// Fails with [`RocksError::Quarantined`] if the database is quarantined after a background
+    /// error.
+    fn ensure_writable(&self) -> Result<(), RocksError> {
+        match self.background_error() {
+            Some(error) => Err(RocksError::Quarantined(self.name.clone(), error)),
+            None => Ok(()),
+        }
+    }
+
+    /// Attempts to lift the quarantine of the database. Succeeds if rocksdb accepts writes again,
+    /// either because it recovered from the error on its own or because the error was transient.
+    #[tracing::instrument(skip_all, fields(db = %self.name))]
+    pub async fn try_resume(&self) -> Result<(), RocksError> {
+        if self.is_healthy() {
+            return Ok(());
+        }
+        let db = self.db.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .kind(StorageTaskKind::Resume)
+            .op(move || db.probe_writable())
+            .build()
+            .unwrap();
+
+        self.manager.async_spawn(task).await??;
+        self.manager.clear_background_error(&self.name);
+        info!(db = %self.name, "Rocksdb database resumed after a background error");
+        Ok(())
+    }
+
+    /// Starts recording the workload of this database to a new trace file at `path`, see
+    /// [`replay_trace`]. Tracing adds the cost of serializing every operation, it's meant to be
+    /// enabled for a limited time only.
+    pub fn start_trace(&self, path: &Path) -> Result<(), TraceError> {
+        let mut tracer = self.tracer.write();
+        if tracer.is_some() {
+            return Err(TraceError::AlreadyTracing(self.name.clone()));
+        }
+        *tracer = Some(Arc::new(WorkloadTracer::create(path)?));
+        info!(db = %self.name, path = %path.display(), "Started tracing rocksdb workload");
+        Ok(())
+    }
+
+    /// Stops tracing and flushes the trace file. Returns the path of the trace, if tracing was
+    /// active.
+    pub fn end_trace(&self) -> Result<Option<PathBuf>, TraceError> {
+        let Some(tracer) = self.tracer.write().take() else {
+            return Ok(None);
+        };
+        tracer.finish()?;
+        info!(db = %self.name, path = %tracer.path().display(), "Stopped tracing rocksdb workload");
+        Ok(Some(tracer.path().to_path_buf()))
+    }
+
+    fn trace(&self, op: impl FnOnce() -> TraceOp) {
+        if let Some(tracer) = self.tracer.read().as_ref() {
+            tracer.record(op());
+        }
+    }
+
+    /// Writes the batch atomically, it is never split. See [`Self::write_batch_allow_split`] for
+    /// writes that don't need to be applied atomically.
     #[tracing::instrument(skip_all, fields(db = %self.name))]
     pub async fn write_batch(
         &self,
@@ -133,6 +267,11 @@ impl RocksDb {
         mut write_options: rocksdb::WriteOptions,
         write_batch: rocksdb::WriteBatch,
     ) -> Result<(), RocksError> {
+        self.ensure_writable()?;
+        self.trace(|| TraceOp::WriteBatch {
+            priority,
+            data: write_batch.data().to_vec(),
+        });
         //  depending on the IoMode, we decide how to do the write.
         match io_mode {
             IoMode::AllowBlockingIO => {
@@ -187,6 +326,7 @@ impl RocksDb {
                 // In the background thread pool we can block on IO
                 write_options.set_no_slowdown(false);
                 let task = StorageTask::default()
+                    .db(self.name.clone())
                     .priority(priority)
                     .kind(StorageTaskKind::WriteBatch)
                     .op(move || db.write_batch(&write_batch, &write_options))
@@ -207,6 +347,143 @@ impl RocksDb {
         }
     }
 
+    /// Like [`Self::write_batch`], but splits the batch if it is larger than
+    /// `rocksdb-max-write-batch-size`. Every split is applied atomically, but a crash can leave
+    /// only the first splits applied. Only use it for batches whose records don't need to be
+    /// applied together.
+    #[tracing::instrument(skip_all, fields(db = %self.name))]
+    pub async fn write_batch_allow_split(
+        &self,
+        priority: Priority,
+        io_mode: IoMode,
+        write_options: rocksdb::WriteOptions,
+        write_batch: rocksdb::WriteBatch,
+    ) -> Result<(), RocksError> {
+        if let Some(max_bytes) = self.manager.max_write_batch_size() {
+            if write_batch.size_in_bytes() > max_bytes {
+                if let Some(splits) = batch_split::split_write_batch(write_batch.data(), max_bytes)
+                {
+                    self.ensure_writable()?;
+                    self.trace(|| TraceOp::WriteBatch {
+                        priority,
+                        data: write_batch.data().to_vec(),
+                    });
+                    return self
+                        .write_split_batch(priority, io_mode, write_options, splits)
+                        .await;
+                }
+            }
+        }
+        self.write_batch(priority, io_mode, write_options, write_batch)
+            .await
+    }
+
+    /// Writes the splits of an oversized batch one after the other. Every split is applied
+    /// atomically, but if a write fails (or the process crashes) only the splits before it are
+    /// applied.
+    async fn write_split_batch(
+        &self,
+        priority: Priority,
+        io_mode: IoMode,
+        mut write_options: rocksdb::WriteOptions,
+        splits: Vec<Vec<u8>>,
+    ) -> Result<(), RocksError> {
+        debug!(
+            "Splitting oversized write batch into {} batches",
+            splits.len()
+        );
+        counter!(ROCKSDB_SPLIT_WRITE_BATCHES, DB_NAME => self.name.to_string()).increment(1);
+        let splits: Vec<_> = splits
+            .iter()
+            .map(|data| rocksdb::WriteBatch::from_data(data))
+            .collect();
+
+        let disposition = match io_mode {
+            IoMode::AllowBlockingIO => {
+                write_options.set_no_slowdown(false);
+                DISPOSITION_MAYBE_BLOCKING
+            }
+            IoMode::OnlyIfNonBlocking => {
+                write_options.set_no_slowdown(true);
+                DISPOSITION_NON_BLOCKING
+            }
+            // Writing a large batch is likely to block, write it in the background right away
+            IoMode::Default => {
+                counter!(STORAGE_IO_OP,
+                    DISPOSITION => DISPOSITION_MOVED_TO_BG,
+                    OP_TYPE => StorageTaskKind::WriteBatch.as_static_str(),
+                    PRIORITY => priority.as_static_str(),
+                )
+                .increment(1);
+                let db = self.db.clone();
+                write_options.set_no_slowdown(false);
+                let task = StorageTask::default()
+                    .db(self.name.clone())
+                    .priority(priority)
+                    .kind(StorageTaskKind::WriteBatch)
+                    .op(move || {
+                        splits
+                            .iter()
+                            .try_for_each(|split| db.write_batch(split, &write_options))
+                    })
+                    .build()
+                    .unwrap();
+
+                return Ok(race_against_stall_detector(self.manager, task).await??);
+            }
+        };
+
+        for split in &splits {
+            self.db.write_batch(split, &write_options)?;
+        }
+        counter!(STORAGE_IO_OP,
+            DISPOSITION => disposition,
+            OP_TYPE => StorageTaskKind::WriteBatch.as_static_str(),
+            PRIORITY => priority.as_static_str(),
+        )
+        .increment(1);
+        Ok(())
+    }
+
+    /// Writes the batch to the WAL and returns once it is synced to disk. Concurrent calls for the
+    /// same database share a single WAL sync. While other calls are still writing their batch,
+    /// the sync is delayed by at most `rocksdb-group-commit-max-latency` (or until
+    /// `rocksdb-group-commit-max-bytes` are waiting) to let them join the group.
+    #[tracing::instrument(skip_all, fields(db = %self.name))]
+    pub async fn write_batch_group_commit(
+        &self,
+        priority: Priority,
+        io_mode: IoMode,
+        write_batch: rocksdb::WriteBatch,
+    ) -> Result<(), RocksError> {
+        let bytes = write_batch.size_in_bytes();
+        let mut write_options = rocksdb::WriteOptions::default();
+        write_options.disable_wal(false);
+        // the group syncs the WAL
+        write_options.set_sync(false);
+        let announcement = self.group_commit.announce();
+        self.write_batch(priority, io_mode, write_options, write_batch)
+            .await?;
+
+        let max_bytes = self.manager.group_commit_max_bytes();
+        let ticket = self.group_commit.join(announcement, bytes, max_bytes);
+        let group_size = self
+            .group_commit
+            .wait_durable(
+                ticket,
+                self.manager.group_commit_max_latency(),
+                max_bytes,
+                || self.flush_wal(true),
+            )
+            .await?;
+        if group_size > 0 {
+            histogram!(ROCKSDB_GROUP_COMMIT_SIZE, DB_NAME => self.name.to_string())
+                .record(group_size as f64);
+        }
+        Ok(())
+    }
+
+    /// Writes the transaction batch atomically, transaction batches are never split.
     // unfortunate side effect of trait objects not supporting generics
     #[tracing::instrument(skip_all, fields(db = %self.name))]
     pub async fn write_tx_batch(
@@ -216,6 +493,11 @@ impl RocksDb {
         mut write_options: rocksdb::WriteOptions,
         write_batch: rocksdb::WriteBatchWithTransaction<true>,
     ) -> Result<(), RocksError> {
+        self.ensure_writable()?;
+        self.trace(|| TraceOp::WriteTxBatch {
+            priority,
+            data: write_batch.data().to_vec(),
+        });
         //  depending on the IoMode, we decide how to do the write.
         match io_mode {
             IoMode::AllowBlockingIO => {
@@ -270,6 +552,7 @@ impl RocksDb {
                 // In the background thread pool we can block on IO
                 write_options.set_no_slowdown(false);
                 let task = StorageTask::default()
+                    .db(self.name.clone())
                     .priority(priority)
                     .kind(StorageTaskKind::WriteBatch)
                     .op(move || db.write_tx_batch(&write_batch, &write_options))
@@ -294,6 +577,7 @@ impl RocksDb {
     pub async fn flush_wal(&self, sync: bool) -> Result<(), RocksError> {
         let db = self.db.clone();
         let task = StorageTask::default()
+            .db(self.name.clone())
             .kind(StorageTaskKind::FlushWal)
             .op(move || db.flush_wal(sync))
             .build()
@@ -306,6 +590,7 @@ impl RocksDb {
     pub fn run_bg_wal_sync(&self) {
         let db = self.db.clone();
         let task = StorageTask::default()
+            .db(self.name.clone())
             .kind(StorageTaskKind::FlushWal)
             .op(move || {
                 if let Err(e) = db.flush_wal(true) {
@@ -318,6 +603,337 @@ impl RocksDb {
         self.manager.spawn_unchecked(task);
     }
 
+    /// Flushes the memtables of the given column families in the background without waiting
+    /// for the flush to complete.
+    #[tracing::instrument(skip_all, fields(db = %self.name))]
+    pub fn run_bg_flush_memtables(&self, cfs: Vec<CfName>) {
+        let db = self.db.clone();
+        let name = self.name.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .priority(Priority::Low)
+            .kind(StorageTaskKind::FlushMemtables)
+            .op(move || {
+                if let Err(e) = db.flush_memtables(&cfs, true) {
+                    warn!(db = %name, "Failed to flush rocksdb memtables: {}", e);
+                }
+            })
+            .build()
+            .unwrap();
+        // ignore shutdown errors, memtables are flushed on shutdown anyway.
+        let _ = self.manager.spawn(task);
+    }
+
+    /// Disk budget of this database, if one was configured through
+    /// [`DbSpecBuilder::with_disk_budget`].
+    pub fn disk_budget(&self) -> Option<&DiskBudget> {
+        self.disk_budget.as_deref()
+    }
+
+    /// Disk space currently taken up by the live SST and WAL files of the database.
+    pub fn disk_usage(&self) -> DiskUsage {
+        DiskUsage::collect(self)
+    }
+
+    /// The tombstone-heavy key ranges of this database, by column family.
+    pub(crate) fn tombstone_heavy_ranges(&self) -> Vec<(CfName, Vec<KeyRange>)> {
+        self.cfs()
+            .into_iter()
+            .filter_map(|cf| {
+                let ranges: Vec<_> = self
+                    .tombstone_heavy
+                    .iter()
+                    .filter(|(m, _)| m.cf_matches(&cf))
+                    .map(|(_, range)| range.clone())
+                    .collect();
+                (!ranges.is_empty()).then_some((cf, ranges))
+            })
+            .collect()
+    }
+
+    /// Compacts the given key ranges one after the other in the background without waiting for
+    /// the compactions to complete.
+    #[tracing::instrument(skip_all, fields(db = %self.name))]
+    pub fn run_bg_compaction(&self, ranges: Vec<(CfName, KeyRange)>) {
+        let db = self.db.clone();
+        let name = self.name.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .priority(Priority::Low)
+            .kind(StorageTaskKind::Compaction)
+            .op(move || {
+                for (cf, range) in ranges {
+                    if let Err(e) =
+                        db.compact_range(&cf, range.from.as_deref(), range.to.as_deref())
+                    {
+                        warn!(db = %name, "Failed to compact column family {}: {}", cf, e);
+                    }
+                }
+            })
+            .build()
+            .unwrap();
+        // ignore shutdown errors, the compaction is retried on the next schedule.
+        let _ = self.manager.spawn(task);
+    }
+
+    /// Compacts the key range `[from, to]` of the given column family on the low-priority
+    /// storage pool, both bounds are inclusive. `None` for either bound means the start/end of
+    /// the column family.
+    #[tracing::instrument(skip_all, fields(db = %self.name, cf = %cf))]
+    pub async fn compact_range(
+        &self,
+        cf: CfName,
+        from: Option<Vec<u8>>,
+        to: Option<Vec<u8>>,
+    ) -> Result<(), RocksError> {
+        let db = self.db.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .priority(Priority::Low)
+            .kind(StorageTaskKind::Compaction)
+            .op(move || db.compact_range(&cf, from.as_deref(), to.as_deref()))
+            .build()
+            .unwrap();
+
+        self.manager.async_spawn(task).await?
+    }
+
+    /// Deletes all keys in `[from, to)` of the given column family with a single range
+    /// tombstone instead of deleting keys one by one.
+    #[tracing::instrument(skip_all, fields(db = %self.name, cf = %cf))]
+    pub async fn delete_range(
+        &self,
+        priority: Priority,
+        cf: CfName,
+        from: Vec<u8>,
+        to: Vec<u8>,
+        write_options: rocksdb::WriteOptions,
+    ) -> Result<(), RocksError> {
+        self.ensure_writable()?;
+        self.trace(|| TraceOp::DeleteRange {
+            priority,
+            cf: cf.to_string(),
+            from: from.clone(),
+            to: to.clone(),
+        });
+        let db = self.db.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .priority(priority)
+            .kind(StorageTaskKind::DeleteRange)
+            .op(move || db.delete_range_cf(&cf, &from, &to, &write_options))
+            .build()
+            .unwrap();
+
+        race_against_stall_detector(self.manager, task).await?
+    }
+
+    /// Looks up a batch of keys of the column family in a single storage task using rocksdb's
+    /// MultiGet, which is considerably cheaper than as many point lookups. The values are
+    /// returned in the order of the keys.
+    #[tracing::instrument(skip_all, fields(db = %self.name, cf = %cf))]
+    pub async fn multi_get_cf(
+        &self,
+        cf: CfName,
+        keys: Vec<Vec<u8>>,
+        priority: Priority,
+    ) -> Result<Vec<Option<Bytes>>, RocksError> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.trace(|| TraceOp::MultiGet {
+            priority,
+            cf: cf.to_string(),
+            keys: keys.clone(),
+        });
+        let db = self.db.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .priority(priority)
+            .kind(StorageTaskKind::MultiGet)
+            .op(move || db.multi_get_cf(&cf, &keys))
+            .build()
+            .unwrap();
+
+        self.manager.async_spawn(task).await?
+    }
+
+    /// Streams the key/value pairs of the column family within `range`. Reads happen on the
+    /// storage pools in chunks of [`ITER_STREAM_CHUNK_SIZE`] entries and the next chunk is
+    /// prefetched while the current one is consumed.
+    ///
+    /// Note that chunks are read independently, the stream doesn't observe a consistent
+    /// snapshot of the column family if it's modified concurrently.
+    pub fn iter_stream(
+        &self,
+        cf: CfName,
+        range: impl RangeBounds<Vec<u8>>,
+        priority: Priority,
+    ) -> impl Stream<Item = Result<(Bytes, Bytes), RocksError>> + Send + 'static {
+        let (from, to) = key_range(range);
+        let from = from.unwrap_or_default();
+        self.trace(|| TraceOp::Scan {
+            priority,
+            cf: cf.to_string(),
+            from: from.clone(),
+            to: to.clone(),
+        });
+
+        let db = self.db.clone();
+        let name = self.name.clone();
+        let manager = self.manager;
+        let fetch = move |from: Vec<u8>| {
+            let db = db.clone();
+            let cf = cf.clone();
+            let to = to.clone();
+            let task = StorageTask::default()
+                .db(name.clone())
+                .priority(priority)
+                .kind(StorageTaskKind::Scan)
+                .op(move || {
+                    let entries = db.scan_cf(&cf, &from, to.as_deref(), ITER_STREAM_CHUNK_SIZE)?;
+                    // a short chunk means we've reached the end of the range
+                    let resume_from = if entries.len() == ITER_STREAM_CHUNK_SIZE {
+                        entries.last().map(|(key, _)| successor(key))
+                    } else {
+                        None
+                    };
+                    Ok::<_, RocksError>((entries, resume_from))
+                })
+                .build()
+                .unwrap();
+            manager.spawn_with_result(task)
+        };
+
+        let first = fetch(from);
+        futures::stream::unfold(Some(first), move |pending| {
+            let fetch = fetch.clone();
+            async move {
+                let chunk = match pending? {
+                    Ok(rx) => rx
+                        .await
+                        .unwrap_or_else(|_| Err(RocksError::Shutdown(ShutdownError))),
+                    Err(e) => Err(e),
+                };
+                match chunk {
+                    Ok((entries, resume_from)) => {
+                        // prefetch the next chunk while this one is consumed
+                        let next = resume_from.map(fetch);
+                        Some((Ok(entries), next))
+                    }
+                    Err(e) => Some((Err(e), None)),
+                }
+            }
+        })
+        .map_ok(|entries| futures::stream::iter(entries.into_iter().map(Ok)))
+        .try_flatten()
+    }
+
+    /// Reads all blocks of the live SST files of every column family within `range` and
+    /// validates their checksums on the low-priority storage pool, one column family at a time.
+    /// Blocks are not added to the block cache.
+    ///
+    /// Returns every corrupted file found, see [`RocksAccess::verify_checksums_cf`].
+    #[tracing::instrument(skip_all, fields(db = %self.name))]
+    pub async fn verify_checksums(
+        &self,
+        range: impl RangeBounds<Vec<u8>>,
+    ) -> Result<Vec<Corruption>, RocksError> {
+        let (from, to) = key_range(range);
+        let mut corruptions = Vec::new();
+        for cf in self.cfs() {
+            let db = self.db.clone();
+            let task = StorageTask::default()
+                .db(self.name.clone())
+                .priority(Priority::Low)
+                .kind(StorageTaskKind::VerifyChecksums)
+                .op({
+                    let cf = cf.clone();
+                    let from = from.clone();
+                    let to = to.clone();
+                    move || db.verify_checksums_cf(&cf, from.as_deref(), to.as_deref())
+                })
+                .build()
+                .unwrap();
+
+            for CorruptFile { file, message } in self.manager.async_spawn(task).await?? {
+                error!(
+                    db = %self.name,
+                    %cf,
+                    file,
+                    "Rocksdb checksum verification found a corrupted file: {}",
+                    message
+                );
+                counter!(ROCKSDB_CORRUPTIONS_DETECTED,
+                    DB_NAME => self.name.to_string(),
+                    CF_NAME => cf.to_string(),
+                )
+                .increment(1);
+                corruptions.push(Corruption {
+                    db: self.name.clone(),
+                    cf: cf.clone(),
+                    file,
+                    message,
+                });
+            }
+        }
+        Ok(corruptions)
+    }
+
+    /// Acquires a snapshot of the database to perform multiple reads, possibly across column
+    /// families, that observe the same point in time. See [`ConsistentReadTxn`].
+    pub fn consistent_read_txn(&self) -> ConsistentReadTxn {
+        self.db.clone().consistent_read_txn()
+    }
+
+    /// Bulk loads pre-built SST files into the given column family on the low-priority storage
+    /// pool. The files are moved into the database, see [`RocksAccess::ingest_external_files`].
+    #[tracing::instrument(skip_all, fields(db = %self.name, cf = %cf))]
+    pub async fn ingest_external_files(
+        &self,
+        cf: CfName,
+        files: Vec<PathBuf>,
+    ) -> Result<(), RocksError> {
+        self.ensure_writable()?;
+        let db = self.db.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .priority(Priority::Low)
+            .kind(StorageTaskKind::IngestExternalFiles)
+            .op(move || db.ingest_external_files(&cf, &files))
+            .build()
+            .unwrap();
+
+        self.manager.async_spawn(task).await?
+    }
+
+    /// Compacts the full key range of every column family of this database, one column family
+    /// at a time.
+    #[tracing::instrument(skip_all, fields(db = %self.name))]
+    pub async fn compact_all(&self) -> Result<(), RocksError> {
+        for cf in self.cfs() {
+            debug!(db = %self.name, "Manually compacting column family {}", cf);
+            self.compact_range(cf, None, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates a consistent checkpoint of this database in `path` using hard-links to the live
+    /// SST files whenever possible. The directory must not exist.
+    #[tracing::instrument(skip_all, fields(db = %self.name, path = %path.display()))]
+    pub async fn create_checkpoint(&self, path: PathBuf) -> Result<(), RocksError> {
+        let db = self.db.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .priority(Priority::Low)
+            .kind(StorageTaskKind::Checkpoint)
+            .op(move || db.create_checkpoint(&path))
+            .build()
+            .unwrap();
+
+        self.manager.async_spawn(task).await?
+    }
+
     pub fn get_histogram_data(&self, histogram: Histogram) -> HistogramData {
         self.db_options.get_histogram_data(histogram)
     }
@@ -330,14 +946,54 @@ impl RocksDb {
         self.db_options.get_statistics()
     }
 
+    /// Creates a new column family at runtime. The column family options are derived from `opts`
+    /// and the matching pattern in the database's `DbSpec`. Fails with
+    /// [`RocksError::ColumnFamilyExists`] if the column family exists already.
+    #[tracing::instrument(skip_all, fields(db = %self.name, cf = %name))]
+    pub async fn create_cf(&self, name: CfName, opts: &RocksDbOptions) -> Result<(), RocksError> {
+        if self.db.cf_handle(&name).is_some() {
+            return Err(RocksError::ColumnFamilyExists(name));
+        }
+        self.open_cf(name, opts).await
+    }
+
+    /// Drops the column family and all of its data.
+    #[tracing::instrument(skip_all, fields(db = %self.name, cf = %name))]
+    pub async fn drop_cf(&self, name: CfName) -> Result<(), RocksError> {
+        let db = self.db.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .kind(StorageTaskKind::DropColumnFamily)
+            .op(move || db.drop_cf(&name))
+            .build()
+            .unwrap();
+
+        self.manager.async_spawn(task).await?
+    }
+
     #[tracing::instrument(skip_all, fields(db = %self.name))]
     pub async fn open_cf(&self, name: CfName, opts: &RocksDbOptions) -> Result<(), RocksError> {
         let default_cf_options = self.manager.default_cf_options(opts);
         let db = self.db.clone();
         let cf_patterns = self.cf_patterns.clone();
+        let opts = opts.clone();
         let task = StorageTask::default()
+            .db(self.name.clone())
             .kind(StorageTaskKind::OpenColumnFamily)
-            .op(move || db.open_cf(name, default_cf_options, cf_patterns))
+            .op(move || db.open_cf(name, default_cf_options, &opts, cf_patterns))
+            .build()
+            .unwrap();
+
+        self.manager.async_spawn(task).await?
+    }
+
+    /// Catches up with the primary instance, see [`OpenMode::Secondary`].
+    pub async fn try_catch_up_with_primary(&self) -> Result<(), RocksError> {
+        let db = self.db.clone();
+        let task = StorageTask::default()
+            .db(self.name.clone())
+            .kind(StorageTaskKind::CatchUpWithPrimary)
+            .op(move || db.try_catch_up_with_primary())
             .build()
             .unwrap();
 
@@ -348,6 +1004,11 @@ impl RocksDb {
     pub async fn shutdown(self: Arc<Self>) {
         let manager = self.manager;
         let op = move || {
+            if !self.open_mode.is_primary() {
+                // nothing to persist, the primary owns the WAL and memtables.
+                self.db.cancel_all_background_work(true);
+                return;
+            }
             if let Err(e) = self.db.flush_wal(true) {
                 warn!(
                     db = %self.name,
@@ -395,6 +1056,29 @@ impl RocksDb {
     }
 }
 
+/// Converts the range into an inclusive start and an exclusive end key, `None` if unbounded.
+fn key_range(range: impl RangeBounds<Vec<u8>>) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
+    let from = match range.start_bound() {
+        Bound::Included(from) => Some(from.clone()),
+        Bound::Excluded(from) => Some(successor(from)),
+        Bound::Unbounded => None,
+    };
+    let to = match range.end_bound() {
+        Bound::Included(to) => Some(successor(to)),
+        Bound::Excluded(to) => Some(to.clone()),
+        Bound::Unbounded => None,
+    };
+    (from, to)
+}
+
+/// The smallest key that sorts after `key`.
+fn successor(key: &[u8]) -> Vec<u8> {
+    let mut next = Vec::with_capacity(key.len() + 1);
+    next.extend_from_slice(key);
+    next.push(0);
+    next
+}
+
 fn is_retryable_error(error_kind: rocksdb::ErrorKind) -> bool {
     matches!(
         error_kind,
@@ -405,7 +1089,7 @@ fn is_retryable_error(error_kind: rocksdb::ErrorKind) -> bool {
 async fn race_against_stall_detector<OP, R>(
     manager: &RocksDbManager,
     task: ReadyStorageTask<OP>,
-) -> Result<R, ShutdownError>
+) -> Result<R, RocksError>
 where
     OP: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
@@ -435,3 +1119,82 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use restate_core::TaskCenterBuilder;
+    use restate_types::arc_util::Constant;
+    use restate_types::config::CommonOptions;
+
+    use super::*;
+
+    async fn put(db: &RocksDb, value: &[u8]) -> Result<(), RocksError> {
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put_cf(&db.inner().cf_handle("data").unwrap(), b"key", value);
+        db.write_batch(
+            Priority::High,
+            IoMode::Default,
+            rocksdb::WriteOptions::default(),
+            batch,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn quarantined_database_rejects_writes_until_resumed() {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        let manager = tc.run_in_scope_sync("db-manager-init", None, || {
+            RocksDbManager::init(Constant::new(CommonOptions::default()))
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let spec = DbSpecBuilder::new(
+            DbName::new("db"),
+            dir.path().to_path_buf(),
+            rocksdb::Options::default(),
+        )
+        .add_cf_pattern(CfPrefixPattern::ANY, |_, opts| opts)
+        .ensure_column_families(vec![CfName::new("data")])
+        .build_as_db();
+        manager
+            .open_db(Constant::new(RocksDbOptions::default()), spec)
+            .unwrap();
+        let db = manager.get_db(DbName::new("db")).unwrap();
+        put(&db, b"1").await.unwrap();
+
+        manager.record_background_error(&db.name, "injected".to_owned());
+        assert!(!db.is_healthy());
+        assert_eq!(manager.unhealthy_dbs(), vec![db.name.clone()]);
+        assert!(matches!(
+            put(&db, b"2").await,
+            Err(RocksError::Quarantined(name, error)) if name == db.name && error == "injected"
+        ));
+        assert!(matches!(
+            db.delete_range(
+                Priority::High,
+                CfName::new("data"),
+                b"a".to_vec(),
+                b"z".to_vec(),
+                rocksdb::WriteOptions::default(),
+            )
+            .await,
+            Err(RocksError::Quarantined(..))
+        ));
+        // reads are still served
+        let raw_db = db.inner().as_raw_db();
+        assert_eq!(
+            raw_db
+                .get_cf(&raw_db.cf_handle("data").unwrap(), b"key")
+                .unwrap(),
+            Some(b"1".to_vec())
+        );
+
+        // rocksdb accepts writes, the error was transient
+        db.try_resume().await.unwrap();
+        assert!(db.is_healthy());
+        assert!(manager.unhealthy_dbs().is_empty());
+        put(&db, b"2").await.unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::{CfName, DbName, RocksDb};
+
+/// Memory used by rocksdb, see [`crate::RocksDbManager::get_memory_usage_stats`].
+#[derive(Debug, Clone, Default)]
+pub struct MemoryUsageStats {
+    /// Bytes held by the block cache that is shared by all databases. Since index and filter
+    /// blocks are cached too, this includes them.
+    pub block_cache_usage: u64,
+    /// Bytes of block cache entries that are currently in use and can't be evicted.
+    pub block_cache_pinned_usage: u64,
+    pub dbs: Vec<DbMemoryUsage>,
+}
+
+impl MemoryUsageStats {
+    pub fn approximate_mem_table_total(&self) -> u64 {
+        self.dbs.iter().map(|db| db.mem_table_total()).sum()
+    }
+
+    pub fn approximate_mem_table_unflushed(&self) -> u64 {
+        self.dbs.iter().map(|db| db.mem_table_unflushed()).sum()
+    }
+
+    pub fn approximate_table_readers_total(&self) -> u64 {
+        self.dbs.iter().map(|db| db.table_readers_total()).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DbMemoryUsage {
+    pub name: DbName,
+    pub cfs: Vec<CfMemoryUsage>,
+}
+
+impl DbMemoryUsage {
+    pub(crate) fn collect(db: &RocksDb) -> Self {
+        let read = |cf: &CfName, property: &str| {
+            db.inner()
+                .get_property_int_cf(cf, property)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        };
+        let cfs = db
+            .cfs()
+            .into_iter()
+            .map(|cf| CfMemoryUsage {
+                mem_table_total: read(&cf, "rocksdb.size-all-mem-tables"),
+                mem_table_unflushed: read(&cf, "rocksdb.cur-size-all-mem-tables"),
+                table_readers: read(&cf, "rocksdb.estimate-table-readers-mem"),
+                block_cache_pinned: read(&cf, "rocksdb.block-cache-pinned-usage"),
+                name: cf,
+            })
+            .collect();
+        Self {
+            name: db.name.clone(),
+            cfs,
+        }
+    }
+
+    pub fn mem_table_total(&self) -> u64 {
+        self.cfs.iter().map(|cf| cf.mem_table_total).sum()
+    }
+
+    pub fn mem_table_unflushed(&self) -> u64 {
+        self.cfs.iter().map(|cf| cf.mem_table_unflushed).sum()
+    }
+
+    pub fn table_readers_total(&self) -> u64 {
+        self.cfs.iter().map(|cf| cf.table_readers).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CfMemoryUsage {
+    pub name: CfName,
+    /// Active, unflushed immutable and pinned immutable memtables.
+    pub mem_table_total: u64,
+    /// Active and unflushed immutable memtables.
+    pub mem_table_unflushed: u64,
+    /// Index and filter blocks held by open SST files outside of the block cache.
+    pub table_readers: u64,
+    /// Block cache entries pinned by this column family, as reported by its block cache. Column
+    /// families that share the block cache report the same value.
+    pub block_cache_pinned: u64,
+}
@@ -21,6 +21,12 @@ pub const STORAGE_BG_TASK_RUN_DURATION: &str =
 pub const STORAGE_BG_TASK_TOTAL_DURATION: &str =
     "restate.rocksdb_manager.bg_task_total_duration.seconds";
 
+pub const STORAGE_BG_TASK_QUEUE_LATENCY: &str =
+    "restate.rocksdb_manager.bg_task_queue_latency.seconds";
+pub const STORAGE_BG_TASK_REJECTED: &str = "restate.rocksdb_manager.bg_task_rejected.total";
+pub const STORAGE_BG_TASK_STOLEN: &str = "restate.rocksdb_manager.bg_task_stolen.total";
+pub const STORAGE_QUEUE_DEPTH: &str = "restate.rocksdb_manager.queue_depth";
+
 pub const BLOCK_READ_COUNT: &str = "restate.rocksdb.perf.num_block_read.total";
 pub const BLOCK_READ_BYTES: &str = "restate.rocksdb.perf.block_read_bytes.total";
 pub const WRITE_WAL_DURATION: &str = "restate.rocksdb.perf.write_wal_duration.seconds";
@@ -33,10 +39,41 @@ pub const WRITE_ARTIFICIAL_DELAY_DURATION: &str =
 pub const ROCKSDB_STALL_FLARE: &str = "restate.rocksdb_stall_flare";
 pub const ROCKSDB_STALL_DURATION: &str = "restate.rocksdb_stall_duration.seconds";
 
+pub const ROCKSDB_ESTIMATE_NUM_KEYS: &str = "restate.rocksdb.estimate_num_keys";
+pub const ROCKSDB_ESTIMATE_PENDING_COMPACTION_BYTES: &str =
+    "restate.rocksdb.estimate_pending_compaction.bytes";
+pub const ROCKSDB_NUM_IMMUTABLE_MEMTABLES: &str = "restate.rocksdb.num_immutable_memtables";
+pub const ROCKSDB_TOTAL_SST_FILES_SIZE: &str = "restate.rocksdb.total_sst_files_size.bytes";
+pub const ROCKSDB_NUM_FILES_AT_LEVEL: &str = "restate.rocksdb.num_files_at_level";
+
+pub const ROCKSDB_FLUSH_IN_PROGRESS: &str = "restate.rocksdb.flush_in_progress";
+pub const ROCKSDB_COMPACTION_IN_PROGRESS: &str = "restate.rocksdb.compaction_in_progress";
+pub const ROCKSDB_WRITE_STALL: &str = "restate.rocksdb.write_stall.total";
+pub const ROCKSDB_BACKGROUND_ERRORS: &str = "restate.rocksdb.background_errors.total";
+pub const ROCKSDB_QUARANTINED: &str = "restate.rocksdb.quarantined";
+pub const ROCKSDB_CORRUPTIONS_DETECTED: &str = "restate.rocksdb.corruptions_detected.total";
+pub const ROCKSDB_GROUP_COMMIT_SIZE: &str = "restate.rocksdb.group_commit_size";
+pub const ROCKSDB_SPLIT_WRITE_BATCHES: &str = "restate.rocksdb.split_write_batches.total";
+pub const ROCKSDB_DISK_USAGE: &str = "restate.rocksdb.disk_usage.bytes";
+pub const ROCKSDB_DISK_BUDGET_HEADROOM: &str = "restate.rocksdb.disk_budget_headroom.bytes";
+
+pub const ROCKSDB_CF_COMPACTION_READ_BYTES: &str = "restate.rocksdb.cf_compaction_read.bytes";
+pub const ROCKSDB_CF_COMPACTION_WRITE_BYTES: &str = "restate.rocksdb.cf_compaction_write.bytes";
+pub const ROCKSDB_CF_COMPACTION_DURATION: &str = "restate.rocksdb.cf_compaction_duration.seconds";
+pub const ROCKSDB_CF_COMPACTIONS: &str = "restate.rocksdb.cf_compactions.total";
+pub const ROCKSDB_CF_WRITE_AMPLIFICATION: &str = "restate.rocksdb.cf_write_amplification";
+pub const ROCKSDB_CF_FLUSH_WRITE_BYTES: &str = "restate.rocksdb.cf_flush_write.bytes";
+pub const ROCKSDB_CF_WRITE_STALLS: &str = "restate.rocksdb.cf_write_stalls.total";
+
 pub const OP_TYPE: &str = "operation";
 pub const PRIORITY: &str = "priority";
 
 pub const DISPOSITION: &str = "disposition";
+pub const DB_NAME: &str = "db";
+pub const CF_NAME: &str = "cf";
+pub const LEVEL: &str = "level";
+pub const STALL_CONDITION: &str = "condition";
+pub const QUANTILE: &str = "quantile";
 
 pub const DISPOSITION_MAYBE_BLOCKING: &str = "maybe-blocking";
 pub const DISPOSITION_NON_BLOCKING: &str = "non-blocking";
@@ -98,6 +135,30 @@ pub fn describe_metrics() {
         "Total time to queue+run a storage task, with 'priority' label"
     );
 
+    describe_histogram!(
+        STORAGE_BG_TASK_QUEUE_LATENCY,
+        Unit::Seconds,
+        "Queueing time of storage tasks per database, with 'priority' and 'db' labels"
+    );
+
+    describe_counter!(
+        STORAGE_BG_TASK_REJECTED,
+        Unit::Count,
+        "Number of storage tasks rejected because the storage queue was full, with 'priority' label"
+    );
+
+    describe_counter!(
+        STORAGE_BG_TASK_STOLEN,
+        Unit::Count,
+        "Number of storage tasks that ran on the thread pool of the other priority, with 'priority' label"
+    );
+
+    describe_gauge!(
+        STORAGE_QUEUE_DEPTH,
+        Unit::Count,
+        "Number of storage tasks queued or running, with 'priority' label"
+    );
+
     describe_histogram!(
         WRITE_WAL_DURATION,
         Unit::Seconds,
@@ -116,6 +177,138 @@ pub fn describe_metrics() {
         "Time spent in pre/post write operations by rocksdb"
     );
 
+    describe_gauge!(
+        ROCKSDB_ESTIMATE_NUM_KEYS,
+        Unit::Count,
+        "Estimated number of keys per column family, with 'db' and 'cf' labels"
+    );
+
+    describe_gauge!(
+        ROCKSDB_ESTIMATE_PENDING_COMPACTION_BYTES,
+        Unit::Bytes,
+        "Estimated bytes that compaction needs to rewrite to bring all levels down to target size"
+    );
+
+    describe_gauge!(
+        ROCKSDB_NUM_IMMUTABLE_MEMTABLES,
+        Unit::Count,
+        "Number of immutable memtables that have not yet been flushed"
+    );
+
+    describe_gauge!(
+        ROCKSDB_TOTAL_SST_FILES_SIZE,
+        Unit::Bytes,
+        "Total size of all SST files of the column family"
+    );
+
+    describe_gauge!(
+        ROCKSDB_NUM_FILES_AT_LEVEL,
+        Unit::Count,
+        "Number of SST files per level, with 'level' label"
+    );
+
+    describe_gauge!(
+        ROCKSDB_FLUSH_IN_PROGRESS,
+        Unit::Count,
+        "Number of memtable flushes running at the last check, with 'db' label"
+    );
+
+    describe_gauge!(
+        ROCKSDB_COMPACTION_IN_PROGRESS,
+        Unit::Count,
+        "Number of compactions running at the last check, with 'db' label"
+    );
+
+    describe_counter!(
+        ROCKSDB_WRITE_STALL,
+        Unit::Count,
+        "Number of times rocksdb started to delay or stop writes, 'condition' is either 'delayed' or 'stopped'"
+    );
+
+    describe_counter!(
+        ROCKSDB_BACKGROUND_ERRORS,
+        Unit::Count,
+        "Number of background errors reported by rocksdb, with 'db' label"
+    );
+
+    describe_gauge!(
+        ROCKSDB_QUARANTINED,
+        Unit::Count,
+        "1 if the database is quarantined because of a background error, with 'db' label"
+    );
+
+    describe_counter!(
+        ROCKSDB_CORRUPTIONS_DETECTED,
+        Unit::Count,
+        "Number of corrupted files found by checksum verification, with 'db' and 'cf' labels"
+    );
+
+    describe_histogram!(
+        ROCKSDB_GROUP_COMMIT_SIZE,
+        Unit::Count,
+        "Number of writes made durable by a single group commit WAL sync, with 'db' label"
+    );
+
+    describe_counter!(
+        ROCKSDB_SPLIT_WRITE_BATCHES,
+        Unit::Count,
+        "Number of write batches that were split because they exceeded rocksdb-max-write-batch-size, with 'db' label"
+    );
+
+    describe_gauge!(
+        ROCKSDB_DISK_USAGE,
+        Unit::Bytes,
+        "Disk space taken up by live SST and WAL files of databases with a disk budget, with 'db' label"
+    );
+
+    describe_gauge!(
+        ROCKSDB_DISK_BUDGET_HEADROOM,
+        Unit::Bytes,
+        "Disk budget left before the budget of the database is exceeded, negative once exceeded, with 'db' label"
+    );
+
+    describe_gauge!(
+        ROCKSDB_CF_COMPACTION_READ_BYTES,
+        Unit::Bytes,
+        "Bytes read by compactions of the column family, with 'db' and 'cf' labels"
+    );
+
+    describe_gauge!(
+        ROCKSDB_CF_COMPACTION_WRITE_BYTES,
+        Unit::Bytes,
+        "Bytes written by flushes and compactions of the column family, with 'db' and 'cf' labels"
+    );
+
+    describe_gauge!(
+        ROCKSDB_CF_COMPACTION_DURATION,
+        Unit::Seconds,
+        "Time spent in flushes and compactions of the column family, with 'db' and 'cf' labels"
+    );
+
+    describe_counter!(
+        ROCKSDB_CF_COMPACTIONS,
+        Unit::Count,
+        "Number of flushes and compactions of the column family, with 'db' and 'cf' labels"
+    );
+
+    describe_gauge!(
+        ROCKSDB_CF_WRITE_AMPLIFICATION,
+        Unit::Count,
+        "Write amplification of the compactions of the column family, with 'db' and 'cf' labels"
+    );
+
+    describe_gauge!(
+        ROCKSDB_CF_FLUSH_WRITE_BYTES,
+        Unit::Bytes,
+        "Bytes written by flushes of the column family, with 'db' and 'cf' labels"
+    );
+
+    describe_counter!(
+        ROCKSDB_CF_WRITE_STALLS,
+        Unit::Count,
+        "Number of write stalls caused by the column family, with 'db', 'cf' and 'condition' labels"
+    );
+
     describe_histogram!(
         WRITE_ARTIFICIAL_DELAY_DURATION,
         Unit::Seconds,
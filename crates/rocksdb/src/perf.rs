@@ -9,13 +9,15 @@
 // by the Apache License, Version 2.0.
 
 use std::cell::RefCell;
+use std::time::Instant;
 
 use metrics::{counter, histogram};
 use rocksdb::{PerfContext, PerfMetric, PerfStatsLevel};
+use tracing::warn;
 
 use crate::background::StorageTaskKind;
 use crate::{
-    BLOCK_READ_BYTES, BLOCK_READ_COUNT, OP_TYPE, WRITE_ARTIFICIAL_DELAY_DURATION,
+    RocksDbManager, BLOCK_READ_BYTES, BLOCK_READ_COUNT, OP_TYPE, WRITE_ARTIFICIAL_DELAY_DURATION,
     WRITE_MEMTABLE_DURATION, WRITE_PRE_AND_POST_DURATION, WRITE_WAL_DURATION,
 };
 
@@ -26,8 +28,12 @@ thread_local! {
 /// This guard must be created and dropped in the same thread, you should never use the same
 /// guard across .await points. This should strictly be used within the bounds of the sync
 /// RocksAccess layer.
+///
+/// Operations that take longer than the stall detection threshold log the full perf context, to
+/// tell whether they were slowed down by block reads, cache misses or write stalls.
 pub struct RocksDbPerfGuard {
     kind: StorageTaskKind,
+    started_at: Instant,
 }
 
 impl RocksDbPerfGuard {
@@ -41,7 +47,10 @@ impl RocksDbPerfGuard {
         ROCKSDB_PERF_CONTEXT.with(|context| {
             context.borrow_mut().reset();
         });
-        RocksDbPerfGuard { kind }
+        RocksDbPerfGuard {
+            kind,
+            started_at: Instant::now(),
+        }
     }
 }
 
@@ -54,6 +63,17 @@ impl Drop for RocksDbPerfGuard {
             // API compared to microseconds in Statistics/Properties. Use n_to_s() to convert to
             // standard prometheus unit (second).
             let context = context.borrow();
+            let elapsed = self.started_at.elapsed();
+            if RocksDbManager::try_get()
+                .is_some_and(|manager| elapsed >= manager.stall_detection_duration())
+            {
+                warn!(
+                    op = self.kind.as_static_str(),
+                    ?elapsed,
+                    perf_context = %context.report(true),
+                    "Slow rocksdb operation"
+                );
+            }
             let v = context.metric(PerfMetric::BlockReadCount);
             if v != 0 {
                 counter!(BLOCK_READ_COUNT,
@@ -0,0 +1,42 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A storage thread pool that keeps track of how many of the tasks that were spawned on it
+/// are queued or running.
+pub(crate) struct StoragePool {
+    threads: rayon::ThreadPool,
+    busy: Arc<AtomicUsize>,
+}
+
+impl StoragePool {
+    pub(crate) fn new(threads: rayon::ThreadPool) -> Self {
+        Self {
+            threads,
+            busy: Arc::default(),
+        }
+    }
+
+    /// All threads are busy, new tasks have to wait.
+    pub(crate) fn is_saturated(&self) -> bool {
+        self.busy.load(Ordering::Relaxed) >= self.threads.current_num_threads()
+    }
+
+    pub(crate) fn spawn(&self, op: impl FnOnce() + Send + 'static) {
+        let busy = self.busy.clone();
+        busy.fetch_add(1, Ordering::Relaxed);
+        self.threads.spawn(move || {
+            op();
+            busy.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+}
@@ -9,19 +9,25 @@
 // by the Apache License, Version 2.0.
 
 use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use rocksdb::perf::MemoryUsageBuilder;
+use bytes::Bytes;
 use rocksdb::ColumnFamilyDescriptor;
 use rocksdb::MultiThreaded;
 use tracing::trace;
 
+use restate_types::config::RocksDbOptions;
+
 use crate::background::StorageTaskKind;
 use crate::perf::RocksDbPerfGuard;
+use crate::snapshot::OwnedSnapshot;
 use crate::BoxedCfMatcher;
 use crate::BoxedCfOptionUpdater;
 use crate::CfName;
+use crate::ConsistentReadTxn;
 use crate::DbSpec;
+use crate::OpenMode;
 use crate::RocksError;
 
 /// Operations in this trait can be IO blocking, prefer using `RocksDb` for efficient async access
@@ -30,6 +36,7 @@ pub trait RocksAccess {
     fn open_db(
         db_spec: &DbSpec<Self>,
         default_cf_options: rocksdb::Options,
+        opts: &RocksDbOptions,
     ) -> Result<Self, RocksError>
     where
         Self: Sized;
@@ -43,9 +50,64 @@ pub trait RocksAccess {
     fn flush_memtables(&self, cfs: &[CfName], wait: bool) -> Result<(), RocksError>;
     fn flush_wal(&self, sync: bool) -> Result<(), RocksError>;
     fn cancel_all_background_work(&self, wait: bool);
+    /// Catches up with the primary instance by tailing its MANIFEST and WAL. Only meaningful for
+    /// databases opened in [`OpenMode::Secondary`], a no-op otherwise.
+    fn try_catch_up_with_primary(&self) -> Result<(), RocksError>;
+    /// Manually compact the key range `[from, to]` of the column family, both bounds are
+    /// inclusive. `None` for either bound means the start/end of the column family respectively. This blocks until the compaction
+    /// is complete.
+    fn compact_range(
+        &self,
+        cf: &CfName,
+        from: Option<&[u8]>,
+        to: Option<&[u8]>,
+    ) -> Result<(), RocksError>;
+    /// Creates a consistent, hard-linked checkpoint of the database at `path`. The target
+    /// directory must not exist, it'll be created by rocksdb.
+    fn create_checkpoint(&self, path: &Path) -> Result<(), RocksError>;
+    /// Loads SST files created by `rocksdb::SstFileWriter` into the column family. Files are
+    /// moved into the database (hard-linked when possible), the caller must not reuse them.
+    fn ingest_external_files(&self, cf: &CfName, files: &[PathBuf]) -> Result<(), RocksError>;
+    /// Deletes all keys in `[from, to)` of the column family by writing a single range
+    /// tombstone, the space is reclaimed by compactions later.
+    fn delete_range_cf(
+        &self,
+        cf: &CfName,
+        from: &[u8],
+        to: &[u8],
+        write_options: &rocksdb::WriteOptions,
+    ) -> Result<(), RocksError>;
+    /// Reads up to `limit` key/value pairs of the column family starting at `from` (inclusive)
+    /// and ending before `to`, if set.
+    fn scan_cf(
+        &self,
+        cf: &CfName,
+        from: &[u8],
+        to: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Bytes, Bytes)>, RocksError>;
+    /// Looks up all keys in the column family with a single MultiGet, the values are returned
+    /// in the order of the keys.
+    fn multi_get_cf(&self, cf: &CfName, keys: &[Vec<u8>])
+        -> Result<Vec<Option<Bytes>>, RocksError>;
+    /// Reads all blocks within `[from, to)` of every live SST file of the column family to
+    /// validate their checksums, one file at a time. Returns the corrupted files, see
+    /// [`verify_live_files`] for the limits of what can be found.
+    fn verify_checksums_cf(
+        &self,
+        cf: &CfName,
+        from: Option<&[u8]>,
+        to: Option<&[u8]>,
+    ) -> Result<Vec<CorruptFile>, RocksError>;
+    /// Performs an empty write to check whether the database accepts writes, it fails if rocksdb
+    /// stopped background work because of an error.
+    fn probe_writable(&self) -> Result<(), RocksError>;
+    /// Takes a snapshot of the database that can be read from until the returned transaction is
+    /// dropped.
+    fn consistent_read_txn(self: Arc<Self>) -> ConsistentReadTxn;
     fn set_options_cf(&self, cf: &CfName, opts: &[(&str, &str)]) -> Result<(), RocksError>;
     fn get_property_int_cf(&self, cf: &CfName, property: &str) -> Result<Option<u64>, RocksError>;
-    fn record_memory_stats(&self, builder: &mut MemoryUsageBuilder);
+    fn get_property_cf(&self, cf: &CfName, property: &str) -> Result<Option<String>, RocksError>;
     /// This is a blocking operation and it's not meant to be called concurrently on the same
     /// database, although it's not dangerous to do so. The only impact would be the one of the
     /// callers will get an error.
@@ -53,8 +115,11 @@ pub trait RocksAccess {
         &self,
         name: CfName,
         default_cf_options: rocksdb::Options,
+        opts: &RocksDbOptions,
         cf_patterns: Arc<[(BoxedCfMatcher, BoxedCfOptionUpdater)]>,
     ) -> Result<(), RocksError>;
+    /// Drops the column family and all of its data. Blocks until the column family is dropped.
+    fn drop_cf(&self, name: &CfName) -> Result<(), RocksError>;
     fn cfs(&self) -> Vec<CfName>;
 
     fn write_batch(
@@ -73,6 +138,7 @@ pub trait RocksAccess {
 
 fn prepare_cf_options(
     cf_patterns: &[(BoxedCfMatcher, BoxedCfOptionUpdater)],
+    opts: &RocksDbOptions,
     default_cf_options: rocksdb::Options,
     cf: &CfName,
 ) -> Result<rocksdb::Options, RocksError> {
@@ -80,7 +146,7 @@ fn prepare_cf_options(
     for (pattern, options_updater) in cf_patterns {
         if pattern.cf_matches(cf) {
             // Stop at first pattern match
-            return Ok(options_updater(default_cf_options));
+            return Ok(options_updater(opts, default_cf_options));
         }
     }
     // default is special case
@@ -91,20 +157,200 @@ fn prepare_cf_options(
     Err(RocksError::UnknownColumnFamily(cf.clone()))
 }
 
+/// Sequential reads during checksum verification
+const VERIFY_READAHEAD_SIZE: usize = 2 * 1024 * 1024;
+
+fn ingest_options() -> rocksdb::IngestExternalFileOptions {
+    let mut opts = rocksdb::IngestExternalFileOptions::default();
+    // avoid copying potentially large files, rocksdb falls back to copying if linking fails.
+    opts.set_move_files(true);
+    opts
+}
+
+/// Secondary instances keep their own info logs, each process gets its own directory next to
+/// the primary's.
+fn secondary_path(primary_path: &Path) -> PathBuf {
+    let mut path = primary_path.as_os_str().to_owned();
+    path.push(format!("-secondary-{}", std::process::id()));
+    PathBuf::from(path)
+}
+
+pub(crate) fn scan_read_options(to: Option<&[u8]>) -> rocksdb::ReadOptions {
+    let mut opts = rocksdb::ReadOptions::default();
+    // scans are not bound to a single prefix
+    opts.set_total_order_seek(true);
+    if let Some(to) = to {
+        opts.set_iterate_upper_bound(to);
+    }
+    opts
+}
+
+pub(crate) fn collect_scan<D: rocksdb::DBAccess>(
+    mut it: rocksdb::DBRawIteratorWithThreadMode<'_, D>,
+    from: &[u8],
+    limit: usize,
+) -> Result<Vec<(Bytes, Bytes)>, RocksError> {
+    let _x = RocksDbPerfGuard::new(StorageTaskKind::Scan);
+    it.seek(from);
+    let mut entries = Vec::with_capacity(limit);
+    while entries.len() < limit {
+        let Some((key, value)) = it.item() else {
+            break;
+        };
+        entries.push((Bytes::copy_from_slice(key), Bytes::copy_from_slice(value)));
+        it.next();
+    }
+    it.status()?;
+    Ok(entries)
+}
+
+fn verify_read_options(to: Option<&[u8]>) -> rocksdb::ReadOptions {
+    let mut opts = scan_read_options(to);
+    opts.set_verify_checksums(true);
+    // verification reads everything once, keep the block cache for the hot data
+    opts.fill_cache(false);
+    opts.set_readahead_size(VERIFY_READAHEAD_SIZE);
+    opts
+}
+
+fn verify_with_iterator<D: rocksdb::DBAccess>(
+    mut it: rocksdb::DBRawIteratorWithThreadMode<'_, D>,
+    from: Option<&[u8]>,
+) -> Result<(), RocksError> {
+    let _x = RocksDbPerfGuard::new(StorageTaskKind::VerifyChecksums);
+    match from {
+        Some(from) => it.seek(from),
+        None => it.seek_to_first(),
+    }
+    while it.valid() {
+        it.next();
+    }
+    Ok(it.status()?)
+}
+
+/// A live SST file that failed checksum verification.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CorruptFile {
+    /// File name relative to the database directory, e.g. `000042.sst`
+    pub file: String,
+    /// Rocksdb's description of the corruption
+    pub message: String,
+}
+
+/// Verifies the live SST files of the column family one by one by scanning the part of each
+/// file's key range that overlaps `[from, to)` with `scan`. Rocksdb reads all files that overlap
+/// the scanned range, so a corruption is attributed to the file named in rocksdb's error message
+/// and each corrupted file is reported once. A corrupted file ends the scan of every file that
+/// overlaps with it, corruptions further into those files are only found after it's repaired.
+fn verify_live_files(
+    live_files: Vec<rocksdb::LiveFile>,
+    cf: &CfName,
+    from: Option<&[u8]>,
+    to: Option<&[u8]>,
+    mut scan: impl FnMut(Option<&[u8]>, Option<&[u8]>) -> Result<(), RocksError>,
+) -> Result<Vec<CorruptFile>, RocksError> {
+    let mut corruptions: Vec<CorruptFile> = Vec::new();
+    for live_file in live_files {
+        if live_file.column_family_name != cf.as_str() {
+            continue;
+        }
+        let file_name = live_file.name.trim_start_matches('/');
+        if corruptions.iter().any(|c| c.file == file_name) {
+            // already found while scanning an overlapping file
+            continue;
+        }
+        let Some((start, end)) = file_scan_range(live_file.start_key, live_file.end_key, from, to)
+        else {
+            continue;
+        };
+        trace!(%cf, file = file_name, "Verifying checksums of rocksdb file");
+        match scan(start.as_deref(), end.as_deref()) {
+            Ok(()) => {}
+            Err(RocksError::Other(e)) if e.kind() == rocksdb::ErrorKind::Corruption => {
+                let message = e.into_string();
+                let file = corrupt_file_name(&message).unwrap_or(file_name).to_owned();
+                if !corruptions.iter().any(|c| c.file == file) {
+                    corruptions.push(CorruptFile { file, message });
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(corruptions)
+}
+
+/// Lower (inclusive) and upper (exclusive) bound of a scan, unbounded if `None`.
+type ScanRange = (Option<Vec<u8>>, Option<Vec<u8>>);
+
+/// The range to scan to read all blocks of a file with the inclusive key range
+/// `[start_key, end_key]`, limited to `[from, to)`. `None` if the file doesn't overlap the range.
+fn file_scan_range(
+    start_key: Option<Vec<u8>>,
+    end_key: Option<Vec<u8>>,
+    from: Option<&[u8]>,
+    to: Option<&[u8]>,
+) -> Option<ScanRange> {
+    let start = match (start_key, from) {
+        (Some(start_key), Some(from)) if start_key.as_slice() < from => Some(from.to_vec()),
+        (Some(start_key), _) => Some(start_key),
+        (None, from) => from.map(<[u8]>::to_vec),
+    };
+    // the upper bound of the scan is exclusive, the end key's successor keeps it in range
+    let end = match (end_key, to) {
+        (Some(mut end_key), to) => {
+            end_key.push(0);
+            match to {
+                Some(to) if to < end_key.as_slice() => Some(to.to_vec()),
+                _ => Some(end_key),
+            }
+        }
+        (None, to) => to.map(<[u8]>::to_vec),
+    };
+    match (&start, &end) {
+        (Some(start), Some(end)) if start >= end => None,
+        _ => Some((start, end)),
+    }
+}
+
+/// Rocksdb names the corrupted file in the message, e.g. `block checksum mismatch: stored = 1,
+/// computed = 2, type = 1  in /data/db/000042.sst offset 0 size 4096`.
+fn corrupt_file_name(message: &str) -> Option<&str> {
+    message
+        .split_whitespace()
+        .map(|token| token.trim_end_matches([':', ',', ';']))
+        .find(|token| token.ends_with(".sst"))
+        .and_then(|path| Path::new(path).file_name())
+        .and_then(|name| name.to_str())
+}
+
+fn manual_compaction_options() -> rocksdb::CompactOptions {
+    let mut opts = rocksdb::CompactOptions::default();
+    // Manual compactions are mostly used to reclaim space after large range deletes, tombstones
+    // are only dropped for good when they reach the bottommost level.
+    opts.set_bottommost_level_compaction(rocksdb::BottommostLevelCompaction::Force);
+    opts.set_exclusive_manual_compaction(false);
+    opts
+}
+
 fn prepare_descriptors<T>(
     db_spec: &DbSpec<T>,
+    opts: &RocksDbOptions,
     default_cf_options: rocksdb::Options,
     all_cfs: &mut HashSet<CfName>,
 ) -> Result<Vec<ColumnFamilyDescriptor>, RocksError> {
     // Make sure default column family uses the global cache so that it doesn't create
     // its own cache (wastes ~32MB RSS per db)
     all_cfs.insert(CfName::new("default"));
-    // Make sure we have all column families we were asked to open/create.
-    all_cfs.extend(db_spec.ensure_column_families.iter().cloned());
+    // Make sure we have all column families we were asked to open/create. Only the primary can
+    // create column families, other modes open what exists on disk.
+    if db_spec.open_mode.is_primary() {
+        all_cfs.extend(db_spec.ensure_column_families.iter().cloned());
+    }
 
     let mut descriptors = Vec::with_capacity(all_cfs.len());
     for cf in all_cfs.iter() {
-        let cf_options = prepare_cf_options(&db_spec.cf_patterns, default_cf_options.clone(), cf)?;
+        let cf_options =
+            prepare_cf_options(&db_spec.cf_patterns, opts, default_cf_options.clone(), cf)?;
         descriptors.push(ColumnFamilyDescriptor::new(cf.as_str(), cf_options));
     }
 
@@ -115,6 +361,7 @@ impl RocksAccess for rocksdb::DB {
     fn open_db(
         db_spec: &DbSpec<Self>,
         default_cf_options: rocksdb::Options,
+        opts: &RocksDbOptions,
     ) -> Result<Self, RocksError> {
         let mut all_cfs: HashSet<CfName> =
             match rocksdb::DB::list_cf(&db_spec.db_options, &db_spec.path) {
@@ -135,10 +382,26 @@ impl RocksAccess for rocksdb::DB {
                 }
             };
 
-        let descriptors = prepare_descriptors(db_spec, default_cf_options, &mut all_cfs)?;
+        let descriptors = prepare_descriptors(db_spec, opts, default_cf_options, &mut all_cfs)?;
 
-        rocksdb::DB::open_cf_descriptors(&db_spec.db_options, &db_spec.path, descriptors)
-            .map_err(RocksError::from_rocksdb_error)
+        match db_spec.open_mode {
+            OpenMode::Primary => {
+                rocksdb::DB::open_cf_descriptors(&db_spec.db_options, &db_spec.path, descriptors)
+            }
+            OpenMode::ReadOnly => rocksdb::DB::open_cf_descriptors_read_only(
+                &db_spec.db_options,
+                &db_spec.path,
+                descriptors,
+                false,
+            ),
+            OpenMode::Secondary { .. } => rocksdb::DB::open_cf_descriptors_as_secondary(
+                &db_spec.db_options,
+                db_spec.path.as_path(),
+                secondary_path(&db_spec.path).as_path(),
+                descriptors,
+            ),
+        }
+        .map_err(RocksError::from_rocksdb_error)
     }
 
     fn cf_handle(&self, cf: &str) -> Option<Arc<rocksdb::BoundColumnFamily>> {
@@ -156,9 +419,10 @@ impl RocksAccess for rocksdb::DB {
         &self,
         name: CfName,
         default_cf_options: rocksdb::Options,
+        opts: &RocksDbOptions,
         cf_patterns: Arc<[(BoxedCfMatcher, BoxedCfOptionUpdater)]>,
     ) -> Result<(), RocksError> {
-        let options = prepare_cf_options(&cf_patterns, default_cf_options, &name)?;
+        let options = prepare_cf_options(&cf_patterns, opts, default_cf_options, &name)?;
         Ok(Self::create_cf(self, name.as_str(), &options)?)
     }
 
@@ -184,6 +448,105 @@ impl RocksAccess for rocksdb::DB {
         self.cancel_all_background_work(wait)
     }
 
+    fn try_catch_up_with_primary(&self) -> Result<(), RocksError> {
+        Ok(self.try_catch_up_with_primary()?)
+    }
+
+    fn compact_range(
+        &self,
+        cf: &CfName,
+        from: Option<&[u8]>,
+        to: Option<&[u8]>,
+    ) -> Result<(), RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        self.compact_range_cf_opt(&handle, from, to, &manual_compaction_options());
+        Ok(())
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<(), RocksError> {
+        let checkpoint = rocksdb::checkpoint::Checkpoint::new(self)?;
+        Ok(checkpoint.create_checkpoint(path)?)
+    }
+
+    fn ingest_external_files(&self, cf: &CfName, files: &[PathBuf]) -> Result<(), RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        Ok(self.ingest_external_file_cf_opts(&handle, &ingest_options(), files.to_vec())?)
+    }
+
+    fn delete_range_cf(
+        &self,
+        cf: &CfName,
+        from: &[u8],
+        to: &[u8],
+        write_options: &rocksdb::WriteOptions,
+    ) -> Result<(), RocksError> {
+        let _x = RocksDbPerfGuard::new(StorageTaskKind::DeleteRange);
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        Ok(self.delete_range_cf_opt(&handle, from, to, write_options)?)
+    }
+
+    fn scan_cf(
+        &self,
+        cf: &CfName,
+        from: &[u8],
+        to: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Bytes, Bytes)>, RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        let it = self.raw_iterator_cf_opt(&handle, scan_read_options(to));
+        collect_scan(it, from, limit)
+    }
+
+    fn multi_get_cf(
+        &self,
+        cf: &CfName,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Bytes>>, RocksError> {
+        let _x = RocksDbPerfGuard::new(StorageTaskKind::MultiGet);
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        self.batched_multi_get_cf_opt(&handle, keys, false, &rocksdb::ReadOptions::default())
+            .into_iter()
+            .map(|value| Ok(value?.map(|value| Bytes::copy_from_slice(&value))))
+            .collect()
+    }
+
+    fn verify_checksums_cf(
+        &self,
+        cf: &CfName,
+        from: Option<&[u8]>,
+        to: Option<&[u8]>,
+    ) -> Result<Vec<CorruptFile>, RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        verify_live_files(self.live_files()?, cf, from, to, |from, to| {
+            let it = self.raw_iterator_cf_opt(&handle, verify_read_options(to));
+            verify_with_iterator(it, from)
+        })
+    }
+
+    fn probe_writable(&self) -> Result<(), RocksError> {
+        Ok(RocksAccess::write_batch(
+            self,
+            &rocksdb::WriteBatch::default(),
+            &rocksdb::WriteOptions::default(),
+        )?)
+    }
+
+    fn consistent_read_txn(self: Arc<Self>) -> ConsistentReadTxn {
+        ConsistentReadTxn::new(Arc::new(OwnedSnapshot::new(self)))
+    }
+
     fn set_options_cf(&self, cf: &CfName, opts: &[(&str, &str)]) -> Result<(), RocksError> {
         let Some(handle) = self.cf_handle(cf) else {
             return Err(RocksError::UnknownColumnFamily(cf.clone()));
@@ -198,8 +561,19 @@ impl RocksAccess for rocksdb::DB {
         Ok(self.property_int_value_cf(&handle, property)?)
     }
 
-    fn record_memory_stats(&self, builder: &mut MemoryUsageBuilder) {
-        builder.add_db(self)
+    fn get_property_cf(&self, cf: &CfName, property: &str) -> Result<Option<String>, RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        Ok(self.property_value_cf(&handle, property)?)
+    }
+
+    fn drop_cf(&self, name: &CfName) -> Result<(), RocksError> {
+        if self.cf_handle(name).is_none() {
+            return Err(RocksError::UnknownColumnFamily(name.clone()));
+        }
+        trace!("Dropping CF: {}", name);
+        Ok(Self::drop_cf(self, name.as_str())?)
     }
 
     fn cfs(&self) -> Vec<CfName> {
@@ -228,7 +602,11 @@ impl RocksAccess for rocksdb::OptimisticTransactionDB<MultiThreaded> {
     fn open_db(
         db_spec: &DbSpec<Self>,
         default_cf_options: rocksdb::Options,
+        opts: &RocksDbOptions,
     ) -> Result<Self, RocksError> {
+        if !db_spec.open_mode.is_primary() {
+            return Err(RocksError::UnsupportedOpenMode(db_spec.open_mode));
+        }
         // copy pasta from DB, this will be removed as soon we as we remove the use of
         // Optimistic Transaction DB
         let mut all_cfs: HashSet<CfName> = match Self::list_cf(&db_spec.db_options, &db_spec.path) {
@@ -249,7 +627,7 @@ impl RocksAccess for rocksdb::OptimisticTransactionDB<MultiThreaded> {
             }
         };
 
-        let descriptors = prepare_descriptors(db_spec, default_cf_options, &mut all_cfs)?;
+        let descriptors = prepare_descriptors(db_spec, opts, default_cf_options, &mut all_cfs)?;
 
         rocksdb::OptimisticTransactionDB::open_cf_descriptors(
             &db_spec.db_options,
@@ -274,9 +652,10 @@ impl RocksAccess for rocksdb::OptimisticTransactionDB<MultiThreaded> {
         &self,
         name: CfName,
         default_cf_options: rocksdb::Options,
+        opts: &RocksDbOptions,
         cf_patterns: Arc<[(BoxedCfMatcher, BoxedCfOptionUpdater)]>,
     ) -> Result<(), RocksError> {
-        let options = prepare_cf_options(&cf_patterns, default_cf_options, &name)?;
+        let options = prepare_cf_options(&cf_patterns, opts, default_cf_options, &name)?;
         trace!("Opening CF: {}", name);
         Ok(Self::create_cf(self, name.as_str(), &options)?)
     }
@@ -303,6 +682,110 @@ impl RocksAccess for rocksdb::OptimisticTransactionDB<MultiThreaded> {
         self.cancel_all_background_work(wait)
     }
 
+    fn try_catch_up_with_primary(&self) -> Result<(), RocksError> {
+        // optimistic transaction databases are always opened as primary
+        Ok(())
+    }
+
+    fn compact_range(
+        &self,
+        cf: &CfName,
+        from: Option<&[u8]>,
+        to: Option<&[u8]>,
+    ) -> Result<(), RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        self.compact_range_cf_opt(&handle, from, to, &manual_compaction_options());
+        Ok(())
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<(), RocksError> {
+        let checkpoint = rocksdb::checkpoint::Checkpoint::new(self)?;
+        Ok(checkpoint.create_checkpoint(path)?)
+    }
+
+    fn ingest_external_files(&self, cf: &CfName, files: &[PathBuf]) -> Result<(), RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        Ok(self.ingest_external_file_cf_opts(&handle, &ingest_options(), files.to_vec())?)
+    }
+
+    fn delete_range_cf(
+        &self,
+        cf: &CfName,
+        from: &[u8],
+        to: &[u8],
+        write_options: &rocksdb::WriteOptions,
+    ) -> Result<(), RocksError> {
+        let _x = RocksDbPerfGuard::new(StorageTaskKind::DeleteRange);
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        // Range deletes can't be part of a transaction, but a plain write of the batch bypasses
+        // the conflict checking and is fine.
+        let mut batch = rocksdb::WriteBatchWithTransaction::<true>::default();
+        batch.delete_range_cf(&handle, from, to);
+        Ok(self.write_opt(&batch, write_options)?)
+    }
+
+    fn scan_cf(
+        &self,
+        cf: &CfName,
+        from: &[u8],
+        to: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Bytes, Bytes)>, RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        let it = self.raw_iterator_cf_opt(&handle, scan_read_options(to));
+        collect_scan(it, from, limit)
+    }
+
+    fn multi_get_cf(
+        &self,
+        cf: &CfName,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Bytes>>, RocksError> {
+        let _x = RocksDbPerfGuard::new(StorageTaskKind::MultiGet);
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        self.batched_multi_get_cf_opt(&handle, keys, false, &rocksdb::ReadOptions::default())
+            .into_iter()
+            .map(|value| Ok(value?.map(|value| Bytes::copy_from_slice(&value))))
+            .collect()
+    }
+
+    fn verify_checksums_cf(
+        &self,
+        cf: &CfName,
+        from: Option<&[u8]>,
+        to: Option<&[u8]>,
+    ) -> Result<Vec<CorruptFile>, RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        verify_live_files(self.live_files()?, cf, from, to, |from, to| {
+            let it = self.raw_iterator_cf_opt(&handle, verify_read_options(to));
+            verify_with_iterator(it, from)
+        })
+    }
+
+    fn probe_writable(&self) -> Result<(), RocksError> {
+        Ok(RocksAccess::write_tx_batch(
+            self,
+            &rocksdb::WriteBatchWithTransaction::<true>::default(),
+            &rocksdb::WriteOptions::default(),
+        )?)
+    }
+
+    fn consistent_read_txn(self: Arc<Self>) -> ConsistentReadTxn {
+        ConsistentReadTxn::new(Arc::new(OwnedSnapshot::new(self)))
+    }
+
     fn set_options_cf(&self, cf: &CfName, opts: &[(&str, &str)]) -> Result<(), RocksError> {
         let Some(handle) = self.cf_handle(cf) else {
             return Err(RocksError::UnknownColumnFamily(cf.clone()));
@@ -317,8 +800,19 @@ impl RocksAccess for rocksdb::OptimisticTransactionDB<MultiThreaded> {
         Ok(self.property_int_value_cf(&handle, property)?)
     }
 
-    fn record_memory_stats(&self, builder: &mut MemoryUsageBuilder) {
-        builder.add_db(self)
+    fn get_property_cf(&self, cf: &CfName, property: &str) -> Result<Option<String>, RocksError> {
+        let Some(handle) = self.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        Ok(self.property_value_cf(&handle, property)?)
+    }
+
+    fn drop_cf(&self, name: &CfName) -> Result<(), RocksError> {
+        if self.cf_handle(name).is_none() {
+            return Err(RocksError::UnknownColumnFamily(name.clone()));
+        }
+        trace!("Dropping CF: {}", name);
+        Ok(Self::drop_cf(self, name.as_str())?)
     }
 
     fn cfs(&self) -> Vec<CfName> {
@@ -342,3 +836,48 @@ impl RocksAccess for rocksdb::OptimisticTransactionDB<MultiThreaded> {
         self.write_opt(batch, write_options)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_range_of_file() {
+        let key = |k: &[u8]| Some(k.to_vec());
+
+        // whole file, the end key is included
+        assert_eq!(
+            file_scan_range(key(b"b"), key(b"d"), None, None),
+            Some((key(b"b"), key(b"d\0")))
+        );
+        // limited to the verified range
+        assert_eq!(
+            file_scan_range(key(b"b"), key(b"d"), Some(b"c"), Some(b"c\0\0")),
+            Some((key(b"c"), key(b"c\0\0")))
+        );
+        // no overlap
+        assert_eq!(
+            file_scan_range(key(b"b"), key(b"d"), Some(b"e"), None),
+            None
+        );
+        assert_eq!(
+            file_scan_range(key(b"b"), key(b"d"), None, Some(b"b")),
+            None
+        );
+    }
+
+    #[test]
+    fn name_of_corrupt_file() {
+        assert_eq!(
+            corrupt_file_name(
+                "Corruption: block checksum mismatch: stored = 1, computed = 2, type = 1  in \
+                 /data/db/000042.sst offset 0 size 4096"
+            ),
+            Some("000042.sst")
+        );
+        assert_eq!(
+            corrupt_file_name("Corruption: Bad table magic number"),
+            None
+        );
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use rocksdb::SnapshotWithThreadMode;
+
+use crate::rock_access::{collect_scan, scan_read_options};
+use crate::{CfName, RocksAccess, RocksError};
+
+pub(crate) trait SnapshotAccess {
+    fn get_cf(&self, cf: &CfName, key: &[u8]) -> Result<Option<Vec<u8>>, RocksError>;
+    fn scan_cf(
+        &self,
+        cf: &CfName,
+        from: &[u8],
+        to: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Bytes, Bytes)>, RocksError>;
+}
+
+/// A rocksdb snapshot that keeps the database it was taken from alive.
+pub(crate) struct OwnedSnapshot<D>
+where
+    D: rocksdb::DBAccess + 'static,
+{
+    // Declared first, fields are dropped in order and the snapshot must be released before the
+    // database.
+    snapshot: SnapshotWithThreadMode<'static, D>,
+    db: Arc<D>,
+}
+
+impl<D> OwnedSnapshot<D>
+where
+    D: rocksdb::DBAccess + RocksAccess + 'static,
+{
+    pub(crate) fn new(db: Arc<D>) -> Self {
+        // SAFETY: The database lives on the heap behind the Arc that we hold for at least as long
+        // as the snapshot (see field order), the reference outlives all of its uses.
+        let db_ref: &'static D = unsafe { &*Arc::as_ptr(&db) };
+        let snapshot = SnapshotWithThreadMode::new(db_ref);
+        Self { snapshot, db }
+    }
+}
+
+impl<D> SnapshotAccess for OwnedSnapshot<D>
+where
+    D: rocksdb::DBAccess + RocksAccess + 'static,
+{
+    fn get_cf(&self, cf: &CfName, key: &[u8]) -> Result<Option<Vec<u8>>, RocksError> {
+        let Some(handle) = self.db.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        Ok(self
+            .snapshot
+            .get_cf_opt(&handle, key, rocksdb::ReadOptions::default())?)
+    }
+
+    fn scan_cf(
+        &self,
+        cf: &CfName,
+        from: &[u8],
+        to: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Bytes, Bytes)>, RocksError> {
+        let Some(handle) = self.db.cf_handle(cf) else {
+            return Err(RocksError::UnknownColumnFamily(cf.clone()));
+        };
+        let it = self
+            .snapshot
+            .raw_iterator_cf_opt(&handle, scan_read_options(to));
+        collect_scan(it, from, limit)
+    }
+}
+
+/// Reads from any column family of a database as of the point in time the transaction was
+/// created, see [`crate::RocksDb::consistent_read_txn`]. Writes that happen afterwards are not
+/// visible to it.
+///
+/// The snapshot is released when the last clone of the transaction is dropped. Holding on to it
+/// for long prevents compactions from dropping overwritten data, keep it short-lived.
+///
+/// Reads can block on IO, use it from the storage pools or blocking threads.
+#[derive(Clone)]
+pub struct ConsistentReadTxn {
+    snapshot: Arc<dyn SnapshotAccess + Send + Sync + 'static>,
+}
+
+static_assertions::assert_impl_all!(ConsistentReadTxn: Send, Sync);
+
+impl ConsistentReadTxn {
+    pub(crate) fn new(snapshot: Arc<dyn SnapshotAccess + Send + Sync + 'static>) -> Self {
+        Self { snapshot }
+    }
+
+    pub fn get(&self, cf: &CfName, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>, RocksError> {
+        self.snapshot.get_cf(cf, key.as_ref())
+    }
+
+    /// Reads up to `limit` key/value pairs in `[from, to)` of the column family, `None` means
+    /// until the end of the column family.
+    pub fn scan(
+        &self,
+        cf: &CfName,
+        from: impl AsRef<[u8]>,
+        to: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Bytes, Bytes)>, RocksError> {
+        self.snapshot.scan_cf(cf, from.as_ref(), to, limit)
+    }
+}
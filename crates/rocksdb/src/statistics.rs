@@ -0,0 +1,311 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Exports the statistics that rocksdb collects per database and per column family as metrics.
+//!
+//! Rocksdb only hands out all of its statistics as a single string (the same one it dumps into
+//! its LOG file), one statistic per line:
+//!
+//! ```text
+//! rocksdb.block.cache.miss COUNT : 42
+//! rocksdb.db.get.micros P50 : 1.2 P95 : 3.4 P99 : 5.6 P100 : 7.0 COUNT : 12 SUM : 34
+//! ```
+//!
+//! The per column family statistics come from the `rocksdb.cfstats` property, a human readable
+//! report that starts with the compaction stats table of the column family:
+//!
+//! ```text
+//! ** Compaction Stats [default] **
+//! Level    Files   Size     Score Read(GB)  Rn(GB) Rnp1(GB) Write(GB) ... Comp(sec) ... Comp(cnt)
+//! ----------------------------------------------------------------------------------------------
+//!   L0      1/0    1.04 KB   0.2      0.0     0.0      0.0       0.0 ...      0.00 ...         1
+//!  Sum      1/0    1.04 KB   0.0      0.0     0.0      0.0       0.0 ...      0.00 ...         1
+//! ...
+//! Flush(GB): cumulative 0.000, interval 0.000
+//! ...
+//! Write Stall (count): l0-file-count-limit-delays: 0, ..., total-stops: 0, interval: 0 total count
+//! ```
+
+use metrics::{counter, gauge};
+use rocksdb::statistics::{Histogram, Ticker};
+
+use crate::metric_definitions::*;
+use crate::RocksDb;
+
+/// Tickers that the node renders on its metrics endpoint straight from the database. They are
+/// not exported again, the endpoint would render the same series twice.
+pub const NODE_RENDERED_TICKERS: &[Ticker] = &[
+    Ticker::BlockCacheBytesRead,
+    Ticker::BlockCacheBytesWrite,
+    Ticker::BlockCacheHit,
+    Ticker::BlockCacheMiss,
+    Ticker::BloomFilterUseful,
+    Ticker::BytesRead,
+    Ticker::BytesWritten,
+    Ticker::CompactReadBytes,
+    Ticker::CompactWriteBytes,
+    Ticker::FlushWriteBytes,
+    Ticker::IterBytesRead,
+    Ticker::MemtableHit,
+    Ticker::MemtableMiss,
+    Ticker::NoIteratorCreated,
+    Ticker::NoIteratorDeleted,
+    Ticker::NumberDbNext,
+    Ticker::NumberDbSeek,
+    Ticker::NumberIterSkip,
+    Ticker::NumberKeysRead,
+    Ticker::NumberKeysUpdated,
+    Ticker::NumberKeysWritten,
+    Ticker::NumberOfReseeksInIteration,
+    Ticker::StallMicros,
+    Ticker::WalFileBytes,
+    Ticker::WalFileSynced,
+    Ticker::WriteWithWal,
+];
+
+/// Histograms that the node renders on its metrics endpoint straight from the database, see
+/// [`NODE_RENDERED_TICKERS`].
+pub const NODE_RENDERED_HISTOGRAMS: &[Histogram] = &[
+    Histogram::DbGet,
+    Histogram::DbMultiget,
+    Histogram::DbWrite,
+    Histogram::DbSeek,
+    Histogram::FlushTime,
+    Histogram::ReadBlockGetMicros,
+    Histogram::SstReadMicros,
+    Histogram::SstWriteMicros,
+    Histogram::ReadNumMergeOperands,
+    Histogram::NumSstReadPerLevel,
+    Histogram::WalFileSyncMicros,
+    Histogram::AsyncReadBytes,
+    Histogram::PollWaitMicros,
+    Histogram::CompactionTime,
+    Histogram::SstBatchSize,
+    Histogram::BytesPerWrite,
+    Histogram::BytesPerRead,
+    Histogram::BytesPerMultiget,
+];
+
+const GIB: f64 = (1u64 << 30) as f64;
+
+#[derive(Debug, Default)]
+struct HistogramStat {
+    p50: f64,
+    p95: f64,
+    p99: f64,
+    p100: f64,
+    count: u64,
+    sum: u64,
+}
+
+#[derive(Debug)]
+enum Statistic<'a> {
+    Ticker { name: &'a str, count: u64 },
+    Histogram { name: &'a str, stat: HistogramStat },
+}
+
+/// Publishes the tickers as counters and the histograms as gauges (one per quantile, plus sum and
+/// count) labeled with the database name. Does nothing if statistics are disabled for the
+/// database. The tickers and histograms the node renders itself are skipped.
+pub(crate) fn export_statistics(db: &RocksDb) {
+    let Some(stats) = db.get_statistics_str() else {
+        return;
+    };
+
+    for statistic in stats.lines().filter_map(parse_line) {
+        match statistic {
+            Statistic::Ticker { name, .. }
+                if NODE_RENDERED_TICKERS.iter().any(|t| t.name() == name) => {}
+            Statistic::Histogram { name, .. }
+                if NODE_RENDERED_HISTOGRAMS.iter().any(|h| h.name() == name) => {}
+            Statistic::Ticker { name, count } => {
+                counter!(format!("restate.{}.total", name),
+                    DB_NAME => db.name.to_string(),
+                )
+                .absolute(count);
+            }
+            Statistic::Histogram { name, stat } => {
+                // Prometheus recommends base units
+                let (name, scale) = match name.strip_suffix(".micros") {
+                    Some(name) => (format!("restate.{}.seconds", name), 1.0 / 1_000_000.0),
+                    None => (format!("restate.{}", name), 1.0),
+                };
+                for (quantile, value) in [
+                    ("0.5", stat.p50),
+                    ("0.95", stat.p95),
+                    ("0.99", stat.p99),
+                    ("1.0", stat.p100),
+                ] {
+                    gauge!(name.clone(),
+                        DB_NAME => db.name.to_string(),
+                        QUANTILE => quantile,
+                    )
+                    .set(value * scale);
+                }
+                gauge!(format!("{}.sum", name), DB_NAME => db.name.to_string())
+                    .set(stat.sum as f64 * scale);
+                gauge!(format!("{}.count", name), DB_NAME => db.name.to_string())
+                    .set(stat.count as f64);
+            }
+        }
+    }
+}
+
+/// Publishes the compaction, flush and write stall statistics of every column family of the
+/// database, labeled with the database and column family name.
+pub(crate) fn export_cf_statistics(db: &RocksDb) {
+    for cf in db.cfs() {
+        let Ok(Some(stats)) = db.inner().get_property_cf(&cf, "rocksdb.cfstats") else {
+            continue;
+        };
+        let stats = parse_cf_stats(&stats);
+        if let Some(compaction) = stats.compaction {
+            gauge!(ROCKSDB_CF_COMPACTION_READ_BYTES,
+                DB_NAME => db.name.to_string(),
+                CF_NAME => cf.to_string(),
+            )
+            .set(compaction.read_gb * GIB);
+            gauge!(ROCKSDB_CF_COMPACTION_WRITE_BYTES,
+                DB_NAME => db.name.to_string(),
+                CF_NAME => cf.to_string(),
+            )
+            .set(compaction.write_gb * GIB);
+            gauge!(ROCKSDB_CF_COMPACTION_DURATION,
+                DB_NAME => db.name.to_string(),
+                CF_NAME => cf.to_string(),
+            )
+            .set(compaction.seconds);
+            counter!(ROCKSDB_CF_COMPACTIONS,
+                DB_NAME => db.name.to_string(),
+                CF_NAME => cf.to_string(),
+            )
+            .absolute(compaction.count);
+            gauge!(ROCKSDB_CF_WRITE_AMPLIFICATION,
+                DB_NAME => db.name.to_string(),
+                CF_NAME => cf.to_string(),
+            )
+            .set(compaction.write_amplification);
+        }
+        if let Some(flush_gb) = stats.flush_gb {
+            gauge!(ROCKSDB_CF_FLUSH_WRITE_BYTES,
+                DB_NAME => db.name.to_string(),
+                CF_NAME => cf.to_string(),
+            )
+            .set(flush_gb * GIB);
+        }
+        for (condition, count) in stats.write_stalls {
+            counter!(ROCKSDB_CF_WRITE_STALLS,
+                DB_NAME => db.name.to_string(),
+                CF_NAME => cf.to_string(),
+                STALL_CONDITION => condition.to_owned(),
+            )
+            .absolute(count);
+        }
+    }
+}
+
+/// The totals of the compaction stats table, flushes count as compactions into L0.
+#[derive(Debug, Default, PartialEq)]
+struct CompactionStats {
+    read_gb: f64,
+    write_gb: f64,
+    write_amplification: f64,
+    seconds: f64,
+    count: u64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct CfStats<'a> {
+    compaction: Option<CompactionStats>,
+    flush_gb: Option<f64>,
+    write_stalls: Vec<(&'a str, u64)>,
+}
+
+fn parse_cf_stats(stats: &str) -> CfStats<'_> {
+    let mut cf_stats = CfStats::default();
+    let mut header = None;
+    for line in stats.lines() {
+        let line = line.trim();
+        if line.starts_with("Level ") && header.is_none() {
+            header = Some(line);
+        } else if line.starts_with("Sum ") && cf_stats.compaction.is_none() {
+            cf_stats.compaction = header.and_then(|header| parse_compaction_sum(header, line));
+        } else if let Some(flush) = line.strip_prefix("Flush(GB): cumulative ") {
+            cf_stats.flush_gb = flush.split(',').next().and_then(|v| v.trim().parse().ok());
+        } else if let Some(stalls) = line.strip_prefix("Write Stall (count): ") {
+            let stalls = stalls.strip_suffix(" total count").unwrap_or(stalls);
+            cf_stats.write_stalls = stalls
+                .split(", ")
+                .filter_map(|stall| {
+                    let (condition, count) = stall.split_once(": ")?;
+                    Some((condition, count.trim().parse().ok()?))
+                })
+                .filter(|(condition, _)| *condition != "interval")
+                .collect();
+        }
+    }
+    cf_stats
+}
+
+/// Parses the `Sum` row of the compaction stats table. Its size column is followed by the size
+/// unit, which has no header of its own.
+fn parse_compaction_sum(header: &str, sum: &str) -> Option<CompactionStats> {
+    let mut values: Vec<_> = sum.split_whitespace().collect();
+    let size = header
+        .split_whitespace()
+        .position(|column| column == "Size")?;
+    if size + 1 < values.len() {
+        values.remove(size + 1);
+    }
+    let column = |name: &str| -> Option<f64> {
+        let index = header
+            .split_whitespace()
+            .position(|column| column == name)?;
+        values.get(index)?.parse().ok()
+    };
+    Some(CompactionStats {
+        read_gb: column("Read(GB)")?.parse().ok()?,
+        write_gb: column("Write(GB)")?.parse().ok()?,
+        write_amplification: column("W-Amp")?.parse().ok()?,
+        seconds: column("Comp(sec)")?.parse().ok()?,
+        count: column("Comp(cnt)")?.parse().ok()?,
+    })
+}
+
+fn parse_line(line: &str) -> Option<Statistic<'_>> {
+    let mut tokens = line.split_whitespace();
+    let name = tokens.next()?;
+
+    let mut ticker_count = None;
+    let mut histogram = HistogramStat::default();
+    // the rest of the line is a sequence of `<KEY> : <VALUE>`
+    while let (Some(key), Some(":"), Some(value)) = (tokens.next(), tokens.next(), tokens.next()) {
+        match key {
+            "P50" => histogram.p50 = value.parse().ok()?,
+            "P95" => histogram.p95 = value.parse().ok()?,
+            "P99" => histogram.p99 = value.parse().ok()?,
+            "P100" => histogram.p100 = value.parse().ok()?,
+            "COUNT" => ticker_count = Some(value.parse().ok()?),
+            "SUM" => histogram.sum = value.parse().ok()?,
+            _ => {}
+        }
+    }
+
+    let count = ticker_count?;
+    if line.contains(" P50 : ") {
+        histogram.count = count;
+        Some(Statistic::Histogram {
+            name,
+            stat: histogram,
+        })
+    } else {
+        Some(Statistic::Ticker { name, count })
+    }
+}
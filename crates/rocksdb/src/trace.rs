@@ -0,0 +1,318 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Records the workload that goes through [`RocksDb`] to a file and replays it, to reproduce
+//! compaction or latency problems of production databases in benchmarks.
+//!
+//! A trace holds one json record per line. Writes are recorded with their full content, keys of
+//! reads are recorded but not the values that were read. Accesses to the raw database (see
+//! [`RocksDb::inner`]) are not traced.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use futures::TryStreamExt;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{CfName, IoMode, Priority, RocksDb, RocksError};
+
+const TRACE_WRITE_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TraceError {
+    #[error("tracing is already active for db {0}")]
+    AlreadyTracing(crate::DbName),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("malformed trace record at line {0}: {1}")]
+    Malformed(usize, serde_json::Error),
+    #[error(transparent)]
+    Rocks(#[from] RocksError),
+}
+
+/// A single traced operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+pub enum TraceOp {
+    /// The serialized content of a `WriteBatch`.
+    WriteBatch {
+        priority: Priority,
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+    /// The serialized content of a `WriteBatchWithTransaction<true>`.
+    WriteTxBatch {
+        priority: Priority,
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+    DeleteRange {
+        priority: Priority,
+        cf: String,
+        #[serde(with = "base64_bytes")]
+        from: Vec<u8>,
+        #[serde(with = "base64_bytes")]
+        to: Vec<u8>,
+    },
+    MultiGet {
+        priority: Priority,
+        cf: String,
+        #[serde(with = "base64_keys")]
+        keys: Vec<Vec<u8>>,
+    },
+    Scan {
+        priority: Priority,
+        cf: String,
+        #[serde(with = "base64_bytes")]
+        from: Vec<u8>,
+        #[serde(default, with = "base64_opt_bytes")]
+        to: Option<Vec<u8>>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// Time since the start of the trace
+    pub offset_micros: u64,
+    #[serde(flatten)]
+    pub op: TraceOp,
+}
+
+/// Appends the operations of a database to a trace file while tracing is active.
+pub(crate) struct WorkloadTracer {
+    path: PathBuf,
+    started_at: Instant,
+    out: Mutex<BufWriter<File>>,
+}
+
+impl WorkloadTracer {
+    pub(crate) fn create(path: &Path) -> Result<Self, TraceError> {
+        let file = File::create(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            started_at: Instant::now(),
+            out: Mutex::new(BufWriter::with_capacity(TRACE_WRITE_BUFFER_SIZE, file)),
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Tracing is best-effort, a failure to record an operation never fails the operation.
+    pub(crate) fn record(&self, op: TraceOp) {
+        let record = TraceRecord {
+            offset_micros: self.started_at.elapsed().as_micros() as u64,
+            op,
+        };
+        let mut out = self.out.lock();
+        let result = serde_json::to_writer(&mut *out, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| out.write_all(b"\n"));
+        if let Err(e) = result {
+            warn!(path = %self.path.display(), "Failed to record rocksdb trace: {}", e);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> Result<(), TraceError> {
+        Ok(self.out.lock().flush()?)
+    }
+}
+
+/// Options of [`replay_trace`].
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    /// Wait between operations as long as the traced workload did, otherwise replay as fast as
+    /// possible.
+    pub preserve_timing: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplaySummary {
+    pub operations: u64,
+    pub failed_operations: u64,
+    pub elapsed: Duration,
+}
+
+/// Replays a trace that was recorded with [`RocksDb::start_trace`] against `db`. Write batches
+/// address column families by id, replay against a copy of the traced database (e.g. a
+/// checkpoint taken before tracing started) so that the ids match.
+///
+/// Failed operations are counted and skipped, the replay only fails if the trace can't be read.
+pub async fn replay_trace(
+    db: &RocksDb,
+    path: &Path,
+    opts: ReplayOptions,
+) -> Result<ReplaySummary, TraceError> {
+    let reader = BufReader::new(File::open(path)?);
+    let started_at = Instant::now();
+    let mut summary = ReplaySummary::default();
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: TraceRecord =
+            serde_json::from_str(&line).map_err(|e| TraceError::Malformed(idx + 1, e))?;
+        if opts.preserve_timing {
+            let due = Duration::from_micros(record.offset_micros);
+            tokio::time::sleep(due.saturating_sub(started_at.elapsed())).await;
+        }
+        summary.operations += 1;
+        if let Err(e) = replay_op(db, record.op).await {
+            summary.failed_operations += 1;
+            warn!(db = %db.name, line = idx + 1, "Failed to replay traced operation: {}", e);
+        }
+    }
+
+    summary.elapsed = started_at.elapsed();
+    info!(
+        db = %db.name,
+        path = %path.display(),
+        operations = summary.operations,
+        failed = summary.failed_operations,
+        "Replayed rocksdb trace in {:?}",
+        summary.elapsed
+    );
+    Ok(summary)
+}
+
+async fn replay_op(db: &RocksDb, op: TraceOp) -> Result<(), RocksError> {
+    match op {
+        TraceOp::WriteBatch { priority, data } => {
+            db.write_batch(
+                priority,
+                IoMode::Default,
+                rocksdb::WriteOptions::default(),
+                rocksdb::WriteBatch::from_data(&data),
+            )
+            .await
+        }
+        TraceOp::WriteTxBatch { priority, data } => {
+            db.write_tx_batch(
+                priority,
+                IoMode::Default,
+                rocksdb::WriteOptions::default(),
+                rocksdb::WriteBatchWithTransaction::<true>::from_data(&data),
+            )
+            .await
+        }
+        TraceOp::DeleteRange {
+            priority,
+            cf,
+            from,
+            to,
+        } => {
+            db.delete_range(
+                priority,
+                CfName::from(cf),
+                from,
+                to,
+                rocksdb::WriteOptions::default(),
+            )
+            .await
+        }
+        TraceOp::MultiGet { priority, cf, keys } => db
+            .multi_get_cf(CfName::from(cf), keys, priority)
+            .await
+            .map(|_| ()),
+        TraceOp::Scan {
+            priority,
+            cf,
+            from,
+            to,
+        } => {
+            let stream = match to {
+                Some(to) => futures::future::Either::Left(db.iter_stream(
+                    CfName::from(cf),
+                    from..to,
+                    priority,
+                )),
+                None => futures::future::Either::Right(db.iter_stream(
+                    CfName::from(cf),
+                    from..,
+                    priority,
+                )),
+            };
+            stream
+                .try_for_each(|_| futures::future::ready(Ok(())))
+                .await
+        }
+    }
+}
+
+mod base64_bytes {
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+mod base64_opt_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => super::base64_bytes::serialize(bytes, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::base64_bytes")] Vec<u8>);
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(bytes)| bytes))
+    }
+}
+
+mod base64_keys {
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(keys: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct Key<'a>(#[serde(with = "super::base64_bytes")] &'a [u8]);
+        let mut seq = serializer.serialize_seq(Some(keys.len()))?;
+        for key in keys {
+            seq.serialize_element(&Key(key))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        #[derive(Deserialize)]
+        struct Key(#[serde(with = "super::base64_bytes")] Vec<u8>);
+        Ok(Vec::<Key>::deserialize(deserializer)?
+            .into_iter()
+            .map(|Key(key)| key)
+            .collect())
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::NonZeroUsize;
+
+use restate_rocksdb::{CfName, Priority, RocksError};
+use restate_types::config::{Configuration, RocksDbOptions};
+
+use crate::{
+    get, init_manager_with_live_config, open_db_with_held_up_compactions, put, release_compactions,
+    start_held_up_compaction, update_config, CF,
+};
+
+#[tokio::test]
+async fn tasks_are_rejected_but_writes_are_admitted_when_the_queue_is_full() {
+    let mut config = Configuration::default();
+    // a thread to spare for the checkpoint while the compaction is held up
+    config.common.storage_low_priority_bg_threads = NonZeroUsize::new(2);
+    config.common.storage_low_priority_queue_depth = NonZeroUsize::new(1).unwrap();
+    let _manager = init_manager_with_live_config(config);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db_with_held_up_compactions("db", dir.path());
+    // the compaction occupies the low priority queue until it's released
+    let compaction = start_held_up_compaction(&db).await;
+
+    assert!(matches!(
+        db.create_checkpoint(dir.path().join("rejected")).await,
+        Err(RocksError::Busy(Priority::Low))
+    ));
+    // the high priority queue is independent
+    db.create_cf(CfName::new("data-high"), &RocksDbOptions::default())
+        .await
+        .unwrap();
+
+    // writes and flushes are admitted even though the queue is full
+    put(&db, b"a", b"1").await;
+    db.delete_range(
+        Priority::Low,
+        CfName::new(CF),
+        b"a".to_vec(),
+        b"b".to_vec(),
+        rocksdb::WriteOptions::default(),
+    )
+    .await
+    .unwrap();
+    db.flush_wal(true).await.unwrap();
+    assert_eq!(get(&db, b"a"), None);
+
+    // the queue depth is raised at runtime
+    update_config(|config| {
+        config.common.storage_low_priority_queue_depth = NonZeroUsize::new(2).unwrap()
+    })
+    .await;
+    db.create_checkpoint(dir.path().join("checkpoint"))
+        .await
+        .unwrap();
+
+    release_compactions();
+    compaction.await.unwrap().unwrap();
+}
@@ -0,0 +1,58 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use metrics_util::debugging::DebugValue;
+use restate_rocksdb::CfName;
+use restate_types::config::CommonOptions;
+
+use crate::{init_manager, install_recorder, metric, open_db, wait_until, CF};
+
+#[tokio::test]
+async fn background_activity_is_sampled_from_db_properties() {
+    let snapshotter = install_recorder();
+    let mut opts = CommonOptions::default();
+    opts.rocksdb_properties_poll_interval = Duration::from_millis(10).into();
+    let _manager = init_manager(opts);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("activity", dir.path());
+
+    // a misspelled property would silently read as idle
+    for property in [
+        "rocksdb.num-running-flushes",
+        "rocksdb.num-running-compactions",
+        "rocksdb.is-write-stopped",
+        "rocksdb.actual-delayed-write-rate",
+        "rocksdb.background-errors",
+    ] {
+        assert_eq!(
+            db.inner()
+                .get_property_int_cf(&CfName::new(CF), property)
+                .expect("property is read"),
+            Some(0),
+            "{property}"
+        );
+    }
+
+    for gauge in [
+        "restate.rocksdb.flush_in_progress",
+        "restate.rocksdb.compaction_in_progress",
+    ] {
+        wait_until(|| {
+            matches!(
+                metric(&snapshotter, gauge, &[("db", "activity")]),
+                Some(DebugValue::Gauge(value)) if value.into_inner() == 0.0
+            )
+        })
+        .await;
+    }
+    assert!(db.is_healthy());
+}
@@ -0,0 +1,75 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use restate_rocksdb::{CfName, CfPrefixPattern, DbName, DbSpecBuilder};
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, RocksDbOptions, RocksDbOptionsBuilder};
+
+use crate::{init_manager, open_db_with_spec, persisted_cf_option, CF};
+
+const DEFAULT_TARGET_FILE_SIZE: usize = 8 * 1024 * 1024;
+
+/// The column family patch only sets the target file size if it's not configured.
+fn spec(name: &str, path: &Path) -> DbSpecBuilder<rocksdb::DB> {
+    DbSpecBuilder::new(
+        DbName::new(name),
+        path.to_path_buf(),
+        rocksdb::Options::default(),
+    )
+    .add_cf_pattern(CfPrefixPattern::new(CF), |rocksdb_opts, mut opts| {
+        if rocksdb_opts.rocksdb_target_file_size_base().is_none() {
+            opts.set_target_file_size_base(DEFAULT_TARGET_FILE_SIZE as u64);
+        }
+        opts
+    })
+    .ensure_column_families(vec![CfName::new(CF)])
+}
+
+#[tokio::test]
+async fn column_family_patches_get_the_configured_options() {
+    let _manager = init_manager(CommonOptions::default());
+
+    let configured = tempfile::tempdir().unwrap();
+    let opts = RocksDbOptionsBuilder::default()
+        .rocksdb_target_file_size_base(NonZeroUsize::new(4 * 1024 * 1024))
+        .build()
+        .unwrap();
+    open_db_with_spec(
+        spec("configured", configured.path()).build_as_db(),
+        Constant::new(opts),
+    );
+    assert_eq!(
+        persisted_cf_option(configured.path(), CF, "target_file_size_base").as_deref(),
+        Some("4194304")
+    );
+
+    let unconfigured = tempfile::tempdir().unwrap();
+    let db = open_db_with_spec(
+        spec("unconfigured", unconfigured.path()).build_as_db(),
+        Constant::new(RocksDbOptions::default()),
+    );
+    let expected = DEFAULT_TARGET_FILE_SIZE.to_string();
+    assert_eq!(
+        persisted_cf_option(unconfigured.path(), CF, "target_file_size_base"),
+        Some(expected.clone())
+    );
+    // column families opened at runtime are patched the same way
+    let cf = format!("{CF}-2");
+    db.open_cf(CfName::new(&cf), &RocksDbOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        persisted_cf_option(unconfigured.path(), &cf, "target_file_size_base"),
+        Some(expected)
+    );
+}
@@ -0,0 +1,53 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_rocksdb::DbName;
+use restate_types::config::CommonOptions;
+
+use crate::{get, init_manager, open_db, put};
+
+#[tokio::test]
+async fn checkpoint_is_a_consistent_copy() {
+    let manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", &dir.path().join("db"));
+
+    put(&db, b"a", b"1").await;
+    let checkpoint_path = dir.path().join("checkpoint");
+    db.create_checkpoint(checkpoint_path.clone())
+        .await
+        .expect("checkpoint is created");
+    // writes after taking the checkpoint are not visible in it
+    put(&db, b"a", b"2").await;
+    put(&db, b"b", b"2").await;
+
+    let checkpoint = open_db("checkpoint", &checkpoint_path);
+    assert_eq!(get(&checkpoint, b"a"), Some(b"1".to_vec()));
+    assert_eq!(get(&checkpoint, b"b"), None);
+
+    // the target directory must not exist
+    assert!(db.create_checkpoint(checkpoint_path).await.is_err());
+
+    let base_dir = dir.path().join("all");
+    std::fs::create_dir(&base_dir).unwrap();
+    let mut checkpoints = manager
+        .checkpoint_all(&base_dir)
+        .await
+        .expect("checkpoints are created");
+    checkpoints.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        checkpoints,
+        vec![
+            (DbName::new("checkpoint"), base_dir.join("checkpoint")),
+            (DbName::new("db"), base_dir.join("db")),
+        ]
+    );
+    assert!(base_dir.join("db").join("CURRENT").exists());
+}
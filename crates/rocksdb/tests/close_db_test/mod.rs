@@ -0,0 +1,56 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use restate_rocksdb::{DbName, RocksDbManager, RocksError};
+use restate_types::config::CommonOptions;
+
+use crate::{get, init_manager, open_db, put};
+
+#[tokio::test]
+async fn databases_are_closed_once_unused() {
+    let _manager = init_manager(CommonOptions::default());
+    let manager = RocksDbManager::get();
+    let dir = tempfile::tempdir().unwrap();
+    let name = DbName::new("db");
+    let db = open_db("db", dir.path());
+    put(&db, b"a", b"1").await;
+
+    assert!(matches!(
+        manager
+            .close_db(name.clone(), Duration::from_millis(50))
+            .await,
+        Err(RocksError::DbInUse(_))
+    ));
+    // the database stays open
+    assert!(manager.get_db(name.clone()).is_some());
+    put(&db, b"b", b"2").await;
+
+    // the handle is released while the manager waits for it
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        drop(db);
+    });
+    manager
+        .close_db(name.clone(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(manager.get_db(name.clone()).is_none());
+    assert!(matches!(
+        manager.close_db(name, Duration::ZERO).await,
+        Err(RocksError::UnknownDb(_))
+    ));
+
+    // closing released the lock on the directory
+    let db = open_db("db", dir.path());
+    assert_eq!(get(&db, b"a"), Some(b"1".to_vec()));
+    assert_eq!(get(&db, b"b"), Some(b"2".to_vec()));
+}
@@ -0,0 +1,51 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_rocksdb::{CfName, RocksError};
+use restate_types::config::{CommonOptions, RocksDbOptions};
+
+use crate::{get_cf, init_manager, open_db, put_cf};
+
+#[tokio::test]
+async fn create_and_drop_column_families() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", dir.path());
+    let opts = RocksDbOptions::default();
+    let cf = CfName::new("data-extra");
+
+    db.create_cf(cf.clone(), &opts)
+        .await
+        .expect("column family is created");
+    assert!(db.cfs().contains(&cf));
+    put_cf(&db, &cf, b"a", b"1").await;
+    assert_eq!(get_cf(&db, &cf, b"a"), Some(b"1".to_vec()));
+
+    assert!(matches!(
+        db.create_cf(cf.clone(), &opts).await,
+        Err(RocksError::ColumnFamilyExists(name)) if name == cf
+    ));
+    // column families need a pattern of the db spec
+    assert!(matches!(
+        db.create_cf(CfName::new("unknown"), &opts).await,
+        Err(RocksError::UnknownColumnFamily(name)) if name.as_str() == "unknown"
+    ));
+
+    db.drop_cf(cf.clone())
+        .await
+        .expect("column family is dropped");
+    assert!(!db.cfs().contains(&cf));
+
+    // the data is gone with the column family
+    db.create_cf(cf.clone(), &opts)
+        .await
+        .expect("column family is created");
+    assert_eq!(get_cf(&db, &cf, b"a"), None);
+}
@@ -0,0 +1,63 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, CompressionType, RocksDbOptionsBuilder};
+
+use crate::{db_spec, flush, init_manager, open_db_with_spec, persisted_cf_option, put, CF};
+
+const VALUE_SIZE: usize = 1024 * 1024;
+
+fn total_sst_size(path: &Path) -> u64 {
+    std::fs::read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(".sst"))
+        .map(|entry| entry.metadata().unwrap().len())
+        .sum()
+}
+
+#[tokio::test]
+async fn compression_is_configured_per_level() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db_with_spec(
+        db_spec("db", dir.path()).build_as_db(),
+        Constant::new(
+            RocksDbOptionsBuilder::default()
+                .rocksdb_compression_per_level(Some(vec![
+                    CompressionType::None,
+                    CompressionType::Lz4,
+                    CompressionType::Zstd,
+                ]))
+                .rocksdb_zstd_max_dict_bytes(NonZeroUsize::new(16 * 1024))
+                .build()
+                .unwrap(),
+        ),
+    );
+
+    assert_eq!(
+        persisted_cf_option(dir.path(), CF, "compression_per_level").as_deref(),
+        Some("kNoCompression:kLZ4Compression:kZSTD")
+    );
+    let compression_opts = persisted_cf_option(dir.path(), CF, "compression_opts").unwrap();
+    assert!(compression_opts.contains("max_dict_bytes=16384;"));
+    assert!(compression_opts.contains("zstd_max_train_bytes=1638400;"));
+
+    // flushed files stay uncompressed in L0, compaction moves them to a compressed level
+    put(&db, b"a", &vec![b'a'; VALUE_SIZE]).await;
+    flush(&db);
+    assert!(total_sst_size(dir.path()) > VALUE_SIZE as u64);
+    db.compact_all().await.unwrap();
+    assert!(total_sst_size(dir.path()) < VALUE_SIZE as u64 / 10);
+}
@@ -0,0 +1,62 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::NonZeroUsize;
+
+use restate_types::config::{CompressionType, Configuration, RocksDbOptionsBuilder};
+
+use crate::{
+    db_spec, init_manager_with_live_config, open_db_with_spec, persisted_cf_option, update_config,
+    wait_until, CF,
+};
+
+#[tokio::test]
+async fn column_family_options_follow_config_updates() {
+    let _manager = init_manager_with_live_config(Configuration::default());
+    let dir = tempfile::tempdir().unwrap();
+    open_db_with_spec(
+        db_spec("db", dir.path()).build_as_db(),
+        Configuration::mapped_updateable(|config| &config.common.rocksdb),
+    );
+    assert_ne!(
+        persisted_cf_option(dir.path(), CF, "write_buffer_size").as_deref(),
+        Some("1048576")
+    );
+
+    update_config(|config| {
+        config.common.rocksdb = RocksDbOptionsBuilder::default()
+            .rocksdb_write_buffer_size(NonZeroUsize::new(1024 * 1024))
+            .rocksdb_compression(Some(CompressionType::Zstd))
+            .build()
+            .unwrap();
+    })
+    .await;
+
+    wait_until(|| {
+        persisted_cf_option(dir.path(), CF, "write_buffer_size").as_deref() == Some("1048576")
+    })
+    .await;
+    assert_eq!(
+        persisted_cf_option(dir.path(), CF, "compression").as_deref(),
+        Some("kZSTD")
+    );
+    // options that are unset are left as they are
+    update_config(|config| {
+        config.common.rocksdb = RocksDbOptionsBuilder::default()
+            .rocksdb_write_buffer_size(NonZeroUsize::new(1024 * 1024))
+            .build()
+            .unwrap()
+    })
+    .await;
+    assert_eq!(
+        persisted_cf_option(dir.path(), CF, "compression").as_deref(),
+        Some("kZSTD")
+    );
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use bytes::Bytes;
+
+use restate_rocksdb::{CfName, RocksError};
+use restate_types::config::{CommonOptions, RocksDbOptions};
+
+use crate::{flush, get_cf, init_manager, open_db, put, put_cf, CF};
+
+#[tokio::test]
+async fn reads_observe_the_database_at_creation_time() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", dir.path());
+    let other_cf = format!("{CF}-other");
+    db.create_cf(CfName::new(&other_cf), &RocksDbOptions::default())
+        .await
+        .unwrap();
+
+    put(&db, b"a", b"1").await;
+    put(&db, b"b", b"1").await;
+    put_cf(&db, &other_cf, b"a", b"1").await;
+    let txn = db.consistent_read_txn();
+
+    put(&db, b"a", b"2").await;
+    put(&db, b"c", b"2").await;
+    put_cf(&db, &other_cf, b"a", b"2").await;
+    // the snapshot keeps the overwritten values alive
+    flush(&db);
+    db.compact_all().await.unwrap();
+    assert_eq!(get_cf(&db, &other_cf, b"a"), Some(b"2".to_vec()));
+
+    let cf = CfName::new(CF);
+    assert_eq!(txn.get(&cf, b"a").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(txn.get(&cf, b"c").unwrap(), None);
+    assert_eq!(
+        txn.get(&CfName::new(&other_cf), b"a").unwrap(),
+        Some(b"1".to_vec())
+    );
+    let entry = |key: &'static [u8], value: &'static [u8]| {
+        (Bytes::from_static(key), Bytes::from_static(value))
+    };
+    assert_eq!(
+        txn.scan(&cf, b"", None, 10).unwrap(),
+        vec![entry(b"a", b"1"), entry(b"b", b"1")]
+    );
+    assert_eq!(
+        txn.scan(&cf, b"a", Some(b"b"), 10).unwrap(),
+        vec![entry(b"a", b"1")]
+    );
+    assert_eq!(
+        txn.scan(&cf, b"", None, 1).unwrap(),
+        vec![entry(b"a", b"1")]
+    );
+    assert!(matches!(
+        txn.get(&CfName::new("unknown"), b"a"),
+        Err(RocksError::UnknownColumnFamily(_))
+    ));
+}
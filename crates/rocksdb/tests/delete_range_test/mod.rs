@@ -0,0 +1,55 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_rocksdb::{CfName, Priority, RocksError};
+use restate_types::config::CommonOptions;
+
+use crate::{flush, get, init_manager, open_db, put, CF};
+
+#[tokio::test]
+async fn delete_range_removes_keys_in_half_open_range() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", dir.path());
+
+    put(&db, b"a", b"1").await;
+    put(&db, b"b", b"2").await;
+    // the tombstone covers both flushed and unflushed keys
+    flush(&db);
+    put(&db, b"ba", b"3").await;
+    put(&db, b"c", b"4").await;
+
+    db.delete_range(
+        Priority::High,
+        CfName::new(CF),
+        b"a".to_vec(),
+        b"c".to_vec(),
+        rocksdb::WriteOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(get(&db, b"a"), None);
+    assert_eq!(get(&db, b"b"), None);
+    assert_eq!(get(&db, b"ba"), None);
+    assert_eq!(get(&db, b"c"), Some(b"4".to_vec()));
+
+    assert!(matches!(
+        db.delete_range(
+            Priority::High,
+            CfName::new("unknown"),
+            b"a".to_vec(),
+            b"c".to_vec(),
+            rocksdb::WriteOptions::default(),
+        )
+        .await,
+        Err(RocksError::UnknownColumnFamily(_))
+    ));
+}
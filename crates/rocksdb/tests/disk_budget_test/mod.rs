@@ -0,0 +1,102 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use metrics_util::debugging::DebugValue;
+
+use restate_rocksdb::{CfName, Priority};
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, RocksDbOptions};
+
+use crate::{
+    db_spec, flush, incompressible, init_manager, install_recorder, metric, open_db,
+    open_db_with_spec, put, wait_until, CF,
+};
+
+const BUDGET: u64 = 1024 * 1024;
+
+#[tokio::test]
+async fn callback_is_invoked_while_the_budget_is_exceeded() {
+    let snapshotter = install_recorder();
+    let mut opts = CommonOptions::default();
+    opts.rocksdb_properties_poll_interval = Duration::from_millis(10).into();
+    let _manager = init_manager(opts);
+    let overflows = Arc::new(AtomicU64::new(0));
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db_with_spec(
+        db_spec("budgeted", dir.path())
+            .with_disk_budget(BUDGET, {
+                let overflows = overflows.clone();
+                move |name, usage| {
+                    assert_eq!(name.as_str(), "budgeted");
+                    assert!(usage.total() > BUDGET);
+                    overflows.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build_as_db(),
+        Constant::new(RocksDbOptions::default()),
+    );
+    let unbudgeted_dir = tempfile::tempdir().unwrap();
+    let unbudgeted = open_db("unbudgeted", unbudgeted_dir.path());
+    assert_eq!(db.disk_budget().map(|budget| budget.limit()), Some(BUDGET));
+    assert!(unbudgeted.disk_budget().is_none());
+
+    for key in [b"a", b"b"] {
+        put(&db, key, &incompressible(BUDGET as usize)).await;
+        put(&unbudgeted, key, &incompressible(BUDGET as usize)).await;
+    }
+    flush(&db);
+    assert!(db.disk_usage().live_sst_files > 2 * BUDGET);
+
+    wait_until(|| overflows.load(Ordering::Relaxed) > 1).await;
+    let labels = [("db", "budgeted")];
+    assert!(matches!(
+        metric(&snapshotter, "restate.rocksdb.disk_usage.bytes", &labels),
+        Some(DebugValue::Gauge(usage)) if usage.into_inner() > BUDGET as f64
+    ));
+    assert!(matches!(
+        metric(&snapshotter, "restate.rocksdb.disk_budget_headroom.bytes", &labels),
+        Some(DebugValue::Gauge(headroom)) if headroom.into_inner() < 0.0
+    ));
+    // only databases with a budget are measured
+    assert!(metric(
+        &snapshotter,
+        "restate.rocksdb.disk_usage.bytes",
+        &[("db", "unbudgeted")]
+    )
+    .is_none());
+
+    // the usage drops back within the budget once the data is removed
+    db.delete_range(
+        Priority::High,
+        CfName::new(CF),
+        b"a".to_vec(),
+        b"c".to_vec(),
+        rocksdb::WriteOptions::default(),
+    )
+    .await
+    .unwrap();
+    flush(&db);
+    db.compact_all().await.unwrap();
+    assert!(db.disk_usage().total() <= BUDGET);
+    wait_until(|| {
+        matches!(
+            metric(&snapshotter, "restate.rocksdb.disk_budget_headroom.bytes", &labels),
+            Some(DebugValue::Gauge(headroom)) if headroom.into_inner() >= 0.0
+        )
+    })
+    .await;
+    let after_cleanup = overflows.load(Ordering::Relaxed);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(overflows.load(Ordering::Relaxed), after_cleanup);
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use restate_rocksdb::{CfName, CfPrefixPattern, DbName, DbSpecBuilder};
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, RocksDbOptions};
+
+use crate::{flush, get, init_manager, open_db_with_spec, put, CF};
+
+/// Values start with the expiry time as big-endian milliseconds since unix epoch, zero if the
+/// entry doesn't expire.
+fn expiry(_key: &[u8], value: &[u8]) -> Option<u64> {
+    let expires_at = u64::from_be_bytes(value.get(..8)?.try_into().ok()?);
+    (expires_at > 0).then_some(expires_at)
+}
+
+fn value(expires_at: u64) -> Vec<u8> {
+    let mut value = expires_at.to_be_bytes().to_vec();
+    value.extend_from_slice(b"value");
+    value
+}
+
+#[tokio::test]
+async fn expired_entries_are_dropped_by_compaction() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let spec = DbSpecBuilder::new(
+        DbName::new("db"),
+        dir.path().to_path_buf(),
+        rocksdb::Options::default(),
+    )
+    .add_cf_pattern_with_expiry(
+        CfPrefixPattern::new(CF),
+        |_, opts| opts,
+        expiry,
+        Duration::from_secs(3600),
+    )
+    .ensure_column_families(vec![CfName::new(CF)])
+    .build_as_db();
+    let db = open_db_with_spec(spec, Constant::new(RocksDbOptions::default()));
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    put(&db, b"expired", &value(now - 1)).await;
+    put(&db, b"expires-later", &value(now + 3_600_000)).await;
+    put(&db, b"never-expires", &value(0)).await;
+    flush(&db);
+    // the filter only runs during compaction
+    assert!(get(&db, b"expired").is_some());
+
+    db.compact_all().await.unwrap();
+
+    assert_eq!(get(&db, b"expired"), None);
+    assert_eq!(get(&db, b"expires-later"), Some(value(now + 3_600_000)));
+    assert_eq!(get(&db, b"never-expires"), Some(value(0)));
+}
@@ -0,0 +1,83 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use metrics_util::debugging::DebugValue;
+use rocksdb::statistics::Ticker;
+
+use restate_rocksdb::{IoMode, Priority, RocksDb};
+use restate_types::config::Configuration;
+
+use crate::{
+    get, init_manager_with_live_config, install_recorder, metric, open_db, update_config, CF,
+};
+
+const NUM_WRITES: usize = 8;
+
+async fn write(db: &RocksDb, key: &[u8]) {
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.put_cf(&db.inner().cf_handle(CF).unwrap(), key, b"value");
+    db.write_batch_group_commit(Priority::High, IoMode::Default, batch)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_writes_share_wal_syncs() {
+    let snapshotter = install_recorder();
+    let mut config = Configuration::default();
+    config.common.rocksdb_group_commit_max_latency = Duration::from_millis(200).into();
+    let _manager = init_manager_with_live_config(config);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("group-commit", dir.path());
+
+    // a lone write doesn't wait for others to join
+    tokio::time::timeout(Duration::from_millis(100), write(&db, b"lone-key"))
+        .await
+        .expect("lone write isn't held back by the latency limit");
+
+    let syncs_before = db.get_ticker_count(Ticker::WalFileSynced);
+    let keys: Vec<_> = (0..NUM_WRITES).map(|i| format!("key-{i}")).collect();
+    futures::future::join_all(keys.iter().map(|key| write(&db, key.as_bytes()))).await;
+    // the concurrent writes share WAL syncs
+    let syncs = db.get_ticker_count(Ticker::WalFileSynced) - syncs_before;
+    assert!(syncs >= 1);
+    assert!(
+        syncs < NUM_WRITES as u64,
+        "{syncs} WAL syncs for {NUM_WRITES} writes"
+    );
+    for key in &keys {
+        assert_eq!(get(&db, key.as_bytes()), Some(b"value".to_vec()));
+    }
+    // every write is part of exactly one group
+    let Some(DebugValue::Histogram(group_sizes)) = metric(
+        &snapshotter,
+        "restate.rocksdb.group_commit_size",
+        &[("db", "group-commit")],
+    ) else {
+        panic!("group sizes are recorded");
+    };
+    let group_sizes: Vec<_> = group_sizes.into_iter().map(|size| size.0).collect();
+    assert_eq!(group_sizes.iter().sum::<f64>(), (NUM_WRITES + 1) as f64);
+    assert_eq!(group_sizes.len() as u64, syncs + 1);
+    assert!(group_sizes.iter().any(|size| *size > 1.0));
+
+    // a group that reaches the size limit is synced without waiting for the latency limit
+    update_config(|config| {
+        config.common.rocksdb_group_commit_max_latency = Duration::from_secs(3600).into();
+        config.common.rocksdb_group_commit_max_bytes = NonZeroUsize::new(1).unwrap();
+    })
+    .await;
+    tokio::time::timeout(Duration::from_secs(5), write(&db, b"key"))
+        .await
+        .expect("write isn't held back by the latency limit");
+}
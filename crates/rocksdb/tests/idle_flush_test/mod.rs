@@ -0,0 +1,52 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use restate_rocksdb::{CfName, RocksDb};
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, RocksDbOptionsBuilder};
+
+use crate::{db_spec, get, init_manager, open_db, open_db_with_spec, put, wait_until, CF};
+
+fn num_files_at_level0(db: &RocksDb) -> u64 {
+    db.inner()
+        .get_property_int_cf(&CfName::new(CF), "rocksdb.num-files-at-level0")
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn idle_memtables_of_databases_without_wal_are_flushed() {
+    let mut opts = CommonOptions::default();
+    opts.rocksdb_properties_poll_interval = Duration::from_millis(10).into();
+    opts.rocksdb_idle_memtable_flush_timeout = Some(Duration::from_millis(50).into());
+    let _manager = init_manager(opts);
+    let dir = tempfile::tempdir().unwrap();
+    let without_wal = open_db("without-wal", &dir.path().join("without-wal"));
+    let with_wal = open_db_with_spec(
+        db_spec("with-wal", &dir.path().join("with-wal")).build_as_db(),
+        Constant::new(
+            RocksDbOptionsBuilder::default()
+                .rocksdb_disable_wal(Some(false))
+                .build()
+                .unwrap(),
+        ),
+    );
+
+    put(&without_wal, b"a", b"1").await;
+    put(&with_wal, b"a", b"1").await;
+
+    wait_until(|| num_files_at_level0(&without_wal) == 1).await;
+    assert_eq!(get(&without_wal, b"a"), Some(b"1".to_vec()));
+    // the WAL protects the memtable of the other database
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(num_files_at_level0(&with_wal), 0);
+}
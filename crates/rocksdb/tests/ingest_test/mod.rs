@@ -0,0 +1,55 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::{Path, PathBuf};
+
+use restate_rocksdb::{CfName, RocksError};
+use restate_types::config::CommonOptions;
+
+use crate::{get, init_manager, open_db, put, CF};
+
+fn write_sst_file(path: &Path, entries: &[(&[u8], &[u8])]) -> PathBuf {
+    let options = rocksdb::Options::default();
+    let mut writer = rocksdb::SstFileWriter::create(&options);
+    writer.open(path).unwrap();
+    for (key, value) in entries {
+        writer.put(key, value).unwrap();
+    }
+    writer.finish().unwrap();
+    path.to_path_buf()
+}
+
+#[tokio::test]
+async fn ingest_sst_files() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", &dir.path().join("db"));
+    put(&db, b"a", b"old").await;
+
+    let files = vec![
+        write_sst_file(&dir.path().join("1.sst"), &[(b"a", b"1"), (b"b", b"1")]),
+        write_sst_file(&dir.path().join("2.sst"), &[(b"c", b"2")]),
+    ];
+    db.ingest_external_files(CfName::new(CF), files)
+        .await
+        .expect("files are ingested");
+
+    // ingested files are newer than the existing data
+    assert_eq!(get(&db, b"a"), Some(b"1".to_vec()));
+    assert_eq!(get(&db, b"b"), Some(b"1".to_vec()));
+    assert_eq!(get(&db, b"c"), Some(b"2".to_vec()));
+
+    let file = write_sst_file(&dir.path().join("3.sst"), &[(b"d", b"3")]);
+    assert!(matches!(
+        db.ingest_external_files(CfName::new("unknown"), vec![file])
+            .await,
+        Err(RocksError::UnknownColumnFamily(_))
+    ));
+}
@@ -0,0 +1,310 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshotter};
+use rocksdb::compaction_filter::Decision;
+use tokio::task::JoinHandle;
+
+use parking_lot::{Mutex, MutexGuard};
+
+use restate_core::TaskCenterBuilder;
+use restate_rocksdb::{
+    CfName, CfPrefixPattern, DbName, DbSpec, DbSpecBuilder, IoMode, Priority, RocksDb,
+    RocksDbManager, RocksError,
+};
+use restate_types::arc_util::{Constant, Updateable};
+use restate_types::config::{set_current_config, CommonOptions, Configuration, RocksDbOptions};
+
+mod admission_test;
+mod background_activity_test;
+mod cf_options_test;
+mod checkpoint_test;
+mod close_db_test;
+mod column_family_test;
+mod compression_test;
+mod config_update_test;
+mod consistent_read_test;
+mod delete_range_test;
+mod disk_budget_test;
+mod expiry_test;
+mod group_commit_test;
+mod idle_flush_test;
+mod ingest_test;
+mod iter_stream_test;
+mod memory_usage_test;
+mod multi_get_test;
+mod open_mode_test;
+mod properties_test;
+mod rate_limit_test;
+mod restore_test;
+mod slow_operation_test;
+mod statistics_test;
+mod tombstone_compaction_test;
+mod trace_test;
+mod verify_checksums_test;
+mod work_stealing_test;
+mod write_batch_split_test;
+
+/// The column family of the databases opened by [`open_db`], column families prefixed with it
+/// can be created at runtime
+const CF: &str = "data";
+
+/// Held by the test that owns the database manager, the manager is a process-wide singleton.
+static MANAGER_OWNER: Mutex<()> = parking_lot::const_mutex(());
+
+/// The database manager of a single test, see [`init_manager`]. The manager and its databases
+/// are dropped with it.
+struct TestManager {
+    manager: &'static RocksDbManager,
+    _owner: MutexGuard<'static, ()>,
+}
+
+impl Deref for TestManager {
+    type Target = RocksDbManager;
+
+    fn deref(&self) -> &RocksDbManager {
+        self.manager
+    }
+}
+
+impl Drop for TestManager {
+    fn drop(&mut self) {
+        RocksDbManager::uninit();
+    }
+}
+
+/// Initializes a database manager with `opts` for the calling test. Tests wait for the manager
+/// of the previous test to be dropped, keep the returned manager until the end of the test.
+fn init_manager(opts: CommonOptions) -> TestManager {
+    init_test_manager(|| RocksDbManager::init(Constant::new(opts)))
+}
+
+fn init_test_manager(init: impl FnOnce() -> &'static RocksDbManager) -> TestManager {
+    let owner = MANAGER_OWNER.lock();
+    let tc = TaskCenterBuilder::default()
+        .default_runtime_handle(tokio::runtime::Handle::current())
+        .build()
+        .expect("task_center builds");
+    TestManager {
+        manager: tc.run_in_scope_sync("db-manager-init", None, init),
+        _owner: owner,
+    }
+}
+
+/// Like [`init_manager`], but the manager follows the updates of the process-wide configuration,
+/// see [`update_config`].
+fn init_manager_with_live_config(config: Configuration) -> TestManager {
+    init_test_manager(|| {
+        set_current_config(config);
+        RocksDbManager::init(Configuration::updateable_common())
+    })
+}
+
+/// Updates the process-wide configuration and gives the manager's watchdog time to apply it.
+async fn update_config(update: impl FnOnce(&mut Configuration)) {
+    let mut config = Configuration::pinned().clone();
+    update(&mut config);
+    set_current_config(config);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+fn db_spec(name: &str, path: &Path) -> DbSpecBuilder<rocksdb::DB> {
+    DbSpecBuilder::new(
+        DbName::new(name),
+        path.to_path_buf(),
+        rocksdb::Options::default(),
+    )
+    .add_cf_pattern(CfPrefixPattern::new(CF), |_, opts| opts)
+    .ensure_column_families(vec![CfName::new(CF)])
+}
+
+/// Opens the database `name` with the column family [`CF`] in `path` using the default options.
+fn open_db(name: &str, path: &Path) -> Arc<RocksDb> {
+    open_db_with_spec(
+        db_spec(name, path).build_as_db(),
+        Constant::new(RocksDbOptions::default()),
+    )
+}
+
+fn open_db_with_spec(
+    spec: DbSpec<rocksdb::DB>,
+    opts: impl Updateable<RocksDbOptions> + Send + 'static,
+) -> Arc<RocksDb> {
+    let manager = RocksDbManager::get();
+    let name = spec.name().clone();
+    manager.open_db(opts, spec).expect("database opens");
+    manager.get_db(name).expect("database is registered")
+}
+
+/// Compactions of the databases opened by [`open_db_with_held_up_compactions`] don't finish
+/// until this is set, so that they keep occupying their storage queue slot and thread.
+static COMPACTIONS_RELEASED: AtomicBool = AtomicBool::new(false);
+
+/// Like [`open_db`], but compactions are held up until [`release_compactions`] is called.
+fn open_db_with_held_up_compactions(name: &str, path: &Path) -> Arc<RocksDb> {
+    // released by an earlier test of this process
+    COMPACTIONS_RELEASED.store(false, Ordering::Release);
+    let spec = DbSpecBuilder::new(
+        DbName::new(name),
+        path.to_path_buf(),
+        rocksdb::Options::default(),
+    )
+    .add_cf_pattern_with_compaction_filter(
+        CfPrefixPattern::new(CF),
+        |_, opts| opts,
+        "held-up",
+        |_, _, _| {
+            while !COMPACTIONS_RELEASED.load(Ordering::Acquire) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Decision::Keep
+        },
+    )
+    .ensure_column_families(vec![CfName::new(CF)])
+    .build_as_db();
+    open_db_with_spec(spec, Constant::new(RocksDbOptions::default()))
+}
+
+/// Starts a compaction of [`CF`] on the low priority storage pool that is held up until
+/// [`release_compactions`] is called.
+async fn start_held_up_compaction(db: &Arc<RocksDb>) -> JoinHandle<Result<(), RocksError>> {
+    // two overlapping files so that the compaction has to rewrite them
+    put(db, b"held-up", b"1").await;
+    flush(db);
+    put(db, b"held-up", b"2").await;
+    flush(db);
+    let compaction = tokio::spawn({
+        let db = db.clone();
+        async move { db.compact_range(CfName::new(CF), None, None).await }
+    });
+    // let the compaction be scheduled
+    tokio::task::yield_now().await;
+    compaction
+}
+
+fn release_compactions() {
+    COMPACTIONS_RELEASED.store(true, Ordering::Release);
+}
+
+async fn put(db: &RocksDb, key: &[u8], value: &[u8]) {
+    put_cf(db, CF, key, value).await
+}
+
+async fn put_cf(db: &RocksDb, cf: &str, key: &[u8], value: &[u8]) {
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.put_cf(&db.inner().cf_handle(cf).unwrap(), key, value);
+    db.write_batch(
+        Priority::High,
+        IoMode::Default,
+        rocksdb::WriteOptions::default(),
+        batch,
+    )
+    .await
+    .expect("write succeeds");
+}
+
+fn get(db: &RocksDb, key: &[u8]) -> Option<Vec<u8>> {
+    get_cf(db, CF, key)
+}
+
+fn get_cf(db: &RocksDb, cf: &str, key: &[u8]) -> Option<Vec<u8>> {
+    let raw_db = db.inner().as_raw_db();
+    raw_db
+        .get_cf(&raw_db.cf_handle(cf).unwrap(), key)
+        .expect("read succeeds")
+}
+
+/// The value of the option `name` of the column family `cf` in the latest OPTIONS file that
+/// rocksdb persisted in the database directory `path`.
+fn persisted_cf_option(path: &Path, cf: &str, name: &str) -> Option<String> {
+    let options_file = std::fs::read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|file| file.starts_with("OPTIONS-") && !file.ends_with(".dbtmp"))
+        .max_by_key(|file| file["OPTIONS-".len()..].parse::<u64>().unwrap())?;
+    let options = std::fs::read_to_string(path.join(options_file)).unwrap();
+    let section = format!("[CFOptions \"{cf}\"]");
+    options
+        .lines()
+        .skip_while(|line| line.trim() != section)
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .find_map(|line| line.trim().strip_prefix(name)?.strip_prefix('='))
+        .map(ToOwned::to_owned)
+}
+
+/// Bytes that don't compress, so that files are about as large as the data written to them.
+fn incompressible(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..len)
+        .map(|_| {
+            // xorshift
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+/// Flushes the memtable of [`CF`] to a new SST file.
+fn flush(db: &RocksDb) {
+    db.inner()
+        .flush_memtables(&[CfName::new(CF)], true)
+        .expect("flush succeeds");
+}
+
+/// Captures the metrics recorded by the tests. The recorder is installed for the whole process on
+/// first use, so the metrics of earlier tests stay around: look them up by the names of the
+/// databases of the calling test.
+fn install_recorder() -> &'static Snapshotter {
+    static SNAPSHOTTER: OnceLock<Snapshotter> = OnceLock::new();
+    SNAPSHOTTER.get_or_init(|| {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        recorder.install().expect("recorder is installed once");
+        snapshotter
+    })
+}
+
+/// The value of the metric `name` with the given labels, other labels are ignored.
+fn metric(snapshotter: &Snapshotter, name: &str, labels: &[(&str, &str)]) -> Option<DebugValue> {
+    snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .find(|(key, _, _, _)| {
+            key.key().name() == name
+                && labels.iter().all(|(label, value)| {
+                    key.key()
+                        .labels()
+                        .any(|l| l.key() == *label && l.value() == *value)
+                })
+        })
+        .map(|(_, _, _, value)| value)
+}
+
+/// Waits for up to 10 seconds for `condition` to hold.
+async fn wait_until(mut condition: impl FnMut() -> bool) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    while !condition() {
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "condition doesn't hold after 10 seconds"
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
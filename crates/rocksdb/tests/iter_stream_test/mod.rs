@@ -0,0 +1,75 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::ops::{Bound, RangeBounds};
+
+use futures::TryStreamExt;
+
+use restate_rocksdb::{CfName, IoMode, Priority, RocksDb, ITER_STREAM_CHUNK_SIZE};
+use restate_types::config::CommonOptions;
+
+use crate::{init_manager, open_db, CF};
+
+const NUM_KEYS: usize = 2 * ITER_STREAM_CHUNK_SIZE + 500;
+
+fn key(i: usize) -> Vec<u8> {
+    format!("key-{i:05}").into_bytes()
+}
+
+async fn scan(db: &RocksDb, range: impl RangeBounds<Vec<u8>>) -> Vec<usize> {
+    db.iter_stream(CfName::new(CF), range, Priority::High)
+        .map_ok(|(key, value)| {
+            assert_eq!(key, value);
+            std::str::from_utf8(&key[4..])
+                .unwrap()
+                .parse::<usize>()
+                .unwrap()
+        })
+        .try_collect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn iter_stream_reads_ranges_across_chunks() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", dir.path());
+
+    let mut batch = rocksdb::WriteBatch::default();
+    let cf = db.inner().cf_handle(CF).unwrap();
+    for i in 0..NUM_KEYS {
+        batch.put_cf(&cf, key(i), key(i));
+    }
+    db.write_batch(
+        Priority::High,
+        IoMode::Default,
+        rocksdb::WriteOptions::default(),
+        batch,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(scan(&db, ..).await, (0..NUM_KEYS).collect::<Vec<_>>());
+    assert_eq!(
+        scan(&db, key(1000)..=key(2047)).await,
+        (1000..=2047).collect::<Vec<_>>()
+    );
+    // the range ends exactly at a chunk boundary
+    assert_eq!(
+        scan(&db, ..key(ITER_STREAM_CHUNK_SIZE)).await,
+        (0..ITER_STREAM_CHUNK_SIZE).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        scan(&db, (Bound::Excluded(key(NUM_KEYS - 3)), Bound::Unbounded)).await,
+        vec![NUM_KEYS - 2, NUM_KEYS - 1]
+    );
+    assert_eq!(scan(&db, key(NUM_KEYS)..).await, Vec::<usize>::new());
+}
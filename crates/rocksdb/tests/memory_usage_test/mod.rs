@@ -0,0 +1,55 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_rocksdb::{CfName, DbName, RocksDbManager};
+use restate_types::config::CommonOptions;
+
+use crate::{flush, get, incompressible, init_manager, open_db, put, CF};
+
+const VALUE_SIZE: usize = 256 * 1024;
+
+#[tokio::test]
+async fn memory_usage_is_reported_per_column_family() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", &dir.path().join("db"));
+    open_db("other", &dir.path().join("other"));
+    let manager = RocksDbManager::get();
+
+    put(&db, b"a", &incompressible(VALUE_SIZE)).await;
+
+    let stats = manager.get_memory_usage_stats(&[DbName::new("db")]);
+    assert_eq!(stats.dbs.len(), 1);
+    assert_eq!(stats.dbs[0].name, DbName::new("db"));
+    let cf = stats.dbs[0]
+        .cfs
+        .iter()
+        .find(|cf| cf.name == CfName::new(CF))
+        .unwrap();
+    assert!(cf.mem_table_unflushed >= VALUE_SIZE as u64);
+    assert!(cf.mem_table_total >= cf.mem_table_unflushed);
+    assert_eq!(
+        stats.approximate_mem_table_unflushed(),
+        stats.dbs[0].mem_table_unflushed()
+    );
+    // no filter means all databases
+    assert_eq!(manager.get_memory_usage_stats(&[]).dbs.len(), 2);
+
+    // flushed data moves out of the memtables and is read through the block cache
+    flush(&db);
+    assert!(get(&db, b"a").is_some());
+    let stats = manager.get_memory_usage_stats(&[DbName::new("db")]);
+    assert!(stats.approximate_mem_table_unflushed() < VALUE_SIZE as u64);
+    assert!(stats.block_cache_usage >= VALUE_SIZE as u64);
+    // pinned entries are part of the block cache usage
+    for cf in &stats.dbs[0].cfs {
+        assert!(cf.block_cache_pinned <= stats.block_cache_usage);
+    }
+}
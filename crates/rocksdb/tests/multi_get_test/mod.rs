@@ -0,0 +1,94 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use bytes::Bytes;
+
+use restate_rocksdb::{
+    CfName, CfPrefixPattern, DbName, DbSpecBuilder, IoMode, Priority, RocksDb, RocksDbManager,
+    RocksError,
+};
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, RocksDbOptions};
+
+use crate::{flush, init_manager, open_db, put, CF};
+
+async fn put_tx(db: &RocksDb, key: &[u8], value: &[u8]) {
+    let mut batch = rocksdb::WriteBatchWithTransaction::<true>::default();
+    batch.put_cf(&db.inner().cf_handle(CF).unwrap(), key, value);
+    db.write_tx_batch(
+        Priority::High,
+        IoMode::Default,
+        rocksdb::WriteOptions::default(),
+        batch,
+    )
+    .await
+    .unwrap();
+}
+
+async fn multi_get(db: &RocksDb, keys: &[&[u8]]) -> Vec<Option<Bytes>> {
+    db.multi_get_cf(
+        CfName::new(CF),
+        keys.iter().map(|key| key.to_vec()).collect(),
+        Priority::High,
+    )
+    .await
+    .unwrap()
+}
+
+/// Expects `a` to be flushed and `b` to be in the memtable.
+async fn check_multi_get(db: &RocksDb) {
+    // values of flushed and unflushed keys are returned in the order of the keys
+    assert_eq!(
+        multi_get(db, &[b"b", b"missing", b"a", b"b"]).await,
+        vec![
+            Some(Bytes::from_static(b"2")),
+            None,
+            Some(Bytes::from_static(b"1")),
+            Some(Bytes::from_static(b"2")),
+        ]
+    );
+    assert!(multi_get(db, &[]).await.is_empty());
+    assert!(matches!(
+        db.multi_get_cf(CfName::new("unknown"), vec![b"a".to_vec()], Priority::High)
+            .await,
+        Err(RocksError::UnknownColumnFamily(_))
+    ));
+}
+
+#[tokio::test]
+async fn multi_get_returns_values_in_key_order() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", &dir.path().join("db"));
+    put(&db, b"a", b"1").await;
+    flush(&db);
+    put(&db, b"b", b"2").await;
+    check_multi_get(&db).await;
+
+    let manager = RocksDbManager::get();
+    manager
+        .open_db(
+            Constant::new(RocksDbOptions::default()),
+            DbSpecBuilder::new(
+                DbName::new("optimistic"),
+                dir.path().join("optimistic"),
+                rocksdb::Options::default(),
+            )
+            .add_cf_pattern(CfPrefixPattern::ANY, |_, opts| opts)
+            .ensure_column_families(vec![CfName::new(CF)])
+            .build_as_optimistic_db(),
+        )
+        .unwrap();
+    let optimistic = manager.get_db(DbName::new("optimistic")).unwrap();
+    put_tx(&optimistic, b"a", b"1").await;
+    flush(&optimistic);
+    put_tx(&optimistic, b"b", b"2").await;
+    check_multi_get(&optimistic).await;
+}
@@ -0,0 +1,92 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use restate_rocksdb::{
+    CfPrefixPattern, DbName, DbSpecBuilder, IoMode, OpenMode, Priority, RocksDbManager, RocksError,
+};
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, RocksDbOptions};
+
+use crate::{db_spec, flush, get, init_manager, open_db, open_db_with_spec, put, wait_until, CF};
+
+#[tokio::test]
+async fn read_only_and_secondary_instances() {
+    let _manager = init_manager(CommonOptions::default());
+    let manager = RocksDbManager::get();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db");
+
+    // nothing to open yet
+    assert!(manager
+        .open_db(
+            Constant::new(RocksDbOptions::default()),
+            db_spec("read-only", &path)
+                .open_mode(OpenMode::ReadOnly)
+                .build_as_db(),
+        )
+        .is_err());
+
+    let primary = open_db("primary", &path);
+    put(&primary, b"a", b"1").await;
+    flush(&primary);
+
+    let read_only = open_db_with_spec(
+        db_spec("read-only", &path)
+            .open_mode(OpenMode::ReadOnly)
+            .build_as_db(),
+        Constant::new(RocksDbOptions::default()),
+    );
+    assert_eq!(get(&read_only, b"a"), Some(b"1".to_vec()));
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.put_cf(&read_only.inner().cf_handle(CF).unwrap(), b"b", b"2");
+    assert!(read_only
+        .write_batch(
+            Priority::High,
+            IoMode::Default,
+            rocksdb::WriteOptions::default(),
+            batch,
+        )
+        .await
+        .is_err());
+
+    let secondary = open_db_with_spec(
+        db_spec("secondary", &path)
+            .open_mode(OpenMode::Secondary {
+                tail_interval: Duration::from_millis(10),
+            })
+            .build_as_db(),
+        Constant::new(RocksDbOptions::default()),
+    );
+    assert_eq!(get(&secondary, b"a"), Some(b"1".to_vec()));
+    // the secondary tails the primary's WAL
+    put(&primary, b"b", b"2").await;
+    wait_until(|| get(&secondary, b"b").is_some()).await;
+    // the read-only instance is a view as of the time of opening
+    assert_eq!(get(&read_only, b"b"), None);
+
+    assert!(matches!(
+        manager.open_db(
+            Constant::new(RocksDbOptions::default()),
+            DbSpecBuilder::new(
+                DbName::new("optimistic-secondary"),
+                path,
+                rocksdb::Options::default(),
+            )
+            .add_cf_pattern(CfPrefixPattern::ANY, |_, opts| opts)
+            .open_mode(OpenMode::Secondary {
+                tail_interval: Duration::from_millis(10),
+            })
+            .build_as_optimistic_db(),
+        ),
+        Err(RocksError::UnsupportedOpenMode(OpenMode::Secondary { .. }))
+    ));
+}
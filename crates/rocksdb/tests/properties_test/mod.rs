@@ -0,0 +1,54 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use metrics_util::debugging::DebugValue;
+use restate_types::config::CommonOptions;
+
+use crate::{flush, init_manager, install_recorder, metric, open_db, put, wait_until, CF};
+
+#[tokio::test]
+async fn properties_are_published_as_gauges() {
+    let snapshotter = install_recorder();
+    let mut opts = CommonOptions::default();
+    opts.rocksdb_properties_poll_interval = Duration::from_millis(10).into();
+    let _manager = init_manager(opts);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("properties", dir.path());
+
+    put(&db, b"a", b"1").await;
+    flush(&db);
+
+    let labels = [("db", "properties"), ("cf", CF)];
+    wait_until(|| {
+        matches!(
+            metric(&snapshotter, "restate.rocksdb.total_sst_files_size.bytes", &labels),
+            Some(DebugValue::Gauge(size)) if size.into_inner() > 0.0
+        )
+    })
+    .await;
+    // the flushed file is in level 0
+    wait_until(|| {
+        matches!(
+            metric(
+                &snapshotter,
+                "restate.rocksdb.num_files_at_level",
+                &[("db", "properties"), ("cf", CF), ("level", "0")]
+            ),
+            Some(DebugValue::Gauge(files)) if files.into_inner() == 1.0
+        )
+    })
+    .await;
+    assert!(matches!(
+        metric(&snapshotter, "restate.rocksdb.estimate_num_keys", &labels),
+        Some(DebugValue::Gauge(_))
+    ));
+}
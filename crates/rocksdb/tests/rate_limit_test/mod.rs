@@ -0,0 +1,40 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use restate_types::config::Configuration;
+
+use crate::{flush, incompressible, init_manager_with_live_config, open_db, put, update_config};
+
+const DATA_SIZE: usize = 2 * 1024 * 1024;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn flushes_are_throttled_by_the_rate_limit() {
+    let mut config = Configuration::default();
+    config.common.rocksdb_rate_limit_bytes_per_sec = NonZeroUsize::new(DATA_SIZE / 2);
+    let _manager = init_manager_with_live_config(config);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", dir.path());
+
+    // writing the file takes about two seconds at the configured rate
+    put(&db, b"a", &incompressible(DATA_SIZE)).await;
+    let start = Instant::now();
+    flush(&db);
+    assert!(start.elapsed() >= Duration::from_secs(1));
+
+    // the limit is lifted at runtime
+    update_config(|config| config.common.rocksdb_rate_limit_bytes_per_sec = None).await;
+    put(&db, b"b", &incompressible(DATA_SIZE)).await;
+    let start = Instant::now();
+    flush(&db);
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
@@ -0,0 +1,45 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_rocksdb::BackupService;
+use restate_types::config::CommonOptions;
+
+use crate::{get, init_manager, open_db, put};
+
+#[tokio::test]
+async fn empty_database_is_restored_from_latest_backup() {
+    let dir = tempfile::tempdir().unwrap();
+    let backups = format!("file://{}", dir.path().join("backups").display());
+    let mut opts = CommonOptions::default();
+    opts.rocksdb_restore_source = Some(format!("{}/n1", backups));
+    let manager = init_manager(opts.clone());
+
+    // nothing to restore from yet, the database is created empty
+    let db = open_db("db", &dir.path().join("original"));
+    assert_eq!(get(&db, b"a"), None);
+    put(&db, b"a", b"1").await;
+    BackupService::from_destination(&backups, 1)
+        .unwrap()
+        .for_node("n1")
+        .backup_db(&db, dir.path().join("staging"))
+        .await
+        .expect("backup succeeds");
+    drop(db);
+
+    // the node is restarted with an empty data directory
+    drop(manager);
+    let _manager = init_manager(opts);
+    let replacement = open_db("db", &dir.path().join("replacement"));
+    assert_eq!(get(&replacement, b"a"), Some(b"1".to_vec()));
+
+    // only databases with a backup are restored
+    let other = open_db("other", &dir.path().join("other"));
+    assert_eq!(get(&other, b"a"), None);
+}
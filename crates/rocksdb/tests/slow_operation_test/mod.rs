@@ -0,0 +1,88 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use restate_rocksdb::{CfName, Priority};
+use restate_types::config::Configuration;
+
+use crate::{flush, init_manager_with_live_config, open_db, put, update_config, CF};
+
+/// The log lines written by the process-wide subscriber installed by [`capture_logs`].
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    fn take(&self) -> String {
+        String::from_utf8(std::mem::take(&mut *self.0.lock().unwrap())).unwrap()
+    }
+}
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Captures the logs of all threads, the storage tasks don't run on the test's thread.
+fn capture_logs() -> CapturedLogs {
+    let logs = CapturedLogs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer({
+            let logs = logs.clone();
+            move || logs.clone()
+        })
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).expect("subscriber is installed once");
+    logs
+}
+
+#[tokio::test]
+async fn operations_slower_than_the_stall_threshold_are_logged() {
+    let logs = capture_logs();
+    let mut config = Configuration::default();
+    // every operation is slow
+    config.common.rocksdb_write_stall_threshold = Duration::ZERO.into();
+    let _manager = init_manager_with_live_config(config);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", dir.path());
+    put(&db, b"a", b"1").await;
+    flush(&db);
+    logs.take();
+
+    let keys = vec![b"a".to_vec()];
+    db.multi_get_cf(CfName::new(CF), keys.clone(), Priority::High)
+        .await
+        .unwrap();
+    let slow = logs.take();
+    assert!(slow.contains("Slow rocksdb operation"), "{slow}");
+    assert!(slow.contains("op=\"multi-get\""), "{slow}");
+    // the perf context tells where the time went
+    assert!(slow.contains("block_read_count"), "{slow}");
+
+    update_config(|config| {
+        config.common.rocksdb_write_stall_threshold = Duration::from_secs(3600).into()
+    })
+    .await;
+    logs.take();
+    db.multi_get_cf(CfName::new(CF), keys, Priority::High)
+        .await
+        .unwrap();
+    let slow = logs.take();
+    assert!(!slow.contains("Slow rocksdb operation"), "{slow}");
+}
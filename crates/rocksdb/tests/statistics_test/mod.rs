@@ -0,0 +1,90 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use metrics_util::debugging::DebugValue;
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, RocksDbOptionsBuilder};
+
+use crate::{
+    db_spec, flush, init_manager, install_recorder, metric, open_db, open_db_with_spec, put,
+    wait_until, CF,
+};
+
+#[tokio::test]
+async fn statistics_are_published_per_database_and_column_family() {
+    let snapshotter = install_recorder();
+    let mut opts = CommonOptions::default();
+    opts.rocksdb_properties_poll_interval = Duration::from_millis(10).into();
+    let _manager = init_manager(opts);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("stats", &dir.path().join("stats"));
+    let without_stats = open_db_with_spec(
+        db_spec("without-stats", &dir.path().join("without-stats")).build_as_db(),
+        Constant::new(
+            RocksDbOptionsBuilder::default()
+                .rocksdb_disable_statistics(Some(true))
+                .build()
+                .unwrap(),
+        ),
+    );
+
+    put(&db, b"a", b"1").await;
+    put(&without_stats, b"a", b"1").await;
+
+    // tickers are counters
+    wait_until(|| {
+        matches!(
+            metric(&snapshotter, "restate.rocksdb.write.self.total", &[("db", "stats")]),
+            Some(DebugValue::Counter(writes)) if writes > 0
+        )
+    })
+    .await;
+    // the tickers that the node renders itself aren't exported twice
+    assert!(metric(
+        &snapshotter,
+        "restate.rocksdb.bytes.written.total",
+        &[("db", "stats")]
+    )
+    .is_none());
+
+    // histograms are gauges per quantile, plus their sum and count
+    flush(&db);
+    wait_until(|| {
+        matches!(
+            metric(&snapshotter, "restate.rocksdb.write.raw.block.seconds.count", &[("db", "stats")]),
+            Some(DebugValue::Gauge(count)) if count.into_inner() >= 1.0
+        )
+    })
+    .await;
+    assert!(metric(
+        &snapshotter,
+        "restate.rocksdb.write.raw.block.seconds",
+        &[("db", "stats"), ("quantile", "0.5")],
+    )
+    .is_some());
+
+    // compaction stats are published per column family, the flush counts as a compaction
+    wait_until(|| {
+        matches!(
+            metric(&snapshotter, "restate.rocksdb.cf_compactions.total", &[("db", "stats"), ("cf", CF)]),
+            Some(DebugValue::Counter(compactions)) if compactions >= 1
+        )
+    })
+    .await;
+
+    assert!(metric(
+        &snapshotter,
+        "restate.rocksdb.write.self.total",
+        &[("db", "without-stats")]
+    )
+    .is_none());
+}
@@ -0,0 +1,123 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use restate_rocksdb::{
+    CfName, CfPrefixPattern, DbName, DbSpecBuilder, IoMode, KeyRange, Priority, RocksDb,
+};
+use restate_types::arc_util::Constant;
+use restate_types::config::{CommonOptions, RocksDbOptions};
+
+use crate::{init_manager, open_db_with_spec, put_cf, wait_until};
+
+const QUEUE_CF: &str = "queue";
+const DATA_CF: &str = "data";
+
+async fn delete_cf(db: &RocksDb, cf: &str, key: &[u8]) {
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.delete_cf(&db.inner().cf_handle(cf).unwrap(), key);
+    db.write_batch(
+        Priority::High,
+        IoMode::Default,
+        rocksdb::WriteOptions::default(),
+        batch,
+    )
+    .await
+    .unwrap();
+}
+
+fn flush_cf(db: &RocksDb, cf: &str) {
+    db.inner()
+        .flush_memtables(&[CfName::new(cf)], true)
+        .unwrap();
+}
+
+fn num_sst_files(db: &RocksDb, cf: &str) -> u64 {
+    (0..7)
+        .map(|level| {
+            db.inner()
+                .get_property_int_cf(
+                    &CfName::new(cf),
+                    &format!("rocksdb.num-files-at-level{level}"),
+                )
+                .unwrap()
+                .unwrap()
+        })
+        .sum()
+}
+
+#[tokio::test]
+async fn tombstone_heavy_column_families_are_compacted_periodically() {
+    let mut opts = CommonOptions::default();
+    opts.rocksdb_properties_poll_interval = Duration::from_millis(10).into();
+    opts.rocksdb_tombstone_compaction_interval = Some(Duration::from_millis(50).into());
+    let _manager = init_manager(opts);
+    let dir = tempfile::tempdir().unwrap();
+    let spec = DbSpecBuilder::new(
+        DbName::new("db"),
+        dir.path().to_path_buf(),
+        rocksdb::Options::default(),
+    )
+    .add_cf_pattern(CfPrefixPattern::ANY, |_, opts| opts)
+    .add_tombstone_heavy_cf(CfPrefixPattern::new(QUEUE_CF))
+    .ensure_column_families(vec![CfName::new(QUEUE_CF), CfName::new(DATA_CF)])
+    .build_as_db();
+    let db = open_db_with_spec(spec, Constant::new(RocksDbOptions::default()));
+
+    for cf in [QUEUE_CF, DATA_CF] {
+        put_cf(&db, cf, b"a", b"1").await;
+        flush_cf(&db, cf);
+        delete_cf(&db, cf, b"a").await;
+        flush_cf(&db, cf);
+    }
+
+    // the compaction drops the deleted entry together with its tombstone
+    wait_until(|| num_sst_files(&db, QUEUE_CF) == 0).await;
+    assert_eq!(num_sst_files(&db, DATA_CF), 2);
+}
+
+#[tokio::test]
+async fn only_the_tombstone_heavy_key_range_is_compacted() {
+    let mut opts = CommonOptions::default();
+    opts.rocksdb_properties_poll_interval = Duration::from_millis(10).into();
+    opts.rocksdb_tombstone_compaction_interval = Some(Duration::from_millis(50).into());
+    let _manager = init_manager(opts);
+    let dir = tempfile::tempdir().unwrap();
+    let spec = DbSpecBuilder::new(
+        DbName::new("db"),
+        dir.path().to_path_buf(),
+        rocksdb::Options::default(),
+    )
+    .add_cf_pattern(CfPrefixPattern::ANY, |_, opts| opts)
+    .add_tombstone_heavy_key_range(
+        CfPrefixPattern::new(DATA_CF),
+        KeyRange {
+            from: Some(b"q".to_vec()),
+            to: Some(b"r".to_vec()),
+        },
+    )
+    .ensure_column_families(vec![CfName::new(DATA_CF)])
+    .build_as_db();
+    let db = open_db_with_spec(spec, Constant::new(RocksDbOptions::default()));
+
+    // one pair of files outside of the range and one inside of it
+    for key in [b"z/1", b"q/1"] {
+        put_cf(&db, DATA_CF, key, b"1").await;
+        flush_cf(&db, DATA_CF);
+        delete_cf(&db, DATA_CF, key).await;
+        flush_cf(&db, DATA_CF);
+    }
+
+    wait_until(|| num_sst_files(&db, DATA_CF) == 2).await;
+    // the files outside of the range are left alone
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(num_sst_files(&db, DATA_CF), 2);
+}
@@ -0,0 +1,99 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use futures::TryStreamExt;
+
+use restate_rocksdb::{
+    replay_trace, CfName, Priority, ReplayOptions, TraceError, TraceOp, TraceRecord,
+};
+use restate_types::config::CommonOptions;
+
+use crate::{get, init_manager, open_db, put, CF};
+
+#[tokio::test]
+async fn traced_workload_replays_against_a_fresh_db() {
+    let _manager = init_manager(CommonOptions::default());
+    let traces = tempfile::tempdir().unwrap();
+    let trace_path = traces.path().join("trace.jsonl");
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("traced", dir.path());
+
+    assert!(db.end_trace().unwrap().is_none());
+    db.start_trace(&trace_path).unwrap();
+    assert!(matches!(
+        db.start_trace(&traces.path().join("other.jsonl")),
+        Err(TraceError::AlreadyTracing(_))
+    ));
+    put(&db, b"a", b"1").await;
+    put(&db, b"b", b"2").await;
+    db.delete_range(
+        Priority::High,
+        CfName::new(CF),
+        b"a".to_vec(),
+        b"b".to_vec(),
+        rocksdb::WriteOptions::default(),
+    )
+    .await
+    .unwrap();
+    db.multi_get_cf(CfName::new(CF), vec![b"b".to_vec()], Priority::Low)
+        .await
+        .unwrap();
+    let scanned: Vec<_> = db
+        .iter_stream(CfName::new(CF), b"a".to_vec().., Priority::Low)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(scanned.len(), 1);
+    assert_eq!(db.end_trace().unwrap(), Some(trace_path.clone()));
+    // operations after the end of the trace aren't recorded
+    put(&db, b"c", b"3").await;
+
+    let records: Vec<TraceRecord> = std::fs::read_to_string(&trace_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 5);
+    assert!(matches!(records[0].op, TraceOp::WriteBatch { .. }));
+    assert!(matches!(records[1].op, TraceOp::WriteBatch { .. }));
+    assert!(matches!(records[2].op, TraceOp::DeleteRange { .. }));
+    assert!(matches!(records[3].op, TraceOp::MultiGet { .. }));
+    assert!(matches!(records[4].op, TraceOp::Scan { .. }));
+    assert!(records
+        .windows(2)
+        .all(|pair| pair[0].offset_micros <= pair[1].offset_micros));
+
+    // a fresh database with the same column families has the same column family ids
+    let replay_dir = tempfile::tempdir().unwrap();
+    let replayed = open_db("replayed", replay_dir.path());
+    let summary = replay_trace(&replayed, &trace_path, ReplayOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(summary.operations, 5);
+    assert_eq!(summary.failed_operations, 0);
+    assert_eq!(get(&replayed, b"a"), None);
+    assert_eq!(get(&replayed, b"b"), Some(b"2".to_vec()));
+    assert_eq!(get(&replayed, b"c"), None);
+}
+
+#[tokio::test]
+async fn malformed_trace_fails_the_replay() {
+    let _manager = init_manager(CommonOptions::default());
+    let traces = tempfile::tempdir().unwrap();
+    let trace_path = traces.path().join("trace.jsonl");
+    std::fs::write(&trace_path, "\nnot a record\n").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", dir.path());
+
+    assert!(matches!(
+        replay_trace(&db, &trace_path, ReplayOptions::default()).await,
+        Err(TraceError::Malformed(2, _))
+    ));
+}
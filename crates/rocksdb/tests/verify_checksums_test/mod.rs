@@ -0,0 +1,69 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+use restate_rocksdb::RocksDbManager;
+use restate_types::config::CommonOptions;
+
+use crate::{flush, incompressible, init_manager, open_db, put, CF};
+
+/// The SST files of the database in the order they were created.
+fn sst_files(path: &Path) -> Vec<String> {
+    let mut files: Vec<_> = std::fs::read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|file| file.ends_with(".sst"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Overwrites a few bytes of the first data block of the file.
+fn corrupt(file: &Path) {
+    let mut file = std::fs::OpenOptions::new().write(true).open(file).unwrap();
+    file.seek(SeekFrom::Start(16)).unwrap();
+    file.write_all(&[0xff; 8]).unwrap();
+    file.sync_all().unwrap();
+}
+
+#[tokio::test]
+async fn corrupted_files_are_reported() {
+    let _manager = init_manager(CommonOptions::default());
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("db", dir.path());
+
+    put(&db, b"a", &incompressible(16 * 1024)).await;
+    flush(&db);
+    put(&db, b"x", &incompressible(16 * 1024)).await;
+    flush(&db);
+    assert!(db.verify_checksums(..).await.unwrap().is_empty());
+
+    let files = sst_files(dir.path());
+    assert_eq!(files.len(), 2);
+    corrupt(&dir.path().join(&files[0]));
+
+    let corruptions = db.verify_checksums(..).await.unwrap();
+    assert_eq!(corruptions.len(), 1);
+    assert_eq!(corruptions[0].db.to_string(), "db");
+    assert_eq!(corruptions[0].cf.to_string(), CF);
+    assert_eq!(corruptions[0].file, files[0]);
+    // the corrupted file is outside of the verified range
+    assert!(db
+        .verify_checksums(b"b".to_vec()..)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let corruptions = RocksDbManager::get().verify_all().await.unwrap();
+    assert_eq!(corruptions.len(), 1);
+    assert_eq!(corruptions[0].file, files[0]);
+}
@@ -0,0 +1,71 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use metrics_util::debugging::DebugValue;
+
+use restate_rocksdb::{CfName, Priority};
+use restate_types::config::{Configuration, StorageWorkStealing};
+
+use crate::{
+    init_manager_with_live_config, install_recorder, metric, open_db_with_held_up_compactions,
+    release_compactions, start_held_up_compaction, update_config, CF,
+};
+
+#[tokio::test]
+async fn low_priority_tasks_steal_idle_high_priority_threads() {
+    let snapshotter = install_recorder();
+    let mut config = Configuration::default();
+    config.common.storage_low_priority_bg_threads = NonZeroUsize::new(1);
+    config.common.storage_high_priority_bg_threads = NonZeroUsize::new(1);
+    config.common.storage_work_stealing = StorageWorkStealing::HighPriority;
+    let _manager = init_manager_with_live_config(config);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db_with_held_up_compactions("db", dir.path());
+    // the compaction occupies the only low priority thread until it's released
+    let compaction = start_held_up_compaction(&db).await;
+
+    let keys = vec![b"held-up".to_vec()];
+    // only high priority tasks may steal, the read waits for the low priority thread
+    let mut waiting = tokio::spawn({
+        let db = db.clone();
+        let keys = keys.clone();
+        async move { db.multi_get_cf(CfName::new(CF), keys, Priority::Low).await }
+    });
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), &mut waiting)
+            .await
+            .is_err()
+    );
+
+    update_config(|config| config.common.storage_work_stealing = StorageWorkStealing::Both).await;
+    let values = tokio::time::timeout(
+        Duration::from_secs(5),
+        db.multi_get_cf(CfName::new(CF), keys, Priority::Low),
+    )
+    .await
+    .expect("read runs on the idle high priority thread")
+    .unwrap();
+    assert_eq!(values, vec![Some(b"2".as_slice().into())]);
+    assert!(matches!(
+        metric(
+            &snapshotter,
+            "restate.rocksdb_manager.bg_task_stolen.total",
+            &[("priority", "low")]
+        ),
+        Some(DebugValue::Counter(stolen)) if stolen >= 1
+    ));
+
+    release_compactions();
+    compaction.await.unwrap().unwrap();
+    waiting.await.unwrap().unwrap();
+}
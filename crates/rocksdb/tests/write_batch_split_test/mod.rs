@@ -0,0 +1,109 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::NonZeroUsize;
+
+use metrics_util::debugging::DebugValue;
+
+use restate_rocksdb::{IoMode, Priority, RocksDb};
+use restate_types::config::Configuration;
+
+use crate::{
+    get, init_manager_with_live_config, install_recorder, metric, open_db, update_config, CF,
+};
+
+const NUM_KEYS: u32 = 100;
+
+/// Builds a batch of about 10KiB that overwrites all keys with `value` and deletes the key 0.
+fn large_batch(db: &RocksDb, value: u8) -> rocksdb::WriteBatch {
+    let cf = db.inner().cf_handle(CF).unwrap();
+    let mut batch = rocksdb::WriteBatch::default();
+    for i in 0..NUM_KEYS {
+        batch.put_cf(&cf, i.to_be_bytes(), [value; 100]);
+    }
+    batch.delete_cf(&cf, 0u32.to_be_bytes());
+    batch
+}
+
+/// Writes a large batch that may be split.
+async fn write_batch(db: &RocksDb, value: u8) {
+    db.write_batch_allow_split(
+        Priority::High,
+        IoMode::Default,
+        rocksdb::WriteOptions::default(),
+        large_batch(db, value),
+    )
+    .await
+    .unwrap();
+}
+
+fn assert_written(db: &RocksDb, value: u8) {
+    assert_eq!(get(db, &0u32.to_be_bytes()), None);
+    for i in 1..NUM_KEYS {
+        assert_eq!(get(db, &i.to_be_bytes()), Some(vec![value; 100]));
+    }
+}
+
+fn split_batches(snapshotter: &metrics_util::debugging::Snapshotter) -> u64 {
+    match metric(
+        snapshotter,
+        "restate.rocksdb.split_write_batches.total",
+        &[("db", "split")],
+    ) {
+        Some(DebugValue::Counter(splits)) => splits,
+        _ => 0,
+    }
+}
+
+#[tokio::test]
+async fn oversized_batches_are_split() {
+    let snapshotter = install_recorder();
+    let mut config = Configuration::default();
+    config.common.rocksdb_max_write_batch_size = NonZeroUsize::new(4 * 1024);
+    let _manager = init_manager_with_live_config(config);
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db("split", dir.path());
+
+    write_batch(&db, 1).await;
+    assert_written(&db, 1);
+    assert_eq!(split_batches(&snapshotter), 1);
+
+    // batches within the limit are written as they are
+    let cf = db.inner().cf_handle(CF).unwrap();
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.put_cf(&cf, b"small", b"1");
+    db.write_batch_allow_split(
+        Priority::High,
+        IoMode::Default,
+        rocksdb::WriteOptions::default(),
+        batch,
+    )
+    .await
+    .unwrap();
+    assert_eq!(split_batches(&snapshotter), 1);
+
+    // batches of writes that don't opt in are never split
+    db.write_batch(
+        Priority::High,
+        IoMode::Default,
+        rocksdb::WriteOptions::default(),
+        large_batch(&db, 3),
+    )
+    .await
+    .unwrap();
+    assert_written(&db, 3);
+    assert_eq!(split_batches(&snapshotter), 1);
+
+    // the limit is removed at runtime
+    update_config(|config| config.common.rocksdb_max_write_batch_size = None).await;
+    write_batch(&db, 2).await;
+    assert_written(&db, 2);
+    assert_eq!(split_batches(&snapshotter), 1);
+}
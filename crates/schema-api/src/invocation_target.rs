@@ -13,6 +13,7 @@ use bytestring::ByteString;
 use itertools::Itertools;
 use restate_types::invocation::InvocationTargetType;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use std::{cmp, fmt};
 
@@ -69,6 +70,10 @@ pub enum InputValidationError {
     BadConfiguration,
     #[error("Content-type '{0}' does not match '{1}'")]
     ContentTypeNotMatching(String, InputContentType),
+    #[error("Body is not a valid JSON value: {0}")]
+    InvalidJson(String),
+    #[error("Body does not match the JSON schema of the handler input: {0}")]
+    JsonSchemaMismatch(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -143,7 +148,9 @@ pub enum InputValidationRule {
     JsonValue {
         // Can use wildcards
         content_type: InputContentType,
-        // TODO add json schema.
+        // If set, the json value must match it
+        #[cfg_attr(feature = "serde", serde(default))]
+        schema: Option<InputJsonSchema>,
     },
 }
 
@@ -154,7 +161,7 @@ impl fmt::Display for InputValidationRule {
             InputValidationRule::ContentType { content_type } => {
                 write!(f, "value of content-type '{}'", content_type)
             }
-            InputValidationRule::JsonValue { content_type } => {
+            InputValidationRule::JsonValue { content_type, .. } => {
                 write!(f, "JSON value of content-type '{}'", content_type)
             }
         }
@@ -181,7 +188,10 @@ impl InputValidationRule {
                 }
                 content_type.validate(input_content_type.unwrap())?;
             }
-            InputValidationRule::JsonValue { content_type } => {
+            InputValidationRule::JsonValue {
+                content_type,
+                schema,
+            } => {
                 if input_content_type.is_none() {
                     return Err(InputValidationError::EmptyContentType);
                 }
@@ -192,13 +202,85 @@ impl InputValidationRule {
                     return Err(InputValidationError::EmptyValue);
                 }
 
-                // TODO add additional json validation.
+                if let Some(schema) = schema {
+                    let value = serde_json::from_slice(buf)
+                        .map_err(|e| InputValidationError::InvalidJson(e.to_string()))?;
+                    schema.validate(&value)?;
+                }
             }
         }
         Ok(())
     }
 }
 
+/// JSON schema of the handler input, as provided by the deployment at discovery time.
+#[derive(Clone)]
+pub struct InputJsonSchema {
+    schema: serde_json::Value,
+    compiled: Arc<jsonschema::JSONSchema>,
+}
+
+impl InputJsonSchema {
+    pub fn new(schema: serde_json::Value) -> Result<Self, BadInputJsonSchema> {
+        let compiled = jsonschema::JSONSchema::compile(&schema)
+            .map_err(|e| BadInputJsonSchema(e.to_string()))?;
+        Ok(Self {
+            schema,
+            compiled: Arc::new(compiled),
+        })
+    }
+
+    pub fn schema(&self) -> &serde_json::Value {
+        &self.schema
+    }
+
+    fn validate(&self, value: &serde_json::Value) -> Result<(), InputValidationError> {
+        self.compiled.validate(value).map_err(|errors| {
+            InputValidationError::JsonSchemaMismatch(
+                errors
+                    .map(|e| format!("{} at '{}'", e, e.instance_path))
+                    .join(", "),
+            )
+        })
+    }
+}
+
+impl fmt::Debug for InputJsonSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InputJsonSchema")
+            .field(&self.schema)
+            .finish()
+    }
+}
+
+impl PartialEq for InputJsonSchema {
+    fn eq(&self, other: &Self) -> bool {
+        self.schema == other.schema
+    }
+}
+
+impl Eq for InputJsonSchema {}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for InputJsonSchema {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Only the schema is stored, it's compiled again when loading it
+        self.schema.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for InputJsonSchema {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        InputJsonSchema::new(serde_json::Value::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("can't compile the JSON schema: {0}")]
+pub struct BadInputJsonSchema(String);
+
 /// Describes a content type in the same format of the [`Accept` header](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Accept).
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -563,6 +645,7 @@ mod tests {
                 InputValidationRule::NoBodyAndContentType,
                 InputValidationRule::JsonValue {
                     content_type: InputContentType::Any,
+                    schema: None,
                 },
             ],
         };
@@ -584,6 +667,7 @@ mod tests {
                     "application".into(),
                     "restate+json".into(),
                 ),
+                schema: None,
             }],
         };
 
@@ -601,6 +685,44 @@ mod tests {
         assert_input_not_valid!(input_rules, Some("application/restate+json"), Bytes::new());
     }
 
+    #[test]
+    fn validate_json_schema() {
+        let input_rules = InputRules {
+            input_validation_rules: vec![InputValidationRule::JsonValue {
+                content_type: InputContentType::Any,
+                schema: Some(
+                    InputJsonSchema::new(serde_json::json!({
+                        "type": "object",
+                        "properties": {"name": {"type": "string"}},
+                        "required": ["name"]
+                    }))
+                    .unwrap(),
+                ),
+            }],
+        };
+
+        assert_input_valid!(
+            input_rules,
+            Some("application/json"),
+            Bytes::from_static(br#"{"name": "Till"}"#)
+        );
+        assert_input_not_valid!(
+            input_rules,
+            Some("application/json"),
+            Bytes::from_static(br#"{"name": 1}"#)
+        );
+        assert_input_not_valid!(
+            input_rules,
+            Some("application/json"),
+            Bytes::from_static(b"{}")
+        );
+        assert_input_not_valid!(
+            input_rules,
+            Some("application/json"),
+            Bytes::from_static(b"{name")
+        );
+    }
+
     #[test]
     fn infer_content_type_default() {
         let input_rules = OutputRules::default();
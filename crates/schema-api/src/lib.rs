@@ -12,16 +12,124 @@
 
 pub const MAX_SERVICE_PROTOCOL_VERSION_VALUE: i32 = i32::MAX;
 
+pub mod listing {
+    /// Options of the paginated listings. Items are listed in ascending order of their name.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct ListOptions {
+        /// List only the items whose name starts with this prefix.
+        pub name_prefix: Option<String>,
+        /// List only the items after this name, see [`Page::next`].
+        pub after: Option<String>,
+        /// Maximum number of listed items.
+        pub limit: Option<usize>,
+        /// List retired items as well, that is draining deployments.
+        pub include_retired: bool,
+    }
+
+    impl ListOptions {
+        pub fn matches(&self, name: &str) -> bool {
+            self.name_prefix
+                .as_ref()
+                .map_or(true, |prefix| name.starts_with(prefix.as_str()))
+                && self
+                    .after
+                    .as_ref()
+                    .map_or(true, |after| name > after.as_str())
+        }
+
+        /// Filters, sorts and truncates the items to the page selected by these options.
+        pub fn paginate<T>(
+            &self,
+            items: impl IntoIterator<Item = T>,
+            name: impl Fn(&T) -> String,
+        ) -> Page<T> {
+            let mut items: Vec<_> = items
+                .into_iter()
+                .map(|item| (name(&item), item))
+                .filter(|(name, _)| self.matches(name))
+                .collect();
+            items.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            let next = match self.limit {
+                Some(limit) if items.len() > limit => {
+                    items.truncate(limit);
+                    items.last().map(|(name, _)| name.clone())
+                }
+                _ => None,
+            };
+
+            Page {
+                items: items.into_iter().map(|(_, item)| item).collect(),
+                next,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Page<T> {
+        pub items: Vec<T>,
+        /// Name of the last listed item, if more items are left. Use it as [`ListOptions::after`]
+        /// to list the next page.
+        pub next: Option<String>,
+    }
+
+    impl<T> Page<T> {
+        pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+            Page {
+                items: self.items.into_iter().map(f).collect(),
+                next: self.next,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn paginate() {
+            let names = ["greeter", "counter", "greeter.v2", "checkout"];
+            let options = ListOptions {
+                limit: Some(2),
+                ..Default::default()
+            };
+
+            let page = options.paginate(names, |name| name.to_string());
+            assert_eq!(page.items, vec!["checkout", "counter"]);
+            assert_eq!(page.next.as_deref(), Some("counter"));
+
+            let page = ListOptions {
+                after: page.next,
+                ..options.clone()
+            }
+            .paginate(names, |name| name.to_string());
+            assert_eq!(page.items, vec!["greeter", "greeter.v2"]);
+            assert_eq!(page.next, None);
+
+            let page = ListOptions {
+                name_prefix: Some("greeter".to_owned()),
+                limit: None,
+                ..options
+            }
+            .paginate(names, |name| name.to_string());
+            assert_eq!(page.items, vec!["greeter", "greeter.v2"]);
+            assert_eq!(page.next, None);
+        }
+    }
+}
+
 #[cfg(feature = "invocation_target")]
 pub mod invocation_target;
 
 #[cfg(feature = "deployment")]
 pub mod deployment {
+    use crate::listing::{ListOptions, Page};
     use crate::service::ServiceMetadata;
     use bytestring::ByteString;
     use http::header::{HeaderName, HeaderValue};
     use http::Uri;
-    use restate_types::identifiers::{DeploymentId, LambdaARN, ServiceRevision};
+    use restate_types::identifiers::{DeploymentId, InvocationId, LambdaARN, ServiceRevision};
+    use restate_types::retries::RetryPolicy;
     use restate_types::time::MillisSinceEpoch;
     use std::collections::HashMap;
     use std::fmt;
@@ -36,6 +144,76 @@ pub mod deployment {
         BidiStream,
     }
 
+    /// Content encoding of the message streams exchanged with a deployment, negotiated on
+    /// discovery.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub enum ContentEncoding {
+        Gzip,
+        Zstd,
+    }
+
+    impl ContentEncoding {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                ContentEncoding::Gzip => "gzip",
+                ContentEncoding::Zstd => "zstd",
+            }
+        }
+
+        /// Picks the preferred content encoding among the ones accepted by the deployment, as
+        /// listed in an `accept-encoding` header value.
+        pub fn negotiate(accept_encoding: &str) -> Option<Self> {
+            let accepted: Vec<_> = accept_encoding
+                .split(',')
+                .filter_map(|coding| {
+                    let mut params = coding.split(';').map(str::trim);
+                    let name = params.next()?;
+                    let rejected = params.any(|param| {
+                        param
+                            .strip_prefix("q=")
+                            .and_then(|q| q.parse::<f32>().ok())
+                            .is_some_and(|q| q == 0.0)
+                    });
+                    (!rejected).then_some(name)
+                })
+                .collect();
+
+            [ContentEncoding::Zstd, ContentEncoding::Gzip]
+                .into_iter()
+                .find(|encoding| {
+                    accepted
+                        .iter()
+                        .any(|name| name.eq_ignore_ascii_case(encoding.as_str()))
+                })
+        }
+    }
+
+    impl Display for ContentEncoding {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            f.write_str(self.as_str())
+        }
+    }
+
+    impl std::str::FromStr for ContentEncoding {
+        type Err = UnknownContentEncoding;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            if s.eq_ignore_ascii_case(ContentEncoding::Gzip.as_str()) {
+                Ok(ContentEncoding::Gzip)
+            } else if s.eq_ignore_ascii_case(ContentEncoding::Zstd.as_str()) {
+                Ok(ContentEncoding::Zstd)
+            } else {
+                Err(UnknownContentEncoding(s.to_owned()))
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+    #[error("unknown content encoding '{0}'")]
+    pub struct UnknownContentEncoding(pub String);
+
     #[derive(Debug, Clone, Default)]
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
@@ -48,11 +226,24 @@ pub mod deployment {
         )]
         #[cfg_attr(feature = "serde_schema", schemars(with = "HashMap<String, String>"))]
         pub additional_headers: HashMap<HeaderName, HeaderValue>,
+        /// Retry policy for the invocations of the deployment's services. If unset, the retry
+        /// policy of the invoker applies.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        pub retry_policy: Option<RetryPolicy>,
     }
 
     impl DeliveryOptions {
-        pub fn new(additional_headers: HashMap<HeaderName, HeaderValue>) -> Self {
-            Self { additional_headers }
+        pub fn new(
+            additional_headers: HashMap<HeaderName, HeaderValue>,
+            retry_policy: Option<RetryPolicy>,
+        ) -> Self {
+            Self {
+                additional_headers,
+                retry_policy,
+            }
         }
     }
 
@@ -88,6 +279,12 @@ pub mod deployment {
             #[cfg_attr(feature = "serde_schema", schemars(with = "String"))]
             address: Uri,
             protocol_type: ProtocolType,
+            /// Content encoding of the request and response streams, if any.
+            #[cfg_attr(
+                feature = "serde",
+                serde(default, skip_serializing_if = "Option::is_none")
+            )]
+            content_encoding: Option<ContentEncoding>,
         },
         Lambda {
             arn: LambdaARN,
@@ -123,6 +320,7 @@ pub mod deployment {
         pub fn new_http(
             address: Uri,
             protocol_type: ProtocolType,
+            content_encoding: Option<ContentEncoding>,
             delivery_options: DeliveryOptions,
             supported_protocol_versions: RangeInclusive<i32>,
         ) -> Self {
@@ -130,6 +328,7 @@ pub mod deployment {
                 ty: DeploymentType::Http {
                     address,
                     protocol_type,
+                    content_encoding,
                 },
                 delivery_options,
                 created_at: MillisSinceEpoch::now(),
@@ -174,6 +373,35 @@ pub mod deployment {
         }
     }
 
+    /// Lifecycle of a deployment. Registering a new deployment of a service routes the new
+    /// invocations of the service to it, while the invocations pinned to the previous deployment
+    /// keep running there. Once a deployment doesn't serve the latest revision of any service, it
+    /// drains, and it can be retired when no invocations are pinned to it anymore.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub enum DeploymentStatus {
+        /// Serves the latest revision of at least one service, or receives part of its traffic.
+        Active,
+        /// Serves only the invocations pinned to it.
+        Draining,
+    }
+
+    /// Record of a removed deployment, kept for auditing.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct DeploymentTombstone {
+        pub id: DeploymentId,
+        pub address: String,
+        pub created_at: MillisSinceEpoch,
+        pub removed_at: MillisSinceEpoch,
+        /// Whether the deployment was removed regardless of the services it was serving and of the
+        /// invocations pinned to it.
+        pub forced: bool,
+        /// Services removed along with the deployment, as it was serving their latest revision.
+        pub removed_services: Vec<String>,
+    }
+
     pub trait DeploymentResolver {
         fn resolve_latest_deployment_for_service(
             &self,
@@ -182,12 +410,41 @@ pub mod deployment {
 
         fn get_deployment(&self, deployment_id: &DeploymentId) -> Option<Deployment>;
 
+        /// Resolves the deployment receiving the given invocation of the service. When the traffic
+        /// of the service is split across multiple deployments, the deployment is picked according
+        /// to their weights, and the same invocation always resolves to the same deployment.
+        fn resolve_deployment_for_invocation(
+            &self,
+            service_name: impl AsRef<str>,
+            invocation_id: &InvocationId,
+        ) -> Option<Deployment>;
+
+        fn get_deployment_status(&self, deployment_id: &DeploymentId) -> Option<DeploymentStatus>;
+
         fn get_deployment_and_services(
             &self,
             deployment_id: &DeploymentId,
         ) -> Option<(Deployment, Vec<ServiceMetadata>)>;
 
         fn get_deployments(&self) -> Vec<(Deployment, Vec<(String, ServiceRevision)>)>;
+
+        /// Lists the deployments by id. Draining deployments are listed only if
+        /// [`ListOptions::include_retired`] is set.
+        fn list_deployments_page(
+            &self,
+            options: &ListOptions,
+        ) -> Page<(Deployment, Vec<(String, ServiceRevision)>)> {
+            options.paginate(
+                self.get_deployments()
+                    .into_iter()
+                    .filter(|(deployment, _)| {
+                        options.include_retired
+                            || self.get_deployment_status(&deployment.id)
+                                != Some(DeploymentStatus::Draining)
+                    }),
+                |(deployment, _)| deployment.id.to_string(),
+            )
+        }
     }
 
     #[cfg(feature = "mocks")]
@@ -205,6 +462,7 @@ pub mod deployment {
                 let metadata = DeploymentMetadata::new_http(
                     "http://localhost:9080".parse().unwrap(),
                     ProtocolType::BidiStream,
+                    None,
                     Default::default(),
                     1..=MAX_SERVICE_PROTOCOL_VERSION_VALUE,
                 );
@@ -217,6 +475,7 @@ pub mod deployment {
                 let metadata = DeploymentMetadata::new_http(
                     uri.parse().unwrap(),
                     ProtocolType::BidiStream,
+                    None,
                     Default::default(),
                     1..=MAX_SERVICE_PROTOCOL_VERSION_VALUE,
                 );
@@ -252,6 +511,14 @@ pub mod deployment {
                     .and_then(|deployment_id| self.get_deployment(deployment_id))
             }
 
+            fn resolve_deployment_for_invocation(
+                &self,
+                service_name: impl AsRef<str>,
+                _invocation_id: &InvocationId,
+            ) -> Option<Deployment> {
+                self.resolve_latest_deployment_for_service(service_name)
+            }
+
             fn get_deployment(&self, deployment_id: &DeploymentId) -> Option<Deployment> {
                 self.deployments
                     .get(deployment_id)
@@ -262,6 +529,24 @@ pub mod deployment {
                     })
             }
 
+            fn get_deployment_status(
+                &self,
+                deployment_id: &DeploymentId,
+            ) -> Option<DeploymentStatus> {
+                self.deployments.get(deployment_id)?;
+                Some(
+                    if self
+                        .latest_deployment
+                        .values()
+                        .any(|latest| latest == deployment_id)
+                    {
+                        DeploymentStatus::Active
+                    } else {
+                        DeploymentStatus::Draining
+                    },
+                )
+            }
+
             fn get_deployment_and_services(
                 &self,
                 deployment_id: &DeploymentId,
@@ -300,6 +585,9 @@ pub mod deployment {
 
 #[cfg(feature = "service")]
 pub mod service {
+    use std::num::NonZeroU32;
+
+    use crate::listing::{ListOptions, Page};
     use restate_types::identifiers::{DeploymentId, ServiceRevision};
     use restate_types::invocation::{
         InvocationTargetType, ServiceType, VirtualObjectHandlerType, WorkflowHandlerType,
@@ -345,6 +633,21 @@ pub mod service {
         #[cfg_attr(feature = "serde_schema", schemars(with = "String"))]
         pub idempotency_retention: humantime::Duration,
 
+        /// # Completion retention
+        ///
+        /// The retention duration of the results of completed invocations for this service.
+        /// Workflow methods use the workflow completion retention instead.
+        #[cfg_attr(
+            feature = "serde",
+            serde(
+                with = "serde_with::As::<Option<serde_with::DisplayFromStr>>",
+                skip_serializing_if = "Option::is_none",
+                default
+            )
+        )]
+        #[cfg_attr(feature = "serde_schema", schemars(with = "Option<String>"))]
+        pub completion_retention: Option<humantime::Duration>,
+
         /// # Workflow completion retention
         ///
         /// The retention duration of workflows. Only available on workflow services.
@@ -358,6 +661,28 @@ pub mod service {
         )]
         #[cfg_attr(feature = "serde_schema", schemars(with = "Option<String>"))]
         pub workflow_completion_retention: Option<humantime::Duration>,
+
+        /// # Traffic split
+        ///
+        /// Deployments receiving the new invocations of this service, with their weights.
+        /// If empty, the latest deployment receives all the new invocations.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Vec::is_empty")
+        )]
+        pub traffic_split: Vec<DeploymentWeight>,
+    }
+
+    /// Share of the new invocations of a service sent to a deployment, relative to the other
+    /// deployments of the traffic split.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct DeploymentWeight {
+        /// # Deployment ID
+        pub deployment_id: DeploymentId,
+        /// # Weight
+        pub weight: u32,
     }
 
     // This type is used only for exposing the handler metadata, and not internally. See [ServiceAndHandlerType].
@@ -403,6 +728,63 @@ pub mod service {
         //
         // If empty, no schema was provided by the user at discovery time.
         pub output_description: String,
+
+        /// # Public
+        ///
+        /// If true, the handler can be invoked through the ingress.
+        /// If false, the handler can be invoked only from another Restate service.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default = "restate_serde_util::default::bool::<true>")
+        )]
+        pub public: bool,
+
+        /// # Idempotency retention
+        ///
+        /// The retention duration of idempotent requests for this handler, overriding the one of
+        /// the service.
+        #[cfg_attr(
+            feature = "serde",
+            serde(
+                with = "serde_with::As::<Option<serde_with::DisplayFromStr>>",
+                skip_serializing_if = "Option::is_none",
+                default
+            )
+        )]
+        #[cfg_attr(feature = "serde_schema", schemars(with = "Option<String>"))]
+        pub idempotency_retention: Option<humantime::Duration>,
+
+        /// # Execution timeout
+        ///
+        /// Maximum duration of an invocation of this handler.
+        #[cfg_attr(
+            feature = "serde",
+            serde(
+                with = "serde_with::As::<Option<serde_with::DisplayFromStr>>",
+                skip_serializing_if = "Option::is_none",
+                default
+            )
+        )]
+        #[cfg_attr(feature = "serde_schema", schemars(with = "Option<String>"))]
+        pub execution_timeout: Option<humantime::Duration>,
+
+        /// # Max concurrent invocations
+        ///
+        /// Maximum number of invocations of this handler running at the same time.
+        #[cfg_attr(
+            feature = "serde",
+            serde(skip_serializing_if = "Option::is_none", default)
+        )]
+        pub max_concurrent_invocations: Option<NonZeroU32>,
+    }
+
+    /// This API resolves the handlers of the services registered by the user.
+    pub trait HandlerMetadataResolver {
+        fn resolve_latest_handler(
+            &self,
+            service_name: impl AsRef<str>,
+            handler_name: impl AsRef<str>,
+        ) -> Option<HandlerMetadata>;
     }
 
     /// This API will return services registered by the user.
@@ -413,6 +795,10 @@ pub mod service {
             -> Option<ServiceType>;
 
         fn list_services(&self) -> Vec<ServiceMetadata>;
+
+        fn list_services_page(&self, options: &ListOptions) -> Page<ServiceMetadata> {
+            options.paginate(self.list_services(), |service| service.name.clone())
+        }
     }
 
     #[cfg(feature = "mocks")]
@@ -466,6 +852,10 @@ pub mod service {
                             ty: HandlerMetadataType::Shared,
                             input_description: "any".to_string(),
                             output_description: "any".to_string(),
+                            public: true,
+                            idempotency_retention: None,
+                            execution_timeout: None,
+                            max_concurrent_invocations: None,
                         })
                         .collect(),
                     ty: ServiceType::Service,
@@ -473,7 +863,9 @@ pub mod service {
                     revision: 0,
                     public: true,
                     idempotency_retention: std::time::Duration::from_secs(60).into(),
+                    completion_retention: None,
                     workflow_completion_retention: None,
+                    traffic_split: vec![],
                 }
             }
 
@@ -490,6 +882,10 @@ pub mod service {
                             ty: HandlerMetadataType::Exclusive,
                             input_description: "any".to_string(),
                             output_description: "any".to_string(),
+                            public: true,
+                            idempotency_retention: None,
+                            execution_timeout: None,
+                            max_concurrent_invocations: None,
                         })
                         .collect(),
                     ty: ServiceType::VirtualObject,
@@ -497,7 +893,9 @@ pub mod service {
                     revision: 0,
                     public: true,
                     idempotency_retention: std::time::Duration::from_secs(60).into(),
+                    completion_retention: None,
                     workflow_completion_retention: None,
+                    traffic_split: vec![],
                 }
             }
         }
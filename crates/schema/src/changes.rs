@@ -0,0 +1,212 @@
+// Copyright (c) 2024 - Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Changes between two versions of the schema information, allowing components to react to them
+//! incrementally instead of reloading the whole schema information.
+
+use super::Schema;
+use restate_types::identifiers::{DeploymentId, ServiceRevision, SubscriptionId};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    /// A new service, or a new revision of an existing service, was registered.
+    ServiceRegistered {
+        service: String,
+        revision: ServiceRevision,
+    },
+    ServiceRemoved {
+        service: String,
+    },
+    ServiceVisibilityChanged {
+        service: String,
+        public: bool,
+    },
+    /// A handler was added by a new revision of its service.
+    HandlerAdded {
+        service: String,
+        handler: String,
+    },
+    /// A handler was removed by a new revision of its service.
+    HandlerRemoved {
+        service: String,
+        handler: String,
+    },
+    HandlerVisibilityChanged {
+        service: String,
+        handler: String,
+        public: bool,
+    },
+    DeploymentRegistered(DeploymentId),
+    DeploymentRemoved(DeploymentId),
+    SubscriptionAdded(SubscriptionId),
+    SubscriptionRemoved(SubscriptionId),
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::ServiceRegistered { service, revision } => {
+                write!(f, "service '{service}' registered with revision {revision}")
+            }
+            SchemaChange::ServiceRemoved { service } => write!(f, "service '{service}' removed"),
+            SchemaChange::ServiceVisibilityChanged { service, public } => {
+                write!(f, "service '{service}' made {}", visibility(*public))
+            }
+            SchemaChange::HandlerAdded { service, handler } => {
+                write!(f, "handler '{service}/{handler}' added")
+            }
+            SchemaChange::HandlerRemoved { service, handler } => {
+                write!(f, "handler '{service}/{handler}' removed")
+            }
+            SchemaChange::HandlerVisibilityChanged {
+                service,
+                handler,
+                public,
+            } => write!(
+                f,
+                "handler '{service}/{handler}' made {}",
+                visibility(*public)
+            ),
+            SchemaChange::DeploymentRegistered(id) => write!(f, "deployment '{id}' registered"),
+            SchemaChange::DeploymentRemoved(id) => write!(f, "deployment '{id}' removed"),
+            SchemaChange::SubscriptionAdded(id) => write!(f, "subscription '{id}' added"),
+            SchemaChange::SubscriptionRemoved(id) => write!(f, "subscription '{id}' removed"),
+        }
+    }
+}
+
+fn visibility(public: bool) -> &'static str {
+    if public {
+        "public"
+    } else {
+        "private"
+    }
+}
+
+impl Schema {
+    /// Returns the changes which turn the previous schema information into this one.
+    pub fn changes_since(&self, previous: &Schema) -> Vec<SchemaChange> {
+        let mut changes = vec![];
+
+        changes.extend(
+            previous
+                .deployments
+                .keys()
+                .filter(|id| !self.deployments.contains_key(id))
+                .map(|id| SchemaChange::DeploymentRemoved(*id)),
+        );
+        changes.extend(
+            self.deployments
+                .keys()
+                .filter(|id| !previous.deployments.contains_key(id))
+                .map(|id| SchemaChange::DeploymentRegistered(*id)),
+        );
+
+        let mut service_names: Vec<_> = previous
+            .services
+            .keys()
+            .chain(self.services.keys())
+            .collect();
+        service_names.sort();
+        service_names.dedup();
+        for service_name in service_names {
+            let (previous_service, service) = match (
+                previous.services.get(service_name),
+                self.services.get(service_name),
+            ) {
+                (Some(_), None) => {
+                    changes.push(SchemaChange::ServiceRemoved {
+                        service: service_name.clone(),
+                    });
+                    continue;
+                }
+                (None, Some(service)) => {
+                    changes.push(SchemaChange::ServiceRegistered {
+                        service: service_name.clone(),
+                        revision: service.revision,
+                    });
+                    continue;
+                }
+                (Some(previous_service), Some(service)) => (previous_service, service),
+                (None, None) => unreachable!("the service name comes from one of the schemas"),
+            };
+
+            if previous_service.revision != service.revision {
+                changes.push(SchemaChange::ServiceRegistered {
+                    service: service_name.clone(),
+                    revision: service.revision,
+                });
+            }
+            if previous_service.location.public != service.location.public {
+                changes.push(SchemaChange::ServiceVisibilityChanged {
+                    service: service_name.clone(),
+                    public: service.location.public,
+                });
+            }
+
+            let mut handler_names: Vec<_> = previous_service
+                .handlers
+                .keys()
+                .chain(service.handlers.keys())
+                .collect();
+            handler_names.sort();
+            handler_names.dedup();
+            for handler_name in handler_names {
+                let (previous_handler, handler) = match (
+                    previous_service.handlers.get(handler_name),
+                    service.handlers.get(handler_name),
+                ) {
+                    (Some(previous_handler), Some(handler)) => (previous_handler, handler),
+                    (Some(_), None) => {
+                        changes.push(SchemaChange::HandlerRemoved {
+                            service: service_name.clone(),
+                            handler: handler_name.clone(),
+                        });
+                        continue;
+                    }
+                    (None, Some(_)) => {
+                        changes.push(SchemaChange::HandlerAdded {
+                            service: service_name.clone(),
+                            handler: handler_name.clone(),
+                        });
+                        continue;
+                    }
+                    (None, None) => unreachable!("the handler name comes from one of the services"),
+                };
+
+                let public = handler.target_meta.public;
+                if previous_handler.target_meta.public != public {
+                    changes.push(SchemaChange::HandlerVisibilityChanged {
+                        service: service_name.clone(),
+                        handler: handler_name.clone(),
+                        public,
+                    });
+                }
+            }
+        }
+
+        changes.extend(
+            previous
+                .subscriptions
+                .keys()
+                .filter(|id| !self.subscriptions.contains_key(id))
+                .map(|id| SchemaChange::SubscriptionRemoved(*id)),
+        );
+        changes.extend(
+            self.subscriptions
+                .keys()
+                .filter(|id| !previous.subscriptions.contains_key(id))
+                .map(|id| SchemaChange::SubscriptionAdded(*id)),
+        );
+
+        changes
+    }
+}
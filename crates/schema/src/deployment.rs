@@ -9,9 +9,12 @@
 // by the Apache License, Version 2.0.
 
 use super::{Schema, UpdateableSchema};
-use restate_schema_api::deployment::{Deployment, DeploymentMetadata, DeploymentResolver};
+use restate_schema_api::deployment::{
+    Deployment, DeploymentMetadata, DeploymentResolver, DeploymentStatus,
+};
+use restate_schema_api::listing::{ListOptions, Page};
 use restate_schema_api::service::ServiceMetadata;
-use restate_types::identifiers::{DeploymentId, ServiceRevision};
+use restate_types::identifiers::{DeploymentId, InvocationId, ServiceRevision};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DeploymentSchemas {
@@ -22,6 +25,18 @@ pub struct DeploymentSchemas {
     pub services: Vec<ServiceMetadata>,
 }
 
+impl Schema {
+    /// Services whose latest revision is served by the given deployment, or whose traffic is
+    /// partly sent to it.
+    pub fn latest_services_of_deployment(&self, deployment_id: &DeploymentId) -> Vec<String> {
+        self.services
+            .iter()
+            .filter(|(_, service)| service.location.is_active(deployment_id))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
 impl DeploymentResolver for Schema {
     fn resolve_latest_deployment_for_service(
         &self,
@@ -36,6 +51,15 @@ impl DeploymentResolver for Schema {
             })
     }
 
+    fn resolve_deployment_for_invocation(
+        &self,
+        service_name: impl AsRef<str>,
+        invocation_id: &InvocationId,
+    ) -> Option<Deployment> {
+        let service = self.services.get(service_name.as_ref())?;
+        self.get_deployment(&service.location.deployment_for_invocation(invocation_id))
+    }
+
     fn get_deployment(&self, deployment_id: &DeploymentId) -> Option<Deployment> {
         self.deployments
             .get(deployment_id)
@@ -45,6 +69,21 @@ impl DeploymentResolver for Schema {
             })
     }
 
+    fn get_deployment_status(&self, deployment_id: &DeploymentId) -> Option<DeploymentStatus> {
+        self.deployments.get(deployment_id)?;
+        Some(
+            if self
+                .services
+                .values()
+                .any(|service| service.location.is_active(deployment_id))
+            {
+                DeploymentStatus::Active
+            } else {
+                DeploymentStatus::Draining
+            },
+        )
+    }
+
     fn get_deployment_and_services(
         &self,
         deployment_id: &DeploymentId,
@@ -78,6 +117,34 @@ impl DeploymentResolver for Schema {
             })
             .collect()
     }
+
+    fn list_deployments_page(
+        &self,
+        options: &ListOptions,
+    ) -> Page<(Deployment, Vec<(String, ServiceRevision)>)> {
+        options
+            .paginate(
+                self.deployments.iter().filter(|(deployment_id, _)| {
+                    options.include_retired
+                        || self.get_deployment_status(deployment_id)
+                            != Some(DeploymentStatus::Draining)
+                }),
+                |(deployment_id, _)| deployment_id.to_string(),
+            )
+            .map(|(deployment_id, schemas)| {
+                (
+                    Deployment {
+                        id: *deployment_id,
+                        metadata: schemas.metadata.clone(),
+                    },
+                    schemas
+                        .services
+                        .iter()
+                        .map(|s| (s.name.clone(), s.revision))
+                        .collect(),
+                )
+            })
+    }
 }
 
 impl DeploymentResolver for UpdateableSchema {
@@ -90,10 +157,24 @@ impl DeploymentResolver for UpdateableSchema {
             .resolve_latest_deployment_for_service(service_name)
     }
 
+    fn resolve_deployment_for_invocation(
+        &self,
+        service_name: impl AsRef<str>,
+        invocation_id: &InvocationId,
+    ) -> Option<Deployment> {
+        self.0
+            .load()
+            .resolve_deployment_for_invocation(service_name, invocation_id)
+    }
+
     fn get_deployment(&self, deployment_id: &DeploymentId) -> Option<Deployment> {
         self.0.load().get_deployment(deployment_id)
     }
 
+    fn get_deployment_status(&self, deployment_id: &DeploymentId) -> Option<DeploymentStatus> {
+        self.0.load().get_deployment_status(deployment_id)
+    }
+
     fn get_deployment_and_services(
         &self,
         deployment_id: &DeploymentId,
@@ -104,4 +185,11 @@ impl DeploymentResolver for UpdateableSchema {
     fn get_deployments(&self) -> Vec<(Deployment, Vec<(String, ServiceRevision)>)> {
         self.0.load().get_deployments()
     }
+
+    fn list_deployments_page(
+        &self,
+        options: &ListOptions,
+    ) -> Page<(Deployment, Vec<(String, ServiceRevision)>)> {
+        self.0.load().list_deployments_page(options)
+    }
 }
@@ -0,0 +1,64 @@
+// Copyright (c) 2024 - Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Self-contained export of the schema information, used to back up the registered services,
+//! deployments and subscriptions, or to promote them to another environment.
+
+use super::Schema;
+use crate::deployment::DeploymentSchemas;
+use crate::service::ServiceSchemas;
+use restate_schema_api::subscription::Subscription;
+use restate_types::identifiers::{DeploymentId, SubscriptionId};
+use restate_types::Version;
+use serde_with::serde_as;
+use std::collections::HashMap;
+
+/// Version of the format of [`SchemaExport`], to be bumped on incompatible changes of it.
+pub const SCHEMA_EXPORT_FORMAT_VERSION: u32 = 1;
+
+#[serde_as]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SchemaExport {
+    pub format_version: u32,
+    /// Version of the exported schema information.
+    pub schema_version: Version,
+    pub services: HashMap<String, ServiceSchemas>,
+    #[serde_as(as = "serde_with::Seq<(_, _)>")]
+    pub deployments: HashMap<DeploymentId, DeploymentSchemas>,
+    #[serde_as(as = "serde_with::Seq<(_, _)>")]
+    pub subscriptions: HashMap<SubscriptionId, Subscription>,
+}
+
+/// How the entries of a [`SchemaExport`] are imported when they conflict with the registered ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflictResolution {
+    /// Reject the import.
+    #[default]
+    Fail,
+    /// Keep the registered entries.
+    Skip,
+    /// Replace the registered entries with the imported ones.
+    Overwrite,
+}
+
+impl Schema {
+    /// Exports the services, deployments and subscriptions. The deployment tombstones are not
+    /// exported, as they only describe the history of this schema information.
+    pub fn export(&self) -> SchemaExport {
+        SchemaExport {
+            format_version: SCHEMA_EXPORT_FORMAT_VERSION,
+            schema_version: self.version,
+            services: self.services.clone(),
+            deployments: self.deployments.clone(),
+            subscriptions: self.subscriptions.clone(),
+        }
+    }
+}
@@ -9,7 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use arc_swap::ArcSwap;
-use restate_schema_api::deployment::DeploymentType;
+use restate_schema_api::deployment::{DeploymentTombstone, DeploymentType};
 use restate_schema_api::service::{HandlerMetadata, ServiceMetadata};
 use restate_schema_api::subscription::Subscription;
 use restate_types::identifiers::{DeploymentId, ServiceRevision, SubscriptionId};
@@ -17,7 +17,9 @@ use serde_with::serde_as;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+pub mod changes;
 pub mod deployment;
+pub mod export;
 mod invocation_target;
 pub mod service;
 mod subscriptions;
@@ -45,6 +47,9 @@ pub struct Schema {
     // flexbuffers only supports string-keyed maps :-( --> so we store it as vector of kv pairs
     #[serde_as(as = "serde_with::Seq<(_, _)>")]
     pub subscriptions: HashMap<SubscriptionId, Subscription>,
+    /// Most recently removed deployments, oldest first.
+    #[serde(default)]
+    pub deployment_tombstones: Vec<DeploymentTombstone>,
 }
 
 impl Default for Schema {
@@ -54,6 +59,7 @@ impl Default for Schema {
             services: HashMap::default(),
             deployments: HashMap::default(),
             subscriptions: HashMap::default(),
+            deployment_tombstones: Vec::default(),
         }
     }
 }
@@ -9,15 +9,45 @@
 // by the Apache License, Version 2.0.
 
 use super::*;
+use std::num::NonZeroU32;
 use std::time::Duration;
 
 use restate_schema_api::invocation_target::InvocationTargetMetadata;
-use restate_schema_api::service::ServiceMetadataResolver;
+use restate_schema_api::listing::{ListOptions, Page};
+use restate_schema_api::service::{
+    DeploymentWeight, HandlerMetadataResolver, ServiceMetadataResolver,
+};
+use restate_types::identifiers::InvocationId;
 use restate_types::invocation::ServiceType;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct HandlerSchemas {
     pub target_meta: InvocationTargetMetadata,
+    /// Visibility of the handler, overriding the visibility of the service when set.
+    #[serde(default)]
+    pub public: Option<bool>,
+    /// Idempotency retention of the handler, overriding the one of the service when set.
+    #[serde(default)]
+    pub idempotency_retention: Option<Duration>,
+    #[serde(default)]
+    pub execution_timeout: Option<Duration>,
+    #[serde(default)]
+    pub max_concurrent_invocations: Option<NonZeroU32>,
+}
+
+impl HandlerSchemas {
+    pub fn as_handler_metadata(&self, name: String) -> HandlerMetadata {
+        HandlerMetadata {
+            name,
+            ty: self.target_meta.target_ty.into(),
+            input_description: self.target_meta.input_rules.to_string(),
+            output_description: self.target_meta.output_rules.to_string(),
+            public: self.target_meta.public,
+            idempotency_retention: self.idempotency_retention.map(Into::into),
+            execution_timeout: self.execution_timeout.map(Into::into),
+            max_concurrent_invocations: self.max_concurrent_invocations,
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -27,6 +57,8 @@ pub struct ServiceSchemas {
     pub ty: ServiceType,
     pub location: ServiceLocation,
     pub idempotency_retention: Duration,
+    #[serde(default)]
+    pub completion_retention: Option<Duration>,
     pub workflow_completion_retention: Option<Duration>,
 }
 
@@ -37,19 +69,16 @@ impl ServiceSchemas {
             handlers: self
                 .handlers
                 .iter()
-                .map(|(h_name, h_schemas)| HandlerMetadata {
-                    name: h_name.clone(),
-                    ty: h_schemas.target_meta.target_ty.into(),
-                    input_description: h_schemas.target_meta.input_rules.to_string(),
-                    output_description: h_schemas.target_meta.output_rules.to_string(),
-                })
+                .map(|(h_name, h_schemas)| h_schemas.as_handler_metadata(h_name.clone()))
                 .collect(),
             ty: self.ty,
             deployment_id: self.location.latest_deployment,
             revision: self.revision,
             public: self.location.public,
             idempotency_retention: self.idempotency_retention.into(),
+            completion_retention: self.completion_retention.map(Into::into),
             workflow_completion_retention: self.workflow_completion_retention.map(Into::into),
+            traffic_split: self.location.traffic_split.clone(),
         }
     }
 }
@@ -58,6 +87,46 @@ impl ServiceSchemas {
 pub struct ServiceLocation {
     pub latest_deployment: DeploymentId,
     pub public: bool,
+    /// Splits the new invocations across multiple deployments. If empty, the latest deployment
+    /// receives all of them.
+    #[serde(default)]
+    pub traffic_split: Vec<DeploymentWeight>,
+}
+
+impl ServiceLocation {
+    /// Picks the deployment receiving the given invocation according to the traffic split. The
+    /// pick is derived from the random part of the invocation id, hence it is the same on every
+    /// node and for every attempt of the invocation.
+    pub fn deployment_for_invocation(&self, invocation_id: &InvocationId) -> DeploymentId {
+        let total_weight: u64 = self
+            .traffic_split
+            .iter()
+            .map(|deployment| u64::from(deployment.weight))
+            .sum();
+        if total_weight == 0 {
+            return self.latest_deployment;
+        }
+
+        let mut point =
+            (u128::from(invocation_id.invocation_uuid()) % u128::from(total_weight)) as u64;
+        for deployment in &self.traffic_split {
+            let weight = u64::from(deployment.weight);
+            if point < weight {
+                return deployment.deployment_id;
+            }
+            point -= weight;
+        }
+        self.latest_deployment
+    }
+
+    /// Whether the deployment receives new invocations of the service, either as part of the
+    /// traffic split or as the latest deployment, which receives them once the split is removed.
+    pub fn is_active(&self, deployment_id: &DeploymentId) -> bool {
+        self.latest_deployment == *deployment_id
+            || self.traffic_split.iter().any(|deployment| {
+                deployment.deployment_id == *deployment_id && deployment.weight > 0
+            })
+    }
 }
 
 impl ServiceMetadataResolver for Schema {
@@ -80,6 +149,44 @@ impl ServiceMetadataResolver for Schema {
             })
             .collect()
     }
+
+    fn list_services_page(&self, options: &ListOptions) -> Page<ServiceMetadata> {
+        options
+            .paginate(&self.services, |(service_name, _)| (*service_name).clone())
+            .map(|(service_name, service_schemas)| {
+                service_schemas.as_service_metadata(service_name.clone())
+            })
+    }
+}
+
+impl HandlerMetadataResolver for Schema {
+    fn resolve_latest_handler(
+        &self,
+        service_name: impl AsRef<str>,
+        handler_name: impl AsRef<str>,
+    ) -> Option<HandlerMetadata> {
+        self.use_service_schema(service_name, |service_schemas| {
+            service_schemas
+                .handlers
+                .get(handler_name.as_ref())
+                .map(|handler_schemas| {
+                    handler_schemas.as_handler_metadata(handler_name.as_ref().to_owned())
+                })
+        })
+        .flatten()
+    }
+}
+
+impl HandlerMetadataResolver for UpdateableSchema {
+    fn resolve_latest_handler(
+        &self,
+        service_name: impl AsRef<str>,
+        handler_name: impl AsRef<str>,
+    ) -> Option<HandlerMetadata> {
+        self.0
+            .load()
+            .resolve_latest_handler(service_name, handler_name)
+    }
 }
 
 impl ServiceMetadataResolver for UpdateableSchema {
@@ -94,4 +201,8 @@ impl ServiceMetadataResolver for UpdateableSchema {
     fn list_services(&self) -> Vec<ServiceMetadata> {
         self.0.load().list_services()
     }
+
+    fn list_services_page(&self, options: &ListOptions) -> Page<ServiceMetadata> {
+        self.0.load().list_services_page(options)
+    }
 }
@@ -0,0 +1,228 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use futures::task::AtomicWaker;
+use futures::{FutureExt, TryFutureExt};
+use hyper::client::connect::{Connected, Connection};
+use hyper::http::uri::Scheme;
+use hyper::service::Service;
+use hyper::Uri;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[derive(Debug, Default)]
+struct Eviction {
+    evicted: AtomicBool,
+    // The task reading from the connection, woken up to close it once evicted
+    reader: AtomicWaker,
+}
+
+impl Eviction {
+    fn evict(&self) {
+        self.evicted.store(true, Ordering::Relaxed);
+        self.reader.wake();
+    }
+
+    fn is_evicted(&self) -> bool {
+        self.evicted.load(Ordering::Relaxed)
+    }
+}
+
+/// The connections opened by the pool, per endpoint. hyper doesn't allow removing the
+/// connections to a given endpoint from its pool, instead the evicted connections fail their
+/// next read or write, which makes hyper close them.
+#[derive(Debug, Default)]
+pub(crate) struct OpenConnections {
+    endpoints: Mutex<HashMap<String, Vec<Weak<Eviction>>>>,
+}
+
+impl OpenConnections {
+    fn register(&self, uri: &Uri) -> Arc<Eviction> {
+        let eviction = Arc::new(Eviction::default());
+        if let Some(key) = endpoint_key(uri) {
+            let mut endpoints = self.endpoints.lock().unwrap();
+            let connections = endpoints.entry(key).or_default();
+            connections.retain(|connection| connection.strong_count() > 0);
+            connections.push(Arc::downgrade(&eviction));
+        }
+        eviction
+    }
+
+    /// Closes the connections to the endpoint of the given URI, returning how many were open.
+    pub(crate) fn evict(&self, uri: &Uri) -> usize {
+        let Some(connections) =
+            endpoint_key(uri).and_then(|key| self.endpoints.lock().unwrap().remove(&key))
+        else {
+            return 0;
+        };
+        connections
+            .iter()
+            .filter_map(Weak::upgrade)
+            .inspect(|eviction| eviction.evict())
+            .count()
+    }
+}
+
+// The pool of hyper keys the connections by scheme and authority
+fn endpoint_key(uri: &Uri) -> Option<String> {
+    let scheme = uri.scheme()?;
+    let default_port = if *scheme == Scheme::HTTPS { 443 } else { 80 };
+    Some(format!(
+        "{}://{}:{}",
+        scheme,
+        uri.host()?.to_ascii_lowercase(),
+        uri.port_u16().unwrap_or(default_port)
+    ))
+}
+
+/// Registers the connections in [`OpenConnections`], so that they can be evicted.
+#[derive(Clone, Debug)]
+pub struct EvictableConnector<C> {
+    connections: Arc<OpenConnections>,
+    connector: C,
+}
+
+impl<C> EvictableConnector<C> {
+    pub(crate) fn new(connections: Arc<OpenConnections>, connector: C) -> Self {
+        Self {
+            connections,
+            connector,
+        }
+    }
+}
+
+impl<C> Service<Uri> for EvictableConnector<C>
+where
+    C: Service<Uri>,
+    C::Future: Send + 'static,
+{
+    type Response = EvictableStream<C::Response>;
+    type Error = C::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.connector.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let eviction = self.connections.register(&uri);
+        self.connector
+            .call(uri)
+            .map_ok(|stream| EvictableStream { stream, eviction })
+            .boxed()
+    }
+}
+
+#[derive(Debug)]
+pub struct EvictableStream<S> {
+    stream: S,
+    eviction: Arc<Eviction>,
+}
+
+fn evicted_error() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "connection evicted")
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for EvictableStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.eviction.reader.register(cx.waker());
+        if self.eviction.is_evicted() {
+            return Poll::Ready(Err(evicted_error()));
+        }
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for EvictableStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.eviction.is_evicted() {
+            return Poll::Ready(Err(evicted_error()));
+        }
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+impl<S: Connection> Connection for EvictableStream<S> {
+    fn connected(&self) -> Connected {
+        self.stream.connected()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn evicted_connections_fail() {
+        let connections = OpenConnections::default();
+        let uri = Uri::from_str("http://localhost:9080/").unwrap();
+        let (client, mut server) = tokio::io::duplex(64);
+        let mut stream = EvictableStream {
+            stream: client,
+            eviction: connections.register(&uri),
+        };
+        let other_endpoint = connections.register(&Uri::from_str("http://localhost:9081").unwrap());
+
+        server.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+
+        // Hosts are case insensitive
+        assert_eq!(
+            connections.evict(&Uri::from_str("http://LOCALHOST:9080/other").unwrap()),
+            1
+        );
+        assert!(stream.read(&mut buf).await.is_err());
+        assert!(stream.write_all(b"pong").await.is_err());
+        assert!(!other_endpoint.is_evicted());
+        assert_eq!(connections.evict(&uri), 0);
+    }
+
+    #[test]
+    fn closed_connections_are_forgotten() {
+        let connections = OpenConnections::default();
+        let uri = Uri::from_str("https://example.com").unwrap();
+
+        drop(connections.register(&uri));
+        let _open = connections.register(&Uri::from_str("https://example.com:443/").unwrap());
+
+        assert_eq!(
+            connections.endpoints.lock().unwrap()[&endpoint_key(&uri).unwrap()].len(),
+            1
+        );
+        assert_eq!(connections.evict(&uri), 1);
+    }
+}
@@ -8,8 +8,13 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use super::proxy::ProxyConnector;
+use super::eviction::{EvictableConnector, OpenConnections};
+use super::proxy::{Proxy, ProxyConnector, TunnelConnector};
 
+use crate::metric_definitions::{
+    self, SERVICE_CLIENT_HTTP_CONNECTIONS_EVICTED, SERVICE_CLIENT_HTTP_CONNECTIONS_OPENED,
+};
+use crate::tls::{self, TlsConfigError};
 use crate::utils::ErrorExt;
 
 use futures::future::Either;
@@ -17,42 +22,92 @@ use futures::FutureExt;
 use hyper::client::HttpConnector;
 use hyper::http::uri::PathAndQuery;
 use hyper::http::HeaderValue;
+use hyper::service::Service;
 use hyper::{Body, HeaderMap, Method, Request, Response, Uri, Version};
 use hyper_rustls::HttpsConnector;
+use metrics::counter;
 use restate_types::config::HttpOptions;
 use std::fmt::Debug;
 use std::future;
 use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll};
 
-type Connector = ProxyConnector<HttpsConnector<HttpConnector>>;
+type Connector = EvictableConnector<
+    ProxyConnector<MeteredConnector<HttpsConnector<TunnelConnector<HttpConnector>>>>,
+>;
+
+/// Counts the connections the pool opens, as hyper doesn't expose the state of its pool.
+#[derive(Clone, Debug)]
+pub struct MeteredConnector<C>(C);
+
+impl<C> Service<Uri> for MeteredConnector<C>
+where
+    C: Service<Uri>,
+{
+    type Response = C::Response;
+    type Error = C::Error;
+    type Future = C::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        counter!(SERVICE_CLIENT_HTTP_CONNECTIONS_OPENED).increment(1);
+        self.0.call(uri)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct HttpClient {
     client: hyper::Client<Connector, Body>,
+    connections: Arc<OpenConnections>,
 }
 
 impl HttpClient {
-    pub fn new(client: hyper::Client<Connector, Body>) -> Self {
-        Self { client }
+    pub fn new(client: hyper::Client<Connector, Body>, connections: Arc<OpenConnections>) -> Self {
+        Self {
+            client,
+            connections,
+        }
     }
 
-    pub fn from_options(options: &HttpOptions) -> HttpClient {
+    pub fn from_options(options: &HttpOptions) -> Result<HttpClient, TlsConfigError> {
         let mut builder = hyper::Client::builder();
         builder
             .http2_only(true)
             .http2_keep_alive_timeout(options.http_keep_alive_options.timeout.into())
-            .http2_keep_alive_interval(Some(options.http_keep_alive_options.interval.into()));
-
-        HttpClient::new(
-            builder.build::<_, hyper::Body>(ProxyConnector::new(
-                options.http_proxy.clone(),
-                hyper_rustls::HttpsConnectorBuilder::new()
-                    .with_native_roots()
-                    .https_or_http()
-                    .enable_http2()
-                    .build(),
+            .http2_keep_alive_interval(Some(options.http_keep_alive_options.interval.into()))
+            .http2_keep_alive_while_idle(options.connection_pool_options.keep_alive_while_idle)
+            .pool_idle_timeout(Some(options.connection_pool_options.idle_timeout.into()));
+        if let Some(max_idle_per_host) = options.connection_pool_options.max_idle_per_host {
+            builder.pool_max_idle_per_host(max_idle_per_host);
+        }
+        metric_definitions::describe_metrics();
+
+        let proxy = Proxy::new(options.http_proxy.clone(), &options.no_proxy);
+        let mut http_connector = HttpConnector::new();
+        // the TLS connector checks the scheme
+        http_connector.enforce_http(false);
+
+        let connections = Arc::new(OpenConnections::default());
+        Ok(HttpClient::new(
+            builder.build::<_, hyper::Body>(EvictableConnector::new(
+                Arc::clone(&connections),
+                ProxyConnector::new(
+                    proxy.clone(),
+                    MeteredConnector(
+                        hyper_rustls::HttpsConnectorBuilder::new()
+                            .with_tls_config(tls::client_config(options)?)
+                            .https_or_http()
+                            .enable_http2()
+                            .wrap_connector(TunnelConnector::new(proxy, http_connector)),
+                    ),
+                ),
             )),
-        )
+            connections,
+        ))
     }
 
     fn build_request(
@@ -106,8 +161,35 @@ impl HttpClient {
         path: PathAndQuery,
         headers: HeaderMap<HeaderValue>,
     ) -> impl Future<Output = Result<Response<Body>, HttpError>> + Send + 'static {
-        let method = Method::POST;
+        self.send(uri, version, body, Method::POST, path, headers)
+    }
 
+    /// Sends a `GET` request, used to check the health of the deployments.
+    pub fn get(
+        &self,
+        uri: Uri,
+        version: Version,
+        path: PathAndQuery,
+        headers: HeaderMap<HeaderValue>,
+    ) -> impl Future<Output = Result<Response<Body>, HttpError>> + Send + 'static {
+        self.send(uri, version, Body::empty(), Method::GET, path, headers)
+    }
+
+    /// Closes the pooled connections to the endpoint of the given URI.
+    pub fn evict_connections(&self, uri: &Uri) {
+        let evicted = self.connections.evict(uri);
+        counter!(SERVICE_CLIENT_HTTP_CONNECTIONS_EVICTED).increment(evicted as u64);
+    }
+
+    fn send(
+        &self,
+        uri: Uri,
+        version: Version,
+        body: Body,
+        method: Method,
+        path: PathAndQuery,
+        headers: HeaderMap<HeaderValue>,
+    ) -> impl Future<Output = Result<Response<Body>, HttpError>> + Send + 'static {
         let request = match Self::build_request(uri, version, body, method, path, headers) {
             Ok(request) => request,
             Err(err) => return future::ready(Err(err.into())).right_future(),
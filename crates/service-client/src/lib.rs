@@ -30,10 +30,13 @@ use std::sync::Arc;
 pub use crate::lambda::AssumeRoleCacheMode;
 use crate::request_identity::SignRequest;
 
+mod eviction;
 mod http;
 mod lambda;
+mod metric_definitions;
 mod proxy;
 mod request_identity;
+mod tls;
 mod utils;
 
 #[derive(Debug, Clone)]
@@ -76,7 +79,7 @@ impl ServiceClient {
         };
 
         Ok(Self::new(
-            HttpClient::from_options(&options.http),
+            HttpClient::from_options(&options.http)?,
             LambdaClient::from_options(&options.lambda, assume_role_cache_mode),
             request_identity_key,
         ))
@@ -87,6 +90,8 @@ impl ServiceClient {
 pub enum BuildError {
     #[error("Failed to read request identity private key: {0}")]
     SigningPrivateKeyReadError(#[from] request_identity::v1::SigningPrivateKeyReadError),
+    #[error("Failed to configure TLS: {0}")]
+    TlsConfigError(#[from] tls::TlsConfigError),
 }
 
 impl ServiceClient {
@@ -96,18 +101,7 @@ impl ServiceClient {
     ) -> impl Future<Output = Result<Response<Body>, ServiceClientError>> + Send + 'static {
         let (mut parts, body) = req.into_parts();
 
-        let request_identity_key = self.request_identity_key.load();
-
-        let signer = if let Some(request_identity_key) = request_identity_key.as_deref() {
-            Some(request_identity::v1::Signer::new(
-                parts.path.path(),
-                request_identity_key,
-            ))
-        } else {
-            None // will use null signing scheme
-        };
-
-        parts.headers = match signer.insert_identity(parts.headers) {
+        parts.headers = match self.insert_identity(&parts.path, parts.headers) {
             Ok(headers) => headers,
             Err(err) => return future::ready(Err(err.into())).right_future(),
         };
@@ -128,6 +122,49 @@ impl ServiceClient {
         }
         .left_future()
     }
+
+    /// Sends a `GET` request to the health check path of an HTTP deployment.
+    pub fn check_health(
+        &self,
+        uri: Uri,
+        version: hyper::http::Version,
+        path: PathAndQuery,
+    ) -> impl Future<Output = Result<Response<Body>, ServiceClientError>> + Send + 'static {
+        let headers = match self.insert_identity(&path, HeaderMap::new()) {
+            Ok(headers) => headers,
+            Err(err) => return future::ready(Err(err.into())).right_future(),
+        };
+
+        let fut = self.http.get(uri, version, path, headers);
+        async move { Ok(fut.await?) }.left_future()
+    }
+
+    /// Closes the pooled connections to an HTTP deployment, for example because it's unhealthy.
+    pub fn evict_connections(&self, uri: &Uri) {
+        self.http.evict_connections(uri)
+    }
+
+    fn insert_identity(
+        &self,
+        path: &PathAndQuery,
+        headers: HeaderMap<HeaderValue>,
+    ) -> Result<
+        HeaderMap<HeaderValue>,
+        <request_identity::v1::Signer<'static, 'static> as SignRequest>::Error,
+    > {
+        let request_identity_key = self.request_identity_key.load();
+
+        let signer = if let Some(request_identity_key) = request_identity_key.as_deref() {
+            Some(request_identity::v1::Signer::new(
+                path.path(),
+                request_identity_key,
+            ))
+        } else {
+            None // will use null signing scheme
+        };
+
+        signer.insert_identity(headers)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,32 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+/// Optional to have but adds description/help message to the metrics emitted to
+/// the metrics' sink.
+use metrics::{describe_counter, Unit};
+
+pub const SERVICE_CLIENT_HTTP_CONNECTIONS_OPENED: &str =
+    "restate.service_client.http.connections_opened.total";
+pub const SERVICE_CLIENT_HTTP_CONNECTIONS_EVICTED: &str =
+    "restate.service_client.http.connections_evicted.total";
+
+pub(crate) fn describe_metrics() {
+    describe_counter!(
+        SERVICE_CLIENT_HTTP_CONNECTIONS_OPENED,
+        Unit::Count,
+        "Number of connections opened by the HTTP connection pool"
+    );
+
+    describe_counter!(
+        SERVICE_CLIENT_HTTP_CONNECTIONS_EVICTED,
+        Unit::Count,
+        "Number of pooled HTTP connections closed because their deployment was unhealthy"
+    );
+}
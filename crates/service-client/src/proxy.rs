@@ -8,19 +8,65 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use hyper::http::uri::Scheme;
 use hyper::service::Service;
 use hyper::Uri;
 use restate_types::config::ProxyUri;
+use std::error::Error;
+use std::io;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// Upper bound of the proxy response to a CONNECT request
+const MAX_CONNECT_RESPONSE_SIZE: usize = 8 * 1024;
+
+/// Proxy to use for the connections to the deployments, except for the hosts of the no proxy list.
+#[derive(Clone, Debug)]
+pub struct Proxy {
+    uri: ProxyUri,
+    no_proxy: Arc<[String]>,
+}
+
+impl Proxy {
+    pub fn new(uri: Option<ProxyUri>, no_proxy: &[String]) -> Option<Self> {
+        uri.map(|uri| Self {
+            uri,
+            no_proxy: no_proxy
+                .iter()
+                .map(|host| host.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect(),
+        })
+    }
+
+    /// Returns the proxy URI if connections to `dst` go through the proxy.
+    fn uri_for(&self, dst: &Uri) -> Option<&ProxyUri> {
+        let host = dst.host()?.to_ascii_lowercase();
+        let bypass = self.no_proxy.iter().any(|no_proxy| {
+            no_proxy == "*"
+                || host == *no_proxy
+                || host
+                    .strip_suffix(no_proxy.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        });
+        (!bypass).then_some(&self.uri)
+    }
+}
+
+/// Sends plain HTTP traffic to the proxy, rewriting the destination of the connections.
 #[derive(Clone, Debug)]
 pub struct ProxyConnector<C> {
-    proxy: Option<ProxyUri>,
+    proxy: Option<Proxy>,
     connector: C,
 }
 
 impl<C> ProxyConnector<C> {
-    pub fn new(proxy: Option<ProxyUri>, connector: C) -> Self {
+    pub fn new(proxy: Option<Proxy>, connector: C) -> Self {
         Self { proxy, connector }
     }
 }
@@ -38,9 +84,169 @@ where
     }
 
     fn call(&mut self, uri: Uri) -> Self::Future {
-        self.connector.call(match &self.proxy {
-            Some(proxy) => proxy.dst(uri),
+        let uri = match self.proxy.as_ref().and_then(|proxy| proxy.uri_for(&uri)) {
+            Some(proxy_uri) => proxy_uri.dst(uri),
             None => uri,
-        })
+        };
+        self.connector.call(uri)
+    }
+}
+
+/// Tunnels the connections to HTTPS deployments through an HTTP proxy using `CONNECT`. It sits
+/// below the TLS connector, so that the TLS session is established end to end with the deployment.
+#[derive(Clone, Debug)]
+pub struct TunnelConnector<C> {
+    proxy: Option<Proxy>,
+    connector: C,
+}
+
+impl<C> TunnelConnector<C> {
+    pub fn new(proxy: Option<Proxy>, connector: C) -> Self {
+        Self { proxy, connector }
+    }
+}
+
+impl<C> Service<Uri> for TunnelConnector<C>
+where
+    C: Service<Uri>,
+    C::Response: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    C::Future: Send + 'static,
+    C::Error: Into<BoxError>,
+{
+    type Response = C::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.connector.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let proxy_uri = self
+            .proxy
+            .as_ref()
+            .and_then(|proxy| proxy.uri_for(&uri))
+            .filter(|proxy_uri| proxy_uri.uri().scheme() == Some(&Scheme::HTTP));
+        let (Some(proxy_uri), Some(host)) = (proxy_uri, uri.host()) else {
+            return self
+                .connector
+                .call(uri)
+                .map(|res| res.map_err(Into::into))
+                .boxed();
+        };
+        if uri.scheme() != Some(&Scheme::HTTPS) {
+            return self
+                .connector
+                .call(uri)
+                .map(|res| res.map_err(Into::into))
+                .boxed();
+        }
+
+        let authority = format!("{}:{}", host, uri.port_u16().unwrap_or(443));
+        let connect = self.connector.call(proxy_uri.uri().clone());
+        async move {
+            let mut stream = connect.await.map_err(Into::into)?;
+            tunnel(&mut stream, &authority).await?;
+            Ok(stream)
+        }
+        .boxed()
+    }
+}
+
+async fn tunnel<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    authority: &str,
+) -> io::Result<()> {
+    stream
+        .write_all(format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n\r\n").as_bytes())
+        .await?;
+
+    let mut response = Vec::with_capacity(256);
+    let mut buf = [0; 256];
+    // Read up to the end of the response head, the proxy doesn't send anything else before the
+    // tunnel is in use
+    while !response.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "proxy closed the connection during CONNECT",
+            ));
+        }
+        response.extend_from_slice(&buf[..n]);
+        if response.len() > MAX_CONNECT_RESPONSE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proxy response to CONNECT is too large",
+            ));
+        }
+    }
+
+    let status_line = response
+        .split(|b| *b == b'\n')
+        .next()
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(io::Error::other(format!(
+            "proxy refused to CONNECT to {authority}: {}",
+            status_line.trim()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    #[test]
+    fn no_proxy_matches_hosts_and_subdomains() {
+        let proxy = Proxy::new(
+            Some(ProxyUri::from_str("http://127.0.0.1:10001/").unwrap()),
+            &["example.com".to_owned(), ".internal".to_owned()],
+        )
+        .unwrap();
+
+        let uses_proxy = |uri: &str| proxy.uri_for(&Uri::from_str(uri).unwrap()).is_some();
+        assert!(!uses_proxy("http://example.com:9080"));
+        assert!(!uses_proxy("https://api.Example.com"));
+        assert!(!uses_proxy("http://service.internal/"));
+        assert!(uses_proxy("http://notexample.com"));
+        assert!(uses_proxy("http://localhost:9080"));
+    }
+
+    #[tokio::test]
+    async fn tunnel_succeeds_on_2xx() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let proxy = tokio::spawn(async move {
+            let mut buf = [0; 1024];
+            let n = server.read(&mut buf).await.unwrap();
+            assert!(buf[..n].starts_with(b"CONNECT example.com:443 HTTP/1.1\r\n"));
+            server
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        tunnel(&mut client, "example.com:443").await.unwrap();
+        proxy.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn tunnel_fails_on_non_2xx() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        tokio::spawn(async move {
+            let mut buf = [0; 1024];
+            let _ = server.read(&mut buf).await.unwrap();
+            server
+                .write_all(b"HTTP/1.1 403 Forbidden\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        assert!(tunnel(&mut client, "example.com:443").await.is_err());
     }
 }
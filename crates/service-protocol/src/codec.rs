@@ -15,7 +15,7 @@ use prost::Message;
 use restate_types::invocation::Header;
 use restate_types::journal::enriched::{EnrichedEntryHeader, EnrichedRawEntry};
 use restate_types::journal::raw::*;
-use restate_types::journal::{CompletionResult, Entry, EntryType};
+use restate_types::journal::{CompletionResult, Entry, EntryResult, EntryType};
 use std::fmt::Debug;
 use std::mem;
 
@@ -66,6 +66,49 @@ impl RawEntryCodec for ProtobufRawEntryCodec {
         )
     }
 
+    fn deserialize_input_entry(
+        entry_value: Bytes,
+    ) -> Result<(Vec<Header>, Bytes), RawEntryCodecError> {
+        let input_entry = protocol::InputEntryMessage::decode(entry_value).map_err(|e| {
+            RawEntryCodecError::new(
+                EntryType::Input,
+                ErrorKind::Decode {
+                    source: Some(e.into()),
+                },
+            )
+        })?;
+        Ok((
+            input_entry
+                .headers
+                .into_iter()
+                .map(|h| Header::new(h.key, h.value))
+                .collect(),
+            input_entry.value,
+        ))
+    }
+
+    fn serialize_as_output_entry(result: EntryResult) -> EnrichedRawEntry {
+        RawEntry::new(
+            EnrichedEntryHeader::Output {},
+            protocol::OutputEntryMessage {
+                result: Some(match result {
+                    EntryResult::Success(success) => {
+                        protocol::output_entry_message::Result::Value(success)
+                    }
+                    EntryResult::Failure(code, reason) => {
+                        protocol::output_entry_message::Result::Failure(protocol::Failure {
+                            code: code.into(),
+                            message: reason.to_string(),
+                        })
+                    }
+                }),
+                ..Default::default()
+            }
+            .encode_to_vec()
+            .into(),
+        )
+    }
+
     fn serialize_get_state_keys_completion(keys: Vec<Bytes>) -> CompletionResult {
         CompletionResult::Success(
             protocol::get_state_keys_entry_message::StateKeys { keys }
@@ -88,6 +131,9 @@ impl RawEntryCodec for ProtobufRawEntryCodec {
             ClearState,
             ClearAllState,
             GetStateKeys,
+            GetPromise,
+            PeekPromise,
+            CompletePromise,
             Sleep,
             Call,
             OneWayCall,
@@ -168,10 +214,12 @@ mod mocks {
     use crate::awakeable_id::AwakeableIdentifier;
     use crate::pb::protocol::{
         awakeable_entry_message, call_entry_message, complete_awakeable_entry_message,
-        get_state_entry_message, get_state_keys_entry_message, output_entry_message,
+        complete_promise_entry_message, get_promise_entry_message, get_state_entry_message,
+        get_state_keys_entry_message, output_entry_message, peek_promise_entry_message,
         AwakeableEntryMessage, CallEntryMessage, ClearAllStateEntryMessage, ClearStateEntryMessage,
-        CompleteAwakeableEntryMessage, Failure, GetStateEntryMessage, GetStateKeysEntryMessage,
-        InputEntryMessage, OneWayCallEntryMessage, OutputEntryMessage, SetStateEntryMessage,
+        CompleteAwakeableEntryMessage, CompletePromiseEntryMessage, Failure,
+        GetPromiseEntryMessage, GetStateEntryMessage, GetStateKeysEntryMessage, InputEntryMessage,
+        OneWayCallEntryMessage, OutputEntryMessage, PeekPromiseEntryMessage, SetStateEntryMessage,
     };
     use restate_types::identifiers::InvocationId;
     use restate_types::invocation::{InvocationTarget, VirtualObjectHandlerType};
@@ -179,8 +227,9 @@ mod mocks {
         AwakeableEnrichmentResult, CallEnrichmentResult, EnrichedEntryHeader, EnrichedRawEntry,
     };
     use restate_types::journal::{
-        AwakeableEntry, CompletableEntry, CompleteAwakeableEntry, EntryResult, GetStateKeysEntry,
-        GetStateKeysResult, GetStateResult, InputEntry, OutputEntry,
+        AwakeableEntry, CompletableEntry, CompleteAwakeableEntry, CompletePromiseEntry,
+        CompletePromiseResult, EntryResult, GetPromiseEntry, GetStateKeysEntry, GetStateKeysResult,
+        GetStateResult, InputEntry, OutputEntry, PeekPromiseEntry, PeekPromiseResult,
     };
 
     impl ProtobufRawEntryCodec {
@@ -339,6 +388,24 @@ mod mocks {
                     },
                     Self::serialize_awakeable_entry(entry),
                 ),
+                Entry::GetPromise(entry) => EnrichedRawEntry::new(
+                    EnrichedEntryHeader::GetPromise {
+                        is_completed: entry.is_completed(),
+                    },
+                    Self::serialize_get_promise_entry(entry),
+                ),
+                Entry::PeekPromise(entry) => EnrichedRawEntry::new(
+                    EnrichedEntryHeader::PeekPromise {
+                        is_completed: entry.is_completed(),
+                    },
+                    Self::serialize_peek_promise_entry(entry),
+                ),
+                Entry::CompletePromise(entry) => EnrichedRawEntry::new(
+                    EnrichedEntryHeader::CompletePromise {
+                        is_completed: entry.is_completed(),
+                    },
+                    Self::serialize_complete_promise_entry(entry),
+                ),
                 _ => unimplemented!(),
             }
         }
@@ -410,6 +477,88 @@ mod mocks {
             .into()
         }
 
+        fn serialize_get_promise_entry(GetPromiseEntry { key, value }: GetPromiseEntry) -> Bytes {
+            GetPromiseEntryMessage {
+                key: key.to_string(),
+                result: value.map(|r| match r {
+                    EntryResult::Success(success) => {
+                        get_promise_entry_message::Result::Value(success)
+                    }
+                    EntryResult::Failure(code, reason) => {
+                        get_promise_entry_message::Result::Failure(Failure {
+                            code: code.into(),
+                            message: reason.to_string(),
+                        })
+                    }
+                }),
+                ..Default::default()
+            }
+            .encode_to_vec()
+            .into()
+        }
+
+        fn serialize_peek_promise_entry(
+            PeekPromiseEntry { key, value }: PeekPromiseEntry,
+        ) -> Bytes {
+            PeekPromiseEntryMessage {
+                key: key.to_string(),
+                result: value.map(|r| match r {
+                    PeekPromiseResult::Empty => {
+                        peek_promise_entry_message::Result::Empty(protocol::Empty {})
+                    }
+                    PeekPromiseResult::Success(success) => {
+                        peek_promise_entry_message::Result::Value(success)
+                    }
+                    PeekPromiseResult::Failure(code, reason) => {
+                        peek_promise_entry_message::Result::Failure(Failure {
+                            code: code.into(),
+                            message: reason.to_string(),
+                        })
+                    }
+                }),
+                ..Default::default()
+            }
+            .encode_to_vec()
+            .into()
+        }
+
+        fn serialize_complete_promise_entry(
+            CompletePromiseEntry {
+                key,
+                completion,
+                value,
+            }: CompletePromiseEntry,
+        ) -> Bytes {
+            CompletePromiseEntryMessage {
+                key: key.to_string(),
+                completion: Some(match completion {
+                    EntryResult::Success(success) => {
+                        complete_promise_entry_message::Completion::CompletionValue(success)
+                    }
+                    EntryResult::Failure(code, reason) => {
+                        complete_promise_entry_message::Completion::CompletionFailure(Failure {
+                            code: code.into(),
+                            message: reason.to_string(),
+                        })
+                    }
+                }),
+                result: value.map(|r| match r {
+                    CompletePromiseResult::Done => {
+                        complete_promise_entry_message::Result::Empty(protocol::Empty {})
+                    }
+                    CompletePromiseResult::Failure(code, reason) => {
+                        complete_promise_entry_message::Result::Failure(Failure {
+                            code: code.into(),
+                            message: reason.to_string(),
+                        })
+                    }
+                }),
+                ..Default::default()
+            }
+            .encode_to_vec()
+            .into()
+        }
+
         fn serialize_complete_awakeable_entry(
             CompleteAwakeableEntry { id, result }: CompleteAwakeableEntry,
         ) -> Bytes {
@@ -439,7 +588,22 @@ mod tests {
     use super::*;
 
     use bytes::Bytes;
-    use restate_types::journal::EntryResult;
+    use restate_types::journal::{CompletePromiseResult, EntryResult};
+
+    #[test]
+    fn input_entry_roundtrip() {
+        let headers = vec![Header::new("key", "value")];
+        let value = Bytes::from_static(b"input");
+
+        let (_, entry_value) =
+            ProtobufRawEntryCodec::serialize_as_input_entry(headers.clone(), value.clone())
+                .into_inner();
+
+        assert_eq!(
+            ProtobufRawEntryCodec::deserialize_input_entry(entry_value).unwrap(),
+            (headers, value)
+        );
+    }
 
     #[test]
     fn complete_invoke() {
@@ -487,4 +651,40 @@ mod tests {
         assert_eq!(actual_raw_entry.header().is_completed(), Some(true));
         assert_eq!(actual_entry, expected_entry);
     }
+
+    #[test]
+    fn complete_complete_promise() {
+        let completion = Bytes::from_static(b"value");
+
+        let mut raw_entry: PlainRawEntry = RawEntry::new(
+            PlainEntryHeader::CompletePromise {
+                is_completed: false,
+            },
+            protocol::CompletePromiseEntryMessage {
+                key: "my-promise".to_string(),
+                completion: Some(
+                    protocol::complete_promise_entry_message::Completion::CompletionValue(
+                        completion.clone(),
+                    ),
+                ),
+                ..protocol::CompletePromiseEntryMessage::default()
+            }
+            .encode_to_vec()
+            .into(),
+        );
+
+        ProtobufRawEntryCodec::write_completion(&mut raw_entry, CompletionResult::Empty).unwrap();
+
+        assert_eq!(raw_entry.header().is_completed(), Some(true));
+        assert_eq!(
+            raw_entry
+                .deserialize_entry_ref::<ProtobufRawEntryCodec>()
+                .unwrap(),
+            Entry::complete_promise(
+                "my-promise",
+                EntryResult::Success(completion),
+                Some(CompletePromiseResult::Done)
+            )
+        );
+    }
 }
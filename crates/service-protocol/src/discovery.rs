@@ -12,13 +12,13 @@ use crate::pb::protocol;
 use crate::{MAX_SERVICE_PROTOCOL_VERSION, MIN_SERVICE_PROTOCOL_VERSION};
 use bytes::Bytes;
 use codederror::CodedError;
-use hyper::header::{ACCEPT, CONTENT_TYPE};
+use hyper::header::{ACCEPT, ACCEPT_ENCODING, CONTENT_TYPE};
 use hyper::http::response::Parts as ResponseParts;
 use hyper::http::uri::PathAndQuery;
 use hyper::http::{HeaderName, HeaderValue};
 use hyper::{Body, HeaderMap, StatusCode};
 use restate_errors::{META0003, META0012, META0013};
-use restate_schema_api::deployment::ProtocolType;
+use restate_schema_api::deployment::{ContentEncoding, ProtocolType};
 use restate_schema_api::MAX_SERVICE_PROTOCOL_VERSION_VALUE;
 use restate_service_client::{Endpoint, Parts, Request, ServiceClient, ServiceClientError};
 use restate_types::retries::{RetryIter, RetryPolicy};
@@ -81,6 +81,9 @@ pub struct DiscoveredMetadata {
     // type is i32 because the generated ServiceProtocolVersion enum uses this as its representation
     // and we need to represent unknown later versions
     pub supported_protocol_versions: RangeInclusive<i32>,
+    /// Content encoding of the message streams, negotiated from the encodings the deployment
+    /// announced in the `accept-encoding` header of the discovery response.
+    pub content_encoding: Option<ContentEncoding>,
 }
 
 #[derive(Debug, thiserror::Error, CodedError)]
@@ -176,11 +179,20 @@ impl ServiceDiscovery {
             }
         }
 
+        let content_encoding = parts
+            .headers
+            .get(ACCEPT_ENCODING)
+            .and_then(|hv| hv.to_str().ok())
+            .and_then(ContentEncoding::negotiate);
+
         // Parse the response
         let response: schema::Endpoint =
             serde_json::from_slice(&body).map_err(|e| DiscoveryError::Decode(e, body))?;
 
-        Self::create_discovered_metadata_from_endpoint_response(response)
+        let mut discovered_metadata =
+            Self::create_discovered_metadata_from_endpoint_response(response)?;
+        discovered_metadata.content_encoding = content_encoding;
+        Ok(discovered_metadata)
     }
 
     fn create_discovered_metadata_from_endpoint_response(
@@ -241,6 +253,7 @@ impl ServiceDiscovery {
             // we need to store the raw representation since the runtime might not know the latest
             // version yet.
             supported_protocol_versions: min_version..=max_version,
+            content_encoding: None,
         })
     }
 
@@ -362,4 +375,19 @@ mod tests {
             matches!(ServiceDiscovery::create_discovered_metadata_from_endpoint_response(response), Err(DiscoveryError::UnsupportedServiceProtocol { min_version, max_version }) if min_version == unsupported_version && max_version == unsupported_version )
         );
     }
+
+    #[test]
+    fn negotiate_content_encoding() {
+        use restate_schema_api::deployment::ContentEncoding;
+
+        assert_eq!(
+            ContentEncoding::negotiate("gzip, zstd"),
+            Some(ContentEncoding::Zstd)
+        );
+        assert_eq!(
+            ContentEncoding::negotiate("GZIP;q=0.5, zstd;q=0"),
+            Some(ContentEncoding::Gzip)
+        );
+        assert_eq!(ContentEncoding::negotiate("br, identity"), None);
+    }
 }
@@ -59,6 +59,18 @@ pub mod pb {
                     None
                 }
             }
+
+            /// Content type of the invocation request and response bodies speaking this version.
+            /// The runtime advertises the negotiated version with the `content-type` and `accept`
+            /// headers of the request, and the deployment must respond with the same content type.
+            /// V1 keeps the content type used before the version negotiation, so that older SDKs
+            /// keep working.
+            pub fn content_type(self) -> Option<&'static str> {
+                match self {
+                    ServiceProtocolVersion::Unspecified => None,
+                    ServiceProtocolVersion::V1 => Some("application/restate"),
+                }
+            }
         }
     }
 
@@ -165,6 +177,64 @@ mod pb_into {
         }
     }
 
+    impl TryFrom<GetPromiseEntryMessage> for Entry {
+        type Error = &'static str;
+
+        fn try_from(msg: GetPromiseEntryMessage) -> Result<Self, Self::Error> {
+            Ok(Self::GetPromise(GetPromiseEntry {
+                key: msg.key.into(),
+                value: msg.result.map(|v| match v {
+                    get_promise_entry_message::Result::Value(r) => EntryResult::Success(r),
+                    get_promise_entry_message::Result::Failure(Failure { code, message }) => {
+                        EntryResult::Failure(code.into(), message.into())
+                    }
+                }),
+            }))
+        }
+    }
+
+    impl TryFrom<PeekPromiseEntryMessage> for Entry {
+        type Error = &'static str;
+
+        fn try_from(msg: PeekPromiseEntryMessage) -> Result<Self, Self::Error> {
+            Ok(Self::PeekPromise(PeekPromiseEntry {
+                key: msg.key.into(),
+                value: msg.result.map(|v| match v {
+                    peek_promise_entry_message::Result::Empty(_) => PeekPromiseResult::Empty,
+                    peek_promise_entry_message::Result::Value(r) => PeekPromiseResult::Success(r),
+                    peek_promise_entry_message::Result::Failure(Failure { code, message }) => {
+                        PeekPromiseResult::Failure(code.into(), message.into())
+                    }
+                }),
+            }))
+        }
+    }
+
+    impl TryFrom<CompletePromiseEntryMessage> for Entry {
+        type Error = &'static str;
+
+        fn try_from(msg: CompletePromiseEntryMessage) -> Result<Self, Self::Error> {
+            Ok(Self::CompletePromise(CompletePromiseEntry {
+                key: msg.key.into(),
+                completion: match msg.completion.ok_or("completion")? {
+                    complete_promise_entry_message::Completion::CompletionValue(r) => {
+                        EntryResult::Success(r)
+                    }
+                    complete_promise_entry_message::Completion::CompletionFailure(Failure {
+                        code,
+                        message,
+                    }) => EntryResult::Failure(code.into(), message.into()),
+                },
+                value: msg.result.map(|v| match v {
+                    complete_promise_entry_message::Result::Empty(_) => CompletePromiseResult::Done,
+                    complete_promise_entry_message::Result::Failure(Failure { code, message }) => {
+                        CompletePromiseResult::Failure(code.into(), message.into())
+                    }
+                }),
+            }))
+        }
+    }
+
     impl TryFrom<SleepEntryMessage> for Entry {
         type Error = &'static str;
 
@@ -97,6 +97,20 @@ fn generate_header(msg: &ProtocolMessage, protocol_version: u16) -> MessageHeade
     }
 }
 
+impl ProtocolMessage {
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            ProtocolMessage::Start(_) => MessageType::Start,
+            ProtocolMessage::Completion(_) => MessageType::Completion,
+            ProtocolMessage::Suspension(_) => MessageType::Suspension,
+            ProtocolMessage::Error(_) => MessageType::Error,
+            ProtocolMessage::End(_) => MessageType::End,
+            ProtocolMessage::EntryAck(_) => MessageType::EntryAck,
+            ProtocolMessage::UnparsedEntry(entry) => raw_header_to_message_type(entry.header()),
+        }
+    }
+}
+
 fn encode_msg(msg: &ProtocolMessage, buf: &mut impl BufMut) -> Result<(), prost::EncodeError> {
     match msg {
         ProtocolMessage::Start(m) => m.encode(buf),
@@ -295,6 +309,15 @@ fn message_header_to_raw_header(message_header: &MessageHeader) -> PlainEntryHea
             is_completed: expect_flag!(message_header, completed),
         },
         MessageType::ClearAllStateEntry => PlainEntryHeader::ClearAllState {},
+        MessageType::GetPromiseEntry => PlainEntryHeader::GetPromise {
+            is_completed: expect_flag!(message_header, completed),
+        },
+        MessageType::PeekPromiseEntry => PlainEntryHeader::PeekPromise {
+            is_completed: expect_flag!(message_header, completed),
+        },
+        MessageType::CompletePromiseEntry => PlainEntryHeader::CompletePromise {
+            is_completed: expect_flag!(message_header, completed),
+        },
         MessageType::SleepEntry => PlainEntryHeader::Sleep {
             is_completed: expect_flag!(message_header, completed),
         },
@@ -325,6 +348,9 @@ fn raw_header_to_message_type(entry_header: &PlainEntryHeader) -> MessageType {
         PlainEntryHeader::ClearState { .. } => MessageType::ClearStateEntry,
         PlainEntryHeader::GetStateKeys { .. } => MessageType::GetStateKeysEntry,
         PlainEntryHeader::ClearAllState { .. } => MessageType::ClearAllStateEntry,
+        PlainEntryHeader::GetPromise { .. } => MessageType::GetPromiseEntry,
+        PlainEntryHeader::PeekPromise { .. } => MessageType::PeekPromiseEntry,
+        PlainEntryHeader::CompletePromise { .. } => MessageType::CompletePromiseEntry,
         PlainEntryHeader::Sleep { .. } => MessageType::SleepEntry,
         PlainEntryHeader::Call { .. } => MessageType::InvokeEntry,
         PlainEntryHeader::OneWayCall { .. } => MessageType::BackgroundInvokeEntry,
@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::pb::protocol::ServiceProtocolVersion;
 use restate_types::journal::EntryType;
 
 const CUSTOM_MESSAGE_MASK: u16 = 0xFC00;
@@ -41,6 +42,9 @@ pub enum MessageType {
     ClearStateEntry,
     GetStateKeysEntry,
     ClearAllStateEntry,
+    GetPromiseEntry,
+    PeekPromiseEntry,
+    CompletePromiseEntry,
     SleepEntry,
     InvokeEntry,
     BackgroundInvokeEntry,
@@ -66,6 +70,9 @@ impl MessageType {
             MessageType::ClearStateEntry => MessageKind::State,
             MessageType::GetStateKeysEntry => MessageKind::State,
             MessageType::ClearAllStateEntry => MessageKind::State,
+            MessageType::GetPromiseEntry => MessageKind::State,
+            MessageType::PeekPromiseEntry => MessageKind::State,
+            MessageType::CompletePromiseEntry => MessageKind::State,
             MessageType::SleepEntry => MessageKind::Syscall,
             MessageType::InvokeEntry => MessageKind::Syscall,
             MessageType::BackgroundInvokeEntry => MessageKind::Syscall,
@@ -76,11 +83,44 @@ impl MessageType {
         }
     }
 
+    /// First service protocol version supporting this message type. The runtime doesn't send a
+    /// message to a deployment which negotiated an older version.
+    pub fn min_service_protocol_version(&self) -> ServiceProtocolVersion {
+        match self {
+            MessageType::Start
+            | MessageType::Completion
+            | MessageType::Suspension
+            | MessageType::Error
+            | MessageType::End
+            | MessageType::EntryAck
+            | MessageType::InputEntry
+            | MessageType::OutputEntry
+            | MessageType::GetStateEntry
+            | MessageType::SetStateEntry
+            | MessageType::ClearStateEntry
+            | MessageType::GetStateKeysEntry
+            | MessageType::ClearAllStateEntry
+            | MessageType::GetPromiseEntry
+            | MessageType::PeekPromiseEntry
+            | MessageType::CompletePromiseEntry
+            | MessageType::SleepEntry
+            | MessageType::InvokeEntry
+            | MessageType::BackgroundInvokeEntry
+            | MessageType::AwakeableEntry
+            | MessageType::CompleteAwakeableEntry
+            | MessageType::SideEffectEntry
+            | MessageType::CustomEntry(_) => ServiceProtocolVersion::V1,
+        }
+    }
+
     fn has_completed_flag(&self) -> bool {
         matches!(
             self,
             MessageType::GetStateEntry
                 | MessageType::GetStateKeysEntry
+                | MessageType::GetPromiseEntry
+                | MessageType::PeekPromiseEntry
+                | MessageType::CompletePromiseEntry
                 | MessageType::SleepEntry
                 | MessageType::InvokeEntry
                 | MessageType::AwakeableEntry
@@ -112,6 +152,9 @@ const SET_STATE_ENTRY_MESSAGE_TYPE: u16 = 0x0801;
 const CLEAR_STATE_ENTRY_MESSAGE_TYPE: u16 = 0x0802;
 const CLEAR_ALL_STATE_ENTRY_MESSAGE_TYPE: u16 = 0x0803;
 const GET_STATE_KEYS_ENTRY_MESSAGE_TYPE: u16 = 0x0804;
+const GET_PROMISE_ENTRY_MESSAGE_TYPE: u16 = 0x0808;
+const PEEK_PROMISE_ENTRY_MESSAGE_TYPE: u16 = 0x0809;
+const COMPLETE_PROMISE_ENTRY_MESSAGE_TYPE: u16 = 0x080A;
 const SLEEP_ENTRY_MESSAGE_TYPE: u16 = 0x0C00;
 const INVOKE_ENTRY_MESSAGE_TYPE: u16 = 0x0C01;
 const BACKGROUND_INVOKE_ENTRY_MESSAGE_TYPE: u16 = 0x0C02;
@@ -135,6 +178,9 @@ impl From<MessageType> for MessageTypeId {
             MessageType::ClearStateEntry => CLEAR_STATE_ENTRY_MESSAGE_TYPE,
             MessageType::ClearAllStateEntry => CLEAR_ALL_STATE_ENTRY_MESSAGE_TYPE,
             MessageType::GetStateKeysEntry => GET_STATE_KEYS_ENTRY_MESSAGE_TYPE,
+            MessageType::GetPromiseEntry => GET_PROMISE_ENTRY_MESSAGE_TYPE,
+            MessageType::PeekPromiseEntry => PEEK_PROMISE_ENTRY_MESSAGE_TYPE,
+            MessageType::CompletePromiseEntry => COMPLETE_PROMISE_ENTRY_MESSAGE_TYPE,
             MessageType::SleepEntry => SLEEP_ENTRY_MESSAGE_TYPE,
             MessageType::InvokeEntry => INVOKE_ENTRY_MESSAGE_TYPE,
             MessageType::BackgroundInvokeEntry => BACKGROUND_INVOKE_ENTRY_MESSAGE_TYPE,
@@ -168,6 +214,9 @@ impl TryFrom<MessageTypeId> for MessageType {
             CLEAR_STATE_ENTRY_MESSAGE_TYPE => Ok(MessageType::ClearStateEntry),
             GET_STATE_KEYS_ENTRY_MESSAGE_TYPE => Ok(MessageType::GetStateKeysEntry),
             CLEAR_ALL_STATE_ENTRY_MESSAGE_TYPE => Ok(MessageType::ClearAllStateEntry),
+            GET_PROMISE_ENTRY_MESSAGE_TYPE => Ok(MessageType::GetPromiseEntry),
+            PEEK_PROMISE_ENTRY_MESSAGE_TYPE => Ok(MessageType::PeekPromiseEntry),
+            COMPLETE_PROMISE_ENTRY_MESSAGE_TYPE => Ok(MessageType::CompletePromiseEntry),
             SLEEP_ENTRY_MESSAGE_TYPE => Ok(MessageType::SleepEntry),
             INVOKE_ENTRY_MESSAGE_TYPE => Ok(MessageType::InvokeEntry),
             BACKGROUND_INVOKE_ENTRY_MESSAGE_TYPE => Ok(MessageType::BackgroundInvokeEntry),
@@ -192,6 +241,9 @@ impl TryFrom<MessageType> for EntryType {
             MessageType::ClearStateEntry => Ok(EntryType::ClearState),
             MessageType::GetStateKeysEntry => Ok(EntryType::GetStateKeys),
             MessageType::ClearAllStateEntry => Ok(EntryType::ClearAllState),
+            MessageType::GetPromiseEntry => Ok(EntryType::GetPromise),
+            MessageType::PeekPromiseEntry => Ok(EntryType::PeekPromise),
+            MessageType::CompletePromiseEntry => Ok(EntryType::CompletePromise),
             MessageType::SleepEntry => Ok(EntryType::Sleep),
             MessageType::InvokeEntry => Ok(EntryType::Call),
             MessageType::BackgroundInvokeEntry => Ok(EntryType::OneWayCall),
@@ -0,0 +1,56 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::{protobuf_storage_encode_decode, Result};
+
+use futures_util::Stream;
+use restate_types::errors::InvocationError;
+use restate_types::identifiers::{InvocationId, PartitionKey};
+use restate_types::invocation::ServiceInvocation;
+use restate_types::time::MillisSinceEpoch;
+use std::future::Future;
+use std::ops::RangeInclusive;
+
+/// An invocation that failed with a non-retryable error, or whose retries were exhausted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLetter {
+    /// The invocation as it was started. It doesn't carry a response sink, because the callers
+    /// already received the failure.
+    pub service_invocation: ServiceInvocation,
+    pub failure: InvocationError,
+    pub failed_at: MillisSinceEpoch,
+}
+
+protobuf_storage_encode_decode!(DeadLetter);
+
+pub trait ReadOnlyDeadLetterTable {
+    fn get_dead_letter(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> impl Future<Output = Result<Option<DeadLetter>>> + Send;
+
+    fn all_dead_letters(
+        &mut self,
+        range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = Result<(InvocationId, DeadLetter)>> + Send;
+}
+
+pub trait DeadLetterTable: ReadOnlyDeadLetterTable {
+    fn put_dead_letter(
+        &mut self,
+        invocation_id: &InvocationId,
+        dead_letter: DeadLetter,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn delete_dead_letter(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> impl Future<Output = ()> + Send;
+}
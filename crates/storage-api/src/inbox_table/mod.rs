@@ -11,6 +11,7 @@
 use crate::{protobuf_storage_encode_decode, Result};
 use futures_util::Stream;
 use restate_types::identifiers::{InvocationId, PartitionKey, ServiceId, WithPartitionKey};
+use restate_types::invocation::ServiceInvocation;
 use restate_types::message::MessageIndex;
 use restate_types::state_mut::ExternalStateMutation;
 use std::future::Future;
@@ -33,10 +34,32 @@ impl InboxEntry {
 
 protobuf_storage_encode_decode!(InboxEntry);
 
+/// Priority lane of an inbox entry. The entries of an inbox are dequeued in the order of their
+/// priority and, within the same priority, in the order they were enqueued.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InboxPriority {
+    /// Invocations whose caller awaits the result
+    Interactive,
+    /// One way calls and state mutations
+    #[default]
+    Background,
+}
+
+impl InboxPriority {
+    pub fn for_invocation(service_invocation: &ServiceInvocation) -> Self {
+        if service_invocation.response_sink.is_some() {
+            InboxPriority::Interactive
+        } else {
+            InboxPriority::Background
+        }
+    }
+}
+
 /// Entry of the inbox
 #[derive(Debug, Clone, PartialEq)]
 pub struct SequenceNumberInboxEntry {
     pub inbox_sequence_number: MessageIndex,
+    pub priority: InboxPriority,
     pub inbox_entry: InboxEntry,
 }
 
@@ -50,9 +73,16 @@ impl SequenceNumberInboxEntry {
     pub fn new(inbox_sequence_number: MessageIndex, inbox_entry: InboxEntry) -> Self {
         Self {
             inbox_sequence_number,
+            priority: InboxPriority::default(),
             inbox_entry,
         }
     }
+
+    pub fn with_priority(mut self, priority: InboxPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn from_invocation(
         inbox_sequence_number: MessageIndex,
         service_id: ServiceId,
@@ -60,6 +90,7 @@ impl SequenceNumberInboxEntry {
     ) -> Self {
         Self {
             inbox_sequence_number,
+            priority: InboxPriority::default(),
             inbox_entry: InboxEntry::Invocation(service_id, invocation_id),
         }
     }
@@ -70,6 +101,7 @@ impl SequenceNumberInboxEntry {
     ) -> Self {
         Self {
             inbox_sequence_number,
+            priority: InboxPriority::default(),
             inbox_entry: InboxEntry::StateMutation(state_mutation),
         }
     }
@@ -79,7 +111,25 @@ impl SequenceNumberInboxEntry {
     }
 }
 
-pub trait InboxTable {
+pub trait ReadOnlyInboxTable {
+    /// Returns the next entry of the inbox, taking the priority of the entries into account.
+    fn peek_inbox(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Future<Output = Result<Option<SequenceNumberInboxEntry>>> + Send;
+
+    fn inbox(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Stream<Item = Result<SequenceNumberInboxEntry>> + Send;
+
+    fn all_inboxes(
+        &mut self,
+        range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = Result<SequenceNumberInboxEntry>> + Send;
+}
+
+pub trait InboxTable: ReadOnlyInboxTable {
     fn put_inbox_entry(
         &mut self,
         service_id: &ServiceId,
@@ -89,26 +139,12 @@ pub trait InboxTable {
     fn delete_inbox_entry(
         &mut self,
         service_id: &ServiceId,
+        priority: InboxPriority,
         sequence_number: u64,
     ) -> impl Future<Output = ()> + Send;
 
-    fn peek_inbox(
-        &mut self,
-        service_id: &ServiceId,
-    ) -> impl Future<Output = Result<Option<SequenceNumberInboxEntry>>> + Send;
-
     fn pop_inbox(
         &mut self,
         service_id: &ServiceId,
     ) -> impl Future<Output = Result<Option<SequenceNumberInboxEntry>>> + Send;
-
-    fn inbox(
-        &mut self,
-        service_id: &ServiceId,
-    ) -> impl Stream<Item = Result<SequenceNumberInboxEntry>> + Send;
-
-    fn all_inboxes(
-        &mut self,
-        range: RangeInclusive<PartitionKey>,
-    ) -> impl Stream<Item = Result<SequenceNumberInboxEntry>> + Send;
 }
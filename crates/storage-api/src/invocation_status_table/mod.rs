@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::inbox_table::InboxPriority;
 use crate::{protobuf_storage_encode_decode, Result};
 use bytes::Bytes;
 use bytestring::ByteString;
@@ -205,6 +206,7 @@ impl JournalMetadata {
 #[derive(Debug, Clone, PartialEq)]
 pub struct InboxedInvocation {
     pub inbox_sequence_number: u64,
+    pub inbox_priority: InboxPriority,
     pub response_sinks: HashSet<ServiceInvocationResponseSink>,
     pub timestamps: StatusTimestamps,
 
@@ -218,6 +220,8 @@ pub struct InboxedInvocation {
     pub headers: Vec<Header>,
     /// Time when the request should be executed
     pub execution_time: Option<MillisSinceEpoch>,
+    /// Time by which the invocation must have completed
+    pub execution_deadline: Option<MillisSinceEpoch>,
     /// If zero, the invocation completion will not be retained.
     pub completion_retention_time: Duration,
     pub idempotency_key: Option<ByteString>,
@@ -227,9 +231,11 @@ impl InboxedInvocation {
     pub fn from_service_invocation(
         service_invocation: ServiceInvocation,
         inbox_sequence_number: u64,
+        inbox_priority: InboxPriority,
     ) -> Self {
         Self {
             inbox_sequence_number,
+            inbox_priority,
             response_sinks: service_invocation.response_sink.into_iter().collect(),
             timestamps: StatusTimestamps::now(),
             invocation_target: service_invocation.invocation_target,
@@ -238,6 +244,7 @@ impl InboxedInvocation {
             span_context: service_invocation.span_context,
             headers: service_invocation.headers,
             execution_time: service_invocation.execution_time,
+            execution_deadline: service_invocation.execution_deadline,
             completion_retention_time: service_invocation
                 .completion_retention_time
                 .unwrap_or_default(),
@@ -257,6 +264,8 @@ pub struct InFlightInvocationMetadata {
     /// If zero, the invocation completion will not be retained.
     pub completion_retention_time: Duration,
     pub idempotency_key: Option<ByteString>,
+    /// Time by which the invocation must have completed
+    pub execution_deadline: Option<MillisSinceEpoch>,
 }
 
 impl InFlightInvocationMetadata {
@@ -275,6 +284,7 @@ impl InFlightInvocationMetadata {
                     .completion_retention_time
                     .unwrap_or_default(),
                 idempotency_key: service_invocation.idempotency_key,
+                execution_deadline: service_invocation.execution_deadline,
             },
             InvocationInput {
                 argument: service_invocation.argument,
@@ -298,6 +308,7 @@ impl InFlightInvocationMetadata {
                 source: inboxed_invocation.source,
                 completion_retention_time: inboxed_invocation.completion_retention_time,
                 idempotency_key: inboxed_invocation.idempotency_key,
+                execution_deadline: inboxed_invocation.execution_deadline,
             },
             InvocationInput {
                 argument: inboxed_invocation.argument,
@@ -355,6 +366,11 @@ pub trait ReadOnlyInvocationStatusTable {
         &mut self,
         partition_key_range: RangeInclusive<PartitionKey>,
     ) -> impl Stream<Item = Result<(InvocationId, InvocationTarget)>> + Send;
+
+    fn inboxed_invocations(
+        &mut self,
+        partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = Result<(InvocationId, InvocationTarget)>> + Send;
 }
 
 pub trait InvocationStatusTable: ReadOnlyInvocationStatusTable {
@@ -392,6 +408,7 @@ mod mocks {
                 source: Source::Ingress,
                 completion_retention_time: Duration::ZERO,
                 idempotency_key: None,
+                execution_deadline: None,
             }
         }
     }
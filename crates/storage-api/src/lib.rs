@@ -25,6 +25,7 @@ pub enum StorageError {
 
 pub type Result<T> = std::result::Result<T, StorageError>;
 
+pub mod dead_letter_table;
 pub mod deduplication_table;
 pub mod fsm_table;
 pub mod idempotency_table;
@@ -32,6 +33,7 @@ pub mod inbox_table;
 pub mod invocation_status_table;
 pub mod journal_table;
 pub mod outbox_table;
+pub mod promise_table;
 pub mod service_status_table;
 pub mod state_table;
 mod storage;
@@ -56,6 +58,8 @@ pub trait Transaction:
     + fsm_table::FsmTable
     + timer_table::TimerTable
     + idempotency_table::IdempotencyTable
+    + dead_letter_table::DeadLetterTable
+    + promise_table::PromiseTable
     + Send
 {
     fn commit(self) -> impl Future<Output = Result<()>> + Send;
@@ -0,0 +1,55 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::{protobuf_storage_encode_decode, Result};
+
+use bytestring::ByteString;
+use restate_types::identifiers::{JournalEntryId, ServiceId};
+use restate_types::journal::EntryResult;
+use std::future::Future;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromiseState {
+    Completed(EntryResult),
+    NotCompleted(
+        // Journal entries waiting for the promise to be completed
+        Vec<JournalEntryId>,
+    ),
+}
+
+/// Durable promise of a workflow, identified by the workflow [`ServiceId`] and the promise key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Promise {
+    pub state: PromiseState,
+}
+
+protobuf_storage_encode_decode!(Promise);
+
+pub trait ReadOnlyPromiseTable {
+    fn get_promise(
+        &mut self,
+        service_id: &ServiceId,
+        key: &ByteString,
+    ) -> impl Future<Output = Result<Option<Promise>>> + Send;
+}
+
+pub trait PromiseTable: ReadOnlyPromiseTable {
+    fn put_promise(
+        &mut self,
+        service_id: &ServiceId,
+        key: &ByteString,
+        promise: Promise,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn delete_all_promises(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
@@ -12,6 +12,7 @@ use crate::Result;
 use bytes::Bytes;
 use futures_util::Stream;
 use restate_types::identifiers::ServiceId;
+use restate_types::time::MillisSinceEpoch;
 use std::future::Future;
 
 pub trait ReadOnlyStateTable {
@@ -25,6 +26,17 @@ pub trait ReadOnlyStateTable {
         &mut self,
         service_id: &ServiceId,
     ) -> impl Stream<Item = Result<(Bytes, Bytes)>> + Send;
+
+    fn get_user_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        state_key: impl AsRef<[u8]>,
+    ) -> impl Future<Output = Result<Option<MillisSinceEpoch>>> + Send;
+
+    fn get_all_user_state_expirations(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Stream<Item = Result<(Bytes, MillisSinceEpoch)>> + Send;
 }
 
 pub trait StateTable: ReadOnlyStateTable {
@@ -35,14 +47,29 @@ pub trait StateTable: ReadOnlyStateTable {
         state_value: impl AsRef<[u8]>,
     ) -> impl Future<Output = ()> + Send;
 
+    /// Deletes the user state entry together with its expiration.
     fn delete_user_state(
         &mut self,
         service_id: &ServiceId,
         state_key: impl AsRef<[u8]>,
     ) -> impl Future<Output = ()> + Send;
 
+    /// Deletes all user state entries of the service together with their expirations.
     fn delete_all_user_state(
         &mut self,
         service_id: &ServiceId,
     ) -> impl Future<Output = Result<()>> + Send;
+
+    fn put_user_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        state_key: impl AsRef<[u8]>,
+        expiration_time: MillisSinceEpoch,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn delete_user_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        state_key: impl AsRef<[u8]>,
+    ) -> impl Future<Output = ()> + Send;
 }
@@ -91,10 +91,13 @@ pub mod v1 {
 
         use crate::storage::v1::dedup_sequence_number::Variant;
         use crate::storage::v1::enriched_entry_header::{
-            Awakeable, BackgroundCall, ClearAllState, ClearState, CompleteAwakeable, Custom,
-            GetState, GetStateKeys, Input, Invoke, Output, SetState, SideEffect, Sleep,
+            Awakeable, BackgroundCall, ClearAllState, ClearState, CompleteAwakeable,
+            CompletePromise, Custom, GetPromise, GetState, GetStateKeys, Input, Invoke, Output,
+            PeekPromise, SetState, SideEffect, Sleep,
+        };
+        use crate::storage::v1::invocation_status::{
+            inboxed, Completed, Free, Inboxed, Invoked, Suspended,
         };
-        use crate::storage::v1::invocation_status::{Completed, Free, Inboxed, Invoked, Suspended};
         use crate::storage::v1::journal_entry::completion_result::{Empty, Failure, Success};
         use crate::storage::v1::journal_entry::{completion_result, CompletionResult, Entry, Kind};
         use crate::storage::v1::outbox_message::{
@@ -105,13 +108,14 @@ pub mod v1 {
         };
         use crate::storage::v1::{
             enriched_entry_header, inbox_entry, invocation_resolution_result, invocation_status,
-            invocation_target, outbox_message, response_result, source, span_relation, timer,
-            virtual_object_status, BackgroundCallResolutionResult, DedupSequenceNumber, Duration,
-            EnrichedEntryHeader, EpochSequenceNumber, Header, IdempotencyMetadata, InboxEntry,
-            InvocationId, InvocationResolutionResult, InvocationStatus, InvocationTarget,
-            JournalEntry, JournalMeta, KvPair, OutboxMessage, ResponseResult, SequenceNumber,
-            ServiceId, ServiceInvocation, ServiceInvocationResponseSink, Source, SpanContext,
-            SpanRelation, StateMutation, Timer, VirtualObjectStatus,
+            invocation_target, outbox_message, promise, response_result, source, span_relation,
+            timer, virtual_object_status, BackgroundCallResolutionResult, DeadLetter,
+            DedupSequenceNumber, Duration, EnrichedEntryHeader, EpochSequenceNumber, Header,
+            IdempotencyMetadata, InboxEntry, InvocationId, InvocationResolutionResult,
+            InvocationStatus, InvocationTarget, JournalEntry, JournalEntryId, JournalMeta, KvPair,
+            OutboxMessage, Promise, ResponseResult, SequenceNumber, ServiceId, ServiceInvocation,
+            ServiceInvocationResponseSink, Source, SpanContext, SpanRelation, StateMutation, Timer,
+            VirtualObjectStatus,
         };
         use crate::StorageError;
 
@@ -341,6 +345,12 @@ pub mod v1 {
 
                 let idempotency_key = value.idempotency_key.map(ByteString::from);
 
+                let execution_deadline = if value.execution_deadline == 0 {
+                    None
+                } else {
+                    Some(MillisSinceEpoch::new(value.execution_deadline))
+                };
+
                 Ok(crate::invocation_status_table::InFlightInvocationMetadata {
                     invocation_target,
                     journal_metadata,
@@ -353,6 +363,7 @@ pub mod v1 {
                     source,
                     completion_retention_time,
                     idempotency_key,
+                    execution_deadline,
                 })
             }
         }
@@ -368,6 +379,7 @@ pub mod v1 {
                     source,
                     completion_retention_time,
                     idempotency_key,
+                    execution_deadline,
                 } = value;
 
                 Invoked {
@@ -388,6 +400,7 @@ pub mod v1 {
                     source: Some(Source::from(source)),
                     completion_retention_time: Some(Duration::from(completion_retention_time)),
                     idempotency_key: idempotency_key.map(|s| s.to_string()),
+                    execution_deadline: execution_deadline.map(|m| m.as_u64()).unwrap_or_default(),
                 }
             }
         }
@@ -447,6 +460,12 @@ pub mod v1 {
 
                 let idempotency_key = value.idempotency_key.map(ByteString::from);
 
+                let execution_deadline = if value.execution_deadline == 0 {
+                    None
+                } else {
+                    Some(MillisSinceEpoch::new(value.execution_deadline))
+                };
+
                 Ok((
                     crate::invocation_status_table::InFlightInvocationMetadata {
                         invocation_target,
@@ -461,6 +480,7 @@ pub mod v1 {
                         source: caller,
                         completion_retention_time,
                         idempotency_key,
+                        execution_deadline,
                     },
                     waiting_for_completed_entries,
                 ))
@@ -505,6 +525,10 @@ pub mod v1 {
                         metadata.completion_retention_time,
                     )),
                     idempotency_key: metadata.idempotency_key.map(|s| s.to_string()),
+                    execution_deadline: metadata
+                        .execution_deadline
+                        .map(|m| m.as_u64())
+                        .unwrap_or_default(),
                 }
             }
         }
@@ -555,14 +579,36 @@ pub mod v1 {
                     Some(MillisSinceEpoch::new(value.execution_time))
                 };
 
+                let execution_deadline = if value.execution_deadline == 0 {
+                    None
+                } else {
+                    Some(MillisSinceEpoch::new(value.execution_deadline))
+                };
+
                 let completion_retention_time = std::time::Duration::try_from(
                     value.completion_retention_time.unwrap_or_default(),
                 )?;
 
                 let idempotency_key = value.idempotency_key.map(ByteString::from);
 
+                let inbox_priority = match inboxed::InboxPriority::try_from(value.inbox_priority) {
+                    Ok(inboxed::InboxPriority::Background) => {
+                        crate::inbox_table::InboxPriority::Background
+                    }
+                    Ok(inboxed::InboxPriority::Interactive) => {
+                        crate::inbox_table::InboxPriority::Interactive
+                    }
+                    Err(_) => {
+                        return Err(ConversionError::unexpected_enum_variant(
+                            "inbox_priority",
+                            value.inbox_priority,
+                        ))
+                    }
+                };
+
                 Ok(crate::invocation_status_table::InboxedInvocation {
                     inbox_sequence_number: value.inbox_sequence_number,
+                    inbox_priority,
                     response_sinks,
                     timestamps: crate::invocation_status_table::StatusTimestamps::new(
                         MillisSinceEpoch::new(value.creation_time),
@@ -573,6 +619,7 @@ pub mod v1 {
                     headers,
                     argument: value.argument,
                     execution_time,
+                    execution_deadline,
                     idempotency_key,
                     completion_retention_time,
                     invocation_target,
@@ -585,6 +632,7 @@ pub mod v1 {
                 let crate::invocation_status_table::InboxedInvocation {
                     invocation_target,
                     inbox_sequence_number,
+                    inbox_priority,
                     response_sinks,
                     timestamps,
                     argument,
@@ -592,6 +640,7 @@ pub mod v1 {
                     span_context,
                     headers,
                     execution_time,
+                    execution_deadline,
                     completion_retention_time,
                     idempotency_key,
                 } = value;
@@ -612,8 +661,18 @@ pub mod v1 {
                     headers,
                     argument,
                     execution_time: execution_time.map(|m| m.as_u64()).unwrap_or_default(),
+                    execution_deadline: execution_deadline.map(|m| m.as_u64()).unwrap_or_default(),
                     completion_retention_time: Some(Duration::from(completion_retention_time)),
                     idempotency_key: idempotency_key.map(|s| s.to_string()),
+                    inbox_priority: match inbox_priority {
+                        crate::inbox_table::InboxPriority::Background => {
+                            inboxed::InboxPriority::Background
+                        }
+                        crate::inbox_table::InboxPriority::Interactive => {
+                            inboxed::InboxPriority::Interactive
+                        }
+                    }
+                    .into(),
                 }
             }
         }
@@ -820,6 +879,7 @@ pub mod v1 {
                     execution_time,
                     idempotency_key,
                     completion_retention_time,
+                    execution_deadline,
                 } = value;
 
                 let invocation_id = restate_types::identifiers::InvocationId::try_from(
@@ -855,6 +915,12 @@ pub mod v1 {
                     Some(MillisSinceEpoch::new(execution_time))
                 };
 
+                let execution_deadline = if execution_deadline == 0 {
+                    None
+                } else {
+                    Some(MillisSinceEpoch::new(execution_deadline))
+                };
+
                 let completion_retention_time = completion_retention_time
                     .map(std::time::Duration::try_from)
                     .transpose()?;
@@ -870,6 +936,7 @@ pub mod v1 {
                     span_context,
                     headers,
                     execution_time,
+                    execution_deadline,
                     completion_retention_time,
                     idempotency_key,
                 })
@@ -895,6 +962,10 @@ pub mod v1 {
                     execution_time: value.execution_time.map(|m| m.as_u64()).unwrap_or_default(),
                     completion_retention_time: value.completion_retention_time.map(Duration::from),
                     idempotency_key: value.idempotency_key.map(|s| s.to_string()),
+                    execution_deadline: value
+                        .execution_deadline
+                        .map(|m| m.as_u64())
+                        .unwrap_or_default(),
                 }
             }
         }
@@ -1467,6 +1538,21 @@ pub mod v1 {
                             is_completed: get_state_keys.is_completed,
                         }
                     }
+                    enriched_entry_header::Kind::GetPromise(get_promise) => {
+                        restate_types::journal::enriched::EnrichedEntryHeader::GetPromise {
+                            is_completed: get_promise.is_completed,
+                        }
+                    }
+                    enriched_entry_header::Kind::PeekPromise(peek_promise) => {
+                        restate_types::journal::enriched::EnrichedEntryHeader::PeekPromise {
+                            is_completed: peek_promise.is_completed,
+                        }
+                    }
+                    enriched_entry_header::Kind::CompletePromise(complete_promise) => {
+                        restate_types::journal::enriched::EnrichedEntryHeader::CompletePromise {
+                            is_completed: complete_promise.is_completed,
+                        }
+                    }
                     enriched_entry_header::Kind::Sleep(sleep) => {
                         restate_types::journal::enriched::EnrichedEntryHeader::Sleep {
                             is_completed: sleep.is_completed,
@@ -1562,6 +1648,20 @@ pub mod v1 {
                     restate_types::journal::enriched::EnrichedEntryHeader::ClearAllState {
                         ..
                     } => enriched_entry_header::Kind::ClearAllState(ClearAllState {}),
+                    restate_types::journal::enriched::EnrichedEntryHeader::GetPromise {
+                        is_completed,
+                        ..
+                    } => enriched_entry_header::Kind::GetPromise(GetPromise { is_completed }),
+                    restate_types::journal::enriched::EnrichedEntryHeader::PeekPromise {
+                        is_completed,
+                        ..
+                    } => enriched_entry_header::Kind::PeekPromise(PeekPromise { is_completed }),
+                    restate_types::journal::enriched::EnrichedEntryHeader::CompletePromise {
+                        is_completed,
+                        ..
+                    } => enriched_entry_header::Kind::CompletePromise(CompletePromise {
+                        is_completed,
+                    }),
                     restate_types::journal::enriched::EnrichedEntryHeader::Sleep {
                         is_completed,
                         ..
@@ -1927,6 +2027,49 @@ pub mod v1 {
                                 )?,
                             )
                         }
+                        timer::Value::ExpireState(expire_state) => {
+                            crate::timer_table::Timer::ExpireState {
+                                invocation_id: restate_types::identifiers::InvocationId::try_from(
+                                    expire_state
+                                        .invocation_id
+                                        .ok_or(ConversionError::missing_field("invocation_id"))?,
+                                )?,
+                                entry_index: expire_state.entry_index,
+                                service_id: restate_types::identifiers::ServiceId::try_from(
+                                    expire_state
+                                        .service_id
+                                        .ok_or(ConversionError::missing_field("service_id"))?,
+                                )?,
+                                state_key: expire_state.state_key,
+                            }
+                        }
+                        timer::Value::InvocationDeadline(invocation_deadline) => {
+                            crate::timer_table::Timer::InvocationDeadline(
+                                restate_types::identifiers::InvocationId::try_from(
+                                    invocation_deadline
+                                        .invocation_id
+                                        .ok_or(ConversionError::missing_field("invocation_id"))?,
+                                )?,
+                            )
+                        }
+                        timer::Value::CleanDeadLetter(clean_dead_letter) => {
+                            crate::timer_table::Timer::CleanDeadLetter(
+                                restate_types::identifiers::InvocationId::try_from(
+                                    clean_dead_letter
+                                        .invocation_id
+                                        .ok_or(ConversionError::missing_field("invocation_id"))?,
+                                )?,
+                            )
+                        }
+                        timer::Value::ResumeInvocation(resume_invocation) => {
+                            crate::timer_table::Timer::ResumeInvocation(
+                                restate_types::identifiers::InvocationId::try_from(
+                                    resume_invocation
+                                        .invocation_id
+                                        .ok_or(ConversionError::missing_field("invocation_id"))?,
+                                )?,
+                            )
+                        }
                     },
                 )
             }
@@ -1952,6 +2095,32 @@ pub mod v1 {
                                 invocation_id: Some(InvocationId::from(invocation_id)),
                             })
                         }
+                        crate::timer_table::Timer::ExpireState {
+                            invocation_id,
+                            entry_index,
+                            service_id,
+                            state_key,
+                        } => timer::Value::ExpireState(timer::ExpireState {
+                            invocation_id: Some(InvocationId::from(invocation_id)),
+                            entry_index,
+                            service_id: Some(ServiceId::from(service_id)),
+                            state_key,
+                        }),
+                        crate::timer_table::Timer::InvocationDeadline(invocation_id) => {
+                            timer::Value::InvocationDeadline(timer::InvocationDeadline {
+                                invocation_id: Some(InvocationId::from(invocation_id)),
+                            })
+                        }
+                        crate::timer_table::Timer::CleanDeadLetter(invocation_id) => {
+                            timer::Value::CleanDeadLetter(timer::CleanDeadLetter {
+                                invocation_id: Some(InvocationId::from(invocation_id)),
+                            })
+                        }
+                        crate::timer_table::Timer::ResumeInvocation(invocation_id) => {
+                            timer::Value::ResumeInvocation(timer::ResumeInvocation {
+                                invocation_id: Some(InvocationId::from(invocation_id)),
+                            })
+                        }
                     }),
                 }
             }
@@ -2058,6 +2227,120 @@ pub mod v1 {
             }
         }
 
+        impl From<crate::dead_letter_table::DeadLetter> for DeadLetter {
+            fn from(value: crate::dead_letter_table::DeadLetter) -> Self {
+                DeadLetter {
+                    service_invocation: Some(ServiceInvocation::from(value.service_invocation)),
+                    failure_code: value.failure.code().into(),
+                    failure_message: value.failure.message().to_owned(),
+                    failed_at: value.failed_at.as_u64(),
+                }
+            }
+        }
+
+        impl TryFrom<DeadLetter> for crate::dead_letter_table::DeadLetter {
+            type Error = ConversionError;
+
+            fn try_from(value: DeadLetter) -> Result<Self, Self::Error> {
+                Ok(crate::dead_letter_table::DeadLetter {
+                    service_invocation: restate_types::invocation::ServiceInvocation::try_from(
+                        value
+                            .service_invocation
+                            .ok_or(ConversionError::missing_field("service_invocation"))?,
+                    )?,
+                    failure: InvocationError::new(value.failure_code, value.failure_message),
+                    failed_at: MillisSinceEpoch::new(value.failed_at),
+                })
+            }
+        }
+
+        impl From<restate_types::identifiers::JournalEntryId> for JournalEntryId {
+            fn from(value: restate_types::identifiers::JournalEntryId) -> Self {
+                JournalEntryId {
+                    invocation_id: Some(InvocationId::from(value.invocation_id())),
+                    journal_index: value.journal_index(),
+                }
+            }
+        }
+
+        impl TryFrom<JournalEntryId> for restate_types::identifiers::JournalEntryId {
+            type Error = ConversionError;
+
+            fn try_from(value: JournalEntryId) -> Result<Self, Self::Error> {
+                Ok(restate_types::identifiers::JournalEntryId::from_parts(
+                    restate_types::identifiers::InvocationId::try_from(
+                        value
+                            .invocation_id
+                            .ok_or(ConversionError::missing_field("invocation_id"))?,
+                    )
+                    .map_err(|e| ConversionError::invalid_data(e))?,
+                    value.journal_index,
+                ))
+            }
+        }
+
+        impl From<crate::promise_table::Promise> for Promise {
+            fn from(value: crate::promise_table::Promise) -> Self {
+                let state = match value.state {
+                    crate::promise_table::PromiseState::Completed(result) => {
+                        promise::State::CompletedState(promise::CompletedState {
+                            result: Some(ResponseResult::from(
+                                restate_types::invocation::ResponseResult::from(result),
+                            )),
+                        })
+                    }
+                    crate::promise_table::PromiseState::NotCompleted(listening_journal_entries) => {
+                        promise::State::NotCompletedState(promise::NotCompletedState {
+                            listening_journal_entries: listening_journal_entries
+                                .into_iter()
+                                .map(JournalEntryId::from)
+                                .collect(),
+                        })
+                    }
+                };
+
+                Promise { state: Some(state) }
+            }
+        }
+
+        impl TryFrom<Promise> for crate::promise_table::Promise {
+            type Error = ConversionError;
+
+            fn try_from(value: Promise) -> Result<Self, Self::Error> {
+                let state = match value.state.ok_or(ConversionError::missing_field("state"))? {
+                    promise::State::CompletedState(completed_state) => {
+                        let result = restate_types::invocation::ResponseResult::try_from(
+                            completed_state
+                                .result
+                                .ok_or(ConversionError::missing_field("result"))?,
+                        )?;
+                        crate::promise_table::PromiseState::Completed(match result {
+                            restate_types::invocation::ResponseResult::Success(value) => {
+                                restate_types::journal::EntryResult::Success(value)
+                            }
+                            restate_types::invocation::ResponseResult::Failure(err) => {
+                                restate_types::journal::EntryResult::Failure(
+                                    err.code(),
+                                    err.message().into(),
+                                )
+                            }
+                        })
+                    }
+                    promise::State::NotCompletedState(not_completed_state) => {
+                        crate::promise_table::PromiseState::NotCompleted(
+                            not_completed_state
+                                .listening_journal_entries
+                                .into_iter()
+                                .map(restate_types::identifiers::JournalEntryId::try_from)
+                                .collect::<Result<Vec<_>, _>>()?,
+                        )
+                    }
+                };
+
+                Ok(crate::promise_table::Promise { state })
+            }
+        }
+
         impl From<crate::fsm_table::SequenceNumber> for SequenceNumber {
             fn from(value: crate::fsm_table::SequenceNumber) -> Self {
                 SequenceNumber {
@@ -9,9 +9,10 @@
 // by the Apache License, Version 2.0.
 
 use crate::{protobuf_storage_encode_decode, Result};
+use bytes::Bytes;
 use futures_util::Stream;
 use restate_types::identifiers::{
-    InvocationId, InvocationUuid, PartitionId, PartitionKey, WithPartitionKey,
+    InvocationId, InvocationUuid, PartitionId, PartitionKey, ServiceId, WithPartitionKey,
 };
 use restate_types::invocation::ServiceInvocation;
 use restate_types::time::MillisSinceEpoch;
@@ -57,6 +58,37 @@ impl TimerKey {
             kind: TimerKeyKind::CleanInvocationStatus { invocation_uuid },
         }
     }
+
+    fn expire_state(timestamp: u64, invocation_uuid: InvocationUuid, journal_index: u32) -> Self {
+        TimerKey {
+            timestamp,
+            kind: TimerKeyKind::ExpireState {
+                invocation_uuid,
+                journal_index,
+            },
+        }
+    }
+
+    fn invocation_deadline(timestamp: u64, invocation_uuid: InvocationUuid) -> Self {
+        TimerKey {
+            timestamp,
+            kind: TimerKeyKind::InvocationDeadline { invocation_uuid },
+        }
+    }
+
+    fn clean_dead_letter(timestamp: u64, invocation_uuid: InvocationUuid) -> Self {
+        TimerKey {
+            timestamp,
+            kind: TimerKeyKind::CleanDeadLetter { invocation_uuid },
+        }
+    }
+
+    fn resume_invocation(timestamp: u64, invocation_uuid: InvocationUuid) -> Self {
+        TimerKey {
+            timestamp,
+            kind: TimerKeyKind::ResumeInvocation { invocation_uuid },
+        }
+    }
 }
 
 impl PartialOrd for TimerKey {
@@ -94,6 +126,17 @@ pub enum TimerKeyKind {
     },
     /// Cleaning of invocation status
     CleanInvocationStatus { invocation_uuid: InvocationUuid },
+    /// Expiration of a state entry, identified by the journal entry that set it
+    ExpireState {
+        invocation_uuid: InvocationUuid,
+        journal_index: u32,
+    },
+    /// Execution deadline of an invocation
+    InvocationDeadline { invocation_uuid: InvocationUuid },
+    /// Cleaning of a dead letter once its retention expired
+    CleanDeadLetter { invocation_uuid: InvocationUuid },
+    /// Resumption of an invocation which was handed back because its deployment was saturated
+    ResumeInvocation { invocation_uuid: InvocationUuid },
 }
 
 impl TimerKeyKind {
@@ -104,6 +147,12 @@ impl TimerKeyKind {
                 invocation_uuid, ..
             } => invocation_uuid,
             TimerKeyKind::CleanInvocationStatus { invocation_uuid } => invocation_uuid,
+            TimerKeyKind::ExpireState {
+                invocation_uuid, ..
+            } => invocation_uuid,
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => invocation_uuid,
+            TimerKeyKind::CleanDeadLetter { invocation_uuid } => invocation_uuid,
+            TimerKeyKind::ResumeInvocation { invocation_uuid } => invocation_uuid,
         }
     }
 }
@@ -122,7 +171,11 @@ impl Ord for TimerKeyKind {
                     invocation_uuid: other_invocation_uuid,
                 } => invocation_uuid.cmp(other_invocation_uuid),
                 TimerKeyKind::CompleteJournalEntry { .. }
-                | TimerKeyKind::CleanInvocationStatus { .. } => Ordering::Less,
+                | TimerKeyKind::CleanInvocationStatus { .. }
+                | TimerKeyKind::ExpireState { .. }
+                | TimerKeyKind::InvocationDeadline { .. }
+                | TimerKeyKind::CleanDeadLetter { .. }
+                | TimerKeyKind::ResumeInvocation { .. } => Ordering::Less,
             },
             TimerKeyKind::CompleteJournalEntry {
                 invocation_uuid,
@@ -135,7 +188,11 @@ impl Ord for TimerKeyKind {
                 } => invocation_uuid
                     .cmp(other_invocation_uuid)
                     .then_with(|| journal_index.cmp(other_journal_index)),
-                TimerKeyKind::CleanInvocationStatus { .. } => Ordering::Less,
+                TimerKeyKind::CleanInvocationStatus { .. }
+                | TimerKeyKind::ExpireState { .. }
+                | TimerKeyKind::InvocationDeadline { .. }
+                | TimerKeyKind::CleanDeadLetter { .. }
+                | TimerKeyKind::ResumeInvocation { .. } => Ordering::Less,
             },
             TimerKeyKind::CleanInvocationStatus { invocation_uuid } => match other {
                 TimerKeyKind::Invoke { .. } | TimerKeyKind::CompleteJournalEntry { .. } => {
@@ -144,6 +201,61 @@ impl Ord for TimerKeyKind {
                 TimerKeyKind::CleanInvocationStatus {
                     invocation_uuid: other_invocation_uuid,
                 } => invocation_uuid.cmp(other_invocation_uuid),
+                TimerKeyKind::ExpireState { .. }
+                | TimerKeyKind::InvocationDeadline { .. }
+                | TimerKeyKind::CleanDeadLetter { .. }
+                | TimerKeyKind::ResumeInvocation { .. } => Ordering::Less,
+            },
+            TimerKeyKind::ExpireState {
+                invocation_uuid,
+                journal_index,
+            } => match other {
+                TimerKeyKind::Invoke { .. }
+                | TimerKeyKind::CompleteJournalEntry { .. }
+                | TimerKeyKind::CleanInvocationStatus { .. } => Ordering::Greater,
+                TimerKeyKind::ExpireState {
+                    invocation_uuid: other_invocation_uuid,
+                    journal_index: other_journal_index,
+                } => invocation_uuid
+                    .cmp(other_invocation_uuid)
+                    .then_with(|| journal_index.cmp(other_journal_index)),
+                TimerKeyKind::InvocationDeadline { .. }
+                | TimerKeyKind::CleanDeadLetter { .. }
+                | TimerKeyKind::ResumeInvocation { .. } => Ordering::Less,
+            },
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => match other {
+                TimerKeyKind::Invoke { .. }
+                | TimerKeyKind::CompleteJournalEntry { .. }
+                | TimerKeyKind::CleanInvocationStatus { .. }
+                | TimerKeyKind::ExpireState { .. } => Ordering::Greater,
+                TimerKeyKind::InvocationDeadline {
+                    invocation_uuid: other_invocation_uuid,
+                } => invocation_uuid.cmp(other_invocation_uuid),
+                TimerKeyKind::CleanDeadLetter { .. } | TimerKeyKind::ResumeInvocation { .. } => {
+                    Ordering::Less
+                }
+            },
+            TimerKeyKind::CleanDeadLetter { invocation_uuid } => match other {
+                TimerKeyKind::Invoke { .. }
+                | TimerKeyKind::CompleteJournalEntry { .. }
+                | TimerKeyKind::CleanInvocationStatus { .. }
+                | TimerKeyKind::ExpireState { .. }
+                | TimerKeyKind::InvocationDeadline { .. } => Ordering::Greater,
+                TimerKeyKind::CleanDeadLetter {
+                    invocation_uuid: other_invocation_uuid,
+                } => invocation_uuid.cmp(other_invocation_uuid),
+                TimerKeyKind::ResumeInvocation { .. } => Ordering::Less,
+            },
+            TimerKeyKind::ResumeInvocation { invocation_uuid } => match other {
+                TimerKeyKind::Invoke { .. }
+                | TimerKeyKind::CompleteJournalEntry { .. }
+                | TimerKeyKind::CleanInvocationStatus { .. }
+                | TimerKeyKind::ExpireState { .. }
+                | TimerKeyKind::InvocationDeadline { .. }
+                | TimerKeyKind::CleanDeadLetter { .. } => Ordering::Greater,
+                TimerKeyKind::ResumeInvocation {
+                    invocation_uuid: other_invocation_uuid,
+                } => invocation_uuid.cmp(other_invocation_uuid),
             },
         }
     }
@@ -161,6 +273,15 @@ pub enum Timer {
     Invoke(ServiceInvocation),
     CompleteJournalEntry(InvocationId, u32),
     CleanInvocationStatus(InvocationId),
+    ExpireState {
+        invocation_id: InvocationId,
+        entry_index: u32,
+        service_id: ServiceId,
+        state_key: Bytes,
+    },
+    InvocationDeadline(InvocationId),
+    CleanDeadLetter(InvocationId),
+    ResumeInvocation(InvocationId),
 }
 
 impl Timer {
@@ -199,11 +320,54 @@ impl Timer {
         )
     }
 
+    pub fn expire_state(
+        timestamp: u64,
+        invocation_id: InvocationId,
+        entry_index: u32,
+        service_id: ServiceId,
+        state_key: Bytes,
+    ) -> (TimerKey, Self) {
+        (
+            TimerKey::expire_state(timestamp, invocation_id.invocation_uuid(), entry_index),
+            Timer::ExpireState {
+                invocation_id,
+                entry_index,
+                service_id,
+                state_key,
+            },
+        )
+    }
+
+    pub fn invocation_deadline(timestamp: u64, invocation_id: InvocationId) -> (TimerKey, Self) {
+        (
+            TimerKey::invocation_deadline(timestamp, invocation_id.invocation_uuid()),
+            Timer::InvocationDeadline(invocation_id),
+        )
+    }
+
+    pub fn clean_dead_letter(timestamp: u64, invocation_id: InvocationId) -> (TimerKey, Self) {
+        (
+            TimerKey::clean_dead_letter(timestamp, invocation_id.invocation_uuid()),
+            Timer::CleanDeadLetter(invocation_id),
+        )
+    }
+
+    pub fn resume_invocation(timestamp: u64, invocation_id: InvocationId) -> (TimerKey, Self) {
+        (
+            TimerKey::resume_invocation(timestamp, invocation_id.invocation_uuid()),
+            Timer::ResumeInvocation(invocation_id),
+        )
+    }
+
     pub fn invocation_id(&self) -> InvocationId {
         match self {
             Timer::Invoke(service_invocation) => service_invocation.invocation_id,
             Timer::CompleteJournalEntry(invocation_id, _) => *invocation_id,
             Timer::CleanInvocationStatus(invocation_id) => *invocation_id,
+            Timer::ExpireState { invocation_id, .. } => *invocation_id,
+            Timer::InvocationDeadline(invocation_id) => *invocation_id,
+            Timer::CleanDeadLetter(invocation_id) => *invocation_id,
+            Timer::ResumeInvocation(invocation_id) => *invocation_id,
         }
     }
 }
@@ -214,6 +378,10 @@ impl WithPartitionKey for Timer {
             Timer::CompleteJournalEntry(invocation_id, _) => invocation_id.partition_key(),
             Timer::Invoke(service_invocation) => service_invocation.partition_key(),
             Timer::CleanInvocationStatus(invocation_id) => invocation_id.partition_key(),
+            Timer::ExpireState { service_id, .. } => service_id.partition_key(),
+            Timer::InvocationDeadline(invocation_id) => invocation_id.partition_key(),
+            Timer::CleanDeadLetter(invocation_id) => invocation_id.partition_key(),
+            Timer::ResumeInvocation(invocation_id) => invocation_id.partition_key(),
         }
     }
 }
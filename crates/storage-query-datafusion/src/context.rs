@@ -20,7 +20,7 @@ use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion::prelude::{SessionConfig, SessionContext};
 
 use restate_core::worker_api::ProcessorsManagerHandle;
-use restate_invoker_api::StatusHandle;
+use restate_invoker_api::{DeploymentHealthReader, StatusHandle};
 use restate_partition_store::PartitionStoreManager;
 use restate_schema_api::deployment::DeploymentResolver;
 use restate_schema_api::service::ServiceMetadataResolver;
@@ -92,6 +92,7 @@ impl QueryContext {
         partition_selector: impl SelectPartitions + Clone,
         partition_store_manager: PartitionStoreManager,
         status: impl StatusHandle + Send + Sync + Debug + Clone + 'static,
+        deployment_health: impl DeploymentHealthReader + Send + Sync + Debug + Clone + 'static,
         schemas: impl DeploymentResolver
             + ServiceMetadataResolver
             + Send
@@ -105,7 +106,7 @@ impl QueryContext {
             options.tmp_dir.clone(),
             options.query_parallelism(),
         );
-        crate::deployment::register_self(&ctx, schemas.clone())?;
+        crate::deployment::register_self(&ctx, schemas.clone(), deployment_health)?;
         crate::service::register_self(&ctx, schemas)?;
         crate::invocation_state::register_self(&ctx, status)?;
         // partition-key-based
@@ -135,6 +136,11 @@ impl QueryContext {
             partition_store_manager.clone(),
         )?;
         crate::idempotency::register_self(
+            &ctx,
+            partition_selector.clone(),
+            partition_store_manager.clone(),
+        )?;
+        crate::dead_letter::register_self(
             &ctx,
             partition_selector.clone(),
             partition_store_manager,
@@ -0,0 +1,53 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::schema::DeadLetterBuilder;
+
+use crate::table_util::format_using;
+use restate_storage_api::dead_letter_table::DeadLetter;
+use restate_types::identifiers::{InvocationId, WithPartitionKey};
+use restate_types::invocation::ServiceType;
+
+#[inline]
+pub(crate) fn append_dead_letter_row(
+    builder: &mut DeadLetterBuilder,
+    output: &mut String,
+    invocation_id: InvocationId,
+    dead_letter: DeadLetter,
+) {
+    let mut row = builder.row();
+    row.partition_key(invocation_id.partition_key());
+    if row.is_id_defined() {
+        row.id(format_using(output, &invocation_id));
+    }
+
+    let invocation_target = &dead_letter.service_invocation.invocation_target;
+    row.target_service_name(invocation_target.service_name());
+    if let Some(key) = invocation_target.key() {
+        row.target_service_key(key);
+    }
+    row.target_handler_name(invocation_target.handler_name());
+    if row.is_target_defined() {
+        row.target(format_using(output, invocation_target));
+    }
+    row.target_service_ty(match invocation_target.service_ty() {
+        ServiceType::Service => "service",
+        ServiceType::VirtualObject => "virtual_object",
+        ServiceType::Workflow => "workflow",
+    });
+
+    if let Some(idempotency_key) = &dead_letter.service_invocation.idempotency_key {
+        row.idempotency_key(idempotency_key);
+    }
+
+    row.failure_code(u16::from(dead_letter.failure.code()).into());
+    row.failure_message(dead_letter.failure.message());
+    row.failed_at(dead_letter.failed_at.as_u64() as i64);
+}
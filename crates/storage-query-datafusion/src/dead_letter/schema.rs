@@ -0,0 +1,32 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#![allow(dead_code)]
+
+use crate::table_macro::*;
+
+use datafusion::arrow::datatypes::DataType;
+
+define_table!(dead_letter(
+    partition_key: DataType::UInt64,
+    id: DataType::LargeUtf8,
+
+    target: DataType::LargeUtf8,
+    target_service_name: DataType::LargeUtf8,
+    target_service_key: DataType::LargeUtf8,
+    target_handler_name: DataType::LargeUtf8,
+    target_service_ty: DataType::LargeUtf8,
+
+    idempotency_key: DataType::LargeUtf8,
+
+    failure_code: DataType::UInt32,
+    failure_message: DataType::LargeUtf8,
+    failed_at: DataType::Date64,
+));
@@ -10,6 +10,7 @@
 
 use super::schema::DeploymentBuilder;
 use crate::table_util::format_using;
+use restate_invoker_api::{DeploymentHealth, DeploymentHealthReader};
 use restate_schema_api::deployment::{Deployment, DeploymentType};
 
 #[inline]
@@ -17,6 +18,7 @@ pub(crate) fn append_deployment_row(
     builder: &mut DeploymentBuilder,
     output: &mut String,
     deployment: Deployment,
+    health: &impl DeploymentHealthReader,
 ) {
     let mut row = builder.row();
     row.id(format_using(output, &deployment.id));
@@ -32,4 +34,13 @@ pub(crate) fn append_deployment_row(
 
     row.endpoint(format_using(output, &deployment.metadata.address_display()));
     row.created_at(deployment.metadata.created_at.as_u64() as i64);
+    if let Some(health) = health.deployment_health(&deployment.id) {
+        row.healthy(health == DeploymentHealth::Healthy);
+    }
+    if let Some(invocations_health) = health.deployment_invocations_health(&deployment.id) {
+        if let Some(last_success) = invocations_health.last_success {
+            row.last_success_at(last_success.as_u64() as i64);
+        }
+        row.consecutive_failures(invocations_health.consecutive_failures);
+    }
 }
@@ -19,4 +19,7 @@ define_table!(deployment(
     ty: DataType::LargeUtf8,
     endpoint: DataType::LargeUtf8,
     created_at: DataType::Date64,
+    healthy: DataType::Boolean,
+    last_success_at: DataType::Date64,
+    consecutive_failures: DataType::UInt64,
 ));
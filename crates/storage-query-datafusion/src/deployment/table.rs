@@ -18,6 +18,7 @@ use datafusion::physical_plan::stream::RecordBatchReceiverStream;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use tokio::sync::mpsc::Sender;
 
+use restate_invoker_api::DeploymentHealthReader;
 use restate_schema_api::deployment::{Deployment, DeploymentResolver};
 use restate_types::identifiers::ServiceRevision;
 
@@ -29,10 +30,11 @@ use crate::table_providers::{GenericTableProvider, Scan};
 pub(crate) fn register_self(
     ctx: &QueryContext,
     resolver: impl DeploymentResolver + Send + Sync + Debug + 'static,
+    health: impl DeploymentHealthReader + Send + Sync + Debug + Clone + 'static,
 ) -> datafusion::common::Result<()> {
     let deployment_table = GenericTableProvider::new(
         DeploymentBuilder::schema(),
-        Arc::new(DeploymentMetadataScanner(resolver)),
+        Arc::new(DeploymentMetadataScanner(resolver, health)),
     );
 
     ctx.as_ref()
@@ -41,10 +43,12 @@ pub(crate) fn register_self(
 }
 
 #[derive(Debug, Clone)]
-struct DeploymentMetadataScanner<DMR>(DMR);
+struct DeploymentMetadataScanner<DMR, H>(DMR, H);
 
-impl<DMR: DeploymentResolver + Debug + Sync + Send + 'static> Scan
-    for DeploymentMetadataScanner<DMR>
+impl<DMR, H> Scan for DeploymentMetadataScanner<DMR, H>
+where
+    DMR: DeploymentResolver + Debug + Sync + Send + 'static,
+    H: DeploymentHealthReader + Debug + Clone + Sync + Send + 'static,
 {
     fn scan(
         &self,
@@ -57,8 +61,9 @@ impl<DMR: DeploymentResolver + Debug + Sync + Send + 'static> Scan
         let tx = stream_builder.tx();
 
         let rows = self.0.get_deployments();
+        let health = self.1.clone();
         stream_builder.spawn(async move {
-            for_each_state(schema, tx, rows).await;
+            for_each_state(schema, tx, rows, health).await;
             Ok(())
         });
         stream_builder.build()
@@ -69,11 +74,12 @@ async fn for_each_state(
     schema: SchemaRef,
     tx: Sender<datafusion::common::Result<RecordBatch>>,
     rows: Vec<(Deployment, Vec<(String, ServiceRevision)>)>,
+    health: impl DeploymentHealthReader,
 ) {
     let mut builder = DeploymentBuilder::new(schema.clone());
     let mut temp = String::new();
     for (deployment, _) in rows {
-        append_deployment_row(&mut builder, &mut temp, deployment);
+        append_deployment_row(&mut builder, &mut temp, deployment, &health);
         if builder.full() {
             let batch = builder.finish();
             if tx.send(Ok(batch)).await.is_err() {
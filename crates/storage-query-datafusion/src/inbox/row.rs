@@ -22,6 +22,7 @@ pub(crate) fn append_inbox_row(
     let SequenceNumberInboxEntry {
         inbox_sequence_number,
         inbox_entry,
+        ..
     } = inbox_entry;
 
     if let InboxEntry::Invocation(service_id, invocation_id) = inbox_entry {
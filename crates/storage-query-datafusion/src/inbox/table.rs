@@ -18,7 +18,7 @@ use futures::{Stream, StreamExt};
 use tokio::sync::mpsc::Sender;
 
 use restate_partition_store::{PartitionStore, PartitionStoreManager};
-use restate_storage_api::inbox_table::{InboxTable, SequenceNumberInboxEntry};
+use restate_storage_api::inbox_table::{ReadOnlyInboxTable, SequenceNumberInboxEntry};
 use restate_storage_api::StorageError;
 use restate_types::identifiers::PartitionKey;
 
@@ -10,6 +10,7 @@
 
 mod analyzer;
 pub mod context;
+mod dead_letter;
 mod deployment;
 mod idempotency;
 mod inbox;
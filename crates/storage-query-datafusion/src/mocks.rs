@@ -18,18 +18,21 @@ use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::execution::SendableRecordBatchStream;
 use googletest::matcher::{Matcher, MatcherResult};
 use restate_core::task_center;
+use restate_invoker_api::deployment_health::mocks::MockDeploymentHealthReader;
 use restate_invoker_api::status_handle::mocks::MockStatusHandle;
 use restate_invoker_api::StatusHandle;
 use restate_partition_store::{OpenMode, PartitionStore, PartitionStoreManager};
 use restate_rocksdb::RocksDbManager;
 use restate_schema_api::deployment::mocks::MockDeploymentMetadataRegistry;
-use restate_schema_api::deployment::{Deployment, DeploymentResolver};
+use restate_schema_api::deployment::{Deployment, DeploymentResolver, DeploymentStatus};
 use restate_schema_api::service::mocks::MockServiceMetadataResolver;
 use restate_schema_api::service::{ServiceMetadata, ServiceMetadataResolver};
 use restate_types::arc_util::Constant;
 use restate_types::config::{CommonOptions, QueryEngineOptions, WorkerOptions};
 use restate_types::errors::GenericError;
-use restate_types::identifiers::{DeploymentId, PartitionId, PartitionKey, ServiceRevision};
+use restate_types::identifiers::{
+    DeploymentId, InvocationId, PartitionId, PartitionKey, ServiceRevision,
+};
 use restate_types::invocation::ServiceType;
 use std::fmt::Debug;
 use std::marker::PhantomData;
@@ -63,10 +66,23 @@ impl DeploymentResolver for MockSchemas {
         self.1.resolve_latest_deployment_for_service(service_name)
     }
 
+    fn resolve_deployment_for_invocation(
+        &self,
+        service_name: impl AsRef<str>,
+        invocation_id: &InvocationId,
+    ) -> Option<Deployment> {
+        self.1
+            .resolve_deployment_for_invocation(service_name, invocation_id)
+    }
+
     fn get_deployment(&self, deployment_id: &DeploymentId) -> Option<Deployment> {
         self.1.get_deployment(deployment_id)
     }
 
+    fn get_deployment_status(&self, deployment_id: &DeploymentId) -> Option<DeploymentStatus> {
+        self.1.get_deployment_status(deployment_id)
+    }
+
     fn get_deployment_and_services(
         &self,
         deployment_id: &DeploymentId,
@@ -132,6 +148,7 @@ impl MockQueryEngine {
                 MockPartitionSelector,
                 manager,
                 status,
+                MockDeploymentHealthReader::default(),
                 schemas,
             )
             .await
@@ -11,6 +11,7 @@
 #![allow(clippy::enum_variant_names)]
 
 use pin_project::pin_project;
+use restate_types::time::MillisSinceEpoch;
 use restate_types::timer::TimerKey;
 use std::collections::HashSet;
 use std::fmt::Debug;
@@ -18,6 +19,7 @@ use std::future;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{ready, Context, Poll, Waker};
+use std::time::Duration;
 use tokio_util::sync::ReusableBoxFuture;
 use tracing::trace;
 
@@ -25,6 +27,10 @@ pub mod clock;
 #[cfg(test)]
 mod tests;
 
+/// Number of timers that are read at once from storage when loading a timer bucket without an
+/// in memory limit.
+const TIMER_BUCKET_READ_BATCH_SIZE: usize = 1024;
+
 // Using ahash for faster hashing operations. See: https://github.com/garro95/priority-queue#speeding-up
 type DoublePriorityQueue<T> =
     priority_queue::DoublePriorityQueue<T, <T as crate::Timer>::TimerKey, ahash::RandomState>;
@@ -105,6 +111,9 @@ where
     timer_queue: DoublePriorityQueue<Timer>,
 
     num_timers_in_memory_limit: Option<usize>,
+
+    /// If set, only the timers of the next due bucket of this width are kept in memory.
+    timer_bucket_width: Option<Duration>,
 }
 
 async fn get_timers<Timer, TimerReader>(
@@ -131,6 +140,7 @@ where
     pub fn new(
         clock: Clock,
         num_timers_in_memory_limit: Option<usize>,
+        timer_bucket_width: Option<Duration>,
         timer_reader: TimerReader,
     ) -> Self {
         debug_assert!(
@@ -142,10 +152,11 @@ where
             timer_reader: None,
             read_future: ReusableBoxFuture::new(get_timers(
                 timer_reader,
-                num_timers_in_memory_limit.unwrap_or(usize::MAX),
+                Self::read_batch_size(num_timers_in_memory_limit, timer_bucket_width),
                 None,
             )),
             num_timers_in_memory_limit,
+            timer_bucket_width,
             state: State::LoadTimers {
                 removed_timers: Some(HashSet::default()),
             },
@@ -205,9 +216,9 @@ where
             } => {
                 let timer_key = timer.timer_key();
 
-                // if memory limit is configured, then check whether timer is in batch, otherwise
-                // add timer to batch (since all timers are kept in memory)
-                if this.num_timers_in_memory_limit.is_none()
+                // if memory limit or timer bucket is configured, then check whether timer is in
+                // batch, otherwise add timer to batch (since all timers are kept in memory)
+                if (this.num_timers_in_memory_limit.is_none() && this.timer_bucket_width.is_none())
                     || timer_batch
                         .as_ref()
                         .map(|batch| batch.contains(timer_key))
@@ -343,7 +354,17 @@ where
                 }
                 StateProj::LoadTimers { removed_timers } => {
                     let (timer_reader, next_timers) = ready!(this.read_future.poll(cx));
-                    *this.timer_reader = Some(timer_reader);
+
+                    let read_batch_size = Self::read_batch_size(
+                        *this.num_timers_in_memory_limit,
+                        *this.timer_bucket_width,
+                    );
+                    // a full read batch indicates that there might be more timers in storage
+                    let mut continue_reading_after = if next_timers.len() >= read_batch_size {
+                        next_timers.last().map(|timer| timer.timer_key().clone())
+                    } else {
+                        None
+                    };
 
                     {
                         let removed_timers = removed_timers
@@ -369,6 +390,15 @@ where
                                     < timer_key
                             {
                                 trace!("Finished loading timers from storage because the in memory limit has been reached.");
+                                continue_reading_after = None;
+                                break;
+                            } else if Self::is_beyond_timer_bucket(
+                                timer_queue,
+                                *this.timer_bucket_width,
+                                timer_key,
+                            ) {
+                                trace!("Finished loading timers from storage because the end of the current timer bucket has been reached.");
+                                continue_reading_after = None;
                                 break;
                             } else {
                                 trace!("Load timer {next_timer:?} into in memory queue.");
@@ -382,6 +412,36 @@ where
                     this.num_timers_in_memory_limit.map(|limit| {
                         Self::trim_timer_queue(timer_queue, limit, max_fired_timer.as_ref())
                     });
+                    // get rid of timers that don't belong to the current timer bucket
+                    this.timer_bucket_width.map(|timer_bucket_width| {
+                        Self::trim_timer_bucket(
+                            timer_queue,
+                            timer_bucket_width,
+                            max_fired_timer.as_ref(),
+                        )
+                    });
+
+                    if this
+                        .num_timers_in_memory_limit
+                        .map(|limit| timer_queue.len() >= limit)
+                        .unwrap_or(false)
+                    {
+                        continue_reading_after = None;
+                    }
+
+                    if let Some(previous_timer_key) = continue_reading_after {
+                        trace!(
+                            "Continue loading timers from storage after {previous_timer_key:?}."
+                        );
+                        this.read_future.set(get_timers(
+                            timer_reader,
+                            read_batch_size,
+                            Some(previous_timer_key),
+                        ));
+                        continue;
+                    }
+
+                    *this.timer_reader = Some(timer_reader);
 
                     *this.removed_timers = removed_timers.take();
                     this.removed_timers
@@ -436,7 +496,10 @@ where
                                 this.timer_reader
                                     .take()
                                     .expect("timer_reader must be present"),
-                                this.num_timers_in_memory_limit.unwrap_or(usize::MAX),
+                                Self::read_batch_size(
+                                    *this.num_timers_in_memory_limit,
+                                    *this.timer_bucket_width,
+                                ),
                                 end_of_batch,
                             ));
                             state.set(State::LoadTimers { removed_timers });
@@ -473,6 +536,29 @@ where
         future::poll_fn(|cx| self.as_mut().poll_next_timer(cx)).await
     }
 
+    /// Awaits the next timer and returns it together with the timers which are due as well, up to
+    /// `max_num_timers` in wake up order.
+    pub async fn next_timers(mut self: Pin<&mut Self>, max_num_timers: usize) -> Vec<Timer> {
+        debug_assert!(max_num_timers >= 1, "Must return at least one timer.");
+        let mut timers = Vec::new();
+
+        future::poll_fn(|cx| {
+            while timers.len() < max_num_timers {
+                match self.as_mut().poll_next_timer(cx) {
+                    Poll::Ready(timer) => timers.push(timer),
+                    Poll::Pending => break,
+                }
+            }
+
+            if timers.is_empty() {
+                Poll::Pending
+            } else {
+                Poll::Ready(std::mem::take(&mut timers))
+            }
+        })
+        .await
+    }
+
     /// Trim timer queue with respect to target queue size and max fired timer so far.
     /// Only timers that are larger than the max fired timer can be trimmed. The next
     /// read from storage needs to continue at least from the max fired timer because
@@ -513,6 +599,81 @@ where
         has_trimmed_queue
     }
 
+    /// Trim timer queue to the timers belonging to the current timer bucket which starts at the
+    /// earliest timer in the queue. Like for [`Self::trim_timer_queue`], only timers that are
+    /// larger than the max fired timer can be trimmed.
+    fn trim_timer_bucket(
+        timer_queue: &mut DoublePriorityQueue<Timer>,
+        timer_bucket_width: Duration,
+        max_fired_timer: Option<&Timer::TimerKey>,
+    ) -> bool {
+        let Some(timer_bucket_end) = Self::timer_bucket_end(timer_queue, timer_bucket_width) else {
+            return false;
+        };
+
+        let mut has_trimmed_queue = false;
+
+        while let Some((_, current_max_key)) = timer_queue.peek_max() {
+            if current_max_key.wake_up_time() > timer_bucket_end
+                && max_fired_timer
+                    .map(|last_fired_timer| last_fired_timer < current_max_key)
+                    .unwrap_or(true)
+            {
+                let (popped_timer, _) = timer_queue
+                    .pop_max()
+                    .expect("Element must exist since queue is not empty.");
+                trace!("Removing timer {popped_timer:?} from in memory timer queue because it is not part of the current timer bucket.");
+                has_trimmed_queue = true;
+            } else {
+                break;
+            }
+        }
+
+        has_trimmed_queue
+    }
+
+    /// Checks whether the given timer fires after the end of the current timer bucket.
+    fn is_beyond_timer_bucket(
+        timer_queue: &DoublePriorityQueue<Timer>,
+        timer_bucket_width: Option<Duration>,
+        timer_key: &Timer::TimerKey,
+    ) -> bool {
+        timer_bucket_width
+            .and_then(|timer_bucket_width| Self::timer_bucket_end(timer_queue, timer_bucket_width))
+            .map(|timer_bucket_end| timer_key.wake_up_time() > timer_bucket_end)
+            .unwrap_or(false)
+    }
+
+    /// The current timer bucket spans from the earliest timer in the queue to the earliest timer's
+    /// wake up time plus the timer bucket width.
+    fn timer_bucket_end(
+        timer_queue: &DoublePriorityQueue<Timer>,
+        timer_bucket_width: Duration,
+    ) -> Option<MillisSinceEpoch> {
+        timer_queue.peek_min().map(|(_, timer_key)| {
+            let timer_bucket_width =
+                u64::try_from(timer_bucket_width.as_millis()).unwrap_or(u64::MAX);
+            MillisSinceEpoch::new(
+                timer_key
+                    .wake_up_time()
+                    .as_u64()
+                    .saturating_add(timer_bucket_width),
+            )
+        })
+    }
+
+    fn read_batch_size(
+        num_timers_in_memory_limit: Option<usize>,
+        timer_bucket_width: Option<Duration>,
+    ) -> usize {
+        match (num_timers_in_memory_limit, timer_bucket_width) {
+            (Some(limit), _) => limit,
+            // read the timer bucket in pages to avoid reading all timers from storage
+            (None, Some(_)) => TIMER_BUCKET_READ_BATCH_SIZE,
+            (None, None) => usize::MAX,
+        }
+    }
+
     fn max_timer_key(
         timer_key: &Timer::TimerKey,
         max_fired_timer: Option<&Timer::TimerKey>,
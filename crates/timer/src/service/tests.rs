@@ -162,7 +162,7 @@ impl TimerKey for TimerValue {
 #[test(tokio::test)]
 async fn no_timer_is_dropped() {
     let timer_reader = MockTimerReader::new();
-    let service = TimerService::new(TokioClock, None, timer_reader);
+    let service = TimerService::new(TokioClock, None, None, timer_reader);
     tokio::pin!(service);
 
     let timer_1 = TimerValue::new(0, 0.into());
@@ -182,7 +182,7 @@ async fn no_timer_is_dropped() {
 async fn timers_fire_in_wake_up_order() {
     let num_timers = 10;
     let timer_reader = MockTimerReader::new();
-    let service = TimerService::new(TokioClock, None, timer_reader);
+    let service = TimerService::new(TokioClock, None, None, timer_reader);
     tokio::pin!(service);
 
     let now = u64::try_from(
@@ -216,7 +216,7 @@ async fn loading_timers_from_reader() {
         timer_reader.add_timer(TimerValue::new(i, i.into()))
     }
 
-    let service = TimerService::new(clock.clone(), Some(1), timer_reader);
+    let service = TimerService::new(clock.clone(), Some(1), None, timer_reader);
     tokio::pin!(service);
 
     // trigger all timers
@@ -230,6 +230,52 @@ async fn loading_timers_from_reader() {
     }
 }
 
+#[test(tokio::test)]
+async fn due_timers_fire_in_batches() {
+    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let timer_reader = MockTimerReader::<TimerValue>::new();
+    let num_timers = 10;
+
+    for i in 0..num_timers {
+        timer_reader.add_timer(TimerValue::new(i, (i / 4).into()));
+    }
+
+    let service = TimerService::new(clock.clone(), Some(3), None, timer_reader);
+    tokio::pin!(service);
+
+    // trigger the timers due at 0 and 1
+    clock.advance_time_by(Duration::from_millis(1));
+
+    assert_eq!(
+        service.as_mut().next_timers(5).await,
+        (0..5)
+            .map(|i| TimerValue::new(i, (i / 4).into()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        service.as_mut().next_timers(5).await,
+        (5..8)
+            .map(|i| TimerValue::new(i, (i / 4).into()))
+            .collect::<Vec<_>>()
+    );
+
+    // no other timer should fire
+    assert!(
+        tokio::time::timeout(Duration::from_millis(10), service.as_mut().next_timers(5))
+            .await
+            .is_err()
+    );
+
+    clock.advance_time_by(Duration::from_millis(1));
+
+    assert_eq!(
+        service.as_mut().next_timers(5).await,
+        (8..num_timers)
+            .map(|i| TimerValue::new(i, (i / 4).into()))
+            .collect::<Vec<_>>()
+    );
+}
+
 #[test(tokio::test)]
 async fn advancing_time_triggers_timer() {
     let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
@@ -240,7 +286,7 @@ async fn advancing_time_triggers_timer() {
         timer_reader.add_timer(TimerValue::new(i, i.into()));
     }
 
-    let service = TimerService::new(clock.clone(), Some(1), timer_reader);
+    let service = TimerService::new(clock.clone(), Some(1), None, timer_reader);
     tokio::pin!(service);
 
     // trigger half of the timers
@@ -282,7 +328,7 @@ async fn add_new_timers() {
         TimerValue::new(3, 10.into()),
     ]);
 
-    let service = TimerService::new(clock.clone(), Some(1), timer_reader.clone());
+    let service = TimerService::new(clock.clone(), Some(1), None, timer_reader.clone());
     tokio::pin!(service);
 
     clock.advance_time_to(MillisSinceEpoch::new(5));
@@ -306,7 +352,7 @@ async fn earlier_timers_replace_older_ones() {
     let timer_reader = MockTimerReader::<TimerValue>::new();
     timer_reader.add_timer(TimerValue::new(1, 10.into()));
 
-    let service = TimerService::new(clock.clone(), Some(1), timer_reader.clone());
+    let service = TimerService::new(clock.clone(), Some(1), None, timer_reader.clone());
     tokio::pin!(service);
 
     // give timer service chance to load timers
@@ -347,7 +393,7 @@ async fn earlier_timers_wont_trigger_reemission_of_fired_timers() {
     timer_reader.add_timer(TimerValue::new(0, 2.into()));
     timer_reader.add_timer(TimerValue::new(2, 5.into()));
 
-    let service = TimerService::new(clock.clone(), Some(1), timer_reader.clone());
+    let service = TimerService::new(clock.clone(), Some(1), None, timer_reader.clone());
     tokio::pin!(service);
 
     // give timer service the chance to load the initial timers
@@ -369,6 +415,101 @@ async fn earlier_timers_wont_trigger_reemission_of_fired_timers() {
     }
 }
 
+#[test(tokio::test)]
+async fn loading_timers_in_buckets() {
+    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let timer_reader = MockTimerReader::<TimerValue>::new();
+    let num_timers = 10;
+
+    for i in 0..num_timers / 2 {
+        timer_reader.add_timer(TimerValue::new(i, (1 + i).into()));
+    }
+    for i in num_timers / 2..num_timers {
+        timer_reader.add_timer(TimerValue::new(i, (100 + i).into()));
+    }
+
+    let service = TimerService::new(
+        clock.clone(),
+        None,
+        Some(Duration::from_millis(10)),
+        timer_reader,
+    );
+    tokio::pin!(service);
+
+    // give timer service the chance to load the initial timers
+    yield_to_timer_service(&mut service).await;
+
+    // only the timers of the first bucket should be kept in memory
+    assert_eq!(service.timer_queue.len(), (num_timers / 2) as usize);
+
+    clock.advance_time_to(MillisSinceEpoch::new(100 + num_timers));
+
+    for i in 0..num_timers {
+        let_assert!(TimerValue { value, .. } = service.as_mut().next_timer().await);
+        assert_eq!(value, i);
+    }
+}
+
+#[test(tokio::test)]
+async fn loading_timer_bucket_in_multiple_reads() {
+    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let timer_reader = MockTimerReader::<TimerValue>::new();
+    let num_timers = 2 * super::TIMER_BUCKET_READ_BATCH_SIZE as u64 + 1;
+
+    timer_reader.add_timers((0..num_timers).map(|i| TimerValue::new(i, (1 + i).into())));
+
+    let service = TimerService::new(
+        clock.clone(),
+        None,
+        Some(Duration::from_millis(num_timers)),
+        timer_reader,
+    );
+    tokio::pin!(service);
+
+    // give timer service the chance to load the initial timers
+    yield_to_timer_service(&mut service).await;
+
+    assert_eq!(service.timer_queue.len(), num_timers as usize);
+
+    clock.advance_time_to(MillisSinceEpoch::new(1 + num_timers));
+
+    for i in 0..num_timers {
+        let_assert!(TimerValue { value, .. } = service.as_mut().next_timer().await);
+        assert_eq!(value, i);
+    }
+}
+
+#[test(tokio::test)]
+async fn timers_beyond_current_bucket_are_loaded_later() {
+    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let timer_reader = MockTimerReader::<TimerValue>::new();
+    timer_reader.add_timer(TimerValue::new(0, 1.into()));
+    timer_reader.add_timer(TimerValue::new(1, 100.into()));
+
+    let service = TimerService::new(
+        clock.clone(),
+        None,
+        Some(Duration::from_millis(10)),
+        timer_reader.clone(),
+    );
+    tokio::pin!(service);
+
+    // give timer service the chance to load the initial timers
+    yield_to_timer_service(&mut service).await;
+
+    // timer is not part of the current bucket and needs to be loaded from storage later
+    let new_timer = TimerValue::new(2, 50.into());
+    timer_reader.add_timer(new_timer);
+    service.as_mut().add_timer(new_timer);
+
+    clock.advance_time_to(MillisSinceEpoch::new(100));
+
+    for expected_value in [0, 2, 1] {
+        let_assert!(TimerValue { value, .. } = service.as_mut().next_timer().await);
+        assert_eq!(value, expected_value);
+    }
+}
+
 #[test(tokio::test)]
 async fn delete_loaded_timer() {
     let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
@@ -378,7 +519,7 @@ async fn delete_loaded_timer() {
     timer_reader.add_timer(timer);
     timer_reader.add_timer(TimerValue::new(2, MillisSinceEpoch::from(2)));
 
-    let service = TimerService::new(clock.clone(), None, timer_reader.clone());
+    let service = TimerService::new(clock.clone(), None, None, timer_reader.clone());
     tokio::pin!(service);
 
     assert_eq!(
@@ -405,7 +546,7 @@ async fn delete_last_loaded_timer() {
     let timer = TimerValue::new(1, MillisSinceEpoch::from(1));
     timer_reader.add_timer(timer);
 
-    let service = TimerService::new(clock.clone(), None, timer_reader.clone());
+    let service = TimerService::new(clock.clone(), None, None, timer_reader.clone());
     tokio::pin!(service);
 
     assert_eq!(
@@ -428,7 +569,7 @@ async fn delete_only_loaded_timer() {
     let timer = TimerValue::new(1, MillisSinceEpoch::from(1));
     timer_reader.add_timer(timer);
 
-    let service = TimerService::new(clock.clone(), None, timer_reader.clone());
+    let service = TimerService::new(clock.clone(), None, None, timer_reader.clone());
     tokio::pin!(service);
 
     assert!(service.as_mut().next_timer().now_or_never().is_none());
@@ -446,7 +587,7 @@ async fn delete_loading_timer() {
     let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let (tx, timer_reader) = AsyncMockTimerReader::new();
 
-    let service = TimerService::new(clock.clone(), None, timer_reader);
+    let service = TimerService::new(clock.clone(), None, None, timer_reader);
     tokio::pin!(service);
     assert!(service.as_mut().next_timer().now_or_never().is_none());
 
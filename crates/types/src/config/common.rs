@@ -84,6 +84,16 @@ pub struct CommonOptions {
     #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub shutdown_timeout: Duration,
 
+    /// # Metadata update interval
+    ///
+    /// How often the node checks the metadata store for a newer version of the schema registry,
+    /// which is updated by the admin servers when registering deployments or updating services.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub metadata_update_interval: Duration,
+
     /// # Default async runtime thread pool
     ///
     /// Size of the default thread pool used to perform internal tasks.
@@ -162,6 +172,32 @@ pub struct CommonOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub storage_low_priority_bg_threads: Option<NonZeroUsize>,
 
+    /// # Storage high priority queue depth
+    ///
+    /// Maximum number of storage tasks that can be queued or running on the high priority
+    /// storage thread pool. Tasks beyond this limit are rejected so that callers can apply
+    /// backpressure, writes and WAL/memtable flushes are never rejected but count towards the
+    /// limit. A single database can exceed its fair share of the queue only while the queue is
+    /// less than half full.
+    ///
+    /// Supports hot-reloading.
+    pub storage_high_priority_queue_depth: NonZeroUsize,
+
+    /// # Storage low priority queue depth
+    ///
+    /// Same as `storage-high-priority-queue-depth` for the low priority storage thread pool.
+    ///
+    /// Supports hot-reloading.
+    pub storage_low_priority_queue_depth: NonZeroUsize,
+
+    /// # Storage work stealing
+    ///
+    /// Whether storage tasks may run on the thread pool of the other priority when all threads
+    /// of their own pool are busy and the other pool has idle threads.
+    ///
+    /// Supports hot-reloading.
+    pub storage_work_stealing: StorageWorkStealing,
+
     /// # Total memory limit for rocksdb caches and memtables.
     ///
     /// This includes memory for uncompressed block cache and all memtables by all open databases.
@@ -200,6 +236,115 @@ pub struct CommonOptions {
     #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub rocksdb_write_stall_threshold: humantime::Duration,
 
+    /// # Rocksdb IO rate limit
+    ///
+    /// Limits the total rate (bytes/sec) of flush and compaction writes across all rocksdb
+    /// databases of this node. This can be used to throttle background IO during peak traffic.
+    /// Unlimited if unset.
+    ///
+    /// Supports hot-reloading.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    pub rocksdb_rate_limit_bytes_per_sec: Option<NonZeroUsize>,
+
+    /// # Rocksdb group commit max latency
+    ///
+    /// Writes that need a synced WAL (the metadata store, and the local loglet if
+    /// `sync-wal-before-ack` is set) wait at most this long for concurrent writes to the same
+    /// database that are in progress to join them before the WAL is synced once for all of them.
+    /// Higher values trade write latency for fewer fsyncs.
+    ///
+    /// Supports hot-reloading.
+    #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub rocksdb_group_commit_max_latency: humantime::Duration,
+
+    /// # Rocksdb group commit max bytes
+    ///
+    /// The WAL is synced early, without waiting for `rocksdb-group-commit-max-latency` to pass,
+    /// once the writes waiting for group commit add up to this many bytes.
+    ///
+    /// Supports hot-reloading.
+    #[serde_as(as = "NonZeroByteCount")]
+    #[cfg_attr(feature = "schemars", schemars(with = "NonZeroByteCount"))]
+    pub rocksdb_group_commit_max_bytes: NonZeroUsize,
+
+    /// # Rocksdb max write batch size
+    ///
+    /// Write batches that are larger than this are split into several smaller batches that are
+    /// written one after the other, so that a single huge write doesn't stall the writes of the
+    /// database for seconds. Each split is written atomically, but a crash can leave only the
+    /// first splits of a batch applied. Only writes that opt in to splitting are split, batches
+    /// that must be applied atomically (e.g. partition store transactions) never are. Batches
+    /// are not split if unset.
+    ///
+    /// Supports hot-reloading.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    pub rocksdb_max_write_batch_size: Option<NonZeroUsize>,
+
+    /// # Rocksdb properties poll interval
+    ///
+    /// How often rocksdb properties (e.g. estimated number of keys, pending compaction bytes,
+    /// number of files per level) of all databases are collected and published as metrics.
+    #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub rocksdb_properties_poll_interval: humantime::Duration,
+
+    /// # Rocksdb idle memtable flush timeout
+    ///
+    /// Memtables of databases that run with the WAL disabled are flushed once they haven't
+    /// received writes for this long. This bounds the amount of data that is lost on a crash
+    /// and reduces the work needed on shutdown. Memtables are checked for idleness every
+    /// `rocksdb-properties-poll-interval`. Disabled if unset.
+    #[serde(with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub rocksdb_idle_memtable_flush_timeout: Option<humantime::Duration>,
+
+    /// # Rocksdb tombstone compaction interval
+    ///
+    /// Key ranges that are marked as tombstone-heavy by their database (e.g. queues that see a
+    /// delete for every insert) are compacted down to the bottommost level at this interval so
+    /// that deleted entries don't linger and slow down scans. Disabled if unset.
+    #[serde(with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub rocksdb_tombstone_compaction_interval: Option<humantime::Duration>,
+
+    /// # Rocksdb backup destination
+    ///
+    /// Object store location to which all rocksdb databases of this node are incrementally
+    /// backed up, e.g. `s3://bucket/prefix`, `gs://bucket/prefix` or `file:///path/to/dir`.
+    /// Backups are kept below `<destination>/<node-name>`, so the location can be shared by all
+    /// nodes of a cluster. Credentials are picked up from the environment. Backups are disabled
+    /// if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rocksdb_backup_destination: Option<String>,
+
+    /// # Rocksdb backup interval
+    ///
+    /// How often a new backup of every database is taken.
+    #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub rocksdb_backup_interval: humantime::Duration,
+
+    /// # Rocksdb backup retention
+    ///
+    /// Number of backups to retain per database. Older backups and the files only they
+    /// reference are deleted from the object store after every successful backup.
+    pub rocksdb_backup_retention: NonZeroUsize,
+
+    /// # Rocksdb restore source
+    ///
+    /// Object store location (same format as `rocksdb-backup-destination`) to restore databases
+    /// from, including the name of the node that took the backups, e.g.
+    /// `s3://bucket/prefix/<node-name>`. If set, a database that is opened with an empty data
+    /// directory is hydrated from its latest backup in this location before it's opened. This
+    /// allows replacement nodes to bootstrap from the backups of the node they replace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rocksdb_restore_source: Option<String>,
+
     /// RocksDb base settings and memory limits that get applied on every database
     #[serde(flatten)]
     pub rocksdb: RocksDbOptions,
@@ -286,6 +431,7 @@ impl Default for CommonOptions {
             disable_prometheus: false,
             service_client: Default::default(),
             shutdown_timeout: std::time::Duration::from_secs(60).into(),
+            metadata_update_interval: std::time::Duration::from_secs(3).into(),
             tracing_endpoint: None,
             tracing_json_path: None,
             tracing_filter: "info".to_owned(),
@@ -295,11 +441,27 @@ impl Default for CommonOptions {
             default_thread_pool_size: None,
             storage_high_priority_bg_threads: None,
             storage_low_priority_bg_threads: None,
+            storage_high_priority_queue_depth: NonZeroUsize::new(1024).unwrap(),
+            storage_low_priority_queue_depth: NonZeroUsize::new(512).unwrap(),
+            storage_work_stealing: StorageWorkStealing::default(),
             rocksdb_total_memtables_ratio: 0.5, // (50% of rocksdb-total-memory-size)
             rocksdb_total_memory_size: NonZeroUsize::new(4_000_000_000).unwrap(), // 4GB
             rocksdb_bg_threads: None,
             rocksdb_high_priority_bg_threads: NonZeroU32::new(2).unwrap(),
             rocksdb_write_stall_threshold: std::time::Duration::from_secs(3).into(),
+            rocksdb_rate_limit_bytes_per_sec: None,
+            rocksdb_group_commit_max_latency: std::time::Duration::from_millis(1).into(),
+            rocksdb_group_commit_max_bytes: NonZeroUsize::new(1024 * 1024).unwrap(), // 1MiB
+            rocksdb_max_write_batch_size: None,
+            rocksdb_properties_poll_interval: std::time::Duration::from_secs(10).into(),
+            rocksdb_idle_memtable_flush_timeout: Some(
+                std::time::Duration::from_secs(5 * 60).into(),
+            ),
+            rocksdb_tombstone_compaction_interval: None,
+            rocksdb_backup_destination: None,
+            rocksdb_backup_interval: std::time::Duration::from_secs(60 * 60).into(),
+            rocksdb_backup_retention: NonZeroUsize::new(3).unwrap(),
+            rocksdb_restore_source: None,
             rocksdb: Default::default(),
         }
     }
@@ -334,6 +496,27 @@ pub struct ServiceClientOptions {
     pub request_identity_private_key_pem_file: Option<PathBuf>,
 }
 
+/// # Storage work stealing
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum StorageWorkStealing {
+    /// # Disabled
+    ///
+    /// Tasks only run on the thread pool of their priority.
+    #[default]
+    Disabled,
+    /// # High priority
+    ///
+    /// High priority tasks may run on idle threads of the low priority pool.
+    HighPriority,
+    /// # Both
+    ///
+    /// Tasks of either priority may run on idle threads of the other pool. Note that long
+    /// running low priority tasks can then delay high priority tasks.
+    Both,
+}
+
 /// # Log format
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 #[derive(Debug, Clone, Copy, Hash, Default, Serialize, Deserialize)]
@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -30,13 +31,46 @@ pub struct HttpOptions {
     /// Configuration for the HTTP/2 keep-alive mechanism, using PING frames.
     /// If unset, HTTP/2 keep-alive are disabled.
     pub http_keep_alive_options: Http2KeepAliveOptions,
+    /// # Connection pool
+    ///
+    /// Configuration for the pool of connections to the deployments.
+    pub connection_pool_options: ConnectionPoolOptions,
     /// # Proxy URI
     ///
     /// A URI, such as `http://127.0.0.1:10001`, of a server to which all invocations should be sent, with the `Host` header set to the deployment URI.
-    /// HTTPS proxy URIs are supported, but only HTTP endpoint traffic will be proxied currently.
+    /// Connections to HTTPS deployments are tunneled through the proxy with `CONNECT`, which requires an HTTP proxy URI.
+    /// With an HTTPS proxy URI, only HTTP endpoint traffic is proxied.
     /// Can be overridden by the `HTTP_PROXY` environment variable.
     #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
     pub http_proxy: Option<ProxyUri>,
+    /// # No proxy
+    ///
+    /// Hosts which are connected to directly rather than through the proxy. An entry matches the
+    /// host itself and its subdomains, e.g. `example.com` matches `api.example.com`, and `*`
+    /// matches every host. Can be overridden by the `NO_PROXY` environment variable, as a comma
+    /// separated list.
+    #[serde_as(
+        as = "serde_with::PickFirst<(_, serde_with::StringWithSeparator<serde_with::formats::CommaSeparator, String>)>"
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<String>"))]
+    pub no_proxy: Vec<String>,
+    /// # Trusted CA certificates PEM file
+    ///
+    /// A path to a file, such as "/var/secrets/ca.pem", which contains one or more CA certificates
+    /// in PEM format. They are trusted in addition to the native root certificates of the system
+    /// when connecting to HTTPS deployments, e.g. deployments using certificates of a private CA.
+    pub tls_ca_certificates_pem_file: Option<PathBuf>,
+    /// # Client certificate PEM file
+    ///
+    /// A path to a file which contains the certificate chain in PEM format, starting with the
+    /// client certificate, that is presented to HTTPS deployments requiring mutual TLS.
+    /// Must be set together with `tls-client-private-key-pem-file`.
+    pub tls_client_certificate_pem_file: Option<PathBuf>,
+    /// # Client private key PEM file
+    ///
+    /// A path to a file which contains exactly one private key in PEM format, matching the client
+    /// certificate set with `tls-client-certificate-pem-file`.
+    pub tls_client_private_key_pem_file: Option<PathBuf>,
 }
 
 /// # HTTP/2 Keep alive options
@@ -93,6 +127,47 @@ impl Http2KeepAliveOptions {
     }
 }
 
+/// # Connection pool options
+///
+/// Configuration for the pool of connections to the deployments. HTTP/2 requests to the same
+/// deployment are multiplexed on the pooled connections, as many streams per connection as the
+/// deployment allows.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, derive_builder::Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(default))]
+#[serde(rename_all = "kebab-case")]
+pub struct ConnectionPoolOptions {
+    /// # Idle timeout
+    ///
+    /// Sets how long idle connections are kept in the pool before being closed.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub idle_timeout: humantime::Duration,
+
+    /// # Max idle connections per deployment
+    ///
+    /// Sets the maximum number of idle connections kept in the pool per deployment.
+    /// If unset, the number of idle connections is not limited.
+    pub max_idle_per_host: Option<usize>,
+
+    /// # Keep-alive while idle
+    ///
+    /// Whether HTTP/2 keep-alive PING frames are sent on idle pooled connections too, so that
+    /// broken connections are detected before being used by an invocation.
+    pub keep_alive_while_idle: bool,
+}
+
+impl Default for ConnectionPoolOptions {
+    fn default() -> Self {
+        Self {
+            idle_timeout: (Duration::from_secs(90)).into(),
+            max_idle_per_host: None,
+            keep_alive_while_idle: false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, thiserror::Error)]
 #[error("invalid proxy Uri (must have scheme, authority, and path): {0}")]
 pub struct InvalidProxyUri(Uri);
@@ -152,6 +227,10 @@ impl ProxyUri {
         }
     }
 
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
     pub fn dst(&self, dst: Uri) -> Uri {
         // only proxy non TLS traffic, otherwise just pass through directly to underlying connector
         if dst.scheme() != Some(&Scheme::HTTPS) {
@@ -80,6 +80,55 @@ pub struct RocksDbOptions {
     /// Default: "except-detailed-timers"
     #[serde(skip_serializing_if = "Option::is_none")]
     rocksdb_statistics_level: Option<StatisticsLevel>,
+
+    /// # Maximum number of memtables
+    ///
+    /// The maximum number of memtables, both active and immutable, per column family. If unset,
+    /// the column family specific default is used.
+    ///
+    /// Supports hot-reloading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rocksdb_max_write_buffer_number: Option<NonZeroU32>,
+
+    /// # Compression
+    ///
+    /// Compression algorithm for SST files. If unset, the column family specific default is used.
+    /// Changing this only affects newly written files.
+    ///
+    /// Supports hot-reloading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rocksdb_compression: Option<CompressionType>,
+
+    /// # Compression per level
+    ///
+    /// Compression algorithm for each level of the LSM tree starting at L0, for example
+    /// `["none", "none", "lz4", "lz4", "lz4", "zstd", "zstd"]`. Takes precedence over
+    /// `rocksdb-compression`. If unset, the column family specific default is used. Changing this
+    /// only affects newly written files.
+    ///
+    /// Supports hot-reloading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rocksdb_compression_per_level: Option<Vec<CompressionType>>,
+
+    /// # Zstd dictionary size
+    ///
+    /// If set, rocksdb trains a zstd dictionary of up to this size for each SST file. This
+    /// improves the compression ratio of small blocks with similar contents (e.g. journal
+    /// entries). Only applies to levels that use zstd compression.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    rocksdb_zstd_max_dict_bytes: Option<NonZeroUsize>,
+
+    /// # Target SST file size
+    ///
+    /// Target size of SST files on level-1. If unset, the column family specific default is used.
+    ///
+    /// Supports hot-reloading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    rocksdb_target_file_size_base: Option<NonZeroUsize>,
 }
 
 impl RocksDbOptions {
@@ -107,6 +156,21 @@ impl RocksDbOptions {
         if self.rocksdb_statistics_level.is_none() {
             self.rocksdb_statistics_level = Some(common.rocksdb_statistics_level());
         }
+        if self.rocksdb_max_write_buffer_number.is_none() {
+            self.rocksdb_max_write_buffer_number = common.rocksdb_max_write_buffer_number;
+        }
+        if self.rocksdb_compression.is_none() {
+            self.rocksdb_compression = common.rocksdb_compression;
+        }
+        if self.rocksdb_compression_per_level.is_none() {
+            self.rocksdb_compression_per_level = common.rocksdb_compression_per_level.clone();
+        }
+        if self.rocksdb_zstd_max_dict_bytes.is_none() {
+            self.rocksdb_zstd_max_dict_bytes = common.rocksdb_zstd_max_dict_bytes;
+        }
+        if self.rocksdb_target_file_size_base.is_none() {
+            self.rocksdb_target_file_size_base = common.rocksdb_target_file_size_base;
+        }
     }
 
     pub fn rocksdb_write_buffer_size(&self) -> NonZeroUsize {
@@ -158,6 +222,37 @@ impl RocksDbOptions {
         self.rocksdb_statistics_level
             .unwrap_or(StatisticsLevel::ExceptDetailedTimers)
     }
+
+    pub fn rocksdb_max_write_buffer_number(&self) -> Option<NonZeroU32> {
+        self.rocksdb_max_write_buffer_number
+    }
+
+    pub fn rocksdb_compression(&self) -> Option<CompressionType> {
+        self.rocksdb_compression
+    }
+
+    pub fn rocksdb_compression_per_level(&self) -> Option<&[CompressionType]> {
+        self.rocksdb_compression_per_level.as_deref()
+    }
+
+    pub fn rocksdb_zstd_max_dict_bytes(&self) -> Option<NonZeroUsize> {
+        self.rocksdb_zstd_max_dict_bytes
+    }
+
+    pub fn rocksdb_target_file_size_base(&self) -> Option<NonZeroUsize> {
+        self.rocksdb_target_file_size_base
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(rename = "RocksDbCompression"))]
+#[serde(rename_all = "kebab-case")]
+pub enum CompressionType {
+    None,
+    Snappy,
+    Lz4,
+    Zstd,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::HashMap;
 use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use std::time::Duration;
@@ -35,6 +36,48 @@ pub struct WorkerOptions {
     /// The number of timers in memory limit is used to bound the amount of timers loaded in memory. If this limit is set, when exceeding it, the timers farther in the future will be spilled to disk.
     num_timers_in_memory_limit: Option<NonZeroUsize>,
 
+    /// # Timer bucket width
+    ///
+    /// If set, the timers are loaded from disk in buckets of this width, starting at the next due timer. Only the timers of the current bucket are kept in memory, which bounds the amount of timers the partition processor reads when many timers are pending.
+    #[serde(with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    timer_bucket_width: Option<humantime::Duration>,
+
+    /// # Partition snapshot destination
+    ///
+    /// Object store location to which snapshots of the partition stores are periodically uploaded, e.g. `s3://bucket/prefix`, `gs://bucket/prefix` or `file:///path/to/dir`. A snapshot contains the partition's state together with the log position it reflects, it allows new partition processors to bootstrap without replaying the whole log. Credentials are picked up from the environment. Snapshots are disabled if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot_destination: Option<String>,
+
+    /// # Partition snapshot interval
+    ///
+    /// How often a new snapshot of every partition is taken.
+    #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    snapshot_interval: humantime::Duration,
+
+    /// # Partition snapshot retention
+    ///
+    /// Number of snapshots to retain per partition. Older snapshots are deleted from the object store after every successful snapshot.
+    snapshot_retention: NonZeroUsize,
+
+    /// # Inbox length limit
+    ///
+    /// Maximum number of entries waiting in the inbox of a single virtual object or workflow key. Invocations of a key whose inbox is full are handled according to the inbox overflow policy, which keeps a single hot key from consuming unbounded storage. Unlimited if unset.
+    max_inbox_length: Option<NonZeroUsize>,
+
+    /// # Inbox overflow policy
+    ///
+    /// How new invocations of a key whose inbox reached the inbox length limit are handled.
+    inbox_overflow_policy: InboxOverflowPolicy,
+
+    /// # Dead letter retention
+    ///
+    /// How long the dead letter of an invocation which the invoker gave up on is kept for inspection and redrive. Dead letters are deleted once the retention expired.
+    #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    dead_letter_retention: humantime::Duration,
+
     pub storage: StorageOptions,
 
     pub invoker: InvokerOptions,
@@ -63,6 +106,34 @@ impl WorkerOptions {
     pub fn num_timers_in_memory_limit(&self) -> Option<usize> {
         self.num_timers_in_memory_limit.map(Into::into)
     }
+
+    pub fn timer_bucket_width(&self) -> Option<Duration> {
+        self.timer_bucket_width.map(Into::into)
+    }
+
+    pub fn snapshot_destination(&self) -> Option<&str> {
+        self.snapshot_destination.as_deref()
+    }
+
+    pub fn snapshot_interval(&self) -> Duration {
+        self.snapshot_interval.into()
+    }
+
+    pub fn snapshot_retention(&self) -> usize {
+        self.snapshot_retention.get()
+    }
+
+    pub fn max_inbox_length(&self) -> Option<usize> {
+        self.max_inbox_length.map(Into::into)
+    }
+
+    pub fn inbox_overflow_policy(&self) -> InboxOverflowPolicy {
+        self.inbox_overflow_policy
+    }
+
+    pub fn dead_letter_retention(&self) -> Duration {
+        self.dead_letter_retention.into()
+    }
 }
 
 impl Default for WorkerOptions {
@@ -70,6 +141,13 @@ impl Default for WorkerOptions {
         Self {
             internal_queue_length: NonZeroUsize::new(64).unwrap(),
             num_timers_in_memory_limit: None,
+            timer_bucket_width: None,
+            snapshot_destination: None,
+            snapshot_interval: Duration::from_secs(60 * 60).into(),
+            snapshot_retention: NonZeroUsize::new(3).unwrap(),
+            max_inbox_length: None,
+            inbox_overflow_policy: InboxOverflowPolicy::default(),
+            dead_letter_retention: Duration::from_secs(7 * 24 * 60 * 60).into(),
             storage: StorageOptions::default(),
             invoker: Default::default(),
             bootstrap_num_partitions: NonZeroU64::new(64).unwrap(),
@@ -77,6 +155,22 @@ impl Default for WorkerOptions {
     }
 }
 
+/// # Inbox overflow policy
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum InboxOverflowPolicy {
+    /// # Reject new
+    ///
+    /// Fail the new invocation.
+    #[default]
+    RejectNew,
+    /// # Drop oldest
+    ///
+    /// Fail the longest waiting invocation in the inbox to make room for the new one.
+    DropOldest,
+}
+
 /// # Invoker options
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize, derive_builder::Builder)]
@@ -119,6 +213,12 @@ pub struct InvokerOptions {
     #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub abort_timeout: humantime::Duration,
 
+    /// # Timeouts per service
+    ///
+    /// Overrides of the 'inactivity timeout' and 'abort timeout', keyed by the service name.
+    /// Services without an override use the timeouts above.
+    service_timeouts: HashMap<String, ServiceTimeoutOptions>,
+
     /// # Message size warning
     ///
     /// Threshold to log a warning in case protocol messages coming from a service are larger than the specified amount.
@@ -144,6 +244,101 @@ pub struct InvokerOptions {
     /// Number of concurrent invocations that can be processed by the invoker.
     concurrent_invocations_limit: Option<NonZeroUsize>,
 
+    /// # Limit number of concurrent invocations per service
+    ///
+    /// Number of concurrent invocations of a service, keyed by the service name, that can be
+    /// processed by the invoker. Invocations of a service that reached its limit wait until one
+    /// of its running invocations ends, without holding up the invocations of other services.
+    /// Services without a limit are only bound by the concurrent invocations limit.
+    service_concurrency_limits: HashMap<String, NonZeroUsize>,
+
+    /// # Limit number of concurrent invocations per deployment
+    ///
+    /// Number of concurrent invocations that can be processed by the invoker against a single
+    /// deployment, so that a slow deployment can't take up all the invoker slots. Invocations
+    /// are counted against the deployment they are pinned to, or against the deployment they
+    /// would be pinned to if they didn't start yet. Invocations of a deployment that reached its
+    /// limit are handed back to their partition, where they wait for the
+    /// 'deployment saturation retry interval' before being invoked again.
+    deployment_concurrency_limit: Option<NonZeroUsize>,
+
+    /// # Deployment saturation retry interval
+    ///
+    /// How long the invocations of a deployment that reached the 'deployment concurrency limit'
+    /// wait in their partition before being invoked again.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub deployment_saturation_retry_interval: humantime::Duration,
+
+    /// # Journal memory budget
+    ///
+    /// Bytes of journal entries that the invoker can hold in memory to replay them to the
+    /// deployments. A journal handed over by the partition processor is only kept in memory if it
+    /// fits in the remaining budget, otherwise it's read again from storage. Entries read from
+    /// storage wait for budget to be available before being sent, delaying the replays while the
+    /// budget is exhausted. Changes require a restart. Unlimited if unset.
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    journal_memory_budget: Option<NonZeroUsize>,
+
+    /// # Journal cache size
+    ///
+    /// Bytes of journal entries that the invoker keeps in memory for the invocations waiting to
+    /// be retried, so that retrying after a transient failure doesn't read the whole journal
+    /// from storage again. The least recently cached journals are evicted first. Changes require
+    /// a restart. Disabled if unset.
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    journal_cache_size: Option<NonZeroUsize>,
+
+    /// # Circuit breaker failure threshold
+    ///
+    /// Number of consecutive connection failures or 5xx responses of a deployment after which
+    /// the invoker stops sending requests to it, and fails its invocations fast instead. Once
+    /// the 'circuit breaker open duration' elapsed, a single invocation probes the deployment,
+    /// and requests resume if it succeeds. If unset, the circuit breaker is disabled.
+    circuit_breaker_failure_threshold: Option<NonZeroUsize>,
+
+    /// # Circuit breaker open duration
+    ///
+    /// How long the invoker stops sending requests to a deployment which reached the
+    /// 'circuit breaker failure threshold', before probing it again.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub circuit_breaker_open_duration: humantime::Duration,
+
+    /// # Health check interval
+    ///
+    /// How often the invoker probes the HTTP deployments with a `GET` request to the 'health
+    /// check path'. A deployment which doesn't respond with a 2xx status within the interval is
+    /// unhealthy: its pooled connections are closed, and its invocations fail fast until it
+    /// passes a health check again. Changes require a restart. Disabled if unset.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    health_check_interval: Option<humantime::Duration>,
+
+    /// # Health check path
+    ///
+    /// Path probed by the health checks, relative to the address of the deployments.
+    pub health_check_path: String,
+
+    /// # Drain grace period
+    ///
+    /// When this node shuts down while leading a partition, the in-flight invocations of the
+    /// partition get this long to suspend or complete before they are aborted. Invocations of the
+    /// partition that didn't start yet are left to the next leader.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub drain_grace_period: humantime::Duration,
+
     // -- Private config options (not exposed in the schema)
     #[cfg_attr(feature = "schemars", schemars(skip))]
     #[serde(skip_serializing_if = "std::ops::Not::not", default)]
@@ -164,6 +359,51 @@ impl InvokerOptions {
     pub fn message_size_limit(&self) -> Option<usize> {
         self.message_size_limit.map(Into::into)
     }
+
+    pub fn circuit_breaker_failure_threshold(&self) -> Option<usize> {
+        self.circuit_breaker_failure_threshold.map(Into::into)
+    }
+
+    pub fn deployment_concurrency_limit(&self) -> Option<usize> {
+        self.deployment_concurrency_limit.map(Into::into)
+    }
+
+    pub fn journal_memory_budget(&self) -> Option<usize> {
+        self.journal_memory_budget.map(Into::into)
+    }
+
+    pub fn journal_cache_size(&self) -> Option<usize> {
+        self.journal_cache_size.map(Into::into)
+    }
+
+    pub fn health_check_interval(&self) -> Option<Duration> {
+        self.health_check_interval.map(Into::into)
+    }
+
+    /// Inactivity timeout of the invocations of the given service.
+    pub fn service_inactivity_timeout(&self, service_name: &str) -> Duration {
+        self.service_timeouts
+            .get(service_name)
+            .and_then(|timeouts| timeouts.inactivity_timeout)
+            .unwrap_or(self.inactivity_timeout)
+            .into()
+    }
+
+    /// Abort timeout of the invocations of the given service.
+    pub fn service_abort_timeout(&self, service_name: &str) -> Duration {
+        self.service_timeouts
+            .get(service_name)
+            .and_then(|timeouts| timeouts.abort_timeout)
+            .unwrap_or(self.abort_timeout)
+            .into()
+    }
+
+    pub fn service_concurrency_limit(&self, service_name: &str) -> Option<usize> {
+        self.service_concurrency_limits
+            .get(service_name)
+            .copied()
+            .map(Into::into)
+    }
 }
 
 impl Default for InvokerOptions {
@@ -178,16 +418,55 @@ impl Default for InvokerOptions {
             ),
             inactivity_timeout: Duration::from_secs(60).into(),
             abort_timeout: Duration::from_secs(60).into(),
+            service_timeouts: HashMap::new(),
             message_size_warning: NonZeroUsize::new(10_000_000).unwrap(), // 10MB
             message_size_limit: None,
             tmp_dir: None,
             concurrent_invocations_limit: None,
+            service_concurrency_limits: HashMap::new(),
+            deployment_concurrency_limit: None,
+            deployment_saturation_retry_interval: Duration::from_secs(1).into(),
+            journal_memory_budget: None,
+            journal_cache_size: None,
+            circuit_breaker_failure_threshold: Some(NonZeroUsize::new(5).unwrap()),
+            circuit_breaker_open_duration: Duration::from_secs(5).into(),
+            health_check_interval: None,
+            health_check_path: "/health".to_owned(),
+            drain_grace_period: Duration::from_secs(10).into(),
             disable_eager_state: false,
         }
     }
 }
 
+/// # Service timeout options
+///
+/// Overrides of the invoker timeouts for the invocations of a service.
+#[serde_as]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct ServiceTimeoutOptions {
+    /// # Inactivity timeout
+    ///
+    /// Overrides the 'inactivity timeout' of the invoker. Can be configured using the
+    /// [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde(default)]
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub inactivity_timeout: Option<humantime::Duration>,
+
+    /// # Abort timeout
+    ///
+    /// Overrides the 'abort timeout' of the invoker. Can be configured using the
+    /// [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde(default)]
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub abort_timeout: Option<humantime::Duration>,
+}
+
 /// # Storage options
+#[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize, derive_builder::Builder)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "schemars", schemars(rename = "StorageOptions", default))]
@@ -197,12 +476,23 @@ pub struct StorageOptions {
     #[serde(flatten)]
     pub rocksdb: RocksDbOptions,
 
+    /// # Journal blob threshold
+    ///
+    /// Journal entries larger than this threshold are stored in a dedicated column family and referenced from the journal by their hash. This keeps journal scans cheap and avoids huge write batches.
+    #[serde_as(as = "NonZeroByteCount")]
+    #[cfg_attr(feature = "schemars", schemars(with = "NonZeroByteCount"))]
+    journal_blob_threshold: NonZeroUsize,
+
     #[cfg(any(test, feature = "test-util"))]
     #[serde(skip, default = "super::default_arc_tmp")]
     data_dir: std::sync::Arc<tempfile::TempDir>,
 }
 
 impl StorageOptions {
+    pub fn journal_blob_threshold(&self) -> usize {
+        self.journal_blob_threshold.get()
+    }
+
     #[cfg(not(any(test, feature = "test-util")))]
     pub fn data_dir(&self) -> PathBuf {
         super::data_dir("db")
@@ -223,6 +513,7 @@ impl Default for StorageOptions {
 
         StorageOptions {
             rocksdb,
+            journal_blob_threshold: NonZeroUsize::new(64 * 1024).unwrap(),
             #[cfg(any(test, feature = "test-util"))]
             data_dir: super::default_arc_tmp(),
         }
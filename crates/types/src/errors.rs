@@ -73,9 +73,13 @@ pub mod codes {
     pub const NOT_FOUND: InvocationErrorCode = InvocationErrorCode(404);
     pub const INTERNAL: InvocationErrorCode = InvocationErrorCode(500);
     pub const UNKNOWN: InvocationErrorCode = INTERNAL;
+    pub const TIMEOUT: InvocationErrorCode = InvocationErrorCode(408);
     pub const ABORTED: InvocationErrorCode = InvocationErrorCode(409);
     pub const KILLED: InvocationErrorCode = ABORTED;
+    pub const CONFLICT: InvocationErrorCode = ABORTED;
     pub const GONE: InvocationErrorCode = InvocationErrorCode(410);
+    pub const TOO_MANY_REQUESTS: InvocationErrorCode = InvocationErrorCode(429);
+    pub const UNAVAILABLE: InvocationErrorCode = InvocationErrorCode(503);
     pub const JOURNAL_MISMATCH: InvocationErrorCode = InvocationErrorCode(570);
     pub const PROTOCOL_VIOLATION: InvocationErrorCode = InvocationErrorCode(571);
 }
@@ -204,6 +208,18 @@ pub const CANCELED_INVOCATION_ERROR: InvocationError =
 
 pub const GONE_INVOCATION_ERROR: InvocationError = InvocationError::new_static(codes::GONE, "gone");
 
+pub const NOT_FOUND_INVOCATION_ERROR: InvocationError =
+    InvocationError::new_static(codes::NOT_FOUND, "invocation not found");
+
+pub const DEADLINE_EXCEEDED_INVOCATION_ERROR: InvocationError =
+    InvocationError::new_static(codes::TIMEOUT, "execution deadline exceeded");
+
+pub const INBOX_FULL_INVOCATION_ERROR: InvocationError =
+    InvocationError::new_static(codes::TOO_MANY_REQUESTS, "inbox is full");
+
+pub const ALREADY_COMPLETED_PROMISE_ERROR: InvocationError =
+    InvocationError::new_static(codes::CONFLICT, "promise already completed");
+
 /// Error parsing/decoding a resource ID.
 #[derive(Debug, thiserror::Error, Clone, Eq, PartialEq)]
 pub enum IdDecodeError {
@@ -528,6 +528,42 @@ impl FromStr for InvocationId {
     }
 }
 
+/// Identifies an entry of an invocation journal.
+#[derive(Eq, Hash, PartialEq, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct JournalEntryId {
+    invocation_id: InvocationId,
+    journal_index: EntryIndex,
+}
+
+impl JournalEntryId {
+    pub const fn from_parts(invocation_id: InvocationId, journal_index: EntryIndex) -> Self {
+        Self {
+            invocation_id,
+            journal_index,
+        }
+    }
+
+    pub fn invocation_id(&self) -> InvocationId {
+        self.invocation_id
+    }
+
+    pub fn journal_index(&self) -> EntryIndex {
+        self.journal_index
+    }
+}
+
+impl From<(InvocationId, EntryIndex)> for JournalEntryId {
+    fn from((invocation_id, journal_index): (InvocationId, EntryIndex)) -> Self {
+        Self::from_parts(invocation_id, journal_index)
+    }
+}
+
+impl WithPartitionKey for JournalEntryId {
+    fn partition_key(&self) -> PartitionKey {
+        self.invocation_id.partition_key()
+    }
+}
+
 #[derive(Eq, Hash, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct IdempotencyId {
     /// Identifies the invoked service
@@ -112,6 +112,10 @@ impl InvocationTargetType {
                 | InvocationTargetType::Workflow(WorkflowHandlerType::Workflow)
         )
     }
+
+    pub fn can_use_promises(&self) -> bool {
+        matches!(self, InvocationTargetType::Workflow(_))
+    }
 }
 
 impl fmt::Display for InvocationTargetType {
@@ -266,6 +270,9 @@ pub struct ServiceInvocation {
     pub headers: Vec<Header>,
     /// Time when the request should be executed
     pub execution_time: Option<MillisSinceEpoch>,
+    /// Time by which the invocation must have completed, otherwise it's canceled and fails
+    /// with a timeout
+    pub execution_deadline: Option<MillisSinceEpoch>,
     pub completion_retention_time: Option<Duration>,
     pub idempotency_key: Option<ByteString>,
 }
@@ -285,6 +292,7 @@ impl ServiceInvocation {
             span_context: ServiceInvocationSpanContext::empty(),
             headers: vec![],
             execution_time: None,
+            execution_deadline: None,
             completion_retention_time: None,
             idempotency_key: None,
         }
@@ -666,6 +674,23 @@ pub enum TerminationFlavor {
     Cancel,
 }
 
+/// Message to run a dead lettered invocation again.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DeadLetterRedrive {
+    /// Id of the invocation that was dead lettered.
+    pub invocation_id: InvocationId,
+    /// Id of the new invocation, must have the same partition key as `invocation_id`.
+    pub new_invocation_id: InvocationId,
+}
+
+/// Message to subscribe to the result of an invocation which might still be running.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AttachInvocationRequest {
+    pub invocation_id: InvocationId,
+    /// Where to send the result of the invocation to once it completes.
+    pub response_sink: ServiceInvocationResponseSink,
+}
+
 // A hack to allow spancontext to be serialized.
 // Details in https://github.com/open-telemetry/opentelemetry-rust/issues/576#issuecomment-1253396100
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -791,6 +816,7 @@ mod mocks {
                 span_context: Default::default(),
                 headers: vec![],
                 execution_time: None,
+                execution_deadline: None,
                 completion_retention_time: None,
                 idempotency_key: None,
             }
@@ -30,6 +30,11 @@ pub enum Entry {
     GetStateKeys(GetStateKeysEntry),
     ClearAllState,
 
+    // Durable promises
+    GetPromise(GetPromiseEntry),
+    PeekPromise(PeekPromiseEntry),
+    CompletePromise(CompletePromiseEntry),
+
     // Syscalls
     Sleep(SleepEntry),
     Call(InvokeEntry),
@@ -77,6 +82,32 @@ impl Entry {
         Entry::ClearAllState
     }
 
+    pub fn get_promise(key: impl Into<ByteString>, value: Option<EntryResult>) -> Self {
+        Entry::GetPromise(GetPromiseEntry {
+            key: key.into(),
+            value,
+        })
+    }
+
+    pub fn peek_promise(key: impl Into<ByteString>, value: Option<PeekPromiseResult>) -> Self {
+        Entry::PeekPromise(PeekPromiseEntry {
+            key: key.into(),
+            value,
+        })
+    }
+
+    pub fn complete_promise(
+        key: impl Into<ByteString>,
+        completion: EntryResult,
+        value: Option<CompletePromiseResult>,
+    ) -> Self {
+        Entry::CompletePromise(CompletePromiseEntry {
+            key: key.into(),
+            completion,
+            value,
+        })
+    }
+
     pub fn invoke(request: InvokeRequest, result: Option<EntryResult>) -> Self {
         Entry::Call(InvokeEntry { request, result })
     }
@@ -134,6 +165,15 @@ impl From<ResponseResult> for CompletionResult {
     }
 }
 
+impl From<EntryResult> for CompletionResult {
+    fn from(value: EntryResult) -> Self {
+        match value {
+            EntryResult::Success(bytes) => CompletionResult::Success(bytes),
+            EntryResult::Failure(code, message) => CompletionResult::Failure(code, message),
+        }
+    }
+}
+
 impl From<&InvocationError> for CompletionResult {
     fn from(value: &InvocationError) -> Self {
         CompletionResult::Failure(value.code(), value.message().into())
@@ -149,6 +189,9 @@ pub enum EntryType {
     ClearState,
     GetStateKeys,
     ClearAllState,
+    GetPromise,
+    PeekPromise,
+    CompletePromise,
     Sleep,
     Call,
     OneWayCall,
@@ -192,6 +235,9 @@ mod private {
     pub trait Sealed {}
     impl Sealed for GetStateEntry {}
     impl Sealed for GetStateKeysEntry {}
+    impl Sealed for GetPromiseEntry {}
+    impl Sealed for PeekPromiseEntry {}
+    impl Sealed for CompletePromiseEntry {}
     impl Sealed for SleepEntry {}
     impl Sealed for InvokeEntry {}
     impl Sealed for AwakeableEntry {}
@@ -254,6 +300,57 @@ impl CompletableEntry for GetStateKeysEntry {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetPromiseEntry {
+    pub key: ByteString,
+    pub value: Option<EntryResult>,
+}
+
+impl CompletableEntry for GetPromiseEntry {
+    fn is_completed(&self) -> bool {
+        self.value.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeekPromiseResult {
+    Empty,
+    Success(Bytes),
+    Failure(InvocationErrorCode, ByteString),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeekPromiseEntry {
+    pub key: ByteString,
+    pub value: Option<PeekPromiseResult>,
+}
+
+impl CompletableEntry for PeekPromiseEntry {
+    fn is_completed(&self) -> bool {
+        self.value.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletePromiseResult {
+    Done,
+    /// The promise was already completed.
+    Failure(InvocationErrorCode, ByteString),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletePromiseEntry {
+    pub key: ByteString,
+    pub completion: EntryResult,
+    pub value: Option<CompletePromiseResult>,
+}
+
+impl CompletableEntry for CompletePromiseEntry {
+    fn is_completed(&self) -> bool {
+        self.value.is_some()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SleepResult {
     Fired,
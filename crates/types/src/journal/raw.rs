@@ -117,6 +117,15 @@ pub enum EntryHeader<CallEnrichmentResult, AwakeableEnrichmentResult> {
         is_completed: bool,
     },
     ClearAllState,
+    GetPromise {
+        is_completed: bool,
+    },
+    PeekPromise {
+        is_completed: bool,
+    },
+    CompletePromise {
+        is_completed: bool,
+    },
     Sleep {
         is_completed: bool,
     },
@@ -151,6 +160,9 @@ impl<InvokeEnrichmentResult, AwakeableEnrichmentResult>
             EntryHeader::ClearState { .. } => None,
             EntryHeader::ClearAllState => None,
             EntryHeader::GetStateKeys { is_completed, .. } => Some(*is_completed),
+            EntryHeader::GetPromise { is_completed, .. } => Some(*is_completed),
+            EntryHeader::PeekPromise { is_completed, .. } => Some(*is_completed),
+            EntryHeader::CompletePromise { is_completed, .. } => Some(*is_completed),
             EntryHeader::Sleep { is_completed, .. } => Some(*is_completed),
             EntryHeader::Call { is_completed, .. } => Some(*is_completed),
             EntryHeader::OneWayCall { .. } => None,
@@ -170,6 +182,9 @@ impl<InvokeEnrichmentResult, AwakeableEnrichmentResult>
             EntryHeader::ClearState { .. } => {}
             EntryHeader::GetStateKeys { is_completed, .. } => *is_completed = true,
             EntryHeader::ClearAllState => {}
+            EntryHeader::GetPromise { is_completed, .. } => *is_completed = true,
+            EntryHeader::PeekPromise { is_completed, .. } => *is_completed = true,
+            EntryHeader::CompletePromise { is_completed, .. } => *is_completed = true,
             EntryHeader::Sleep { is_completed, .. } => *is_completed = true,
             EntryHeader::Call { is_completed, .. } => *is_completed = true,
             EntryHeader::OneWayCall { .. } => {}
@@ -189,6 +204,9 @@ impl<InvokeEnrichmentResult, AwakeableEnrichmentResult>
             EntryHeader::ClearState { .. } => EntryType::ClearState,
             EntryHeader::GetStateKeys { .. } => EntryType::GetStateKeys,
             EntryHeader::ClearAllState => EntryType::ClearAllState,
+            EntryHeader::GetPromise { .. } => EntryType::GetPromise,
+            EntryHeader::PeekPromise { .. } => EntryType::PeekPromise,
+            EntryHeader::CompletePromise { .. } => EntryType::CompletePromise,
             EntryHeader::Sleep { .. } => EntryType::Sleep,
             EntryHeader::Call { .. } => EntryType::Call,
             EntryHeader::OneWayCall { .. } => EntryType::OneWayCall,
@@ -210,6 +228,11 @@ impl<InvokeEnrichmentResult, AwakeableEnrichmentResult>
                 EntryHeader::GetStateKeys { is_completed }
             }
             EntryHeader::ClearAllState => EntryHeader::ClearAllState,
+            EntryHeader::GetPromise { is_completed } => EntryHeader::GetPromise { is_completed },
+            EntryHeader::PeekPromise { is_completed } => EntryHeader::PeekPromise { is_completed },
+            EntryHeader::CompletePromise { is_completed } => {
+                EntryHeader::CompletePromise { is_completed }
+            }
             EntryHeader::Sleep { is_completed } => EntryHeader::Sleep { is_completed },
             EntryHeader::Call { is_completed, .. } => EntryHeader::Call {
                 is_completed,
@@ -260,6 +283,13 @@ pub trait RawEntryCodec {
         input_message: Bytes,
     ) -> enriched::EnrichedRawEntry;
 
+    /// Inverse of [`Self::serialize_as_input_entry`], returns the headers and the input message.
+    fn deserialize_input_entry(
+        entry_value: Bytes,
+    ) -> Result<(Vec<Header>, Bytes), RawEntryCodecError>;
+
+    fn serialize_as_output_entry(result: EntryResult) -> enriched::EnrichedRawEntry;
+
     fn serialize_get_state_keys_completion(keys: Vec<Bytes>) -> CompletionResult;
 
     fn deserialize(entry_type: EntryType, entry_value: Bytes) -> Result<Entry, RawEntryCodecError>;
@@ -107,6 +107,21 @@ impl Logs {
         }
     }
 
+    /// The smallest log id which is larger than the ids of all logs.
+    pub fn next_log_id(&self) -> LogId {
+        self.logs
+            .keys()
+            .max()
+            .map_or(LogId::MIN, |log_id| LogId::from(u64::from(*log_id) + 1))
+    }
+
+    /// Adds a log with a chain of the given loglet provider kind, which uses the log id as
+    /// loglet identifier like [`create_static_metadata`]. The version is not changed.
+    pub fn add_log(&mut self, log_id: LogId, kind: ProviderKind) {
+        let config = LogletParams::from(log_id.to_string());
+        self.logs.insert(log_id, Chain::new(kind, config));
+    }
+
     pub fn tail_segment(&self, log_id: LogId) -> Option<Segment> {
         self.logs
             .get(&log_id)
@@ -190,4 +205,16 @@ mod tests {
         assert_eq!(ProviderKind::Local, loglet_config.kind);
         assert_eq!("test".to_string(), loglet_config.params.0);
     }
+
+    #[test]
+    fn test_add_log() {
+        let mut logs = create_static_metadata(ProviderKind::Local, 2);
+        assert_eq!(LogId::from(2), logs.next_log_id());
+
+        logs.add_log(logs.next_log_id(), ProviderKind::InMemory);
+        assert_eq!(LogId::from(3), logs.next_log_id());
+        let_assert!(Some(segment) = logs.tail_segment(LogId::from(2)));
+        assert_eq!(ProviderKind::InMemory, segment.config.kind);
+        assert_eq!("2".to_string(), segment.config.params.0);
+    }
 }
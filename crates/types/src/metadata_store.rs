@@ -18,10 +18,15 @@ pub mod keys {
     pub static BIFROST_CONFIG_KEY: ByteString = ByteString::from_static("bifrost_config");
     pub static PARTITION_TABLE_KEY: ByteString = ByteString::from_static("partition_table");
     pub static PARTITION_PROCESSOR_EPOCH_PREFIX: &str = "pp_epoch";
+    pub static PARTITION_SPLIT_PREFIX: &str = "partition_split";
 
     pub static SCHEMA_INFORMATION_KEY: ByteString = ByteString::from_static("schema_registry");
 
     pub fn partition_processor_epoch_key(partition_id: PartitionId) -> ByteString {
         ByteString::from(format!("{PARTITION_PROCESSOR_EPOCH_PREFIX}_{partition_id}"))
     }
+
+    pub fn partition_split_key(partition_id: PartitionId) -> ByteString {
+        ByteString::from(format!("{PARTITION_SPLIT_PREFIX}_{partition_id}"))
+    }
 }
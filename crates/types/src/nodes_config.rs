@@ -18,7 +18,7 @@ use serde_with::serde_as;
 
 use crate::net::AdvertisedAddress;
 use crate::{flexbuffers_storage_encode_decode, GenerationalNodeId, NodeId, PlainNodeId};
-use crate::{Version, Versioned};
+use crate::{StateMachineVersion, Version, Versioned};
 
 #[derive(Debug, thiserror::Error)]
 pub enum NodesConfigError {
@@ -72,6 +72,10 @@ pub struct NodeConfig {
     pub current_generation: GenerationalNodeId,
     pub address: AdvertisedAddress,
     pub roles: EnumSet<Role>,
+    /// Latest partition state machine version the node supports. Nodes which registered before
+    /// the version was advertised only support the initial one.
+    #[serde(default)]
+    pub state_machine_version: StateMachineVersion,
 }
 
 impl NodeConfig {
@@ -86,6 +90,7 @@ impl NodeConfig {
             current_generation,
             address,
             roles,
+            state_machine_version: StateMachineVersion::LATEST,
         }
     }
 }
@@ -178,6 +183,20 @@ impl NodesConfiguration {
         })
     }
 
+    /// Returns the minimum state machine version supported by all worker nodes or `None` if there
+    /// are no workers. Partitions must not be upgraded beyond it.
+    pub fn min_state_machine_version(&self) -> Option<StateMachineVersion> {
+        self.nodes
+            .values()
+            .filter_map(|maybe| match maybe {
+                MaybeNode::Node(node) if node.roles.contains(Role::Worker) => {
+                    Some(node.state_machine_version)
+                }
+                _ => None,
+            })
+            .min()
+    }
+
     /// Returns the maximum known plain node id.
     pub fn max_plain_node_id(&self) -> Option<PlainNodeId> {
         self.nodes.keys().max().cloned()
@@ -272,4 +291,50 @@ mod tests {
         let found = config.find_node_by_name("nodeX").expect("known id");
         assert_eq!(&node, found);
     }
+
+    #[test]
+    fn test_min_state_machine_version() {
+        let mut config = NodesConfiguration::new(Version::MIN, "test-cluster".to_owned());
+        let address: AdvertisedAddress = "unix:/tmp/my_socket".parse().unwrap();
+        assert_eq!(None, config.min_state_machine_version());
+
+        let node = NodeConfig::new(
+            "node1".to_owned(),
+            GenerationalNodeId::new(1, 1),
+            address.clone(),
+            EnumSet::only(Role::Worker),
+        );
+        config.upsert_node(node);
+        assert_eq!(
+            Some(StateMachineVersion::LATEST),
+            config.min_state_machine_version()
+        );
+
+        // admin only nodes don't run partition processors
+        let mut admin = NodeConfig::new(
+            "node2".to_owned(),
+            GenerationalNodeId::new(2, 1),
+            address.clone(),
+            EnumSet::only(Role::Admin),
+        );
+        admin.state_machine_version = StateMachineVersion::V1;
+        config.upsert_node(admin);
+        assert_eq!(
+            Some(StateMachineVersion::LATEST),
+            config.min_state_machine_version()
+        );
+
+        let mut old_worker = NodeConfig::new(
+            "node3".to_owned(),
+            GenerationalNodeId::new(3, 1),
+            address,
+            EnumSet::only(Role::Worker),
+        );
+        old_worker.state_machine_version = StateMachineVersion::V1;
+        config.upsert_node(old_worker);
+        assert_eq!(
+            Some(StateMachineVersion::V1),
+            config.min_state_machine_version()
+        );
+    }
 }
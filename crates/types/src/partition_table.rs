@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use crate::identifiers::{PartitionId, PartitionKey};
+use crate::logs::Lsn;
 use crate::{flexbuffers_storage_encode_decode, Version, Versioned};
 use std::borrow::Borrow;
 use std::ops::RangeInclusive;
@@ -24,10 +25,27 @@ pub trait FindPartition {
     ) -> Result<PartitionId, PartitionTableError>;
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum PartitionSplitError {
+    #[error("unknown partition {0}")]
+    UnknownPartition(PartitionId),
+    #[error("partition {0} has already been split")]
+    AlreadySplit(PartitionId),
+    #[error("partition id {0} is already in use")]
+    PartitionIdInUse(PartitionId),
+    #[error("split key {0} does not split the key range of the partition")]
+    InvalidSplitKey(PartitionKey),
+}
+
+/// Divides the key space into `num_partitions` partitions of the same size. Partitions can be
+/// split later on, the split partition's keys are owned by the two new partitions from then on.
 #[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct FixedPartitionTable {
     version: Version,
     num_partitions: u64,
+    /// Completed splits in the order they have been registered
+    #[serde(default)]
+    splits: Vec<PartitionSplit>,
 }
 
 impl FixedPartitionTable {
@@ -37,25 +55,80 @@ impl FixedPartitionTable {
         Self {
             version,
             num_partitions,
+            splits: Vec::new(),
         }
     }
 
+    /// Number of partitions the key space has initially been divided into.
     pub fn num_partitions(&self) -> u64 {
         self.num_partitions
     }
 
+    /// Returns the key range of the partition. Partitions which have been split keep their range.
     pub fn partition_range(
         &self,
         partition_id: PartitionId,
     ) -> Option<RangeInclusive<PartitionKey>> {
-        if *partition_id >= self.num_partitions {
-            None
-        } else {
-            Some(Self::partition_id_to_partition_range(
+        if *partition_id < self.num_partitions {
+            return Some(Self::partition_id_to_partition_range(
                 self.num_partitions,
                 partition_id,
-            ))
+            ));
         }
+
+        self.splits.iter().find_map(|split| {
+            let (left_range, right_range) =
+                split.split_range(&self.partition_range(split.partition_id)?)?;
+            if split.left_partition_id == partition_id {
+                Some(left_range)
+            } else if split.right_partition_id == partition_id {
+                Some(right_range)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Ids of all partitions, including the ones which have been split.
+    pub fn partition_ids(&self) -> impl Iterator<Item = PartitionId> + '_ {
+        (0..self.num_partitions).map(PartitionId::from).chain(
+            self.splits
+                .iter()
+                .flat_map(|split| [split.left_partition_id, split.right_partition_id]),
+        )
+    }
+
+    /// Returns true if the partition has been split and doesn't own any keys anymore.
+    pub fn is_split(&self, partition_id: PartitionId) -> bool {
+        self.splits
+            .iter()
+            .any(|split| split.partition_id == partition_id)
+    }
+
+    /// Registers a completed split. The version is not changed.
+    pub fn add_split(&mut self, split: PartitionSplit) -> Result<(), PartitionSplitError> {
+        let partition_range = self
+            .partition_range(split.partition_id)
+            .ok_or(PartitionSplitError::UnknownPartition(split.partition_id))?;
+        if self.is_split(split.partition_id) {
+            return Err(PartitionSplitError::AlreadySplit(split.partition_id));
+        }
+        for partition_id in [split.left_partition_id, split.right_partition_id] {
+            if self.partition_ids().any(|id| id == partition_id) {
+                return Err(PartitionSplitError::PartitionIdInUse(partition_id));
+            }
+        }
+        if split.left_partition_id == split.right_partition_id {
+            return Err(PartitionSplitError::PartitionIdInUse(
+                split.right_partition_id,
+            ));
+        }
+        if split.split_range(&partition_range).is_none() {
+            return Err(PartitionSplitError::InvalidSplitKey(split.split_key));
+        }
+
+        self.splits.push(split);
+        Ok(())
     }
 
     pub fn version(&self) -> Version {
@@ -71,8 +144,21 @@ impl FixedPartitionTable {
         self.version = version;
     }
 
+    /// Returns the partitions which haven't been split.
     pub fn partitioner(&self) -> Partitioner {
-        Partitioner::new(self.num_partitions)
+        Partitioner {
+            partitions: self
+                .partition_ids()
+                .filter(|partition_id| !self.is_split(*partition_id))
+                .map(|partition_id| {
+                    let partition_range = self
+                        .partition_range(partition_id)
+                        .expect("partition range of known partition");
+                    (partition_id, partition_range)
+                })
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
     }
 
     fn partition_key_to_partition_id(
@@ -133,61 +219,120 @@ where
         &self,
         partition_key: PartitionKey,
     ) -> Result<PartitionId, PartitionTableError> {
-        let partition_id = FixedPartitionTable::partition_key_to_partition_id(
-            self.borrow().num_partitions,
+        let partition_table = self.borrow();
+        let mut partition_id = FixedPartitionTable::partition_key_to_partition_id(
+            partition_table.num_partitions,
             partition_key,
         );
 
+        // later splits can only split partitions created by earlier ones
+        for split in &partition_table.splits {
+            if split.partition_id == partition_id {
+                partition_id = if partition_key < split.split_key {
+                    split.left_partition_id
+                } else {
+                    split.right_partition_id
+                };
+            }
+        }
+
         Ok(partition_id)
     }
 }
 
 #[derive(Debug)]
 pub struct Partitioner {
-    num_partitions: u64,
-    next_partition_id: PartitionId,
-}
-
-impl Partitioner {
-    fn new(num_partitions: u64) -> Self {
-        Self {
-            num_partitions,
-            next_partition_id: PartitionId::MIN,
-        }
-    }
+    partitions: std::vec::IntoIter<(PartitionId, RangeInclusive<PartitionKey>)>,
 }
 
 impl Iterator for Partitioner {
     type Item = (PartitionId, RangeInclusive<PartitionKey>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if *self.next_partition_id < self.num_partitions {
-            let partition_id = self.next_partition_id;
-            self.next_partition_id = self.next_partition_id.next();
+        self.partitions.next()
+    }
+}
 
-            let partition_range = FixedPartitionTable::partition_id_to_partition_range(
-                self.num_partitions,
-                partition_id,
-            );
+/// Splits the key range of a partition in two. The left partition owns the keys below
+/// `split_key`, the right partition the keys from `split_key` on.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PartitionSplit {
+    pub partition_id: PartitionId,
+    pub split_key: PartitionKey,
+    pub left_partition_id: PartitionId,
+    pub right_partition_id: PartitionId,
+}
 
-            Some((partition_id, partition_range))
+impl PartitionSplit {
+    /// Returns the key ranges of the left and the right partition, or `None` if the split key
+    /// doesn't split the range into two non-empty ranges.
+    pub fn split_range(
+        &self,
+        range: &RangeInclusive<PartitionKey>,
+    ) -> Option<(RangeInclusive<PartitionKey>, RangeInclusive<PartitionKey>)> {
+        if self.split_key > *range.start() && self.split_key <= *range.end() {
+            Some((
+                *range.start()..=self.split_key - 1,
+                self.split_key..=*range.end(),
+            ))
         } else {
             None
         }
     }
 }
 
+flexbuffers_storage_encode_decode!(PartitionSplit);
+
+/// A completed partition split, registered with the cluster controller through the metadata store.
+/// The split partition doesn't apply any log records after `sealed_lsn`, the new partitions
+/// start with empty logs.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RegisteredPartitionSplit {
+    version: Version,
+    pub split: PartitionSplit,
+    pub left_partition_range: RangeInclusive<PartitionKey>,
+    pub right_partition_range: RangeInclusive<PartitionKey>,
+    pub sealed_lsn: Lsn,
+}
+
+impl RegisteredPartitionSplit {
+    pub fn new(
+        split: PartitionSplit,
+        left_partition_range: RangeInclusive<PartitionKey>,
+        right_partition_range: RangeInclusive<PartitionKey>,
+        sealed_lsn: Lsn,
+    ) -> Self {
+        Self {
+            version: Version::MIN,
+            split,
+            left_partition_range,
+            right_partition_range,
+            sealed_lsn,
+        }
+    }
+}
+
+impl Versioned for RegisteredPartitionSplit {
+    fn version(&self) -> Version {
+        self.version
+    }
+}
+
+flexbuffers_storage_encode_decode!(RegisteredPartitionSplit);
+
 #[cfg(test)]
 mod tests {
     use test_log::test;
 
     use crate::identifiers::{PartitionId, PartitionKey};
-    use crate::partition_table::{FindPartition, FixedPartitionTable, Partitioner};
+    use crate::partition_table::{
+        FindPartition, FixedPartitionTable, PartitionSplit, PartitionSplitError,
+    };
     use crate::Version;
 
     #[test]
     fn partitioner_produces_consecutive_ranges() {
-        let partitioner = Partitioner::new(10);
+        let partitioner = FixedPartitionTable::new(Version::MIN, 10).partitioner();
         let mut previous_end = None;
         let mut previous_length = None::<PartitionKey>;
 
@@ -238,4 +383,111 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn partition_split_splits_range() {
+        let split = PartitionSplit {
+            partition_id: PartitionId::MIN,
+            split_key: 10,
+            left_partition_id: PartitionId::from(1),
+            right_partition_id: PartitionId::from(2),
+        };
+
+        assert_eq!(split.split_range(&(0..=20)), Some((0..=9, 10..=20)));
+        assert_eq!(split.split_range(&(0..=10)), Some((0..=9, 10..=10)));
+        // both halves must be non-empty
+        assert_eq!(split.split_range(&(10..=20)), None);
+        assert_eq!(split.split_range(&(0..=9)), None);
+    }
+
+    #[test]
+    fn partition_table_resolves_split_partitions() {
+        let mut partition_table = FixedPartitionTable::new(Version::MIN, 2);
+        let (_, first_range) = partition_table.partitioner().next().unwrap();
+        let split_key = *first_range.end() / 2;
+        partition_table
+            .add_split(PartitionSplit {
+                partition_id: PartitionId::MIN,
+                split_key,
+                left_partition_id: PartitionId::from(2),
+                right_partition_id: PartitionId::from(3),
+            })
+            .unwrap();
+        // split the right partition again
+        partition_table
+            .add_split(PartitionSplit {
+                partition_id: PartitionId::from(3),
+                split_key: split_key + 10,
+                left_partition_id: PartitionId::from(4),
+                right_partition_id: PartitionId::from(5),
+            })
+            .unwrap();
+
+        assert_eq!(
+            partition_table
+                .partitioner()
+                .map(|(partition_id, _)| partition_id)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 4, 5]
+                .into_iter()
+                .map(PartitionId::from)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            partition_table.partition_range(PartitionId::from(4)),
+            Some(split_key..=split_key + 9)
+        );
+        assert_eq!(
+            partition_table.partition_range(PartitionId::from(5)),
+            Some(split_key + 10..=*first_range.end())
+        );
+        for (partition_id, partition_range) in partition_table.partitioner() {
+            assert_eq!(
+                partition_table.unchecked_partition_key_to_target_peer(*partition_range.start()),
+                partition_id
+            );
+            assert_eq!(
+                partition_table.unchecked_partition_key_to_target_peer(*partition_range.end()),
+                partition_id
+            );
+        }
+    }
+
+    #[test]
+    fn partition_table_rejects_invalid_splits() {
+        let mut partition_table = FixedPartitionTable::new(Version::MIN, 1);
+        let split = PartitionSplit {
+            partition_id: PartitionId::MIN,
+            split_key: 42,
+            left_partition_id: PartitionId::from(1),
+            right_partition_id: PartitionId::from(2),
+        };
+
+        assert!(matches!(
+            partition_table.add_split(PartitionSplit {
+                partition_id: PartitionId::from(7),
+                ..split.clone()
+            }),
+            Err(PartitionSplitError::UnknownPartition(_))
+        ));
+        assert!(matches!(
+            partition_table.add_split(PartitionSplit {
+                left_partition_id: PartitionId::MIN,
+                ..split.clone()
+            }),
+            Err(PartitionSplitError::PartitionIdInUse(_))
+        ));
+        assert!(matches!(
+            partition_table.add_split(PartitionSplit {
+                split_key: 0,
+                ..split.clone()
+            }),
+            Err(PartitionSplitError::InvalidSplitKey(0))
+        ));
+        partition_table.add_split(split.clone()).unwrap();
+        assert!(matches!(
+            partition_table.add_split(split),
+            Err(PartitionSplitError::AlreadySplit(_))
+        ));
+    }
 }
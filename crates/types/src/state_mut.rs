@@ -31,6 +31,18 @@ pub struct ExternalStateMutation {
     pub state: HashMap<Bytes, Bytes>,
 }
 
+/// ServiceKeyPurge
+///
+/// represents an external request to purge all state of a virtual object instance: its user state,
+/// its inbox and the journal of its running invocation.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ServiceKeyPurge {
+    pub service_id: ServiceId,
+    /// Kill the running invocation, if any. Otherwise, the purge is refused while an invocation
+    /// is running.
+    pub force: bool,
+}
+
 /// # StateMutationVersion
 ///
 /// This type represents a user state version. This implementation hashes canonically the raw key-value
@@ -163,7 +163,7 @@ macro_rules! flexbuffers_storage_encode_decode {
             fn encode<B: ::bytes::BufMut>(
                 &self,
                 buf: &mut B,
-            ) -> Result<(), $crate::storage::StorageEncodeError> {
+            ) -> ::std::result::Result<(), $crate::storage::StorageEncodeError> {
                 $crate::storage::encode_as_flexbuffers(self, buf)
                     .map_err(|err| $crate::storage::StorageEncodeError::EncodeValue(err.into()))
             }
@@ -173,7 +173,7 @@ macro_rules! flexbuffers_storage_encode_decode {
             fn decode<B: ::bytes::Buf>(
                 buf: &mut B,
                 kind: $crate::storage::StorageCodecKind,
-            ) -> Result<Self, $crate::storage::StorageDecodeError>
+            ) -> ::std::result::Result<Self, $crate::storage::StorageDecodeError>
             where
                 Self: Sized,
             {
@@ -42,6 +42,48 @@ impl Default for Version {
     }
 }
 
+/// Version of the partition processor state machine.
+///
+/// Behavioral changes of the state machine, e.g. new commands or journal entries, are gated behind
+/// a version so that all replicas of a partition switch to the new behavior at the same log
+/// position. A partition is only upgraded once every worker of the cluster supports the version.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Ord,
+    PartialOrd,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[display(fmt = "v{}", _0)]
+pub struct StateMachineVersion(u16);
+
+impl StateMachineVersion {
+    /// Behavior of partitions which have never been upgraded.
+    pub const V1: StateMachineVersion = StateMachineVersion(1);
+    /// Batched timer commands.
+    pub const V2: StateMachineVersion = StateMachineVersion(2);
+    /// The latest version supported by this binary.
+    pub const LATEST: StateMachineVersion = StateMachineVersion::V2;
+
+    pub fn is_supported(self) -> bool {
+        self <= Self::LATEST
+    }
+}
+
+impl Default for StateMachineVersion {
+    fn default() -> Self {
+        Self::V1
+    }
+}
+
+crate::flexbuffers_storage_encode_decode!(StateMachineVersion);
+
 /// A trait for all metadata types that have a version.
 pub trait Versioned {
     /// Returns the version of the versioned value
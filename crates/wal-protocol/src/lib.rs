@@ -9,20 +9,26 @@
 // by the Apache License, Version 2.0.
 
 use bytes::{Bytes, BytesMut};
+use bytestring::ByteString;
 use restate_bifrost::Bifrost;
 use restate_core::{metadata, ShutdownError};
 use restate_storage_api::deduplication_table::DedupInformation;
-use restate_types::identifiers::{LeaderEpoch, PartitionId, PartitionKey, WithPartitionKey};
-use restate_types::invocation::{InvocationResponse, InvocationTermination, ServiceInvocation};
+use restate_types::identifiers::{
+    InvocationId, LeaderEpoch, PartitionId, PartitionKey, WithPartitionKey,
+};
+use restate_types::invocation::{
+    AttachInvocationRequest, DeadLetterRedrive, InvocationResponse, InvocationTermination,
+    ServiceInvocation,
+};
 use restate_types::message::MessageIndex;
-use restate_types::state_mut::ExternalStateMutation;
-use restate_types::{flexbuffers_storage_encode_decode, Version};
+use restate_types::state_mut::{ExternalStateMutation, ServiceKeyPurge};
+use restate_types::{flexbuffers_storage_encode_decode, StateMachineVersion, Version};
 
 use crate::control::AnnounceLeader;
 use crate::effects::BuiltinServiceEffects;
 use crate::timer::TimerKeyValue;
 use restate_types::logs::{LogId, Lsn, Payload};
-use restate_types::partition_table::{FindPartition, PartitionTableError};
+use restate_types::partition_table::{FindPartition, PartitionSplit, PartitionTableError};
 use restate_types::storage::{StorageCodec, StorageDecodeError, StorageEncodeError};
 use restate_types::{GenerationalNodeId, PlainNodeId};
 
@@ -117,6 +123,10 @@ pub enum Destination {
 pub enum Command {
     // -- Control-plane related events
     AnnounceLeader(AnnounceLeader),
+    /// Seal this partition and split it in two
+    SplitPartition(PartitionSplit),
+    /// Switch the partition to the behavior of the given state machine version
+    UpgradeStateMachine(StateMachineVersion),
 
     // -- Partition processor commands
     /// Manual patching of storage state
@@ -129,12 +139,26 @@ pub enum Command {
     TruncateOutbox(MessageIndex),
     /// Proxy a service invocation through this partition processor, to reuse the deduplication id map.
     ProxyThrough(ServiceInvocation),
+    /// Run a dead lettered invocation again and remove it from the dead letter queue
+    RedriveDeadLetter(DeadLetterRedrive),
+    /// Remove a dead lettered invocation from the dead letter queue
+    PurgeDeadLetter(InvocationId),
+    /// Purge the user state, inbox and journal of a virtual object instance
+    PurgeServiceKey(ServiceKeyPurge),
+    /// Subscribe to the result of an invocation
+    AttachInvocation(AttachInvocationRequest),
+    /// Hold back new invocations of the given service in the inbox
+    PauseService(ByteString),
+    /// Dispatch the invocations of the given service again, including the held back ones
+    ResumeService(ByteString),
 
     // -- Partition processor events for PP
     /// Invoker is reporting effect(s) from an ongoing invocation.
     InvokerEffect(restate_invoker_api::Effect),
     /// Timer has fired
     Timer(TimerKeyValue),
+    /// Timers which have fired at the same time, applied at once
+    Timers(Vec<TimerKeyValue>),
     /// Schedule timer
     ScheduleTimer(TimerKeyValue),
     /// Another partition processor is reporting a response of an invocation we requested.
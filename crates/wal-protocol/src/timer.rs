@@ -8,8 +8,9 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use bytes::Bytes;
 use restate_storage_api::timer_table::{Timer, TimerKey, TimerKeyKind};
-use restate_types::identifiers::{EntryIndex, InvocationId};
+use restate_types::identifiers::{EntryIndex, InvocationId, ServiceId};
 use restate_types::invocation::ServiceInvocation;
 use restate_types::time::MillisSinceEpoch;
 use std::borrow::Borrow;
@@ -54,6 +55,38 @@ impl TimerKeyValue {
         Self { timer_key, value }
     }
 
+    pub fn expire_state(
+        expiration_time: MillisSinceEpoch,
+        invocation_id: InvocationId,
+        entry_index: EntryIndex,
+        service_id: ServiceId,
+        state_key: Bytes,
+    ) -> Self {
+        let (timer_key, value) = Timer::expire_state(
+            expiration_time.as_u64(),
+            invocation_id,
+            entry_index,
+            service_id,
+            state_key,
+        );
+        Self { timer_key, value }
+    }
+
+    pub fn invocation_deadline(deadline: MillisSinceEpoch, invocation_id: InvocationId) -> Self {
+        let (timer_key, value) = Timer::invocation_deadline(deadline.as_u64(), invocation_id);
+        Self { timer_key, value }
+    }
+
+    pub fn clean_dead_letter(wake_up_time: MillisSinceEpoch, invocation_id: InvocationId) -> Self {
+        let (timer_key, value) = Timer::clean_dead_letter(wake_up_time.as_u64(), invocation_id);
+        Self { timer_key, value }
+    }
+
+    pub fn resume_invocation(wake_up_time: MillisSinceEpoch, invocation_id: InvocationId) -> Self {
+        let (timer_key, value) = Timer::resume_invocation(wake_up_time.as_u64(), invocation_id);
+        Self { timer_key, value }
+    }
+
     pub fn into_inner(self) -> (TimerKey, Timer) {
         (self.timer_key, self.value)
     }
@@ -125,6 +158,23 @@ impl<'a> fmt::Display for TimerKeyDisplay<'a> {
             TimerKeyKind::CleanInvocationStatus { invocation_uuid } => {
                 write!(f, "Clean invocation status '{}'", invocation_uuid)
             }
+            TimerKeyKind::ExpireState {
+                invocation_uuid,
+                journal_index,
+            } => write!(
+                f,
+                "Expire state set by journal entry [{}] of '{}'",
+                journal_index, invocation_uuid
+            ),
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => {
+                write!(f, "Execution deadline of '{}'", invocation_uuid)
+            }
+            TimerKeyKind::CleanDeadLetter { invocation_uuid } => {
+                write!(f, "Clean dead letter of '{}'", invocation_uuid)
+            }
+            TimerKeyKind::ResumeInvocation { invocation_uuid } => {
+                write!(f, "Resume invocation '{}'", invocation_uuid)
+            }
         }
     }
 }
@@ -162,6 +162,27 @@ where
                 )?;
                 EnrichedEntryHeader::ClearAllState {}
             }
+            PlainEntryHeader::GetPromise { is_completed } => {
+                can_use_promises(
+                    &header.as_entry_type(),
+                    &current_invocation_target.invocation_target_ty(),
+                )?;
+                EnrichedEntryHeader::GetPromise { is_completed }
+            }
+            PlainEntryHeader::PeekPromise { is_completed } => {
+                can_use_promises(
+                    &header.as_entry_type(),
+                    &current_invocation_target.invocation_target_ty(),
+                )?;
+                EnrichedEntryHeader::PeekPromise { is_completed }
+            }
+            PlainEntryHeader::CompletePromise { is_completed } => {
+                can_use_promises(
+                    &header.as_entry_type(),
+                    &current_invocation_target.invocation_target_ty(),
+                )?;
+                EnrichedEntryHeader::CompletePromise { is_completed }
+            }
             PlainEntryHeader::Sleep { is_completed } => EnrichedEntryHeader::Sleep { is_completed },
             PlainEntryHeader::Call { is_completed, .. } => {
                 if !is_completed {
@@ -267,3 +288,20 @@ fn can_write_state(
     }
     Ok(())
 }
+
+#[inline]
+fn can_use_promises(
+    entry_type: &EntryType,
+    invocation_target_type: &InvocationTargetType,
+) -> Result<(), InvocationError> {
+    if !invocation_target_type.can_use_promises() {
+        return Err(InvocationError::new(
+            codes::BAD_REQUEST,
+            format!(
+                "The service/handler type {} is not a workflow and, therefore, does not support the entry type {}",
+                invocation_target_type, entry_type
+            ),
+        ));
+    }
+    Ok(())
+}
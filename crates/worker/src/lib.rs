@@ -16,17 +16,28 @@ mod invoker_integration;
 mod metric_definitions;
 mod partition;
 mod partition_processor_manager;
+mod partition_snapshots;
 mod subscription_controller;
 mod subscription_integration;
 
 pub use error::*;
 pub use handle::*;
+#[cfg(feature = "replay")]
+pub use partition::{
+    recording_path, replay_recording, CommandRecorder, RecordedCommand, RecordingReader,
+    ReplayError,
+};
+pub use partition_snapshots::{
+    PartitionSnapshotMetadata, PartitionSnapshotRepository, SnapshotError,
+};
 use restate_types::arc_util::ArcSwapExt;
 use restate_types::config::UpdateableConfiguration;
 pub use subscription_controller::SubscriptionController;
 pub use subscription_integration::SubscriptionControllerHandle;
 
 use codederror::CodedError;
+use tracing::warn;
+
 use restate_bifrost::Bifrost;
 use restate_core::network::MessageRouterBuilder;
 use restate_core::{task_center, Metadata, TaskKind};
@@ -47,6 +58,7 @@ use restate_storage_query_postgres::service::PostgresQueryService;
 use crate::invoker_integration::EntryEnricher;
 use crate::partition::storage::invoker::InvokerStorageReader;
 use crate::partition_processor_manager::PartitionProcessorManager;
+use crate::partition_snapshots::PartitionSnapshotter;
 
 type PartitionProcessor = partition::PartitionProcessor<
     ProtobufRawEntryCodec,
@@ -103,6 +115,7 @@ pub struct Worker {
     ingress_kafka: IngressKafkaService,
     subscription_controller_handle: SubscriptionControllerHandle,
     partition_processor_manager: PartitionProcessorManager,
+    partition_snapshotter: Option<PartitionSnapshotter>,
 }
 
 impl Worker {
@@ -154,6 +167,26 @@ impl Worker {
             schema_view.clone(),
         )?;
 
+        let snapshot_repository = match PartitionSnapshotRepository::from_options(&config.worker) {
+            Some(Ok(repository)) => Some(repository),
+            Some(Err(e)) => {
+                warn!(
+                    "Partition snapshots are disabled, invalid snapshot configuration: {}",
+                    e
+                );
+                None
+            }
+            None => None,
+        };
+        let partition_snapshotter = snapshot_repository.clone().map(|repository| {
+            PartitionSnapshotter::new(
+                repository,
+                partition_store_manager.clone(),
+                updateable_config.clone(),
+                partition_snapshots::staging_dir(),
+            )
+        });
+
         let partition_processor_manager = PartitionProcessorManager::new(
             updateable_config.clone(),
             metadata.clone(),
@@ -162,6 +195,7 @@ impl Worker {
             networking,
             bifrost,
             invoker.handle(),
+            snapshot_repository,
         );
 
         let storage_query_context = QueryContext::create(
@@ -169,6 +203,7 @@ impl Worker {
             partition_processor_manager.handle(),
             partition_store_manager.clone(),
             invoker.status_reader(),
+            invoker.health_check_reader(),
             schema_view.clone(),
         )
         .await?;
@@ -187,6 +222,7 @@ impl Worker {
             ingress_kafka,
             subscription_controller_handle,
             partition_processor_manager,
+            partition_snapshotter,
         })
     }
 
@@ -248,6 +284,15 @@ impl Worker {
             self.partition_processor_manager.run(),
         )?;
 
+        if let Some(partition_snapshotter) = self.partition_snapshotter {
+            tc.spawn_child(
+                TaskKind::SystemService,
+                "partition-snapshotter",
+                None,
+                partition_snapshotter.run(),
+            )?;
+        }
+
         Ok(())
     }
 }
@@ -13,10 +13,15 @@
 use metrics::{describe_counter, describe_histogram, Unit};
 
 pub const PARTITION_APPLY_COMMAND: &str = "restate.partition.apply_command.total";
+pub const PARTITION_APPLY_COMMAND_DURATION: &str =
+    "restate.partition.apply_command_duration.seconds";
+pub const PARTITION_APPLY_COMMAND_EFFECTS: &str = "restate.partition.apply_command_effects";
+pub const PARTITION_JOURNAL_ENTRY_SIZE: &str = "restate.partition.journal_entry_size.bytes";
 pub const PARTITION_ACTUATOR_HANDLED: &str = "restate.partition.actuator_handled.total";
 pub const PARTITION_TIMER_DUE_HANDLED: &str = "restate.partition.timer_due_handled.total";
 pub const PARTITION_STORAGE_TX_CREATED: &str = "restate.partition.storage_tx_created.total";
 pub const PARTITION_STORAGE_TX_COMMITTED: &str = "restate.partition.storage_tx_committed.total";
+pub const PARTITION_INBOX_OVERFLOW: &str = "restate.partition.inbox_overflow.total";
 
 pub const PP_APPLY_RECORD_DURATION: &str = "restate.partition.apply_record_duration.seconds";
 pub const PP_APPLY_ACTIONS_DURATION: &str = "restate.partition.apply_actions_duration.seconds";
@@ -29,6 +34,21 @@ pub(crate) fn describe_metrics() {
         Unit::Count,
         "Total consensus commands processed by partition processor"
     );
+    describe_histogram!(
+        PARTITION_APPLY_COMMAND_DURATION,
+        Unit::Seconds,
+        "Time spent applying a single command to the partition state machine, by command type"
+    );
+    describe_histogram!(
+        PARTITION_APPLY_COMMAND_EFFECTS,
+        Unit::Count,
+        "Number of effects produced by applying a single command, by command type"
+    );
+    describe_histogram!(
+        PARTITION_JOURNAL_ENTRY_SIZE,
+        Unit::Bytes,
+        "Size of the journal entries appended by the partition state machine, by command type"
+    );
     describe_counter!(
         PARTITION_ACTUATOR_HANDLED,
         Unit::Count,
@@ -49,6 +69,11 @@ pub(crate) fn describe_metrics() {
         Unit::Count,
         "Storage transactions committed by applying partition state machine commands"
     );
+    describe_counter!(
+        PARTITION_INBOX_OVERFLOW,
+        Unit::Count,
+        "Invocations failed because the inbox of their virtual object was full"
+    );
     describe_histogram!(
         PP_APPLY_RECORD_DURATION,
         Unit::Seconds,
@@ -14,6 +14,7 @@ use restate_core::metadata;
 use restate_storage_api::deduplication_table::{DedupInformation, EpochSequenceNumber};
 use restate_types::identifiers::{PartitionId, PartitionKey, WithPartitionKey};
 use restate_types::time::MillisSinceEpoch;
+use restate_types::StateMachineVersion;
 use restate_wal_protocol::timer::TimerKeyValue;
 use restate_wal_protocol::{
     append_envelope_to_bifrost, Command, Destination, Envelope, Header, Source,
@@ -44,7 +45,13 @@ impl ActionEffectHandler {
         }
     }
 
-    pub(super) async fn handle(&mut self, actuator_output: ActionEffect) -> anyhow::Result<()> {
+    /// Commands which are newer than the given state machine version of the partition are not
+    /// proposed, because replicas which haven't been upgraded yet might fail decoding them.
+    pub(super) async fn handle(
+        &mut self,
+        actuator_output: ActionEffect,
+        state_machine_version: StateMachineVersion,
+    ) -> anyhow::Result<()> {
         match actuator_output {
             ActionEffect::Invoker(invoker_output) => {
                 let header = self.create_header(invoker_output.invocation_id.partition_key());
@@ -64,14 +71,26 @@ impl ActionEffectHandler {
                 )
                 .await?;
             }
-            ActionEffect::Timer(timer) => {
-                let partition_key = timer.invocation_id().partition_key();
-                let header = self.create_header(partition_key);
-                append_envelope_to_bifrost(
-                    &mut self.bifrost,
-                    Envelope::new(header, Command::Timer(timer)),
-                )
-                .await?;
+            ActionEffect::Timers(timers) => {
+                if timers.len() > 1 && state_machine_version >= StateMachineVersion::V2 {
+                    // all timers belong to this partition, any of their partition keys routes the
+                    // command to it
+                    let header = self.create_header(timers[0].invocation_id().partition_key());
+                    append_envelope_to_bifrost(
+                        &mut self.bifrost,
+                        Envelope::new(header, Command::Timers(timers)),
+                    )
+                    .await?;
+                } else {
+                    for timer in timers {
+                        let header = self.create_header(timer.invocation_id().partition_key());
+                        append_envelope_to_bifrost(
+                            &mut self.bifrost,
+                            Envelope::new(header, Command::Timer(timer)),
+                        )
+                        .await?;
+                    }
+                }
             }
             ActionEffect::ScheduleCleanupTimer(invocation_id, duration) => {
                 // We need this self proposal because we need to agree between leaders and followers on the wakeup time.
@@ -89,6 +108,32 @@ impl ActionEffectHandler {
                 )
                 .await?;
             }
+            ActionEffect::ScheduleDeadLetterCleanupTimer(invocation_id, duration) => {
+                // Self proposed for the same reason as the invocation status cleanup timer
+                let header = self.create_header(invocation_id.partition_key());
+                append_envelope_to_bifrost(
+                    &mut self.bifrost,
+                    Envelope::new(
+                        header,
+                        Command::ScheduleTimer(TimerKeyValue::clean_dead_letter(
+                            MillisSinceEpoch::from(SystemTime::now() + duration),
+                            invocation_id,
+                        )),
+                    ),
+                )
+                .await?;
+            }
+            ActionEffect::UpgradeStateMachine(version) => {
+                let header = self.create_header(*self.partition_key_range.start());
+                append_envelope_to_bifrost(
+                    &mut self.bifrost,
+                    Envelope::new(header, Command::UpgradeStateMachine(version)),
+                )
+                .await?;
+            }
+            ActionEffect::InvokerReleased => {
+                // nothing to propose
+            }
         };
 
         Ok(())
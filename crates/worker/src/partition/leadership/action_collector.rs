@@ -9,8 +9,9 @@
 // by the Apache License, Version 2.0.
 
 use crate::partition::shuffle;
-use futures::{Stream, StreamExt};
+use futures::{ready, stream, Stream, StreamExt};
 use restate_types::identifiers::InvocationId;
+use restate_types::StateMachineVersion;
 use restate_wal_protocol::timer::TimerKeyValue;
 use std::ops::DerefMut;
 use std::pin::Pin;
@@ -22,7 +23,8 @@ use tokio_stream::wrappers::ReceiverStream;
 pub(crate) enum ActionEffectStream {
     Follower,
     Leader {
-        invoker_stream: ReceiverStream<restate_invoker_api::Effect>,
+        /// Becomes `None` once the invoker released the partition
+        invoker_stream: Option<ReceiverStream<restate_invoker_api::Effect>>,
         shuffle_stream: ReceiverStream<shuffle::OutboxTruncation>,
         action_effects_stream: ReceiverStream<ActionEffect>,
     },
@@ -35,7 +37,7 @@ impl ActionEffectStream {
         action_effects_rx: mpsc::Receiver<ActionEffect>,
     ) -> Self {
         ActionEffectStream::Leader {
-            invoker_stream: ReceiverStream::new(invoker_rx),
+            invoker_stream: Some(ReceiverStream::new(invoker_rx)),
             shuffle_stream: ReceiverStream::new(shuffle_rx),
             action_effects_stream: ReceiverStream::new(action_effects_rx),
         }
@@ -46,8 +48,13 @@ impl ActionEffectStream {
 pub(crate) enum ActionEffect {
     Invoker(restate_invoker_api::Effect),
     Shuffle(shuffle::OutboxTruncation),
-    Timer(TimerKeyValue),
+    Timers(Vec<TimerKeyValue>),
     ScheduleCleanupTimer(InvocationId, Duration),
+    ScheduleDeadLetterCleanupTimer(InvocationId, Duration),
+    UpgradeStateMachine(StateMachineVersion),
+    /// The invoker won't send any more effects for this leader epoch, e.g. because the partition
+    /// has been drained.
+    InvokerReleased,
 }
 
 impl Stream for ActionEffectStream {
@@ -61,7 +68,18 @@ impl Stream for ActionEffectStream {
                 shuffle_stream,
                 action_effects_stream,
             } => {
-                let invoker_stream = invoker_stream.map(ActionEffect::Invoker);
+                let invoker_stream = stream::poll_fn(|cx| {
+                    let Some(stream) = invoker_stream.as_mut() else {
+                        return Poll::Pending;
+                    };
+                    match ready!(stream.poll_next_unpin(cx)) {
+                        Some(effect) => Poll::Ready(Some(ActionEffect::Invoker(effect))),
+                        None => {
+                            *invoker_stream = None;
+                            Poll::Ready(Some(ActionEffect::InvokerReleased))
+                        }
+                    }
+                });
                 let shuffle_stream = shuffle_stream.map(ActionEffect::Shuffle);
 
                 let mut all_streams =
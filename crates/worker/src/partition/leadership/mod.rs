@@ -23,8 +23,9 @@ use restate_timer::TokioClock;
 use std::fmt::Debug;
 use std::ops::RangeInclusive;
 use std::pin::Pin;
+use std::time::Duration;
 use tokio::sync::mpsc;
-use tracing::{trace, warn};
+use tracing::{info, trace, warn};
 
 mod action_collector;
 
@@ -37,6 +38,7 @@ use restate_partition_store::PartitionStore;
 use restate_storage_api::deduplication_table::EpochSequenceNumber;
 use restate_types::identifiers::{InvocationId, PartitionKey};
 use restate_types::identifiers::{LeaderEpoch, PartitionId, PartitionLeaderEpoch};
+use restate_types::StateMachineVersion;
 use restate_wal_protocol::timer::TimerKeyValue;
 
 use super::storage::invoker::InvokerStorageReader;
@@ -44,6 +46,9 @@ use super::storage::invoker::InvokerStorageReader;
 type PartitionStorage = storage::PartitionStorage<PartitionStore>;
 type TimerService = restate_timer::TimerService<TimerKeyValue, TokioClock, PartitionStorage>;
 
+/// Maximum number of due timers which are proposed as a single command.
+const MAX_TIMER_BATCH_SIZE: usize = 256;
+
 pub(crate) struct LeaderState {
     leader_epoch: LeaderEpoch,
     shuffle_hint_tx: HintSender,
@@ -56,7 +61,9 @@ pub(crate) struct LeaderState {
 pub(crate) struct FollowerState<I> {
     partition_id: PartitionId,
     num_timers_in_memory_limit: Option<usize>,
+    timer_bucket_width: Option<Duration>,
     channel_size: usize,
+    drain_grace_period: Duration,
     invoker_tx: I,
     networking: Networking,
     partition_key_range: RangeInclusive<PartitionKey>,
@@ -91,7 +98,9 @@ where
         partition_id: PartitionId,
         partition_key_range: RangeInclusive<PartitionKey>,
         num_timers_in_memory_limit: Option<usize>,
+        timer_bucket_width: Option<Duration>,
         channel_size: usize,
+        drain_grace_period: Duration,
         invoker_tx: InvokerInputSender,
         bifrost: Bifrost,
         networking: Networking,
@@ -101,7 +110,9 @@ where
                 partition_id,
                 partition_key_range,
                 num_timers_in_memory_limit,
+                timer_bucket_width,
                 channel_size,
+                drain_grace_period,
                 invoker_tx,
                 bifrost,
                 networking,
@@ -151,6 +162,7 @@ where
             let timer_service = Box::pin(TimerService::new(
                 TokioClock,
                 follower_state.num_timers_in_memory_limit,
+                follower_state.timer_bucket_width,
                 partition_storage.clone(),
             ));
 
@@ -247,6 +259,30 @@ where
         Ok(invoker_rx)
     }
 
+    /// Drains the invocations of the partition before handing it over: no new invocations are
+    /// started, while the in-flight ones get the drain grace period to suspend or complete. This
+    /// node keeps leading meanwhile, so that their effects are proposed and their stored entries
+    /// acked. The action effect stream yields [`ActionEffect::InvokerReleased`] once the invoker
+    /// is done with the partition.
+    pub(crate) async fn drain_invocations(&mut self) -> Result<(), Error> {
+        if let LeadershipState::Leader {
+            follower_state,
+            leader_state,
+        } = self
+        {
+            follower_state
+                .invoker_tx
+                .drain_partition(
+                    (follower_state.partition_id, leader_state.leader_epoch),
+                    follower_state.drain_grace_period,
+                )
+                .await
+                .map_err(Error::Invoker)?;
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn become_follower(self) -> Result<(Self, ActionEffectStream), Error> {
         if let LeadershipState::Leader {
             follower_state:
@@ -255,6 +291,8 @@ where
                     partition_key_range,
                     channel_size,
                     num_timers_in_memory_limit,
+                    timer_bucket_width,
+                    drain_grace_period,
                     mut invoker_tx,
                     bifrost,
                     networking,
@@ -284,7 +322,9 @@ where
                 partition_id,
                 partition_key_range,
                 num_timers_in_memory_limit,
+                timer_bucket_width,
                 channel_size,
+                drain_grace_period,
                 invoker_tx,
                 bifrost,
                 networking,
@@ -294,13 +334,19 @@ where
         }
     }
 
-    pub(crate) async fn run_timer(&mut self) -> TimerKeyValue {
+    /// Awaits the next due timer, together with all other timers which are due by then.
+    pub(crate) async fn run_timers(&mut self) -> Vec<TimerKeyValue> {
         match self {
             LeadershipState::Follower { .. } => future::pending().await,
             LeadershipState::Leader {
                 leader_state: LeaderState { timer_service, .. },
                 ..
-            } => timer_service.as_mut().next_timer().await,
+            } => {
+                timer_service
+                    .as_mut()
+                    .next_timers(MAX_TIMER_BATCH_SIZE)
+                    .await
+            }
         }
     }
 
@@ -452,6 +498,18 @@ where
                     .send(ActionEffect::ScheduleCleanupTimer(invocation_id, retention))
                     .await;
             }
+            Action::ScheduleDeadLetterCleanup {
+                invocation_id,
+                retention,
+            } => {
+                // We can ignore this error. It means the PP is shutting down.
+                let _ = actions_effects_tx
+                    .send(ActionEffect::ScheduleDeadLetterCleanupTimer(
+                        invocation_id,
+                        retention,
+                    ))
+                    .await;
+            }
         }
 
         Ok(())
@@ -460,6 +518,7 @@ where
     pub async fn handle_action_effect(
         &mut self,
         action_effect: ActionEffect,
+        state_machine_version: StateMachineVersion,
     ) -> anyhow::Result<()> {
         match self {
             LeadershipState::Follower(_) => {
@@ -468,13 +527,45 @@ where
             LeadershipState::Leader { leader_state, .. } => {
                 leader_state
                     .action_effect_handler
-                    .handle(action_effect)
+                    .handle(action_effect, state_machine_version)
                     .await?
             }
         };
 
         Ok(())
     }
+
+    /// Proposes to upgrade the state machine of the partition once all workers of the cluster
+    /// support a newer version than the given one.
+    pub async fn upgrade_state_machine_if_supported(
+        &mut self,
+        state_machine_version: StateMachineVersion,
+    ) -> anyhow::Result<()> {
+        let LeadershipState::Leader { leader_state, .. } = self else {
+            return Ok(());
+        };
+        let Some(cluster_version) = metadata().nodes_config().min_state_machine_version() else {
+            return Ok(());
+        };
+        // never propose a version this node can't apply itself
+        let target_version = cluster_version.min(StateMachineVersion::LATEST);
+
+        if target_version > state_machine_version {
+            info!(
+                "All workers support state machine version {}, upgrading partition from {}",
+                target_version, state_machine_version
+            );
+            leader_state
+                .action_effect_handler
+                .handle(
+                    ActionEffect::UpgradeStateMachine(target_version),
+                    state_machine_version,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
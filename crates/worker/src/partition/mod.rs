@@ -18,15 +18,16 @@ use crate::partition::storage::{DedupSequenceNumberResolver, PartitionStorage, T
 use assert2::let_assert;
 use futures::StreamExt;
 use metrics::{counter, histogram};
-use restate_core::metadata;
+use restate_core::{metadata, MetadataKind};
 use restate_network::Networking;
 use restate_partition_store::{PartitionStore, RocksDBTransaction};
+use restate_types::config::InboxOverflowPolicy;
 use restate_types::identifiers::{PartitionId, PartitionKey};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops::RangeInclusive;
-use std::time::Instant;
-use tracing::{debug, instrument, trace, Span};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, instrument, trace, warn, Span};
 
 mod action_effect_handler;
 mod leadership;
@@ -35,6 +36,12 @@ mod state_machine;
 pub mod storage;
 pub mod types;
 
+#[cfg(feature = "replay")]
+pub use state_machine::{
+    recording_path, replay_recording, CommandRecorder, RecordedCommand, RecordingReader,
+    ReplayError,
+};
+
 use restate_bifrost::{Bifrost, FindTailAttributes, LogReadStream, LogRecord, Record};
 use restate_core::cancellation_watcher;
 use restate_storage_api::deduplication_table::{
@@ -42,6 +49,7 @@ use restate_storage_api::deduplication_table::{
 };
 use restate_storage_api::StorageError;
 use restate_types::logs::{LogId, Lsn, SequenceNumber};
+use restate_types::partition_table::PartitionSplit;
 use restate_wal_protocol::control::AnnounceLeader;
 use restate_wal_protocol::{Command, Destination, Envelope, Header};
 
@@ -53,7 +61,12 @@ pub(super) struct PartitionProcessor<RawEntryCodec, InvokerInputSender> {
     pub partition_key_range: RangeInclusive<PartitionKey>,
 
     num_timers_in_memory_limit: Option<usize>,
+    timer_bucket_width: Option<Duration>,
     channel_size: usize,
+    max_inbox_length: Option<usize>,
+    inbox_overflow_policy: InboxOverflowPolicy,
+    dead_letter_retention: Duration,
+    drain_grace_period: Duration,
 
     invoker_tx: InvokerInputSender,
 
@@ -71,31 +84,48 @@ where
         partition_id: PartitionId,
         partition_key_range: RangeInclusive<PartitionKey>,
         num_timers_in_memory_limit: Option<usize>,
+        timer_bucket_width: Option<Duration>,
         channel_size: usize,
+        max_inbox_length: Option<usize>,
+        inbox_overflow_policy: InboxOverflowPolicy,
+        dead_letter_retention: Duration,
+        drain_grace_period: Duration,
         invoker_tx: InvokerInputSender,
     ) -> Self {
         Self {
             partition_id,
             partition_key_range,
             num_timers_in_memory_limit,
+            timer_bucket_width,
             channel_size,
+            max_inbox_length,
+            inbox_overflow_policy,
+            dead_letter_retention,
+            drain_grace_period,
             invoker_tx,
             _entry_codec: Default::default(),
         }
     }
 
+    /// Runs the partition processor until shutdown or until the partition is sealed for a split,
+    /// in which case the split is returned.
     #[instrument(level = "info", skip_all, fields(partition_id = %self.partition_id, is_leader = tracing::field::Empty))]
     pub(super) async fn run(
         self,
         networking: Networking,
         bifrost: Bifrost,
         partition_store: PartitionStore,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Option<PartitionSplit>> {
         let PartitionProcessor {
             partition_id,
             partition_key_range,
             num_timers_in_memory_limit,
+            timer_bucket_width,
             channel_size,
+            max_inbox_length,
+            inbox_overflow_policy,
+            dead_letter_retention,
+            drain_grace_period,
             invoker_tx,
             ..
         } = self;
@@ -103,11 +133,21 @@ where
         let mut partition_storage =
             PartitionStorage::new(partition_id, partition_key_range.clone(), partition_store);
 
+        if let Some(split) = partition_storage.load_partition_split().await? {
+            debug!("Partition has been sealed for a split, not reading the log");
+            return Ok(Some(split));
+        }
+
         let mut state_machine = Self::create_state_machine::<RawEntryCodec>(
             &mut partition_storage,
             partition_key_range.clone(),
+            max_inbox_length,
+            inbox_overflow_policy,
+            dead_letter_retention,
         )
         .await?;
+        #[cfg(feature = "replay")]
+        state_machine.set_recorder(CommandRecorder::create(recording_path(partition_id))?);
 
         let last_applied_lsn = partition_storage.load_applied_lsn().await?;
         let last_applied_lsn = last_applied_lsn.unwrap_or(Lsn::INVALID);
@@ -130,17 +170,31 @@ where
             partition_id,
             partition_key_range.clone(),
             num_timers_in_memory_limit,
+            timer_bucket_width,
             channel_size,
+            drain_grace_period,
             invoker_tx,
             bifrost,
             networking,
         );
 
+        let mut nodes_config_watch = metadata().watch(MetadataKind::NodesConfiguration);
         let mut cancellation = std::pin::pin!(cancellation_watcher());
         let partition_id_str: &'static str = Box::leak(Box::new(self.partition_id.to_string()));
+        let mut sealed_for_split = None;
+        let mut draining = false;
         loop {
             tokio::select! {
-                _ = &mut cancellation => break,
+                _ = &mut cancellation, if !draining => {
+                    if !state.is_leader() {
+                        break;
+                    }
+                    // keep leading until the in-flight invocations are drained, so that the next
+                    // leader doesn't have to retry them
+                    debug!("Draining the invocations of the partition before shutting down");
+                    state.drain_invocations().await?;
+                    draining = true;
+                },
                 record = log_reader.read_next() => {
                     let command_start = Instant::now();
                     let record = record?;
@@ -152,61 +206,89 @@ where
                     action_collector.clear();
                     effects.clear();
 
-                    let leadership_change = Self::apply_record(
+                    let control_event = Self::apply_record(
                             record,
                             &mut state_machine,
                             &mut transaction,
                             &mut action_collector,
                             &mut effects, state.is_leader(),
+                            partition_id,
                             &partition_key_range)
                         .await?;
 
-                    if let Some(announce_leader) = leadership_change {
-                        let new_esn = EpochSequenceNumber::new(announce_leader.leader_epoch);
-
-                        // update our own epoch sequence number to filter out messages from previous leaders
-                        transaction.store_dedup_sequence_number(ProducerId::self_producer(), DedupSequenceNumber::Esn(new_esn)).await;
-                        // commit all changes so far, this is important so that the actuators see all changes
-                        // when becoming leader.
-                        transaction.commit().await?;
-
-                        // We can ignore all actions collected so far because as a new leader we have to instruct the
-                        // actuators afresh.
-                        action_collector.clear();
-
-                        if announce_leader.node_id == metadata().my_node_id() {
-                            let was_follower = !state.is_leader();
-                            (state, action_effect_stream) = state.become_leader(new_esn, &mut partition_storage).await?;
-                            if was_follower {
-                                Span::current().record("is_leader", state.is_leader());
-                                debug!(leader_epoch = %new_esn.leader_epoch, "Partition leadership acquired");
+                    match control_event {
+                        Some(ControlEvent::AnnounceLeader(announce_leader)) => {
+                            let new_esn = EpochSequenceNumber::new(announce_leader.leader_epoch);
+
+                            // update our own epoch sequence number to filter out messages from previous leaders
+                            transaction.store_dedup_sequence_number(ProducerId::self_producer(), DedupSequenceNumber::Esn(new_esn)).await;
+                            // commit all changes so far, this is important so that the actuators see all changes
+                            // when becoming leader.
+                            transaction.commit().await?;
+
+                            // We can ignore all actions collected so far because as a new leader we have to instruct the
+                            // actuators afresh.
+                            action_collector.clear();
+
+                            if announce_leader.node_id == metadata().my_node_id() {
+                                let was_follower = !state.is_leader();
+                                (state, action_effect_stream) = state.become_leader(new_esn, &mut partition_storage).await?;
+                                if was_follower {
+                                    Span::current().record("is_leader", state.is_leader());
+                                    debug!(leader_epoch = %new_esn.leader_epoch, "Partition leadership acquired");
+                                }
+                                state.upgrade_state_machine_if_supported(state_machine.state_machine_version()).await?;
+                            } else {
+                                let was_leader = state.is_leader();
+                                (state, action_effect_stream) = state.become_follower().await?;
+                                if was_leader {
+                                    Span::current().record("is_leader", state.is_leader());
+                                    debug!(leader_epoch = %new_esn.leader_epoch, "Partition leadership lost to {}", announce_leader.node_id);
+                                }
                             }
-                        } else {
-                            let was_leader = state.is_leader();
-                            (state, action_effect_stream) = state.become_follower().await?;
-                            if was_leader {
-                                Span::current().record("is_leader", state.is_leader());
-                                debug!(leader_epoch = %new_esn.leader_epoch, "Partition leadership lost to {}", announce_leader.node_id);
+                            histogram!(PP_APPLY_RECORD_DURATION, PARTITION_LABEL => partition_id_str).record(command_start.elapsed());
+                            if draining {
+                                // the invocations of the drained leader epoch have been aborted
+                                break;
                             }
                         }
-                        histogram!(PP_APPLY_RECORD_DURATION, PARTITION_LABEL => partition_id_str).record(command_start.elapsed());
-                    } else {
-                        // Commit our changes and notify actuators about actions if we are the leader
-                        transaction.commit().await?;
-                        histogram!(PP_APPLY_RECORD_DURATION, PARTITION_LABEL => partition_id_str).record(command_start.elapsed());
-                        let actions_start = Instant::now();
-                        state.handle_actions(action_collector.drain(..)).await?;
-                        histogram!(PP_APPLY_ACTIONS_DURATION).record(actions_start.elapsed());
+                        Some(ControlEvent::SplitPartition(split)) => {
+                            // the partition is sealed, no further records are applied
+                            transaction.commit().await?;
+                            histogram!(PP_APPLY_RECORD_DURATION, PARTITION_LABEL => partition_id_str).record(command_start.elapsed());
+                            info!(split_key = split.split_key, "Partition sealed for split");
+                            sealed_for_split = Some(split);
+                            break;
+                        }
+                        None => {
+                            // Commit our changes and notify actuators about actions if we are the leader
+                            transaction.commit().await?;
+                            histogram!(PP_APPLY_RECORD_DURATION, PARTITION_LABEL => partition_id_str).record(command_start.elapsed());
+                            let actions_start = Instant::now();
+                            state.handle_actions(action_collector.drain(..)).await?;
+                            histogram!(PP_APPLY_ACTIONS_DURATION).record(actions_start.elapsed());
+                        }
                     }
                 },
                 action_effect = action_effect_stream.next() => {
                     counter!(PARTITION_ACTUATOR_HANDLED).increment(1);
                     let action_effect = action_effect.ok_or_else(|| anyhow::anyhow!("action effect stream is closed"))?;
-                    state.handle_action_effect(action_effect).await?;
+                    if let ActionEffect::InvokerReleased = action_effect {
+                        if draining {
+                            debug!("Partition drained");
+                            break;
+                        }
+                        continue;
+                    }
+                    state.handle_action_effect(action_effect, state_machine.state_machine_version()).await?;
                 },
-                timer = state.run_timer() => {
-                    counter!(PARTITION_TIMER_DUE_HANDLED).increment(1);
-                    state.handle_action_effect(ActionEffect::Timer(timer)).await?;
+                timers = state.run_timers() => {
+                    counter!(PARTITION_TIMER_DUE_HANDLED).increment(timers.len() as u64);
+                    state.handle_action_effect(ActionEffect::Timers(timers), state_machine.state_machine_version()).await?;
+                },
+                Ok(()) = nodes_config_watch.changed() => {
+                    // workers advertise the state machine version they support when registering
+                    state.upgrade_state_machine_if_supported(state_machine.state_machine_version()).await?;
                 },
             }
         }
@@ -214,21 +296,32 @@ where
         debug!(restate.node = %metadata().my_node_id(), %partition_id, "Shutting partition processor down.");
         let _ = state.become_follower().await;
 
-        Ok(())
+        Ok(sealed_for_split)
     }
 
     async fn create_state_machine<Codec>(
         partition_storage: &mut PartitionStorage<PartitionStore>,
         partition_key_range: RangeInclusive<PartitionKey>,
+        max_inbox_length: Option<usize>,
+        inbox_overflow_policy: InboxOverflowPolicy,
+        dead_letter_retention: Duration,
     ) -> Result<StateMachine<Codec>, restate_storage_api::StorageError>
     where
         Codec: restate_types::journal::raw::RawEntryCodec + Default + Debug,
     {
         let inbox_seq_number = partition_storage.load_inbox_seq_number().await?;
         let outbox_seq_number = partition_storage.load_outbox_seq_number().await?;
-
-        let state_machine =
-            StateMachine::new(inbox_seq_number, outbox_seq_number, partition_key_range);
+        let paused_services = partition_storage.load_paused_services().await?;
+        let state_machine_version = partition_storage.load_state_machine_version().await?;
+
+        let mut state_machine =
+            StateMachine::new(inbox_seq_number, outbox_seq_number, partition_key_range)
+                .with_paused_services(paused_services)
+                .with_dead_letter_retention(dead_letter_retention)
+                .with_state_machine_version(state_machine_version);
+        if let Some(max_inbox_length) = max_inbox_length {
+            state_machine = state_machine.with_inbox_limit(max_inbox_length, inbox_overflow_policy);
+        }
 
         Ok(state_machine)
     }
@@ -240,8 +333,9 @@ where
         action_collector: &mut ActionCollector,
         effects: &mut Effects,
         is_leader: bool,
+        partition_id: PartitionId,
         partition_key_range: &RangeInclusive<PartitionKey>,
-    ) -> Result<Option<AnnounceLeader>, state_machine::Error>
+    ) -> Result<Option<ControlEvent>, state_machine::Error>
     where
         Codec: restate_types::journal::raw::RawEntryCodec + Default + Debug,
     {
@@ -286,7 +380,7 @@ where
                     .unwrap_or(true)
                 {
                     // leadership change detected, let's finish our transaction here
-                    return Ok(Some(announce_leader));
+                    return Ok(Some(ControlEvent::AnnounceLeader(announce_leader)));
                 }
                 debug!(
                     last_known_esn = %last_known_esn.as_ref().unwrap().leader_epoch,
@@ -294,7 +388,34 @@ where
                     node_id = %announce_leader.node_id,
                     "Ignoring outdated leadership announcement."
                 );
+            } else if let Command::SplitPartition(split) = envelope.command {
+                if split.partition_id != partition_id {
+                    // the log of a partition is only written for the partition itself
+                    warn!(
+                        split_partition_id = %split.partition_id,
+                        "Ignoring partition split of another partition."
+                    );
+                } else if split.split_range(partition_key_range).is_some() {
+                    if transaction.is_outbox_empty().await? {
+                        transaction.store_partition_split(&split).await?;
+                        return Ok(Some(ControlEvent::SplitPartition(split)));
+                    }
+                    // receivers deduplicate messages by producer partition and sequence number,
+                    // the new partitions would send delivered but not yet truncated messages again
+                    // under their own ids
+                    info!(
+                        split_key = split.split_key,
+                        "Partition split waits for the outbox to be truncated."
+                    );
+                    transaction.store_pending_partition_split(&split).await?;
+                } else {
+                    warn!(
+                        split_key = split.split_key,
+                        "Ignoring partition split with a split key which doesn't split the partition."
+                    );
+                }
             } else {
+                let truncates_outbox = matches!(envelope.command, Command::TruncateOutbox(_));
                 state_machine
                     .apply(
                         envelope.command,
@@ -304,6 +425,15 @@ where
                         is_leader,
                     )
                     .await?;
+
+                if truncates_outbox {
+                    if let Some(split) = transaction.load_pending_partition_split().await? {
+                        if transaction.is_outbox_empty().await? {
+                            transaction.store_partition_split(&split).await?;
+                            return Ok(Some(ControlEvent::SplitPartition(split)));
+                        }
+                    }
+                }
             }
         } else {
             trace!(
@@ -316,6 +446,12 @@ where
     }
 }
 
+/// Records which are handled by the partition processor rather than the state machine.
+enum ControlEvent {
+    AnnounceLeader(AnnounceLeader),
+    SplitPartition(PartitionSplit),
+}
+
 fn is_targeted_to_me<'a>(
     header: &'a Header,
     partition_key_range: &RangeInclusive<PartitionKey>,
@@ -50,4 +50,8 @@ pub enum Action {
         invocation_id: InvocationId,
         retention: Duration,
     },
+    ScheduleDeadLetterCleanup {
+        invocation_id: InvocationId,
+        retention: Duration,
+    },
 }
@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use super::Error;
+use crate::metric_definitions::PARTITION_INBOX_OVERFLOW;
 
 use crate::partition::state_machine::effects::Effects;
 use crate::partition::types::{
@@ -16,30 +17,40 @@ use crate::partition::types::{
 };
 use assert2::let_assert;
 use bytes::Bytes;
-use futures::{Stream, StreamExt};
+use bytestring::ByteString;
+use futures::{future, Stream, StreamExt, TryStreamExt};
+use metrics::counter;
 use restate_service_protocol::codec::ProtobufRawEntryCodec;
+use restate_storage_api::dead_letter_table::{DeadLetter, ReadOnlyDeadLetterTable};
 use restate_storage_api::idempotency_table::ReadOnlyIdempotencyTable;
-use restate_storage_api::inbox_table::InboxEntry;
+use restate_storage_api::inbox_table::{
+    InboxEntry, InboxPriority, ReadOnlyInboxTable, SequenceNumberInboxEntry,
+};
 use restate_storage_api::invocation_status_table::{
     CompletedInvocation, InFlightInvocationMetadata, InboxedInvocation, InvocationStatus,
 };
 use restate_storage_api::journal_table::{JournalEntry, ReadOnlyJournalTable};
 use restate_storage_api::outbox_table::OutboxMessage;
+use restate_storage_api::promise_table::{Promise, PromiseState, ReadOnlyPromiseTable};
 use restate_storage_api::service_status_table::VirtualObjectStatus;
 use restate_storage_api::timer_table::Timer;
 use restate_storage_api::Result as StorageResult;
+use restate_types::config::InboxOverflowPolicy;
 use restate_types::errors::{
-    InvocationError, InvocationErrorCode, CANCELED_INVOCATION_ERROR, GONE_INVOCATION_ERROR,
-    KILLED_INVOCATION_ERROR,
+    InvocationError, InvocationErrorCode, ALREADY_COMPLETED_PROMISE_ERROR,
+    CANCELED_INVOCATION_ERROR, DEADLINE_EXCEEDED_INVOCATION_ERROR, GONE_INVOCATION_ERROR,
+    INBOX_FULL_INVOCATION_ERROR, KILLED_INVOCATION_ERROR, NOT_FOUND_INVOCATION_ERROR,
 };
 use restate_types::identifiers::{
-    EntryIndex, IdempotencyId, InvocationId, PartitionKey, ServiceId, WithPartitionKey,
+    EntryIndex, IdempotencyId, InvocationId, JournalEntryId, PartitionKey, ServiceId,
+    WithPartitionKey,
 };
 use restate_types::ingress::IngressResponse;
 use restate_types::invocation::{
-    InvocationResponse, InvocationTarget, InvocationTargetType, InvocationTermination,
-    ResponseResult, ServiceInvocation, ServiceInvocationResponseSink, ServiceInvocationSpanContext,
-    Source, SpanRelationCause, TerminationFlavor, VirtualObjectHandlerType, WorkflowHandlerType,
+    AttachInvocationRequest, DeadLetterRedrive, InvocationResponse, InvocationTarget,
+    InvocationTargetType, InvocationTermination, ResponseResult, ServiceInvocation,
+    ServiceInvocationResponseSink, ServiceInvocationSpanContext, Source, SpanRelationCause,
+    TerminationFlavor, VirtualObjectHandlerType, WorkflowHandlerType,
 };
 use restate_types::journal::enriched::{
     AwakeableEnrichmentResult, CallEnrichmentResult, EnrichedEntryHeader, EnrichedRawEntry,
@@ -48,8 +59,9 @@ use restate_types::journal::raw::RawEntryCodec;
 use restate_types::journal::Completion;
 use restate_types::journal::*;
 use restate_types::message::MessageIndex;
-use restate_types::state_mut::ExternalStateMutation;
+use restate_types::state_mut::{ExternalStateMutation, ServiceKeyPurge};
 use restate_types::time::MillisSinceEpoch;
+use restate_types::StateMachineVersion;
 use restate_wal_protocol::effects::{BuiltinServiceEffect, BuiltinServiceEffects};
 use restate_wal_protocol::timer::TimerKeyValue;
 use restate_wal_protocol::Command;
@@ -60,6 +72,7 @@ use std::iter;
 use std::marker::PhantomData;
 use std::ops::RangeInclusive;
 use std::pin::pin;
+use std::time::Duration;
 use tracing::{debug, instrument, trace, warn};
 
 pub trait StateReader {
@@ -90,6 +103,12 @@ pub trait StateReader {
         service_id: &ServiceId,
     ) -> impl Future<Output = StorageResult<Vec<Bytes>>> + Send;
 
+    fn load_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        key: &Bytes,
+    ) -> impl Future<Output = StorageResult<Option<MillisSinceEpoch>>> + Send;
+
     fn load_completion_result(
         &mut self,
         invocation_id: &InvocationId,
@@ -101,6 +120,11 @@ pub trait StateReader {
         invocation_id: &InvocationId,
         length: EntryIndex,
     ) -> impl Stream<Item = StorageResult<(EntryIndex, JournalEntry)>> + Send;
+
+    fn inboxed_invocations(
+        &mut self,
+        partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = StorageResult<(InvocationId, InvocationTarget)>> + Send;
 }
 
 pub(crate) struct CommandInterpreter<Codec> {
@@ -108,15 +132,30 @@ pub(crate) struct CommandInterpreter<Codec> {
     inbox_seq_number: MessageIndex,
     outbox_seq_number: MessageIndex,
     partition_key_range: RangeInclusive<PartitionKey>,
+    paused_services: HashSet<ByteString>,
+    inbox_limit: Option<InboxLimit>,
+    dead_letter_retention: Option<Duration>,
+    state_machine_version: StateMachineVersion,
 
     _codec: PhantomData<Codec>,
 }
 
+/// Bounds the number of entries in the inbox of a single virtual object.
+#[derive(Debug, Clone, Copy)]
+struct InboxLimit {
+    max_length: usize,
+    overflow_policy: InboxOverflowPolicy,
+}
+
 impl<Codec> Debug for CommandInterpreter<Codec> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("EffectCollector")
             .field("inbox_seq_number", &self.inbox_seq_number)
             .field("outbox_seq_number", &self.outbox_seq_number)
+            .field("paused_services", &self.paused_services)
+            .field("inbox_limit", &self.inbox_limit)
+            .field("dead_letter_retention", &self.dead_letter_retention)
+            .field("state_machine_version", &self.state_machine_version)
             .finish()
     }
 }
@@ -131,9 +170,47 @@ impl<Codec> CommandInterpreter<Codec> {
             inbox_seq_number,
             outbox_seq_number,
             partition_key_range,
+            paused_services: HashSet::new(),
+            inbox_limit: None,
+            dead_letter_retention: None,
+            state_machine_version: StateMachineVersion::default(),
             _codec: PhantomData,
         }
     }
+
+    pub(crate) fn with_paused_services(mut self, paused_services: HashSet<ByteString>) -> Self {
+        self.paused_services = paused_services;
+        self
+    }
+
+    pub(crate) fn with_inbox_limit(
+        mut self,
+        max_inbox_length: usize,
+        overflow_policy: InboxOverflowPolicy,
+    ) -> Self {
+        self.inbox_limit = Some(InboxLimit {
+            max_length: max_inbox_length,
+            overflow_policy,
+        });
+        self
+    }
+
+    pub(crate) fn with_dead_letter_retention(mut self, retention: Duration) -> Self {
+        self.dead_letter_retention = Some(retention);
+        self
+    }
+
+    pub(crate) fn with_state_machine_version(
+        mut self,
+        state_machine_version: StateMachineVersion,
+    ) -> Self {
+        self.state_machine_version = state_machine_version;
+        self
+    }
+
+    pub(crate) fn state_machine_version(&self) -> StateMachineVersion {
+        self.state_machine_version
+    }
 }
 
 impl<Codec> CommandInterpreter<Codec>
@@ -148,7 +225,12 @@ where
     /// We use the returned service invocation id and span relation to log the effects (see [`Effects#log`]).
     #[instrument(level = "trace", skip_all, fields(command = ?command), err)]
     pub(crate) async fn on_apply<
-        State: StateReader + ReadOnlyJournalTable + ReadOnlyIdempotencyTable,
+        State: StateReader
+            + ReadOnlyJournalTable
+            + ReadOnlyIdempotencyTable
+            + ReadOnlyDeadLetterTable
+            + ReadOnlyPromiseTable
+            + ReadOnlyInboxTable,
     >(
         &mut self,
         command: Command,
@@ -178,12 +260,26 @@ where
                 );
                 Ok(())
             }
+            Command::RedriveDeadLetter(dead_letter_redrive) => {
+                self.redrive_dead_letter(effects, state, dead_letter_redrive)
+                    .await
+            }
+            Command::PurgeDeadLetter(invocation_id) => {
+                effects.delete_dead_letter(invocation_id);
+                Ok(())
+            }
             Command::InvokerEffect(effect) => self.try_invoker_effect(effects, state, effect).await,
             Command::TruncateOutbox(index) => {
                 effects.truncate_outbox(index);
                 Ok(())
             }
             Command::Timer(timer) => self.on_timer(timer, state, effects).await,
+            Command::Timers(timers) => {
+                for timer in timers {
+                    self.on_timer(timer, state, effects).await?;
+                }
+                Ok(())
+            }
             Command::TerminateInvocation(invocation_termination) => {
                 self.try_terminate_invocation(invocation_termination, state, effects)
                     .await
@@ -196,10 +292,27 @@ where
                 self.handle_external_state_mutation(mutation, state, effects)
                     .await
             }
+            Command::PurgeServiceKey(purge) => self.purge_service_key(purge, state, effects).await,
+            Command::AttachInvocation(attach_invocation_request) => {
+                self.attach_invocation(attach_invocation_request, state, effects)
+                    .await
+            }
+            Command::PauseService(service_name) => {
+                self.pause_service(service_name, effects);
+                Ok(())
+            }
+            Command::ResumeService(service_name) => {
+                self.resume_service(service_name, state, effects).await
+            }
             Command::AnnounceLeader(_) => {
                 // no-op :-)
                 Ok(())
             }
+            Command::SplitPartition(_) => {
+                // handled by the partition processor, which seals the partition
+                Ok(())
+            }
+            Command::UpgradeStateMachine(version) => self.upgrade_state_machine(version, effects),
             Command::ScheduleTimer(timer) => {
                 effects.register_timer(timer, Default::default());
                 Ok(())
@@ -207,7 +320,188 @@ where
         }
     }
 
-    async fn handle_invoke<State: StateReader + ReadOnlyIdempotencyTable>(
+    fn upgrade_state_machine(
+        &mut self,
+        version: StateMachineVersion,
+        effects: &mut Effects,
+    ) -> Result<(), Error> {
+        if version <= self.state_machine_version {
+            trace!(
+                "Ignoring upgrade to state machine version {} because the partition runs {} already",
+                version,
+                self.state_machine_version
+            );
+            return Ok(());
+        }
+
+        if !version.is_supported() {
+            // Applying the following commands with the old behavior would let this replica
+            // diverge from the upgraded ones
+            return Err(Error::UnsupportedStateMachineVersion(version));
+        }
+
+        self.state_machine_version = version;
+        effects.upgrade_state_machine(version);
+        Ok(())
+    }
+
+    fn pause_service(&mut self, service_name: ByteString, effects: &mut Effects) {
+        if self.paused_services.insert(service_name.clone()) {
+            effects.add_paused_service(service_name);
+        }
+    }
+
+    async fn resume_service<State: StateReader + ReadOnlyInboxTable>(
+        &mut self,
+        service_name: ByteString,
+        state: &mut State,
+        effects: &mut Effects,
+    ) -> Result<(), Error> {
+        if !self.paused_services.remove(&service_name) {
+            trace!(
+                "Received resume for service '{}' which is not paused",
+                service_name
+            );
+            return Ok(());
+        }
+        effects.remove_paused_service(service_name.clone());
+
+        // Dispatch the held back invocations, except for the ones of exclusive handlers which
+        // wait in the inbox of their virtual object
+        let held_back_invocations: Vec<_> = state
+            .inboxed_invocations(self.partition_key_range.clone())
+            .try_filter(|(_, invocation_target)| {
+                future::ready(
+                    invocation_target.service_name() == &service_name
+                        && invocation_target.invocation_target_ty()
+                            != InvocationTargetType::VirtualObject(
+                                VirtualObjectHandlerType::Exclusive,
+                            ),
+                )
+            })
+            .try_collect()
+            .await?;
+        for (invocation_id, _) in held_back_invocations {
+            effects.dispatch_inboxed_invocation(invocation_id);
+        }
+
+        // The inbox entries are sorted by service id, so the duplicates are consecutive
+        let mut inbox_service_ids: Vec<ServiceId> = state
+            .all_inboxes(self.partition_key_range.clone())
+            .try_filter_map(|inbox_entry| {
+                let service_id = inbox_entry.service_id();
+                future::ready(Ok(
+                    (service_id.service_name == service_name).then(|| service_id.clone())
+                ))
+            })
+            .try_collect()
+            .await?;
+        inbox_service_ids.dedup();
+
+        // If a virtual object is locked, its inbox is popped once the running invocation completes
+        for service_id in inbox_service_ids {
+            if let VirtualObjectStatus::Unlocked =
+                state.get_virtual_object_status(&service_id).await?
+            {
+                effects.pop_inbox(service_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn attach_invocation<State: StateReader>(
+        &mut self,
+        AttachInvocationRequest {
+            invocation_id,
+            response_sink,
+        }: AttachInvocationRequest,
+        state: &mut State,
+        effects: &mut Effects,
+    ) -> Result<(), Error> {
+        match state.get_invocation_status(&invocation_id).await? {
+            InvocationStatus::Completed(CompletedInvocation {
+                invocation_target,
+                idempotency_key,
+                response_result,
+                ..
+            }) => {
+                let idempotency_id = idempotency_key.map(|idempotency| {
+                    IdempotencyId::combine(invocation_id, &invocation_target, idempotency)
+                });
+                self.send_response_to_sinks(
+                    effects,
+                    &invocation_id,
+                    idempotency_id,
+                    iter::once(response_sink),
+                    response_result,
+                );
+            }
+            InvocationStatus::Free => {
+                trace!(
+                    "Received attach request for unknown invocation {}",
+                    invocation_id
+                );
+                self.send_response_to_sinks(
+                    effects,
+                    &invocation_id,
+                    None,
+                    iter::once(response_sink),
+                    &NOT_FOUND_INVOCATION_ERROR,
+                );
+            }
+            // The response is sent to all response sinks once the invocation completes
+            is => effects.append_response_sink(invocation_id, is, response_sink),
+        }
+
+        Ok(())
+    }
+
+    async fn redrive_dead_letter<
+        State: StateReader + ReadOnlyIdempotencyTable + ReadOnlyDeadLetterTable + ReadOnlyInboxTable,
+    >(
+        &mut self,
+        effects: &mut Effects,
+        state: &mut State,
+        DeadLetterRedrive {
+            invocation_id,
+            new_invocation_id,
+        }: DeadLetterRedrive,
+    ) -> Result<(), Error> {
+        let Some(dead_letter) = state.get_dead_letter(&invocation_id).await? else {
+            trace!(
+                restate.invocation.id = %invocation_id,
+                "Received redrive for unknown dead letter. Ignoring it."
+            );
+            return Ok(());
+        };
+        if new_invocation_id.partition_key() != invocation_id.partition_key() {
+            warn!(
+                restate.invocation.id = %invocation_id,
+                "Ignoring redrive of dead letter, because the new invocation id {} belongs to a different partition key.",
+                new_invocation_id
+            );
+            return Ok(());
+        }
+
+        effects.delete_dead_letter(invocation_id);
+
+        let span_context = ServiceInvocationSpanContext::start(
+            &new_invocation_id,
+            dead_letter.service_invocation.span_context.as_linked(),
+        );
+        let service_invocation = ServiceInvocation {
+            invocation_id: new_invocation_id,
+            span_context,
+            // The idempotency key might still point to the failed invocation, the redrive is a
+            // new attempt that must not be deduplicated against it.
+            idempotency_key: None,
+            ..dead_letter.service_invocation
+        };
+        self.handle_invoke(effects, state, service_invocation).await
+    }
+
+    async fn handle_invoke<State: StateReader + ReadOnlyIdempotencyTable + ReadOnlyInboxTable>(
         &mut self,
         effects: &mut Effects,
         state: &mut State,
@@ -264,6 +558,19 @@ where
             return Ok(());
         }
 
+        self.invoke_or_enqueue(effects, state, service_invocation)
+            .await
+    }
+
+    /// Invokes the service right away, unless the target's lock is held. In that case the
+    /// invocation is enqueued in the inbox of the virtual object, or attached to the running
+    /// workflow.
+    async fn invoke_or_enqueue<State: StateReader + ReadOnlyInboxTable>(
+        &mut self,
+        effects: &mut Effects,
+        state: &mut State,
+        service_invocation: ServiceInvocation,
+    ) -> Result<(), Error> {
         // If it's exclusive, we need to acquire the exclusive lock
         if service_invocation.invocation_target.invocation_target_ty()
             == InvocationTargetType::VirtualObject(VirtualObjectHandlerType::Exclusive)
@@ -279,18 +586,14 @@ where
 
             // If locked, enqueue in inbox and be done with it
             if let VirtualObjectStatus::Locked(_) = service_status {
-                let inbox_seq_number = self.enqueue_into_inbox(
-                    effects,
-                    InboxEntry::Invocation(keyed_service_id, service_invocation.invocation_id),
-                );
-                effects.store_inboxed_invocation(
-                    service_invocation.invocation_id,
-                    InboxedInvocation::from_service_invocation(
+                return self
+                    .enqueue_invocation_into_bounded_inbox(
+                        effects,
+                        state,
+                        keyed_service_id,
                         service_invocation,
-                        inbox_seq_number,
-                    ),
-                );
-                return Ok(());
+                    )
+                    .await;
             }
         }
 
@@ -335,18 +638,268 @@ where
             }
         }
 
+        // Hold back the invocation until its service is resumed
+        if self
+            .paused_services
+            .contains(service_invocation.invocation_target.service_name())
+        {
+            return self
+                .hold_back_invocation(effects, state, service_invocation)
+                .await;
+        }
+
         // We're ready to invoke the service!
+        Self::register_execution_deadline(effects, &service_invocation);
         effects.invoke_service(service_invocation);
         Ok(())
     }
 
+    /// The deadline also covers the time the invocation spends in the inbox, hence it's
+    /// registered once the invocation is invoked or inboxed.
+    fn register_execution_deadline(effects: &mut Effects, service_invocation: &ServiceInvocation) {
+        if let Some(execution_deadline) = service_invocation.execution_deadline {
+            effects.register_timer(
+                TimerKeyValue::invocation_deadline(
+                    execution_deadline,
+                    service_invocation.invocation_id,
+                ),
+                service_invocation.span_context.clone(),
+            );
+        }
+    }
+
+    fn delete_execution_deadline(
+        effects: &mut Effects,
+        invocation_id: InvocationId,
+        execution_deadline: Option<MillisSinceEpoch>,
+    ) {
+        if let Some(execution_deadline) = execution_deadline {
+            let (timer_key, _) =
+                Timer::invocation_deadline(execution_deadline.as_u64(), invocation_id);
+            effects.delete_timer(timer_key);
+        }
+    }
+
+    /// Invocations of exclusive handlers wait in the inbox of their virtual object, the other
+    /// invocations are only stored until the service is resumed. Workflow runs acquire the lock
+    /// of their workflow right away, so that later runs attach to them.
+    async fn hold_back_invocation<State: StateReader + ReadOnlyInboxTable>(
+        &mut self,
+        effects: &mut Effects,
+        state: &mut State,
+        service_invocation: ServiceInvocation,
+    ) -> Result<(), Error> {
+        let invocation_target_type = service_invocation.invocation_target.invocation_target_ty();
+        if invocation_target_type
+            == InvocationTargetType::VirtualObject(VirtualObjectHandlerType::Exclusive)
+        {
+            let keyed_service_id = service_invocation
+                .invocation_target
+                .as_keyed_service_id()
+                .expect(
+                    "When the handler type is Exclusive, the invocation target must have a key",
+                );
+            return self
+                .enqueue_invocation_into_bounded_inbox(
+                    effects,
+                    state,
+                    keyed_service_id,
+                    service_invocation,
+                )
+                .await;
+        }
+
+        if invocation_target_type == InvocationTargetType::Workflow(WorkflowHandlerType::Workflow) {
+            effects.lock_service_id(
+                service_invocation
+                    .invocation_target
+                    .as_keyed_service_id()
+                    .expect(
+                        "When the handler type is Workflow, the invocation target must have a key",
+                    ),
+                service_invocation.invocation_id,
+            );
+        }
+
+        let inbox_priority = InboxPriority::for_invocation(&service_invocation);
+        Self::register_execution_deadline(effects, &service_invocation);
+        effects.store_inboxed_invocation(
+            service_invocation.invocation_id,
+            // Held back invocations have no inbox entry
+            InboxedInvocation::from_service_invocation(service_invocation, 0, inbox_priority),
+        );
+        Ok(())
+    }
+
+    /// Enqueues the invocation into the inbox of its virtual object. If the inbox is full, the
+    /// inbox overflow policy decides whether the new or the oldest invocation in the inbox fails.
+    async fn enqueue_invocation_into_bounded_inbox<State: StateReader + ReadOnlyInboxTable>(
+        &mut self,
+        effects: &mut Effects,
+        state: &mut State,
+        inbox_service_id: ServiceId,
+        service_invocation: ServiceInvocation,
+    ) -> Result<(), Error> {
+        if let Some(InboxLimit {
+            max_length,
+            overflow_policy,
+        }) = self.inbox_limit
+        {
+            let inbox: Vec<_> = state
+                .inbox(&inbox_service_id)
+                .take(max_length)
+                .try_collect()
+                .await?;
+
+            if inbox.len() >= max_length {
+                // state mutations are never dropped, fall back to rejecting the new invocation
+                let oldest_invocation = match overflow_policy {
+                    InboxOverflowPolicy::RejectNew => None,
+                    InboxOverflowPolicy::DropOldest => inbox
+                        .iter()
+                        .filter_map(|entry| match entry.inbox_entry {
+                            InboxEntry::Invocation(_, invocation_id) => {
+                                Some((entry.inbox_sequence_number, invocation_id))
+                            }
+                            InboxEntry::StateMutation(_) => None,
+                        })
+                        .min()
+                        .map(|(_, invocation_id)| invocation_id),
+                };
+
+                match oldest_invocation {
+                    Some(invocation_id) => {
+                        let_assert!(
+                            InvocationStatus::Inboxed(inboxed_invocation) =
+                                state.get_invocation_status(&invocation_id).await?,
+                            "Invocation {} in the inbox must be inboxed",
+                            invocation_id
+                        );
+                        debug!(
+                            restate.invocation.id = %invocation_id,
+                            "Inbox of {:?} is full, dropping its oldest invocation",
+                            inbox_service_id
+                        );
+                        counter!(PARTITION_INBOX_OVERFLOW, "policy" => "drop-oldest").increment(1);
+                        self.fail_inboxed_invocation(
+                            invocation_id,
+                            inboxed_invocation,
+                            &INBOX_FULL_INVOCATION_ERROR,
+                            effects,
+                        );
+                    }
+                    None => {
+                        debug!(
+                            restate.invocation.id = %service_invocation.invocation_id,
+                            "Inbox of {:?} is full, rejecting invocation",
+                            inbox_service_id
+                        );
+                        counter!(PARTITION_INBOX_OVERFLOW, "policy" => "reject-new").increment(1);
+                        self.reject_invocation(
+                            effects,
+                            service_invocation,
+                            &INBOX_FULL_INVOCATION_ERROR,
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        self.enqueue_invocation_into_inbox(effects, state, inbox_service_id, service_invocation)
+            .await
+    }
+
+    /// Fails an invocation before it has been stored.
+    fn reject_invocation(
+        &mut self,
+        effects: &mut Effects,
+        service_invocation: ServiceInvocation,
+        error: &InvocationError,
+    ) {
+        let ServiceInvocation {
+            invocation_id,
+            invocation_target,
+            response_sink,
+            span_context,
+            idempotency_key,
+            ..
+        } = service_invocation;
+
+        // No result is retained, release the idempotency key
+        let idempotency_id = idempotency_key.map(|idempotency| {
+            IdempotencyId::combine(invocation_id, &invocation_target, idempotency)
+        });
+        self.send_response_to_sinks(
+            effects,
+            &invocation_id,
+            idempotency_id.clone(),
+            response_sink,
+            error,
+        );
+        if let Some(idempotency_id) = idempotency_id {
+            effects.delete_idempotency_id(idempotency_id);
+        }
+
+        self.notify_invocation_result(
+            invocation_id,
+            invocation_target,
+            span_context,
+            MillisSinceEpoch::now(),
+            Err((error.code(), error.to_string())),
+            effects,
+        );
+    }
+
+    async fn enqueue_invocation_into_inbox<State: ReadOnlyInboxTable>(
+        &mut self,
+        effects: &mut Effects,
+        state: &mut State,
+        inbox_service_id: ServiceId,
+        service_invocation: ServiceInvocation,
+    ) -> Result<(), Error> {
+        let mut inbox_priority = InboxPriority::for_invocation(&service_invocation);
+        if inbox_priority == InboxPriority::Interactive {
+            // State mutations are applied in the order they were received relative to the
+            // invocations of the same key, so interactive invocations must not overtake them
+            let queued_state_mutations: Vec<_> = state
+                .inbox(&inbox_service_id)
+                .try_filter(|entry| {
+                    future::ready(matches!(entry.inbox_entry, InboxEntry::StateMutation(_)))
+                })
+                .take(1)
+                .try_collect()
+                .await?;
+            if !queued_state_mutations.is_empty() {
+                inbox_priority = InboxPriority::Background;
+            }
+        }
+
+        let inbox_seq_number = self.enqueue_into_inbox(
+            effects,
+            inbox_priority,
+            InboxEntry::Invocation(inbox_service_id, service_invocation.invocation_id),
+        );
+        Self::register_execution_deadline(effects, &service_invocation);
+        effects.store_inboxed_invocation(
+            service_invocation.invocation_id,
+            InboxedInvocation::from_service_invocation(
+                service_invocation,
+                inbox_seq_number,
+                inbox_priority,
+            ),
+        );
+        Ok(())
+    }
+
     fn enqueue_into_inbox(
         &mut self,
         effects: &mut Effects,
+        priority: InboxPriority,
         inbox_entry: InboxEntry,
     ) -> MessageIndex {
         let inbox_seq_number = self.inbox_seq_number;
-        effects.enqueue_into_inbox(self.inbox_seq_number, inbox_entry);
+        effects.enqueue_into_inbox(self.inbox_seq_number, priority, inbox_entry);
         self.inbox_seq_number += 1;
         inbox_seq_number
     }
@@ -426,7 +979,11 @@ where
 
         match service_status {
             VirtualObjectStatus::Locked(_) => {
-                self.enqueue_into_inbox(effects, InboxEntry::StateMutation(mutation));
+                self.enqueue_into_inbox(
+                    effects,
+                    InboxPriority::Background,
+                    InboxEntry::StateMutation(mutation),
+                );
             }
             VirtualObjectStatus::Unlocked => effects.apply_state_mutation(mutation),
         }
@@ -434,6 +991,71 @@ where
         Ok(())
     }
 
+    async fn purge_service_key<State: StateReader + ReadOnlyInboxTable>(
+        &mut self,
+        ServiceKeyPurge { service_id, force }: ServiceKeyPurge,
+        state: &mut State,
+        effects: &mut Effects,
+    ) -> Result<(), Error> {
+        let running_invocation = match state.get_virtual_object_status(&service_id).await? {
+            VirtualObjectStatus::Locked(invocation_id) if !force => {
+                warn!(
+                    restate.invocation.id = %invocation_id,
+                    "Refusing to purge service key '{:?}' because of a running invocation. Use force to kill it.",
+                    service_id
+                );
+                return Ok(());
+            }
+            VirtualObjectStatus::Locked(invocation_id) => Some(invocation_id),
+            VirtualObjectStatus::Unlocked => None,
+        };
+
+        // Purge the inbox before killing the running invocation, otherwise killing it would
+        // start the next inboxed invocation
+        let inbox_entries: Vec<_> = state.inbox(&service_id).try_collect().await?;
+        for SequenceNumberInboxEntry {
+            inbox_sequence_number,
+            priority,
+            inbox_entry,
+        } in inbox_entries
+        {
+            match inbox_entry {
+                InboxEntry::Invocation(_, invocation_id) => {
+                    if let InvocationStatus::Inboxed(inboxed) =
+                        state.get_invocation_status(&invocation_id).await?
+                    {
+                        self.terminate_inboxed_invocation(
+                            TerminationFlavor::Kill,
+                            invocation_id,
+                            inboxed,
+                            effects,
+                        )?;
+                    } else {
+                        effects.delete_inbox_entry(
+                            service_id.clone(),
+                            priority,
+                            inbox_sequence_number,
+                        );
+                    }
+                }
+                InboxEntry::StateMutation(_) => {
+                    effects.delete_inbox_entry(service_id.clone(), priority, inbox_sequence_number);
+                }
+            }
+        }
+
+        // Killing the running invocation drops its journal
+        if let Some(invocation_id) = running_invocation {
+            self.try_kill_invocation(invocation_id, state, effects)
+                .await?;
+        }
+
+        effects.purge_state(service_id.clone());
+        effects.clear_all_promises(service_id);
+
+        Ok(())
+    }
+
     async fn try_built_in_invoker_effect<State: StateReader>(
         &mut self,
         effects: &mut Effects,
@@ -485,6 +1107,7 @@ where
                     invocation_metadata.journal_metadata.span_context.clone(),
                     Bytes::from(key.into_owned()),
                     value,
+                    None,
                 );
             }
             BuiltinServiceEffect::ClearState(key) => {
@@ -640,12 +1263,25 @@ where
             TerminationFlavor::Kill => KILLED_INVOCATION_ERROR,
             TerminationFlavor::Cancel => CANCELED_INVOCATION_ERROR,
         };
+        self.fail_inboxed_invocation(invocation_id, inboxed_invocation, &error, effects);
+
+        Ok(())
+    }
 
+    fn fail_inboxed_invocation(
+        &mut self,
+        invocation_id: InvocationId,
+        inboxed_invocation: InboxedInvocation,
+        error: &InvocationError,
+        effects: &mut Effects,
+    ) {
         let InboxedInvocation {
             inbox_sequence_number,
+            inbox_priority,
             response_sinks,
             span_context,
             invocation_target,
+            execution_deadline,
             ..
         } = inboxed_invocation;
 
@@ -657,19 +1293,34 @@ where
         self.send_response_to_sinks(
             effects,
             &invocation_id,
-            idempotency_id,
+            idempotency_id.clone(),
             response_sinks,
-            &error,
+            error,
         );
 
-        // Delete inbox entry and invocation status.
-        effects.delete_inbox_entry(
-            invocation_target
-                .as_keyed_service_id()
-                .expect("Because the invocation is inboxed, it must have a keyed service id"),
-            inbox_sequence_number,
-        );
+        // Delete inbox entry and invocation status. Only invocations of exclusive handlers wait in
+        // an inbox, the other ones have been held back while their service is paused.
+        match invocation_target.invocation_target_ty() {
+            InvocationTargetType::VirtualObject(VirtualObjectHandlerType::Exclusive) => effects
+                .delete_inbox_entry(
+                    invocation_target.as_keyed_service_id().expect(
+                        "When the handler type is Exclusive, the invocation target must have a key",
+                    ),
+                    inbox_priority,
+                    inbox_sequence_number,
+                ),
+            InvocationTargetType::Workflow(WorkflowHandlerType::Workflow) => effects
+                .unlock_service_id(invocation_target.as_keyed_service_id().expect(
+                    "When the handler type is Workflow, the invocation target must have a key",
+                )),
+            _ => {}
+        }
+        Self::delete_execution_deadline(effects, invocation_id, execution_deadline);
         effects.free_invocation(invocation_id);
+        // No result is retained, release the idempotency key
+        if let Some(idempotency_id) = idempotency_id {
+            effects.delete_idempotency_id(idempotency_id);
+        }
 
         self.notify_invocation_result(
             invocation_id,
@@ -679,8 +1330,6 @@ where
             Err((error.code(), error.to_string())),
             effects,
         );
-
-        Ok(())
     }
 
     async fn kill_invocation<State: StateReader>(
@@ -690,8 +1339,9 @@ where
         state: &mut State,
         effects: &mut Effects,
     ) -> Result<(), Error> {
-        self.kill_child_invocations(
+        self.kill_journal_leaves(
             &invocation_id,
+            TerminationFlavor::Kill,
             state,
             effects,
             metadata.journal_metadata.length,
@@ -704,32 +1354,52 @@ where
         Ok(())
     }
 
-    async fn kill_child_invocations<State: StateReader>(
+    /// Terminates the uncompleted child invocations with the given flavor and deletes the pending
+    /// sleep timers of the journal. Unlike cancellation, no entries are completed since the journal
+    /// is dropped anyway.
+    async fn kill_journal_leaves<State: StateReader>(
         &mut self,
         invocation_id: &InvocationId,
+        child_termination: TerminationFlavor,
         state: &mut State,
         effects: &mut Effects,
         journal_length: EntryIndex,
     ) -> Result<(), Error> {
         let mut journal_entries = pin!(state.get_journal(invocation_id, journal_length));
         while let Some(journal_entry) = journal_entries.next().await {
-            let (_, journal_entry) = journal_entry?;
+            let (journal_index, journal_entry) = journal_entry?;
 
             if let JournalEntry::Entry(enriched_entry) = journal_entry {
-                let (h, _) = enriched_entry.into_inner();
+                let (h, entry) = enriched_entry.into_inner();
                 match h {
-                    // we only need to kill child invocations if they are not completed and the target was resolved
+                    // we only need to terminate child invocations if they are not completed and the target was resolved
                     EnrichedEntryHeader::Call {
                         is_completed,
                         enrichment_result: Some(enrichment_result),
                     } if !is_completed => {
                         self.handle_outgoing_message(
-                            OutboxMessage::InvocationTermination(InvocationTermination::kill(
-                                enrichment_result.invocation_id,
-                            )),
+                            OutboxMessage::InvocationTermination(InvocationTermination {
+                                invocation_id: enrichment_result.invocation_id,
+                                flavor: child_termination,
+                            }),
                             effects,
                         );
                     }
+                    // the timer would otherwise fire for an invocation that no longer exists
+                    EnrichedEntryHeader::Sleep { is_completed } if !is_completed => {
+                        let_assert!(
+                            Entry::Sleep(SleepEntry { wake_up_time, .. }) =
+                                ProtobufRawEntryCodec::deserialize(EntryType::Sleep, entry)?
+                        );
+
+                        let (timer_key, _) = Timer::complete_journal_entry(
+                            wake_up_time,
+                            *invocation_id,
+                            journal_index,
+                        );
+
+                        effects.delete_timer(timer_key);
+                    }
                     // we neither kill background calls nor delayed calls since we are considering them detached from this
                     // call tree. In the future we want to support a mode which also kills these calls (causally related).
                     // See https://github.com/restatedev/restate/issues/979
@@ -774,6 +1444,7 @@ where
                     }
                     EnrichedEntryHeader::Awakeable { is_completed }
                     | EnrichedEntryHeader::GetState { is_completed }
+                    | EnrichedEntryHeader::GetPromise { is_completed }
                         if !is_completed =>
                     {
                         resume_invocation |= Self::cancel_journal_entry_with(
@@ -846,12 +1517,13 @@ where
         }
     }
 
-    async fn on_timer<State: StateReader + ReadOnlyIdempotencyTable>(
+    async fn on_timer<State: StateReader + ReadOnlyIdempotencyTable + ReadOnlyInboxTable>(
         &mut self,
         timer_value: TimerKeyValue,
         state: &mut State,
         effects: &mut Effects,
     ) -> Result<(), Error> {
+        let wake_up_time = timer_value.wake_up_time();
         let (key, value) = timer_value.into_inner();
         effects.delete_timer(key);
 
@@ -872,9 +1544,15 @@ where
                 // Remove the execution time from the service invocation request
                 service_invocation.execution_time = None;
 
+                effects.set_related_invocation_id(&service_invocation.invocation_id);
+                effects.set_related_invocation_target(&service_invocation.invocation_target);
+                effects.set_parent_span_context(&service_invocation.span_context);
+
                 // ServiceInvocations scheduled with a timer are always owned by the same partition processor
-                // where the invocation should be executed
-                self.handle_invoke(effects, state, service_invocation).await
+                // where the invocation should be executed. The idempotency key was registered when the
+                // invocation was scheduled, resolving it again would find this very invocation.
+                self.invoke_or_enqueue(effects, state, service_invocation)
+                    .await
             }
             Timer::CleanInvocationStatus(invocation_id) => {
                 match Self::get_invocation_status_and_trace(state, &invocation_id, effects).await? {
@@ -908,7 +1586,7 @@ where
                                 invocation_id,
                                 ServiceInvocationSpanContext::empty(),
                             );
-                            // TODO CLEANUP PROMISES
+                            effects.clear_all_promises(service_id);
                         }
                     }
                     InvocationStatus::Free => {
@@ -921,10 +1599,102 @@ where
 
                 Ok(())
             }
+            Timer::ExpireState {
+                invocation_id,
+                service_id,
+                state_key,
+                ..
+            } => {
+                // The state entry might have been set again after this timer was registered
+                if state.load_state_expiration(&service_id, &state_key).await? == Some(wake_up_time)
+                {
+                    effects.clear_state(
+                        service_id,
+                        invocation_id,
+                        ServiceInvocationSpanContext::empty(),
+                        state_key,
+                    );
+                }
+
+                Ok(())
+            }
+            Timer::InvocationDeadline(invocation_id) => {
+                self.on_invocation_deadline(invocation_id, state, effects)
+                    .await
+            }
+            Timer::CleanDeadLetter(invocation_id) => {
+                // The dead letter might have been redriven or purged already, deleting it is a no-op then
+                effects.delete_dead_letter(invocation_id);
+                Ok(())
+            }
+            Timer::ResumeInvocation(invocation_id) => {
+                match Self::get_invocation_status_and_trace(state, &invocation_id, effects).await? {
+                    InvocationStatus::Suspended {
+                        metadata,
+                        waiting_for_completed_entries,
+                    } if waiting_for_completed_entries.is_empty() => {
+                        effects.resume_service(invocation_id, metadata);
+                    }
+                    _ => {
+                        trace!("Invocation '{invocation_id}' was resumed before its deployment saturation timer fired");
+                    }
+                }
+                Ok(())
+            }
         }
     }
 
-    async fn try_invoker_effect<State: StateReader + ReadOnlyJournalTable>(
+    /// Fails the invocation with a timeout unless it completed before its execution deadline. Its
+    /// uncompleted child invocations are canceled.
+    async fn on_invocation_deadline<State: StateReader>(
+        &mut self,
+        invocation_id: InvocationId,
+        state: &mut State,
+        effects: &mut Effects,
+    ) -> Result<(), Error> {
+        match Self::get_invocation_status_and_trace(state, &invocation_id, effects).await? {
+            InvocationStatus::Invoked(mut metadata)
+            | InvocationStatus::Suspended { mut metadata, .. } => {
+                // the timer has fired, there is nothing left to delete
+                metadata.execution_deadline = None;
+                self.kill_journal_leaves(
+                    &invocation_id,
+                    TerminationFlavor::Cancel,
+                    state,
+                    effects,
+                    metadata.journal_metadata.length,
+                )
+                .await?;
+
+                self.fail_invocation(
+                    effects,
+                    invocation_id,
+                    metadata,
+                    DEADLINE_EXCEEDED_INVOCATION_ERROR,
+                )
+                .await?;
+                effects.abort_invocation(invocation_id);
+            }
+            InvocationStatus::Inboxed(mut inboxed_invocation) => {
+                inboxed_invocation.execution_deadline = None;
+                self.fail_inboxed_invocation(
+                    invocation_id,
+                    inboxed_invocation,
+                    &DEADLINE_EXCEEDED_INVOCATION_ERROR,
+                    effects,
+                );
+            }
+            _ => {
+                trace!("Invocation '{invocation_id}' completed before its execution deadline");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn try_invoker_effect<
+        State: StateReader + ReadOnlyJournalTable + ReadOnlyPromiseTable,
+    >(
         &mut self,
         effects: &mut Effects,
         state: &mut State,
@@ -948,7 +1718,7 @@ where
         Ok(())
     }
 
-    async fn on_invoker_effect<State: StateReader + ReadOnlyJournalTable>(
+    async fn on_invoker_effect<State: StateReader + ReadOnlyJournalTable + ReadOnlyPromiseTable>(
         &mut self,
         effects: &mut Effects,
         state: &mut State,
@@ -1004,11 +1774,40 @@ where
                     );
                 }
             }
+            InvokerEffectKind::DeploymentSaturated {
+                deployment_id,
+                retry_at,
+            } => {
+                // The invocation waits in the partition rather than in the invoker, suspended
+                // without awaiting any entry until the timer resumes it
+                debug!(
+                    restate.invocation.id = %invocation_id,
+                    restate.deployment.id = %deployment_id,
+                    "Deployment is saturated, retrying the invocation at {}", retry_at
+                );
+                effects.suspend_service(invocation_id, invocation_metadata, HashSet::new());
+                effects.register_timer(
+                    TimerKeyValue::resume_invocation(retry_at, invocation_id),
+                    Default::default(),
+                );
+            }
             InvokerEffectKind::End => {
                 self.end_invocation(state, effects, invocation_id, invocation_metadata)
                     .await?;
             }
-            InvokerEffectKind::Failed(e) => {
+            InvokerEffectKind::Failed {
+                error: e,
+                failed_at,
+            } => {
+                self.store_dead_letter(
+                    effects,
+                    state,
+                    invocation_id,
+                    &invocation_metadata,
+                    &e,
+                    failed_at,
+                )
+                .await?;
                 self.fail_invocation(effects, invocation_id, invocation_metadata, e)
                     .await?;
             }
@@ -1024,8 +1823,23 @@ where
         invocation_id: InvocationId,
         invocation_metadata: InFlightInvocationMetadata,
     ) -> Result<(), Error> {
+        Self::delete_execution_deadline(
+            effects,
+            invocation_id,
+            invocation_metadata.execution_deadline,
+        );
         let journal_length = invocation_metadata.journal_metadata.length;
         let completion_retention_time = invocation_metadata.completion_retention_time;
+        let idempotency_id = invocation_metadata
+            .idempotency_key
+            .as_ref()
+            .map(|idempotency_key| {
+                IdempotencyId::combine(
+                    invocation_id,
+                    &invocation_metadata.invocation_target,
+                    idempotency_key.clone(),
+                )
+            });
 
         self.notify_invocation_result(
             invocation_id,
@@ -1037,7 +1851,7 @@ where
         );
 
         // Pop from inbox
-        Self::try_pop_inbox(effects, &invocation_metadata.invocation_target);
+        self.try_pop_inbox(effects, &invocation_metadata.invocation_target);
 
         // If there are any response sinks, or we need to store back the completed status,
         //  we need to find the latest output entry
@@ -1053,23 +1867,11 @@ where
                 return Ok(());
             };
 
-            let idempotency_id =
-                invocation_metadata
-                    .idempotency_key
-                    .as_ref()
-                    .map(|idempotency_key| {
-                        IdempotencyId::combine(
-                            invocation_id,
-                            &invocation_metadata.invocation_target,
-                            idempotency_key.clone(),
-                        )
-                    });
-
             // Send responses out
             self.send_response_to_sinks(
                 effects,
                 &invocation_id,
-                idempotency_id,
+                idempotency_id.clone(),
                 invocation_metadata.response_sinks.clone(),
                 result.clone(),
             );
@@ -1089,9 +1891,12 @@ where
             }
         }
 
-        // If no retention, immediately cleanup the invocation status
+        // If no retention, immediately cleanup the invocation status and the idempotency key
         if completion_retention_time.is_zero() {
             effects.free_invocation(invocation_id);
+            if let Some(idempotency_id) = idempotency_id {
+                effects.delete_idempotency_id(idempotency_id);
+            }
         }
         effects.drop_journal(invocation_id, journal_length);
 
@@ -1105,6 +1910,11 @@ where
         invocation_id: InvocationId,
         invocation_metadata: InFlightInvocationMetadata,
     ) -> Result<(), Error> {
+        Self::delete_execution_deadline(
+            effects,
+            invocation_id,
+            invocation_metadata.execution_deadline,
+        );
         self.notify_invocation_result(
             invocation_id,
             invocation_metadata.invocation_target.clone(),
@@ -1116,8 +1926,56 @@ where
 
         effects.free_invocation(invocation_id);
         effects.drop_journal(invocation_id, invocation_metadata.journal_metadata.length);
-        Self::try_pop_inbox(effects, &invocation_metadata.invocation_target);
+        self.try_pop_inbox(effects, &invocation_metadata.invocation_target);
+
+        Ok(())
+    }
+
+    /// Keeps the input of an invocation which the invoker gave up on, so that it can be inspected
+    /// and redriven until the dead letter retention expires.
+    async fn store_dead_letter<State: ReadOnlyJournalTable>(
+        &self,
+        effects: &mut Effects,
+        state: &mut State,
+        invocation_id: InvocationId,
+        invocation_metadata: &InFlightInvocationMetadata,
+        error: &InvocationError,
+        failed_at: MillisSinceEpoch,
+    ) -> Result<(), Error> {
+        let Some(JournalEntry::Entry(input_entry)) =
+            state.get_journal_entry(&invocation_id, 0).await?
+        else {
+            warn!(
+                restate.invocation.id = %invocation_id,
+                "Cannot dead letter the failed invocation, because its input entry is missing."
+            );
+            return Ok(());
+        };
+        debug_assert_eq!(input_entry.ty(), EntryType::Input);
+        let (headers, argument) =
+            Codec::deserialize_input_entry(input_entry.serialized_entry().clone())?;
 
+        effects.store_dead_letter(
+            invocation_id,
+            DeadLetter {
+                service_invocation: ServiceInvocation {
+                    invocation_id,
+                    invocation_target: invocation_metadata.invocation_target.clone(),
+                    argument,
+                    source: invocation_metadata.source.clone(),
+                    response_sink: None,
+                    span_context: invocation_metadata.journal_metadata.span_context.clone(),
+                    headers,
+                    execution_time: None,
+                    execution_deadline: None,
+                    completion_retention_time: Some(invocation_metadata.completion_retention_time),
+                    idempotency_key: invocation_metadata.idempotency_key.clone(),
+                },
+                failure: error.clone(),
+                failed_at,
+            },
+            self.dead_letter_retention,
+        );
         Ok(())
     }
 
@@ -1128,6 +1986,11 @@ where
         invocation_metadata: InFlightInvocationMetadata,
         error: InvocationError,
     ) -> Result<(), Error> {
+        Self::delete_execution_deadline(
+            effects,
+            invocation_id,
+            invocation_metadata.execution_deadline,
+        );
         let journal_length = invocation_metadata.journal_metadata.length;
 
         self.notify_invocation_result(
@@ -1156,15 +2019,15 @@ where
         self.send_response_to_sinks(
             effects,
             &invocation_id,
-            idempotency_id,
+            idempotency_id.clone(),
             invocation_metadata.response_sinks.clone(),
             response_result.clone(),
         );
 
         // Pop from inbox
-        Self::try_pop_inbox(effects, &invocation_metadata.invocation_target);
+        self.try_pop_inbox(effects, &invocation_metadata.invocation_target);
 
-        // Store the completed status or free it
+        // Store the completed status or free it together with the idempotency key
         if !invocation_metadata.completion_retention_time.is_zero() {
             let (completed_invocation, completion_retention_time) =
                 CompletedInvocation::from_in_flight_invocation_metadata(
@@ -1178,6 +2041,9 @@ where
             );
         } else {
             effects.free_invocation(invocation_id);
+            if let Some(idempotency_id) = idempotency_id {
+                effects.delete_idempotency_id(idempotency_id);
+            }
         }
 
         effects.drop_journal(invocation_id, journal_length);
@@ -1208,18 +2074,27 @@ where
         }
     }
 
-    fn try_pop_inbox(effects: &mut Effects, invocation_target: &InvocationTarget) {
+    fn try_pop_inbox(&self, effects: &mut Effects, invocation_target: &InvocationTarget) {
         // Inbox exists only for virtual object exclusive handler cases
         if invocation_target.invocation_target_ty()
             == InvocationTargetType::VirtualObject(VirtualObjectHandlerType::Exclusive)
         {
-            effects.pop_inbox(invocation_target.as_keyed_service_id().expect(
+            let service_id = invocation_target.as_keyed_service_id().expect(
                 "When the handler type is Exclusive, the invocation target must have a key",
-            ))
+            );
+            if self
+                .paused_services
+                .contains(invocation_target.service_name())
+            {
+                // The inbox is popped once the service is resumed
+                effects.unlock_service_id(service_id);
+            } else {
+                effects.pop_inbox(service_id);
+            }
         }
     }
 
-    async fn handle_journal_entry<State: StateReader>(
+    async fn handle_journal_entry<State: StateReader + ReadOnlyPromiseTable>(
         &mut self,
         effects: &mut Effects,
         state: &mut State,
@@ -1249,7 +2124,9 @@ where
                     if let Some(service_id) =
                         invocation_metadata.invocation_target.as_keyed_service_id()
                     {
-                        // Load state and write completion
+                        // Load state and write completion. Expired state is visible until its
+                        // expiration timer removes it, applying a command must not depend on the
+                        // wall clock.
                         let value = state.load_state(&service_id, &key).await?;
                         let completion_result = value
                             .map(CompletionResult::Success)
@@ -1287,6 +2164,7 @@ where
                         invocation_metadata.journal_metadata.span_context.clone(),
                         key,
                         value,
+                        None,
                     );
                 } else {
                     warn!(
@@ -1358,6 +2236,156 @@ where
                     );
                 }
             }
+            EnrichedEntryHeader::GetPromise { is_completed, .. } => {
+                if !is_completed {
+                    let_assert!(
+                        Entry::GetPromise(GetPromiseEntry { key, .. }) =
+                            journal_entry.deserialize_entry_ref::<Codec>()?
+                    );
+
+                    if let Some(service_id) =
+                        invocation_metadata.invocation_target.as_keyed_service_id()
+                    {
+                        let listener = JournalEntryId::from_parts(invocation_id, entry_index);
+                        match state.get_promise(&service_id, &key).await? {
+                            Some(Promise {
+                                state: PromiseState::Completed(result),
+                            }) => {
+                                // The promise is already completed, we can complete the entry right away
+                                let completion_result = CompletionResult::from(result);
+                                Codec::write_completion(
+                                    &mut journal_entry,
+                                    completion_result.clone(),
+                                )?;
+
+                                effects.forward_completion(
+                                    invocation_id,
+                                    Completion::new(entry_index, completion_result),
+                                );
+                            }
+                            Some(Promise {
+                                state: PromiseState::NotCompleted(mut listeners),
+                            }) => {
+                                listeners.push(listener);
+                                effects.put_promise(
+                                    service_id,
+                                    key,
+                                    Promise {
+                                        state: PromiseState::NotCompleted(listeners),
+                                    },
+                                );
+                            }
+                            None => effects.put_promise(
+                                service_id,
+                                key,
+                                Promise {
+                                    state: PromiseState::NotCompleted(vec![listener]),
+                                },
+                            ),
+                        }
+                    } else {
+                        warn!(
+                            "Trying to process entry {} for a target that has no promises",
+                            journal_entry.header().as_entry_type()
+                        );
+                        effects.forward_completion(
+                            invocation_id,
+                            Completion::new(entry_index, CompletionResult::Empty),
+                        );
+                    }
+                }
+            }
+            EnrichedEntryHeader::PeekPromise { is_completed, .. } => {
+                if !is_completed {
+                    let_assert!(
+                        Entry::PeekPromise(PeekPromiseEntry { key, .. }) =
+                            journal_entry.deserialize_entry_ref::<Codec>()?
+                    );
+
+                    let completion_result = if let Some(service_id) =
+                        invocation_metadata.invocation_target.as_keyed_service_id()
+                    {
+                        match state.get_promise(&service_id, &key).await? {
+                            Some(Promise {
+                                state: PromiseState::Completed(result),
+                            }) => CompletionResult::from(result),
+                            _ => CompletionResult::Empty,
+                        }
+                    } else {
+                        warn!(
+                            "Trying to process entry {} for a target that has no promises",
+                            journal_entry.header().as_entry_type()
+                        );
+                        CompletionResult::Empty
+                    };
+
+                    Codec::write_completion(&mut journal_entry, completion_result.clone())?;
+                    effects.forward_completion(
+                        invocation_id,
+                        Completion::new(entry_index, completion_result),
+                    );
+                }
+            }
+            EnrichedEntryHeader::CompletePromise { is_completed, .. } => {
+                if !is_completed {
+                    let_assert!(
+                        Entry::CompletePromise(CompletePromiseEntry {
+                            key,
+                            completion,
+                            ..
+                        }) = journal_entry.deserialize_entry_ref::<Codec>()?
+                    );
+
+                    let completion_result = if let Some(service_id) =
+                        invocation_metadata.invocation_target.as_keyed_service_id()
+                    {
+                        match state.get_promise(&service_id, &key).await? {
+                            Some(Promise {
+                                state: PromiseState::Completed(_),
+                            }) => CompletionResult::from(&ALREADY_COMPLETED_PROMISE_ERROR),
+                            promise => {
+                                // Complete the journal entries waiting on this promise
+                                if let Some(Promise {
+                                    state: PromiseState::NotCompleted(listeners),
+                                }) = promise
+                                {
+                                    for listener in listeners {
+                                        self.handle_outgoing_message(
+                                            OutboxMessage::ServiceResponse(InvocationResponse {
+                                                id: listener.invocation_id(),
+                                                entry_index: listener.journal_index(),
+                                                result: completion.clone().into(),
+                                            }),
+                                            effects,
+                                        );
+                                    }
+                                }
+
+                                effects.put_promise(
+                                    service_id,
+                                    key,
+                                    Promise {
+                                        state: PromiseState::Completed(completion),
+                                    },
+                                );
+                                CompletionResult::Empty
+                            }
+                        }
+                    } else {
+                        warn!(
+                            "Trying to process entry {} for a target that has no promises",
+                            journal_entry.header().as_entry_type()
+                        );
+                        CompletionResult::Empty
+                    };
+
+                    Codec::write_completion(&mut journal_entry, completion_result.clone())?;
+                    effects.forward_completion(
+                        invocation_id,
+                        Completion::new(entry_index, completion_result),
+                    );
+                }
+            }
             EnrichedEntryHeader::Sleep { is_completed, .. } => {
                 debug_assert!(!is_completed, "Sleep entry must not be completed.");
                 let_assert!(
@@ -1403,6 +2431,7 @@ where
                         span_context: span_context.clone(),
                         headers: vec![],
                         execution_time: None,
+                        execution_deadline: None,
                         completion_retention_time: *completion_retention_time,
                         idempotency_key: None,
                     };
@@ -1451,6 +2480,7 @@ where
                     span_context: span_context.clone(),
                     headers: vec![],
                     execution_time: delay,
+                    execution_deadline: None,
                     completion_retention_time: *completion_retention_time,
                     idempotency_key: None,
                 };
@@ -13,14 +13,14 @@ use super::*;
 use bytestring::ByteString;
 use futures::stream;
 use googletest::matcher::Matcher;
-use googletest::{all, any, assert_that, pat, unordered_elements_are};
+use googletest::{all, any, assert_that, elements_are, pat, unordered_elements_are};
 use prost::Message;
 use restate_invoker_api::EffectKind;
 use restate_service_protocol::awakeable_id::AwakeableIdentifier;
 use restate_service_protocol::codec::ProtobufRawEntryCodec;
 use restate_service_protocol::pb::protocol::SleepEntryMessage;
 use restate_storage_api::idempotency_table::IdempotencyMetadata;
-use restate_storage_api::inbox_table::SequenceNumberInboxEntry;
+use restate_storage_api::inbox_table::{InboxPriority, SequenceNumberInboxEntry};
 use restate_storage_api::invocation_status_table::{JournalMetadata, StatusTimestamps};
 use restate_storage_api::timer_table::{TimerKey, TimerKeyKind};
 use restate_storage_api::{Result as StorageResult, StorageError};
@@ -187,6 +187,14 @@ impl StateReader for StateReaderMock {
         todo!()
     }
 
+    async fn load_state_expiration(
+        &mut self,
+        _service_id: &ServiceId,
+        _key: &Bytes,
+    ) -> StorageResult<Option<MillisSinceEpoch>> {
+        todo!()
+    }
+
     async fn load_completion_result(
         &mut self,
         _invocation_id: &InvocationId,
@@ -202,6 +210,26 @@ impl StateReader for StateReaderMock {
     ) -> impl Stream<Item = Result<(EntryIndex, JournalEntry), StorageError>> + Send {
         ReadOnlyJournalTable::get_journal(self, invocation_id, length)
     }
+
+    fn inboxed_invocations(
+        &mut self,
+        _partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = StorageResult<(InvocationId, InvocationTarget)>> + Send {
+        let inboxed_invocations: Vec<_> = self
+            .invocations
+            .iter()
+            .filter_map(
+                |(invocation_id, invocation_status)| match invocation_status {
+                    InvocationStatus::Inboxed(inboxed_invocation) => Some(Ok((
+                        *invocation_id,
+                        inboxed_invocation.invocation_target.clone(),
+                    ))),
+                    _ => None,
+                },
+            )
+            .collect();
+        stream::iter(inboxed_invocations)
+    }
 }
 
 impl ReadOnlyJournalTable for StateReaderMock {
@@ -270,6 +298,76 @@ impl ReadOnlyIdempotencyTable for StateReaderMock {
     }
 }
 
+impl ReadOnlyPromiseTable for StateReaderMock {
+    async fn get_promise(
+        &mut self,
+        _service_id: &ServiceId,
+        _key: &ByteString,
+    ) -> StorageResult<Option<Promise>> {
+        unimplemented!();
+    }
+}
+
+impl ReadOnlyDeadLetterTable for StateReaderMock {
+    async fn get_dead_letter(
+        &mut self,
+        _invocation_id: &InvocationId,
+    ) -> StorageResult<Option<DeadLetter>> {
+        unimplemented!();
+    }
+
+    fn all_dead_letters(
+        &mut self,
+        _range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = StorageResult<(InvocationId, DeadLetter)>> + Send {
+        unimplemented!();
+
+        // I need this for type inference to work
+        #[allow(unreachable_code)]
+        futures::stream::iter(vec![])
+    }
+}
+
+impl ReadOnlyInboxTable for StateReaderMock {
+    async fn peek_inbox(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> StorageResult<Option<SequenceNumberInboxEntry>> {
+        Ok(self
+            .inboxes
+            .get(service_id)
+            .and_then(|inbox| inbox.first().cloned()))
+    }
+
+    fn inbox(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Stream<Item = StorageResult<SequenceNumberInboxEntry>> + Send {
+        stream::iter(
+            self.inboxes
+                .get(service_id)
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .map(Ok),
+        )
+    }
+
+    fn all_inboxes(
+        &mut self,
+        _range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = StorageResult<SequenceNumberInboxEntry>> + Send {
+        stream::iter(
+            self.inboxes
+                .values()
+                .flatten()
+                .cloned()
+                .map(Ok)
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
 #[test(tokio::test)]
 async fn awakeable_with_success() {
     let mut state_machine: CommandInterpreter<ProtobufRawEntryCodec> =
@@ -414,6 +512,331 @@ async fn send_response_using_invocation_id() {
     );
 }
 
+#[test(tokio::test)]
+async fn attach_to_running_invocation() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut effects = Effects::default();
+    let mut state_reader = StateReaderMock::default();
+
+    let invocation_id = state_reader
+        .register_invoked_status_and_locked(InvocationTarget::mock_virtual_object(), vec![]);
+    let response_sink = ServiceInvocationResponseSink::PartitionProcessor {
+        caller: InvocationId::mock_random(),
+        entry_index: 1,
+    };
+
+    command_interpreter
+        .on_apply(
+            Command::AttachInvocation(AttachInvocationRequest {
+                invocation_id,
+                response_sink: response_sink.clone(),
+            }),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        contains(pat!(Effect::AppendResponseSink {
+            invocation_id: eq(invocation_id),
+            additional_response_sink: eq(response_sink)
+        }))
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn attach_to_completed_invocation() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut effects = Effects::default();
+    let mut state_reader = StateReaderMock::default();
+
+    let (invocation_id, invocation_target) =
+        InvocationId::mock_with(InvocationTarget::mock_service());
+    let caller_invocation_id = InvocationId::mock_random();
+    let result = ResponseResult::Success(Bytes::from_static(b"hello"));
+    state_reader.register_invocation_status(
+        invocation_id,
+        InvocationStatus::Completed(CompletedInvocation {
+            invocation_target,
+            source: Source::Ingress,
+            idempotency_key: None,
+            timestamps: StatusTimestamps::now(),
+            response_result: result.clone(),
+        }),
+        vec![],
+    );
+
+    command_interpreter
+        .on_apply(
+            Command::AttachInvocation(AttachInvocationRequest {
+                invocation_id,
+                response_sink: ServiceInvocationResponseSink::PartitionProcessor {
+                    caller: caller_invocation_id,
+                    entry_index: 1,
+                },
+            }),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        contains(pat!(Effect::EnqueueIntoOutbox {
+            message: pat!(
+                restate_storage_api::outbox_table::OutboxMessage::ServiceResponse(pat!(
+                    InvocationResponse {
+                        id: eq(caller_invocation_id),
+                        entry_index: eq(1),
+                        result: eq(result)
+                    }
+                ))
+            )
+        }))
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn paused_service_holds_back_invocations() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut effects = Effects::default();
+    let mut state_mock = StateReaderMock::default();
+
+    let service_invocation = ServiceInvocation::mock();
+    let service_name = service_invocation.invocation_target.service_name().clone();
+    let invocation_id = service_invocation.invocation_id;
+
+    command_interpreter
+        .on_apply(
+            Command::PauseService(service_name.clone()),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+    command_interpreter
+        .on_apply(
+            Command::Invoke(service_invocation),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+
+    assert_that!(
+        effects.drain().collect::<Vec<_>>(),
+        all!(
+            contains(pat!(Effect::AddPausedService(eq(service_name.clone())))),
+            contains(pat!(Effect::StoreInboxedInvocation(
+                eq(invocation_id),
+                anything()
+            ))),
+            not(contains(pat!(Effect::InvokeService(anything()))))
+        )
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn upgrade_state_machine() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut effects = Effects::default();
+    let mut state_mock = StateReaderMock::default();
+    assert_eq!(
+        command_interpreter.state_machine_version(),
+        StateMachineVersion::V1
+    );
+
+    command_interpreter
+        .on_apply(
+            Command::UpgradeStateMachine(StateMachineVersion::V2),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+    assert_that!(
+        effects.drain().collect::<Vec<_>>(),
+        elements_are![pat!(Effect::UpgradeStateMachine(eq(
+            StateMachineVersion::V2
+        )))]
+    );
+    assert_eq!(
+        command_interpreter.state_machine_version(),
+        StateMachineVersion::V2
+    );
+
+    // Upgrades are proposed by every new leader, older versions are ignored
+    command_interpreter
+        .on_apply(
+            Command::UpgradeStateMachine(StateMachineVersion::V1),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+    assert_that!(effects.drain().collect::<Vec<_>>(), empty());
+    assert_eq!(
+        command_interpreter.state_machine_version(),
+        StateMachineVersion::V2
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn resume_service_dispatches_held_back_invocations() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    )
+    .with_paused_services(HashSet::from([ByteString::from_static("svc")]));
+    let mut effects = Effects::default();
+    let mut state_mock = StateReaderMock::default();
+
+    // held back invocation of a service handler
+    let service_invocation_target = InvocationTarget::service("svc", "handler");
+    let service_invocation_id = InvocationId::generate(&service_invocation_target);
+    state_mock.invocations.insert(
+        service_invocation_id,
+        InvocationStatus::Inboxed(InboxedInvocation::from_service_invocation(
+            ServiceInvocation {
+                invocation_id: service_invocation_id,
+                invocation_target: service_invocation_target,
+                ..ServiceInvocation::mock()
+            },
+            0,
+            InboxPriority::Interactive,
+        )),
+    );
+
+    // invocation of an exclusive handler waiting in the inbox of its virtual object
+    let object_invocation_target = InvocationTarget::virtual_object(
+        "svc",
+        "key",
+        "handler",
+        VirtualObjectHandlerType::Exclusive,
+    );
+    let object_invocation_id = InvocationId::generate(&object_invocation_target);
+    let object_service_id = object_invocation_target.as_keyed_service_id().unwrap();
+    state_mock.enqueue_into_inbox(
+        object_service_id.clone(),
+        SequenceNumberInboxEntry {
+            inbox_sequence_number: 1,
+            priority: InboxPriority::Interactive,
+            inbox_entry: InboxEntry::Invocation(object_service_id.clone(), object_invocation_id),
+        },
+    );
+    state_mock.invocations.insert(
+        object_invocation_id,
+        InvocationStatus::Inboxed(InboxedInvocation::from_service_invocation(
+            ServiceInvocation {
+                invocation_id: object_invocation_id,
+                invocation_target: object_invocation_target,
+                ..ServiceInvocation::mock()
+            },
+            1,
+            InboxPriority::Interactive,
+        )),
+    );
+
+    command_interpreter
+        .on_apply(
+            Command::ResumeService(ByteString::from_static("svc")),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        all!(
+            contains(pat!(Effect::RemovePausedService(eq(
+                ByteString::from_static("svc")
+            )))),
+            contains(pat!(Effect::DispatchInboxedInvocation(eq(
+                service_invocation_id
+            )))),
+            not(contains(pat!(Effect::DispatchInboxedInvocation(eq(
+                object_invocation_id
+            ))))),
+            contains(pat!(Effect::PopInbox(eq(object_service_id))))
+        )
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn fire_multiple_timers_at_once() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut effects = Effects::default();
+    let mut state_mock = StateReaderMock::default();
+
+    let wake_up_time = MillisSinceEpoch::new(1000);
+    let service_invocations =
+        [ServiceInvocation::mock(), ServiceInvocation::mock()].map(|service_invocation| {
+            ServiceInvocation {
+                execution_time: Some(wake_up_time),
+                ..service_invocation
+            }
+        });
+    let timers = service_invocations
+        .iter()
+        .map(|service_invocation| TimerKeyValue::invoke(wake_up_time, service_invocation.clone()))
+        .collect::<Vec<_>>();
+
+    command_interpreter
+        .on_apply(
+            Command::Timers(timers.clone()),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+
+    // all timers are fired with the same command
+    assert_that!(
+        effects.into_inner(),
+        all!(
+            contains(pat!(Effect::DeleteTimer(eq(timers[0].key().clone())))),
+            contains(pat!(Effect::DeleteTimer(eq(timers[1].key().clone())))),
+            contains(pat!(Effect::InvokeService(pat!(ServiceInvocation {
+                invocation_id: eq(service_invocations[0].invocation_id),
+                execution_time: none()
+            })))),
+            contains(pat!(Effect::InvokeService(pat!(ServiceInvocation {
+                invocation_id: eq(service_invocations[1].invocation_id),
+                execution_time: none()
+            }))))
+        )
+    );
+
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn kill_inboxed_invocation() -> Result<(), Error> {
     let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
@@ -434,6 +857,7 @@ async fn kill_inboxed_invocation() -> Result<(), Error> {
         inboxed_invocation_target.as_keyed_service_id().unwrap(),
         SequenceNumberInboxEntry {
             inbox_sequence_number: 0,
+            priority: InboxPriority::Interactive,
             inbox_entry: InboxEntry::Invocation(
                 inboxed_invocation_target.as_keyed_service_id().unwrap(),
                 inboxed_invocation_id,
@@ -444,6 +868,7 @@ async fn kill_inboxed_invocation() -> Result<(), Error> {
         inboxed_invocation_id,
         InvocationStatus::Inboxed(InboxedInvocation {
             inbox_sequence_number: 0,
+            inbox_priority: InboxPriority::Interactive,
             response_sinks: HashSet::from([ServiceInvocationResponseSink::PartitionProcessor {
                 caller: caller_invocation_id,
                 entry_index: 0,
@@ -455,6 +880,7 @@ async fn kill_inboxed_invocation() -> Result<(), Error> {
             span_context: Default::default(),
             headers: vec![],
             execution_time: None,
+            execution_deadline: None,
             completion_retention_time: Default::default(),
             idempotency_key: None,
         }),
@@ -473,6 +899,7 @@ async fn kill_inboxed_invocation() -> Result<(), Error> {
         all!(
             contains(pat!(Effect::DeleteInboxEntry {
                 service_id: eq(inboxed_invocation_target.as_keyed_service_id().unwrap(),),
+                priority: eq(InboxPriority::Interactive),
                 sequence_number: eq(0)
             })),
             contains(pat!(Effect::EnqueueIntoOutbox {
@@ -492,18 +919,235 @@ async fn kill_inboxed_invocation() -> Result<(), Error> {
     Ok(())
 }
 
-#[test(tokio::test)]
-async fn kill_call_tree() -> Result<(), Error> {
-    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
-        0,
-        0,
-        PartitionKey::MIN..=PartitionKey::MAX,
-    );
-    let mut state_reader = StateReaderMock::default();
-    let mut effects = Effects::default();
+/// Locks the virtual object of the target and puts an invocation of it into the inbox.
+fn mock_inboxed_invocation(
+    state_mock: &mut StateReaderMock,
+    invocation_target: &InvocationTarget,
+    caller_invocation_id: InvocationId,
+) -> InvocationId {
+    let service_id = invocation_target.as_keyed_service_id().unwrap();
+    let invocation_id = InvocationId::generate(invocation_target);
 
-    let call_invocation_id = InvocationId::mock_random();
-    let background_call_invocation_id = InvocationId::mock_random();
+    state_mock.lock_service(service_id.clone());
+    state_mock.enqueue_into_inbox(
+        service_id.clone(),
+        SequenceNumberInboxEntry {
+            inbox_sequence_number: 0,
+            priority: InboxPriority::Interactive,
+            inbox_entry: InboxEntry::Invocation(service_id, invocation_id),
+        },
+    );
+    state_mock.invocations.insert(
+        invocation_id,
+        InvocationStatus::Inboxed(InboxedInvocation {
+            inbox_sequence_number: 0,
+            inbox_priority: InboxPriority::Interactive,
+            response_sinks: HashSet::from([ServiceInvocationResponseSink::partition_processor(
+                caller_invocation_id,
+                0,
+            )]),
+            timestamps: StatusTimestamps::now(),
+            invocation_target: invocation_target.clone(),
+            argument: Default::default(),
+            source: Source::Ingress,
+            span_context: Default::default(),
+            headers: vec![],
+            execution_time: None,
+            execution_deadline: None,
+            completion_retention_time: Default::default(),
+            idempotency_key: None,
+        }),
+    );
+
+    invocation_id
+}
+
+#[test(tokio::test)]
+async fn full_inbox_rejects_new_invocation() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    )
+    .with_inbox_limit(1, InboxOverflowPolicy::RejectNew);
+    let mut effects = Effects::default();
+    let mut state_mock = StateReaderMock::default();
+
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    mock_inboxed_invocation(
+        &mut state_mock,
+        &invocation_target,
+        InvocationId::mock_random(),
+    );
+
+    let caller_invocation_id = InvocationId::mock_random();
+    let invocation_id = InvocationId::generate(&invocation_target);
+    command_interpreter
+        .on_apply(
+            Command::Invoke(ServiceInvocation {
+                invocation_id,
+                invocation_target,
+                response_sink: Some(ServiceInvocationResponseSink::partition_processor(
+                    caller_invocation_id,
+                    1,
+                )),
+                ..ServiceInvocation::mock()
+            }),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        all!(
+            not(contains(pat!(Effect::EnqueueIntoInbox {
+                inbox_entry: anything()
+            }))),
+            not(contains(pat!(Effect::DeleteInboxEntry {
+                service_id: anything()
+            }))),
+            contains(pat!(Effect::EnqueueIntoOutbox {
+                message: pat!(
+                    restate_storage_api::outbox_table::OutboxMessage::ServiceResponse(pat!(
+                        InvocationResponse {
+                            id: eq(caller_invocation_id),
+                            entry_index: eq(1),
+                            result: eq(ResponseResult::Failure(INBOX_FULL_INVOCATION_ERROR))
+                        }
+                    ))
+                )
+            }))
+        )
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn full_inbox_drops_oldest_invocation() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    )
+    .with_inbox_limit(1, InboxOverflowPolicy::DropOldest);
+    let mut effects = Effects::default();
+    let mut state_mock = StateReaderMock::default();
+
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    let caller_invocation_id = InvocationId::mock_random();
+    mock_inboxed_invocation(&mut state_mock, &invocation_target, caller_invocation_id);
+
+    let invocation_id = InvocationId::generate(&invocation_target);
+    command_interpreter
+        .on_apply(
+            Command::Invoke(ServiceInvocation {
+                invocation_id,
+                invocation_target: invocation_target.clone(),
+                ..ServiceInvocation::mock()
+            }),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        all!(
+            contains(pat!(Effect::DeleteInboxEntry {
+                service_id: eq(invocation_target.as_keyed_service_id().unwrap()),
+                priority: eq(InboxPriority::Interactive),
+                sequence_number: eq(0)
+            })),
+            contains(pat!(Effect::EnqueueIntoOutbox {
+                message: pat!(
+                    restate_storage_api::outbox_table::OutboxMessage::ServiceResponse(pat!(
+                        InvocationResponse {
+                            id: eq(caller_invocation_id),
+                            entry_index: eq(0),
+                            result: eq(ResponseResult::Failure(INBOX_FULL_INVOCATION_ERROR))
+                        }
+                    ))
+                )
+            })),
+            contains(pat!(Effect::EnqueueIntoInbox {
+                inbox_entry: eq(InboxEntry::Invocation(
+                    invocation_target.as_keyed_service_id().unwrap(),
+                    invocation_id
+                ))
+            }))
+        )
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn interactive_invocation_does_not_overtake_state_mutation() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        1,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut effects = Effects::default();
+    let mut state_mock = StateReaderMock::default();
+
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    let service_id = invocation_target.as_keyed_service_id().unwrap();
+    state_mock.lock_service(service_id.clone());
+    state_mock.enqueue_into_inbox(
+        service_id.clone(),
+        SequenceNumberInboxEntry::from_state_mutation(
+            0,
+            ExternalStateMutation {
+                service_id: service_id.clone(),
+                version: None,
+                state: HashMap::default(),
+            },
+        ),
+    );
+
+    command_interpreter
+        .on_apply(
+            Command::Invoke(ServiceInvocation {
+                invocation_id: InvocationId::generate(&invocation_target),
+                invocation_target,
+                response_sink: Some(ServiceInvocationResponseSink::partition_processor(
+                    InvocationId::mock_random(),
+                    1,
+                )),
+                ..ServiceInvocation::mock()
+            }),
+            &mut effects,
+            &mut state_mock,
+        )
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        contains(pat!(Effect::EnqueueIntoInbox {
+            seq_number: eq(1),
+            priority: eq(InboxPriority::Background),
+            inbox_entry: pat!(InboxEntry::Invocation(eq(service_id), anything()))
+        }))
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn kill_call_tree() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut state_reader = StateReaderMock::default();
+    let mut effects = Effects::default();
+
+    let call_invocation_id = InvocationId::mock_random();
+    let background_call_invocation_id = InvocationId::mock_random();
     let finished_call_invocation_id = InvocationId::mock_random();
 
     let invocation_target = InvocationTarget::mock_virtual_object();
@@ -561,6 +1205,351 @@ async fn kill_call_tree() -> Result<(), Error> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn kill_invocation_deletes_sleep_timers() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut state_reader = StateReaderMock::default();
+    let mut effects = Effects::default();
+
+    let call_invocation_id = InvocationId::mock_random();
+    let background_call_invocation_id = InvocationId::mock_random();
+    let finished_call_invocation_id = InvocationId::mock_random();
+
+    let invocation_id = state_reader.register_suspended_status_and_locked(
+        InvocationTarget::mock_virtual_object(),
+        vec![3, 4, 5, 6],
+        create_termination_journal(
+            call_invocation_id,
+            background_call_invocation_id,
+            finished_call_invocation_id,
+        ),
+    );
+
+    command_interpreter
+        .on_apply(
+            Command::TerminateInvocation(InvocationTermination::kill(invocation_id)),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    let effects = effects.into_inner();
+
+    assert_that!(
+        effects,
+        all!(
+            contains(terminate_invocation_outbox_message_matcher(
+                call_invocation_id,
+                TerminationFlavor::Kill
+            )),
+            contains(delete_timer(5)),
+            contains(pat!(Effect::FreeInvocation(eq(invocation_id)))),
+            contains(pat!(Effect::DropJournal {
+                invocation_id: eq(invocation_id),
+            })),
+            not(contains(pat!(Effect::StoreCompletion {
+                invocation_id: eq(invocation_id),
+            }))),
+            not(contains(pat!(Effect::ResumeService {
+                invocation_id: eq(invocation_id),
+            })))
+        )
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn execution_deadline_cancels_invocation() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut state_reader = StateReaderMock::default();
+    let mut effects = Effects::default();
+
+    let call_invocation_id = InvocationId::mock_random();
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    let invocation_id = state_reader.register_invoked_status_and_locked(
+        invocation_target.clone(),
+        vec![uncompleted_invoke_entry(call_invocation_id)],
+    );
+
+    let deadline_timer =
+        TimerKeyValue::invocation_deadline(MillisSinceEpoch::new(1000), invocation_id);
+    command_interpreter
+        .on_apply(
+            Command::Timer(deadline_timer.clone()),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        all!(
+            contains(pat!(Effect::DeleteTimer(eq(deadline_timer.key().clone())))),
+            contains(pat!(Effect::SendAbortInvocationToInvoker(eq(
+                invocation_id
+            )))),
+            contains(pat!(Effect::FreeInvocation(eq(invocation_id)))),
+            contains(pat!(Effect::PopInbox(eq(invocation_target
+                .as_keyed_service_id()
+                .unwrap())))),
+            contains(terminate_invocation_outbox_message_matcher(
+                call_invocation_id,
+                TerminationFlavor::Cancel
+            ))
+        )
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn execution_deadline_is_deleted_when_invocation_ends() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut state_reader = StateReaderMock::default();
+    let mut effects = Effects::default();
+
+    let (invocation_id, invocation_target) =
+        InvocationId::mock_with(InvocationTarget::mock_service());
+    let execution_deadline = MillisSinceEpoch::new(1000);
+    state_reader.register_invocation_status(
+        invocation_id,
+        InvocationStatus::Invoked(InFlightInvocationMetadata {
+            execution_deadline: Some(execution_deadline),
+            ..StateReaderMock::mock_invocation_metadata(0, invocation_target)
+        }),
+        vec![],
+    );
+
+    command_interpreter
+        .on_apply(
+            Command::InvokerEffect(InvokerEffect {
+                invocation_id,
+                kind: EffectKind::End,
+            }),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    let deadline_timer = TimerKeyValue::invocation_deadline(execution_deadline, invocation_id);
+    assert_that!(
+        effects.into_inner(),
+        all!(
+            contains(pat!(Effect::DeleteTimer(eq(deadline_timer.key().clone())))),
+            contains(pat!(Effect::FreeInvocation(eq(invocation_id))))
+        )
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn attached_workflow_invocation_does_not_register_execution_deadline() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut state_reader = StateReaderMock::default();
+    let mut effects = Effects::default();
+
+    let invocation_target = InvocationTarget::mock_workflow();
+    let running_invocation_id =
+        state_reader.register_invoked_status_and_locked(invocation_target.clone(), vec![]);
+    let response_sink = ServiceInvocationResponseSink::PartitionProcessor {
+        caller: InvocationId::mock_random(),
+        entry_index: 1,
+    };
+
+    command_interpreter
+        .on_apply(
+            Command::Invoke(ServiceInvocation {
+                invocation_id: InvocationId::generate(&invocation_target),
+                invocation_target,
+                response_sink: Some(response_sink.clone()),
+                execution_deadline: Some(MillisSinceEpoch::new(1000)),
+                ..ServiceInvocation::mock()
+            }),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        all!(
+            contains(pat!(Effect::AppendResponseSink {
+                invocation_id: eq(running_invocation_id),
+                additional_response_sink: eq(response_sink)
+            })),
+            not(contains(pat!(Effect::RegisterTimer { .. })))
+        )
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn purge_service_key_refuses_running_invocation() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut state_reader = StateReaderMock::default();
+    let mut effects = Effects::default();
+
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    let service_id = invocation_target.as_keyed_service_id().unwrap();
+    state_reader.register_invoked_status_and_locked(invocation_target, vec![]);
+
+    command_interpreter
+        .on_apply(
+            Command::PurgeServiceKey(ServiceKeyPurge {
+                service_id,
+                force: false,
+            }),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    assert_that!(effects.into_inner(), empty());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn force_purge_service_key() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(
+        0,
+        0,
+        PartitionKey::MIN..=PartitionKey::MAX,
+    );
+    let mut state_reader = StateReaderMock::default();
+    let mut effects = Effects::default();
+
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    let service_id = invocation_target.as_keyed_service_id().unwrap();
+    let invocation_id =
+        state_reader.register_invoked_status_and_locked(invocation_target.clone(), vec![]);
+
+    let inboxed_invocation_id = InvocationId::generate(&invocation_target);
+    let caller_invocation_id = InvocationId::mock_random();
+    state_reader.enqueue_into_inbox(
+        service_id.clone(),
+        SequenceNumberInboxEntry::from_invocation(0, service_id.clone(), inboxed_invocation_id)
+            .with_priority(InboxPriority::Interactive),
+    );
+    state_reader.enqueue_into_inbox(
+        service_id.clone(),
+        SequenceNumberInboxEntry::from_state_mutation(
+            1,
+            ExternalStateMutation {
+                service_id: service_id.clone(),
+                version: None,
+                state: HashMap::default(),
+            },
+        ),
+    );
+    state_reader.invocations.insert(
+        inboxed_invocation_id,
+        InvocationStatus::Inboxed(InboxedInvocation {
+            inbox_sequence_number: 0,
+            inbox_priority: InboxPriority::Interactive,
+            response_sinks: HashSet::from([ServiceInvocationResponseSink::PartitionProcessor {
+                caller: caller_invocation_id,
+                entry_index: 0,
+            }]),
+            timestamps: StatusTimestamps::now(),
+            invocation_target: invocation_target.clone(),
+            argument: Default::default(),
+            source: Source::Ingress,
+            span_context: Default::default(),
+            headers: vec![],
+            execution_time: None,
+            execution_deadline: None,
+            completion_retention_time: Default::default(),
+            idempotency_key: None,
+        }),
+    );
+
+    command_interpreter
+        .on_apply(
+            Command::PurgeServiceKey(ServiceKeyPurge {
+                service_id: service_id.clone(),
+                force: true,
+            }),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    let effects = effects.into_inner();
+
+    assert_that!(
+        effects,
+        all!(
+            contains(pat!(Effect::DeleteInboxEntry {
+                service_id: eq(service_id.clone()),
+                priority: eq(InboxPriority::Interactive),
+                sequence_number: eq(0)
+            })),
+            contains(pat!(Effect::DeleteInboxEntry {
+                service_id: eq(service_id.clone()),
+                priority: eq(InboxPriority::Background),
+                sequence_number: eq(1)
+            })),
+            contains(pat!(Effect::EnqueueIntoOutbox {
+                message: pat!(
+                    restate_storage_api::outbox_table::OutboxMessage::ServiceResponse(pat!(
+                        InvocationResponse {
+                            id: eq(caller_invocation_id),
+                            entry_index: eq(0),
+                            result: eq(ResponseResult::Failure(KILLED_INVOCATION_ERROR))
+                        }
+                    ))
+                )
+            })),
+            contains(pat!(Effect::SendAbortInvocationToInvoker(eq(
+                invocation_id
+            )))),
+            contains(pat!(Effect::DropJournal {
+                invocation_id: eq(invocation_id),
+            })),
+            contains(pat!(Effect::PurgeState(eq(service_id.clone()))))
+        )
+    );
+
+    // the inbox must be purged before the killed invocation pops it
+    let pop_inbox = effects
+        .iter()
+        .position(|effect| matches!(effect, Effect::PopInbox(_)))
+        .unwrap();
+    assert_eq!(
+        effects[..pop_inbox]
+            .iter()
+            .filter(|effect| matches!(effect, Effect::DeleteInboxEntry { .. }))
+            .count(),
+        2
+    );
+
+    Ok(())
+}
+
 fn completed_invoke_entry(invocation_id: InvocationId) -> JournalEntry {
     JournalEntry::Entry(EnrichedRawEntry::new(
         EnrichedEntryHeader::Call {
@@ -14,10 +14,11 @@ use crate::partition::state_machine::actions::Action;
 use crate::partition::state_machine::effects::Effect;
 use assert2::let_assert;
 use bytes::Bytes;
+use bytestring::ByteString;
 use futures::{Stream, TryStreamExt};
 use restate_invoker_api::InvokeInputJournal;
 use restate_storage_api::idempotency_table::IdempotencyMetadata;
-use restate_storage_api::inbox_table::{InboxEntry, SequenceNumberInboxEntry};
+use restate_storage_api::inbox_table::{InboxEntry, InboxPriority, SequenceNumberInboxEntry};
 use restate_storage_api::invocation_status_table::{InFlightInvocationMetadata, InvocationStatus};
 use restate_storage_api::outbox_table::OutboxMessage;
 use restate_storage_api::service_status_table::VirtualObjectStatus;
@@ -32,6 +33,8 @@ use restate_types::journal::raw::{PlainRawEntry, RawEntryCodec};
 use restate_types::journal::{Completion, CompletionResult, EntryType};
 use restate_types::message::MessageIndex;
 use restate_types::state_mut::{ExternalStateMutation, StateMutationVersion};
+use restate_types::time::MillisSinceEpoch;
+use restate_types::StateMachineVersion;
 use std::future::Future;
 use std::marker::PhantomData;
 use tracing::{debug, warn};
@@ -87,6 +90,7 @@ pub trait StateStorage {
     fn enqueue_into_inbox(
         &mut self,
         seq_number: MessageIndex,
+        priority: InboxPriority,
         inbox_entry: InboxEntry,
     ) -> impl Future<Output = StorageResult<()>> + Send;
 
@@ -111,6 +115,21 @@ pub trait StateStorage {
         outbox_sequence_number: MessageIndex,
     ) -> impl Future<Output = StorageResult<()>> + Send;
 
+    fn add_paused_service(
+        &mut self,
+        service_name: &ByteString,
+    ) -> impl Future<Output = StorageResult<()>> + Send;
+
+    fn remove_paused_service(
+        &mut self,
+        service_name: &ByteString,
+    ) -> impl Future<Output = StorageResult<()>> + Send;
+
+    fn store_state_machine_version(
+        &mut self,
+        version: StateMachineVersion,
+    ) -> impl Future<Output = StorageResult<()>> + Send;
+
     fn pop_inbox(
         &mut self,
         service_id: &ServiceId,
@@ -119,6 +138,7 @@ pub trait StateStorage {
     fn delete_inbox_entry(
         &mut self,
         service_id: &ServiceId,
+        priority: InboxPriority,
         sequence_number: MessageIndex,
     ) -> impl Future<Output = ()> + Send;
 
@@ -133,6 +153,7 @@ pub trait StateStorage {
         service_id: &ServiceId,
         key: Bytes,
         value: Bytes,
+        expiration_time: Option<MillisSinceEpoch>,
     ) -> impl Future<Output = StorageResult<()>> + Send;
 
     fn load_state(
@@ -173,7 +194,9 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
     pub(crate) async fn interpret_effects<
         S: StateStorage
             + restate_storage_api::invocation_status_table::ReadOnlyInvocationStatusTable
-            + restate_storage_api::idempotency_table::IdempotencyTable,
+            + restate_storage_api::idempotency_table::IdempotencyTable
+            + restate_storage_api::dead_letter_table::DeadLetterTable
+            + restate_storage_api::promise_table::PromiseTable,
     >(
         effects: &mut Effects,
         state_storage: &mut S,
@@ -189,7 +212,9 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
     async fn interpret_effect<
         S: StateStorage
             + restate_storage_api::invocation_status_table::ReadOnlyInvocationStatusTable
-            + restate_storage_api::idempotency_table::IdempotencyTable,
+            + restate_storage_api::idempotency_table::IdempotencyTable
+            + restate_storage_api::dead_letter_table::DeadLetterTable
+            + restate_storage_api::promise_table::PromiseTable,
     >(
         effect: Effect,
         state_storage: &mut S,
@@ -262,6 +287,9 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                     retention,
                 });
             }
+            Effect::DispatchInboxedInvocation(invocation_id) => {
+                Self::invoke_inboxed_invocation(state_storage, collector, invocation_id).await?;
+            }
             Effect::FreeInvocation(invocation_id) => {
                 state_storage
                     .store_invocation_status(&invocation_id, InvocationStatus::Free)
@@ -269,10 +297,11 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
             }
             Effect::EnqueueIntoInbox {
                 seq_number,
+                priority,
                 inbox_entry,
             } => {
                 state_storage
-                    .enqueue_into_inbox(seq_number, inbox_entry)
+                    .enqueue_into_inbox(seq_number, priority, inbox_entry)
                     .await?;
                 // need to store the next inbox sequence number
                 state_storage.store_inbox_seq_number(seq_number + 1).await?;
@@ -282,10 +311,11 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
             }
             Effect::DeleteInboxEntry {
                 service_id,
+                priority,
                 sequence_number,
             } => {
                 state_storage
-                    .delete_inbox_entry(&service_id, sequence_number)
+                    .delete_inbox_entry(&service_id, priority, sequence_number)
                     .await;
             }
             Effect::EnqueueIntoOutbox {
@@ -305,6 +335,11 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                     message,
                 });
             }
+            Effect::LockService(service_id, invocation_id) => {
+                state_storage
+                    .store_service_status(&service_id, VirtualObjectStatus::Locked(invocation_id))
+                    .await?;
+            }
             Effect::UnlockService(service_id) => {
                 state_storage
                     .store_service_status(&service_id, VirtualObjectStatus::Unlocked)
@@ -314,9 +349,12 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                 service_id,
                 key,
                 value,
+                expiration_time,
                 ..
             } => {
-                state_storage.store_state(&service_id, key, value).await?;
+                state_storage
+                    .store_state(&service_id, key, value, expiration_time)
+                    .await?;
             }
             Effect::ClearState {
                 service_id, key, ..
@@ -426,6 +464,34 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                     .delete_idempotency_metadata(&idempotency_id)
                     .await;
             }
+            Effect::StoreDeadLetter {
+                invocation_id,
+                dead_letter,
+                retention,
+            } => {
+                state_storage
+                    .put_dead_letter(&invocation_id, *dead_letter)
+                    .await;
+                if let Some(retention) = retention {
+                    collector.push(Action::ScheduleDeadLetterCleanup {
+                        invocation_id,
+                        retention,
+                    });
+                }
+            }
+            Effect::DeleteDeadLetter(invocation_id) => {
+                state_storage.delete_dead_letter(&invocation_id).await;
+            }
+            Effect::PutPromise {
+                service_id,
+                key,
+                metadata,
+            } => {
+                state_storage.put_promise(&service_id, &key, metadata).await;
+            }
+            Effect::ClearAllPromises { service_id } => {
+                state_storage.delete_all_promises(&service_id).await?;
+            }
             Effect::TraceInvocationResult { .. } | Effect::TraceBackgroundInvoke { .. } => {
                 // these effects are only needed for span creation
             }
@@ -441,6 +507,18 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
             Effect::MutateState(state_mutation) => {
                 Self::mutate_state(state_storage, state_mutation).await?;
             }
+            Effect::PurgeState(service_id) => {
+                state_storage.clear_all_state(&service_id).await?;
+            }
+            Effect::AddPausedService(service_name) => {
+                state_storage.add_paused_service(&service_name).await?;
+            }
+            Effect::RemovePausedService(service_name) => {
+                state_storage.remove_paused_service(&service_name).await?;
+            }
+            Effect::UpgradeStateMachine(version) => {
+                state_storage.store_state_machine_version(version).await?;
+            }
             Effect::IngressResponse(ingress_response) => {
                 collector.push(Action::IngressResponse(ingress_response));
             }
@@ -463,25 +541,8 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
         while let Some(inbox_entry) = state_storage.pop_inbox(&service_id).await? {
             match inbox_entry.inbox_entry {
                 InboxEntry::Invocation(_, invocation_id) => {
-                    let inboxed_status =
-                        state_storage.get_invocation_status(&invocation_id).await?;
-
-                    let_assert!(
-                        InvocationStatus::Inboxed(inboxed_invocation) = inboxed_status,
-                        "InvocationStatus must contain an Inboxed invocation for the id {}",
-                        invocation_id
-                    );
-
-                    let (in_flight_invocation_meta, invocation_input) =
-                        InFlightInvocationMetadata::from_inboxed_invocation(inboxed_invocation);
-                    Self::invoke_service(
-                        state_storage,
-                        collector,
-                        invocation_id,
-                        in_flight_invocation_meta,
-                        invocation_input,
-                    )
-                    .await?;
+                    Self::invoke_inboxed_invocation(state_storage, collector, invocation_id)
+                        .await?;
                     return Ok(());
                 }
                 InboxEntry::StateMutation(state_mutation) => {
@@ -497,6 +558,35 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
         Ok(())
     }
 
+    async fn invoke_inboxed_invocation<S>(
+        state_storage: &mut S,
+        collector: &mut ActionCollector,
+        invocation_id: InvocationId,
+    ) -> Result<(), Error>
+    where
+        S: StateStorage
+            + restate_storage_api::invocation_status_table::ReadOnlyInvocationStatusTable,
+    {
+        let inboxed_status = state_storage.get_invocation_status(&invocation_id).await?;
+
+        let_assert!(
+            InvocationStatus::Inboxed(inboxed_invocation) = inboxed_status,
+            "InvocationStatus must contain an Inboxed invocation for the id {}",
+            invocation_id
+        );
+
+        let (in_flight_invocation_meta, invocation_input) =
+            InFlightInvocationMetadata::from_inboxed_invocation(inboxed_invocation);
+        Self::invoke_service(
+            state_storage,
+            collector,
+            invocation_id,
+            in_flight_invocation_meta,
+            invocation_input,
+        )
+        .await
+    }
+
     async fn mutate_state<S: StateStorage>(
         state_storage: &mut S,
         state_mutation: ExternalStateMutation,
@@ -532,7 +622,9 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
 
         // overwrite existing key value pairs
         for (key, value) in state {
-            state_storage.store_state(&service_id, key, value).await?
+            state_storage
+                .store_state(&service_id, key, value, None)
+                .await?
         }
 
         Ok(())
@@ -615,18 +707,23 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
             .load_journal_entry(invocation_id, entry_index)
             .await?
         {
-            if journal_entry.ty() == EntryType::Awakeable
-                && journal_entry.header().is_completed() == Some(true)
+            if matches!(
+                journal_entry.ty(),
+                EntryType::Awakeable | EntryType::GetPromise
+            ) && journal_entry.header().is_completed() == Some(true)
             {
                 // We can ignore when we get an awakeable completion twice as they might be a result of
                 // some request being retried from the ingress to complete the awakeable.
+                // Same for promises, whose get entries might have been canceled before the promise completed.
                 // We'll use only the first completion, because changing the awakeable result
                 // after it has been completed for the first time can cause non-deterministic execution.
                 warn!(
                     restate.invocation.id = %invocation_id,
                     restate.journal.index = entry_index,
-                    "Trying to complete an awakeable already completed. Ignoring this completion");
-                debug!("Discarded awakeable completion: {:?}", completion_result);
+                    "Trying to complete an already completed {} entry. Ignoring this completion",
+                    journal_entry.ty()
+                );
+                debug!("Discarded completion: {:?}", completion_result);
                 return Ok(false);
             }
             Codec::write_completion(&mut journal_entry, completion_result)?;
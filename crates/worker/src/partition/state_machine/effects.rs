@@ -10,13 +10,16 @@
 
 use crate::partition::types::InvocationIdAndTarget;
 use bytes::Bytes;
+use bytestring::ByteString;
 use opentelemetry::trace::SpanId;
-use restate_storage_api::inbox_table::InboxEntry;
+use restate_storage_api::dead_letter_table::DeadLetter;
+use restate_storage_api::inbox_table::{InboxEntry, InboxPriority};
 use restate_storage_api::invocation_status_table::{
     CompletedInvocation, InFlightInvocationMetadata, InboxedInvocation,
 };
 use restate_storage_api::invocation_status_table::{InvocationStatus, JournalMetadata};
 use restate_storage_api::outbox_table::OutboxMessage;
+use restate_storage_api::promise_table::Promise;
 use restate_storage_api::timer_table::{Timer, TimerKey};
 use restate_types::errors::InvocationErrorCode;
 use restate_types::identifiers::{
@@ -32,6 +35,7 @@ use restate_types::journal::{Completion, CompletionResult};
 use restate_types::message::MessageIndex;
 use restate_types::state_mut::ExternalStateMutation;
 use restate_types::time::MillisSinceEpoch;
+use restate_types::StateMachineVersion;
 use restate_wal_protocol::timer::TimerKeyDisplay;
 use restate_wal_protocol::timer::TimerKeyValue;
 use std::collections::HashSet;
@@ -59,11 +63,13 @@ pub(crate) enum Effect {
         completed_invocation: CompletedInvocation,
     },
     StoreInboxedInvocation(InvocationId, InboxedInvocation),
+    DispatchInboxedInvocation(InvocationId),
     FreeInvocation(InvocationId),
 
     // In-/outbox
     EnqueueIntoInbox {
         seq_number: MessageIndex,
+        priority: InboxPriority,
         inbox_entry: InboxEntry,
     },
     PopInbox(ServiceId),
@@ -74,10 +80,12 @@ pub(crate) enum Effect {
     TruncateOutbox(MessageIndex),
     DeleteInboxEntry {
         service_id: ServiceId,
+        priority: InboxPriority,
         sequence_number: MessageIndex,
     },
 
     // Lock status
+    LockService(ServiceId, InvocationId),
     UnlockService(ServiceId),
 
     // State
@@ -87,6 +95,7 @@ pub(crate) enum Effect {
         span_context: ServiceInvocationSpanContext,
         key: Bytes,
         value: Bytes,
+        expiration_time: Option<MillisSinceEpoch>,
     },
     ClearState {
         service_id: ServiceId,
@@ -162,11 +171,37 @@ pub(crate) enum Effect {
 
     // State mutations
     MutateState(ExternalStateMutation),
+    PurgeState(ServiceId),
+
+    // Paused services
+    AddPausedService(ByteString),
+    RemovePausedService(ByteString),
+
+    // State machine version
+    UpgradeStateMachine(StateMachineVersion),
 
     // Idempotency
     StoreIdempotencyId(IdempotencyId, InvocationId),
     DeleteIdempotencyId(IdempotencyId),
 
+    // Dead letter queue
+    StoreDeadLetter {
+        invocation_id: InvocationId,
+        dead_letter: Box<DeadLetter>,
+        retention: Option<Duration>,
+    },
+    DeleteDeadLetter(InvocationId),
+
+    // Durable promises
+    PutPromise {
+        service_id: ServiceId,
+        key: ByteString,
+        metadata: Promise,
+    },
+    ClearAllPromises {
+        service_id: ServiceId,
+    },
+
     // Send ingress response
     IngressResponse(IngressResponse),
 }
@@ -201,6 +236,28 @@ macro_rules! info_span_if_leader {
 }
 
 impl Effect {
+    /// Renders the effect for comparing the effects of replayed commands with their recording.
+    /// The entries a suspended invocation waits for are sorted since their order depends on the
+    /// hasher of the set.
+    #[cfg(feature = "replay")]
+    fn fingerprint(&self) -> String {
+        match self {
+            Effect::SuspendService {
+                invocation_id,
+                metadata,
+                waiting_for_completed_entries,
+            } => {
+                let waiting_for_completed_entries: std::collections::BTreeSet<_> =
+                    waiting_for_completed_entries.iter().collect();
+                format!(
+                    "SuspendService {{ invocation_id: {invocation_id:?}, metadata: {metadata:?}, \
+                     waiting_for_completed_entries: {waiting_for_completed_entries:?} }}"
+                )
+            }
+            effect => format!("{effect:?}"),
+        }
+    }
+
     fn log(&self, is_leader: bool) {
         match self {
             Effect::InvokeService(ServiceInvocation { .. }) => {
@@ -247,6 +304,13 @@ impl Effect {
                     "Effect: Store inboxed invocation"
                 )
             }
+            Effect::DispatchInboxedInvocation(id) => {
+                debug_if_leader!(
+                    is_leader,
+                    restate.invocation.id = %id,
+                    "Effect: Dispatch inboxed invocation"
+                )
+            }
             Effect::FreeInvocation(id) => {
                 debug_if_leader!(
                     is_leader,
@@ -254,9 +318,14 @@ impl Effect {
                     "Effect: Free invocation"
                 )
             }
-            Effect::EnqueueIntoInbox { seq_number, .. } => debug_if_leader!(
+            Effect::EnqueueIntoInbox {
+                seq_number,
+                priority,
+                ..
+            } => debug_if_leader!(
                 is_leader,
                 restate.inbox.seq = seq_number,
+                restate.inbox.priority = ?priority,
                 "Effect: Enqueue invocation in inbox"
             ),
             Effect::EnqueueIntoOutbox {
@@ -333,6 +402,7 @@ impl Effect {
             Effect::DeleteInboxEntry {
                 service_id,
                 sequence_number,
+                ..
             } => {
                 debug_if_leader!(
                     is_leader,
@@ -341,6 +411,14 @@ impl Effect {
                     "Effect: Delete inbox entry",
                 );
             }
+            Effect::LockService(service_id, invocation_id) => {
+                debug_if_leader!(
+                    is_leader,
+                    rpc.service = %service_id.service_name,
+                    restate.invocation.id = %invocation_id,
+                    "Effect: Lock service id",
+                );
+            }
             Effect::UnlockService(service_id) => {
                 debug_if_leader!(
                     is_leader,
@@ -363,6 +441,7 @@ impl Effect {
                 invocation_id,
                 span_context,
                 key,
+                expiration_time,
                 ..
             } => {
                 info_span_if_leader!(
@@ -379,6 +458,7 @@ impl Effect {
                 debug_if_leader!(
                     is_leader,
                     restate.state.key = ?key,
+                    restate.state.expiration_time = ?expiration_time,
                     "Effect: Set state"
                 )
             }
@@ -468,6 +548,39 @@ impl Effect {
                         "Effect: Register cleanup invocation status timer"
                     )
                 }
+                Timer::ExpireState { state_key, .. } => {
+                    debug_if_leader!(
+                        is_leader,
+                        restate.state.key = ?state_key,
+                        restate.timer.wake_up_time = %timer_value.wake_up_time(),
+                        restate.timer.key = %TimerKeyDisplay(timer_value.key()),
+                        "Effect: Register state expiration timer"
+                    )
+                }
+                Timer::InvocationDeadline(_) => {
+                    debug_if_leader!(
+                        is_leader,
+                        restate.timer.wake_up_time = %timer_value.wake_up_time(),
+                        restate.timer.key = %TimerKeyDisplay(timer_value.key()),
+                        "Effect: Register invocation deadline timer"
+                    )
+                }
+                Timer::CleanDeadLetter(_) => {
+                    debug_if_leader!(
+                        is_leader,
+                        restate.timer.wake_up_time = %timer_value.wake_up_time(),
+                        restate.timer.key = %TimerKeyDisplay(timer_value.key()),
+                        "Effect: Register cleanup dead letter timer"
+                    )
+                }
+                Timer::ResumeInvocation(_) => {
+                    debug_if_leader!(
+                        is_leader,
+                        restate.timer.wake_up_time = %timer_value.wake_up_time(),
+                        restate.timer.key = %TimerKeyDisplay(timer_value.key()),
+                        "Effect: Register resume invocation timer"
+                    )
+                }
             },
             Effect::DeleteTimer(timer_key) => {
                 let timer_key_display = TimerKeyDisplay(timer_key);
@@ -601,6 +714,26 @@ impl Effect {
                     &state_mutation.service_id
                 );
             }
+            Effect::PurgeState(service_id) => {
+                debug_if_leader!(
+                    is_leader,
+                    "Effect: Purge state for service id '{:?}'",
+                    service_id
+                );
+            }
+            Effect::AddPausedService(service_name) => {
+                debug_if_leader!(is_leader, "Effect: Add paused service '{}'", service_name);
+            }
+            Effect::RemovePausedService(service_name) => {
+                debug_if_leader!(
+                    is_leader,
+                    "Effect: Remove paused service '{}'",
+                    service_name
+                );
+            }
+            Effect::UpgradeStateMachine(version) => {
+                debug_if_leader!(is_leader, "Effect: Upgrade state machine to {}", version);
+            }
             Effect::StoreCompletedInvocation { invocation_id, .. } => {
                 debug_if_leader!(
                     is_leader,
@@ -642,6 +775,45 @@ impl Effect {
                     idempotency_id
                 );
             }
+            Effect::StoreDeadLetter {
+                invocation_id,
+                dead_letter,
+                ..
+            } => {
+                debug_if_leader!(
+                    is_leader,
+                    restate.invocation.id = %invocation_id,
+                    restate.invocation.target = %dead_letter.service_invocation.invocation_target,
+                    "Effect: Store dead letter"
+                );
+            }
+            Effect::DeleteDeadLetter(invocation_id) => {
+                debug_if_leader!(
+                    is_leader,
+                    restate.invocation.id = %invocation_id,
+                    "Effect: Delete dead letter"
+                );
+            }
+            Effect::PutPromise {
+                service_id,
+                key,
+                metadata,
+            } => {
+                debug_if_leader!(
+                    is_leader,
+                    rpc.service = %service_id.service_name,
+                    "Effect: Put promise {} in state {:?}",
+                    key,
+                    metadata.state
+                );
+            }
+            Effect::ClearAllPromises { service_id } => {
+                debug_if_leader!(
+                    is_leader,
+                    rpc.service = %service_id.service_name,
+                    "Effect: Clear all promises"
+                );
+            }
         }
     }
 }
@@ -753,17 +925,33 @@ impl Effects {
         })
     }
 
+    pub(crate) fn dispatch_inboxed_invocation(&mut self, invocation_id: InvocationId) {
+        self.effects
+            .push(Effect::DispatchInboxedInvocation(invocation_id))
+    }
+
     pub(crate) fn free_invocation(&mut self, invocation_id: InvocationId) {
         self.effects.push(Effect::FreeInvocation(invocation_id))
     }
 
+    pub(crate) fn lock_service_id(&mut self, service_id: ServiceId, invocation_id: InvocationId) {
+        self.effects
+            .push(Effect::LockService(service_id, invocation_id))
+    }
+
     pub(crate) fn unlock_service_id(&mut self, service_id: ServiceId) {
         self.effects.push(Effect::UnlockService(service_id))
     }
 
-    pub(crate) fn enqueue_into_inbox(&mut self, seq_number: MessageIndex, inbox_entry: InboxEntry) {
+    pub(crate) fn enqueue_into_inbox(
+        &mut self,
+        seq_number: MessageIndex,
+        priority: InboxPriority,
+        inbox_entry: InboxEntry,
+    ) {
         self.effects.push(Effect::EnqueueIntoInbox {
             seq_number,
+            priority,
             inbox_entry,
         })
     }
@@ -771,10 +959,12 @@ impl Effects {
     pub(crate) fn delete_inbox_entry(
         &mut self,
         service_id: ServiceId,
+        priority: InboxPriority,
         sequence_number: MessageIndex,
     ) {
         self.effects.push(Effect::DeleteInboxEntry {
             service_id,
+            priority,
             sequence_number,
         });
     }
@@ -801,6 +991,7 @@ impl Effects {
         span_context: ServiceInvocationSpanContext,
         key: Bytes,
         value: Bytes,
+        expiration_time: Option<MillisSinceEpoch>,
     ) {
         self.effects.push(Effect::SetState {
             service_id,
@@ -808,6 +999,7 @@ impl Effects {
             span_context,
             key,
             value,
+            expiration_time,
         })
     }
 
@@ -974,6 +1166,40 @@ impl Effects {
             .push(Effect::DeleteIdempotencyId(idempotency_id));
     }
 
+    pub(crate) fn store_dead_letter(
+        &mut self,
+        invocation_id: InvocationId,
+        dead_letter: DeadLetter,
+        retention: Option<Duration>,
+    ) {
+        self.effects.push(Effect::StoreDeadLetter {
+            invocation_id,
+            dead_letter: Box::new(dead_letter),
+            retention,
+        });
+    }
+
+    pub(crate) fn delete_dead_letter(&mut self, invocation_id: InvocationId) {
+        self.effects.push(Effect::DeleteDeadLetter(invocation_id));
+    }
+
+    pub(crate) fn put_promise(
+        &mut self,
+        service_id: ServiceId,
+        key: ByteString,
+        metadata: Promise,
+    ) {
+        self.effects.push(Effect::PutPromise {
+            service_id,
+            key,
+            metadata,
+        });
+    }
+
+    pub(crate) fn clear_all_promises(&mut self, service_id: ServiceId) {
+        self.effects.push(Effect::ClearAllPromises { service_id });
+    }
+
     pub(crate) fn send_stored_ack_to_invoker(
         &mut self,
         invocation_id: InvocationId,
@@ -989,6 +1215,42 @@ impl Effects {
         self.effects.push(Effect::MutateState(state_mutation));
     }
 
+    pub(crate) fn purge_state(&mut self, service_id: ServiceId) {
+        self.effects.push(Effect::PurgeState(service_id));
+    }
+
+    pub(crate) fn add_paused_service(&mut self, service_name: ByteString) {
+        self.effects.push(Effect::AddPausedService(service_name));
+    }
+
+    pub(crate) fn remove_paused_service(&mut self, service_name: ByteString) {
+        self.effects.push(Effect::RemovePausedService(service_name));
+    }
+
+    pub(crate) fn upgrade_state_machine(&mut self, version: StateMachineVersion) {
+        self.effects.push(Effect::UpgradeStateMachine(version));
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    /// Deterministic rendering of the effects, see [`Effect::fingerprint`].
+    #[cfg(feature = "replay")]
+    pub(crate) fn fingerprint(&self) -> Vec<String> {
+        self.effects.iter().map(Effect::fingerprint).collect()
+    }
+
+    /// Sizes of the serialized journal entries appended by these effects.
+    pub(crate) fn journal_entry_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.effects.iter().filter_map(|effect| match effect {
+            Effect::AppendJournalEntry { journal_entry, .. } => {
+                Some(journal_entry.serialized_entry().len())
+            }
+            _ => None,
+        })
+    }
+
     /// We log only if the log level is TRACE, or if the log level is DEBUG and we're the leader,
     /// or if the span level is INFO and we're the leader.
     pub(crate) fn log(&self, is_leader: bool) {
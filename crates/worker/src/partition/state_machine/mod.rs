@@ -8,29 +8,48 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::metric_definitions::PARTITION_APPLY_COMMAND;
+use crate::metric_definitions::{
+    PARTITION_APPLY_COMMAND, PARTITION_APPLY_COMMAND_DURATION, PARTITION_APPLY_COMMAND_EFFECTS,
+    PARTITION_JOURNAL_ENTRY_SIZE,
+};
 use crate::partition::storage::Transaction;
+use bytestring::ByteString;
 use command_interpreter::CommandInterpreter;
-use metrics::counter;
+use metrics::{counter, histogram};
+use restate_types::config::InboxOverflowPolicy;
 use restate_types::message::MessageIndex;
+use restate_types::StateMachineVersion;
+use std::collections::HashSet;
 use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
 
 mod actions;
 mod command_interpreter;
 mod effect_interpreter;
 mod effects;
+#[cfg(feature = "replay")]
+mod replay;
 
 pub use actions::Action;
 pub use command_interpreter::StateReader;
 pub use effect_interpreter::ActionCollector;
 pub use effect_interpreter::StateStorage;
 pub use effects::Effects;
+#[cfg(feature = "replay")]
+pub use replay::{
+    recording_path, replay_recording, CommandRecorder, RecordedCommand, RecordingReader,
+    ReplayError,
+};
 use restate_types::identifiers::PartitionKey;
 use restate_types::journal::raw::{RawEntryCodec, RawEntryCodecError};
 use restate_wal_protocol::Command;
 
 #[derive(Debug)]
-pub struct StateMachine<Codec>(CommandInterpreter<Codec>);
+pub struct StateMachine<Codec> {
+    interpreter: CommandInterpreter<Codec>,
+    #[cfg(feature = "replay")]
+    recorder: Option<CommandRecorder>,
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -38,6 +57,11 @@ pub enum Error {
     Codec(#[from] RawEntryCodecError),
     #[error(transparent)]
     Storage(#[from] restate_storage_api::StorageError),
+    #[error(
+        "partition has been upgraded to state machine version {0}, but this node only supports up to {latest}",
+        latest = StateMachineVersion::LATEST
+    )]
+    UnsupportedStateMachineVersion(StateMachineVersion),
 }
 
 impl<Codec> StateMachine<Codec> {
@@ -46,11 +70,57 @@ impl<Codec> StateMachine<Codec> {
         outbox_seq_number: MessageIndex,
         partition_key_range: RangeInclusive<PartitionKey>,
     ) -> Self {
-        Self(CommandInterpreter::new(
-            inbox_seq_number,
-            outbox_seq_number,
-            partition_key_range,
-        ))
+        Self {
+            interpreter: CommandInterpreter::new(
+                inbox_seq_number,
+                outbox_seq_number,
+                partition_key_range,
+            ),
+            #[cfg(feature = "replay")]
+            recorder: None,
+        }
+    }
+
+    pub fn with_paused_services(mut self, paused_services: HashSet<ByteString>) -> Self {
+        self.interpreter = self.interpreter.with_paused_services(paused_services);
+        self
+    }
+
+    pub fn with_inbox_limit(
+        mut self,
+        max_inbox_length: usize,
+        overflow_policy: InboxOverflowPolicy,
+    ) -> Self {
+        self.interpreter = self
+            .interpreter
+            .with_inbox_limit(max_inbox_length, overflow_policy);
+        self
+    }
+
+    pub fn with_dead_letter_retention(mut self, retention: Duration) -> Self {
+        self.interpreter = self.interpreter.with_dead_letter_retention(retention);
+        self
+    }
+
+    pub fn with_state_machine_version(
+        mut self,
+        state_machine_version: StateMachineVersion,
+    ) -> Self {
+        self.interpreter = self
+            .interpreter
+            .with_state_machine_version(state_machine_version);
+        self
+    }
+
+    /// Version of the state machine behavior the partition has been upgraded to.
+    pub fn state_machine_version(&self) -> StateMachineVersion {
+        self.interpreter.state_machine_version()
+    }
+
+    /// Records every command applied from now on together with its effects.
+    #[cfg(feature = "replay")]
+    pub fn set_recorder(&mut self, recorder: CommandRecorder) {
+        self.recorder = Some(recorder);
     }
 }
 
@@ -65,19 +135,55 @@ impl<Codec: RawEntryCodec> StateMachine<Codec> {
     ) -> Result<(), Error> {
         // Handle the command, returns the span_relation to use to log effects
         let command_type = command.name();
-        self.0.on_apply(command, effects, transaction).await?;
+        let command_start = Instant::now();
+        #[cfg(feature = "replay")]
+        let recorded_command = self.recorder.is_some().then(|| command.clone());
+        self.interpreter
+            .on_apply(command, effects, transaction)
+            .await?;
         counter!(PARTITION_APPLY_COMMAND, "command" => command_type).increment(1);
+        histogram!(PARTITION_APPLY_COMMAND_EFFECTS, "command" => command_type)
+            .record(effects.len() as f64);
+        for journal_entry_size in effects.journal_entry_sizes() {
+            histogram!(PARTITION_JOURNAL_ENTRY_SIZE, "command" => command_type)
+                .record(journal_entry_size as f64);
+        }
+
+        #[cfg(feature = "replay")]
+        if let Some(command) = recorded_command {
+            self.record(command, effects);
+        }
 
         // Log the effects
         effects.log(is_leader);
 
         // Interpret effects
-        effect_interpreter::EffectInterpreter::<Codec>::interpret_effects(
+        let result = effect_interpreter::EffectInterpreter::<Codec>::interpret_effects(
             effects,
             transaction,
             action_collector,
         )
-        .await
+        .await;
+        histogram!(PARTITION_APPLY_COMMAND_DURATION, "command" => command_type)
+            .record(command_start.elapsed());
+
+        result
+    }
+
+    #[cfg(feature = "replay")]
+    fn record(&mut self, command: Command, effects: &Effects) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        if let Err(err) = recorder.record(command, effects) {
+            // a partial recording can't be replayed, stop recording altogether
+            tracing::warn!(
+                path = %recorder.path().display(),
+                "Failed recording applied command, stop recording: {}",
+                err
+            );
+            self.recorder = None;
+        }
     }
 }
 
@@ -86,7 +192,7 @@ mod tests {
     use super::*;
 
     use crate::partition::types::{InvokerEffect, InvokerEffectKind};
-    use assert2::assert;
+    use assert2::{assert, let_assert};
     use bytes::Bytes;
     use bytestring::ByteString;
     use futures::{StreamExt, TryStreamExt};
@@ -97,6 +203,9 @@ mod tests {
     use restate_partition_store::{OpenMode, PartitionStore, PartitionStoreManager};
     use restate_rocksdb::RocksDbManager;
     use restate_service_protocol::codec::ProtobufRawEntryCodec;
+    use restate_storage_api::inbox_table::{
+        InboxEntry, InboxPriority, ReadOnlyInboxTable, SequenceNumberInboxEntry,
+    };
     use restate_storage_api::invocation_status_table::{
         InFlightInvocationMetadata, InvocationStatus, InvocationStatusTable,
         ReadOnlyInvocationStatusTable,
@@ -112,7 +221,9 @@ mod tests {
     use restate_types::arc_util::Constant;
     use restate_types::config::{CommonOptions, WorkerOptions};
     use restate_types::errors::KILLED_INVOCATION_ERROR;
-    use restate_types::identifiers::{InvocationId, PartitionId, PartitionKey, ServiceId};
+    use restate_types::identifiers::{
+        DeploymentId, InvocationId, PartitionId, PartitionKey, ServiceId,
+    };
     use restate_types::ingress::IngressResponse;
     use restate_types::invocation::{
         InvocationResponse, InvocationTarget, InvocationTermination, ResponseResult,
@@ -122,7 +233,9 @@ mod tests {
     use restate_types::journal::{Completion, CompletionResult, EntryResult};
     use restate_types::journal::{Entry, EntryType};
     use restate_types::state_mut::ExternalStateMutation;
+    use restate_types::time::MillisSinceEpoch;
     use restate_types::GenerationalNodeId;
+    use restate_wal_protocol::timer::TimerKeyValue;
     use std::collections::{HashMap, HashSet};
     use test_log::test;
     use tracing::info;
@@ -134,6 +247,8 @@ mod tests {
         //  Perhaps we could make these tests faster by having those.
         rocksdb_storage: PartitionStore,
         effects_buffer: Effects,
+        #[cfg(feature = "replay")]
+        manager: PartitionStoreManager,
     }
 
     impl MockStateMachine {
@@ -175,9 +290,32 @@ mod tests {
                 ),
                 rocksdb_storage,
                 effects_buffer: Default::default(),
+                #[cfg(feature = "replay")]
+                manager,
+            }
+        }
+
+        pub fn with_dead_letter_retention(self, retention: Duration) -> Self {
+            Self {
+                state_machine: self.state_machine.with_dead_letter_retention(retention),
+                ..self
             }
         }
 
+        /// Opens another, empty partition store.
+        #[cfg(feature = "replay")]
+        pub async fn open_partition_store(&self, partition_id: PartitionId) -> PartitionStore {
+            self.manager
+                .open_partition_store(
+                    partition_id,
+                    RangeInclusive::new(PartitionKey::MIN, PartitionKey::MAX),
+                    OpenMode::CreateIfMissing,
+                    &WorkerOptions::default().storage.rocksdb,
+                )
+                .await
+                .unwrap()
+        }
+
         pub async fn apply(&mut self, command: Command) -> Vec<Action> {
             let partition_id = self.partition_id();
             let mut transaction = crate::partition::storage::Transaction::new(
@@ -241,6 +379,71 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "replay")]
+    #[test(tokio::test)]
+    async fn replay_recorded_commands() -> TestResult {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        let mut state_machine = tc
+            .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+            .await;
+        let recording_dir = tempfile::tempdir()?;
+        let recording = recording_dir.path().join("partition.rec");
+        state_machine
+            .state_machine
+            .set_recorder(CommandRecorder::create(&recording)?);
+
+        // the second invocation of the virtual object is enqueued into the inbox
+        let invocation_target = InvocationTarget::mock_virtual_object();
+        mock_start_invocation_with_invocation_target(&mut state_machine, invocation_target.clone())
+            .await;
+        state_machine
+            .apply(Command::Invoke(ServiceInvocation::initialize(
+                InvocationId::generate(&invocation_target),
+                invocation_target,
+                Source::Ingress,
+            )))
+            .await;
+
+        let num_replayed = replay_recording::<ProtobufRawEntryCodec>(
+            &recording,
+            PartitionId::from(1),
+            PartitionKey::MIN..=PartitionKey::MAX,
+            state_machine
+                .open_partition_store(PartitionId::from(1))
+                .await,
+        )
+        .await?;
+        assert_eq!(num_replayed, 2);
+
+        // replaying a recording with different effects fails at the diverging command
+        let mut recorded_commands =
+            RecordingReader::open(&recording)?.collect::<Result<Vec<_>, _>>()?;
+        recorded_commands[1].effects.pop();
+        let mut partition_storage = crate::partition::storage::PartitionStorage::new(
+            PartitionId::from(2),
+            PartitionKey::MIN..=PartitionKey::MAX,
+            state_machine
+                .open_partition_store(PartitionId::from(2))
+                .await,
+        );
+        let result = replay::replay(
+            &mut StateMachine::<ProtobufRawEntryCodec>::new(
+                0,
+                0,
+                PartitionKey::MIN..=PartitionKey::MAX,
+            ),
+            &mut partition_storage,
+            recorded_commands.into_iter().map(Ok),
+        )
+        .await;
+        assert!(let Err(ReplayError::Divergence { index: 1, .. }) = result);
+
+        Ok(())
+    }
+
     #[test(tokio::test)]
     async fn shared_invocation_skips_inbox() -> TestResult {
         let tc = TaskCenterBuilder::default()
@@ -382,6 +585,65 @@ mod tests {
         Ok(())
     }
 
+    #[test(tokio::test)]
+    async fn interactive_invocations_are_dequeued_first() -> anyhow::Result<()> {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        let mut state_machine = tc
+            .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+            .await;
+
+        let (invocation_id, invocation_target) =
+            InvocationId::mock_with(InvocationTarget::mock_virtual_object());
+        let (background_id, background_target) = InvocationId::mock_with(invocation_target.clone());
+        let (interactive_id, interactive_target) =
+            InvocationId::mock_with(invocation_target.clone());
+
+        let _ = state_machine
+            .apply(Command::Invoke(ServiceInvocation {
+                invocation_id,
+                invocation_target: invocation_target.clone(),
+                ..ServiceInvocation::mock()
+            }))
+            .await;
+        let _ = state_machine
+            .apply(Command::Invoke(ServiceInvocation {
+                invocation_id: background_id,
+                invocation_target: background_target,
+                ..ServiceInvocation::mock()
+            }))
+            .await;
+        let _ = state_machine
+            .apply(Command::Invoke(ServiceInvocation {
+                invocation_id: interactive_id,
+                invocation_target: interactive_target,
+                response_sink: Some(ServiceInvocationResponseSink::PartitionProcessor {
+                    caller: InvocationId::mock_random(),
+                    entry_index: 0,
+                }),
+                ..ServiceInvocation::mock()
+            }))
+            .await;
+
+        let next_inbox_entry = state_machine
+            .storage()
+            .transaction()
+            .peek_inbox(&invocation_target.as_keyed_service_id().unwrap())
+            .await?;
+
+        assert_that!(
+            next_inbox_entry,
+            some(pat!(SequenceNumberInboxEntry {
+                priority: eq(InboxPriority::Interactive),
+                inbox_entry: pat!(InboxEntry::Invocation(anything(), eq(interactive_id)))
+            }))
+        );
+
+        Ok(())
+    }
+
     #[test(tokio::test)]
     async fn kill_inboxed_invocation() -> anyhow::Result<()> {
         let tc = TaskCenterBuilder::default()
@@ -639,6 +901,172 @@ mod tests {
         Ok(())
     }
 
+    #[test(tokio::test)]
+    async fn expire_state() -> TestResult {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        let mut state_machine = tc
+            .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+            .await;
+        let service_id = ServiceId::mock_random();
+        let invocation_id =
+            mock_start_invocation_with_service_id(&mut state_machine, service_id.clone()).await;
+        let state_key = Bytes::from_static(b"key");
+        let expiration_time = MillisSinceEpoch::new(1000);
+
+        let set_state = |entry_index| {
+            Command::InvokerEffect(InvokerEffect {
+                invocation_id,
+                kind: InvokerEffectKind::JournalEntry {
+                    entry_index,
+                    entry: ProtobufRawEntryCodec::serialize_enriched(Entry::set_state(
+                        "key", "value",
+                    )),
+                },
+            })
+        };
+        let expire_state = |entry_index| {
+            Command::Timer(TimerKeyValue::expire_state(
+                expiration_time,
+                invocation_id,
+                entry_index,
+                service_id.clone(),
+                Bytes::from_static(b"key"),
+            ))
+        };
+
+        // Firing the timer removes the state
+        state_machine.apply(set_state(1)).await;
+        let mut tx = state_machine.rocksdb_storage.transaction();
+        tx.put_user_state_expiration(&service_id, &state_key, expiration_time)
+            .await;
+        tx.commit().await.unwrap();
+        state_machine.apply(expire_state(1)).await;
+        assert_that!(
+            state_machine
+                .storage()
+                .get_user_state(&service_id, &state_key)
+                .await?,
+            none()
+        );
+
+        // Setting the state again drops its expiration and keeps it
+        state_machine.apply(set_state(2)).await;
+        let mut tx = state_machine.rocksdb_storage.transaction();
+        tx.put_user_state_expiration(&service_id, &state_key, expiration_time)
+            .await;
+        tx.commit().await.unwrap();
+        state_machine
+            .apply_multiple([set_state(3), expire_state(2)])
+            .await;
+        assert_that!(
+            state_machine
+                .storage()
+                .get_user_state_expiration(&service_id, &state_key)
+                .await?,
+            none()
+        );
+        assert_that!(
+            state_machine
+                .storage()
+                .get_user_state(&service_id, &state_key)
+                .await?,
+            some(eq(Bytes::from_static(b"value")))
+        );
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn lazy_state_reads_return_state_until_its_timer_fires() -> TestResult {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        let mut state_machine = tc
+            .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+            .await;
+        let service_id = ServiceId::mock_random();
+        let invocation_id =
+            mock_start_invocation_with_service_id(&mut state_machine, service_id.clone()).await;
+        let journal_entry = |entry_index, entry| {
+            Command::InvokerEffect(InvokerEffect {
+                invocation_id,
+                kind: InvokerEffectKind::JournalEntry {
+                    entry_index,
+                    entry: ProtobufRawEntryCodec::serialize_enriched(entry),
+                },
+            })
+        };
+
+        state_machine
+            .apply_multiple([
+                journal_entry(1, Entry::set_state("expired", "value")),
+                journal_entry(2, Entry::set_state("kept", "value")),
+            ])
+            .await;
+        let mut tx = state_machine.rocksdb_storage.transaction();
+        tx.put_user_state_expiration(&service_id, "expired", MillisSinceEpoch::new(1000))
+            .await;
+        tx.commit().await.unwrap();
+
+        // Only the expiration timer removes the state, no matter the wall clock
+        let actions = state_machine
+            .apply(journal_entry(3, Entry::get_state("expired", None)))
+            .await;
+        assert_that!(
+            actions,
+            contains(pat!(Action::ForwardCompletion {
+                invocation_id: eq(invocation_id),
+                completion: eq(Completion::new(
+                    3,
+                    CompletionResult::Success(Bytes::from_static(b"value"))
+                ))
+            }))
+        );
+
+        state_machine
+            .apply(Command::Timer(TimerKeyValue::expire_state(
+                MillisSinceEpoch::new(1000),
+                invocation_id,
+                1,
+                service_id.clone(),
+                Bytes::from_static(b"expired"),
+            )))
+            .await;
+
+        let actions = state_machine
+            .apply(journal_entry(4, Entry::get_state("expired", None)))
+            .await;
+        assert_that!(
+            actions,
+            contains(pat!(Action::ForwardCompletion {
+                invocation_id: eq(invocation_id),
+                completion: eq(Completion::new(4, CompletionResult::Empty))
+            }))
+        );
+
+        let actions = state_machine
+            .apply(journal_entry(5, Entry::get_state_keys(None)))
+            .await;
+        assert_that!(
+            actions,
+            contains(pat!(Action::ForwardCompletion {
+                invocation_id: eq(invocation_id),
+                completion: eq(Completion::new(
+                    5,
+                    ProtobufRawEntryCodec::serialize_get_state_keys_completion(vec![
+                        Bytes::from_static(b"kept")
+                    ])
+                ))
+            }))
+        );
+
+        Ok(())
+    }
+
     #[test(tokio::test)]
     async fn send_ingress_response_to_multiple_targets() -> TestResult {
         let tc = TaskCenterBuilder::default()
@@ -663,6 +1091,7 @@ mod tests {
                 span_context: Default::default(),
                 headers: vec![],
                 execution_time: None,
+                execution_deadline: None,
                 completion_retention_time: None,
                 idempotency_key: None,
             }))
@@ -738,6 +1167,7 @@ mod tests {
         use restate_types::errors::GONE_INVOCATION_ERROR;
         use restate_types::identifiers::IdempotencyId;
         use restate_types::invocation::InvocationTarget;
+        use restate_types::time::MillisSinceEpoch;
         use restate_wal_protocol::timer::TimerKeyValue;
         use test_log::test;
 
@@ -846,7 +1276,7 @@ mod tests {
         }
 
         #[test(tokio::test)]
-        async fn complete_already_completed_invocation() {
+        async fn release_idempotency_key_without_retention() {
             let tc = TaskCenterBuilder::default()
                 .default_runtime_handle(tokio::runtime::Handle::current())
                 .build()
@@ -864,36 +1294,160 @@ mod tests {
             let idempotency_id =
                 IdempotencyId::combine(invocation_id, &invocation_target, idempotency_key.clone());
 
-            let response_bytes = Bytes::from_static(b"123");
-            let ingress_id = GenerationalNodeId::new(1, 1);
-
-            // Prepare idempotency metadata and completed status
-            let mut txn = state_machine.storage().transaction();
-            txn.put_idempotency_metadata(&idempotency_id, IdempotencyMetadata { invocation_id })
-                .await;
-            txn.put_invocation_status(
-                &invocation_id,
-                InvocationStatus::Completed(CompletedInvocation {
-                    invocation_target: invocation_target.clone(),
-                    source: Source::Ingress,
-                    idempotency_key: Some(idempotency_key.clone()),
-                    timestamps: StatusTimestamps::now(),
-                    response_result: ResponseResult::Success(response_bytes.clone()),
-                }),
-            )
-            .await;
-            txn.commit().await.unwrap();
-
-            // Send a request, should be completed immediately with result
-            let second_invocation_id = InvocationId::generate_with_idempotency_key(
-                &invocation_target,
-                Some(idempotency_key.clone()),
-            );
-            let actions = state_machine
+            // Send fresh invocation with idempotency key, but without retention
+            let _ = state_machine
                 .apply(Command::Invoke(ServiceInvocation {
-                    invocation_id: second_invocation_id,
+                    invocation_id,
                     invocation_target: invocation_target.clone(),
-                    response_sink: Some(ServiceInvocationResponseSink::Ingress(ingress_id)),
+                    response_sink: Some(ServiceInvocationResponseSink::Ingress(
+                        GenerationalNodeId::new(1, 1),
+                    )),
+                    idempotency_key: Some(idempotency_key),
+                    completion_retention_time: None,
+                    ..ServiceInvocation::mock()
+                }))
+                .await;
+
+            // Send output, then end
+            let _ = state_machine
+                .apply_multiple([
+                    Command::InvokerEffect(InvokerEffect {
+                        invocation_id,
+                        kind: InvokerEffectKind::JournalEntry {
+                            entry_index: 1,
+                            entry: ProtobufRawEntryCodec::serialize_enriched(Entry::output(
+                                EntryResult::Success(Bytes::from_static(b"123")),
+                            )),
+                        },
+                    }),
+                    Command::InvokerEffect(InvokerEffect {
+                        invocation_id,
+                        kind: InvokerEffectKind::End,
+                    }),
+                ])
+                .await;
+
+            // Neither the invocation status nor the idempotency key are retained
+            let mut txn = state_machine.storage().transaction();
+            assert_that!(
+                txn.get_invocation_status(&invocation_id).await.unwrap(),
+                pat!(InvocationStatus::Free)
+            );
+            assert_that!(
+                txn.get_idempotency_metadata(&idempotency_id).await.unwrap(),
+                none()
+            );
+            txn.commit().await.unwrap();
+        }
+
+        #[test(tokio::test)]
+        async fn start_scheduled_idempotent_invocation() {
+            let tc = TaskCenterBuilder::default()
+                .default_runtime_handle(tokio::runtime::Handle::current())
+                .build()
+                .expect("task_center builds");
+            let mut state_machine = tc
+                .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+                .await;
+
+            let idempotency_key = ByteString::from_static("my-idempotency-key");
+            let retention = Duration::from_secs(60) * 60 * 24;
+            let execution_time = MillisSinceEpoch::new(1337);
+            let invocation_target = InvocationTarget::mock_virtual_object();
+            let invocation_id = InvocationId::generate_with_idempotency_key(
+                &invocation_target,
+                Some(idempotency_key.clone()),
+            );
+            let service_invocation = ServiceInvocation {
+                invocation_id,
+                invocation_target,
+                response_sink: Some(ServiceInvocationResponseSink::Ingress(
+                    GenerationalNodeId::new(1, 1),
+                )),
+                idempotency_key: Some(idempotency_key),
+                completion_retention_time: Some(retention),
+                execution_time: Some(execution_time),
+                ..ServiceInvocation::mock()
+            };
+
+            // The invocation is scheduled, not invoked
+            let actions = state_machine
+                .apply(Command::Invoke(service_invocation.clone()))
+                .await;
+            assert_that!(
+                actions,
+                not(contains(pat!(Action::Invoke {
+                    invocation_id: eq(invocation_id)
+                })))
+            );
+
+            // Once the timer fires, the invocation starts
+            let actions = state_machine
+                .apply(Command::Timer(TimerKeyValue::invoke(
+                    execution_time,
+                    service_invocation,
+                )))
+                .await;
+            assert_that!(
+                actions,
+                all!(
+                    contains(pat!(Action::Invoke {
+                        invocation_id: eq(invocation_id)
+                    })),
+                    not(contains(pat!(Action::IngressResponse(_))))
+                )
+            );
+        }
+
+        #[test(tokio::test)]
+        async fn complete_already_completed_invocation() {
+            let tc = TaskCenterBuilder::default()
+                .default_runtime_handle(tokio::runtime::Handle::current())
+                .build()
+                .expect("task_center builds");
+            let mut state_machine = tc
+                .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+                .await;
+
+            let idempotency_key = ByteString::from_static("my-idempotency-key");
+            let invocation_target = InvocationTarget::mock_virtual_object();
+            let invocation_id = InvocationId::generate_with_idempotency_key(
+                &invocation_target,
+                Some(idempotency_key.clone()),
+            );
+            let idempotency_id =
+                IdempotencyId::combine(invocation_id, &invocation_target, idempotency_key.clone());
+
+            let response_bytes = Bytes::from_static(b"123");
+            let ingress_id = GenerationalNodeId::new(1, 1);
+
+            // Prepare idempotency metadata and completed status
+            let mut txn = state_machine.storage().transaction();
+            txn.put_idempotency_metadata(&idempotency_id, IdempotencyMetadata { invocation_id })
+                .await;
+            txn.put_invocation_status(
+                &invocation_id,
+                InvocationStatus::Completed(CompletedInvocation {
+                    invocation_target: invocation_target.clone(),
+                    source: Source::Ingress,
+                    idempotency_key: Some(idempotency_key.clone()),
+                    timestamps: StatusTimestamps::now(),
+                    response_result: ResponseResult::Success(response_bytes.clone()),
+                }),
+            )
+            .await;
+            txn.commit().await.unwrap();
+
+            // Send a request, should be completed immediately with result
+            let second_invocation_id = InvocationId::generate_with_idempotency_key(
+                &invocation_target,
+                Some(idempotency_key.clone()),
+            );
+            let actions = state_machine
+                .apply(Command::Invoke(ServiceInvocation {
+                    invocation_id: second_invocation_id,
+                    invocation_target: invocation_target.clone(),
+                    response_sink: Some(ServiceInvocationResponseSink::Ingress(ingress_id)),
                     idempotency_key: Some(idempotency_key),
                     ..ServiceInvocation::mock()
                 }))
@@ -1361,6 +1915,410 @@ mod tests {
         }
     }
 
+    mod promise {
+        use super::*;
+
+        use restate_storage_api::promise_table::{Promise, PromiseState, ReadOnlyPromiseTable};
+        use restate_types::errors::ALREADY_COMPLETED_PROMISE_ERROR;
+        use restate_types::identifiers::JournalEntryId;
+        use restate_types::invocation::{InvocationTarget, WorkflowHandlerType};
+        use test_log::test;
+
+        #[test(tokio::test)]
+        async fn complete_promise_from_shared_handler() {
+            let tc = TaskCenterBuilder::default()
+                .default_runtime_handle(tokio::runtime::Handle::current())
+                .build()
+                .expect("task_center builds");
+            let mut state_machine = tc
+                .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+                .await;
+
+            let workflow_target = InvocationTarget::mock_workflow();
+            let service_id = workflow_target.as_keyed_service_id().unwrap();
+            let shared_target = InvocationTarget::workflow(
+                service_id.service_name.clone(),
+                service_id.key.clone(),
+                "shared",
+                WorkflowHandlerType::Shared,
+            );
+            let workflow_id = InvocationId::generate(&workflow_target);
+            let shared_id = InvocationId::generate(&shared_target);
+            let promise_key = ByteString::from_static("my-promise");
+            let promise_value = Bytes::from_static(b"my-value");
+
+            let _ = state_machine
+                .apply_multiple([
+                    Command::Invoke(ServiceInvocation {
+                        invocation_id: workflow_id,
+                        invocation_target: workflow_target,
+                        ..ServiceInvocation::mock()
+                    }),
+                    Command::Invoke(ServiceInvocation {
+                        invocation_id: shared_id,
+                        invocation_target: shared_target,
+                        ..ServiceInvocation::mock()
+                    }),
+                ])
+                .await;
+
+            // The workflow waits on the promise
+            let actions = state_machine
+                .apply(Command::InvokerEffect(InvokerEffect {
+                    invocation_id: workflow_id,
+                    kind: InvokerEffectKind::JournalEntry {
+                        entry_index: 1,
+                        entry: ProtobufRawEntryCodec::serialize_enriched(Entry::get_promise(
+                            promise_key.clone(),
+                            None,
+                        )),
+                    },
+                }))
+                .await;
+            assert_that!(
+                actions,
+                not(contains(pat!(Action::ForwardCompletion {
+                    invocation_id: eq(workflow_id)
+                })))
+            );
+            assert_eq!(
+                state_machine
+                    .storage()
+                    .transaction()
+                    .get_promise(&service_id, &promise_key)
+                    .await
+                    .unwrap(),
+                Some(Promise {
+                    state: PromiseState::NotCompleted(vec![JournalEntryId::from_parts(
+                        workflow_id,
+                        1
+                    )])
+                })
+            );
+
+            // The shared handler completes it
+            let actions = state_machine
+                .apply(Command::InvokerEffect(InvokerEffect {
+                    invocation_id: shared_id,
+                    kind: InvokerEffectKind::JournalEntry {
+                        entry_index: 1,
+                        entry: ProtobufRawEntryCodec::serialize_enriched(Entry::complete_promise(
+                            promise_key.clone(),
+                            EntryResult::Success(promise_value.clone()),
+                            None,
+                        )),
+                    },
+                }))
+                .await;
+            assert_that!(
+                actions,
+                all!(
+                    contains(pat!(Action::ForwardCompletion {
+                        invocation_id: eq(shared_id),
+                        completion: eq(Completion::new(1, CompletionResult::Empty))
+                    })),
+                    contains(pat!(Action::NewOutboxMessage {
+                        message: pat!(
+                            restate_storage_api::outbox_table::OutboxMessage::ServiceResponse(
+                                pat!(restate_types::invocation::InvocationResponse {
+                                    id: eq(workflow_id),
+                                    entry_index: eq(1),
+                                    result: eq(ResponseResult::Success(promise_value.clone()))
+                                })
+                            )
+                        )
+                    }))
+                )
+            );
+
+            // Completing it a second time fails
+            let actions = state_machine
+                .apply(Command::InvokerEffect(InvokerEffect {
+                    invocation_id: shared_id,
+                    kind: InvokerEffectKind::JournalEntry {
+                        entry_index: 2,
+                        entry: ProtobufRawEntryCodec::serialize_enriched(Entry::complete_promise(
+                            promise_key.clone(),
+                            EntryResult::Success(Bytes::from_static(b"other-value")),
+                            None,
+                        )),
+                    },
+                }))
+                .await;
+            assert_that!(
+                actions,
+                contains(pat!(Action::ForwardCompletion {
+                    invocation_id: eq(shared_id),
+                    completion: eq(Completion::new(
+                        2,
+                        CompletionResult::from(&ALREADY_COMPLETED_PROMISE_ERROR)
+                    ))
+                }))
+            );
+
+            // Once completed, getting the promise completes the entry right away
+            let actions = state_machine
+                .apply(Command::InvokerEffect(InvokerEffect {
+                    invocation_id: shared_id,
+                    kind: InvokerEffectKind::JournalEntry {
+                        entry_index: 3,
+                        entry: ProtobufRawEntryCodec::serialize_enriched(Entry::get_promise(
+                            promise_key,
+                            None,
+                        )),
+                    },
+                }))
+                .await;
+            assert_that!(
+                actions,
+                contains(pat!(Action::ForwardCompletion {
+                    invocation_id: eq(shared_id),
+                    completion: eq(Completion::new(3, CompletionResult::Success(promise_value)))
+                }))
+            );
+        }
+    }
+
+    mod dead_letter {
+        use super::*;
+
+        use assert2::assert;
+        use restate_storage_api::dead_letter_table::ReadOnlyDeadLetterTable;
+        use restate_types::errors::InvocationError;
+        use restate_types::identifiers::{InvocationUuid, WithPartitionKey};
+        use restate_types::invocation::{DeadLetterRedrive, Header};
+        use test_log::test;
+
+        #[test(tokio::test)]
+        async fn dead_letter_redrive_and_purge() {
+            let tc = TaskCenterBuilder::default()
+                .default_runtime_handle(tokio::runtime::Handle::current())
+                .build()
+                .expect("task_center builds");
+            let mut state_machine = tc
+                .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+                .await;
+
+            let invocation_target = InvocationTarget::mock_virtual_object();
+            let invocation_id = InvocationId::generate(&invocation_target);
+            let argument = Bytes::from_static(b"my-argument");
+            let headers = vec![Header::new("my-header", "my-value")];
+            let failure = InvocationError::new(500u16, "my-failure");
+
+            state_machine
+                .apply(Command::Invoke(ServiceInvocation {
+                    invocation_id,
+                    invocation_target: invocation_target.clone(),
+                    argument: argument.clone(),
+                    source: Source::Ingress,
+                    response_sink: None,
+                    span_context: Default::default(),
+                    headers: headers.clone(),
+                    execution_time: None,
+                    execution_deadline: None,
+                    completion_retention_time: None,
+                    idempotency_key: None,
+                }))
+                .await;
+            state_machine
+                .apply(Command::InvokerEffect(InvokerEffect {
+                    invocation_id,
+                    kind: InvokerEffectKind::Failed {
+                        error: failure.clone(),
+                        failed_at: MillisSinceEpoch::new(1706027034946),
+                    },
+                }))
+                .await;
+
+            // The failed invocation is dead lettered together with its input
+            let dead_letter = state_machine
+                .storage()
+                .get_dead_letter(&invocation_id)
+                .await
+                .unwrap()
+                .expect("dead letter must be stored");
+            assert_eq!(dead_letter.service_invocation.invocation_id, invocation_id);
+            assert_eq!(
+                dead_letter.service_invocation.invocation_target,
+                invocation_target
+            );
+            assert_eq!(dead_letter.service_invocation.argument, argument);
+            assert_eq!(dead_letter.service_invocation.headers, headers);
+            assert_eq!(dead_letter.failure, failure);
+            // the failure time is the one of the invoker effect, not of the apply
+            assert_eq!(dead_letter.failed_at, MillisSinceEpoch::new(1706027034946));
+            assert_that!(
+                state_machine
+                    .storage()
+                    .get_invocation_status(&invocation_id)
+                    .await
+                    .unwrap(),
+                pat!(InvocationStatus::Free)
+            );
+
+            // Redrive starts a new invocation with the same input
+            let new_invocation_id =
+                InvocationId::from_parts(invocation_id.partition_key(), InvocationUuid::new());
+            let actions = state_machine
+                .apply(Command::RedriveDeadLetter(DeadLetterRedrive {
+                    invocation_id,
+                    new_invocation_id,
+                }))
+                .await;
+            assert_that!(
+                actions,
+                contains(pat!(Action::Invoke {
+                    invocation_id: eq(new_invocation_id),
+                    invocation_target: eq(invocation_target.clone())
+                }))
+            );
+            assert_eq!(
+                state_machine
+                    .storage()
+                    .get_dead_letter(&invocation_id)
+                    .await
+                    .unwrap(),
+                None
+            );
+
+            // Purge drops the dead letter without running it again
+            state_machine
+                .apply(Command::InvokerEffect(InvokerEffect {
+                    invocation_id: new_invocation_id,
+                    kind: InvokerEffectKind::failed(failure),
+                }))
+                .await;
+            assert!(state_machine
+                .storage()
+                .get_dead_letter(&new_invocation_id)
+                .await
+                .unwrap()
+                .is_some());
+            let actions = state_machine
+                .apply(Command::PurgeDeadLetter(new_invocation_id))
+                .await;
+            assert!(actions.is_empty());
+            assert_eq!(
+                state_machine
+                    .storage()
+                    .get_dead_letter(&new_invocation_id)
+                    .await
+                    .unwrap(),
+                None
+            );
+        }
+
+        #[test(tokio::test)]
+        async fn dead_letter_cleaned_after_retention() {
+            let tc = TaskCenterBuilder::default()
+                .default_runtime_handle(tokio::runtime::Handle::current())
+                .build()
+                .expect("task_center builds");
+            let retention = Duration::from_secs(60 * 60);
+            let mut state_machine = tc
+                .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+                .await
+                .with_dead_letter_retention(retention);
+
+            let invocation_id = mock_start_invocation(&mut state_machine).await;
+            let actions = state_machine
+                .apply(Command::InvokerEffect(InvokerEffect {
+                    invocation_id,
+                    kind: InvokerEffectKind::failed(InvocationError::new(500u16, "my-failure")),
+                }))
+                .await;
+
+            // The leader schedules the cleanup once the dead letter is stored
+            assert_that!(
+                actions,
+                contains(pat!(Action::ScheduleDeadLetterCleanup {
+                    invocation_id: eq(invocation_id),
+                    retention: eq(retention)
+                }))
+            );
+            assert!(state_machine
+                .storage()
+                .get_dead_letter(&invocation_id)
+                .await
+                .unwrap()
+                .is_some());
+
+            let cleanup_timer =
+                TimerKeyValue::clean_dead_letter(MillisSinceEpoch::new(1000), invocation_id);
+            state_machine
+                .apply(Command::ScheduleTimer(cleanup_timer.clone()))
+                .await;
+            state_machine.apply(Command::Timer(cleanup_timer)).await;
+
+            assert_eq!(
+                state_machine
+                    .storage()
+                    .get_dead_letter(&invocation_id)
+                    .await
+                    .unwrap(),
+                None
+            );
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn saturated_deployment_resumes_invocation_with_timer() {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        let mut state_machine = tc
+            .run_in_scope("mock-state-machine", None, MockStateMachine::create())
+            .await;
+
+        let invocation_id = mock_start_invocation(&mut state_machine).await;
+        let retry_at = MillisSinceEpoch::new(1000);
+        let actions = state_machine
+            .apply(Command::InvokerEffect(InvokerEffect {
+                invocation_id,
+                kind: InvokerEffectKind::DeploymentSaturated {
+                    deployment_id: DeploymentId::new(),
+                    retry_at,
+                },
+            }))
+            .await;
+
+        let resume_timer = TimerKeyValue::resume_invocation(retry_at, invocation_id);
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            Action::RegisterTimer { timer_value } if timer_value.key() == resume_timer.key()
+        )));
+        let_assert!(
+            InvocationStatus::Suspended {
+                waiting_for_completed_entries,
+                ..
+            } = state_machine
+                .storage()
+                .transaction()
+                .get_invocation_status(&invocation_id)
+                .await
+                .unwrap()
+        );
+        assert!(waiting_for_completed_entries.is_empty());
+
+        let actions = state_machine.apply(Command::Timer(resume_timer)).await;
+        assert_that!(
+            actions,
+            contains(pat!(Action::Invoke {
+                invocation_id: eq(invocation_id)
+            }))
+        );
+        assert_that!(
+            state_machine
+                .storage()
+                .transaction()
+                .get_invocation_status(&invocation_id)
+                .await
+                .unwrap(),
+            pat!(InvocationStatus::Invoked(_))
+        );
+    }
+
     async fn mock_start_invocation_with_service_id(
         state_machine: &mut MockStateMachine,
         service_id: ServiceId,
@@ -1388,6 +2346,7 @@ mod tests {
                 span_context: Default::default(),
                 headers: vec![],
                 execution_time: None,
+                execution_deadline: None,
                 completion_retention_time: None,
                 idempotency_key: None,
             }))
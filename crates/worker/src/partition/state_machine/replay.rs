@@ -0,0 +1,240 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Recording and replaying of the commands applied by the state machine.
+//!
+//! With the `replay` feature the partition processor records every applied command together with
+//! the effects it produced. Replaying a recording against a partition store in the state the
+//! recording started from must produce the very same effects, a divergence points at
+//! non-determinism of the state machine, e.g. between the replicas of a partition.
+//!
+//! Commands are recorded before their transaction is committed, so a recording continued after a
+//! crash can contain commands which have been applied again after the restart. Effects which carry
+//! the wall clock time, e.g. of dead letters or terminated inboxed invocations, differ on replay.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use bytes::{BufMut, BytesMut};
+use restate_partition_store::PartitionStore;
+use restate_storage_api::StorageError;
+use restate_types::config::node_filepath;
+use restate_types::flexbuffers_storage_encode_decode;
+use restate_types::identifiers::{PartitionId, PartitionKey};
+use restate_types::journal::raw::RawEntryCodec;
+use restate_types::storage::{StorageCodec, StorageDecodeError};
+use restate_wal_protocol::Command;
+
+use super::effect_interpreter::EffectInterpreter;
+use super::{ActionCollector, Effects, StateMachine};
+use crate::partition::storage::PartitionStorage;
+
+/// A command applied by the state machine together with the fingerprints of its effects.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RecordedCommand {
+    pub command: Command,
+    pub effects: Vec<String>,
+}
+
+flexbuffers_storage_encode_decode!(RecordedCommand);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("failed reading recording: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed decoding recorded command: {0}")]
+    Decode(#[from] StorageDecodeError),
+    #[error(transparent)]
+    StateMachine(#[from] super::Error),
+    #[error(
+        "command #{index} '{command}' diverged from its recording\nrecorded: {recorded:#?}\nreplayed: {replayed:#?}"
+    )]
+    Divergence {
+        index: usize,
+        command: &'static str,
+        recorded: Vec<String>,
+        replayed: Vec<String>,
+    },
+}
+
+impl From<StorageError> for ReplayError {
+    fn from(value: StorageError) -> Self {
+        ReplayError::StateMachine(value.into())
+    }
+}
+
+/// Where the partition processor records the commands of the given partition.
+pub fn recording_path(partition_id: PartitionId) -> PathBuf {
+    node_filepath("replay").join(format!("partition-{partition_id}.rec"))
+}
+
+/// Appends the recorded commands to a file, each one prefixed by its length.
+#[derive(Debug)]
+pub struct CommandRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    buf: BytesMut,
+}
+
+impl CommandRecorder {
+    pub fn create(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+            buf: BytesMut::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(super) fn record(&mut self, command: Command, effects: &Effects) -> io::Result<()> {
+        let recorded_command = RecordedCommand {
+            command,
+            effects: effects.fingerprint(),
+        };
+
+        self.buf.clear();
+        // reserve the length prefix
+        self.buf.put_u32(0);
+        StorageCodec::encode(&recorded_command, &mut self.buf)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let len = u32::try_from(self.buf.len() - 4)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.buf[..4].copy_from_slice(&len.to_be_bytes());
+
+        self.writer.write_all(&self.buf)?;
+        // keep the recording usable if the process crashes
+        self.writer.flush()
+    }
+}
+
+/// Reads the commands of a recording in the order they were applied.
+#[derive(Debug)]
+pub struct RecordingReader<R> {
+    reader: R,
+}
+
+impl RecordingReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> RecordingReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn read_next(&mut self) -> Result<Option<RecordedCommand>, ReplayError> {
+        let mut len = [0; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+
+        let mut buf = vec![0; u32::from_be_bytes(len) as usize];
+        self.reader.read_exact(&mut buf)?;
+        Ok(Some(StorageCodec::decode::<RecordedCommand, _>(
+            &mut buf.as_slice(),
+        )?))
+    }
+}
+
+impl<R: Read> Iterator for RecordingReader<R> {
+    type Item = Result<RecordedCommand, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+/// Replays the recorded commands against the state machine and fails on the first command whose
+/// effects differ from the recorded ones. Returns the number of replayed commands.
+pub(crate) async fn replay<Codec: RawEntryCodec>(
+    state_machine: &mut StateMachine<Codec>,
+    partition_storage: &mut PartitionStorage<PartitionStore>,
+    recording: impl IntoIterator<Item = Result<RecordedCommand, ReplayError>>,
+) -> Result<usize, ReplayError> {
+    let mut effects = Effects::default();
+    let mut action_collector = ActionCollector::default();
+    let mut num_replayed = 0;
+
+    for (index, recorded_command) in recording.into_iter().enumerate() {
+        let RecordedCommand {
+            command,
+            effects: recorded,
+        } = recorded_command?;
+        let command_type = command.name();
+
+        effects.clear();
+        action_collector.clear();
+        let mut transaction = partition_storage.create_transaction();
+        state_machine
+            .interpreter
+            .on_apply(command, &mut effects, &mut transaction)
+            .await?;
+
+        let replayed = effects.fingerprint();
+        if replayed != recorded {
+            return Err(ReplayError::Divergence {
+                index,
+                command: command_type,
+                recorded,
+                replayed,
+            });
+        }
+
+        EffectInterpreter::<Codec>::interpret_effects(
+            &mut effects,
+            &mut transaction,
+            &mut action_collector,
+        )
+        .await?;
+        transaction.commit().await?;
+        num_replayed += 1;
+    }
+
+    Ok(num_replayed)
+}
+
+/// Replays the recording at `path` against the given partition store, which must be in the state
+/// the recording started from. Returns the number of replayed commands.
+pub async fn replay_recording<Codec: RawEntryCodec>(
+    path: impl AsRef<Path>,
+    partition_id: PartitionId,
+    partition_key_range: RangeInclusive<PartitionKey>,
+    partition_store: PartitionStore,
+) -> Result<usize, ReplayError> {
+    let recording = RecordingReader::open(path)?;
+    let mut partition_storage =
+        PartitionStorage::new(partition_id, partition_key_range.clone(), partition_store);
+
+    let inbox_seq_number = partition_storage.load_inbox_seq_number().await?;
+    let outbox_seq_number = partition_storage.load_outbox_seq_number().await?;
+    let paused_services = partition_storage.load_paused_services().await?;
+    let state_machine_version = partition_storage.load_state_machine_version().await?;
+    let mut state_machine =
+        StateMachine::<Codec>::new(inbox_seq_number, outbox_seq_number, partition_key_range)
+            .with_paused_services(paused_services)
+            .with_state_machine_version(state_machine_version);
+
+    replay(&mut state_machine, &mut partition_storage, recording).await
+}
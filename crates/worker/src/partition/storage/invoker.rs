@@ -9,7 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use bytes::Bytes;
-use futures::{stream, StreamExt, TryStreamExt};
+use futures::{future, stream, StreamExt, TryStreamExt};
 use restate_invoker_api::{EagerState, JournalMetadata};
 use restate_storage_api::invocation_status_table::{
     InvocationStatus, ReadOnlyInvocationStatusTable,
@@ -19,6 +19,8 @@ use restate_storage_api::state_table::ReadOnlyStateTable;
 use restate_types::identifiers::InvocationId;
 use restate_types::identifiers::ServiceId;
 use restate_types::journal::raw::PlainRawEntry;
+use restate_types::time::MillisSinceEpoch;
+use std::collections::HashMap;
 use std::vec::IntoIter;
 
 #[derive(Debug, thiserror::Error)]
@@ -93,9 +95,24 @@ where
         &'a mut self,
         service_id: &'a ServiceId,
     ) -> Result<EagerState<Self::StateIter>, Self::Error> {
+        let expirations = self
+            .0
+            .get_all_user_state_expirations(service_id)
+            .try_collect::<HashMap<_, _>>()
+            .await?;
+        let now = MillisSinceEpoch::now();
+
         let user_states = self
             .0
             .get_all_user_states(service_id)
+            // Expired entries are removed by their timer, which might not have fired yet
+            .try_filter(|(key, _)| {
+                future::ready(
+                    expirations
+                        .get(key)
+                        .map_or(true, |expiration_time| *expiration_time > now),
+                )
+            })
             .try_collect::<Vec<_>>()
             .await?;
 
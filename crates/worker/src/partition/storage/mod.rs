@@ -11,19 +11,22 @@
 use crate::metric_definitions::{PARTITION_STORAGE_TX_COMMITTED, PARTITION_STORAGE_TX_CREATED};
 use crate::partition::shuffle::{OutboxReader, OutboxReaderError};
 use bytes::Bytes;
+use bytestring::ByteString;
 use futures::{Stream, StreamExt, TryStreamExt};
 use metrics::counter;
+use restate_storage_api::dead_letter_table::DeadLetter;
 use restate_storage_api::deduplication_table::{
     DedupSequenceNumber, ProducerId, ReadOnlyDeduplicationTable,
 };
 use restate_storage_api::fsm_table::{ReadOnlyFsmTable, SequenceNumber};
 use restate_storage_api::idempotency_table::IdempotencyMetadata;
-use restate_storage_api::inbox_table::{InboxEntry, SequenceNumberInboxEntry};
+use restate_storage_api::inbox_table::{InboxEntry, InboxPriority, SequenceNumberInboxEntry};
 use restate_storage_api::invocation_status_table::{
     InvocationStatus, ReadOnlyInvocationStatusTable,
 };
 use restate_storage_api::journal_table::{JournalEntry, ReadOnlyJournalTable};
 use restate_storage_api::outbox_table::{OutboxMessage, OutboxTable};
+use restate_storage_api::promise_table::Promise;
 use restate_storage_api::service_status_table::{
     ReadOnlyVirtualObjectStatusTable, VirtualObjectStatus,
 };
@@ -32,6 +35,7 @@ use restate_storage_api::timer_table::{Timer, TimerKey, TimerTable};
 use restate_storage_api::Result as StorageResult;
 use restate_storage_api::StorageError;
 use restate_timer::TimerReader;
+use restate_types::flexbuffers_storage_encode_decode;
 use restate_types::identifiers::{
     EntryIndex, IdempotencyId, InvocationId, PartitionId, PartitionKey, ServiceId, WithPartitionKey,
 };
@@ -40,7 +44,11 @@ use restate_types::journal::enriched::EnrichedRawEntry;
 use restate_types::journal::CompletionResult;
 use restate_types::logs::Lsn;
 use restate_types::message::MessageIndex;
+use restate_types::partition_table::PartitionSplit;
+use restate_types::time::MillisSinceEpoch;
+use restate_types::StateMachineVersion;
 use restate_wal_protocol::timer::TimerKeyValue;
+use std::collections::HashSet;
 use std::future::Future;
 use std::ops::RangeInclusive;
 
@@ -55,7 +63,7 @@ pub(crate) struct PartitionStorage<Storage> {
 }
 
 impl<Storage> PartitionStorage<Storage> {
-    pub(super) fn new(
+    pub(crate) fn new(
         partition_id: PartitionId,
         partition_key_range: RangeInclusive<PartitionKey>,
         storage: Storage,
@@ -95,6 +103,22 @@ async fn load_seq_number<F: ReadOnlyFsmTable + Send>(
         .unwrap_or(MessageIndex::default()))
 }
 
+/// Names of the paused services, whose new invocations are held back in the inbox.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+struct PausedServices(HashSet<ByteString>);
+
+flexbuffers_storage_encode_decode!(PausedServices);
+
+async fn load_paused_services<F: ReadOnlyFsmTable + Send>(
+    storage: &mut F,
+    partition_id: PartitionId,
+) -> Result<HashSet<ByteString>, StorageError> {
+    let paused_services = storage
+        .get::<PausedServices>(partition_id, fsm_variable::PAUSED_SERVICES)
+        .await?;
+    Ok(paused_services.unwrap_or_default().0)
+}
+
 impl<Storage> PartitionStorage<Storage>
 where
     Storage: ReadOnlyFsmTable
@@ -138,6 +162,26 @@ where
         Ok(seq_number.map(|seq_number| Lsn::from(u64::from(seq_number))))
     }
 
+    pub async fn load_paused_services(&mut self) -> StorageResult<HashSet<ByteString>> {
+        load_paused_services(&mut self.storage, self.partition_id).await
+    }
+
+    /// Partitions which have never been upgraded run the initial state machine version.
+    pub async fn load_state_machine_version(&mut self) -> StorageResult<StateMachineVersion> {
+        let version = self
+            .storage
+            .get::<StateMachineVersion>(self.partition_id, fsm_variable::STATE_MACHINE_VERSION)
+            .await?;
+        Ok(version.unwrap_or_default())
+    }
+
+    /// Returns the split if the partition has been sealed for it.
+    pub async fn load_partition_split(&mut self) -> StorageResult<Option<PartitionSplit>> {
+        self.storage
+            .get::<PartitionSplit>(self.partition_id, fsm_variable::PARTITION_SPLIT)
+            .await
+    }
+
     pub fn scan_invoked_invocations(
         &mut self,
     ) -> impl Stream<Item = Result<(InvocationId, InvocationTarget), StorageError>> + Send + '_
@@ -231,6 +275,51 @@ where
 
         Ok(())
     }
+
+    /// Seals the partition, it won't apply any further log records.
+    pub async fn store_partition_split(&mut self, split: &PartitionSplit) -> StorageResult<()> {
+        self.inner
+            .put(
+                self.partition_id,
+                fsm_variable::PARTITION_SPLIT,
+                split.clone(),
+            )
+            .await;
+
+        Ok(())
+    }
+
+    /// Remembers a split which has to wait for the outbox to be truncated before the partition
+    /// can be sealed.
+    pub async fn store_pending_partition_split(
+        &mut self,
+        split: &PartitionSplit,
+    ) -> StorageResult<()> {
+        self.inner
+            .put(
+                self.partition_id,
+                fsm_variable::PENDING_PARTITION_SPLIT,
+                split.clone(),
+            )
+            .await;
+
+        Ok(())
+    }
+
+    pub async fn load_pending_partition_split(&mut self) -> StorageResult<Option<PartitionSplit>> {
+        self.inner
+            .get::<PartitionSplit>(self.partition_id, fsm_variable::PENDING_PARTITION_SPLIT)
+            .await
+    }
+
+    /// Returns true if all outbox messages have been delivered and truncated.
+    pub async fn is_outbox_empty(&mut self) -> StorageResult<bool> {
+        Ok(self
+            .inner
+            .get_next_outbox_message(self.partition_id, 0)
+            .await?
+            .is_none())
+    }
 }
 
 // Avoid adding methods here, but rather use directly the storage_api traits!!!
@@ -290,6 +379,15 @@ where
             .await
     }
 
+    async fn load_state_expiration(
+        &mut self,
+        service_id: &ServiceId,
+        key: &Bytes,
+    ) -> StorageResult<Option<MillisSinceEpoch>> {
+        self.assert_partition_key(service_id);
+        self.inner.get_user_state_expiration(service_id, key).await
+    }
+
     async fn load_completion_result(
         &mut self,
         invocation_id: &InvocationId,
@@ -306,6 +404,13 @@ where
     ) -> impl Stream<Item = StorageResult<(EntryIndex, JournalEntry)>> + Send {
         self.inner.get_journal(invocation_id, length)
     }
+
+    fn inboxed_invocations(
+        &mut self,
+        partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = StorageResult<(InvocationId, InvocationTarget)>> + Send {
+        self.inner.inboxed_invocations(partition_key_range)
+    }
 }
 
 // Avoid adding methods here, but rather use directly the storage_api traits!!!
@@ -422,6 +527,7 @@ where
     async fn enqueue_into_inbox(
         &mut self,
         seq_number: MessageIndex,
+        priority: InboxPriority,
         inbox_entry: InboxEntry,
     ) -> StorageResult<()> {
         self.assert_partition_key(inbox_entry.service_id());
@@ -432,7 +538,7 @@ where
         self.inner
             .put_inbox_entry(
                 &service_id,
-                SequenceNumberInboxEntry::new(seq_number, inbox_entry),
+                SequenceNumberInboxEntry::new(seq_number, inbox_entry).with_priority(priority),
             )
             .await;
 
@@ -461,6 +567,48 @@ where
             .await
     }
 
+    async fn add_paused_service(&mut self, service_name: &ByteString) -> StorageResult<()> {
+        let mut paused_services = load_paused_services(&mut self.inner, self.partition_id).await?;
+        if paused_services.insert(service_name.clone()) {
+            self.inner
+                .put(
+                    self.partition_id,
+                    fsm_variable::PAUSED_SERVICES,
+                    PausedServices(paused_services),
+                )
+                .await;
+        }
+        Ok(())
+    }
+
+    async fn remove_paused_service(&mut self, service_name: &ByteString) -> StorageResult<()> {
+        let mut paused_services = load_paused_services(&mut self.inner, self.partition_id).await?;
+        if paused_services.remove(service_name) {
+            self.inner
+                .put(
+                    self.partition_id,
+                    fsm_variable::PAUSED_SERVICES,
+                    PausedServices(paused_services),
+                )
+                .await;
+        }
+        Ok(())
+    }
+
+    async fn store_state_machine_version(
+        &mut self,
+        version: StateMachineVersion,
+    ) -> StorageResult<()> {
+        self.inner
+            .put(
+                self.partition_id,
+                fsm_variable::STATE_MACHINE_VERSION,
+                version,
+            )
+            .await;
+        Ok(())
+    }
+
     async fn truncate_outbox(&mut self, outbox_sequence_number: MessageIndex) -> StorageResult<()> {
         self.inner
             .truncate_outbox(
@@ -479,9 +627,14 @@ where
         self.inner.pop_inbox(service_id).await
     }
 
-    async fn delete_inbox_entry(&mut self, service_id: &ServiceId, sequence_number: MessageIndex) {
+    async fn delete_inbox_entry(
+        &mut self,
+        service_id: &ServiceId,
+        priority: InboxPriority,
+        sequence_number: MessageIndex,
+    ) {
         self.inner
-            .delete_inbox_entry(service_id, sequence_number)
+            .delete_inbox_entry(service_id, priority, sequence_number)
             .await;
     }
 
@@ -497,9 +650,22 @@ where
         service_id: &ServiceId,
         key: Bytes,
         value: Bytes,
+        expiration_time: Option<MillisSinceEpoch>,
     ) -> StorageResult<()> {
         self.assert_partition_key(service_id);
         self.inner.put_user_state(service_id, &key, &value).await;
+        match expiration_time {
+            Some(expiration_time) => {
+                self.inner
+                    .put_user_state_expiration(service_id, &key, expiration_time)
+                    .await
+            }
+            None => {
+                self.inner
+                    .delete_user_state_expiration(service_id, &key)
+                    .await
+            }
+        }
 
         Ok(())
     }
@@ -577,6 +743,13 @@ where
     ) -> impl Stream<Item = StorageResult<(InvocationId, InvocationTarget)>> + Send {
         self.inner.invoked_invocations(partition_key_range)
     }
+
+    fn inboxed_invocations(
+        &mut self,
+        partition_key_range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = StorageResult<(InvocationId, InvocationTarget)>> + Send {
+        self.inner.inboxed_invocations(partition_key_range)
+    }
 }
 
 // Workaround until https://github.com/restatedev/restate/issues/276 is sorted out
@@ -623,11 +796,136 @@ where
     }
 }
 
-mod fsm_variable {
+// Workaround until https://github.com/restatedev/restate/issues/276 is sorted out
+impl<TransactionType> restate_storage_api::dead_letter_table::ReadOnlyDeadLetterTable
+    for Transaction<TransactionType>
+where
+    TransactionType: restate_storage_api::Transaction + Send,
+{
+    fn get_dead_letter(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> impl Future<Output = StorageResult<Option<DeadLetter>>> + Send {
+        self.inner.get_dead_letter(invocation_id)
+    }
+
+    fn all_dead_letters(
+        &mut self,
+        range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = StorageResult<(InvocationId, DeadLetter)>> + Send {
+        self.inner.all_dead_letters(range)
+    }
+}
+
+// Workaround until https://github.com/restatedev/restate/issues/276 is sorted out
+impl<TransactionType> restate_storage_api::dead_letter_table::DeadLetterTable
+    for Transaction<TransactionType>
+where
+    TransactionType: restate_storage_api::Transaction + Send,
+{
+    fn put_dead_letter(
+        &mut self,
+        invocation_id: &InvocationId,
+        dead_letter: DeadLetter,
+    ) -> impl Future<Output = ()> + Send {
+        self.inner.put_dead_letter(invocation_id, dead_letter)
+    }
+
+    fn delete_dead_letter(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> impl Future<Output = ()> + Send {
+        self.inner.delete_dead_letter(invocation_id)
+    }
+}
+
+// Workaround until https://github.com/restatedev/restate/issues/276 is sorted out
+impl<TransactionType> restate_storage_api::promise_table::ReadOnlyPromiseTable
+    for Transaction<TransactionType>
+where
+    TransactionType: restate_storage_api::Transaction + Send,
+{
+    fn get_promise(
+        &mut self,
+        service_id: &ServiceId,
+        key: &ByteString,
+    ) -> impl Future<Output = StorageResult<Option<Promise>>> + Send {
+        self.assert_partition_key(service_id);
+        self.inner.get_promise(service_id, key)
+    }
+}
+
+// Workaround until https://github.com/restatedev/restate/issues/276 is sorted out
+impl<TransactionType> restate_storage_api::promise_table::PromiseTable
+    for Transaction<TransactionType>
+where
+    TransactionType: restate_storage_api::Transaction + Send,
+{
+    fn put_promise(
+        &mut self,
+        service_id: &ServiceId,
+        key: &ByteString,
+        promise: Promise,
+    ) -> impl Future<Output = ()> + Send {
+        self.assert_partition_key(service_id);
+        self.inner.put_promise(service_id, key, promise)
+    }
+
+    fn delete_all_promises(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Future<Output = StorageResult<()>> + Send {
+        self.assert_partition_key(service_id);
+        self.inner.delete_all_promises(service_id)
+    }
+}
+
+// Workaround until https://github.com/restatedev/restate/issues/276 is sorted out
+impl<TransactionType> restate_storage_api::inbox_table::ReadOnlyInboxTable
+    for Transaction<TransactionType>
+where
+    TransactionType: restate_storage_api::Transaction + Send,
+{
+    fn peek_inbox(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Future<Output = StorageResult<Option<SequenceNumberInboxEntry>>> + Send {
+        self.assert_partition_key(service_id);
+        self.inner.peek_inbox(service_id)
+    }
+
+    fn inbox(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Stream<Item = StorageResult<SequenceNumberInboxEntry>> + Send {
+        self.assert_partition_key(service_id);
+        self.inner.inbox(service_id)
+    }
+
+    fn all_inboxes(
+        &mut self,
+        range: RangeInclusive<PartitionKey>,
+    ) -> impl Stream<Item = StorageResult<SequenceNumberInboxEntry>> + Send {
+        self.inner.all_inboxes(range)
+    }
+}
+
+pub(crate) mod fsm_variable {
     pub(crate) const INBOX_SEQ_NUMBER: u64 = 0;
     pub(crate) const OUTBOX_SEQ_NUMBER: u64 = 1;
 
     pub(crate) const APPLIED_LSN: u64 = 2;
+
+    /// Set once the partition has been sealed to be split
+    pub(crate) const PARTITION_SPLIT: u64 = 3;
+
+    pub(crate) const PAUSED_SERVICES: u64 = 4;
+
+    /// Version of the state machine behavior the partition has been upgraded to
+    pub(crate) const STATE_MACHINE_VERSION: u64 = 5;
+
+    /// Set while a split waits for the outbox to be truncated
+    pub(crate) const PENDING_PARTITION_SPLIT: u64 = 6;
 }
 
 impl<Storage> OutboxReader for PartitionStorage<Storage>
@@ -9,21 +9,26 @@
 // by the Apache License, Version 2.0.
 
 use crate::partition::storage::invoker::InvokerStorageReader;
+use crate::partition::storage::{fsm_variable, PartitionStorage};
+use crate::partition_snapshots::{restore_partition_store, PartitionSnapshotRepository};
 use crate::PartitionProcessor;
 use anyhow::Context;
 use restate_bifrost::Bifrost;
 use restate_core::worker_api::{ProcessorsManagerCommand, ProcessorsManagerHandle};
 use restate_core::{cancellation_watcher, task_center, Metadata, ShutdownError, TaskId, TaskKind};
 use restate_invoker_impl::InvokerHandle;
-use restate_metadata_store::{MetadataStoreClient, ReadModifyWriteError};
+use restate_metadata_store::{MetadataStoreClient, Precondition, ReadModifyWriteError};
 use restate_network::Networking;
-use restate_partition_store::{OpenMode, PartitionStore, PartitionStoreManager};
+use restate_partition_store::{
+    OpenMode, PartitionStore, PartitionStoreManager, SplitFsmVariables, SplitTarget,
+};
 use restate_types::arc_util::ArcSwapExt;
 use restate_types::config::{UpdateableConfiguration, WorkerOptions};
 use restate_types::epoch::EpochMetadata;
 use restate_types::identifiers::{LeaderEpoch, PartitionId, PartitionKey};
 use restate_types::logs::{LogId, Payload};
-use restate_types::metadata_store::keys::partition_processor_epoch_key;
+use restate_types::metadata_store::keys::{partition_processor_epoch_key, partition_split_key};
+use restate_types::partition_table::{PartitionSplit, RegisteredPartitionSplit};
 use restate_types::{GenerationalNodeId, Version};
 use restate_wal_protocol::control::AnnounceLeader;
 use restate_wal_protocol::{Command as WalCommand, Destination, Envelope, Header, Source};
@@ -42,8 +47,13 @@ pub struct PartitionProcessorManager {
     networking: Networking,
     bifrost: Bifrost,
     invoker_handle: InvokerHandle<InvokerStorageReader<PartitionStore>>,
+    snapshot_repository: Option<PartitionSnapshotRepository>,
     rx: mpsc::Receiver<ProcessorsManagerCommand>,
     tx: mpsc::Sender<ProcessorsManagerCommand>,
+    /// Splits completed by the partition processors of this node, the new partitions are started
+    /// in place of the split partition
+    split_rx: mpsc::Receiver<(RegisteredPartitionSplit, Role)>,
+    split_tx: mpsc::Sender<(RegisteredPartitionSplit, Role)>,
 }
 
 impl PartitionProcessorManager {
@@ -55,8 +65,10 @@ impl PartitionProcessorManager {
         networking: Networking,
         bifrost: Bifrost,
         invoker_handle: InvokerHandle<InvokerStorageReader<PartitionStore>>,
+        snapshot_repository: Option<PartitionSnapshotRepository>,
     ) -> Self {
         let (tx, rx) = mpsc::channel(updateable_config.load().worker.internal_queue_length());
+        let (split_tx, split_rx) = mpsc::channel(1);
         Self {
             updateable_config,
             running_partition_processors: HashMap::default(),
@@ -66,8 +78,11 @@ impl PartitionProcessorManager {
             networking,
             bifrost,
             invoker_handle,
+            snapshot_repository,
             rx,
             tx,
+            split_rx,
+            split_tx,
         }
     }
 
@@ -98,6 +113,9 @@ impl PartitionProcessorManager {
                     self.handle_command(command).await;
                     debug!("PartitionProcessorManager shutting down");
                 }
+                Some((registered_split, role)) = self.split_rx.recv() => {
+                    self.start_split_partitions(registered_split, role)?;
+                }
               _ = &mut shutdown => {
                     return Ok(());
                 }
@@ -169,6 +187,46 @@ impl PartitionProcessorManager {
         Ok(())
     }
 
+    /// Replaces the processor of a split partition with the processors of the new partitions.
+    fn start_split_partitions(
+        &mut self,
+        registered_split: RegisteredPartitionSplit,
+        role: Role,
+    ) -> Result<(), ShutdownError> {
+        let config = self.updateable_config.pinned();
+        let split = registered_split.split;
+        self.running_partition_processors
+            .remove(&split.partition_id);
+
+        // the partition table might not contain the new partitions yet
+        for (partition_id, partition_range) in [
+            (
+                split.left_partition_id,
+                registered_split.left_partition_range,
+            ),
+            (
+                split.right_partition_id,
+                registered_split.right_partition_range,
+            ),
+        ] {
+            if !self
+                .running_partition_processors
+                .contains_key(&partition_id)
+            {
+                let task_id = self.spawn_partition_processor(
+                    &config.worker,
+                    partition_id,
+                    partition_range,
+                    role,
+                )?;
+                self.running_partition_processors
+                    .insert(partition_id, task_id);
+            }
+        }
+
+        Ok(())
+    }
+
     fn spawn_partition_processor(
         &mut self,
         options: &WorkerOptions,
@@ -182,6 +240,8 @@ impl PartitionProcessorManager {
         let mut bifrost = self.bifrost.clone();
         let metadata_store_client = self.metadata_store_client.clone();
         let node_id = self.metadata.my_node_id();
+        let snapshot_repository = self.snapshot_repository.clone();
+        let split_tx = self.split_tx.clone();
 
         task_center().spawn_child(
             TaskKind::PartitionProcessor,
@@ -191,7 +251,35 @@ impl PartitionProcessorManager {
                 let storage_manager = self.partition_store_manager.clone();
                 let options = options.clone();
                 async move {
-                    let partition_store = storage_manager
+                    // the split is registered once the stores of the new partitions are complete
+                    if let Some(registered_split) = metadata_store_client
+                        .get::<RegisteredPartitionSplit>(partition_split_key(partition_id))
+                        .await?
+                    {
+                        debug!("Partition has been split, starting the new partitions instead");
+                        storage_manager.drop_partition_store(partition_id).await?;
+                        let _ = split_tx.send((registered_split, role)).await;
+                        return Ok(());
+                    }
+
+                    if let Some(repository) = snapshot_repository {
+                        let trim_point = bifrost
+                            .get_trim_point(LogId::from(partition_id))
+                            .await
+                            .context("failed reading trim point of partition log")?;
+                        restore_partition_store(
+                            &repository,
+                            &storage_manager,
+                            trim_point,
+                            partition_id,
+                            partition_range.clone(),
+                            &options.storage.rocksdb,
+                        )
+                        .await
+                        .context("failed restoring partition store from snapshot")?;
+                    }
+
+                    let mut partition_store = storage_manager
                         .open_partition_store(
                             partition_id,
                             partition_range.clone(),
@@ -200,18 +288,45 @@ impl PartitionProcessorManager {
                         )
                         .await?;
 
+                    let migrated_inbox_entries = partition_store
+                        .migrate_legacy_inbox()
+                        .await
+                        .context("failed migrating the inbox of the partition store")?;
+                    if migrated_inbox_entries > 0 {
+                        info!(
+                            "Migrated {} inbox entries to the background priority lane",
+                            migrated_inbox_entries
+                        );
+                    }
+
                     if role == Role::Leader {
                         Self::claim_leadership(
                             &mut bifrost,
-                            metadata_store_client,
+                            metadata_store_client.clone(),
                             partition_id,
-                            partition_range,
+                            partition_range.clone(),
                             node_id,
                         )
                         .await?;
                     }
 
-                    processor.run(networking, bifrost, partition_store).await
+                    if let Some(split) = processor
+                        .run(networking, bifrost, partition_store.clone())
+                        .await?
+                    {
+                        let registered_split = Self::complete_split(
+                            &storage_manager,
+                            &metadata_store_client,
+                            partition_store,
+                            split,
+                            partition_range,
+                            &options,
+                        )
+                        .await?;
+                        let _ = split_tx.send((registered_split, role)).await;
+                    }
+
+                    Ok(())
                 }
             },
         )
@@ -227,11 +342,80 @@ impl PartitionProcessorManager {
             partition_id,
             partition_key_range,
             options.num_timers_in_memory_limit(),
+            options.timer_bucket_width(),
             options.internal_queue_length(),
+            options.max_inbox_length(),
+            options.inbox_overflow_policy(),
+            options.dead_letter_retention(),
+            options.invoker.drain_grace_period.into(),
             self.invoker_handle.clone(),
         )
     }
 
+    /// Splits the store of a partition which has been sealed for a split into the stores of the
+    /// two new partitions and registers them. The sealed partition store is dropped afterwards.
+    ///
+    /// Every replica of the partition splits its own store. Since the sealed partition store is
+    /// only dropped once the split has been registered, a split interrupted by a crash is redone
+    /// on restart. Once registered, the new partitions are started instead of the split one.
+    async fn complete_split(
+        storage_manager: &PartitionStoreManager,
+        metadata_store_client: &MetadataStoreClient,
+        partition_store: PartitionStore,
+        split: PartitionSplit,
+        partition_range: RangeInclusive<PartitionKey>,
+        options: &WorkerOptions,
+    ) -> anyhow::Result<RegisteredPartitionSplit> {
+        let partition_id = split.partition_id;
+        let (left_range, right_range) = split
+            .split_range(&partition_range)
+            .context("split key does not split the partition key range")?;
+        let sealed_lsn = PartitionStorage::new(partition_id, partition_range, partition_store)
+            .load_applied_lsn()
+            .await?
+            .context("sealed partition has no applied lsn")?;
+
+        storage_manager
+            .split_partition_store(
+                partition_id,
+                SplitTarget::new(split.left_partition_id, left_range.clone()),
+                SplitTarget::new(split.right_partition_id, right_range.clone()),
+                SplitFsmVariables {
+                    inherited: vec![
+                        fsm_variable::PAUSED_SERVICES,
+                        fsm_variable::STATE_MACHINE_VERSION,
+                    ],
+                    inbox_seq_number: fsm_variable::INBOX_SEQ_NUMBER,
+                    outbox_seq_number: fsm_variable::OUTBOX_SEQ_NUMBER,
+                },
+                &options.storage.rocksdb,
+            )
+            .await
+            .context("failed splitting partition store")?;
+
+        let registered_split =
+            RegisteredPartitionSplit::new(split.clone(), left_range, right_range, sealed_lsn);
+        metadata_store_client
+            .put(
+                partition_split_key(partition_id),
+                registered_split.clone(),
+                Precondition::None,
+            )
+            .await
+            .context("failed registering partition split")?;
+
+        storage_manager.drop_partition_store(partition_id).await?;
+
+        info!(
+            %partition_id,
+            %sealed_lsn,
+            left_partition_id = %split.left_partition_id,
+            right_partition_id = %split.right_partition_id,
+            "Partition split completed"
+        );
+        Ok(registered_split)
+    }
+
     async fn claim_leadership(
         bifrost: &mut Bifrost,
         metadata_store_client: MetadataStoreClient,
@@ -327,9 +511,669 @@ pub enum Action {
     Start(Role),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Role {
     Leader,
     #[allow(dead_code)]
     Follower,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::partition::storage::invoker::InvokerStorageReader;
+    use futures::future::{ready, Ready};
+    use futures::TryStreamExt;
+    use restate_core::{metadata, TestCoreEnvBuilder};
+    use restate_errors::NotRunningError;
+    use restate_invoker_api::{Effect, EffectKind, InvokeInputJournal, ServiceHandle};
+    use restate_rocksdb::RocksDbManager;
+    use restate_service_protocol::codec::ProtobufRawEntryCodec;
+    use restate_storage_api::inbox_table::{InboxEntry, ReadOnlyInboxTable};
+    use restate_storage_api::invocation_status_table::{
+        InvocationStatus, ReadOnlyInvocationStatusTable,
+    };
+    use restate_storage_api::outbox_table::{OutboxMessage, OutboxTable};
+    use restate_storage_api::Transaction;
+    use restate_types::arc_util::Constant;
+    use restate_types::config::CommonOptions;
+    use restate_types::identifiers::{
+        EntryIndex, InvocationId, PartitionLeaderEpoch, ServiceId, WithPartitionKey,
+    };
+    use restate_types::invocation::{
+        InvocationTarget, InvocationTermination, ServiceInvocation, Source as InvocationSource,
+        VirtualObjectHandlerType,
+    };
+    use restate_types::journal::Completion;
+    use restate_types::logs::metadata::ProviderKind;
+    use restate_types::partition_table::FixedPartitionTable;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use test_log::test;
+
+    const LEFT: PartitionId = PartitionId::new_unchecked(1);
+    const RIGHT: PartitionId = PartitionId::new_unchecked(2);
+
+    /// Followers never talk to the invoker.
+    #[derive(Clone)]
+    struct NoopInvoker;
+
+    impl ServiceHandle<InvokerStorageReader<PartitionStore>> for NoopInvoker {
+        type Future = Ready<Result<(), NotRunningError>>;
+
+        fn invoke(
+            &mut self,
+            _: PartitionLeaderEpoch,
+            _: InvocationId,
+            _: InvocationTarget,
+            _: InvokeInputJournal,
+        ) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn notify_completion(
+            &mut self,
+            _: PartitionLeaderEpoch,
+            _: InvocationId,
+            _: Completion,
+        ) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn notify_stored_entry_ack(
+            &mut self,
+            _: PartitionLeaderEpoch,
+            _: InvocationId,
+            _: EntryIndex,
+        ) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn abort_all_partition(&mut self, _: PartitionLeaderEpoch) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn drain_partition(&mut self, _: PartitionLeaderEpoch, _: Duration) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn abort_invocation(&mut self, _: PartitionLeaderEpoch, _: InvocationId) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn register_partition(
+            &mut self,
+            _: PartitionLeaderEpoch,
+            _: RangeInclusive<PartitionKey>,
+            _: InvokerStorageReader<PartitionStore>,
+            _: mpsc::Sender<Effect>,
+        ) -> Self::Future {
+            ready(Ok(()))
+        }
+    }
+
+    /// Lets the invoked invocations complete while the partition is drained.
+    #[derive(Clone, Default)]
+    struct DrainingInvoker {
+        invoked: Arc<Mutex<Vec<InvocationId>>>,
+        effects_tx: Arc<Mutex<Option<mpsc::Sender<Effect>>>>,
+    }
+
+    impl ServiceHandle<InvokerStorageReader<PartitionStore>> for DrainingInvoker {
+        type Future = Ready<Result<(), NotRunningError>>;
+
+        fn invoke(
+            &mut self,
+            _: PartitionLeaderEpoch,
+            invocation_id: InvocationId,
+            _: InvocationTarget,
+            _: InvokeInputJournal,
+        ) -> Self::Future {
+            self.invoked.lock().unwrap().push(invocation_id);
+            ready(Ok(()))
+        }
+
+        fn notify_completion(
+            &mut self,
+            _: PartitionLeaderEpoch,
+            _: InvocationId,
+            _: Completion,
+        ) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn notify_stored_entry_ack(
+            &mut self,
+            _: PartitionLeaderEpoch,
+            _: InvocationId,
+            _: EntryIndex,
+        ) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn abort_all_partition(&mut self, _: PartitionLeaderEpoch) -> Self::Future {
+            self.effects_tx.lock().unwrap().take();
+            ready(Ok(()))
+        }
+
+        fn drain_partition(&mut self, _: PartitionLeaderEpoch, _: Duration) -> Self::Future {
+            // the partition is released once its last invocation ended
+            let effects_tx = self.effects_tx.lock().unwrap().take().unwrap();
+            for invocation_id in self.invoked.lock().unwrap().drain(..) {
+                effects_tx
+                    .try_send(Effect {
+                        invocation_id,
+                        kind: EffectKind::End,
+                    })
+                    .unwrap();
+            }
+            ready(Ok(()))
+        }
+
+        fn abort_invocation(&mut self, _: PartitionLeaderEpoch, _: InvocationId) -> Self::Future {
+            ready(Ok(()))
+        }
+
+        fn register_partition(
+            &mut self,
+            _: PartitionLeaderEpoch,
+            _: RangeInclusive<PartitionKey>,
+            _: InvokerStorageReader<PartitionStore>,
+            effects_tx: mpsc::Sender<Effect>,
+        ) -> Self::Future {
+            *self.effects_tx.lock().unwrap() = Some(effects_tx);
+            ready(Ok(()))
+        }
+    }
+
+    fn partition_processor<I>(
+        partition_id: PartitionId,
+        partition_key_range: RangeInclusive<PartitionKey>,
+        invoker: I,
+    ) -> crate::partition::PartitionProcessor<ProtobufRawEntryCodec, I> {
+        let options = WorkerOptions::default();
+        crate::partition::PartitionProcessor::new(
+            partition_id,
+            partition_key_range,
+            options.num_timers_in_memory_limit(),
+            options.timer_bucket_width(),
+            options.internal_queue_length(),
+            options.max_inbox_length(),
+            options.inbox_overflow_policy(),
+            options.dead_letter_retention(),
+            options.invoker.drain_grace_period.into(),
+            invoker,
+        )
+    }
+
+    async fn append(
+        bifrost: &mut Bifrost,
+        log_id: LogId,
+        partition_key: PartitionKey,
+        command: WalCommand,
+    ) {
+        let envelope = Envelope::new(
+            Header {
+                source: Source::ControlPlane {},
+                dest: Destination::Processor {
+                    partition_key,
+                    dedup: None,
+                },
+            },
+            command,
+        );
+        bifrost
+            .append(log_id, Payload::from(envelope.to_bytes().unwrap()))
+            .await
+            .unwrap();
+    }
+
+    /// Appends an exclusive invocation of the virtual object.
+    async fn invoke(bifrost: &mut Bifrost, log_id: LogId, service_id: &ServiceId) -> InvocationId {
+        let invocation_target = InvocationTarget::virtual_object(
+            service_id.service_name.clone(),
+            service_id.key.clone(),
+            "handler",
+            VirtualObjectHandlerType::Exclusive,
+        );
+        let invocation_id = InvocationId::generate(&invocation_target);
+        let service_invocation = ServiceInvocation {
+            invocation_id,
+            invocation_target,
+            argument: Default::default(),
+            source: InvocationSource::Ingress,
+            response_sink: None,
+            span_context: Default::default(),
+            headers: vec![],
+            execution_time: None,
+            execution_deadline: None,
+            completion_retention_time: None,
+            idempotency_key: None,
+        };
+        append(
+            bifrost,
+            log_id,
+            service_id.partition_key(),
+            WalCommand::Invoke(service_invocation),
+        )
+        .await;
+        invocation_id
+    }
+
+    /// Waits until the inbox of the virtual object contains `len` invocations and returns their
+    /// ids and inbox sequence numbers.
+    async fn await_inbox(
+        partition_store: &mut PartitionStore,
+        service_id: &ServiceId,
+        len: usize,
+    ) -> Vec<(u64, InvocationId)> {
+        loop {
+            let inbox: Vec<_> = partition_store
+                .transaction()
+                .inbox(service_id)
+                .map_ok(|entry| match entry.inbox_entry {
+                    InboxEntry::Invocation(_, invocation_id) => {
+                        (entry.inbox_sequence_number, invocation_id)
+                    }
+                    InboxEntry::StateMutation(_) => panic!("inbox entry is an invocation"),
+                })
+                .try_collect()
+                .await
+                .unwrap();
+            if inbox.len() >= len {
+                return inbox;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn split_partitions_keep_processing() {
+        let env = TestCoreEnvBuilder::new_with_mock_network()
+            .add_mock_nodes_config()
+            .with_partition_table(FixedPartitionTable::new(Version::MIN, 1))
+            .build()
+            .await;
+        let metadata_store_client = env.metadata_store_client.clone();
+        let metadata_writer = env.metadata_writer.clone();
+        env.tc
+            .run_in_scope("test", None, async move {
+                RocksDbManager::init(Constant::new(CommonOptions::default()));
+                let options = WorkerOptions::default();
+                let storage_manager = PartitionStoreManager::create(
+                    Constant::new(options.storage.clone()),
+                    Constant::new(options.storage.rocksdb.clone()),
+                    &[],
+                )
+                .await
+                .unwrap();
+                let mut bifrost = Bifrost::init().await;
+
+                let mut logs = (*metadata().logs().unwrap()).clone();
+                logs.add_log(LogId::from(LEFT), ProviderKind::InMemory);
+                logs.add_log(LogId::from(RIGHT), ProviderKind::InMemory);
+                logs.version = logs.version.next();
+                metadata_writer.update(logs).await.unwrap();
+
+                // split between the keys of the two virtual objects
+                let mut service_ids = [ServiceId::new("svc", "a"), ServiceId::new("svc", "b")];
+                service_ids.sort_by_key(|service_id| service_id.partition_key());
+                let [low, high] = service_ids;
+                let partition_range = PartitionKey::MIN..=PartitionKey::MAX;
+                let split = PartitionSplit {
+                    partition_id: PartitionId::MIN,
+                    split_key: high.partition_key(),
+                    left_partition_id: LEFT,
+                    right_partition_id: RIGHT,
+                };
+
+                // the first invocations lock the virtual objects, the following ones are inboxed
+                let parent_log = LogId::from(PartitionId::MIN);
+                for service_id in [&low, &high] {
+                    invoke(&mut bifrost, parent_log, service_id).await;
+                }
+                let low_inboxed = invoke(&mut bifrost, parent_log, &low).await;
+                let high_inboxed = invoke(&mut bifrost, parent_log, &high).await;
+                append(
+                    &mut bifrost,
+                    parent_log,
+                    PartitionKey::MIN,
+                    WalCommand::SplitPartition(split.clone()),
+                )
+                .await;
+
+                let partition_store = storage_manager
+                    .open_partition_store(
+                        PartitionId::MIN,
+                        partition_range.clone(),
+                        OpenMode::CreateIfMissing,
+                        &options.storage.rocksdb,
+                    )
+                    .await
+                    .unwrap();
+                let sealed_split =
+                    partition_processor(PartitionId::MIN, partition_range.clone(), NoopInvoker)
+                        .run(
+                            Networking::default(),
+                            bifrost.clone(),
+                            partition_store.clone(),
+                        )
+                        .await
+                        .unwrap();
+                assert_eq!(sealed_split, Some(split.clone()));
+
+                let registered_split = PartitionProcessorManager::complete_split(
+                    &storage_manager,
+                    &metadata_store_client,
+                    partition_store,
+                    split,
+                    partition_range,
+                    &options,
+                )
+                .await
+                .unwrap();
+                assert!(!storage_manager.partition_store_exists(PartitionId::MIN));
+
+                // both new partitions continue with the invocations of their keys
+                let mut stores = vec![];
+                for (partition_id, partition_key_range) in [
+                    (LEFT, registered_split.left_partition_range.clone()),
+                    (RIGHT, registered_split.right_partition_range.clone()),
+                ] {
+                    let partition_store = storage_manager
+                        .open_partition_store(
+                            partition_id,
+                            partition_key_range.clone(),
+                            OpenMode::OpenExisting,
+                            &options.storage.rocksdb,
+                        )
+                        .await
+                        .unwrap();
+                    let processor =
+                        partition_processor(partition_id, partition_key_range, NoopInvoker);
+                    let bifrost = bifrost.clone();
+                    let store = partition_store.clone();
+                    task_center()
+                        .spawn_child(
+                            TaskKind::PartitionProcessor,
+                            "partition-processor",
+                            Some(partition_id),
+                            async move {
+                                processor.run(Networking::default(), bifrost, store).await?;
+                                Ok(())
+                            },
+                        )
+                        .unwrap();
+                    stores.push(partition_store);
+                }
+                let low_invoked = invoke(&mut bifrost, LogId::from(LEFT), &low).await;
+                let high_invoked = invoke(&mut bifrost, LogId::from(RIGHT), &high).await;
+
+                // inbox sequence numbers continue after the inherited entries
+                assert_eq!(
+                    await_inbox(&mut stores[0], &low, 2).await,
+                    vec![(0, low_inboxed), (1, low_invoked)]
+                );
+                assert_eq!(
+                    await_inbox(&mut stores[1], &high, 2).await,
+                    vec![(1, high_inboxed), (2, high_invoked)]
+                );
+
+                task_center()
+                    .cancel_tasks(Some(TaskKind::PartitionProcessor), None)
+                    .await;
+            })
+            .await;
+    }
+
+    #[test(tokio::test)]
+    async fn split_waits_for_the_outbox_to_be_truncated() {
+        // don't share the partition stores with the other split test
+        const SOURCE: PartitionId = PartitionId::new_unchecked(3);
+        const LEFT: PartitionId = PartitionId::new_unchecked(4);
+        const RIGHT: PartitionId = PartitionId::new_unchecked(5);
+
+        let env = TestCoreEnvBuilder::new_with_mock_network()
+            .add_mock_nodes_config()
+            .with_partition_table(FixedPartitionTable::new(Version::MIN, 1))
+            .build()
+            .await;
+        let metadata_store_client = env.metadata_store_client.clone();
+        let metadata_writer = env.metadata_writer.clone();
+        env.tc
+            .run_in_scope("test", None, async move {
+                RocksDbManager::init(Constant::new(CommonOptions::default()));
+                let options = WorkerOptions::default();
+                let storage_manager = PartitionStoreManager::create(
+                    Constant::new(options.storage.clone()),
+                    Constant::new(options.storage.rocksdb.clone()),
+                    &[],
+                )
+                .await
+                .unwrap();
+                let mut bifrost = Bifrost::init().await;
+
+                let mut logs = (*metadata().logs().unwrap()).clone();
+                for partition_id in [SOURCE, LEFT, RIGHT] {
+                    logs.add_log(LogId::from(partition_id), ProviderKind::InMemory);
+                }
+                logs.version = logs.version.next();
+                metadata_writer.update(logs).await.unwrap();
+
+                let partition_range = PartitionKey::MIN..=PartitionKey::MAX;
+                let mut partition_store = storage_manager
+                    .open_partition_store(
+                        SOURCE,
+                        partition_range.clone(),
+                        OpenMode::CreateIfMissing,
+                        &options.storage.rocksdb,
+                    )
+                    .await
+                    .unwrap();
+
+                // a message that has been delivered, but whose truncation hasn't been applied yet
+                let service_id = ServiceId::new("svc", "a");
+                let target = InvocationId::generate(&InvocationTarget::virtual_object(
+                    "other",
+                    "key",
+                    "handler",
+                    VirtualObjectHandlerType::Exclusive,
+                ));
+                let mut txn = partition_store.transaction();
+                txn.add_message(
+                    SOURCE,
+                    0,
+                    OutboxMessage::InvocationTermination(InvocationTermination::kill(target)),
+                )
+                .await;
+                txn.commit().await.unwrap();
+
+                let split = PartitionSplit {
+                    partition_id: SOURCE,
+                    split_key: service_id.partition_key(),
+                    left_partition_id: LEFT,
+                    right_partition_id: RIGHT,
+                };
+                let source_log = LogId::from(SOURCE);
+                append(
+                    &mut bifrost,
+                    source_log,
+                    PartitionKey::MIN,
+                    WalCommand::SplitPartition(split.clone()),
+                )
+                .await;
+                // the partition keeps processing until the outbox has been truncated
+                let invoked = invoke(&mut bifrost, source_log, &service_id).await;
+                append(
+                    &mut bifrost,
+                    source_log,
+                    PartitionKey::MIN,
+                    WalCommand::TruncateOutbox(0),
+                )
+                .await;
+
+                let sealed_split =
+                    partition_processor(SOURCE, partition_range.clone(), NoopInvoker)
+                        .run(
+                            Networking::default(),
+                            bifrost.clone(),
+                            partition_store.clone(),
+                        )
+                        .await
+                        .unwrap();
+                assert_eq!(sealed_split, Some(split.clone()));
+
+                let registered_split = PartitionProcessorManager::complete_split(
+                    &storage_manager,
+                    &metadata_store_client,
+                    partition_store,
+                    split,
+                    partition_range,
+                    &options,
+                )
+                .await
+                .unwrap();
+
+                // the new partitions don't send the truncated message again
+                for (partition_id, partition_key_range) in [
+                    (LEFT, registered_split.left_partition_range.clone()),
+                    (RIGHT, registered_split.right_partition_range.clone()),
+                ] {
+                    let mut partition_store = storage_manager
+                        .open_partition_store(
+                            partition_id,
+                            partition_key_range.clone(),
+                            OpenMode::OpenExisting,
+                            &options.storage.rocksdb,
+                        )
+                        .await
+                        .unwrap();
+                    let mut txn = partition_store.transaction();
+                    assert_eq!(
+                        txn.get_next_outbox_message(partition_id, 0).await.unwrap(),
+                        None
+                    );
+                    if partition_key_range.contains(&service_id.partition_key()) {
+                        assert!(!matches!(
+                            txn.get_invocation_status(&invoked).await.unwrap(),
+                            InvocationStatus::Free
+                        ));
+                    }
+                }
+            })
+            .await;
+    }
+
+    #[test(tokio::test)]
+    async fn drained_invocations_end_before_shutdown() {
+        // don't share the partition store with the other tests
+        const PARTITION_ID: PartitionId = PartitionId::new_unchecked(6);
+
+        let env = TestCoreEnvBuilder::new_with_mock_network()
+            .add_mock_nodes_config()
+            .with_partition_table(FixedPartitionTable::new(Version::MIN, 1))
+            .build()
+            .await;
+        let metadata_writer = env.metadata_writer.clone();
+        env.tc
+            .run_in_scope("test", None, async move {
+                RocksDbManager::init(Constant::new(CommonOptions::default()));
+                let options = WorkerOptions::default();
+                let storage_manager = PartitionStoreManager::create(
+                    Constant::new(options.storage.clone()),
+                    Constant::new(options.storage.rocksdb.clone()),
+                    &[],
+                )
+                .await
+                .unwrap();
+                let mut bifrost = Bifrost::init().await;
+
+                let mut logs = (*metadata().logs().unwrap()).clone();
+                logs.add_log(LogId::from(PARTITION_ID), ProviderKind::InMemory);
+                logs.version = logs.version.next();
+                metadata_writer.update(logs).await.unwrap();
+
+                let partition_range = PartitionKey::MIN..=PartitionKey::MAX;
+                let mut partition_store = storage_manager
+                    .open_partition_store(
+                        PARTITION_ID,
+                        partition_range.clone(),
+                        OpenMode::CreateIfMissing,
+                        &options.storage.rocksdb,
+                    )
+                    .await
+                    .unwrap();
+
+                let log_id = LogId::from(PARTITION_ID);
+                append(
+                    &mut bifrost,
+                    log_id,
+                    PartitionKey::MIN,
+                    WalCommand::AnnounceLeader(AnnounceLeader {
+                        node_id: metadata().my_node_id(),
+                        leader_epoch: LeaderEpoch::INITIAL,
+                    }),
+                )
+                .await;
+                let invocation_id = invoke(&mut bifrost, log_id, &ServiceId::new("svc", "a")).await;
+
+                let invoker = DrainingInvoker::default();
+                let processor =
+                    partition_processor(PARTITION_ID, partition_range.clone(), invoker.clone());
+                let (processor_bifrost, store) = (bifrost.clone(), partition_store.clone());
+                task_center()
+                    .spawn_child(
+                        TaskKind::PartitionProcessor,
+                        "partition-processor",
+                        Some(PARTITION_ID),
+                        async move {
+                            processor
+                                .run(Networking::default(), processor_bifrost, store)
+                                .await?;
+                            Ok(())
+                        },
+                    )
+                    .unwrap();
+                while !invoker.invoked.lock().unwrap().contains(&invocation_id) {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+
+                // the leader proposes the effects of the drained invocation before it stops
+                task_center()
+                    .cancel_tasks(Some(TaskKind::PartitionProcessor), None)
+                    .await;
+
+                // they haven't been discarded in favour of a newer leader epoch
+                let processor =
+                    partition_processor(PARTITION_ID, partition_range.clone(), NoopInvoker);
+                let store = partition_store.clone();
+                task_center()
+                    .spawn_child(
+                        TaskKind::PartitionProcessor,
+                        "partition-processor",
+                        Some(PARTITION_ID),
+                        async move {
+                            processor.run(Networking::default(), bifrost, store).await?;
+                            Ok(())
+                        },
+                    )
+                    .unwrap();
+                while !matches!(
+                    partition_store
+                        .transaction()
+                        .get_invocation_status(&invocation_id)
+                        .await
+                        .unwrap(),
+                    InvocationStatus::Free
+                ) {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+
+                task_center()
+                    .cancel_tasks(Some(TaskKind::PartitionProcessor), None)
+                    .await;
+            })
+            .await;
+    }
+}
@@ -0,0 +1,811 @@
+// Copyright (c) 2024 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Periodic snapshots of partition stores to an object store.
+//!
+//! Snapshots are exported from a checkpoint of the partition store database, this guarantees
+//! that the exported state of a partition and the log position it has been applied up to are
+//! consistent. A new partition processor can be bootstrapped from a snapshot by importing its
+//! data and reading the log after the snapshot's applied lsn, the log before that position is no
+//! longer needed and can be trimmed. This happens on startup of a partition processor without
+//! local state, or whose local state lags behind the trim point of the log, see
+//! [`restore_partition_store`].
+//!
+//! Layout in the object store:
+//!
+//! ```text
+//! <prefix>/<partition-id>/<snapshot-id>/data.sst
+//! <prefix>/<partition-id>/<snapshot-id>/blobs.sst
+//! <prefix>/<partition-id>/<snapshot-id>.json
+//! ```
+
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use restate_core::cancellation_watcher;
+use restate_partition_store::{OpenMode, PartitionStoreCheckpoint, PartitionStoreManager};
+use restate_rocksdb::{download_file, upload_file, BackupError, BackupId, RocksError};
+use restate_storage_api::fsm_table::{ReadOnlyFsmTable, SequenceNumber};
+use restate_storage_api::StorageError;
+use restate_types::arc_util::ArcSwapExt;
+use restate_types::config::{
+    node_filepath, RocksDbOptions, UpdateableConfiguration, WorkerOptions,
+};
+use restate_types::identifiers::{PartitionId, PartitionKey};
+use restate_types::logs::{Lsn, SequenceNumber as _};
+
+use crate::partition::storage::fsm_variable;
+
+const DATA_FILE: &str = "data.sst";
+const BLOBS_FILE: &str = "blobs.sst";
+const METADATA_SUFFIX: &str = ".json";
+
+/// Local directory for checkpoints and data files in transit to and from the object store.
+pub(crate) fn staging_dir() -> PathBuf {
+    node_filepath("partition-snapshot-staging")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error(transparent)]
+    Backup(#[from] BackupError),
+    #[error(transparent)]
+    ObjectStore(#[from] object_store::Error),
+    #[error(transparent)]
+    Rocks(#[from] RocksError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("malformed snapshot metadata: {0}")]
+    Metadata(#[from] serde_json::Error),
+    #[error("log of partition {partition_id} has been trimmed up to {trim_point}, beyond the latest snapshot {snapshot_id} that has applied it up to {min_applied_lsn}")]
+    TrimmedBeyondSnapshot {
+        partition_id: PartitionId,
+        trim_point: Lsn,
+        snapshot_id: BackupId,
+        min_applied_lsn: Lsn,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartitionSnapshotMetadata {
+    pub id: BackupId,
+    pub partition_id: PartitionId,
+    pub key_range: RangeInclusive<PartitionKey>,
+    /// The snapshot contains the effects of all log records up to and including this lsn.
+    pub min_applied_lsn: Lsn,
+    /// Snapshots of empty partition stores have no data file.
+    pub has_data: bool,
+    /// Snapshots of partition stores without journal blobs have no blobs file.
+    #[serde(default)]
+    pub has_blobs: bool,
+}
+
+/// Stores partition snapshots in an object store and enforces the retention policy.
+#[derive(Clone)]
+pub struct PartitionSnapshotRepository {
+    store: Arc<dyn ObjectStore>,
+    prefix: ObjectPath,
+    retention: usize,
+}
+
+impl PartitionSnapshotRepository {
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: ObjectPath, retention: usize) -> Self {
+        Self {
+            store,
+            prefix,
+            retention: retention.max(1),
+        }
+    }
+
+    /// Creates a repository from a destination url such as `s3://bucket/prefix`,
+    /// `gs://bucket/prefix` or `file:///path`. Cloud credentials are read from the environment.
+    pub fn from_destination(destination: &str, retention: usize) -> Result<Self, SnapshotError> {
+        let (store, prefix) = restate_rocksdb::build_object_store(destination)?;
+        Ok(Self::new(store, prefix, retention))
+    }
+
+    pub(crate) fn from_options(opts: &WorkerOptions) -> Option<Result<Self, SnapshotError>> {
+        opts.snapshot_destination()
+            .map(|destination| Self::from_destination(destination, opts.snapshot_retention()))
+    }
+
+    fn partition_prefix(&self, partition_id: PartitionId) -> ObjectPath {
+        self.prefix.child(partition_id.to_string())
+    }
+
+    fn metadata_path(&self, partition_id: PartitionId, id: BackupId) -> ObjectPath {
+        self.partition_prefix(partition_id)
+            .child(format!("{}{}", id, METADATA_SUFFIX))
+    }
+
+    pub(crate) fn data_path(&self, partition_id: PartitionId, id: BackupId) -> ObjectPath {
+        self.partition_prefix(partition_id)
+            .child(id.to_string())
+            .child(DATA_FILE)
+    }
+
+    pub(crate) fn blobs_path(&self, partition_id: PartitionId, id: BackupId) -> ObjectPath {
+        self.partition_prefix(partition_id)
+            .child(id.to_string())
+            .child(BLOBS_FILE)
+    }
+
+    /// Lists the ids of all complete snapshots of the partition, oldest first.
+    pub async fn list_snapshots(
+        &self,
+        partition_id: PartitionId,
+    ) -> Result<Vec<BackupId>, SnapshotError> {
+        let mut ids: Vec<BackupId> = self
+            .store
+            .list(Some(&self.partition_prefix(partition_id)))
+            .try_filter_map(|meta| async move {
+                Ok(meta
+                    .location
+                    .filename()
+                    .and_then(|name| name.strip_suffix(METADATA_SUFFIX))
+                    .and_then(|id| id.parse::<BackupId>().ok()))
+            })
+            .try_collect()
+            .await?;
+        ids.sort();
+        Ok(ids)
+    }
+
+    pub async fn read_metadata(
+        &self,
+        partition_id: PartitionId,
+        id: BackupId,
+    ) -> Result<PartitionSnapshotMetadata, SnapshotError> {
+        let bytes = self
+            .store
+            .get(&self.metadata_path(partition_id, id))
+            .await?
+            .bytes()
+            .await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Returns the metadata of the most recent snapshot of the partition, if any.
+    pub async fn latest_snapshot(
+        &self,
+        partition_id: PartitionId,
+    ) -> Result<Option<PartitionSnapshotMetadata>, SnapshotError> {
+        match self.list_snapshots(partition_id).await?.last() {
+            Some(id) => Ok(Some(self.read_metadata(partition_id, *id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Uploads a snapshot. `data_file` must be present iff the snapshot has data, `blobs_file`
+    /// iff it has journal blobs.
+    pub async fn put_snapshot(
+        &self,
+        metadata: &PartitionSnapshotMetadata,
+        data_file: Option<&Path>,
+        blobs_file: Option<&Path>,
+    ) -> Result<(), SnapshotError> {
+        debug_assert_eq!(metadata.has_data, data_file.is_some());
+        debug_assert_eq!(metadata.has_blobs, blobs_file.is_some());
+        if let Some(data_file) = data_file {
+            upload_file(
+                self.store.as_ref(),
+                data_file,
+                &self.data_path(metadata.partition_id, metadata.id),
+            )
+            .await?;
+        }
+        if let Some(blobs_file) = blobs_file {
+            upload_file(
+                self.store.as_ref(),
+                blobs_file,
+                &self.blobs_path(metadata.partition_id, metadata.id),
+            )
+            .await?;
+        }
+
+        // The metadata is uploaded last, a snapshot without metadata is incomplete and will be
+        // ignored.
+        self.store
+            .put(
+                &self.metadata_path(metadata.partition_id, metadata.id),
+                serde_json::to_vec(metadata)?.into(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Downloads the data file of the snapshot to `target`.
+    pub async fn download_data(
+        &self,
+        metadata: &PartitionSnapshotMetadata,
+        target: &Path,
+    ) -> Result<(), SnapshotError> {
+        Ok(download_file(
+            self.store.as_ref(),
+            &self.data_path(metadata.partition_id, metadata.id),
+            target,
+        )
+        .await?)
+    }
+
+    /// Downloads the journal blobs file of the snapshot to `target`.
+    pub async fn download_blobs(
+        &self,
+        metadata: &PartitionSnapshotMetadata,
+        target: &Path,
+    ) -> Result<(), SnapshotError> {
+        Ok(download_file(
+            self.store.as_ref(),
+            &self.blobs_path(metadata.partition_id, metadata.id),
+            target,
+        )
+        .await?)
+    }
+
+    /// Deletes the snapshots of the partition exceeding the retention limit.
+    pub async fn apply_retention(&self, partition_id: PartitionId) -> Result<(), SnapshotError> {
+        let snapshots = self.list_snapshots(partition_id).await?;
+        let num_expired = snapshots.len().saturating_sub(self.retention);
+
+        for id in &snapshots[..num_expired] {
+            // remove the metadata first, so that a partially deleted snapshot is never visible
+            self.store
+                .delete(&self.metadata_path(partition_id, *id))
+                .await?;
+            for path in [
+                self.data_path(partition_id, *id),
+                self.blobs_path(partition_id, *id),
+            ] {
+                match self.store.delete(&path).await {
+                    Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            debug!(%partition_id, snapshot_id = %id, "Deleted expired partition snapshot");
+        }
+        Ok(())
+    }
+}
+
+/// Periodically snapshots all partition stores of this node.
+pub(crate) struct PartitionSnapshotter {
+    repository: PartitionSnapshotRepository,
+    partition_store_manager: PartitionStoreManager,
+    updateable_config: UpdateableConfiguration,
+    staging_dir: PathBuf,
+}
+
+impl PartitionSnapshotter {
+    pub(crate) fn new(
+        repository: PartitionSnapshotRepository,
+        partition_store_manager: PartitionStoreManager,
+        updateable_config: UpdateableConfiguration,
+        staging_dir: PathBuf,
+    ) -> Self {
+        Self {
+            repository,
+            partition_store_manager,
+            updateable_config,
+            staging_dir,
+        }
+    }
+
+    pub(crate) async fn run(self) -> anyhow::Result<()> {
+        let interval = self.updateable_config.pinned().worker.snapshot_interval();
+        let mut ticker = tokio::time::interval(interval);
+        // the first tick completes immediately, partition processors are probably still starting.
+        ticker.tick().await;
+
+        let shutdown_watch = cancellation_watcher();
+        tokio::pin!(shutdown_watch);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_watch => {
+                    break;
+                }
+                _ = ticker.tick() => {
+                    if let Err(e) = self.snapshot_partitions().await {
+                        warn!("Failed to snapshot partition stores: {}", e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn snapshot_partitions(&self) -> Result<(), SnapshotError> {
+        let id = BackupId::now();
+        tokio::fs::create_dir_all(&self.staging_dir).await?;
+
+        let mut checkpoint = self
+            .partition_store_manager
+            .open_checkpoint(
+                self.staging_dir.join(id.to_string()),
+                &self.updateable_config.pinned().worker.storage.rocksdb,
+            )
+            .await?;
+
+        for (partition_id, key_range) in self.partition_store_manager.get_live_partitions().await {
+            if let Err(e) = self
+                .snapshot_partition(&mut checkpoint, id, partition_id, key_range)
+                .await
+            {
+                warn!(%partition_id, "Failed to snapshot partition store: {}", e);
+                continue;
+            }
+            if let Err(e) = self.repository.apply_retention(partition_id).await {
+                warn!(%partition_id, "Failed to apply partition snapshot retention: {}", e);
+            }
+        }
+
+        checkpoint.close().await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(%partition_id, snapshot_id = %id))]
+    async fn snapshot_partition(
+        &self,
+        checkpoint: &mut PartitionStoreCheckpoint,
+        id: BackupId,
+        partition_id: PartitionId,
+        key_range: RangeInclusive<PartitionKey>,
+    ) -> Result<(), SnapshotError> {
+        if !checkpoint.has_partition(partition_id) {
+            // partition store was created after the checkpoint was taken
+            return Ok(());
+        }
+        let Some(applied_lsn) = checkpoint
+            .get::<SequenceNumber>(partition_id, fsm_variable::APPLIED_LSN)
+            .await?
+        else {
+            debug!("Skipping snapshot, partition hasn't applied any log records yet");
+            return Ok(());
+        };
+
+        let data_file = self
+            .staging_dir
+            .join(format!("{}-{}.sst", id, partition_id));
+        let blobs_file = self
+            .staging_dir
+            .join(format!("{}-{}-blobs.sst", id, partition_id));
+        let has_data = checkpoint
+            .export_partition(partition_id, data_file.clone())
+            .await?;
+        let has_blobs = checkpoint
+            .export_partition_blobs(partition_id, blobs_file.clone())
+            .await?;
+
+        let metadata = PartitionSnapshotMetadata {
+            id,
+            partition_id,
+            key_range,
+            min_applied_lsn: Lsn::from(u64::from(applied_lsn)),
+            has_data,
+            has_blobs,
+        };
+        let result = self
+            .repository
+            .put_snapshot(
+                &metadata,
+                has_data.then_some(data_file.as_path()),
+                has_blobs.then_some(blobs_file.as_path()),
+            )
+            .await;
+        for (exported, file) in [(has_data, &data_file), (has_blobs, &blobs_file)] {
+            if exported {
+                if let Err(e) = tokio::fs::remove_file(file).await {
+                    warn!(
+                        path = %file.display(),
+                        "Failed to remove exported partition store: {}", e
+                    );
+                }
+            }
+        }
+        result?;
+
+        info!(
+            min_applied_lsn = %metadata.min_applied_lsn,
+            "Snapshot of partition store completed"
+        );
+        Ok(())
+    }
+}
+
+/// Restores the partition store from the most recent snapshot if the partition has no local
+/// state, or if its local state lags behind the `trim_point` of the log and can't catch up by
+/// reading the log anymore. The partition processor then replays the log after the applied lsn
+/// of the snapshot. Returns the metadata of the restored snapshot, if any.
+///
+/// Fails if the log has been trimmed beyond the latest snapshot, the partition can't be restored
+/// without losing the records in between.
+pub(crate) async fn restore_partition_store(
+    repository: &PartitionSnapshotRepository,
+    partition_store_manager: &PartitionStoreManager,
+    trim_point: Lsn,
+    partition_id: PartitionId,
+    partition_key_range: RangeInclusive<PartitionKey>,
+    opts: &RocksDbOptions,
+) -> Result<Option<PartitionSnapshotMetadata>, SnapshotError> {
+    let applied_lsn = if partition_store_manager.partition_store_exists(partition_id) {
+        let mut partition_store = partition_store_manager
+            .open_partition_store(
+                partition_id,
+                partition_key_range.clone(),
+                OpenMode::OpenExisting,
+                opts,
+            )
+            .await?;
+        partition_store
+            .get::<SequenceNumber>(partition_id, fsm_variable::APPLIED_LSN)
+            .await?
+            .map(|lsn| Lsn::from(u64::from(lsn)))
+    } else {
+        None
+    };
+
+    if applied_lsn.is_some_and(|applied_lsn| applied_lsn >= trim_point) {
+        // the log still contains all records which haven't been applied yet
+        return Ok(None);
+    }
+
+    let Some(snapshot) = repository.latest_snapshot(partition_id).await? else {
+        if trim_point > applied_lsn.unwrap_or(Lsn::INVALID) {
+            warn!(
+                %partition_id,
+                %trim_point,
+                "Log of partition has been trimmed beyond its local state and there is no snapshot to restore from"
+            );
+        }
+        return Ok(None);
+    };
+
+    if applied_lsn.is_some_and(|applied_lsn| applied_lsn >= snapshot.min_applied_lsn) {
+        warn!(
+            %partition_id,
+            %trim_point,
+            snapshot_id = %snapshot.id,
+            "Log of partition has been trimmed beyond its local state, but the latest snapshot is not more recent"
+        );
+        return Ok(None);
+    }
+    if snapshot.min_applied_lsn < trim_point {
+        // the records between the snapshot and the trim point are lost
+        return Err(SnapshotError::TrimmedBeyondSnapshot {
+            partition_id,
+            trim_point,
+            snapshot_id: snapshot.id,
+            min_applied_lsn: snapshot.min_applied_lsn,
+        });
+    }
+
+    let staging_dir = staging_dir();
+    tokio::fs::create_dir_all(&staging_dir).await?;
+    let data_file = if snapshot.has_data {
+        let data_file = staging_dir.join(format!("restore-{}-{}.sst", partition_id, snapshot.id));
+        repository.download_data(&snapshot, &data_file).await?;
+        Some(data_file)
+    } else {
+        None
+    };
+    let blobs_file = if snapshot.has_blobs {
+        let blobs_file = staging_dir.join(format!(
+            "restore-{}-{}-blobs.sst",
+            partition_id, snapshot.id
+        ));
+        repository.download_blobs(&snapshot, &blobs_file).await?;
+        Some(blobs_file)
+    } else {
+        None
+    };
+
+    let result = partition_store_manager
+        .import_partition_store(
+            partition_id,
+            partition_key_range,
+            data_file.clone(),
+            blobs_file.clone(),
+            opts,
+        )
+        .await;
+    // ingestion copies the files into the database
+    for file in data_file.iter().chain(blobs_file.iter()) {
+        if let Err(e) = tokio::fs::remove_file(file).await {
+            warn!(
+                path = %file.display(),
+                "Failed to remove downloaded partition snapshot: {}", e
+            );
+        }
+    }
+    result?;
+
+    info!(
+        %partition_id,
+        snapshot_id = %snapshot.id,
+        min_applied_lsn = %snapshot.min_applied_lsn,
+        "Restored partition store from snapshot"
+    );
+    Ok(Some(snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use object_store::memory::InMemory;
+    use restate_core::{task_center, TaskCenterBuilder};
+    use restate_partition_store::PartitionStore;
+    use restate_rocksdb::RocksDbManager;
+    use restate_storage_api::fsm_table::FsmTable;
+    use restate_storage_api::Transaction;
+    use restate_types::arc_util::Constant;
+    use restate_types::config::CommonOptions;
+    use test_log::test;
+
+    const PARTITION_ID: PartitionId = PartitionId::MIN;
+    const STATE_ID: u64 = 1337;
+
+    fn repository(retention: usize) -> PartitionSnapshotRepository {
+        PartitionSnapshotRepository::new(
+            Arc::new(InMemory::new()),
+            ObjectPath::from("snapshots"),
+            retention,
+        )
+    }
+
+    fn empty_snapshot(id: u64) -> PartitionSnapshotMetadata {
+        PartitionSnapshotMetadata {
+            id: id.to_string().parse().unwrap(),
+            partition_id: PARTITION_ID,
+            key_range: PartitionKey::MIN..=PartitionKey::MAX,
+            min_applied_lsn: Lsn::from(id),
+            has_data: false,
+            has_blobs: false,
+        }
+    }
+
+    /// The partition store lives in the temp data dir of `worker_options`, which must outlive it.
+    async fn create_partition_store(
+        worker_options: &WorkerOptions,
+    ) -> (PartitionStoreManager, PartitionStore) {
+        task_center().run_in_scope_sync("db-manager-init", None, || {
+            RocksDbManager::init(Constant::new(CommonOptions::default()))
+        });
+        let manager = PartitionStoreManager::create(
+            Constant::new(worker_options.storage.clone()),
+            Constant::new(worker_options.storage.rocksdb.clone()),
+            &[],
+        )
+        .await
+        .unwrap();
+        let partition_store = manager
+            .open_partition_store(
+                PARTITION_ID,
+                PartitionKey::MIN..=PartitionKey::MAX,
+                OpenMode::CreateIfMissing,
+                &worker_options.storage.rocksdb,
+            )
+            .await
+            .unwrap();
+        (manager, partition_store)
+    }
+
+    async fn write_state(partition_store: &mut PartitionStore, applied_lsn: u64, value: u64) {
+        let mut txn = partition_store.transaction();
+        txn.put(
+            PARTITION_ID,
+            fsm_variable::APPLIED_LSN,
+            SequenceNumber::from(applied_lsn),
+        )
+        .await;
+        txn.put(PARTITION_ID, STATE_ID, SequenceNumber::from(value))
+            .await;
+        txn.commit().await.unwrap();
+    }
+
+    async fn read_state(partition_store: &mut PartitionStore) -> Option<u64> {
+        partition_store
+            .get::<SequenceNumber>(PARTITION_ID, STATE_ID)
+            .await
+            .unwrap()
+            .map(u64::from)
+    }
+
+    /// Takes a snapshot of the partition store like the [`PartitionSnapshotter`] does.
+    async fn take_snapshot(
+        repository: &PartitionSnapshotRepository,
+        manager: &PartitionStoreManager,
+        id: u64,
+    ) -> PartitionSnapshotMetadata {
+        let staging_dir = tempfile::tempdir().unwrap();
+        let mut checkpoint = manager
+            .open_checkpoint(
+                staging_dir.path().join("checkpoint"),
+                &WorkerOptions::default().storage.rocksdb,
+            )
+            .await
+            .unwrap();
+        let applied_lsn = checkpoint
+            .get::<SequenceNumber>(PARTITION_ID, fsm_variable::APPLIED_LSN)
+            .await
+            .unwrap()
+            .unwrap();
+        let data_file = staging_dir.path().join(DATA_FILE);
+        let metadata = PartitionSnapshotMetadata {
+            min_applied_lsn: Lsn::from(u64::from(applied_lsn)),
+            has_data: checkpoint
+                .export_partition(PARTITION_ID, data_file.clone())
+                .await
+                .unwrap(),
+            ..empty_snapshot(id)
+        };
+        repository
+            .put_snapshot(&metadata, Some(&data_file), None)
+            .await
+            .unwrap();
+        checkpoint.close().await.unwrap();
+        metadata
+    }
+
+    async fn restore(
+        repository: &PartitionSnapshotRepository,
+        manager: &PartitionStoreManager,
+        trim_point: u64,
+    ) -> Option<BackupId> {
+        restore_partition_store(
+            repository,
+            manager,
+            Lsn::from(trim_point),
+            PARTITION_ID,
+            PartitionKey::MIN..=PartitionKey::MAX,
+            &WorkerOptions::default().storage.rocksdb,
+        )
+        .await
+        .unwrap()
+        .map(|snapshot| snapshot.id)
+    }
+
+    #[test(tokio::test)]
+    async fn retention_keeps_latest_snapshots() {
+        let repository = repository(2);
+        for id in 1..=3 {
+            repository
+                .put_snapshot(&empty_snapshot(id), None, None)
+                .await
+                .unwrap();
+        }
+
+        repository.apply_retention(PARTITION_ID).await.unwrap();
+
+        assert_eq!(
+            repository.list_snapshots(PARTITION_ID).await.unwrap(),
+            vec![empty_snapshot(2).id, empty_snapshot(3).id]
+        );
+        assert_eq!(
+            repository
+                .latest_snapshot(PARTITION_ID)
+                .await
+                .unwrap()
+                .map(|snapshot| snapshot.min_applied_lsn),
+            Some(Lsn::from(3))
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn restores_partition_store_without_local_state() {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        tc.run_in_scope("test", None, async {
+            let repository = repository(1);
+            let worker_options = WorkerOptions::default();
+            let (manager, mut partition_store) = create_partition_store(&worker_options).await;
+            write_state(&mut partition_store, 10, 1).await;
+            let snapshot = take_snapshot(&repository, &manager, 1).await;
+
+            manager.drop_partition_store(PARTITION_ID).await.unwrap();
+            assert_eq!(restore(&repository, &manager, 0).await, Some(snapshot.id));
+
+            let mut partition_store = manager.get_partition_store(PARTITION_ID).await.unwrap();
+            assert_eq!(read_state(&mut partition_store).await, Some(1));
+        })
+        .await;
+    }
+
+    #[test(tokio::test)]
+    async fn restores_partition_store_lagging_behind_trim_point() {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        tc.run_in_scope("test", None, async {
+            let repository = repository(1);
+            let worker_options = WorkerOptions::default();
+            let (manager, mut partition_store) = create_partition_store(&worker_options).await;
+            write_state(&mut partition_store, 20, 2).await;
+            let snapshot = take_snapshot(&repository, &manager, 1).await;
+            // the local state falls behind, e.g. because the node was offline
+            write_state(&mut partition_store, 10, 1).await;
+
+            // the log still contains the records after the local state
+            assert_eq!(restore(&repository, &manager, 10).await, None);
+            assert_eq!(read_state(&mut partition_store).await, Some(1));
+
+            assert_eq!(restore(&repository, &manager, 15).await, Some(snapshot.id));
+            let mut partition_store = manager.get_partition_store(PARTITION_ID).await.unwrap();
+            assert_eq!(read_state(&mut partition_store).await, Some(2));
+        })
+        .await;
+    }
+
+    #[test(tokio::test)]
+    async fn refuses_to_restore_snapshot_behind_trim_point() {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        tc.run_in_scope("test", None, async {
+            let repository = repository(1);
+            let worker_options = WorkerOptions::default();
+            let (manager, mut partition_store) = create_partition_store(&worker_options).await;
+            write_state(&mut partition_store, 10, 1).await;
+            let snapshot = take_snapshot(&repository, &manager, 1).await;
+            manager.drop_partition_store(PARTITION_ID).await.unwrap();
+
+            // the records 11 to 15 are neither in the snapshot nor in the log
+            let result = restore_partition_store(
+                &repository,
+                &manager,
+                Lsn::from(15),
+                PARTITION_ID,
+                PartitionKey::MIN..=PartitionKey::MAX,
+                &worker_options.storage.rocksdb,
+            )
+            .await;
+            assert!(matches!(
+                result,
+                Err(SnapshotError::TrimmedBeyondSnapshot { snapshot_id, min_applied_lsn, .. })
+                    if snapshot_id == snapshot.id && min_applied_lsn == Lsn::from(10)
+            ));
+            assert!(!manager.partition_store_exists(PARTITION_ID));
+        })
+        .await;
+    }
+
+    #[test(tokio::test)]
+    async fn keeps_local_state_if_snapshot_is_not_more_recent() {
+        let tc = TaskCenterBuilder::default()
+            .default_runtime_handle(tokio::runtime::Handle::current())
+            .build()
+            .expect("task_center builds");
+        tc.run_in_scope("test", None, async {
+            let repository = repository(1);
+            let worker_options = WorkerOptions::default();
+            let (manager, mut partition_store) = create_partition_store(&worker_options).await;
+            write_state(&mut partition_store, 10, 1).await;
+            take_snapshot(&repository, &manager, 1).await;
+            write_state(&mut partition_store, 20, 2).await;
+
+            assert_eq!(restore(&repository, &manager, 30).await, None);
+            assert_eq!(read_state(&mut partition_store).await, Some(2));
+        })
+        .await;
+    }
+}